@@ -1,11 +1,14 @@
 use std::collections::HashMap;
 
+use pact_models::PactSpecification;
 use pact_models::bodies::OptionalBody;
 use pact_models::expression_parser::DataType;
 use pact_models::generators::{Generator, GeneratorCategory, Generators};
-use pact_models::headers::parse_header;
-use pact_models::matchingrules::MatchingRules;
-use pact_models::path_exp::DocPath;
+use pact_models::headers::{find_header, parse_header};
+use pact_models::matchingrules::{Category, MatchingRule, MatchingRules, RuleLogic};
+use pact_models::path_exp::{DocPath, PathToken};
+#[cfg(feature = "xml")] use pact_models::xml_builder::XmlElement;
+use serde_json::Value;
 
 use crate::prelude::*;
 
@@ -60,7 +63,7 @@ pub trait HttpPartBuilder {
       let example = parse_header(name.as_str(), value.to_example().as_str());
       {
         let (headers, rules) = self.headers_and_matching_rules_mut();
-        let entry = headers.keys().cloned().find(|k| k.to_lowercase() == name.to_lowercase());
+        let entry = find_header(headers, name.as_str()).map(|(k, _)| k.clone());
         if let Some(key) = entry {
           headers.get_mut(&key).map(|val| {
             val.extend(example);
@@ -188,6 +191,37 @@ pub trait HttpPartBuilder {
         self
     }
 
+  /// Specify an XML body (application/xml) built with the XML builder. Any matching rules and
+  /// generators attached to the elements, attributes or text nodes are added to the body.
+  ///
+  /// Panics:
+  /// Will panic if a valid XML document can not be generated.
+  ///
+  /// ```
+  /// use pact_consumer::prelude::*;
+  /// use pact_consumer::builders::RequestBuilder;
+  /// use pact_models::matchingrules::MatchingRule;
+  /// use pact_models::xml_builder::{XmlAttribute, XmlElement};
+  ///
+  /// RequestBuilder::default().xml_body(&XmlElement::new("projects")
+  ///     .child(XmlElement::new("project")
+  ///       .matching_rule(MatchingRule::Type)
+  ///       .examples(2)
+  ///       .with_attribute(XmlAttribute::new("id", 1).matching_rule(MatchingRule::Integer))));
+  /// ```
+  #[cfg(feature = "xml")]
+  fn xml_body(&mut self, body: &XmlElement) -> &mut Self {
+    let mut generators = Generators::default();
+    {
+      let (body_ref, rules) = self.body_and_matching_rules_mut();
+      let contents = body.build(rules.add_category("body"), &mut generators)
+        .unwrap_or_else(|err| panic!("Failed to build the XML body - {}", err));
+      *body_ref = OptionalBody::Present(contents.into(), Some("application/xml".into()), None);
+    }
+    self.generators().add_generators(generators);
+    self
+  }
+
   /// Specify a text body (text/plain) matching the given pattern.
   ///
   /// ```
@@ -227,14 +261,95 @@ pub trait HttpPartBuilder {
   }
 }
 
+/// Attaches a matching rule, given in its raw JSON form, to the matching rules of a request or
+/// response, and records it so it can be checked against the specification version when the
+/// request or response is built.
+///
+/// Panics:
+/// Will panic if the JSON is not a valid matching rule or the path is not valid for the request
+/// or response.
+pub(crate) fn add_raw_matching_rule(
+  matching_rules: &mut MatchingRules,
+  raw_matching_rules: &mut Vec<(DocPath, MatchingRule)>,
+  path: DocPath,
+  rule: Value,
+  is_response: bool
+) {
+  let matching_rule = MatchingRule::from_json(&rule)
+    .unwrap_or_else(|err| panic!("'{}' is not a valid matching rule - {}", rule, err));
+  let (category, rule_path) = split_rule_path(&path, is_response)
+    .unwrap_or_else(|| panic!("'{}' is not a valid path to attach a matching rule to", path));
+  matching_rules.add_category(category).add_rule(rule_path, matching_rule.clone(), RuleLogic::And);
+  raw_matching_rules.push((path, matching_rule));
+}
+
+/// Splits a path like `$.body.a.b` or `$.header.X-Test` into the matching rule category and the
+/// path within that category. Requests can have rules for the path and query parameters, while
+/// responses can have rules for the status.
+fn split_rule_path(path: &DocPath, is_response: bool) -> Option<(Category, DocPath)> {
+  let tokens = path.tokens();
+  if tokens.first() != Some(&PathToken::Root) {
+    return None;
+  }
+  let category = match tokens.get(1) {
+    Some(PathToken::Field(name)) => name.as_str(),
+    _ => return None
+  };
+  let rest = &tokens[2..];
+  match category {
+    "body" => {
+      let mut body_path = DocPath::root();
+      for token in rest {
+        body_path.push(token.clone());
+      }
+      Some((Category::BODY, body_path))
+    }
+    "header" | "headers" => match rest {
+      [PathToken::Field(name)] => {
+        let mut sub_path = DocPath::root();
+        sub_path.push_field(name.as_str());
+        Some((Category::HEADER, sub_path))
+      }
+      _ => None
+    }
+    "query" if !is_response => match rest {
+      [PathToken::Field(name)] => {
+        let mut sub_path = DocPath::root();
+        sub_path.push_field(name.as_str());
+        Some((Category::QUERY, sub_path))
+      }
+      _ => None
+    }
+    "path" if rest.is_empty() && !is_response => Some((Category::PATH, DocPath::empty())),
+    "status" if rest.is_empty() && is_response => Some((Category::STATUS, DocPath::empty())),
+    _ => None
+  }
+}
+
+/// Checks that any raw matching rules are supported by the given specification version.
+///
+/// Panics:
+/// Will panic if any of the rules require a later version of the specification.
+pub(crate) fn check_raw_matching_rules(rules: &[(DocPath, MatchingRule)], spec: PactSpecification) {
+  for (path, rule) in rules {
+    let required = rule.min_spec_version();
+    if required > spec {
+      panic!("Matching rule '{}' at path '{}' requires a {:?} specification pact, but a {:?} pact is being built",
+        rule.name(), path, required, spec);
+    }
+  }
+}
+
 #[cfg(test)]
 mod tests {
   use std::collections::HashMap;
 
   use expectest::prelude::*;
   use maplit::hashmap;
+  use pact_models::HttpStatus;
   use pact_models::matchingrules::MatchingRule;
   use pact_models::matchingrules_list;
+  use pact_models::path_exp::DocPath;
   use regex::Regex;
   use serde_json::json;
 
@@ -401,4 +516,95 @@ mod tests {
       ]
     }));
   }
+
+  #[test]
+  fn raw_matching_rule() {
+    let pact = PactBuilder::new_v4("C", "P")
+      .interaction("I", "", |mut i| {
+        i.request
+          .matching_rule(DocPath::new_unwrap("$.body.version"), json!({ "match": "semver" }))
+          .matching_rule(DocPath::new_unwrap("$.header.X-Version"), json!({ "match": "regex", "regex": "\\d+" }));
+        i.response.matching_rule(DocPath::new_unwrap("$.status"), json!({ "match": "statusCode", "status": "success" }));
+        i
+      })
+      .build()
+      .as_v4_pact().unwrap();
+    let interaction = pact.interactions.first()
+      .unwrap().as_v4_http().unwrap();
+    expect!(interaction.request.matching_rules.rules_for_category("body").unwrap()).to(
+      be_equal_to(matchingrules_list! {
+        "body"; "$.version" => [ MatchingRule::Semver ]
+      })
+    );
+    expect!(interaction.request.matching_rules.rules_for_category("header").unwrap()).to(
+      be_equal_to(matchingrules_list! {
+        "header"; "$['X-Version']" => [ MatchingRule::Regex("\\d+".to_string()) ]
+      })
+    );
+    expect!(interaction.response.matching_rules.rules_for_category("status").unwrap()).to(
+      be_equal_to(matchingrules_list! {
+        "status"; [ MatchingRule::StatusCode(HttpStatus::Success) ]
+      })
+    );
+  }
+
+  #[test]
+  #[cfg(feature = "xml")]
+  fn xml_body() {
+    use pact_models::xml_builder::{XmlAttribute, XmlElement};
+
+    let pact = PactBuilder::new_v4("C", "P")
+      .interaction("I", "", |mut i| {
+        i.request.xml_body(&XmlElement::new("projects")
+          .child(XmlElement::new("project")
+            .matching_rule(MatchingRule::Type)
+            .examples(2)
+            .with_attribute(XmlAttribute::new("id", 1).matching_rule(MatchingRule::Integer))));
+        i
+      })
+      .build()
+      .as_v4_pact().unwrap();
+    let interaction = pact.interactions.first()
+      .unwrap().as_v4_http().unwrap();
+    expect!(interaction.request.body.value_as_string()).to(be_some().value(
+      "<?xml version='1.0'?><projects><project id='1'/><project id='1'/></projects>"));
+    expect!(interaction.request.body.content_type().map(|ct| ct.to_string())).to(be_some().value("application/xml"));
+    expect!(interaction.request.matching_rules.rules_for_category("body").unwrap()).to(
+      be_equal_to(matchingrules_list! {
+        "body";
+        "$.projects.project" => [ MatchingRule::Type ],
+        "$.projects.project['@id']" => [ MatchingRule::Integer ]
+      })
+    );
+  }
+
+  #[test]
+  #[should_panic(expected = "requires a V4 specification pact")]
+  fn raw_matching_rule_not_supported_by_spec_version() {
+    PactBuilder::new("C", "P")
+      .interaction("I", "", |mut i| {
+        i.request.matching_rule(DocPath::new_unwrap("$.body.version"), json!({ "match": "semver" }));
+        i
+      });
+  }
+
+  #[test]
+  #[should_panic(expected = "is not a valid path to attach a matching rule to")]
+  fn raw_matching_rule_with_invalid_path() {
+    PactBuilder::new_v4("C", "P")
+      .interaction("I", "", |mut i| {
+        i.request.matching_rule(DocPath::new_unwrap("$.version"), json!({ "match": "semver" }));
+        i
+      });
+  }
+
+  #[test]
+  #[should_panic(expected = "is not a valid path to attach a matching rule to")]
+  fn raw_matching_rule_with_status_path_for_a_request() {
+    PactBuilder::new_v4("C", "P")
+      .interaction("I", "", |mut i| {
+        i.request.matching_rule(DocPath::new_unwrap("$.status"), json!({ "match": "statusCode", "status": "success" }));
+        i
+      });
+  }
 }
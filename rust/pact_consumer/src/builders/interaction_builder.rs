@@ -1,4 +1,6 @@
 use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+use std::panic::Location;
 
 use maplit::hashmap;
 use pact_models::provider_states::ProviderState;
@@ -10,6 +12,39 @@ use tracing::debug;
 use super::request_builder::RequestBuilder;
 use super::response_builder::ResponseBuilder;
 
+/// Number of times an interaction is expected to be called during a test
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ExpectedCalls {
+  /// Interaction must be called exactly this number of times
+  Exactly(usize),
+  /// Interaction must be called at least this number of times
+  AtLeast(usize),
+  /// Interaction must be called at most this number of times
+  AtMost(usize)
+}
+
+impl ExpectedCalls {
+  /// If the actual number of calls satisfies this expectation
+  pub fn is_satisfied_by(&self, calls: usize) -> bool {
+    match self {
+      ExpectedCalls::Exactly(n) => calls == *n,
+      ExpectedCalls::AtLeast(n) => calls >= *n,
+      ExpectedCalls::AtMost(n) => calls <= *n
+    }
+  }
+}
+
+impl Display for ExpectedCalls {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    let times = |n: &usize| if *n == 1 { "1 time".to_string() } else { format!("{} times", n) };
+    match self {
+      ExpectedCalls::Exactly(n) => write!(f, "exactly {}", times(n)),
+      ExpectedCalls::AtLeast(n) => write!(f, "at least {}", times(n)),
+      ExpectedCalls::AtMost(n) => write!(f, "at most {}", times(n))
+    }
+  }
+}
+
 /// Builder for `Interaction` objects. Normally created via
 /// `PactBuilder::interaction`.
 #[derive(Clone, Debug)]
@@ -20,6 +55,8 @@ pub struct InteractionBuilder {
     test_name: Option<String>,
     key: Option<String>,
     pending: Option<bool>,
+    source_location: Option<(String, u32)>,
+    expected_calls: Option<ExpectedCalls>,
 
     /// Protocol transport for this interaction
     transport: Option<String>,
@@ -48,6 +85,8 @@ impl InteractionBuilder {
       test_name: None,
       key: None,
       pending: None,
+      source_location: None,
+      expected_calls: None,
       transport: None,
       request: RequestBuilder::default(),
       response: ResponseBuilder::default(),
@@ -112,6 +151,37 @@ impl InteractionBuilder {
     self
   }
 
+  /// Records the source file and line where this method is called as the location the
+  /// interaction was defined. For V4 pacts, this is stored in the interaction comments, and
+  /// is displayed with any mock server mismatches for the interaction.
+  #[track_caller]
+  pub fn record_source_location(&mut self) -> &mut Self {
+    let location = Location::caller();
+    self.source_location(location.file(), location.line())
+  }
+
+  /// Sets the source file and line where this interaction was defined. For V4 pacts, this is
+  /// stored in the interaction comments, and is displayed with any mock server mismatches for
+  /// the interaction.
+  pub fn source_location<G: Into<String>>(&mut self, file: G, line: u32) -> &mut Self {
+    self.source_location = Some((file.into(), line));
+    self
+  }
+
+  /// Sets the number of times this interaction is expected to be called. By default, an
+  /// interaction must be called at least once. Any violations will be reported as mismatches
+  /// by the mock server when it is shut down. This is only supported by the HTTP mock server,
+  /// starting a plugin mock server for a Pact with expected calls will panic.
+  pub fn expect_calls(&mut self, expected_calls: ExpectedCalls) -> &mut Self {
+    self.expected_calls = Some(expected_calls);
+    self
+  }
+
+  /// The number of times this interaction is expected to be called, if set
+  pub(crate) fn expected_calls(&self) -> Option<ExpectedCalls> {
+    self.expected_calls
+  }
+
   /// Sets the protocol transport for this interaction. This would be required when there are
   /// different types of interactions in the Pact file (i.e. HTTP and messages).
   pub fn transport<G: Into<String>>(&mut self, name: G) -> &mut Self {
@@ -135,6 +205,13 @@ impl InteractionBuilder {
     debug!("Building V4 HTTP interaction: {:?}", self);
 
     let markup = self.request.interaction_markup().merge(self.response.interaction_markup());
+    let mut comments = hashmap!{
+      "text".to_string() => json!(self.comments),
+      "testname".to_string() => json!(self.test_name)
+    };
+    if let Some((file, line)) = &self.source_location {
+      comments.insert("source".to_string(), json!({ "file": file, "line": line }));
+    }
     SynchronousHttp {
       id: None,
       key: self.key.clone(),
@@ -142,10 +219,7 @@ impl InteractionBuilder {
       provider_states: self.provider_states.clone(),
       request: self.request.build_v4(),
       response: self.response.build_v4(),
-      comments: hashmap!{
-        "text".to_string() => json!(self.comments),
-        "testname".to_string() => json!(self.test_name)
-      },
+      comments,
       pending: self.pending.unwrap_or(false),
       plugin_config: self.plugin_config(),
       interaction_markup: markup,
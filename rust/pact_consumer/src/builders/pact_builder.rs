@@ -1,5 +1,6 @@
+use std::collections::HashMap;
 use std::fmt::{Debug, Formatter};
-use std::panic::RefUnwindSafe;
+use std::panic::{Location, RefUnwindSafe};
 use std::path::PathBuf;
 use pact_mock_server::mock_server::MockServerConfig;
 
@@ -34,7 +35,7 @@ use crate::mock_server::http_mock_server::ValidatingHttpMockServer;
 use crate::PACT_CONSUMER_VERSION;
 use crate::prelude::*;
 
-use super::interaction_builder::InteractionBuilder;
+use super::interaction_builder::{ExpectedCalls, InteractionBuilder};
 
 /// Builder for `Pact` objects.
 ///
@@ -58,7 +59,9 @@ use super::interaction_builder::InteractionBuilder;
 /// ```
 pub struct PactBuilder {
   pact: Box<dyn Pact + Send + Sync + RefUnwindSafe>,
-  output_dir: Option<PathBuf>
+  output_dir: Option<PathBuf>,
+  record_source_locations: bool,
+  expected_calls: HashMap<String, ExpectedCalls>
 }
 
 impl PactBuilder {
@@ -84,7 +87,12 @@ impl PactBuilder {
           pact.add_md_version("consumer", version);
         }
 
-        PactBuilder { pact: pact.boxed(), output_dir: None }
+        PactBuilder {
+          pact: pact.boxed(),
+          output_dir: None,
+          record_source_locations: false,
+          expected_calls: HashMap::default()
+        }
     }
 
   /// Create a new `PactBuilder`, specifying the names of the service
@@ -110,7 +118,12 @@ impl PactBuilder {
       pact.add_md_version("consumer", version);
     }
 
-    PactBuilder { pact: pact.boxed(), output_dir: None }
+    PactBuilder {
+      pact: pact.boxed(),
+      output_dir: None,
+      record_source_locations: false,
+      expected_calls: HashMap::default()
+    }
   }
 
     /// Create a new `PactBuilder` for a V4 specification Pact, specifying the names of the service
@@ -133,7 +146,12 @@ impl PactBuilder {
         pact.add_md_version("consumer", version);
       }
 
-      PactBuilder { pact: pact.boxed(), output_dir: None }
+      PactBuilder {
+        pact: pact.boxed(),
+        output_dir: None,
+        record_source_locations: false,
+        expected_calls: HashMap::default()
+      }
     }
 
   ///  Sets the output directory to write any pact files to. If this is not set, will default
@@ -144,6 +162,14 @@ impl PactBuilder {
     self
   }
 
+  /// Enables recording the source file and line where each HTTP interaction is defined with
+  /// `interaction`. This is stored in the interaction comments of V4 pacts, and is displayed
+  /// with any mock server mismatches for the interaction.
+  pub fn with_source_locations(&mut self, enabled: bool) -> &mut Self {
+    self.record_source_locations = enabled;
+    self
+  }
+
     /// Add a plugin to be used by the test. Note this will return an async version of the Pact
     /// builder and requires the plugin crate feature.
     ///
@@ -170,24 +196,37 @@ impl PactBuilder {
         Err(err) => panic!("Could not load plugin - {}", err)
       }
 
-      PactBuilderAsync::from_builder(pact, self.output_dir.clone())
+      PactBuilderAsync::from_builder(pact, self.output_dir.clone(), self.record_source_locations,
+        self.expected_calls.clone())
     }
 
     /// Add a new HTTP `Interaction` to the `Pact`. Needs to return a clone of the builder
     /// that is passed in.
+    #[track_caller]
     pub fn interaction<D, F>(&mut self, description: D, interaction_type: D, build_fn: F) -> &mut Self
     where
         D: Into<String>,
         F: FnOnce(InteractionBuilder) -> InteractionBuilder
     {
-        let interaction = InteractionBuilder::new(description.into(), interaction_type.into());
+        let mut interaction = InteractionBuilder::new(description.into(), interaction_type.into());
+        if self.record_source_locations {
+          let location = Location::caller();
+          interaction.source_location(location.file(), location.line());
+        }
         let interaction = build_fn(interaction);
 
         if self.pact.is_v4() {
-          self.push_interaction(&interaction.build_v4())
+          self.push_interaction(&interaction.build_v4());
         } else {
-          self.push_interaction(&interaction.build())
+          self.push_interaction(&interaction.build());
+        }
+
+        if let Some(expected_calls) = interaction.expected_calls() {
+          if let Some(key) = last_interaction_key(self.pact.as_ref()) {
+            self.expected_calls.insert(key, expected_calls);
+          }
         }
+        self
     }
 
     /// Directly add a pre-built `Interaction` to our `Pact`. Normally it's
@@ -293,6 +332,9 @@ impl StartMockServer for PactBuilder {
       match _catalog_entry {
         Some(entry_name) => match catalogue_manager::lookup_entry(entry_name) {
           Some(entry) => if entry.entry_type == CatalogueEntryType::TRANSPORT {
+            if !self.expected_calls.is_empty() {
+              panic!("Expected call counts are not supported by plugin mock servers");
+            }
             PluginMockServer::start(self.build(), self.output_dir.clone(), &entry)
               .expect("Could not start the plugin mock server")
           } else {
@@ -300,17 +342,27 @@ impl StartMockServer for PactBuilder {
           }
           None => panic!("Did not find a catalogue entry for key '{}'", entry_name)
         }
-        None => ValidatingHttpMockServer::start(self.build(), self.output_dir.clone(), mock_server_config)
+        None => ValidatingHttpMockServer::start_with_expected_calls(self.build(), self.output_dir.clone(),
+          mock_server_config, self.expected_calls.clone())
       }
     }
 
     #[cfg(not(feature = "plugins"))]
     {
-      ValidatingHttpMockServer::start(self.build(), self.output_dir.clone(), mock_server_config)
+      ValidatingHttpMockServer::start_with_expected_calls(self.build(), self.output_dir.clone(),
+          mock_server_config, self.expected_calls.clone())
     }
   }
 }
 
+/// Key of the last interaction added to the Pact, which is used to look up the expected calls
+/// for the interaction
+pub(crate) fn last_interaction_key(pact: &dyn Pact) -> Option<String> {
+  pact.interactions().last()
+    .and_then(|interaction| interaction.as_v4())
+    .map(|interaction| interaction.unique_key())
+}
+
 impl Debug for PactBuilder {
   fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
     write!(f, "PactBuilder({:?}, {:?})", self.pact, self.output_dir)
@@ -419,4 +471,30 @@ mod tests {
     }, v4interaction);
     expect!(v4interaction.key.as_ref().unwrap()).to(be_equal_to("93371e6e7ae2556"));
   }
+
+  #[test]
+  fn records_source_locations_when_enabled() {
+    let pact = PactBuilder::new_v4("Consumer", "Alice Service")
+      .with_source_locations(true)
+      .interaction("a retrieve Mallory request", "", |mut i| {
+        i.request.path("/mallory");
+        i
+      })
+      .build();
+    let interactions = pact.interactions();
+    let interaction = interactions.first().unwrap().as_v4_http().unwrap();
+    let source = interaction.comments.get("source").unwrap();
+    expect!(source["file"].as_str().unwrap().ends_with("pact_builder.rs")).to(be_true());
+    expect!(source["line"].as_u64().unwrap()).to(be_greater_than(0));
+
+    let pact = PactBuilder::new_v4("Consumer", "Alice Service")
+      .interaction("a retrieve Mallory request", "", |mut i| {
+        i.request.path("/mallory");
+        i
+      })
+      .build();
+    let interactions = pact.interactions();
+    let interaction = interactions.first().unwrap().as_v4_http().unwrap();
+    expect!(interaction.comments.get("source")).to(be_none());
+  }
 }
@@ -1,4 +1,6 @@
+use std::collections::HashMap;
 use std::future::Future;
+use std::panic::Location;
 use std::path::PathBuf;
 
 use async_trait::async_trait;
@@ -27,7 +29,8 @@ use crate::mock_server::StartMockServerAsync;
 use crate::PACT_CONSUMER_VERSION;
 use crate::prelude::*;
 
-use super::interaction_builder::InteractionBuilder;
+use super::interaction_builder::{ExpectedCalls, InteractionBuilder};
+use super::pact_builder::last_interaction_key;
 
 /// Builder for `Pact` objects (async version).
 ///
@@ -55,7 +58,9 @@ use super::interaction_builder::InteractionBuilder;
 #[derive(Debug)]
 pub struct PactBuilderAsync {
   pact: Box<dyn Pact + Send + Sync>,
-  output_dir: Option<PathBuf>
+  output_dir: Option<PathBuf>,
+  record_source_locations: bool,
+  expected_calls: HashMap<String, ExpectedCalls>
 }
 
 impl PactBuilderAsync {
@@ -81,7 +86,7 @@ impl PactBuilderAsync {
           pact.add_md_version("consumer", version);
         }
 
-      PactBuilderAsync { pact: pact.boxed(), output_dir: None }
+      PactBuilderAsync { pact: pact.boxed(), output_dir: None, record_source_locations: false, expected_calls: HashMap::default() }
     }
 
     /// Create a new `PactBuilder` for a V4 specification Pact, specifying the names of the service
@@ -104,17 +109,32 @@ impl PactBuilderAsync {
         pact.add_md_version("consumer", version);
       }
 
-      PactBuilderAsync { pact: pact.boxed(), output_dir: None }
+      PactBuilderAsync { pact: pact.boxed(), output_dir: None, record_source_locations: false, expected_calls: HashMap::default() }
     }
 
     #[cfg(feature = "plugins")]
-    pub(crate) fn from_builder(pact: Box<dyn Pact + Send + Sync>, output_dir: Option<PathBuf>) -> Self {
+    pub(crate) fn from_builder(
+      pact: Box<dyn Pact + Send + Sync>,
+      output_dir: Option<PathBuf>,
+      record_source_locations: bool,
+      expected_calls: HashMap<String, ExpectedCalls>
+    ) -> Self {
       PactBuilderAsync {
         pact,
-        output_dir
+        output_dir,
+        record_source_locations,
+        expected_calls
       }
     }
 
+    /// Enables recording the source file and line where each HTTP interaction is defined with
+    /// `interaction`. This is stored in the interaction comments of V4 pacts, and is displayed
+    /// with any mock server mismatches for the interaction.
+    pub fn with_source_locations(&mut self, enabled: bool) -> &mut Self {
+      self.record_source_locations = enabled;
+      self
+    }
+
     /// Add a plugin to be used by the test. This requires the plugins feature.
     ///
     /// Panics:
@@ -143,27 +163,49 @@ impl PactBuilderAsync {
 
     /// Add a new HTTP `Interaction` to the `Pact`. Needs to return a clone of the builder
     /// that is passed in.
-    pub async fn interaction<D, F, O>(&mut self, description: D, interaction_type: D, build_fn: F) -> &mut Self
+    ///
+    /// This is not an `async fn` so that the location of the caller can be recorded (see
+    /// `with_source_locations`), but returns a future that must be awaited.
+    #[track_caller]
+    pub fn interaction<'a, D, F, O>(
+      &'a mut self,
+      description: D,
+      interaction_type: D,
+      build_fn: F
+    ) -> impl Future<Output=&'a mut Self> + 'a
     where
         D: Into<String>,
-        F: FnOnce(InteractionBuilder) -> O,
-        O: Future<Output=InteractionBuilder> + Send
+        F: FnOnce(InteractionBuilder) -> O + 'a,
+        O: Future<Output=InteractionBuilder> + Send + 'a
     {
-        let interaction = InteractionBuilder::new(description.into(), interaction_type.into());
-        let interaction = build_fn(interaction).await;
-
-        if self.pact.is_v4() {
-          #[cfg(feature = "plugins")]
-          for (plugin_name, plugin_config) in interaction.pact_plugin_config() {
-            // TODO: Update this when there is an update plugin data function added to Pact models
-            if let Some(plugin_data) = self.pact.plugin_data().iter().find(|pl| pl.name == plugin_name) {
-              self.pact.add_plugin(plugin_data.name.as_str(), plugin_data.version.as_str(),
-                Some(plugin_config)).expect("Could not update Pact with plugin data");
+        let mut interaction = InteractionBuilder::new(description.into(), interaction_type.into());
+        if self.record_source_locations {
+          let location = Location::caller();
+          interaction.source_location(location.file(), location.line());
+        }
+        async move {
+          let interaction = build_fn(interaction).await;
+
+          if self.pact.is_v4() {
+            #[cfg(feature = "plugins")]
+            for (plugin_name, plugin_config) in interaction.pact_plugin_config() {
+              // TODO: Update this when there is an update plugin data function added to Pact models
+              if let Some(plugin_data) = self.pact.plugin_data().iter().find(|pl| pl.name == plugin_name) {
+                self.pact.add_plugin(plugin_data.name.as_str(), plugin_data.version.as_str(),
+                  Some(plugin_config)).expect("Could not update Pact with plugin data");
+              }
             }
+            self.push_interaction(&interaction.build_v4());
+          } else {
+            self.push_interaction(&interaction.build());
           }
-          self.push_interaction(&interaction.build_v4())
-        } else {
-          self.push_interaction(&interaction.build())
+
+          if let Some(expected_calls) = interaction.expected_calls() {
+            if let Some(key) = last_interaction_key(self.pact.as_ref()) {
+              self.expected_calls.insert(key, expected_calls);
+            }
+          }
+          self
         }
     }
 
@@ -262,6 +304,9 @@ impl StartMockServer for PactBuilderAsync {
       match _catalog_entry {
         Some(entry_name) => match catalogue_manager::lookup_entry(entry_name) {
           Some(entry) => if entry.entry_type == CatalogueEntryType::TRANSPORT {
+            if !self.expected_calls.is_empty() {
+              panic!("Expected call counts are not supported by plugin mock servers");
+            }
             PluginMockServer::start(self.build(), self.output_dir.clone(), &entry)
               .expect("Could not start the plugin mock server")
           } else {
@@ -269,13 +314,15 @@ impl StartMockServer for PactBuilderAsync {
           }
           None => panic!("Did not find a catalogue entry for key '{}'", entry_name)
         }
-        None => ValidatingHttpMockServer::start(self.build(), self.output_dir.clone(), mock_server_config)
+        None => ValidatingHttpMockServer::start_with_expected_calls(self.build(), self.output_dir.clone(),
+          mock_server_config, self.expected_calls.clone())
       }
     }
 
     #[cfg(not(feature = "plugins"))]
     {
-      ValidatingHttpMockServer::start(self.build(), self.output_dir.clone(), mock_server_config)
+      ValidatingHttpMockServer::start_with_expected_calls(self.build(), self.output_dir.clone(),
+          mock_server_config, self.expected_calls.clone())
     }
   }
 }
@@ -292,6 +339,9 @@ impl StartMockServerAsync for PactBuilderAsync {
       match _catalog_entry {
         Some(entry_name) => match catalogue_manager::lookup_entry(entry_name) {
           Some(entry) => if entry.entry_type == CatalogueEntryType::TRANSPORT {
+            if !self.expected_calls.is_empty() {
+              panic!("Expected call counts are not supported by plugin mock servers");
+            }
             PluginMockServer::start_async(self.build(), self.output_dir.clone(), &entry).await
               .expect("Could not start the plugin mock server")
           } else {
@@ -299,13 +349,15 @@ impl StartMockServerAsync for PactBuilderAsync {
           }
           None => panic!("Did not find a catalogue entry for key '{}'", entry_name)
         }
-        None => ValidatingHttpMockServer::start_async(self.build(), self.output_dir.clone(), mock_server_config).await
+        None => ValidatingHttpMockServer::start_async_with_expected_calls(self.build(), self.output_dir.clone(),
+          mock_server_config, self.expected_calls.clone()).await
       }
     }
 
     #[cfg(not(feature = "plugins"))]
     {
-      ValidatingHttpMockServer::start_async(self.build(), self.output_dir.clone(), mock_server_config).await
+      ValidatingHttpMockServer::start_async_with_expected_calls(self.build(), self.output_dir.clone(),
+          mock_server_config, self.expected_calls.clone()).await
     }
   }
 }
@@ -5,13 +5,14 @@ use bytes::Bytes;
 #[allow(unused_imports)]
 use env_logger;
 use maplit::*;
+use pact_models::PactSpecification;
 use pact_models::bodies::OptionalBody;
 use pact_models::content_types::ContentType;
 use pact_models::expression_parser::DataType;
 use pact_models::generators::{Generator, GeneratorCategory, Generators};
 #[cfg(feature = "plugins")] use pact_models::http_parts::HttpPart;
 use pact_models::json_utils::body_from_json;
-use pact_models::matchingrules::{Category, MatchingRules};
+use pact_models::matchingrules::{Category, MatchingRule, MatchingRules};
 use pact_models::path_exp::DocPath;
 use pact_models::request::Request;
 use pact_models::v4::http_parts::HttpRequest;
@@ -25,6 +26,7 @@ use serde_json::json;
 use serde_json::Value;
 #[allow(unused_imports)] use tracing::debug;
 
+use crate::builders::http_part_builder::{add_raw_matching_rule, check_raw_matching_rules};
 use crate::prelude::*;
 use crate::util::GetDefaulting;
 
@@ -37,7 +39,8 @@ struct PluginConfiguration {}
 pub struct RequestBuilder {
   request: HttpRequest,
   #[allow(dead_code)] plugin_config: HashMap<String, PluginConfiguration>,
-  interaction_markup: InteractionMarkup
+  interaction_markup: InteractionMarkup,
+  raw_matching_rules: Vec<(DocPath, MatchingRule)>
 }
 
 impl RequestBuilder {
@@ -146,8 +149,36 @@ impl RequestBuilder {
         self
     }
 
+    /// Attach a matching rule, given in its raw JSON form, at an arbitrary path. This allows
+    /// matchers to be used before there is a dedicated DSL helper for them. The first field of the
+    /// path selects the part of the request the rule applies to: `$.body...`, `$.header.<name>`,
+    /// `$.query.<name>` or `$.path`.
+    ///
+    /// Panics:
+    /// Will panic if the JSON is not a valid matching rule or the path does not start with one
+    /// of the parts above. Building a V3 request will panic if the rule requires a later
+    /// specification version.
+    ///
+    /// ```
+    /// use pact_consumer::builders::RequestBuilder;
+    /// use pact_models::path_exp::DocPath;
+    /// use serde_json::json;
+    ///
+    /// RequestBuilder::default()
+    ///     .matching_rule(DocPath::new_unwrap("$.body.version"), json!({ "match": "semver" }));
+    /// ```
+    pub fn matching_rule(&mut self, path: DocPath, rule: Value) -> &mut Self {
+        add_raw_matching_rule(&mut self.request.matching_rules, &mut self.raw_matching_rules,
+          path, rule, false);
+        self
+    }
+
     /// Build the specified `Request` object.
+    ///
+    /// Panics:
+    /// Will panic if any raw matching rules require a later version of the specification.
     pub fn build(&self) -> Request {
+         check_raw_matching_rules(&self.raw_matching_rules, PactSpecification::V3);
          self.request.as_v3_request()
     }
 
@@ -271,7 +302,8 @@ impl Default for RequestBuilder {
         RequestBuilder {
           request: HttpRequest::default(),
           plugin_config: Default::default(),
-          interaction_markup: Default::default()
+          interaction_markup: Default::default(),
+          raw_matching_rules: vec![]
         }
     }
 }
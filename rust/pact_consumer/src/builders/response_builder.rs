@@ -2,10 +2,12 @@ use std::collections::HashMap;
 
 use bytes::Bytes;
 use maplit::*;
+use pact_models::{HttpStatus, PactSpecification};
 use pact_models::bodies::OptionalBody;
 use pact_models::generators::Generators;
 #[cfg(feature = "plugins")] use pact_models::http_parts::HttpPart;
-use pact_models::matchingrules::MatchingRules;
+use pact_models::matchingrules::{Category, MatchingRule, MatchingRules, RuleLogic};
+use pact_models::path_exp::DocPath;
 use pact_models::prelude::ContentType;
 use pact_models::response::Response;
 use pact_models::v4::http_parts::{body_from_json, HttpResponse};
@@ -15,6 +17,7 @@ use pact_models::v4::interaction::InteractionMarkup;
 use serde_json::Value;
 #[allow(unused_imports)] use tracing::debug;
 
+use crate::builders::http_part_builder::{add_raw_matching_rule, check_raw_matching_rules};
 use crate::prelude::*;
 
 #[cfg(not(feature = "plugins"))]
@@ -26,7 +29,8 @@ struct PluginConfiguration {}
 pub struct ResponseBuilder {
   response: HttpResponse,
   #[allow(dead_code)] plugin_config: HashMap<String, PluginConfiguration>,
-  interaction_markup: InteractionMarkup
+  interaction_markup: InteractionMarkup,
+  raw_matching_rules: Vec<(DocPath, MatchingRule)>
 }
 
 impl ResponseBuilder {
@@ -44,6 +48,37 @@ impl ResponseBuilder {
         self
     }
 
+    /// Match any response status of the given class (or in the given list of status codes), using
+    /// the V4 `statusCode` matcher. The status code of the response is set to a representative
+    /// example of the class, which can be changed by calling `status` afterwards.
+    ///
+    /// ```
+    /// use pact_consumer::builders::ResponseBuilder;
+    /// use pact_models::HttpStatus;
+    ///
+    /// let response = ResponseBuilder::default().status_matching(HttpStatus::ClientError).build_v4();
+    /// assert_eq!(response.status, 400);
+    /// ```
+    ///
+    /// Panics:
+    /// The `statusCode` matcher requires a V4 specification pact, so building a V3 response will
+    /// panic.
+    pub fn status_matching(&mut self, status: HttpStatus) -> &mut Self {
+        let example = match &status {
+          HttpStatus::Information => 100,
+          HttpStatus::Success | HttpStatus::NonError => 200,
+          HttpStatus::Redirect => 300,
+          HttpStatus::ClientError => 400,
+          HttpStatus::ServerError | HttpStatus::Error => 500,
+          HttpStatus::StatusCodes(codes) => codes.first().cloned().unwrap_or(200)
+        };
+        let rule = MatchingRule::StatusCode(status);
+        self.response.matching_rules.add_category(Category::STATUS)
+          .add_rule(DocPath::empty(), rule.clone(), RuleLogic::And);
+        self.raw_matching_rules.push((DocPath::new_unwrap("$.status"), rule));
+        self.status(example)
+    }
+
     // This is a partial list of popular HTTP status codes. If you use any
     // others regularly, feel free to add them.
 
@@ -77,8 +112,36 @@ impl ResponseBuilder {
         self.status(404)
     }
 
+    /// Attach a matching rule, given in its raw JSON form, at an arbitrary path. This allows
+    /// matchers to be used before there is a dedicated DSL helper for them. The first field of the
+    /// path selects the part of the response the rule applies to: `$.body...`, `$.header.<name>`
+    /// or `$.status`.
+    ///
+    /// Panics:
+    /// Will panic if the JSON is not a valid matching rule or the path does not start with one
+    /// of the parts above. Building a V3 response will panic if the rule requires a later
+    /// specification version.
+    ///
+    /// ```
+    /// use pact_consumer::builders::ResponseBuilder;
+    /// use pact_models::path_exp::DocPath;
+    /// use serde_json::json;
+    ///
+    /// ResponseBuilder::default()
+    ///     .matching_rule(DocPath::new_unwrap("$.status"), json!({ "match": "statusCode", "status": "success" }));
+    /// ```
+    pub fn matching_rule(&mut self, path: DocPath, rule: Value) -> &mut Self {
+        add_raw_matching_rule(&mut self.response.matching_rules, &mut self.raw_matching_rules,
+          path, rule, true);
+        self
+    }
+
     /// Build the specified `Response` object.
+    ///
+    /// Panics:
+    /// Will panic if any raw matching rules require a later version of the specification.
     pub fn build(&self) -> Response {
+        check_raw_matching_rules(&self.raw_matching_rules, PactSpecification::V3);
         self.response.as_v3_response()
     }
 
@@ -201,7 +264,8 @@ impl Default for ResponseBuilder {
         ResponseBuilder {
           response: HttpResponse::default(),
           plugin_config: Default::default(),
-          interaction_markup: Default::default()
+          interaction_markup: Default::default(),
+          raw_matching_rules: vec![]
         }
     }
 }
@@ -225,3 +289,25 @@ impl HttpPartBuilder for ResponseBuilder {
     )
   }
 }
+
+#[test]
+fn status_matching() {
+  use expectest::prelude::*;
+  use pact_matching::{CoreMatchingContext, DiffConfig, match_status};
+
+  let response = ResponseBuilder::default()
+    .status_matching(HttpStatus::StatusCodes(vec![201, 202]))
+    .build_v4();
+  expect!(response.status).to(be_equal_to(201));
+
+  let rules = response.matching_rules.rules_for_category(Category::STATUS).unwrap();
+  let context = CoreMatchingContext::new(DiffConfig::AllowUnexpectedKeys, &rules, &hashmap!{});
+  expect!(match_status(response.status, 202, &context)).to(be_ok());
+  expect!(match_status(response.status, 200, &context)).to(be_err());
+}
+
+#[test]
+#[should_panic(expected = "requires a V4 specification pact")]
+fn status_matching_requires_v4() {
+  ResponseBuilder::default().status_matching(HttpStatus::Success).build();
+}
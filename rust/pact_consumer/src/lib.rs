@@ -392,18 +392,28 @@ pub mod prelude {
     pub use crate::{
         like,
         each_like,
+        array_containing,
         each_like_helper,
         term,
+        semver,
+        equals_ignore_case,
+        decimal_with_tolerance,
+        json_schema,
         json_pattern,
         json_pattern_internal
     };
-    pub use crate::builders::{HttpPartBuilder, PactBuilder, PactBuilderAsync};
+    pub use crate::builders::{ExpectedCalls, HttpPartBuilder, PactBuilder, PactBuilderAsync};
     #[cfg(feature = "plugins")] pub use crate::builders::plugin_builder::PluginInteractionBuilder;
     pub use crate::mock_server::{StartMockServer, ValidatingMockServer};
     pub use crate::patterns::{
+        ArrayContaining,
         EachLike,
         Like,
         Term,
+        SemVer,
+        EqualsIgnoreCase,
+        DecimalWithTolerance,
+        JsonSchema,
         ObjectMatching,
         EachKey,
         EachValue,
@@ -1,8 +1,14 @@
 //! Support for mock HTTP servers that verify pacts.
 
+use std::io::Write;
+
+use anyhow::anyhow;
 use async_trait::async_trait;
+use pact_matching::Mismatch;
 use pact_models::pact::Pact;
 use pact_models::sync_pact::RequestResponsePact;
+use pact_models::v4::http_parts::HttpRequest;
+use serde_json::Value;
 use url::Url;
 
 use pact_mock_server::matching::MatchResult;
@@ -36,6 +42,94 @@ pub trait ValidatingMockServer {
 
   /// Returns the metrics collected by the mock server
   fn metrics(&self) -> MockServerMetrics;
+
+  /// Returns the results of verifying the received requests, grouped by the interactions in the
+  /// pact. Returns an error if the mock server implementation is not able to provide the results
+  /// (i.e. mock servers provided by plugins), which is the default.
+  fn verification_results(&self) -> anyhow::Result<VerificationResults> {
+    Err(anyhow!("Verification results are not supported by this mock server"))
+  }
+
+  /// Returns all the requests received by the mock server in the order they were received,
+  /// including any unexpected requests. Note that with some mock server implementations, the
+  /// requests will only be available once the mock server has shutdown. Returns an error if the
+  /// mock server implementation is not able to provide the requests (i.e. mock servers provided
+  /// by plugins), which is the default.
+  fn received_requests(&self) -> anyhow::Result<Vec<ReceivedRequest>> {
+    Err(anyhow!("Received requests are not supported by this mock server"))
+  }
+
+  /// Returns the pact for the mock server as JSON, in the form that will be written to the pact
+  /// file when the mock server is shutdown. This does not include any interactions from an
+  /// existing pact file that the pact would be merged with. Returns an error by default, if the
+  /// mock server implementation is not able to provide the pact.
+  fn pact_json(&self) -> anyhow::Result<Value> {
+    Err(anyhow!("The pact JSON is not supported by this mock server"))
+  }
+
+  /// Writes the pact for the mock server as pretty-printed JSON to the given writer, instead of
+  /// to the filesystem. See `pact_json` for what is included.
+  fn write_pact_to_writer(&self, writer: &mut dyn Write) -> anyhow::Result<()> {
+    let json = self.pact_json()?;
+    serde_json::to_writer_pretty(&mut *writer, &json)?;
+    writer.flush()?;
+    Ok(())
+  }
+}
+
+/// A request received by a mock server
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReceivedRequest {
+  /// The actual request that was received
+  pub request: HttpRequest,
+  /// Description of the interaction the request was matched against, if any. Note that the
+  /// description may not be unique, use `interaction_key` to identify the interaction.
+  pub interaction: Option<String>,
+  /// Unique key of the interaction the request was matched against, if any. This is the same as
+  /// the key in the `InteractionVerificationResult` for the interaction.
+  pub interaction_key: Option<String>,
+  /// If the request matched the interaction
+  pub matched: bool
+}
+
+/// Result of verifying the requests received by a mock server for a single interaction
+#[derive(Debug, Clone, PartialEq)]
+pub struct InteractionVerificationResult {
+  /// Unique key of the interaction
+  pub key: String,
+  /// Description of the interaction
+  pub description: String,
+  /// Expected request for the interaction
+  pub request: HttpRequest,
+  /// Number of received requests that matched the interaction
+  pub calls: usize,
+  /// Received requests that were closest to the interaction but did not match, along with the
+  /// mismatches found
+  pub mismatches: Vec<(HttpRequest, Vec<Mismatch>)>
+}
+
+impl InteractionVerificationResult {
+  /// If the interaction was matched at least once, with no mismatched requests
+  pub fn matched(&self) -> bool {
+    self.calls > 0 && self.mismatches.is_empty()
+  }
+}
+
+/// Results of verifying the requests received by a mock server against the interactions in the
+/// pact
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct VerificationResults {
+  /// Results for each interaction in the pact
+  pub interactions: Vec<InteractionVerificationResult>,
+  /// Received requests that did not correspond to any interaction
+  pub unexpected_requests: Vec<HttpRequest>
+}
+
+impl VerificationResults {
+  /// If all the interactions were matched and no unexpected requests were received
+  pub fn all_matched(&self) -> bool {
+    self.unexpected_requests.is_empty() && self.interactions.iter().all(|i| i.matched())
+  }
 }
 
 /// This trait is implemented by types which allow us to start a mock server.
@@ -80,3 +174,42 @@ impl StartMockServerAsync for RequestResponsePact {
     ValidatingHttpMockServer::start_async(self.boxed(), None, mock_server_config).await
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use expectest::prelude::*;
+  use pact_mock_server::matching::MatchResult;
+  use pact_mock_server::mock_server::MockServerMetrics;
+  use url::Url;
+
+  use crate::mock_server::ValidatingMockServer;
+
+  struct TestMockServer;
+
+  impl ValidatingMockServer for TestMockServer {
+    fn url(&self) -> Url {
+      Url::parse("http://127.0.0.1:1234").unwrap()
+    }
+
+    fn path(&self, path: &str) -> Url {
+      self.url().join(path).unwrap()
+    }
+
+    fn status(&self) -> Vec<MatchResult> {
+      vec![]
+    }
+
+    fn metrics(&self) -> MockServerMetrics {
+      MockServerMetrics::default()
+    }
+  }
+
+  #[test]
+  fn mock_servers_only_need_to_implement_the_required_methods() {
+    let mock_server = TestMockServer;
+    expect!(mock_server.verification_results()).to(be_err());
+    expect!(mock_server.received_requests()).to(be_err());
+    expect!(mock_server.pact_json()).to(be_err());
+    expect!(mock_server.write_pact_to_writer(&mut Vec::new())).to(be_err());
+  }
+}
@@ -1,7 +1,9 @@
 //! Interface to a standard HTTP mock server provided by Pact
 
 use std::{env, thread};
+use std::collections::HashMap;
 use std::fmt::Write;
+use std::fs;
 use std::path::PathBuf;
 use std::sync::Arc;
 
@@ -16,14 +18,19 @@ use pact_mock_server::mock_server::{MockServerConfig, MockServerMetrics};
 use tokio::runtime::Runtime;
 #[allow(unused_imports)] use tracing::{debug, trace, warn};
 use url::Url;
+use uuid::Uuid;
 #[cfg(feature = "colour")] use yansi::Paint;
 
 use pact_matching::metrics::{MetricEvent, send_metrics};
-use pact_models::pact::Pact;
+use pact_models::pact::{Pact, ReadWritePact};
 #[cfg(feature = "plugins")] use pact_models::plugins::PluginData;
 use pact_models::v4::http_parts::HttpRequest;
+use pact_models::v4::interaction::V4Interaction;
+use pact_models::v4::synch_http::SynchronousHttp;
+use serde_json::Value;
 
-use crate::mock_server::ValidatingMockServer;
+use crate::builders::ExpectedCalls;
+use crate::mock_server::{InteractionVerificationResult, ReceivedRequest, ValidatingMockServer, VerificationResults};
 use crate::util::panic_or_print_error;
 
 /// A mock HTTP server that handles the requests described in a `Pact`, intended
@@ -44,7 +51,9 @@ pub struct ValidatingHttpMockServer {
   // overwrite or merge Pact files
   overwrite: bool,
   // Tokio Runtime used to drive the mock server
-  runtime: Option<Arc<Runtime>>
+  runtime: Option<Arc<Runtime>>,
+  // Expected number of calls for interactions, keyed by the interaction key
+  expected_calls: HashMap<String, ExpectedCalls>
 }
 
 impl ValidatingHttpMockServer {
@@ -57,6 +66,21 @@ impl ValidatingHttpMockServer {
     pact: Box<dyn Pact + Send + Sync>,
     output_dir: Option<PathBuf>,
     mock_server_config: Option<MockServerConfig>
+  ) -> Box<dyn ValidatingMockServer> {
+    Self::start_with_expected_calls(pact, output_dir, mock_server_config, HashMap::default())
+  }
+
+  /// Create a new mock server which handles requests as described in the
+  /// pact, and runs in a background thread. The number of calls made to each interaction will be
+  /// checked against the expected calls (keyed by the interaction key).
+  ///
+  /// Panics:
+  /// Will panic if the provided Pact can not be sent to the background thread.
+  pub fn start_with_expected_calls(
+    pact: Box<dyn Pact + Send + Sync>,
+    output_dir: Option<PathBuf>,
+    mock_server_config: Option<MockServerConfig>,
+    expected_calls: HashMap<String, ExpectedCalls>
   ) -> Box<dyn ValidatingMockServer> {
     debug!("Starting mock server from pact {:?}", pact);
 
@@ -103,7 +127,8 @@ impl ValidatingHttpMockServer {
       mock_server,
       output_dir,
       overwrite: false,
-      runtime: Some(runtime)
+      runtime: Some(runtime),
+      expected_calls
     })
   }
 
@@ -140,6 +165,22 @@ impl ValidatingHttpMockServer {
     pact: Box<dyn Pact + Send + Sync>,
     output_dir: Option<PathBuf>,
     mock_server_config: Option<MockServerConfig>
+  ) -> Box<dyn ValidatingMockServer> {
+    Self::start_async_with_expected_calls(pact, output_dir, mock_server_config, HashMap::default()).await
+  }
+
+  /// Create a new mock server which handles requests as described in the
+  /// pact, and runs in a background task in the current Tokio runtime. The number of calls made
+  /// to each interaction will be checked against the expected calls (keyed by the interaction
+  /// key).
+  ///
+  /// Panics:
+  /// Will panic if unable to get the URL to the spawned mock server
+  pub async fn start_async_with_expected_calls(
+    pact: Box<dyn Pact + Send + Sync>,
+    output_dir: Option<PathBuf>,
+    mock_server_config: Option<MockServerConfig>,
+    expected_calls: HashMap<String, ExpectedCalls>
   ) -> Box<dyn ValidatingMockServer> {
     debug!("Starting mock server from pact {:?}", pact);
 
@@ -167,7 +208,8 @@ impl ValidatingHttpMockServer {
       mock_server,
       output_dir,
       overwrite: false,
-      runtime: None
+      runtime: None,
+      expected_calls
     })
   }
 
@@ -198,9 +240,20 @@ impl ValidatingHttpMockServer {
       });
     });
 
-    // Look up any mismatches which occurred with the mock server.
-    let mismatches = self.mock_server.mismatches();
-    if mismatches.is_empty() {
+    // Look up any mismatches which occurred with the mock server. Missing requests are
+    // ignored for interactions that are allowed to not be called.
+    let mismatches = self.mock_server.mismatches().iter()
+      .filter(|mismatch| match mismatch {
+        MatchResult::MissingRequest(_) => !self.interaction_for_result(mismatch)
+          .and_then(|interaction| self.expected_calls.get(&interaction.unique_key()))
+          .map(|expected| expected.is_satisfied_by(0))
+          .unwrap_or(false),
+        _ => true
+      })
+      .cloned()
+      .collect::<Vec<_>>();
+    let call_count_errors = self.call_count_errors();
+    if mismatches.is_empty() && call_count_errors.is_empty() {
       // Success! Write out the generated pact file.
       let output_dir = self.output_dir.as_ref()
         .map(|dir| {
@@ -226,24 +279,130 @@ impl ValidatingHttpMockServer {
       Ok(())
     } else {
       // Failure. Format our errors.
-      Err(anyhow!(self.display_errors(mismatches)))
+      Err(anyhow!(self.display_errors(mismatches, call_count_errors)))
+    }
+  }
+
+  // Looks up the index of the interaction in the pact that a result from the mock server is for.
+  // Matched requests are identified by both the expected request and response, so interactions
+  // with the same request are told apart. Mismatches and missing requests only have the
+  // expected request, so a missing request is attributed to an interaction with that request
+  // that has not been matched.
+  fn interaction_index(&self, result: &MatchResult) -> Option<usize> {
+    let interactions = self.mock_server.pact.interactions.iter()
+      .map(|interaction| interaction.as_v4_http())
+      .collect_vec();
+    let request_index = |request: &HttpRequest| interactions.iter()
+      .position(|interaction| matches!(interaction, Some(i) if &i.request == request));
+    match result {
+      MatchResult::RequestMatch(request, response, _) => interactions.iter()
+        .position(|interaction| matches!(interaction, Some(i) if &i.request == request && &i.response == response))
+        .or_else(|| request_index(request)),
+      MatchResult::RequestMismatch(request, _, _) => request_index(request),
+      MatchResult::MissingRequest(request) => {
+        let matched = self.mock_server.matches().iter()
+          .filter(|result| matches!(result, MatchResult::RequestMatch(..)))
+          .filter_map(|result| self.interaction_index(result))
+          .collect_vec();
+        interactions.iter().enumerate()
+          .position(|(index, interaction)| !matched.contains(&index) &&
+            matches!(interaction, Some(i) if &i.request == request))
+          .or_else(|| request_index(request))
+      }
+      MatchResult::RequestNotFound(_) => None
+    }
+  }
+
+  // Looks up the interaction in the pact that a result from the mock server is for
+  fn interaction_for_result(&self, result: &MatchResult) -> Option<SynchronousHttp> {
+    self.interaction_index(result)
+      .and_then(|index| self.mock_server.pact.interactions.get(index))
+      .and_then(|interaction| interaction.as_v4_http())
+  }
+
+  // Groups the requests received by the mock server by the interactions in the pact
+  fn interaction_results(&self) -> VerificationResults {
+    let matches = self.mock_server.matches().into_iter()
+      .map(|result| (self.interaction_index(&result), result))
+      .collect_vec();
+    let interactions = self.mock_server.pact.interactions.iter()
+      .enumerate()
+      .filter_map(|(index, interaction)| interaction.as_v4_http().map(|i| (index, i)))
+      .map(|(index, interaction)| {
+        let calls = matches.iter()
+          .filter(|(i, result)| *i == Some(index) && matches!(result, MatchResult::RequestMatch(..)))
+          .count();
+        let mismatches = matches.iter()
+          .filter_map(|(i, result)| match result {
+            MatchResult::RequestMismatch(_, actual, mismatches) if *i == Some(index) =>
+              Some((actual.clone(), mismatches.clone())),
+            _ => None
+          })
+          .collect();
+        InteractionVerificationResult {
+          key: interaction.unique_key(),
+          description: interaction.description.clone(),
+          request: interaction.request.clone(),
+          calls,
+          mismatches
+        }
+      })
+      .collect();
+    let unexpected_requests = matches.iter()
+      .filter_map(|(_, result)| match result {
+        MatchResult::RequestNotFound(request) => Some(request.clone()),
+        _ => None
+      })
+      .collect();
+    VerificationResults {
+      interactions,
+      unexpected_requests
     }
   }
 
+  // Checks the number of times each interaction was called against any expected number of calls
+  fn call_count_errors(&self) -> Vec<String> {
+    self.interaction_results().interactions.iter()
+      .filter_map(|result| {
+        let expected = self.expected_calls.get(&result.key)?;
+        if expected.is_satisfied_by(result.calls) {
+          None
+        } else {
+          Some(format!("interaction '{}' was expected to be called {}, but was called {} {}",
+            result.description, expected, result.calls, if result.calls == 1 { "time" } else { "times" }))
+        }
+      })
+      .collect()
+  }
+
+  // Looks up the source location recorded for the interaction that a result is for
+  fn source_location(&self, result: &MatchResult) -> Option<String> {
+    self.interaction_for_result(result)
+      .and_then(|interaction| interaction.comments.get("source").cloned())
+      .and_then(|source| match (source.get("file"), source.get("line")) {
+        (Some(Value::String(file)), Some(line)) => Some(format!("{}:{}", file, line)),
+        _ => None
+      })
+  }
+
   #[cfg(feature = "colour")]
-  fn display_errors(&self, mismatches: Vec<MatchResult>) -> String {
+  fn display_errors(&self, mismatches: Vec<MatchResult>, call_count_errors: Vec<String>) -> String {
     let size = termsize::get()
       .map(|sz| if sz.cols > 2 { sz.cols - 2 } else { 0 })
       .unwrap_or(78);
     let pad = "-".repeat(size as usize);
     let mut msg = format!(" {} \nMock server {} failed verification:\n", pad, self.description.white().bold());
     for mismatch in mismatches {
+      let location = self.source_location(&mismatch);
       match mismatch {
         MatchResult::RequestMatch(..) => {
           warn!("list of mismatches contains a match");
         }
         MatchResult::RequestMismatch(request, _, mismatches) => {
           let _ = writeln!(&mut msg, "\n  - request {}:\n", request);
+          if let Some(location) = &location {
+            let _ = writeln!(&mut msg, "    (interaction defined at {})\n", location);
+          }
           for m in mismatches {
             let _ = writeln!(&mut msg, "    - {}", m.description());
           }
@@ -259,30 +418,40 @@ impl ValidatingHttpMockServer {
             &mut msg,
             "\n  - request {} expected, but never occurred:\n", short_description(&request).white().bold(),
           );
+          if let Some(location) = &location {
+            let _ = writeln!(&mut msg, "    (interaction defined at {})\n", location);
+          }
           let debug_str = format!("{:#?}", request);
           let debug_padded = debug_str.lines().map(|ln| format!("      {}", ln)).join("\n");
           let _ = writeln!(&mut msg, "{}", debug_padded.italic());
         }
       }
     }
+    for error in call_count_errors {
+      let _ = writeln!(&mut msg, "\n  - {}", error);
+    }
     let _ = writeln!(&mut msg, " {} ", pad);
     msg
   }
 
   #[cfg(not(feature = "colour"))]
-  fn display_errors(&self, mismatches: Vec<MatchResult>) -> String {
+  fn display_errors(&self, mismatches: Vec<MatchResult>, call_count_errors: Vec<String>) -> String {
     let size = termsize::get()
       .map(|sz| if sz.cols > 2 { sz.cols - 2 } else { 0 })
       .unwrap_or(78);
     let pad = "-".repeat(size as usize);
     let mut msg = format!(" {} \nMock server {} failed verification:\n", pad, self.description);
     for mismatch in mismatches {
+      let location = self.source_location(&mismatch);
       match mismatch {
         MatchResult::RequestMatch(..) => {
           warn!("list of mismatches contains a match");
         }
         MatchResult::RequestMismatch(request, _, mismatches) => {
           let _ = writeln!(&mut msg, "\n  - request {}:\n", request);
+          if let Some(location) = &location {
+            let _ = writeln!(&mut msg, "    (interaction defined at {})\n", location);
+          }
           for m in mismatches {
             let _ = writeln!(&mut msg, "    - {}", m.description());
           }
@@ -297,11 +466,17 @@ impl ValidatingHttpMockServer {
             &mut msg,
             "\n  - request {} expected, but never occurred:\n", short_description(&request),
           );
+          if let Some(location) = &location {
+            let _ = writeln!(&mut msg, "    (interaction defined at {})\n", location);
+          }
           let debug_str = format!("{:#?}", request);
           let _ = writeln!(&mut msg, "{}", debug_str.lines().map(|ln| format!("      {}", ln)).join("\n"));
         }
       }
     }
+    for error in call_count_errors {
+      let _ = writeln!(&mut msg, "\n  - {}", error);
+    }
     let _ = writeln!(&mut msg, " {} ", pad);
     msg
   }
@@ -331,6 +506,58 @@ impl ValidatingMockServer for ValidatingHttpMockServer {
   fn metrics(&self) -> MockServerMetrics {
     self.mock_server.metrics.lock().unwrap().clone()
   }
+
+  fn verification_results(&self) -> anyhow::Result<VerificationResults> {
+    Ok(self.interaction_results())
+  }
+
+  fn pact_json(&self) -> anyhow::Result<Value> {
+    // Write the pact with the mock server into a temporary directory, so the JSON is exactly what
+    // is written to the pact file when the mock server is dropped (including the metadata)
+    let output_dir = env::temp_dir().join(format!("pact-consumer-{}", Uuid::new_v4()));
+    let result = self.mock_server.write_pact(&Some(output_dir.to_string_lossy().to_string()), true)
+      .map_err(|err| anyhow!("error writing pact: {}", err))
+      .and_then(|_| {
+        let pact_file = output_dir.join(self.mock_server.pact.default_file_name());
+        let contents = fs::read(&pact_file)?;
+        serde_json::from_slice(&contents)
+          .map_err(|err| anyhow!("error reading pact file {}: {}", pact_file.display(), err))
+      });
+    let _ = fs::remove_dir_all(&output_dir);
+    result
+  }
+
+  fn received_requests(&self) -> anyhow::Result<Vec<ReceivedRequest>> {
+    Ok(self.mock_server.matches().iter()
+      .filter_map(|result| match result {
+        MatchResult::RequestMatch(_, _, actual) => {
+          let interaction = self.interaction_for_result(result);
+          Some(ReceivedRequest {
+            request: actual.clone(),
+            interaction: interaction.as_ref().map(|i| i.description.clone()),
+            interaction_key: interaction.as_ref().map(|i| i.unique_key()),
+            matched: true
+          })
+        }
+        MatchResult::RequestMismatch(_, actual, _) => {
+          let interaction = self.interaction_for_result(result);
+          Some(ReceivedRequest {
+            request: actual.clone(),
+            interaction: interaction.as_ref().map(|i| i.description.clone()),
+            interaction_key: interaction.as_ref().map(|i| i.unique_key()),
+            matched: false
+          })
+        }
+        MatchResult::RequestNotFound(actual) => Some(ReceivedRequest {
+          request: actual.clone(),
+          interaction: None,
+          interaction_key: None,
+          matched: false
+        }),
+        MatchResult::MissingRequest(_) => None
+      })
+      .collect())
+  }
 }
 
 impl Drop for ValidatingHttpMockServer {
@@ -9,6 +9,7 @@ use itertools::Itertools;
 use maplit::hashmap;
 use pact_models::pact::{Pact, write_pact};
 use pact_models::PactSpecification;
+use serde_json::Value;
 use pact_plugin_driver::catalogue_manager::CatalogueEntry;
 use pact_plugin_driver::mock_server::{MockServerConfig, MockServerDetails};
 use pact_plugin_driver::plugin_manager::{shutdown_mock_server, start_mock_server_v2};
@@ -121,6 +122,20 @@ impl PluginMockServer {
     }
   }
 
+  // Returns the pact with the transport for the plugin set on all the interactions
+  fn pact_with_transport(&self) -> Box<dyn Pact + Send + Sync> {
+    let mut v4_pact = self.pact.as_v4_pact();
+    if let Ok(ref mut pact) = v4_pact {
+      for interaction in &mut pact.interactions {
+        let catalogue_entry = &self.catalogue_entry;
+        interaction.set_transport(Some(catalogue_entry.key.clone()));
+      }
+      pact.boxed()
+    } else {
+      self.pact.boxed()
+    }
+  }
+
   fn write_pact(&self) -> anyhow::Result<()> {
     let output_dir = self.output_path.as_ref().map(|dir| dir.to_string_lossy().to_string())
       .unwrap_or_else(|| {
@@ -132,17 +147,7 @@ impl PluginMockServer {
       .unwrap_or_else(|_| "false".to_owned()) == "true";
     debug!("env:PACT_OVERWRITE = {:?}", overwrite);
 
-    let mut v4_pact = self.pact.as_v4_pact();
-    let pact = if let Ok(ref mut pact) = v4_pact {
-      for interaction in &mut pact.interactions {
-        let catalogue_entry = &self.catalogue_entry;
-        interaction.set_transport(Some(catalogue_entry.key.clone()));
-      }
-      pact.boxed()
-    } else {
-      self.pact.boxed()
-    };
-
+    let pact = self.pact_with_transport();
     let pact_file_name = pact.default_file_name();
     let mut filename = PathBuf::from(output_dir);
     filename.push(pact_file_name);
@@ -171,6 +176,10 @@ impl ValidatingMockServer for PluginMockServer {
   fn metrics(&self) -> MockServerMetrics {
     MockServerMetrics::default()
   }
+
+  fn pact_json(&self) -> anyhow::Result<Value> {
+    self.pact_with_transport().to_json(PactSpecification::V4)
+  }
 }
 
 impl Drop for PluginMockServer {
@@ -1,5 +1,6 @@
 //! Special matching rules, including `Like`, `Term`, etc.
 
+use std::collections::HashMap;
 use std::iter::repeat;
 use std::marker::PhantomData;
 use itertools::{Either, Itertools};
@@ -412,6 +413,341 @@ macro_rules! matching_regex {
     }
 }
 
+/// Match and generate strings that are valid semantic versions (see <https://semver.org>).
+#[derive(Debug)]
+pub struct SemVer<Nested: Pattern> {
+    /// The example version we generate when asked.
+    example: String,
+    /// Since we always store `example` as a string, we need to mention our
+    /// `Nested` type somewhere. We can do that using the zero-length
+    /// `PhantomData` type.
+    phantom: PhantomData<Nested>,
+}
+
+impl<Nested: Pattern> SemVer<Nested> {
+    /// Construct a new `SemVer`, given the example version to generate.
+    pub fn new<S: Into<String>>(example: S) -> Self {
+        SemVer {
+            example: example.into(),
+            phantom: PhantomData,
+        }
+    }
+}
+
+impl<Nested> Pattern for SemVer<Nested>
+where
+    Nested: Pattern,
+    Nested::Matches: From<String>,
+{
+    type Matches = Nested::Matches;
+
+    fn to_example(&self) -> Self::Matches {
+        From::from(self.example.clone())
+    }
+
+    fn to_example_bytes(&self) -> Vec<u8> {
+        self.example.clone().into_bytes()
+    }
+
+    fn extract_matching_rules(&self, path: DocPath, rules_out: &mut MatchingRuleCategory) {
+        rules_out.add_rule(path, MatchingRule::Semver, RuleLogic::And);
+    }
+}
+
+impl_from_for_pattern!(SemVer<JsonPattern>, JsonPattern);
+impl_from_for_pattern!(SemVer<StringPattern>, StringPattern);
+
+#[test]
+fn semver_is_pattern() {
+    use maplit::*;
+    use serde_json::*;
+
+    let matchable = SemVer::<JsonPattern>::new("1.2.3-beta.1");
+    assert_eq!(matchable.to_example(), json!("1.2.3-beta.1"));
+
+    let mut rules = MatchingRuleCategory::empty("body");
+    matchable.extract_matching_rules(DocPath::root().join("version"), &mut rules);
+    let expected_rules = hashmap!(
+        "$.body.version".to_string() => json!({ "match": "semver" })
+    );
+    assert_eq!(rules.to_v2_json(), expected_rules);
+}
+
+#[test]
+fn semver_into() {
+    // Make sure we can convert `SemVer` into different pattern types.
+    let _: JsonPattern = SemVer::new("1.0.0").into();
+    let _: StringPattern = SemVer::new("1.0.0").into();
+}
+
+/// A pattern which matches any valid semantic version, and which generates `$example`.
+///
+/// ```
+/// use pact_consumer::*;
+///
+/// # fn main() {
+/// json_pattern!({
+///   // Match any semantic version, and generate `"1.0.0-rc.1"`.
+///   "version": semver!("1.0.0-rc.1")
+/// });
+/// # }
+/// ```
+#[macro_export]
+macro_rules! semver {
+    ($example:expr) => {
+        {
+            $crate::patterns::SemVer::new($example)
+        }
+    }
+}
+
+/// Match and generate strings that are equal to the example, ignoring case. This is useful for
+/// values like authorization schemes, where `Bearer` and `bearer` are equivalent.
+#[derive(Debug)]
+pub struct EqualsIgnoreCase<Nested: Pattern> {
+    /// The example value we generate when asked.
+    example: String,
+    /// Since we always store `example` as a string, we need to mention our
+    /// `Nested` type somewhere. We can do that using the zero-length
+    /// `PhantomData` type.
+    phantom: PhantomData<Nested>,
+}
+
+impl<Nested: Pattern> EqualsIgnoreCase<Nested> {
+    /// Construct a new `EqualsIgnoreCase`, given the example value to generate.
+    pub fn new<S: Into<String>>(example: S) -> Self {
+        EqualsIgnoreCase {
+            example: example.into(),
+            phantom: PhantomData,
+        }
+    }
+}
+
+impl<Nested> Pattern for EqualsIgnoreCase<Nested>
+where
+    Nested: Pattern,
+    Nested::Matches: From<String>,
+{
+    type Matches = Nested::Matches;
+
+    fn to_example(&self) -> Self::Matches {
+        From::from(self.example.clone())
+    }
+
+    fn to_example_bytes(&self) -> Vec<u8> {
+        self.example.clone().into_bytes()
+    }
+
+    fn extract_matching_rules(&self, path: DocPath, rules_out: &mut MatchingRuleCategory) {
+        rules_out.add_rule(path, MatchingRule::EqualsIgnoreCase, RuleLogic::And);
+    }
+}
+
+impl_from_for_pattern!(EqualsIgnoreCase<JsonPattern>, JsonPattern);
+impl_from_for_pattern!(EqualsIgnoreCase<StringPattern>, StringPattern);
+
+#[test]
+fn equals_ignore_case_is_pattern() {
+    use maplit::*;
+    use serde_json::*;
+
+    let matchable = EqualsIgnoreCase::<StringPattern>::new("Bearer abc");
+    assert_eq!(matchable.to_example(), "Bearer abc");
+
+    let mut rules = MatchingRuleCategory::empty("header");
+    matchable.extract_matching_rules(DocPath::root().join("Authorization"), &mut rules);
+    let expected_rules = hashmap!(
+        "$.headers.Authorization".to_string() => json!({ "match": "equalsIgnoreCase" })
+    );
+    assert_eq!(rules.to_v2_json(), expected_rules);
+}
+
+#[test]
+fn equals_ignore_case_into() {
+    // Make sure we can convert `EqualsIgnoreCase` into different pattern types.
+    let _: JsonPattern = EqualsIgnoreCase::new("Bearer").into();
+    let _: StringPattern = EqualsIgnoreCase::new("Bearer").into();
+}
+
+/// A pattern which matches any string equal to `$example` ignoring case, and which generates
+/// `$example`.
+///
+/// ```
+/// use pact_consumer::prelude::*;
+/// use pact_consumer::builders::RequestBuilder;
+///
+/// RequestBuilder::default()
+///     // Match `Bearer abc123`, `bearer abc123` or `BEARER ABC123`.
+///     .header("Authorization", equals_ignore_case!("Bearer abc123"));
+/// ```
+#[macro_export]
+macro_rules! equals_ignore_case {
+    ($example:expr) => {
+        {
+            $crate::patterns::EqualsIgnoreCase::new($example)
+        }
+    }
+}
+
+/// Match and generate numbers that are within a tolerance of the example value. This is useful for
+/// values like currency amounts, which can differ in the last digits once serialised.
+#[derive(Debug)]
+pub struct DecimalWithTolerance {
+    /// The example value we generate when asked.
+    example: f64,
+    /// The maximum amount the actual value can differ from the example by.
+    tolerance: f64,
+}
+
+impl DecimalWithTolerance {
+    /// Construct a new `DecimalWithTolerance`, given the example value to generate and the
+    /// tolerance to allow.
+    pub fn new(example: f64, tolerance: f64) -> Self {
+        DecimalWithTolerance {
+            example,
+            tolerance,
+        }
+    }
+}
+
+impl Pattern for DecimalWithTolerance {
+    type Matches = serde_json::Value;
+
+    fn to_example(&self) -> serde_json::Value {
+        serde_json::Value::from(self.example)
+    }
+
+    fn to_example_bytes(&self) -> Vec<u8> {
+        self.example.to_string().into_bytes()
+    }
+
+    fn extract_matching_rules(&self, path: DocPath, rules_out: &mut MatchingRuleCategory) {
+        rules_out.add_rule(path, MatchingRule::DecimalWithTolerance(self.tolerance), RuleLogic::And);
+    }
+}
+
+impl_from_for_pattern!(DecimalWithTolerance, JsonPattern);
+
+#[test]
+fn decimal_with_tolerance_is_pattern() {
+    use maplit::*;
+    use serde_json::*;
+
+    let matchable = DecimalWithTolerance::new(19.99, 0.01);
+    assert_eq!(matchable.to_example(), json!(19.99));
+
+    let mut rules = MatchingRuleCategory::empty("body");
+    matchable.extract_matching_rules(DocPath::root().join("price"), &mut rules);
+    let expected_rules = hashmap!(
+        "$.body.price".to_string() => json!({ "match": "decimalWithTolerance", "tolerance": 0.01 })
+    );
+    assert_eq!(rules.to_v2_json(), expected_rules);
+}
+
+/// A pattern which matches any number within `$tolerance` of `$example`, and which generates
+/// `$example`.
+///
+/// ```
+/// use pact_consumer::*;
+///
+/// # fn main() {
+/// json_pattern!({
+///   // Match any amount between 19.98 and 20.00, and generate `19.99`.
+///   "price": decimal_with_tolerance!(19.99, 0.01)
+/// });
+/// # }
+/// ```
+#[macro_export]
+macro_rules! decimal_with_tolerance {
+    ($example:expr, $tolerance:expr) => {
+        {
+            $crate::patterns::DecimalWithTolerance::new($example, $tolerance)
+        }
+    }
+}
+
+/// Match a value (and everything below it) against a JSON Schema document, and generate the
+/// example value. This allows an existing schema to be used in place of per-field matching rules.
+#[derive(Debug)]
+pub struct JsonSchema {
+    /// The example value we generate when asked.
+    example: JsonPattern,
+    /// The JSON Schema document the actual value must be valid against.
+    schema: serde_json::Value,
+}
+
+impl JsonSchema {
+    /// Construct a new `JsonSchema`, given the example value to generate and the schema to
+    /// validate the actual value against.
+    pub fn new<E: Into<JsonPattern>>(example: E, schema: serde_json::Value) -> Self {
+        JsonSchema {
+            example: example.into(),
+            schema,
+        }
+    }
+}
+
+impl Pattern for JsonSchema {
+    type Matches = serde_json::Value;
+
+    fn to_example(&self) -> serde_json::Value {
+        self.example.to_example()
+    }
+
+    fn to_example_bytes(&self) -> Vec<u8> {
+        self.example.to_example_bytes()
+    }
+
+    fn extract_matching_rules(&self, path: DocPath, rules_out: &mut MatchingRuleCategory) {
+        rules_out.add_rule(path, MatchingRule::JsonSchema(self.schema.clone()), RuleLogic::And);
+    }
+}
+
+impl_from_for_pattern!(JsonSchema, JsonPattern);
+
+#[test]
+fn json_schema_is_pattern() {
+    use maplit::*;
+    use serde_json::*;
+
+    let matchable = JsonSchema::new(json_pattern!({ "id": 10 }), json!({ "type": "object", "required": ["id"] }));
+    assert_eq!(matchable.to_example(), json!({ "id": 10 }));
+
+    let mut rules = MatchingRuleCategory::empty("body");
+    matchable.extract_matching_rules(DocPath::root().join("item"), &mut rules);
+    let expected_rules = hashmap!(
+        "$.body.item".to_string() => json!({ "match": "jsonSchema", "schema": { "type": "object", "required": ["id"] } })
+    );
+    assert_eq!(rules.to_v2_json(), expected_rules);
+}
+
+/// A pattern which validates the actual value against the JSON Schema `$schema`, and which
+/// generates `$example`.
+///
+/// ```
+/// use pact_consumer::*;
+/// use serde_json::json;
+///
+/// # fn main() {
+/// json_pattern!({
+///   // Match any object with an integer `id`, and generate `{ "id": 10 }`.
+///   "item": json_schema!({ "id": 10 }, json!({
+///     "type": "object",
+///     "properties": { "id": { "type": "integer" } },
+///     "required": ["id"]
+///   }))
+/// });
+/// # }
+/// ```
+#[macro_export]
+macro_rules! json_schema {
+    ($example:tt, $schema:expr) => {
+        {
+            $crate::patterns::JsonSchema::new(json_pattern!($example), $schema)
+        }
+    }
+}
+
 /// Match keys and values in an Object based on associated matching rules
 #[derive(Debug)]
 pub struct ObjectMatching {
@@ -825,3 +1161,100 @@ fn each_value_test() {
     ]
   }));
 }
+
+/// Match an array that contains elements matching each of the variants, in any order. The
+/// array may contain other elements as well.
+#[derive(Debug)]
+pub struct ArrayContaining {
+  variants: Vec<JsonPattern>
+}
+
+impl ArrayContaining {
+  /// Match arrays containing elements that match each of the `variants`.
+  pub fn new(variants: Vec<JsonPattern>) -> Self {
+    ArrayContaining {
+      variants
+    }
+  }
+}
+
+impl Pattern for ArrayContaining {
+  type Matches = Value;
+
+  fn to_example(&self) -> Self::Matches {
+    Value::Array(self.variants.iter().map(|variant| variant.to_example()).collect())
+  }
+
+  fn to_example_bytes(&self) -> Vec<u8> {
+    self.to_example().to_string().into_bytes()
+  }
+
+  fn extract_matching_rules(&self, path: DocPath, rules_out: &mut MatchingRuleCategory) {
+    let variants = self.variants.iter().enumerate().map(|(index, variant)| {
+      let mut variant_rules = MatchingRuleCategory::empty("body");
+      variant.extract_matching_rules(DocPath::root(), &mut variant_rules);
+      (index, variant_rules, HashMap::default())
+    }).collect();
+    rules_out.add_rule(path, MatchingRule::ArrayContains(variants), RuleLogic::And);
+  }
+}
+
+impl_from_for_pattern!(ArrayContaining, JsonPattern);
+
+#[test]
+fn array_containing_is_pattern() {
+  use expectest::prelude::*;
+  use maplit::hashmap;
+  use pact_models::matchingrules_list;
+  use serde_json::json;
+
+  let matchable = ArrayContaining::new(vec![
+    json_pattern!({ "id": like!(1), "type": "a" }),
+    json_pattern!("b")
+  ]);
+  expect!(matchable.to_example()).to(be_equal_to(json!([ { "id": 1, "type": "a" }, "b" ])));
+
+  let mut rules = MatchingRuleCategory::empty("body");
+  matchable.extract_matching_rules(DocPath::root().join("items"), &mut rules);
+  expect!(rules).to(be_equal_to(matchingrules_list! {
+    "body"; "$.items" => [
+      MatchingRule::ArrayContains(vec![
+        (0, matchingrules_list! { "body"; "$.id" => [ MatchingRule::Type ] }, hashmap!{}),
+        (1, MatchingRuleCategory::empty("body"), hashmap!{})
+      ])
+    ]
+  }));
+}
+
+/// Generates an array of the given variants, and matches any array that contains elements
+/// matching each of the variants, in any order. This is intended for use inside `json_pattern!`,
+/// and it interprets each of its arguments as a `json_pattern!`.
+///
+/// ```
+/// use pact_consumer::*;
+///
+/// # fn main() {
+/// json_pattern!({
+///   // Expect the actions to include a `self` link and an `edit` link, along with any others.
+///   "actions": array_containing!(
+///     { "name": "self", "href": like!("/orders/1") },
+///     { "name": "edit", "href": like!("/orders/1/edit") }
+///   ),
+/// });
+/// # }
+/// ```
+#[macro_export]
+macro_rules! array_containing {
+  ($($variant:tt)+) => {
+    $crate::patterns::ArrayContaining::new(json_pattern_internal!(@array [] $($variant)+))
+  }
+}
+
+#[test]
+fn array_containing_test() {
+  use expectest::prelude::*;
+  use serde_json::json;
+
+  let matchable = array_containing!({ "name": "self" }, like!("edit"), null);
+  expect!(matchable.to_example()).to(be_equal_to(json!([ { "name": "self" }, "edit", null ])));
+}
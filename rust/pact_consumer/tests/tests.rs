@@ -172,6 +172,23 @@ async fn mock_server_passing_validation_async_version() {
   assert_eq!(body, "That is some good Mallory.");
 }
 
+#[test_log::test(tokio::test)]
+async fn async_builder_records_source_locations_when_enabled() {
+  let pact = PactBuilderAsync::new_v4("Consumer", "Alice Service Async")
+    .with_source_locations(true)
+    .interaction("a retrieve Mallory request", "", |mut i| async move {
+      i.request.path("/mallory");
+      i
+    })
+    .await
+    .build();
+  let interactions = pact.interactions();
+  let interaction = interactions.first().unwrap().as_v4_http().unwrap();
+  let source = interaction.comments.get("source").unwrap();
+  expect!(source["file"].as_str().unwrap().ends_with("tests.rs")).to(be_true());
+  expect!(source["line"].as_u64().unwrap()).to(be_greater_than(0));
+}
+
 #[test_log::test]
 #[should_panic]
 fn mock_server_failing_validation() {
@@ -188,6 +205,284 @@ fn mock_server_failing_validation() {
     let _ = reqwest::blocking::get(url);
 }
 
+#[test_log::test]
+fn mock_server_passing_expected_calls() {
+  let hello_service = PactBuilder::new_v4("Hello CLI", "Hello Server")
+    .interaction("request a greeting", "", |mut i| {
+      i.expect_calls(ExpectedCalls::Exactly(2));
+      i.request.path("/hello");
+      i.response.body("Hello!");
+      i.clone()
+    })
+    .interaction("request a farewell", "", |mut i| {
+      i.expect_calls(ExpectedCalls::AtMost(1));
+      i.request.path("/goodbye");
+      i.response.body("Goodbye!");
+      i.clone()
+    })
+    .start_mock_server(None, None);
+  let url = hello_service.path("/hello");
+  let _ = reqwest::blocking::get(url.clone());
+  let _ = reqwest::blocking::get(url);
+}
+
+#[test_log::test]
+fn mock_server_expected_calls_with_the_same_description() {
+  let hello_service = PactBuilder::new_v4("Hello CLI", "Hello Server")
+    .interaction("request a greeting", "", |mut i| {
+      i.given("the greeting is in English");
+      i.expect_calls(ExpectedCalls::Exactly(1));
+      i.request.path("/hello");
+      i.response.body("Hello!");
+      i.clone()
+    })
+    .interaction("request a greeting", "", |mut i| {
+      i.given("the greeting is in French");
+      i.expect_calls(ExpectedCalls::Exactly(2));
+      i.request.path("/bonjour");
+      i.response.body("Bonjour!");
+      i.clone()
+    })
+    .start_mock_server(None, None);
+  let _ = reqwest::blocking::get(hello_service.path("/hello"));
+  let _ = reqwest::blocking::get(hello_service.path("/bonjour"));
+  let _ = reqwest::blocking::get(hello_service.path("/bonjour"));
+}
+
+#[test_log::test]
+#[should_panic]
+fn mock_server_failing_expected_calls() {
+  let hello_service = PactBuilder::new_v4("Hello CLI", "Hello Server")
+    .interaction("request a greeting", "", |mut i| {
+      i.expect_calls(ExpectedCalls::AtLeast(2));
+      i.request.path("/hello");
+      i.response.body("Hello!");
+      i.clone()
+    })
+    .start_mock_server(None, None);
+  // Only call the interaction once, which should lead to a panic at the end of
+  // the function.
+  let url = hello_service.path("/hello");
+  let _ = reqwest::blocking::get(url);
+}
+
+// The mock server records the received requests asynchronously, so give it some time to catch up
+fn wait_until<F: Fn() -> bool>(condition: F) {
+  for _ in 0..100 {
+    if condition() {
+      break;
+    }
+    std::thread::sleep(std::time::Duration::from_millis(10));
+  }
+}
+
+#[test_log::test]
+fn mock_server_verification_results() {
+  let hello_service = PactBuilder::new_v4("Hello CLI", "Hello Server")
+    .interaction("request a greeting", "", |mut i| {
+      i.request.path("/hello");
+      i.response.body("Hello!");
+      i.clone()
+    })
+    .interaction("request a farewell", "", |mut i| {
+      i.request.path("/goodbye");
+      i.response.body("Goodbye!");
+      i.clone()
+    })
+    .start_mock_server(None, None);
+  let _ = reqwest::blocking::get(hello_service.path("/hello"));
+  let _ = reqwest::blocking::get(hello_service.path("/hello"));
+  wait_until(|| hello_service.verification_results().unwrap().interactions[0].calls == 2);
+
+  let results = hello_service.verification_results().unwrap();
+  expect!(results.all_matched()).to(be_false());
+  expect!(results.unexpected_requests.is_empty()).to(be_true());
+  expect!(results.interactions.iter().map(|i| (i.description.as_str(), i.calls)).collect::<Vec<_>>())
+    .to(be_equal_to(vec![("request a greeting", 2), ("request a farewell", 0)]));
+
+  // Make the missing request so the mock server does not fail the test on drop
+  let _ = reqwest::blocking::get(hello_service.path("/goodbye"));
+  wait_until(|| hello_service.verification_results().unwrap().all_matched());
+  expect!(hello_service.verification_results().unwrap().all_matched()).to(be_true());
+}
+
+#[test_log::test]
+fn mock_server_verification_results_with_interactions_with_the_same_request() {
+  let hello_service = PactBuilder::new_v4("Hello CLI", "Hello Server")
+    .interaction("request a greeting", "", |mut i| {
+      i.expect_calls(ExpectedCalls::AtMost(1));
+      i.request.path("/hello");
+      i.response.body("Hello!");
+      i.clone()
+    })
+    .interaction("request another greeting", "", |mut i| {
+      i.expect_calls(ExpectedCalls::AtMost(1));
+      i.request.path("/hello");
+      i.response.body("Hi!");
+      i.clone()
+    })
+    .start_mock_server(None, None);
+  let _ = reqwest::blocking::get(hello_service.path("/hello"));
+  wait_until(|| hello_service.verification_results().unwrap().interactions.iter().any(|i| i.calls > 0));
+
+  // The request must only be counted against the interaction the mock server matched it with
+  let results = hello_service.verification_results().unwrap();
+  expect!(results.interactions.iter().map(|i| i.calls).sum::<usize>()).to(be_equal_to(1));
+  let received = hello_service.received_requests().unwrap();
+  let matched = results.interactions.iter().find(|i| i.calls == 1).unwrap();
+  expect!(received[0].interaction_key.clone()).to(be_some().value(matched.key.clone()));
+}
+
+#[test_log::test]
+fn mock_server_received_requests() {
+  let hello_service = PactBuilder::new_v4("Hello CLI", "Hello Server")
+    .interaction("request a greeting", "", |mut i| {
+      i.request.path("/hello");
+      i.response.body("Hello!");
+      i.clone()
+    })
+    .start_mock_server(None, None);
+  let _ = reqwest::blocking::get(hello_service.path("/hello"));
+  let _ = reqwest::blocking::get(hello_service.path("/hello?name=Fred"));
+  wait_until(|| hello_service.received_requests().unwrap().len() == 2);
+
+  let received = hello_service.received_requests().unwrap();
+  expect!(received.iter().map(|r| (r.request.path.as_str(), r.interaction.clone(), r.matched)).collect::<Vec<_>>())
+    .to(be_equal_to(vec![
+      ("/hello", Some("request a greeting".to_string()), true),
+      ("/hello", Some("request a greeting".to_string()), false)
+    ]));
+  expect!(received[1].request.query.clone()).to(be_some());
+  let key = hello_service.verification_results().unwrap().interactions[0].key.clone();
+  expect!(received.iter().map(|r| r.interaction_key.clone()).collect::<Vec<_>>())
+    .to(be_equal_to(vec![Some(key.clone()), Some(key)]));
+
+  // One of the requests had mismatches, so the mock server must fail verification when dropped
+  let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| drop(hello_service)));
+  expect!(result).to(be_err());
+}
+
+#[test_log::test]
+fn mock_server_write_pact_to_writer() {
+  let hello_service = PactBuilder::new_v4("Hello CLI", "Hello Server")
+    .interaction("request a greeting", "", |mut i| {
+      i.request.path("/hello");
+      i.response.body("Hello!");
+      i.clone()
+    })
+    .start_mock_server(None, None);
+  let _ = reqwest::blocking::get(hello_service.path("/hello"));
+
+  let mut buffer = Vec::new();
+  hello_service.write_pact_to_writer(&mut buffer).unwrap();
+  let json: serde_json::Value = serde_json::from_slice(&buffer).unwrap();
+  expect!(&json["consumer"]["name"]).to(be_equal_to(&json!("Hello CLI")));
+  expect!(&json["interactions"][0]["description"]).to(be_equal_to(&json!("request a greeting")));
+  expect!(&json["metadata"]["pactSpecification"]["version"]).to(be_equal_to(&json!("3.0.0")));
+  expect!(json).to(be_equal_to(hello_service.pact_json().unwrap()));
+}
+
+#[test_log::test]
+fn mock_server_pact_json_has_the_same_metadata_as_the_pact_file() {
+  let output_dir = std::env::temp_dir().join(format!("pact-consumer-test-{}", uuid::Uuid::new_v4()));
+  let json = {
+    let hello_service = PactBuilder::new_v4("Hello CLI", "Hello Metadata Server")
+      .with_output_dir(&output_dir)
+      .interaction("request a greeting", "", |mut i| {
+        i.request.path("/hello");
+        i.response.body("Hello!");
+        i.clone()
+      })
+      .start_mock_server(None, None);
+    let _ = reqwest::blocking::get(hello_service.path("/hello"));
+    hello_service.pact_json().unwrap()
+  };
+
+  let file = std::fs::File::open(output_dir.join("Hello CLI-Hello Metadata Server.json")).unwrap();
+  let written: serde_json::Value = serde_json::from_reader(file).unwrap();
+  let _ = std::fs::remove_dir_all(&output_dir);
+  expect!(&json["metadata"]["pactRust"]).to(be_equal_to(&written["metadata"]["pactRust"]));
+  expect!(json).to(be_equal_to(written));
+}
+
+#[test_log::test]
+fn mock_server_with_array_containing_matcher() {
+  let order_service = PactBuilder::new_v4("Order CLI", "Order Server")
+    .interaction("create an order", "", |mut i| {
+      i.request
+        .post()
+        .path("/orders")
+        .json_body(json_pattern!({
+          "items": array_containing!(
+            { "sku": like!("A-100"), "quantity": 1 },
+            { "sku": "GIFT-WRAP" }
+          )
+        }));
+      i.response.created();
+      i.clone()
+    })
+    .start_mock_server(None, None);
+
+  let response = reqwest::blocking::Client::new()
+    .post(order_service.path("/orders"))
+    .json(&json!({
+      "items": [
+        { "sku": "GIFT-WRAP" },
+        { "sku": "C-300", "quantity": 2 },
+        { "sku": "B-200", "quantity": 1 }
+      ]
+    }))
+    .send()
+    .expect("could not send request");
+  expect!(response.status()).to(be_equal_to(StatusCode::CREATED));
+}
+
+#[test_log::test]
+fn mock_server_with_semver_matcher() {
+  let release_service = PactBuilder::new_v4("Release CLI", "Release Server")
+    .interaction("publish a release", "", |mut i| {
+      i.request
+        .post()
+        .path("/releases")
+        .header("X-Client-Version", semver!("1.0.0"))
+        .json_body(json_pattern!({
+          "version": semver!("2.1.0-rc.1+build.5")
+        }));
+      i.response.created();
+      i.clone()
+    })
+    .start_mock_server(None, None);
+
+  let response = reqwest::blocking::Client::new()
+    .post(release_service.path("/releases"))
+    .header("X-Client-Version", "3.4.10")
+    .json(&json!({ "version": "10.0.0-alpha" }))
+    .send()
+    .expect("could not send request");
+  expect!(response.status()).to(be_equal_to(StatusCode::CREATED));
+}
+
+#[test_log::test]
+fn mock_server_with_equals_ignore_case_matcher() {
+  let auth_service = PactBuilder::new_v4("Auth CLI", "Auth Server")
+    .interaction("fetch the current user", "", |mut i| {
+      i.request
+        .get()
+        .path("/user")
+        .header("Authorization", equals_ignore_case!("Bearer abc123"));
+      i.response.ok();
+      i.clone()
+    })
+    .start_mock_server(None, None);
+
+  let response = reqwest::blocking::Client::new()
+    .get(auth_service.path("/user"))
+    .header("Authorization", "bearer ABC123")
+    .send()
+    .expect("could not send request");
+  expect!(response.status()).to(be_equal_to(StatusCode::OK));
+}
+
 #[test_log::test(tokio::test)]
 async fn duplicate_interactions() {
   let u8 = random::<u8>();
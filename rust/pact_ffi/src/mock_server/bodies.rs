@@ -3,12 +3,12 @@
 use std::path::Path;
 
 use anyhow::{anyhow, bail};
-use bytes::{Bytes, BytesMut};
+use bytes::Bytes;
 use either::Either;
 use lazy_static::lazy_static;
 use regex::Regex;
 use serde_json::{Map, Value};
-use tracing::{debug, error, trace};
+use tracing::{debug, error, trace, warn};
 
 use pact_models::bodies::OptionalBody;
 use pact_models::content_types::ContentTypeHint;
@@ -16,6 +16,7 @@ use pact_models::generators::{Generator, GeneratorCategory, Generators};
 use pact_models::json_utils::json_to_string;
 use pact_models::matchingrules::{MatchingRule, MatchingRuleCategory, RuleLogic};
 use pact_models::matchingrules::expressions::{is_matcher_def, parse_matcher_def};
+use pact_models::multipart::Multipart;
 use pact_models::path_exp::DocPath;
 use pact_models::v4::http_parts::{HttpRequest, HttpResponse};
 
@@ -301,48 +302,46 @@ pub fn request_multipart(
 }
 
 fn add_part_to_multipart(body: &OptionalBody, new_part: &OptionalBody, boundary: &str) -> Option<Bytes> {
-  if let Some(boundary_marker) = contains_existing_multipart(body) {
-    let existing_parts = body.value().unwrap_or_default();
-    let end_marker = format!("--{}--\r\n", boundary_marker);
-    let base = existing_parts.strip_suffix(end_marker.as_bytes()).unwrap_or(&existing_parts);
-    let new_part = part_body_replace_marker(new_part, boundary, &boundary_marker.as_str());
-
-    let mut bytes = BytesMut::from(base);
-    bytes.extend(new_part);
-    Some(bytes.freeze())
-  } else {
-    None
+  let boundary_marker = contains_existing_multipart(body)?;
+  let existing = Multipart::parse(&body.value().unwrap_or_default(), boundary_marker.as_str());
+  let new_parts = Multipart::parse(&new_part.value().unwrap_or_default(), boundary);
+  match (existing, new_parts) {
+    (Ok(mut existing), Ok(new_parts)) => {
+      existing.parts.extend(new_parts.parts);
+      Some(existing.to_bytes())
+    }
+    (Err(err), _) | (_, Err(err)) => {
+      warn!("Failed to parse the multipart body, will replace it - {}", err);
+      None
+    }
   }
 }
 
 /// Replace multipart marker in body
 pub fn part_body_replace_marker(body: &OptionalBody, boundary: &str, new_boundary: &str) -> Bytes {
-  let marker = format!("--{}\r\n", new_boundary);
-  let end_marker = format!("--{}--\r\n", new_boundary);
-
-  let marker_to_replace = format!("--{}\r\n", boundary);
-  let end_marker_to_replace = format!("--{}--\r\n", boundary);
-  let body = body.value().unwrap_or_default();
-  let body = body.strip_prefix(marker_to_replace.as_bytes()).unwrap_or(&body);
-  let body = body.strip_suffix(end_marker_to_replace.as_bytes()).unwrap_or(&body);
-
-  let mut bytes = BytesMut::new();
-  bytes.extend(marker.as_bytes());
-  bytes.extend(body);
-  bytes.extend(end_marker.as_bytes());
-  bytes.freeze()
+  let bytes = body.value().unwrap_or_default();
+  match Multipart::parse(&bytes, boundary) {
+    Ok(mut multipart) => {
+      multipart.boundary = new_boundary.to_string();
+      multipart.to_bytes()
+    }
+    Err(err) => {
+      warn!("Failed to parse the multipart body, the boundary marker will not be replaced - {}", err);
+      bytes
+    }
+  }
 }
 
 /// Get content type hint from body
 pub fn get_content_type_hint(body: &OptionalBody) -> Option<ContentTypeHint> {
   match &body {
-    OptionalBody::Present(_, _, hint) => *hint,
+    OptionalBody::Present(_, _, hint) | OptionalBody::File(_, _, hint) => *hint,
     _ => None
   }
 }
 
 fn contains_existing_multipart(body: &OptionalBody) -> Option<String> {
-  if let OptionalBody::Present(body, ..) = &body {
+  if let Some(body) = body.value_ref() {
     let body_str = String::from_utf8_lossy(&body);
     if let Some(captures) = MULTIPART_MARKER.captures(&body_str) {
       captures.get(1).map(|marker| marker.as_str().to_string())
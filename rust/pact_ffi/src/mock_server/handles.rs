@@ -121,7 +121,7 @@ use pact_models::{Consumer, PactSpecification, Provider};
 use pact_models::bodies::OptionalBody;
 use pact_models::content_types::{detect_content_type_from_string, ContentType, TEXT};
 use pact_models::generators::{generators_from_json, Generator, Generators};
-use pact_models::headers::parse_header;
+use pact_models::headers::{find_header, parse_header};
 use pact_models::http_parts::HttpPart;
 use pact_models::interaction::Interaction;
 use pact_models::json_utils::json_to_string;
@@ -591,8 +591,14 @@ ffi_fn! {
       interaction.with_interaction(&|_, started, inner| {
         if !started {
           if let Some(i) = inner.as_v4_mut() {
-            i.comments_mut().insert("testname".to_string(), json!(test_name));
-            0
+            match i.set_test_name(Some(test_name)) {
+              Ok(_) => 0,
+              Err(err) => {
+                error!("interaction_test_name: {}", err);
+                set_error_msg(err.to_string());
+                1
+              }
+            }
           } else {
             4
           }
@@ -1461,9 +1467,7 @@ pub extern fn pactffi_with_header_v2(
             };
             // Lookup any exiting key in the map. May have a different case
             trace!("Existing header keys = {:?}", headers.keys());
-            let name_lookup = name.to_lowercase();
-            let header_key = headers.iter()
-              .find(|(k, _v)| k.to_lowercase() == name_lookup)
+            let header_key = find_header(headers, name)
               .map(|(k, _v)| k.clone());
             if index > 0 {
               // Index is set, so we set that value to the string provided
@@ -2450,19 +2454,11 @@ ffi_fn!{
     };
 
     interaction.with_interaction(&|_, _, inner| {
-      if let Some(reqres) = inner.as_v4_http_mut() {
-        reqres.key = value.clone();
-        Ok(())
-      } else if let Some(message) = inner.as_v4_async_message_mut() {
-        message.key = value.clone();
-        Ok(())
-      } else if let Some(sync_message) = inner.as_v4_sync_message_mut() {
-        sync_message.key = value.clone();
-        Ok(())
-      } else {
-        error!("Interaction is an unknown type, is {}", inner.type_of());
-        Err(anyhow!("Interaction is an unknown type, is {}", inner.type_of()))
-      }
+      inner.set_key(value.clone())
+        .map_err(|err| {
+          error!("set_key: {}", err);
+          err
+        })
     }).unwrap_or(Err(anyhow!("Not value to unwrap"))).is_ok()
   } {
     false
@@ -2478,19 +2474,8 @@ ffi_fn!{
   /// This function will return `true` if the key was successfully updated.
   fn pactffi_set_pending(interaction: InteractionHandle, pending: bool) -> bool {
     interaction.with_interaction(&|_, _, inner| {
-      if let Some(reqres) = inner.as_v4_http_mut() {
-        reqres.pending = pending;
-        Ok(())
-      } else if let Some(message) = inner.as_v4_async_message_mut() {
-        message.pending = pending;
-        Ok(())
-      } else if let Some(sync_message) = inner.as_v4_sync_message_mut() {
-        sync_message.pending = pending;
-        Ok(())
-      } else {
-        error!("Interaction is an unknown type, is {}", inner.type_of());
-        Err(anyhow!("Interaction is an unknown type, is {}", inner.type_of()))
-      }
+      inner.set_pending(pending);
+      Ok(())
     }).unwrap_or(Err(anyhow!("Not value to unwrap"))).is_ok()
   } {
     false
@@ -2586,14 +2571,7 @@ ffi_fn!{
     };
 
     interaction.with_interaction(&|_, _, inner| {
-      match inner.comments_mut().entry("text".to_string()).or_insert(serde_json::Value::Array(vec![])) {
-        serde_json::Value::Array(array) => {
-          array.push(serde_json::Value::String(comment.to_string()));
-        },
-        other_value => {
-          *other_value = serde_json::Value::Array(vec![serde_json::Value::String(comment.to_string())]);
-        }
-      };
+      inner.add_text_comment(comment);
       Ok(())
     }).unwrap_or(Err(anyhow!("Not value to unwrap"))).is_ok()
   } {
@@ -2941,7 +2919,7 @@ pub extern fn pactffi_message_reify(message_handle: MessageHandle) -> *const c_c
     if let Some(message) = inner.as_v4_async_message() {
       match message.contents.contents {
         OptionalBody::Null => "null".to_string(),
-        OptionalBody::Present(_, _, _) => if spec_version <= pact_models::PactSpecification::V3 {
+        OptionalBody::Present(_, _, _) | OptionalBody::File(_, _, _) => if spec_version <= pact_models::PactSpecification::V3 {
           let message = message.as_message().unwrap_or_default();
           let message = block_on(generate_message(&message, &GeneratorTestMode::Consumer, &hashmap!{}, &vec![], &hashmap!{}));
           message.to_json(&spec_version).to_string()
@@ -51,12 +51,11 @@ use std::net::ToSocketAddrs;
 use std::panic::catch_unwind;
 use std::str::from_utf8;
 
-use chrono::Local;
 use either::Either;
 use libc::c_char;
 use onig::Regex;
 use pact_models::pact::Pact;
-use pact_models::time_utils::{parse_pattern, to_chrono_pattern};
+use pact_models::time_utils::generate_string;
 use rand::prelude::*;
 use serde_json::Value;
 use tokio_rustls::rustls::ServerConfig;
@@ -585,14 +584,12 @@ pub unsafe extern fn pactffi_generate_datetime_string(format: *const c_char) ->
   } else {
     let c_str = CStr::from_ptr(format);
     match c_str.to_str() {
-      Ok(s) => match parse_pattern(s) {
-        Ok(pattern_tokens) => {
-          let result = Local::now().format(to_chrono_pattern(&pattern_tokens).as_str()).to_string();
+      Ok(s) => match generate_string(s) {
+        Ok(result) => {
           let result_str = CString::new(result.as_str()).unwrap();
           StringResult::Ok(result_str.into_raw())
         },
-        Err(err) => {
-          let error = format!("Error parsing '{}': {:?}", s, err);
+        Err(error) => {
           let error_str = CString::new(error.as_str()).unwrap();
           StringResult::Failed(error_str.into_raw())
         }
@@ -1,22 +1,16 @@
 //! XML matching support
 
-use std::collections::HashMap;
-
-use either::Either;
-use maplit::hashmap;
 use serde_json::map::Map;
 use serde_json::Value;
 use serde_json::Value::Number;
-use sxd_document::dom::{ChildOfElement, Document, Element, Text};
 use sxd_document::Package;
 use sxd_document::writer::format_document;
 use tracing::{debug, trace, warn};
 
-use pact_models::generators::{Generator, GeneratorCategory, Generators};
+use pact_models::generators::{Generator, Generators};
 use pact_models::json_utils::json_to_string;
 use pact_models::matchingrules::MatchingRuleCategory;
-use pact_models::matchingrules::RuleLogic;
-use pact_models::path_exp::DocPath;
+use pact_models::xml_builder::{XmlAttribute, XmlElement, XmlNode, XmlText};
 
 use crate::mock_server::bodies::matchers_from_integration_json;
 
@@ -25,102 +19,60 @@ pub fn generate_xml_body(
   matching_rules: &mut MatchingRuleCategory,
   generators: &mut Generators
 ) -> Result<Vec<u8>, String> {
-  let package = Package::new();
-  let doc = package.as_document();
-
   trace!("generate_xml_body: attributes = {:?}", attributes);
-  match attributes.get("root") {
+  let root = match attributes.get("root") {
     Some(val) => match val {
-      Value::Object(obj) => {
-        match create_element_from_json(doc, None, obj, matching_rules, generators, &vec!["$"], false, &mut hashmap!{}) {
-          Either::Left(element) => doc.root().append_child(element),
-          Either::Right(_) => warn!("Can't append text node to the root")
+      Value::Object(obj) => match xml_node_from_json(obj) {
+        XmlNode::Element(element) => Some(element),
+        XmlNode::Text(_) => {
+          warn!("Can't append text node to the root");
+          None
         }
       },
       _ => {
         warn!("Root XML element is not an object: {}", val);
+        None
       }
     },
     None => {
       warn!("No Root XML element");
+      None
     }
-  }
+  };
 
   debug!("Done processing XML body");
-  let mut output = vec![];
-  match format_document(&doc, &mut output) {
-    Ok(_) => Ok(output),
-    Err(err) => Err(format!("Unable to generate a valid XML document: {}", err) )
+  match root {
+    Some(root) => root.build(matching_rules, generators).map_err(|err| err.to_string()),
+    None => {
+      let package = Package::new();
+      let mut output = vec![];
+      match format_document(&package.as_document(), &mut output) {
+        Ok(_) => Ok(output),
+        Err(err) => Err(format!("Unable to generate a valid XML document: {}", err))
+      }
+    }
   }
 }
 
-fn create_element_from_json<'a>(
-  doc: Document<'a>,
-  parent: Option<Element<'a>>,
-  object: &Map<String, Value>,
-  matching_rules: &mut MatchingRuleCategory,
-  generators: &mut Generators,
-  path: &Vec<&str>,
-  _type_matcher: bool,
-  namespaces: &mut HashMap<String, String>
-) -> Either<Element<'a>, Text<'a>> {
-  trace!("create_element_from_json {:?}: object = {:?}", path, object);
-  let mut element = None;
-  let mut text = None;
+fn xml_node_from_json(object: &Map<String, Value>) -> XmlNode {
+  trace!("xml_node_from_json: object = {:?}", object);
   if object.contains_key("pact:matcher:type") {
-    let mut updated_path = path.clone();
     if let Some(val) = object.get("value") {
       if let Value::Object(attr) = val {
-        let name = json_to_string(attr.get("name").unwrap());
-        updated_path.push(&name);
-        let doc_path = DocPath::new(updated_path.join(".").to_string()).unwrap_or(DocPath::root());
+        let mut element = xml_element_from_json(attr);
 
         if let Ok((rules, generator)) = matchers_from_integration_json(object) {
-          for rule in rules {
-            matching_rules.add_rule(doc_path.clone(), rule, RuleLogic::And);
-          }
-
-          if let Some(generator) = generator {
-            generators.add_generator_with_subcategory(&GeneratorCategory::BODY, doc_path.clone(), generator);
-          }
+          element.matching_rules.extend(rules);
+          element.generator = generator;
         }
 
         if let Some(gen) = object.get("pact:generator:type") {
-          match Generator::from_map(&json_to_string(gen), object) {
-            Some(generator) => generators.add_generator_with_subcategory(&GeneratorCategory::BODY, doc_path.clone(), generator),
-            _ => ()
-          };
-        }
-
-        let new_element = doc.create_element(name.as_str());
-        if let Some(attributes) = val.get("attributes") {
-          match attributes {
-            Value::Object(attributes) => {
-              let mut updated_path = path.clone();
-              updated_path.push(&name);
-              add_attributes(&new_element, attributes, matching_rules, generators, &updated_path);
-            },
-            _ => ()
-          }
-        };
-
-        if let Some(children) = val.get("children") {
-          match children {
-            Value::Array(children) => for child in children {
-              match child {
-                Value::Object(attributes) => {
-                  let mut updated_path = path.clone();
-                  updated_path.push(new_element.name().local_part());
-                  create_element_from_json(doc, Some(new_element), attributes, matching_rules, generators, &updated_path, true, namespaces);
-                },
-                _ => panic!("Intermediate JSON format is invalid, child is not an object: {:?}", child)
-              }
-            },
-            _ => panic!("Intermediate JSON format is invalid, children is not an Array: {:?}", children)
+          if let Some(generator) = Generator::from_map(&json_to_string(gen), object) {
+            element.generator = Some(generator);
           }
-        };
+        }
 
-        element = Some(new_element)
+        XmlNode::Element(element.examples(examples(object)))
       } else {
         panic!("Intermediate JSON format is invalid, corresponding value for the given matcher was not an object: {:?}", object)
       }
@@ -128,147 +80,198 @@ fn create_element_from_json<'a>(
       panic!("Intermediate JSON format is invalid, no corresponding value for the given matcher: {:?}", object)
     }
   } else if let Some(content) = object.get("content") {
-    text = Some(doc.create_text(json_to_string(content).as_str()));
+    let mut text = XmlText::new(json_to_string(content));
     if let Some(matcher) = object.get("matcher") {
-      let mut text_path = path.clone();
-      text_path.push("#text");
-      let doc_path = DocPath::new(&text_path.join(".")).unwrap_or(DocPath::root());
-
       if let Value::Object(matcher) = matcher {
         if let Ok((rules, generator)) = matchers_from_integration_json(matcher) {
-          for rule in rules {
-            matching_rules.add_rule(doc_path.clone(), rule, RuleLogic::And);
-          }
-
-          if let Some(generator) = generator {
-            generators.add_generator_with_subcategory(&GeneratorCategory::BODY, doc_path.clone(), generator);
-          }
+          text.matching_rules.extend(rules);
+          text.generator = generator;
         }
       }
       if let Some(gen) = object.get("pact:generator:type") {
         if let Value::Object(matcher) = matcher {
-          match Generator::from_map(&json_to_string(gen), matcher) {
-            Some(generator) => generators.add_generator_with_subcategory(&GeneratorCategory::BODY, doc_path, generator),
-            _ => ()
-          };
+          if let Some(generator) = Generator::from_map(&json_to_string(gen), matcher) {
+            text.generator = Some(generator);
+          }
         }
       }
     }
-  } else if let Some(name) = object.get("name") {
-    let name = json_to_string(name);
-    let new_element = doc.create_element(name.as_str());
-    if let Some(attributes) = object.get("attributes") {
-      match attributes {
-        Value::Object(attributes) => {
-          let mut updated_path = path.clone();
-          updated_path.push(&name);
-          add_attributes(&new_element, attributes, matching_rules, generators, &updated_path);
-        },
-        _ => ()
-      }
-    };
-
-    if let Some(children) = object.get("children") {
-      match children {
-        Value::Array(children) => for child in children {
-          match child {
-            Value::Object(attributes) => {
-              let mut updated_path = path.clone();
-              updated_path.push(&name);
-              create_element_from_json(doc, Some(new_element), attributes, matching_rules, generators, &updated_path, false, namespaces);
-            },
-            _ => panic!("Intermediate JSON format is invalid, child is not an object: {:?}", child)
-          }
-        },
-        _ => panic!("Intermediate JSON format is invalid, children is not an Array: {:?}", children)
-      }
-    };
-    element = Some(new_element);
+    XmlNode::Text(text)
+  } else if object.contains_key("name") {
+    XmlNode::Element(xml_element_from_json(object).examples(examples(object)))
   } else {
     panic!("Ignoring invalid object {:?}", object);
+  }
+}
+
+fn xml_element_from_json(object: &Map<String, Value>) -> XmlElement {
+  let name = json_to_string(object.get("name").unwrap());
+  let mut element = XmlElement::new(name);
+
+  if let Some(Value::Object(attributes)) = object.get("attributes") {
+    for (k, v) in attributes {
+      element = element.with_attribute(xml_attribute_from_json(k, v));
+    }
+  };
+
+  if let Some(children) = object.get("children") {
+    match children {
+      Value::Array(children) => for child in children {
+        match child {
+          Value::Object(attributes) => element.children.push(xml_node_from_json(attributes)),
+          _ => panic!("Intermediate JSON format is invalid, child is not an object: {:?}", child)
+        }
+      },
+      _ => panic!("Intermediate JSON format is invalid, children is not an Array: {:?}", children)
+    }
   };
 
-  if let Some(parent) = parent {
-    let examples = match object.get("examples") {
-      Some(val) => match val {
-        Number(val) => val.as_u64().unwrap(),
-        _ => 1
+  element
+}
+
+fn xml_attribute_from_json(name: &str, value: &Value) -> XmlAttribute {
+  let attribute = match value {
+    Value::Object(matcher_definition) => if matcher_definition.contains_key("pact:matcher:type") {
+      let mut attribute = XmlAttribute::new(name, json_to_string(matcher_definition.get("value").unwrap()));
+      if let Ok((rules, generator)) = matchers_from_integration_json(matcher_definition) {
+        attribute.matching_rules.extend(rules);
+        attribute.generator = generator;
       }
-      None => 1
-    };
 
-    if let Some(element) = element {
-      for _ in 0..examples {
-        parent.append_child(duplicate_element(doc, &element));
+      if let Some(gen) = matcher_definition.get("pact:generator:type") {
+        if let Some(generator) = Generator::from_map(&json_to_string(gen), matcher_definition) {
+          attribute.generator = Some(generator);
+        }
       }
-    } else if let Some(text) = text {
-      parent.append_child(&text);
-    }
-  }
+      attribute
+    } else {
+      XmlAttribute::new(name, json_to_string(value))
+    },
+    _ => XmlAttribute::new(name, json_to_string(value))
+  };
 
-  if let Some(element) = element {
-    Either::Left(element)
-  } else if let Some(text) = text {
-    Either::Right(text)
-  } else {
-    panic!("Intermediate JSON was mapped to neither an element or a text node")
+  trace!("xml_attribute_from_json: setting attribute key {}, value {}", attribute.name, attribute.value);
+  attribute
+}
+
+fn examples(object: &Map<String, Value>) -> usize {
+  match object.get("examples") {
+    Some(Number(val)) => val.as_u64().unwrap() as usize,
+    _ => 1
   }
 }
 
-fn add_attributes(
-  element: &Element,
-  attributes: &Map<String, Value>,
-  matching_rules: &mut MatchingRuleCategory,
-  generators: &mut Generators,
-  path: &Vec<&str>
-) {
-  trace!("add_attributes: attributes = {:?}", attributes);
-  for (k, v) in attributes {
-    let path = format!("{}['@{}']", path.join("."), k);
+#[cfg(test)]
+mod tests {
+  use expectest::prelude::*;
+  use pact_models::generators::Generators;
+  use pact_models::matchingrules::{MatchingRule, MatchingRuleCategory};
+  use pact_models::path_exp::DocPath;
+  use serde_json::{json, Value};
 
-    let value = match v {
-      Value::Object(matcher_definition) => if matcher_definition.contains_key("pact:matcher:type") {
-        let doc_path = DocPath::new(path).unwrap_or(DocPath::root());
-        if let Ok((rules, generator)) = matchers_from_integration_json(matcher_definition) {
-          for rule in rules {
-            matching_rules.add_rule(doc_path.clone(), rule, RuleLogic::And);
-          }
+  use super::generate_xml_body;
 
-          if let Some(generator) = generator {
-            generators.add_generator_with_subcategory(&GeneratorCategory::BODY, doc_path.clone(), generator);
-          }
-        }
+  fn generate(body: Value) -> (String, MatchingRuleCategory) {
+    let mut matching_rules = MatchingRuleCategory::empty("body");
+    let mut generators = Generators::default();
+    let body = generate_xml_body(body.as_object().unwrap(), &mut matching_rules, &mut generators).unwrap();
+    (String::from_utf8(body).unwrap(), matching_rules)
+  }
 
-        if let Some(gen) = matcher_definition.get("pact:generator:type") {
-          match Generator::from_map(&json_to_string(gen), matcher_definition) {
-            Some(generator) => generators.add_generator_with_subcategory(&GeneratorCategory::BODY, doc_path, generator),
-            _ => ()
-          };
-        }
-        json_to_string(matcher_definition.get("value").unwrap())
-      } else {
-        json_to_string(&v)
-      },
-      _ => json_to_string(&v)
-    };
+  #[test]
+  fn generate_xml_body_with_namespaces_and_attributes() {
+    let (body, matching_rules) = generate(json!({
+      "version": "1.0",
+      "charset": "UTF-8",
+      "root": {
+        "name": "ns1:projects",
+        "children": [
+          {
+            "pact:matcher:type": "type",
+            "value": {
+              "name": "ns1:project",
+              "children": [
+                {
+                  "name": "ns1:tasks",
+                  "children": [
+                    {
+                      "pact:matcher:type": "type",
+                      "value": {
+                        "name": "ns1:task",
+                        "children": [],
+                        "attributes": {
+                          "id": { "pact:matcher:type": "integer", "value": 1 },
+                          "name": { "pact:matcher:type": "type", "value": "Task 1" },
+                          "done": { "pact:matcher:type": "type", "value": true }
+                        }
+                      },
+                      "examples": 5
+                    }
+                  ],
+                  "attributes": {}
+                }
+              ],
+              "attributes": {
+                "id": { "pact:matcher:type": "integer", "value": 1 },
+                "type": "activity",
+                "name": { "pact:matcher:type": "type", "value": "Project 1" }
+              }
+            },
+            "examples": 2
+          }
+        ],
+        "attributes": { "id": "1234", "xmlns:ns1": "http://some.namespace/and/more/stuff" }
+      }
+    }));
 
-    trace!("add_attributes: setting attribute key {}, value {}", k.as_str(), value.as_str());
+    let task = "<ns1:task done='true' id='1' name='Task 1'/>".repeat(5);
+    let project = format!("<ns1:project id='1' name='Project 1' type='activity'><ns1:tasks>{}</ns1:tasks></ns1:project>", task);
+    expect!(body).to(be_equal_to(format!(
+      "<?xml version='1.0'?><ns1:projects id='1234' xmlns:ns1='http://some.namespace/and/more/stuff'>{}{}</ns1:projects>",
+      project, project)));
 
-    element.set_attribute_value(k.as_str(), value.as_str());
+    let mut paths = matching_rules.rules.keys().map(|path| path.to_string()).collect::<Vec<_>>();
+    paths.sort();
+    expect!(paths).to(be_equal_to(vec![
+      "$.ns1:projects.ns1:project",
+      "$.ns1:projects.ns1:project.ns1:tasks.ns1:task",
+      "$.ns1:projects.ns1:project.ns1:tasks.ns1:task['@done']",
+      "$.ns1:projects.ns1:project.ns1:tasks.ns1:task['@id']",
+      "$.ns1:projects.ns1:project.ns1:tasks.ns1:task['@name']",
+      "$.ns1:projects.ns1:project['@id']",
+      "$.ns1:projects.ns1:project['@name']"
+    ]));
   }
-}
 
-fn duplicate_element<'a>(doc: Document<'a>, el: &Element<'a>) -> Element<'a> {
-  let element = doc.create_element(el.name());
-  for attr in el.attributes() {
-    element.set_attribute_value(attr.name(), attr.value());
+  #[test]
+  fn generate_xml_body_with_text_nodes() {
+    let (body, matching_rules) = generate(json!({
+      "root": {
+        "name": "ns1:projects",
+        "attributes": { "xmlns:ns1": "urn:projects", "id": "1" },
+        "children": [
+          {
+            "name": "ns1:name",
+            "children": [
+              { "content": "Project 1", "matcher": { "pact:matcher:type": "type" } }
+            ]
+          },
+          { "content": "some text" }
+        ]
+      }
+    }));
+
+    expect!(body).to(be_equal_to(
+      "<?xml version='1.0'?><ns1:projects id='1' xmlns:ns1='urn:projects'>\
+      <ns1:name>Project 1</ns1:name>some text</ns1:projects>"));
+    expect!(matching_rules.rules.get(&DocPath::new_unwrap("$.ns1:projects.ns1:name.#text")).map(|rules| rules.rules.clone()))
+      .to(be_some().value(vec![MatchingRule::Type]));
   }
-  for child in el.children() {
-    match child {
-      ChildOfElement::Element(el) => element.append_child(duplicate_element(doc, &el)),
-      ChildOfElement::Text(txt) => element.append_child(txt),
-      _ => ()
-    }
+
+  #[test]
+  fn generate_xml_body_with_no_root_element() {
+    let (body, matching_rules) = generate(json!({ "version": "1.0" }));
+    expect!(body).to(be_equal_to("<?xml version='1.0'?>"));
+    expect!(matching_rules.is_empty()).to(be_true());
   }
-  element
 }
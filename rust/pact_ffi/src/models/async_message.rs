@@ -181,7 +181,8 @@ ffi_fn! {
 
         match &message.contents.contents {
             OptionalBody::Missing | OptionalBody::Empty | OptionalBody::Null => 0 as size_t,
-            OptionalBody::Present(bytes, _, _) => bytes.len() as size_t
+            OptionalBody::Present(bytes, _, _) => bytes.len() as size_t,
+            OptionalBody::File(file, _, _) => file.contents().map(|bytes| bytes.len()).unwrap_or_default() as size_t
         }
     } {
         0 as size_t
@@ -206,7 +207,8 @@ ffi_fn! {
 
         match &message.contents.contents {
             OptionalBody::Empty | OptionalBody::Null | OptionalBody::Missing => std::ptr::null(),
-            OptionalBody::Present(bytes, _, _) => bytes.as_ptr()
+            OptionalBody::Present(bytes, _, _) => bytes.as_ptr(),
+            OptionalBody::File(file, _, _) => file.contents().map(|bytes| bytes.as_ptr()).unwrap_or(std::ptr::null())
         }
     } {
         std::ptr::null()
@@ -226,6 +226,15 @@ ffi_fn! {
 /// | Semver | 21 |
 /// | EachKey | 22 |
 /// | EachValue | 23 |
+/// | Custom | 24 |
+/// | EqualsIgnoreCase | 25 |
+/// | DecimalWithTolerance | 26 |
+/// | TimestampWithin | 27 |
+/// | JsonSchema | 28 |
+/// | EqualsIgnoreOrder | 29 |
+/// | MinEqualsIgnoreOrder | 30 |
+/// | MaxEqualsIgnoreOrder | 31 |
+/// | MinMaxEqualsIgnoreOrder | 32 |
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum MatchingRuleResult {
   /// The matching rule from the expression.
@@ -291,7 +300,18 @@ impl MatchingRuleIterator {
               MatchingRule::NotEmpty => None,
               MatchingRule::Semver => None,
               MatchingRule::EachKey(_) => None,
-              MatchingRule::EachValue(_) => None
+              MatchingRule::EachValue(_) => None,
+              MatchingRule::Custom(name, _) => Some(CString::new(name.as_str()).unwrap()),
+              MatchingRule::EqualsIgnoreCase => None,
+              MatchingRule::DecimalWithTolerance(tolerance) => Some(CString::new(tolerance.to_string()).unwrap()),
+              MatchingRule::TimestampWithin(format, _, _) => Some(CString::new(format.as_str()).unwrap()),
+              MatchingRule::JsonSchema(schema) => Some(CString::new(schema.to_string()).unwrap()),
+              MatchingRule::EqualsIgnoreOrder => None,
+              MatchingRule::MinEqualsIgnoreOrder(min) => Some(CString::new(min.to_string()).unwrap()),
+              MatchingRule::MaxEqualsIgnoreOrder(max) => Some(CString::new(max.to_string()).unwrap()),
+              MatchingRule::MinMaxEqualsIgnoreOrder(min, max) => {
+                Some(CString::new(format!("{}:{}", min, max)).unwrap())
+              }
             };
             let rule_value = val.as_ref().map(|v| v.as_ptr()).unwrap_or_else(|| null());
             let rule_result = MatchingRuleResult::MatchingRule(rule_id(rule), rule_value, rule.clone());
@@ -345,7 +365,16 @@ fn rule_id(rule: &MatchingRule) -> u16 {
     MatchingRule::NotEmpty => 20,
     MatchingRule::Semver => 21,
     MatchingRule::EachKey(_) => 22,
-    MatchingRule::EachValue(_) => 23
+    MatchingRule::EachValue(_) => 23,
+    MatchingRule::Custom(_, _) => 24,
+    MatchingRule::EqualsIgnoreCase => 25,
+    MatchingRule::DecimalWithTolerance(_) => 26,
+    MatchingRule::TimestampWithin(_, _, _) => 27,
+    MatchingRule::JsonSchema(_) => 28,
+    MatchingRule::EqualsIgnoreOrder => 29,
+    MatchingRule::MinEqualsIgnoreOrder(_) => 30,
+    MatchingRule::MaxEqualsIgnoreOrder(_) => 31,
+    MatchingRule::MinMaxEqualsIgnoreOrder(_, _) => 32
   }
 }
 
@@ -424,6 +453,15 @@ ffi_fn! {
     /// | Semver | 21 |
     /// | EachKey | 22 |
     /// | EachValue | 23 |
+    /// | Custom | 24 |
+    /// | EqualsIgnoreCase | 25 |
+    /// | DecimalWithTolerance | 26 |
+    /// | TimestampWithin | 27 |
+    /// | JsonSchema | 28 |
+    /// | EqualsIgnoreOrder | 29 |
+    /// | MinEqualsIgnoreOrder | 30 |
+    /// | MaxEqualsIgnoreOrder | 31 |
+    /// | MinMaxEqualsIgnoreOrder | 32 |
     ///
     /// # Safety
     ///
@@ -470,6 +508,15 @@ ffi_fn! {
     /// | Semver | 21 | NULL |
     /// | EachKey | 22 | NULL |
     /// | EachValue | 23 | NULL |
+    /// | Custom | 24 | Custom matcher name |
+    /// | EqualsIgnoreCase | 25 | NULL |
+    /// | DecimalWithTolerance | 26 | Tolerance value |
+    /// | TimestampWithin | 27 | Format string |
+    /// | JsonSchema | 28 | Schema document as a JSON string |
+    /// | EqualsIgnoreOrder | 29 | NULL |
+    /// | MinEqualsIgnoreOrder | 30 | Minimum value |
+    /// | MaxEqualsIgnoreOrder | 31 | Maximum value |
+    /// | MinMaxEqualsIgnoreOrder | 32 | "min:max" |
     ///
     /// Will return a NULL pointer if the matching rule was a reference or does not have an
     /// associated value.
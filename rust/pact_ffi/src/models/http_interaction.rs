@@ -145,7 +145,8 @@ ffi_fn! {
 
         match &interaction.request.body {
             OptionalBody::Missing | OptionalBody::Empty | OptionalBody::Null => 0 as size_t,
-            OptionalBody::Present(bytes, _, _) => bytes.len() as size_t
+            OptionalBody::Present(bytes, _, _) => bytes.len() as size_t,
+            OptionalBody::File(file, _, _) => file.contents().map(|bytes| bytes.len()).unwrap_or_default() as size_t
         }
     } {
         0 as size_t
@@ -170,7 +171,8 @@ ffi_fn! {
 
         match &interaction.request.body {
             OptionalBody::Empty | OptionalBody::Null | OptionalBody::Missing => std::ptr::null(),
-            OptionalBody::Present(bytes, _, _) => bytes.as_ptr()
+            OptionalBody::Present(bytes, _, _) => bytes.as_ptr(),
+            OptionalBody::File(file, _, _) => file.contents().map(|bytes| bytes.as_ptr()).unwrap_or(std::ptr::null())
         }
     } {
         std::ptr::null()
@@ -319,7 +321,8 @@ ffi_fn! {
 
         match &interaction.response.body {
             OptionalBody::Missing | OptionalBody::Empty | OptionalBody::Null => 0 as size_t,
-            OptionalBody::Present(bytes, _, _) => bytes.len() as size_t
+            OptionalBody::Present(bytes, _, _) => bytes.len() as size_t,
+            OptionalBody::File(file, _, _) => file.contents().map(|bytes| bytes.len()).unwrap_or_default() as size_t
         }
     } {
         0 as size_t
@@ -344,7 +347,8 @@ ffi_fn! {
 
         match &interaction.response.body {
             OptionalBody::Empty | OptionalBody::Null | OptionalBody::Missing => std::ptr::null(),
-            OptionalBody::Present(bytes, _, _) => bytes.as_ptr()
+            OptionalBody::Present(bytes, _, _) => bytes.as_ptr(),
+            OptionalBody::File(file, _, _) => file.contents().map(|bytes| bytes.as_ptr()).unwrap_or(std::ptr::null())
         }
     } {
         std::ptr::null()
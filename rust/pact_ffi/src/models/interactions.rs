@@ -1,5 +1,6 @@
 //! FFI functions to support Pact interaction models.
 
+use libc::c_char;
 use pact_models::message::Message;
 use pact_models::v4::async_message::AsynchronousMessage;
 use pact_models::v4::sync_message::SynchronousMessage;
@@ -7,7 +8,7 @@ use pact_models::v4::synch_http::SynchronousHttp;
 
 use crate::{as_ref, ffi_fn};
 use crate::models::PactInteraction;
-use crate::util::ptr;
+use crate::util::{ptr, string};
 
 ffi_fn! {
   /// Casts this interaction to a `SynchronousHttp` interaction. Returns a NULL pointer if the
@@ -110,3 +111,29 @@ ffi_fn! {
     std::ptr::null()
   }
 }
+
+ffi_fn! {
+  /// Calculates the key for a V4 interaction from a hash of its contents. This is the same key
+  /// that is written to the Pact file, and ignores any key, ID or comments already set on the
+  /// interaction, so it can be used to find the same interaction across versions of a Pact.
+  /// Returns a NULL pointer if the interaction is not a V4 interaction.
+  ///
+  /// The returned string must be deleted with `pactffi_string_delete`.
+  ///
+  /// # Safety
+  /// This function is safe as long as the interaction pointer is a valid pointer.
+  ///
+  /// # Errors
+  /// On any error, this function will return a NULL pointer.
+  fn pactffi_pact_interaction_calculate_key(interaction: *const PactInteraction) -> *const c_char {
+    let interaction = as_ref!(interaction);
+    let inner = interaction.inner.lock().unwrap();
+    if let Some(v4) = inner.as_v4() {
+      string::to_c(v4.calculate_key().as_str())? as *const c_char
+    } else {
+      std::ptr::null()
+    }
+  } {
+    std::ptr::null()
+  }
+}
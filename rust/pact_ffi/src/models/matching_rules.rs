@@ -49,7 +49,9 @@ pub enum MatchingRuleCategory {
   /// Message contents (body)
   CONTENTS,
   /// Message metadata
-  METADATA
+  METADATA,
+  /// Request/Response Trailer
+  TRAILER
 }
 
 impl From<Category> for MatchingRuleCategory {
@@ -63,7 +65,8 @@ impl From<Category> for MatchingRuleCategory {
       Category::BODY => MatchingRuleCategory::BODY,
       Category::STATUS => MatchingRuleCategory::STATUS,
       Category::CONTENTS => MatchingRuleCategory::CONTENTS,
-      Category::METADATA => MatchingRuleCategory::METADATA
+      Category::METADATA => MatchingRuleCategory::METADATA,
+      Category::TRAILER => MatchingRuleCategory::TRAILER
     }
   }
 }
@@ -79,7 +82,8 @@ impl From<MatchingRuleCategory> for Category {
       MatchingRuleCategory::BODY => Category::BODY,
       MatchingRuleCategory::STATUS => Category::STATUS,
       MatchingRuleCategory::CONTENTS => Category::CONTENTS,
-      MatchingRuleCategory::METADATA => Category::METADATA
+      MatchingRuleCategory::METADATA => Category::METADATA,
+      MatchingRuleCategory::TRAILER => Category::TRAILER
     }
   }
 }
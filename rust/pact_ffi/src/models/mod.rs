@@ -151,7 +151,8 @@ mod tests {
     pactffi_pact_interaction_as_asynchronous_message,
     pactffi_pact_interaction_as_message,
     pactffi_pact_interaction_as_synchronous_http,
-    pactffi_pact_interaction_as_synchronous_message
+    pactffi_pact_interaction_as_synchronous_message,
+    pactffi_pact_interaction_calculate_key
   };
   use crate::models::iterators::{
     pactffi_pact_interaction_iter_delete,
@@ -278,6 +279,10 @@ mod tests {
     expect!(as_message.is_null()).to(be_true());
     let s_message = pactffi_pact_interaction_as_synchronous_message(first);
     expect!(s_message.is_null()).to(be_true());
+    let key_ptr = pactffi_pact_interaction_calculate_key(first);
+    expect!(key_ptr.is_null()).to(be_false());
+    let key = unsafe { CString::from_raw(key_ptr as *mut c_char) };
+    expect!(key.to_string_lossy().is_empty()).to(be_false());
 
     pactffi_sync_http_delete(http);
 
@@ -127,7 +127,8 @@ ffi_fn! {
 
         match &message.request.contents {
             OptionalBody::Missing | OptionalBody::Empty | OptionalBody::Null => 0 as size_t,
-            OptionalBody::Present(bytes, _, _) => bytes.len() as size_t
+            OptionalBody::Present(bytes, _, _) => bytes.len() as size_t,
+            OptionalBody::File(file, _, _) => file.contents().map(|bytes| bytes.len()).unwrap_or_default() as size_t
         }
     } {
         0 as size_t
@@ -152,7 +153,8 @@ ffi_fn! {
 
         match &message.request.contents {
             OptionalBody::Empty | OptionalBody::Null | OptionalBody::Missing => std::ptr::null(),
-            OptionalBody::Present(bytes, _, _) => bytes.as_ptr()
+            OptionalBody::Present(bytes, _, _) => bytes.as_ptr(),
+            OptionalBody::File(file, _, _) => file.contents().map(|bytes| bytes.as_ptr()).unwrap_or(std::ptr::null())
         }
     } {
         std::ptr::null()
@@ -369,7 +371,8 @@ ffi_fn! {
         match message.response.get(index) {
             Some(response) => match &response.contents {
                 OptionalBody::Missing | OptionalBody::Empty | OptionalBody::Null => 0 as size_t,
-                OptionalBody::Present(bytes, _, _) => bytes.len() as size_t
+                OptionalBody::Present(bytes, _, _) => bytes.len() as size_t,
+                OptionalBody::File(file, _, _) => file.contents().map(|bytes| bytes.len()).unwrap_or_default() as size_t
             }
             None => 0 as size_t
         }
@@ -397,7 +400,8 @@ ffi_fn! {
         match message.response.get(index) {
             Some(response) => match &response.contents {
                 OptionalBody::Empty | OptionalBody::Null | OptionalBody::Missing => std::ptr::null(),
-                OptionalBody::Present(bytes, _, _) => bytes.as_ptr()
+                OptionalBody::Present(bytes, _, _) => bytes.as_ptr(),
+                OptionalBody::File(file, _, _) => file.contents().map(|bytes| bytes.as_ptr()).unwrap_or(std::ptr::null())
             }
             None => std::ptr::null()
         }
@@ -16,7 +16,6 @@ use bytes::Bytes;
 #[cfg(feature = "multipart")] use http::header::{HeaderMap, HeaderName};
 #[cfg(feature = "multipart")] use itertools::Itertools;
 #[cfg(feature = "multipart")] use multer::Multipart;
-#[cfg(feature = "multipart")] use onig::Regex;
 #[cfg(feature = "multipart")] use pact_models::bodies::OptionalBody;
 use pact_models::content_types::{ContentType, detect_content_type_from_bytes};
 use pact_models::http_parts::HttpPart;
@@ -31,6 +30,7 @@ use crate::{MatchingContext, Mismatch};
 #[cfg(feature = "multipart")] use crate::{BodyMatchResult, CoreMatchingContext, HeaderMatchingContext};
 use crate::matchers::Matches;
 #[cfg(feature = "multipart")] use crate::matchers::match_values;
+#[cfg(feature = "multipart")] use crate::regex_engine::MatchingRegex;
 
 /// Compares the binary data using a magic test and comparing the resulting detected content
 /// type against the expected content type
@@ -44,7 +44,7 @@ where
   let inferred_content_type = infer::get(data)
     .map(|result| result.mime_type())
     .unwrap_or_default();
-  let inferred_match = inferred_content_type == expected;
+  let inferred_match = is_detected_content_type(inferred_content_type, &expected);
   debug!("Matching binary contents by content type: expected '{}', detection method: infer '{}' -> {}",
   expected, inferred_content_type, inferred_match);
   if inferred_match {
@@ -56,7 +56,7 @@ where
   if inferred_content_type == "" {
     // Use tree_magic_mini crate to detect via magic bytes using mime-db (requires user to install)
     magic_content_type = tree_magic_mini::from_u8(data);
-    magic_match = magic_content_type == expected;
+    magic_match = is_detected_content_type(magic_content_type, &expected);
     debug!("Matching binary contents by content type: expected '{}', detection method: tree_magic_mini '{}' -> {}",
   expected, magic_content_type, magic_match);
     if magic_match {
@@ -89,7 +89,9 @@ where
         expected,
         detected_content_type
       ))?;
-    let bytes_match = bytes_detected_content_type == ContentType::from(&expected);
+    let bytes_match = ContentType::parse(expected.as_str())
+      .map(|expected| same_base_type(&bytes_detected_content_type, &expected))
+      .unwrap_or(false);
     debug!("Matching binary contents by content type: expected '{}', detection method: detect_content_type_from_bytes '{}' -> {}",
   expected, bytes_detected_content_type, bytes_match);
     return if bytes_match {
@@ -110,6 +112,23 @@ where
   ))
 }
 
+// Compares a detected content type to the expected one. Case and any parameters (like charset) on
+// the expected content type are ignored.
+fn is_detected_content_type(detected: &str, expected: &str) -> bool {
+  if detected.is_empty() {
+    false
+  } else {
+    match (ContentType::parse(detected), ContentType::parse(expected)) {
+      (Ok(detected), Ok(expected)) => same_base_type(&detected, &expected),
+      _ => detected == expected
+    }
+  }
+}
+
+fn same_base_type(a: &ContentType, b: &ContentType) -> bool {
+  a.main_type == b.main_type && a.sub_type == b.sub_type
+}
+
 pub(crate) fn convert_data(data: &Value) -> Vec<u8> {
   match data {
     Value::String(s) => BASE64.decode(s.as_str()).unwrap_or_else(|_| s.clone().into_bytes()),
@@ -136,6 +155,7 @@ pub fn match_octet_stream(
         expected: Some(expected_body),
         actual: Some(actual_body),
         mismatch: format!("No matcher found for category 'body' and path '{}'", path),
+        location: None
       })
     } else {
       let results = matchers.rules.iter().map(|rule|
@@ -148,6 +168,7 @@ pub fn match_octet_stream(
               expected: Some(expected_body.clone()),
               actual: Some(actual_body.clone()),
               mismatch: err.to_string(),
+              location: None
             })
           }
         },
@@ -160,6 +181,7 @@ pub fn match_octet_stream(
                   expected: Some(expected_body.clone()),
                   actual: Some(actual_body.clone()),
                   mismatch: err.to_string(),
+                  location: None
                 })
               }
             }
@@ -176,7 +198,8 @@ pub fn match_octet_stream(
       actual: Some(actual_body.clone()),
       mismatch: format!("Actual body [{}, {} bytes, starting with {}] is not equal to the expected body [{}, {} bytes, starting with {}]",
         actual_ct, actual_body.len(), display_bytes(&actual_body, 32),
-        expected_ct, expected_body.len(), display_bytes(&expected_body, 32))
+        expected_ct, expected_body.len(), display_bytes(&expected_body, 32)),
+      location: None
     });
   }
 
@@ -199,6 +222,17 @@ fn display_bytes(bytes: &Bytes, max_bytes: usize) -> String {
   }
 }
 
+/// Path to a MIME part in the multipart body. Parts are identified by their name, or by their
+/// index if they don't have one.
+#[cfg(feature = "multipart")]
+fn part_path(name: &str, index: usize) -> DocPath {
+  if name.is_empty() {
+    DocPath::root().join(index.to_string())
+  } else {
+    DocPath::root().join(name)
+  }
+}
+
 #[cfg(feature = "multipart")]
 enum MimePart {
   Field(MimeField),
@@ -330,7 +364,8 @@ pub fn match_mime_multipart(
             path: "$".into(),
             expected: expected.body().value(),
             actual: actual.body().value(),
-            mismatch: format!("Timeout error, failed to parse the expected body as a MIME multipart body: {}", err)
+            mismatch: format!("Timeout error, failed to parse the expected body as a MIME multipart body: {}", err),
+            location: None
           }
         ]
       })?;
@@ -368,7 +403,8 @@ async fn match_mime_multipart_inner(
         path: "$".into(),
         expected: expected_body.value(),
         actual: actual_body.value(),
-        mismatch: format!("Failed to parse the expected body as a MIME multipart body: '{}'", e)
+        mismatch: format!("Failed to parse the expected body as a MIME multipart body: '{}'", e),
+        location: None
       });
     }
     if let Err(e) = actual_parts {
@@ -376,7 +412,8 @@ async fn match_mime_multipart_inner(
         path: "$".into(),
         expected: expected_body.value(),
         actual: actual_body.value(),
-        mismatch: format!("Failed to parse the actual body as a MIME multipart body: '{}'", e)
+        mismatch: format!("Failed to parse the actual body as a MIME multipart body: '{}'", e),
+        location: None
       });
     }
   } else {
@@ -404,10 +441,11 @@ async fn match_mime_multipart_inner(
         None => {
           debug!("MIME multipart '{}' is missing in the actual body", name);
           mismatches.push(Mismatch::BodyMismatch {
-            path: "$".into(),
+            path: part_path(name, expected_part.index()).to_string(),
             expected: Some(Bytes::from(name.clone())),
             actual: None,
-            mismatch: format!("Expected a MIME part '{}' but was missing", name)
+            mismatch: format!("Expected a MIME part '{}' but was missing", name),
+            location: None
           });
         }
       }
@@ -423,6 +461,7 @@ async fn match_mime_part(
   context: &(dyn MatchingContext + Send + Sync)
 ) -> Result<(), Vec<Mismatch>> {
   let key = expected.name();
+  let path = part_path(key, expected.index()).to_string();
 
   match (expected, actual) {
     (MimePart::Field(expected_field), MimePart::Field(actual_field)) => {
@@ -433,18 +472,18 @@ async fn match_mime_part(
     }
     (MimePart::Field(_), MimePart::File(_)) => {
       Err(vec![
-        Mismatch::BodyMismatch { path: "$".into(),
+        Mismatch::BodyMismatch { path: path.clone(),
           expected: Some(Bytes::from(key.clone())),
           actual: None,
-          mismatch: format!("Expected a MIME field '{}' but was file", key)}
+          mismatch: format!("Expected a MIME field '{}' but was file", key), location: None}
       ])
     },
     (MimePart::File(_), MimePart::Field(_)) => {
       Err(vec![
-        Mismatch::BodyMismatch { path: "$".into(),
+        Mismatch::BodyMismatch { path: path.clone(),
           expected: Some(Bytes::from(key.clone())),
           actual: None,
-          mismatch: format!("Expected a MIME file '{}' but was field", key)}
+          mismatch: format!("Expected a MIME file '{}' but was field", key), location: None}
       ])
     }
   }
@@ -458,11 +497,7 @@ fn match_field(
   context: &(dyn MatchingContext + Send + Sync)
 ) -> Result<(), Vec<Mismatch>> {
   debug!("Comparing MIME part '{}' as a field", key);
-  let path = if key.is_empty() {
-    DocPath::root().join(expected.index.to_string())
-  } else {
-    DocPath::root().join(key)
-  };
+  let path = part_path(key, expected.index);
   let expected_str = match expected.decode_data() {
     Ok(data) => String::from_utf8_lossy(data.as_ref()).to_string(),
     Err(err) => {
@@ -501,7 +536,8 @@ fn match_field(
         path: path.to_string(),
         expected: Some(expected.data.clone()),
         actual: Some(actual.data.clone()),
-        mismatch: message.clone()
+        mismatch: message.clone(),
+        location: None
       }
     }));
   }
@@ -552,7 +588,8 @@ pub(crate) fn match_headers(
             path: key_path.to_string(),
             expected: Some(Bytes::from(expected_value_str.clone())),
             actual: Some(Bytes::from(actual_value_str.clone())),
-            mismatch: format!("MIME part '{}': {}", part_name, m)
+            mismatch: format!("MIME part '{}': {}", part_name, m),
+            location: None
           }
         }));
       }
@@ -564,7 +601,8 @@ pub(crate) fn match_headers(
         expected: Some(Bytes::from(expected_value_str.clone())),
         actual: None,
         mismatch: format!("MIME part '{}': Expected multipart header '{}' with value '{}' but was missing",
-          part_name, key, expected_value_str)
+          part_name, key, expected_value_str),
+        location: None
       });
     }
   }
@@ -591,10 +629,10 @@ impl Matches<&MimeFile> for &MimeFile {
     debug!("FilePart: comparing binary data to '{:?}' using {:?}", actual.content_type, matcher);
     match matcher {
       MatchingRule::Regex(ref regex) => {
-        match Regex::new(regex) {
+        match MatchingRegex::new(regex) {
           Ok(re) => {
             match from_utf8(&*actual.data) {
-              Ok(a) => if re.is_match(a) {
+              Ok(a) => if re.is_match(a)? {
                   Ok(())
                 } else {
                   Err(anyhow!("Expected binary file '{}' to match '{}'", actual.filename, regex))
@@ -645,11 +683,7 @@ async fn match_file_part(
     key.to_string()
   };
   debug!("Comparing MIME part '{}' as binary data", part_name);
-  let path = if key.is_empty() {
-    DocPath::root().join(expected.index.to_string())
-  } else {
-    DocPath::root().join(key)
-  };
+  let path = part_path(key, expected.index);
 
   let header_result = match_headers(&path, &expected.headers, &actual.headers, context);
   debug!("Comparing headers at path '{}' -> {:?}", path, header_result);
@@ -710,12 +744,19 @@ async fn match_file_part(
     results.extend(header_mismatches);
   }
   results.extend(matcher_result.mismatches().iter().map(|m| {
-    if let Mismatch::BodyMismatch { path, expected, actual, mismatch } = m {
+    if let Mismatch::BodyMismatch { path: part_body_path, expected, actual, mismatch, .. } = m {
+      // Paths from matching the part body are relative to the part, so make them relative to the
+      // multipart body
+      let full_path = match part_body_path.strip_prefix('$') {
+        Some(remainder) => format!("{}{}", path, remainder),
+        None => part_body_path.clone()
+      };
       Mismatch::BodyMismatch {
-        path: path.clone(),
+        path: full_path,
         expected: expected.clone(),
         actual: actual.clone(),
-        mismatch: format!("MIME part '{}': {}", part_name, mismatch)
+        mismatch: format!("MIME part '{}': {}", part_name, mismatch),
+        location: None
       }
     } else {
       m.clone()
@@ -836,6 +877,14 @@ mod tests {
     }
   }
 
+  #[cfg(feature = "multipart")]
+  fn mismatch_path(m: &Mismatch) -> &str {
+    match m {
+      Mismatch::BodyMismatch { path, .. } => path.as_str(),
+      _ => ""
+    }
+  }
+
   #[test_log::test]
   #[cfg(feature = "multipart")]
   fn match_mime_multipart_error_when_not_multipart() {
@@ -938,6 +987,39 @@ mod tests {
     expect!(mismatches.iter().map(|m| mismatch(m)).collect::<Vec<&str>>()).to(be_equal_to(vec![
       "Expected a MIME part \'age\' but was missing", "Expected a MIME part \'file\' but was missing"
     ]));
+    expect!(mismatches.iter().map(mismatch_path).collect::<Vec<&str>>()).to(be_equal_to(vec![
+      "$.age", "$.file"
+    ]));
+  }
+
+  #[test_log::test]
+  #[cfg(feature = "multipart")]
+  fn match_mime_multipart_reports_paths_within_parts() {
+    let expected_body = Bytes::from("--1234\r\n\
+      Content-Type: application/json\r\n\
+      Content-Disposition: form-data; name=\"data\"; filename=\"data.json\"\r\n\r\n\
+      {\"a\": 100, \"b\": \"one\"}\r\n\
+      --1234--\r\n");
+    let expected = Request {
+      headers: Some(hashmap!{ "Content-Type".into() => vec![ "multipart/form-data; boundary=1234".into() ] }),
+      body: OptionalBody::Present(expected_body, None, None),
+      ..Request::default()
+    };
+    let actual_body = Bytes::from("--1234\r\n\
+      Content-Type: application/json\r\n\
+      Content-Disposition: form-data; name=\"data\"; filename=\"data.json\"\r\n\r\n\
+      {\"a\": 100, \"b\": \"two\"}\r\n\
+      --1234--\r\n");
+    let actual = Request {
+      headers: Some(hashmap!{ "Content-Type".into() => vec![ "multipart/form-data; boundary=1234".into() ] }),
+      body: OptionalBody::Present(actual_body, None, None),
+      ..Request::default()
+    };
+    let context = CoreMatchingContext::with_config(DiffConfig::AllowUnexpectedKeys);
+
+    let result = match_mime_multipart(&expected, &actual, &context);
+    let mismatches = result.unwrap_err();
+    expect!(mismatches.iter().map(mismatch_path).collect::<Vec<&str>>()).to(be_equal_to(vec![ "$.data.b" ]));
   }
 
   #[test_log::test(tokio::test(flavor = "multi_thread", worker_threads = 2))]
@@ -981,6 +1063,16 @@ mod tests {
 
     let result = match_mime_multipart(&expected, &actual, &context);
     let mismatches = result.unwrap_err();
+    #[cfg(feature = "csv")]
+    expect!(mismatches.iter().map(|m| mismatch(m)).collect::<Vec<&str>>()).to(be_equal_to(vec![
+      "MIME part 'name': Expected 'Fred' to be equal to 'Baxter'",
+      "MIME part 'age': Expected '2 months' to be equal to '1 month'",
+      "MIME part 'file': Expected column '1' but was missing",
+      "MIME part 'file': Expected column '2' but was missing",
+      "MIME part 'file': Expected column '3' but was missing",
+      "MIME part 'file': Expected column '4' but was missing"
+    ]));
+    #[cfg(not(feature = "csv"))]
     expect!(mismatches.iter().map(|m| mismatch(m)).collect::<Vec<&str>>()).to(be_equal_to(vec![
       "MIME part 'name': Expected 'Fred' to be equal to 'Baxter'",
       "MIME part 'age': Expected '2 months' to be equal to '1 month'",
@@ -1202,6 +1294,19 @@ mod tests {
     expect!(match_content_type(&bytes, "image/jpeg")).to(be_ok());
   }
 
+  #[test]
+  #[cfg(feature = "multipart")]
+  fn match_content_type_ignores_case_and_parameters() {
+    let bytes: [u8; 16] = [
+      0x89, 0x50, 0x4e, 0x47, 0x0d, 0x0a, 0x1a, 0x0a, 0x00, 0x00, 0x00, 0x0d, 0x49, 0x48, 0x44, 0x52
+    ];
+    expect!(match_content_type(&bytes, "IMAGE/PNG")).to(be_ok());
+    expect!(match_content_type(&bytes, "image/png; name=test.png")).to(be_ok());
+    expect!(match_content_type("{\"val\": \"some text\"}".as_bytes(), "application/json; charset=UTF-8")).to(be_ok());
+    expect!(match_content_type(&bytes, "image/jpeg").unwrap_err().to_string()).to(be_equal_to(
+      "Expected binary contents to have content type 'image/jpeg' but detected contents was 'image/png'"));
+  }
+
   #[test]
   #[cfg(feature = "multipart")]
   fn match_content_type_common_text_types() {
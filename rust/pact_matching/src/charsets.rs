@@ -0,0 +1,196 @@
+//! Support for decoding text bodies according to the charset of their content type.
+//!
+//! Bodies are matched as UTF-8, so before a body is matched, if its content type has a `charset`
+//! parameter (i.e. `application/json; charset=UTF-16LE`), the body is decoded into UTF-8 and any
+//! byte order mark is removed. Line endings can also be normalised (`\r\n` and `\r` are converted
+//! to `\n`), so text bodies that only differ in their line endings match. This is disabled by
+//! default, and can be enabled with the `PACT_MATCHING_NORMALISE_LINE_ENDINGS` environment
+//! variable, or set on the matching context with `CoreMatchingContext::with_normalised_line_endings`.
+
+use std::sync::OnceLock;
+
+use anyhow::anyhow;
+use bytes::Bytes;
+use pact_models::bodies::OptionalBody;
+use pact_models::content_types::ContentType;
+use pact_models::http_parts::HttpPart;
+use pact_models::v4::http_parts::HttpRequest;
+use tracing::{debug, warn};
+
+/// Environment variable that enables normalising the line endings of text bodies (set it to `true`)
+pub const NORMALISE_LINE_ENDINGS_ENV_VAR: &str = "PACT_MATCHING_NORMALISE_LINE_ENDINGS";
+
+/// Returns if line endings should be normalised by default. The environment variable is only read
+/// the first time this is called.
+pub(crate) fn normalise_line_endings_from_env() -> bool {
+  static NORMALISE_LINE_ENDINGS: OnceLock<bool> = OnceLock::new();
+  *NORMALISE_LINE_ENDINGS.get_or_init(|| {
+    std::env::var(NORMALISE_LINE_ENDINGS_ENV_VAR)
+      .map(|v| v == "true")
+      .unwrap_or(false)
+  })
+}
+
+/// Decodes the bytes using the given charset. UTF-8, US-ASCII, UTF-16 (using the byte order mark
+/// to determine the byte order, otherwise big-endian), UTF-16LE, UTF-16BE and ISO-8859-1 (latin-1)
+/// are supported. Any byte order mark is removed. Returns an error if the charset is not supported,
+/// or the bytes are not valid for the charset.
+pub fn decode_text(bytes: &[u8], charset: &str) -> anyhow::Result<String> {
+  match charset.trim_matches('"').to_lowercase().as_str() {
+    "utf-8" | "utf8" | "us-ascii" | "ascii" => {
+      let bytes = bytes.strip_prefix(&[0xEF, 0xBB, 0xBF]).unwrap_or(bytes);
+      String::from_utf8(bytes.to_vec())
+        .map_err(|err| anyhow!("Body is not valid UTF-8 - {}", err))
+    }
+    "utf-16" | "utf16" => if let Some(bytes) = bytes.strip_prefix(&[0xFF, 0xFE]) {
+      decode_utf16(bytes, u16::from_le_bytes)
+    } else {
+      decode_utf16(bytes.strip_prefix(&[0xFE, 0xFF]).unwrap_or(bytes), u16::from_be_bytes)
+    }
+    "utf-16le" | "utf16le" => decode_utf16(bytes.strip_prefix(&[0xFF, 0xFE]).unwrap_or(bytes), u16::from_le_bytes),
+    "utf-16be" | "utf16be" => decode_utf16(bytes.strip_prefix(&[0xFE, 0xFF]).unwrap_or(bytes), u16::from_be_bytes),
+    "iso-8859-1" | "iso8859-1" | "iso_8859-1" | "latin1" | "latin-1" | "l1" =>
+      Ok(bytes.iter().map(|b| *b as char).collect()),
+    _ => Err(anyhow!("Charset '{}' is not supported", charset))
+  }
+}
+
+fn decode_utf16(bytes: &[u8], from_bytes: fn([u8; 2]) -> u16) -> anyhow::Result<String> {
+  let chunks = bytes.chunks_exact(2);
+  if !chunks.remainder().is_empty() {
+    return Err(anyhow!("Body is not valid UTF-16 - it has an odd number of bytes ({})", bytes.len()));
+  }
+  let units = chunks
+    .map(|chunk| from_bytes([chunk[0], chunk[1]]))
+    .collect::<Vec<_>>();
+  String::from_utf16(&units).map_err(|err| anyhow!("Body is not valid UTF-16 - {}", err))
+}
+
+/// Converts all the line endings in the text (`\r\n` and `\r`) to `\n`
+pub fn normalise_line_endings(text: &str) -> String {
+  text.replace("\r\n", "\n").replace('\r', "\n")
+}
+
+/// Returns the body decoded to UTF-8 according to the charset of the content type, with the line
+/// endings normalised if `normalise_line_endings` is set (only for text content types). Returns
+/// `None` if the body does not need to be changed, or could not be decoded.
+pub fn normalise_body(
+  body: &OptionalBody,
+  content_type: &ContentType,
+  normalise_line_endings: bool
+) -> Option<OptionalBody> {
+  if let OptionalBody::Present(_, body_content_type, hint) | OptionalBody::File(_, body_content_type, hint) = body {
+    let bytes = body.value_ref()?;
+    let charset = content_type.attributes.iter()
+      .find(|(key, _)| key.eq_ignore_ascii_case("charset"))
+      .map(|(_, value)| value.as_str());
+    let text = match charset {
+      Some(charset) => match decode_text(bytes, charset) {
+        Ok(text) => text,
+        Err(err) => {
+          warn!("Not decoding the body using the charset from the content type '{}' - {}", content_type, err);
+          return None;
+        }
+      },
+      None if normalise_line_endings && content_type.is_text() => match std::str::from_utf8(bytes) {
+        Ok(text) => text.to_string(),
+        Err(_) => return None
+      },
+      None => return None
+    };
+    let text = if normalise_line_endings && content_type.is_text() {
+      self::normalise_line_endings(&text)
+    } else {
+      text
+    };
+
+    if text.as_bytes() == bytes.as_ref() {
+      None
+    } else {
+      debug!("Body was decoded from '{}'", content_type);
+      let body_content_type = body_content_type.as_ref().map(|ct| {
+        let mut ct = ct.clone();
+        ct.attributes.retain(|key, _| !key.eq_ignore_ascii_case("charset"));
+        ct
+      });
+      Some(OptionalBody::Present(Bytes::from(text), body_content_type, *hint))
+    }
+  } else {
+    None
+  }
+}
+
+/// Returns a copy of the HTTP part with the body normalised (see [`normalise_body`]), or `None`
+/// if the body does not need to be changed. Only the parts of the HTTP part used by the body
+/// matchers are copied.
+pub(crate) fn with_normalised_body(
+  part: &(dyn HttpPart + Send + Sync),
+  content_type: &ContentType,
+  normalise_line_endings: bool
+) -> Option<HttpRequest> {
+  normalise_body(part.body(), content_type, normalise_line_endings).map(|body| HttpRequest {
+    headers: part.headers().clone(),
+    body,
+    matching_rules: part.matching_rules().clone(),
+    generators: part.generators().clone(),
+    .. HttpRequest::default()
+  })
+}
+
+#[cfg(test)]
+mod tests {
+  use expectest::prelude::*;
+  use pact_models::content_types::ContentType;
+
+  use super::*;
+
+  fn utf16le(text: &str) -> Vec<u8> {
+    text.encode_utf16().flat_map(|unit| unit.to_le_bytes()).collect()
+  }
+
+  fn utf16be(text: &str) -> Vec<u8> {
+    text.encode_utf16().flat_map(|unit| unit.to_be_bytes()).collect()
+  }
+
+  #[test]
+  fn decode_text_test() {
+    expect!(decode_text("caf\u{e9}".as_bytes(), "UTF-8").unwrap()).to(be_equal_to("caf\u{e9}"));
+    expect!(decode_text(b"\xEF\xBB\xBFtext", "utf-8").unwrap()).to(be_equal_to("text"));
+    expect!(decode_text(&utf16le("{\"a\":\"\u{e9}\"}"), "UTF-16LE").unwrap()).to(be_equal_to("{\"a\":\"\u{e9}\"}"));
+    expect!(decode_text(&utf16be("text"), "utf-16be").unwrap()).to(be_equal_to("text"));
+    expect!(decode_text(&[&[0xFF, 0xFE], utf16le("text").as_slice()].concat(), "utf-16").unwrap()).to(be_equal_to("text"));
+    expect!(decode_text(&[&[0xFE, 0xFF], utf16be("text").as_slice()].concat(), "utf-16").unwrap()).to(be_equal_to("text"));
+    expect!(decode_text(&utf16be("text"), "utf-16").unwrap()).to(be_equal_to("text"));
+    expect!(decode_text(b"caf\xE9", "ISO-8859-1").unwrap()).to(be_equal_to("caf\u{e9}"));
+    expect!(decode_text(b"caf\xE9", "utf-8")).to(be_err());
+    expect!(decode_text(b"abc", "utf-16le")).to(be_err());
+    expect!(decode_text(b"abc", "koi8-r")).to(be_err());
+  }
+
+  #[test]
+  fn normalise_line_endings_test() {
+    expect!(normalise_line_endings("a\r\nb\rc\nd")).to(be_equal_to("a\nb\nc\nd"));
+  }
+
+  #[test]
+  fn normalise_body_test() {
+    let content_type = ContentType::parse("application/json; charset=UTF-16LE").unwrap();
+    let body = OptionalBody::Present(Bytes::from(utf16le("{\"a\":1}")), Some(content_type.clone()), None);
+    expect!(normalise_body(&body, &content_type, false)).to(be_some().value(
+      OptionalBody::Present(Bytes::from("{\"a\":1}"), Some(ContentType::parse("application/json").unwrap()), None)));
+
+    let content_type = ContentType::parse("text/plain").unwrap();
+    let body = OptionalBody::Present(Bytes::from("a\r\nb"), None, None);
+    expect!(normalise_body(&body, &content_type, false)).to(be_none());
+    expect!(normalise_body(&body, &content_type, true)).to(be_some().value(
+      OptionalBody::Present(Bytes::from("a\nb"), None, None)));
+
+    let content_type = ContentType::parse("text/plain; charset=utf-8").unwrap();
+    let body = OptionalBody::Present(Bytes::from("a\nb"), None, None);
+    expect!(normalise_body(&body, &content_type, true)).to(be_none());
+
+    let content_type = ContentType::parse("application/octet-stream").unwrap();
+    let body = OptionalBody::Present(Bytes::from("a\r\nb"), None, None);
+    expect!(normalise_body(&body, &content_type, true)).to(be_none());
+  }
+}
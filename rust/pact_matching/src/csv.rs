@@ -0,0 +1,375 @@
+//! Matching functions for CSV documents (`text/csv`).
+//!
+//! Each data row is matched as an item in a list, so rules configured on the root path (i.e.
+//! `$` with a `min` or `max` type matcher) apply to the number of rows. Rules for a column can
+//! either be configured using the column header name (i.e. `$[*].name`) or the index of the
+//! column (i.e. `$[*][1]`). The first row is treated as the header row, unless the content
+//! type has a `header=absent` parameter, in which case columns can only be addressed by index.
+
+use anyhow::anyhow;
+use maplit::hashset;
+use pact_models::content_types::ContentType;
+use pact_models::http_parts::HttpPart;
+use pact_models::path_exp::DocPath;
+use serde_json::Value;
+use tracing::debug;
+
+use crate::{CommonMismatch, DiffConfig, MatchingContext, Mismatch};
+use crate::matchers::match_values;
+use crate::matchingrules::compare_lists_with_matchingrules;
+
+/// Column from the expected CSV document, and where to find it in the actual document
+#[derive(Debug, Clone)]
+struct Column {
+  index: usize,
+  name: Option<String>,
+  actual_index: Option<usize>
+}
+
+/// If the CSV document for the content type has a header row. This is determined by the
+/// `header` parameter from RFC 7111, and defaults to having a header.
+pub fn has_header(content_type: &ContentType) -> bool {
+  content_type.attributes.get("header")
+    .map(|header| !header.eq_ignore_ascii_case("absent"))
+    .unwrap_or(true)
+}
+
+/// Parses a CSV document into its rows of values
+pub fn parse_csv(data: &[u8]) -> anyhow::Result<Vec<Vec<String>>> {
+  let mut reader = ::csv::ReaderBuilder::new()
+    .has_headers(false)
+    .flexible(true)
+    .from_reader(data);
+  reader.records()
+    .map(|record| record
+      .map(|record| record.iter().map(|value| value.to_string()).collect())
+      .map_err(|err| anyhow!(err)))
+    .collect()
+}
+
+/// Matches the expected CSV body to the actual, and returns any mismatches
+pub fn match_csv(
+  expected: &(dyn HttpPart + Send + Sync),
+  actual: &(dyn HttpPart + Send + Sync),
+  context: &(dyn MatchingContext + Send + Sync)
+) -> Result<(), Vec<Mismatch>> {
+  let header = expected.content_type()
+    .map(|content_type| has_header(&content_type))
+    .unwrap_or(true);
+  let expected_csv = parse_csv(&expected.body().value().unwrap_or_default());
+  let actual_csv = parse_csv(&actual.body().value().unwrap_or_default());
+  debug!("match_csv: header = {}, expected = {:?}, actual = {:?}", header, expected_csv, actual_csv);
+
+  match (expected_csv, actual_csv) {
+    (Ok(expected_csv), Ok(actual_csv)) => {
+      compare_csv(header, expected_csv, actual_csv, context)
+        .map_err(|mismatches| mismatches.iter().map(|mismatch| mismatch.to_body_mismatch()).collect())
+    }
+    (expected_csv, actual_csv) => {
+      let mut mismatches = vec![];
+      if let Err(e) = expected_csv {
+        mismatches.push(Mismatch::BodyMismatch {
+          path: "$".to_string(),
+          expected: expected.body().value(),
+          actual: actual.body().value(),
+          mismatch: format!("Failed to parse the expected body: '{}'", e),
+          location: None
+        });
+      }
+      if let Err(e) = actual_csv {
+        mismatches.push(Mismatch::BodyMismatch {
+          path: "$".to_string(),
+          expected: expected.body().value(),
+          actual: actual.body().value(),
+          mismatch: format!("Failed to parse the actual body: '{}'", e),
+          location: None
+        });
+      }
+      Err(mismatches)
+    }
+  }
+}
+
+fn compare_csv(
+  header: bool,
+  mut expected: Vec<Vec<String>>,
+  mut actual: Vec<Vec<String>>,
+  context: &(dyn MatchingContext + Send + Sync)
+) -> Result<(), Vec<CommonMismatch>> {
+  let root = DocPath::root();
+  let mut mismatches = vec![];
+
+  let columns = if header {
+    let expected_header = if expected.is_empty() { vec![] } else { expected.remove(0) };
+    let actual_header = if actual.is_empty() { vec![] } else { actual.remove(0) };
+    for name in actual_header.iter().filter(|name| !expected_header.contains(name)) {
+      if context.config() == DiffConfig::NoUnexpectedKeys {
+        mismatches.push(CommonMismatch {
+          path: root.to_string(),
+          expected: expected_header.join(","),
+          actual: actual_header.join(","),
+          description: format!("Unexpected column '{}'", name),
+          location: None
+        });
+      }
+    }
+    expected_header.iter().enumerate().map(|(index, name)| {
+      let actual_index = actual_header.iter().position(|actual_name| actual_name == name);
+      if actual_index.is_none() {
+        mismatches.push(CommonMismatch {
+          path: root.to_string(),
+          expected: expected_header.join(","),
+          actual: actual_header.join(","),
+          description: format!("Expected column '{}' but was missing", name),
+          location: None
+        });
+      }
+      Column { index, name: Some(name.clone()), actual_index }
+    }).collect()
+  } else {
+    let count = expected.iter().map(|row| row.len()).max().unwrap_or_default();
+    (0..count).map(|index| Column { index, name: None, actual_index: Some(index) }).collect::<Vec<_>>()
+  };
+
+  let expected_rows = expected.iter().map(|row| row_to_value(row)).collect::<Vec<_>>();
+  let actual_rows = actual.iter().map(|row| row_to_value(row)).collect::<Vec<_>>();
+  let mut callback = |path: &DocPath, expected: &Value, actual: &Value, context: &(dyn MatchingContext + Send + Sync)| {
+    compare_row(path, &columns, expected, actual, context)
+  };
+
+  let result = if context.matcher_is_defined(&root) {
+    debug!("compare_csv: matcher defined for the rows");
+    compare_lists_with_matchingrules(&root, &context.select_best_matcher(&root), &expected_rows,
+      &actual_rows, context, &mut callback)
+  } else {
+    let mut row_mismatches = vec![];
+    for (index, (expected, actual)) in expected_rows.iter().zip(actual_rows.iter()).enumerate() {
+      if let Err(m) = callback(&root.join(index.to_string()), expected, actual, context) {
+        row_mismatches.extend(m);
+      }
+    }
+    if expected_rows.len() != actual_rows.len() {
+      row_mismatches.push(CommonMismatch {
+        path: root.to_string(),
+        expected: expected_rows.len().to_string(),
+        actual: actual_rows.len().to_string(),
+        description: format!("Expected {} rows but received {} rows", expected_rows.len(), actual_rows.len()),
+        location: None
+      });
+    }
+    if row_mismatches.is_empty() { Ok(()) } else { Err(row_mismatches) }
+  };
+
+  if let Err(m) = result {
+    mismatches.extend(m);
+  }
+  if mismatches.is_empty() {
+    Ok(())
+  } else {
+    Err(mismatches)
+  }
+}
+
+fn row_to_value(row: &[String]) -> Value {
+  Value::Array(row.iter().map(|value| Value::String(value.clone())).collect())
+}
+
+fn compare_row(
+  path: &DocPath,
+  columns: &[Column],
+  expected: &Value,
+  actual: &Value,
+  context: &(dyn MatchingContext + Send + Sync)
+) -> Result<(), Vec<CommonMismatch>> {
+  let expected_values = expected.as_array().cloned().unwrap_or_default();
+  let actual_values = actual.as_array().cloned().unwrap_or_default();
+  let mut mismatches = vec![];
+
+  for column in columns {
+    let mut index_path = path.clone();
+    index_path.push_index(column.index);
+    let name_path = column.name.as_ref().map(|name| {
+      let mut name_path = path.clone();
+      name_path.push_field(name);
+      name_path
+    });
+    let column_path = name_path.clone().unwrap_or_else(|| index_path.clone());
+    let expected_value = expected_values.get(column.index)
+      .and_then(|value| value.as_str())
+      .unwrap_or_default();
+    let actual_value = match column.actual_index {
+      Some(actual_index) => actual_values.get(actual_index).and_then(|value| value.as_str()),
+      // Missing columns have already been reported against the header row
+      None => continue
+    };
+
+    match actual_value {
+      Some(actual_value) => {
+        // Rules defined directly against the column take precedence over any cascaded from the rows
+        let paths = name_path.iter().chain(Some(&index_path)).collect::<Vec<_>>();
+        let rule_path = paths.iter()
+          .find(|path| context.direct_matcher_defined(path, &hashset!{}))
+          .or_else(|| paths.iter().find(|path| context.matcher_is_defined(path)));
+        if let Some(&rule_path) = rule_path {
+          let matchers = context.select_best_matcher(rule_path);
+          if let Err(messages) = match_values(rule_path, &matchers, expected_value, actual_value) {
+            mismatches.extend(messages.iter().map(|message| CommonMismatch {
+              path: column_path.to_string(),
+              expected: expected_value.to_string(),
+              actual: actual_value.to_string(),
+              description: message.clone(),
+              location: None
+            }));
+          }
+        } else if expected_value != actual_value {
+          mismatches.push(CommonMismatch {
+            path: column_path.to_string(),
+            expected: expected_value.to_string(),
+            actual: actual_value.to_string(),
+            description: format!("Expected '{}' to be equal to '{}'", actual_value, expected_value),
+            location: None
+          });
+        }
+      }
+      None => mismatches.push(CommonMismatch {
+        path: column_path.to_string(),
+        expected: expected_value.to_string(),
+        actual: String::default(),
+        description: format!("Expected a value for column {} but the row only has {} columns",
+          column.name.clone().unwrap_or_else(|| column.index.to_string()), actual_values.len()),
+        location: None
+      })
+    }
+  }
+
+  if columns.iter().all(|column| column.name.is_none()) && actual_values.len() > expected_values.len()
+    && context.config() == DiffConfig::NoUnexpectedKeys {
+    mismatches.push(CommonMismatch {
+      path: path.to_string(),
+      expected: expected.to_string(),
+      actual: actual.to_string(),
+      description: format!("Expected a row with {} columns but received {} columns",
+        expected_values.len(), actual_values.len()),
+      location: None
+    });
+  }
+
+  if mismatches.is_empty() {
+    Ok(())
+  } else {
+    Err(mismatches)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use bytes::Bytes;
+  use expectest::prelude::*;
+  use maplit::hashmap;
+  use pact_models::bodies::OptionalBody;
+  use pact_models::matchingrules::MatchingRule;
+  use pact_models::matchingrules_list;
+  use pact_models::request::Request;
+
+  use crate::CoreMatchingContext;
+
+  use super::*;
+
+  fn request(body: &str, content_type: &str) -> Request {
+    Request {
+      body: OptionalBody::Present(Bytes::from(body.to_string()), Some(content_type.into()), None),
+      .. Request::default()
+    }
+  }
+
+  fn descriptions(result: Result<(), Vec<Mismatch>>) -> Vec<String> {
+    result.unwrap_err().iter().map(|mismatch| mismatch.description()).collect()
+  }
+
+  #[test]
+  fn has_header_test() {
+    expect!(has_header(&ContentType::parse("text/csv").unwrap())).to(be_true());
+    expect!(has_header(&ContentType::parse("text/csv;header=present").unwrap())).to(be_true());
+    expect!(has_header(&ContentType::parse("text/csv;header=absent").unwrap())).to(be_false());
+  }
+
+  #[test]
+  fn match_csv_with_equal_documents() {
+    let expected = request("id,name\n1,Fred\n2,\"Mary, Jane\"\n", "text/csv");
+    let actual = request("name,id\nFred,1\n\"Mary, Jane\",2\n", "text/csv");
+    expect!(match_csv(&expected, &actual, &CoreMatchingContext::default())).to(be_ok());
+  }
+
+  #[test]
+  fn match_csv_with_different_values() {
+    let expected = request("id,name\n1,Fred\n2,Mary\n", "text/csv");
+    let actual = request("id,name\n1,Fred\n2,Jane\n3,Bob\n", "text/csv");
+    expect!(descriptions(match_csv(&expected, &actual, &CoreMatchingContext::default()))).to(be_equal_to(vec![
+      "$[1].name -> Expected 'Jane' to be equal to 'Mary'".to_string(),
+      "$ -> Expected 2 rows but received 3 rows".to_string()
+    ]));
+  }
+
+  #[test]
+  fn match_csv_with_missing_and_unexpected_columns() {
+    let expected = request("id,name\n1,Fred\n", "text/csv");
+    let actual = request("id,age\n1,42\n", "text/csv");
+    expect!(descriptions(match_csv(&expected, &actual, &CoreMatchingContext::default()))).to(be_equal_to(vec![
+      "$ -> Expected column 'name' but was missing".to_string()
+    ]));
+    let context = CoreMatchingContext::with_config(DiffConfig::NoUnexpectedKeys);
+    expect!(descriptions(match_csv(&expected, &actual, &context))).to(be_equal_to(vec![
+      "$ -> Unexpected column 'age'".to_string(),
+      "$ -> Expected column 'name' but was missing".to_string()
+    ]));
+  }
+
+  #[test]
+  fn match_csv_with_column_rules() {
+    let expected = request("id,name,date\n1,Fred,2024-01-01\n", "text/csv");
+    let actual = request("id,name,date\n100,Mary,2024-05-12\n200,Jane,2023-11-30\n", "text/csv");
+    let rules = matchingrules_list! {
+      "body";
+      "$" => [ MatchingRule::MinType(1) ],
+      "$[*].id" => [ MatchingRule::Integer ],
+      "$[*][2]" => [ MatchingRule::Regex("\\d{4}-\\d{2}-\\d{2}".to_string()) ]
+    };
+    let context = CoreMatchingContext::new(DiffConfig::AllowUnexpectedKeys, &rules, &hashmap!{});
+    expect!(match_csv(&expected, &actual, &context)).to(be_ok());
+
+    let actual = request("id,name,date\n100,Mary,2024-05-12\nabc,Jane,30/11/2023\n", "text/csv");
+    expect!(descriptions(match_csv(&expected, &actual, &context))).to(be_equal_to(vec![
+      "$[1].id -> Expected 'abc' to match an integer number".to_string(),
+      "$[1].date -> Expected '30/11/2023' to match '\\d{4}-\\d{2}-\\d{2}'".to_string()
+    ]));
+  }
+
+  #[test]
+  fn match_csv_with_row_cardinality() {
+    let expected = request("id,name\n1,Fred\n", "text/csv");
+    let actual = request("id,name\n1,Fred\n2,Mary\n3,Jane\n", "text/csv");
+    let rules = matchingrules_list! {
+      "body";
+      "$" => [ MatchingRule::MinMaxType(1, 2) ]
+    };
+    let context = CoreMatchingContext::new(DiffConfig::AllowUnexpectedKeys, &rules, &hashmap!{});
+    expect!(match_csv(&expected, &actual, &context)).to(be_err());
+
+    let actual = request("id,name\n1,Fred\n2,Mary\n", "text/csv");
+    expect!(match_csv(&expected, &actual, &context)).to(be_ok());
+  }
+
+  #[test]
+  fn match_csv_without_header() {
+    let expected = request("1,Fred\n2,Mary\n", "text/csv;header=absent");
+    let actual = request("100,Fred\n2\n", "text/csv;header=absent");
+    let rules = matchingrules_list! {
+      "body";
+      "$[0][0]" => [ MatchingRule::Integer ]
+    };
+    let context = CoreMatchingContext::new(DiffConfig::AllowUnexpectedKeys, &rules, &hashmap!{});
+    expect!(descriptions(match_csv(&expected, &actual, &context))).to(be_equal_to(vec![
+      "$[1][1] -> Expected a value for column 1 but the row only has 1 columns".to_string()
+    ]));
+  }
+}
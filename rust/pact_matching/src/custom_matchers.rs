@@ -0,0 +1,166 @@
+//! User supplied matcher functions. Custom matchers are registered against a name on the matching
+//! context (see `CoreMatchingContext::with_custom_matcher`), and are invoked for any custom matching
+//! rule (`{ "match": "pact-rust:custom", "name": "<name>" }`) that references that name. This allows
+//! matching logic (like validating a JWT or checking for a ULID) to be added without needing to
+//! change the matching engine.
+//!
+//! Note that custom matching rules are not part of the Pact specification, and will only be
+//! understood by this implementation.
+
+use std::collections::HashMap;
+use std::fmt::{Debug, Formatter};
+use std::sync::Arc;
+
+use anyhow::anyhow;
+use itertools::Itertools;
+use serde_json::Value;
+use tracing::{debug, warn};
+
+use crate::MatchingContext;
+
+/// Function signature for a custom matcher. It is called with the expected and actual values and
+/// the configuration from the matching rule, and must return the list of mismatches found (an
+/// empty list means the values matched).
+pub type CustomMatcherFn = dyn Fn(&Value, &Value, &HashMap<String, Value>) -> Vec<String> + Send + Sync;
+
+/// Set of custom matchers, keyed by name
+#[derive(Clone, Default)]
+pub struct CustomMatchers {
+  matchers: HashMap<String, Arc<CustomMatcherFn>>
+}
+
+impl CustomMatchers {
+  /// Registers a custom matcher function with the given name. Any existing matcher registered with
+  /// the same name will be replaced.
+  pub fn register<F>(&mut self, name: &str, matcher: F)
+    where F: Fn(&Value, &Value, &HashMap<String, Value>) -> Vec<String> + Send + Sync + 'static {
+    debug!("Registering custom matcher '{}'", name);
+    if self.matchers.insert(name.to_string(), Arc::new(matcher)).is_some() {
+      warn!("Custom matcher '{}' was already registered, it has been replaced", name);
+    }
+  }
+
+  /// If a custom matcher has been registered with the given name
+  pub fn is_registered(&self, name: &str) -> bool {
+    self.matchers.contains_key(name)
+  }
+
+  /// If there are no custom matchers registered
+  pub fn is_empty(&self) -> bool {
+    self.matchers.is_empty()
+  }
+
+  /// Applies the custom matcher registered with the given name to the expected and actual values.
+  /// Returns an error if there is no matcher registered with that name, or if the matcher returned
+  /// any mismatches.
+  pub fn match_custom(
+    &self,
+    name: &str,
+    config: &HashMap<String, Value>,
+    expected: &Value,
+    actual: &Value
+  ) -> anyhow::Result<()> {
+    match self.matchers.get(name) {
+      Some(matcher) => {
+        let mismatches = matcher(expected, actual, config);
+        if mismatches.is_empty() {
+          Ok(())
+        } else {
+          Err(anyhow!(mismatches.join(", ")))
+        }
+      }
+      None => Err(anyhow!("No custom matcher has been registered with name '{}'", name))
+    }
+  }
+}
+
+impl Debug for CustomMatchers {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    f.debug_struct("CustomMatchers")
+      .field("matchers", &self.matchers.keys().sorted().collect_vec())
+      .finish()
+  }
+}
+
+/// Values that can be passed to a custom matcher
+pub trait CustomMatcherValue {
+  /// Returns the value as JSON
+  fn custom_matcher_value(&self) -> Value;
+}
+
+impl CustomMatcherValue for Value {
+  fn custom_matcher_value(&self) -> Value {
+    self.clone()
+  }
+}
+
+impl CustomMatcherValue for &Value {
+  fn custom_matcher_value(&self) -> Value {
+    (*self).clone()
+  }
+}
+
+impl CustomMatcherValue for &str {
+  fn custom_matcher_value(&self) -> Value {
+    Value::String(self.to_string())
+  }
+}
+
+impl CustomMatcherValue for String {
+  fn custom_matcher_value(&self) -> Value {
+    Value::String(self.clone())
+  }
+}
+
+/// Applies the custom matcher registered with the matching context to the expected and actual
+/// values. Returns an error if the context does not have a matcher registered with that name.
+pub fn match_custom<E: CustomMatcherValue, A: CustomMatcherValue>(
+  name: &str,
+  config: &HashMap<String, Value>,
+  expected: &E,
+  actual: &A,
+  context: &dyn MatchingContext
+) -> anyhow::Result<()> {
+  match context.custom_matchers() {
+    Some(matchers) => matchers.match_custom(name, config, &expected.custom_matcher_value(),
+      &actual.custom_matcher_value()),
+    None => Err(anyhow!("No custom matcher has been registered with name '{}'", name))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use expectest::prelude::*;
+  use maplit::hashmap;
+  use serde_json::json;
+
+  use crate::{CoreMatchingContext, DiffConfig};
+
+  use super::*;
+
+  #[test]
+  fn match_custom_test() {
+    let context = CoreMatchingContext::with_config(DiffConfig::AllowUnexpectedKeys)
+      .with_custom_matcher("prefix", |_, actual, config| {
+        let prefix = config.get("prefix").and_then(|p| p.as_str()).unwrap_or_default();
+        match actual.as_str() {
+          Some(s) if s.starts_with(prefix) => vec![],
+          _ => vec![format!("Expected {} to start with '{}'", actual, prefix)]
+        }
+      });
+    let config = hashmap!{ "prefix".to_string() => json!("01") };
+
+    expect!(context.custom_matchers().unwrap().is_registered("prefix")).to(be_true());
+    expect!(match_custom("prefix", &config, &json!("01ARZ"), &json!("01BX5"), &context)).to(be_ok());
+    expect!(match_custom("prefix", &config, &"01ARZ", &"01BX5", &context)).to(be_ok());
+    expect!(match_custom("prefix", &config, &json!("01ARZ"), &json!("7ZZZZ"), &context).unwrap_err().to_string())
+      .to(be_equal_to("Expected \"7ZZZZ\" to start with '01'"));
+    expect!(match_custom("prefix", &config, &json!("01ARZ"), &json!(100), &context)).to(be_err());
+    expect!(match_custom("other", &config, &json!("01ARZ"), &json!("01BX5"), &context).unwrap_err().to_string())
+      .to(be_equal_to("No custom matcher has been registered with name 'other'"));
+
+    let context = CoreMatchingContext::with_config(DiffConfig::AllowUnexpectedKeys);
+    expect!(match_custom("prefix", &config, &json!("01ARZ"), &json!("01BX5"), &context).unwrap_err().to_string())
+      .to(be_equal_to("No custom matcher has been registered with name 'prefix'"));
+  }
+}
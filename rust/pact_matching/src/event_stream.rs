@@ -0,0 +1,217 @@
+//! Matching functions for bodies that contain a stream of events, either server-sent events
+//! (`text/event-stream`) or newline delimited JSON (`application/x-ndjson`).
+//!
+//! The stream is split into events, and the events are then compared as a JSON list. This means
+//! matching rules can be applied to each event (i.e. `$[*].data.id`), and the number of events can
+//! be matched with a `min` or `max` type matcher on the root path. An `arrayContains` matcher can
+//! be used to ignore the order of the events.
+//!
+//! For NDJSON, each non-empty line is parsed as a JSON document. For server-sent events, each event
+//! is converted to a JSON object with the `event`, `data`, `id` and `retry` fields that were set,
+//! and the data will be parsed as JSON if it is a JSON document.
+
+use pact_models::content_types::ContentType;
+use pact_models::http_parts::HttpPart;
+use pact_models::path_exp::DocPath;
+use serde_json::{Map, Value};
+use tracing::debug;
+
+use crate::{MatchingContext, Mismatch};
+use crate::json::compare_json;
+
+/// If the content type is for a stream of server-sent events
+pub fn is_event_stream(content_type: &ContentType) -> bool {
+  content_type.base_type() == "text/event-stream"
+}
+
+/// If the content type is for newline delimited JSON
+pub fn is_ndjson(content_type: &ContentType) -> bool {
+  matches!(content_type.base_type().to_string().as_str(),
+    "application/x-ndjson" | "application/ndjson" | "application/jsonl" | "application/x-jsonlines")
+}
+
+/// Parses a newline delimited JSON document into the list of JSON values. Blank lines are ignored.
+pub fn parse_ndjson(data: &str) -> Result<Vec<Value>, String> {
+  data.lines()
+    .enumerate()
+    .filter(|(_, line)| !line.trim().is_empty())
+    .map(|(index, line)| serde_json::from_str(line)
+      .map_err(|err| format!("line {} is not valid JSON - {}", index + 1, err)))
+    .collect()
+}
+
+/// Parses a stream of server-sent events. Each event is returned as a JSON object with the fields
+/// that were set on the event. Comment lines are ignored, and multiple data lines are joined with
+/// a newline.
+pub fn parse_event_stream(data: &str) -> Vec<Value> {
+  let mut events = vec![];
+  let mut event = Map::new();
+  let mut data_lines: Vec<&str> = vec![];
+  for line in data.lines().chain(Some("")) {
+    if line.is_empty() {
+      if !data_lines.is_empty() {
+        let data = data_lines.join("\n");
+        let value = serde_json::from_str(&data).unwrap_or(Value::String(data));
+        event.insert("data".to_string(), value);
+        data_lines.clear();
+      }
+      if !event.is_empty() {
+        events.push(Value::Object(event));
+        event = Map::new();
+      }
+    } else if !line.starts_with(':') {
+      let (field, value) = line.split_once(':').unwrap_or((line, ""));
+      let value = value.strip_prefix(' ').unwrap_or(value);
+      match field {
+        "data" => data_lines.push(value),
+        "event" | "id" => {
+          event.insert(field.to_string(), Value::String(value.to_string()));
+        }
+        "retry" => if let Ok(retry) = value.parse::<u64>() {
+          event.insert(field.to_string(), Value::from(retry));
+        }
+        _ => debug!("Ignoring unknown event stream field '{}'", field)
+      }
+    }
+  }
+  events
+}
+
+/// Matches the expected event stream body to the actual, and returns any mismatches. The content
+/// type of the expected body is used to determine how the stream is split into events.
+pub fn match_event_stream(
+  expected: &(dyn HttpPart + Send + Sync),
+  actual: &(dyn HttpPart + Send + Sync),
+  context: &(dyn MatchingContext + Send + Sync)
+) -> Result<(), Vec<Mismatch>> {
+  let ndjson = expected.content_type().map(|ct| is_ndjson(&ct)).unwrap_or_default();
+  let expected_body = String::from_utf8_lossy(&expected.body().value().unwrap_or_default()).to_string();
+  let actual_body = String::from_utf8_lossy(&actual.body().value().unwrap_or_default()).to_string();
+  let (expected_events, actual_events) = if ndjson {
+    (parse_ndjson(&expected_body), parse_ndjson(&actual_body))
+  } else {
+    (Ok(parse_event_stream(&expected_body)), Ok(parse_event_stream(&actual_body)))
+  };
+  debug!("match_event_stream: expected = {:?}, actual = {:?}", expected_events, actual_events);
+
+  match (expected_events, actual_events) {
+    (Ok(expected_events), Ok(actual_events)) => {
+      compare_json(&DocPath::root(), &Value::Array(expected_events), &Value::Array(actual_events), context)
+        .map_err(|mismatches| mismatches.iter().map(|mismatch| mismatch.to_body_mismatch()).collect())
+    }
+    (expected_events, actual_events) => {
+      let mut mismatches = vec![];
+      if let Err(e) = expected_events {
+        mismatches.push(Mismatch::BodyMismatch {
+          path: "$".to_string(),
+          expected: expected.body().value(),
+          actual: actual.body().value(),
+          mismatch: format!("Failed to parse the expected body: {}", e),
+          location: None
+        });
+      }
+      if let Err(e) = actual_events {
+        mismatches.push(Mismatch::BodyMismatch {
+          path: "$".to_string(),
+          expected: expected.body().value(),
+          actual: actual.body().value(),
+          mismatch: format!("Failed to parse the actual body: {}", e),
+          location: None
+        });
+      }
+      Err(mismatches)
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use bytes::Bytes;
+  use expectest::prelude::*;
+  use maplit::hashmap;
+  use pact_models::bodies::OptionalBody;
+  use pact_models::matchingrules::MatchingRule;
+  use pact_models::matchingrules_list;
+  use pact_models::request::Request;
+  use serde_json::json;
+
+  use crate::{CoreMatchingContext, DiffConfig};
+
+  use super::*;
+
+  fn request(body: &str, content_type: &str) -> Request {
+    Request {
+      body: OptionalBody::Present(Bytes::from(body.to_string()), Some(content_type.into()), None),
+      .. Request::default()
+    }
+  }
+
+  fn descriptions(result: Result<(), Vec<Mismatch>>) -> Vec<String> {
+    result.unwrap_err().iter().map(|mismatch| mismatch.description()).collect()
+  }
+
+  #[test]
+  fn parse_event_stream_test() {
+    let stream = ": keep-alive\n\nevent: update\nid: 1\ndata: {\"id\": 100}\n\ndata: line one\ndata: line two\nretry: 5000\n\n";
+    expect!(parse_event_stream(stream)).to(be_equal_to(vec![
+      json!({"event": "update", "id": "1", "data": {"id": 100}}),
+      json!({"data": "line one\nline two", "retry": 5000})
+    ]));
+    expect!(parse_event_stream("data: no trailing newline")).to(be_equal_to(vec![
+      json!({"data": "no trailing newline"})
+    ]));
+  }
+
+  #[test]
+  fn parse_ndjson_test() {
+    expect!(parse_ndjson("{\"a\": 1}\n\n[1, 2]\n")).to(be_ok().value(vec![json!({"a": 1}), json!([1, 2])]));
+    expect!(parse_ndjson("{\"a\": 1}\n{\"a\": ")).to(be_err());
+  }
+
+  #[test]
+  fn match_ndjson_with_rules() {
+    let expected = request("{\"id\": 1, \"name\": \"Fred\"}\n", "application/x-ndjson");
+    let actual = request("{\"id\": 100, \"name\": \"Mary\"}\n{\"id\": 200, \"name\": \"Jane\"}\n", "application/x-ndjson");
+    expect!(descriptions(match_event_stream(&expected, &actual, &CoreMatchingContext::default()))).to(be_equal_to(vec![
+      "$[0].id -> Expected 100 (Integer) to be equal to 1 (Integer)".to_string(),
+      "$[0].name -> Expected 'Mary' (String) to be equal to 'Fred' (String)".to_string(),
+      "$ -> Expected a List with 1 elements but received 2 elements".to_string()
+    ]));
+
+    let rules = matchingrules_list! {
+      "body";
+      "$" => [ MatchingRule::MinType(1) ],
+      "$[*].id" => [ MatchingRule::Integer ]
+    };
+    let context = CoreMatchingContext::new(DiffConfig::AllowUnexpectedKeys, &rules, &hashmap!{});
+    expect!(match_event_stream(&expected, &actual, &context)).to(be_ok());
+  }
+
+  #[test]
+  fn match_event_stream_with_rules() {
+    let expected = request("event: created\ndata: {\"id\": 1}\n\n", "text/event-stream");
+    let actual = request("event: created\ndata: {\"id\": 10}\n\nevent: created\ndata: {\"id\": 20}\n\n", "text/event-stream");
+    let rules = matchingrules_list! {
+      "body";
+      "$" => [ MatchingRule::MinMaxType(1, 2) ],
+      "$[*].event" => [ MatchingRule::Equality ],
+      "$[*].data.id" => [ MatchingRule::Integer ]
+    };
+    let context = CoreMatchingContext::new(DiffConfig::AllowUnexpectedKeys, &rules, &hashmap!{});
+    expect!(match_event_stream(&expected, &actual, &context)).to(be_ok());
+
+    let actual = request("event: deleted\ndata: {\"id\": 10}\n\n", "text/event-stream");
+    expect!(descriptions(match_event_stream(&expected, &actual, &context))).to(be_equal_to(vec![
+      "$[0].event -> Expected 'deleted' (String) to be equal to 'created' (String)".to_string()
+    ]));
+  }
+
+  #[test]
+  fn match_ndjson_with_invalid_line() {
+    let expected = request("{\"id\": 1}\n", "application/x-ndjson");
+    let actual = request("{\"id\": 1}\nnot json\n", "application/x-ndjson");
+    expect!(descriptions(match_event_stream(&expected, &actual, &CoreMatchingContext::default()))).to(be_equal_to(vec![
+      "$ -> Failed to parse the actual body: line 2 is not valid JSON - expected ident at line 1 column 2".to_string()
+    ]));
+  }
+}
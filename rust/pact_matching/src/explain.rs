@@ -0,0 +1,205 @@
+//! Support for explaining which matching rules were applied during matching.
+//!
+//! When a [`MatchingExplanation`] is attached to a matching context (see
+//! `CoreMatchingContext::with_explanation`), every time the context selects the matching rules for
+//! a path, the selection is recorded. This includes rules that were cascaded from a parent path,
+//! and paths where no rules applied and the values were compared with equality. This can be used
+//! to diagnose why a matching rule (i.e. from a `like!` pattern) did not apply to a value.
+//!
+//! Each selection is also emitted as a structured trace event with the
+//! `pact_matching::explain` target.
+
+use std::sync::{Arc, Mutex};
+
+use pact_models::matchingrules::{Category, RuleList};
+use pact_models::path_exp::DocPath;
+use serde_json::{json, Value};
+use tracing::debug;
+
+/// Matching rules that were selected for a path
+#[derive(Debug, Clone, PartialEq)]
+pub enum RuleSelection {
+  /// Rules were selected for the path. The rule path is the path the rules were defined against,
+  /// if it is known, and the rule list will be marked as cascaded if the rules were defined
+  /// against a parent of the path.
+  Rules {
+    /// Path the selected rules were defined against
+    rule_path: Option<DocPath>,
+    /// Rules that were selected
+    rules: RuleList
+  },
+  /// There were no rules for the path, so the values will be compared with equality
+  Equality
+}
+
+/// Record of the rules selected for a path
+#[derive(Debug, Clone, PartialEq)]
+pub struct RuleTrace {
+  /// Category of the matching rules
+  pub category: Category,
+  /// Path that was being matched
+  pub path: DocPath,
+  /// Rules that were selected for the path
+  pub selection: RuleSelection
+}
+
+impl RuleTrace {
+  /// Converts this trace record to a JSON form
+  pub fn to_json(&self) -> Value {
+    match &self.selection {
+      RuleSelection::Rules { rule_path, rules } => json!({
+        "category": self.category.to_string(),
+        "path": self.path.to_string(),
+        "rulePath": rule_path.as_ref().map(|p| p.to_string()),
+        "rules": rules.rules.iter().map(|rule| rule.to_json()).collect::<Vec<_>>(),
+        "cascaded": rules.cascaded
+      }),
+      RuleSelection::Equality => json!({
+        "category": self.category.to_string(),
+        "path": self.path.to_string(),
+        "equality": true
+      })
+    }
+  }
+}
+
+/// Records the matching rules selected while matching. Clones of this struct share the same
+/// records, so it can be attached to a context that is cloned during matching.
+#[derive(Debug, Clone, Default)]
+pub struct MatchingExplanation {
+  traces: Arc<Mutex<Vec<RuleTrace>>>
+}
+
+impl MatchingExplanation {
+  /// Creates a new, empty, explanation
+  pub fn new() -> Self {
+    MatchingExplanation::default()
+  }
+
+  /// Records the rules selected for a path. Repeated selections for the same path are only
+  /// recorded once.
+  pub fn record(&self, category: &Category, path: &DocPath, selection: RuleSelection) {
+    let trace = RuleTrace {
+      category: category.clone(),
+      path: path.clone(),
+      selection
+    };
+    match &trace.selection {
+      RuleSelection::Rules { rule_path, rules } => debug!(
+        target: "pact_matching::explain",
+        category = %category,
+        path = %path,
+        rule_path = rule_path.as_ref().map(|p| p.to_string()),
+        rules = ?rules.rules,
+        cascaded = rules.cascaded,
+        "Selected matching rules"
+      ),
+      RuleSelection::Equality => debug!(
+        target: "pact_matching::explain",
+        category = %category,
+        path = %path,
+        "No matching rules, using equality"
+      )
+    }
+
+    let mut traces = self.traces.lock().unwrap();
+    if !traces.contains(&trace) {
+      traces.push(trace);
+    }
+  }
+
+  /// Returns the recorded selections, in the order they were made
+  pub fn traces(&self) -> Vec<RuleTrace> {
+    self.traces.lock().unwrap().clone()
+  }
+
+  /// Returns the selections recorded for the given path
+  pub fn traces_for_path(&self, path: &DocPath) -> Vec<RuleTrace> {
+    self.traces.lock().unwrap().iter()
+      .filter(|trace| &trace.path == path)
+      .cloned()
+      .collect()
+  }
+
+  /// Returns the recorded selections as a JSON array
+  pub fn to_json(&self) -> Value {
+    Value::Array(self.traces.lock().unwrap().iter().map(|trace| trace.to_json()).collect())
+  }
+
+  /// Clears all the recorded selections
+  pub fn clear(&self) {
+    self.traces.lock().unwrap().clear();
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use expectest::prelude::*;
+  use maplit::hashmap;
+  use pact_models::matchingrules::MatchingRule;
+  use pact_models::matchingrules_list;
+  use serde_json::json;
+
+  use crate::{CoreMatchingContext, DiffConfig, MatchingContext};
+  use crate::json::compare_json;
+
+  use super::*;
+
+  #[test]
+  fn records_the_rules_selected_while_matching() {
+    let rules = matchingrules_list! {
+      "body";
+      "$.items" => [ MatchingRule::MinType(1) ],
+      "$.items[*].id" => [ MatchingRule::Integer ]
+    };
+    let explanation = MatchingExplanation::new();
+    let context = CoreMatchingContext::new(DiffConfig::AllowUnexpectedKeys, &rules, &hashmap!{})
+      .with_explanation(&explanation);
+
+    let expected = json!({ "name": "Fred", "items": [{ "id": 1, "status": "ok" }] });
+    let actual = json!({ "name": "Mary", "items": [{ "id": 100, "status": "failed" }] });
+    let result = compare_json(&DocPath::root(), &expected, &actual, &context);
+    expect!(result).to(be_err());
+
+    expect!(explanation.traces_for_path(&DocPath::new_unwrap("$.name"))).to(be_equal_to(vec![
+      RuleTrace {
+        category: Category::BODY,
+        path: DocPath::new_unwrap("$.name"),
+        selection: RuleSelection::Equality
+      }
+    ]));
+    expect!(explanation.traces_for_path(&DocPath::new_unwrap("$.items[0].id"))).to(be_equal_to(vec![
+      RuleTrace {
+        category: Category::BODY,
+        path: DocPath::new_unwrap("$.items[0].id"),
+        selection: RuleSelection::Rules {
+          rule_path: Some(DocPath::new_unwrap("$.items[*].id")),
+          rules: RuleList::new(MatchingRule::Integer)
+        }
+      }
+    ]));
+    let status_traces = explanation.traces_for_path(&DocPath::new_unwrap("$.items[0].status"));
+    expect!(status_traces.iter().map(|trace| trace.to_json()).collect::<Vec<_>>()).to(be_equal_to(vec![
+      json!({
+        "category": "body",
+        "path": "$.items[0].status",
+        "rulePath": "$.items",
+        "rules": [{ "match": "type", "min": 1 }],
+        "cascaded": true
+      })
+    ]));
+  }
+
+  #[test]
+  fn explanation_is_shared_with_cloned_contexts() {
+    let explanation = MatchingExplanation::new();
+    let context = CoreMatchingContext::default().with_explanation(&explanation);
+    let rules = matchingrules_list! { "body"; "$.id" => [ MatchingRule::Integer ] };
+    let cloned = context.clone_with(&rules);
+    cloned.select_best_matcher(&DocPath::new_unwrap("$.id"));
+    expect!(explanation.traces().len()).to(be_equal_to(1));
+
+    explanation.clear();
+    expect!(explanation.traces().is_empty()).to(be_true());
+  }
+}
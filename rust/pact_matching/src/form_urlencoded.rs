@@ -1,8 +1,9 @@
-//! Functions for matching `multipart/form` bodies
+//! Functions for matching `application/x-www-form-urlencoded` bodies
 
 use itertools::Itertools;
 use pact_models::bodies::OptionalBody;
 use pact_models::http_parts::HttpPart;
+use pact_models::path_exp::DocPath;
 use tracing::debug;
 
 use crate::{MatchingContext, Mismatch};
@@ -25,18 +26,21 @@ pub(crate) fn match_form_urlencoded(
           path: "$".into(),
           expected: expected_body.value(),
           actual: actual_body.value(),
-          mismatch: format!("Expected an empty body, but got '{}'", actual_body.value_as_string().unwrap_or(actual_body.display_string()))
+          mismatch: format!("Expected an empty body, but got '{}'", actual_body.value_as_string().unwrap_or(actual_body.display_string())),
+          location: None
         }
       ])
     }
-    OptionalBody::Present(b, _, _) => {
-      let expected_form = serde_urlencoded::from_bytes::<Vec<(String, String)>>(b)
+    OptionalBody::Present(..) | OptionalBody::File(..) => {
+      let expected_bytes = expected_body.value().unwrap_or_default();
+      let expected_form = serde_urlencoded::from_bytes::<Vec<(String, String)>>(expected_bytes.as_ref())
         .map_err(|err| {
           Mismatch::BodyMismatch {
             path: "$".into(),
             expected: expected_body.value(),
             actual: actual_body.value(),
-            mismatch: format!("Could not parse expected body: {}", err)
+            mismatch: format!("Could not parse expected body: {}", err),
+            location: None
           }
         });
       let actual_bytes = actual_body.value().unwrap_or_default();
@@ -46,7 +50,8 @@ pub(crate) fn match_form_urlencoded(
             path: "$".into(),
             expected: expected_body.value(),
             actual: actual_body.value(),
-            mismatch: format!("Could not parse actual body: {}", err)
+            mismatch: format!("Could not parse actual body: {}", err),
+            location: None
           }
         });
       match (expected_form, actual_form) {
@@ -63,21 +68,27 @@ pub(crate) fn match_form_urlencoded(
             .iter()
             .map(|(k, v)| (k.clone(), v.iter().map(|(_, v)| Some(v.clone())).collect_vec()))
             .collect();
+          // Form fields are matched the same way as query parameters, with the mismatches ordered
+          // by field name so they are reported consistently
           let result: Vec<_> = match_query_maps(expected_params, actual_params, context)
-            .values().flat_map(|m| m.iter().map(|mismatch| {
+            .iter()
+            .sorted_by(|(a, _), (b, _)| Ord::cmp(a, b))
+            .flat_map(|(_, m)| m.iter().map(|mismatch| {
             if let Mismatch::QueryMismatch { parameter, expected, actual, mismatch } = mismatch {
               Mismatch::BodyMismatch {
-                path: format!("$.{}", parameter),
+                path: DocPath::root().join(parameter.as_str()).to_string(),
                 expected: Some(expected.clone().into()),
                 actual: Some(actual.clone().into()),
-                mismatch: mismatch.replace("query parameter", "form post parameter")
+                mismatch: mismatch.replace("query parameter", "form post parameter"),
+                location: None
               }
             } else {
               Mismatch::BodyMismatch {
                 path: "$".to_string(),
                 expected: None,
                 actual: None,
-                mismatch: mismatch.description()
+                mismatch: mismatch.description(),
+                location: None
               }
             }
           })).collect();
@@ -201,6 +212,7 @@ mod tests {
       expected: Some("".into()),
       actual: Some("[\"b\"]".into()),
       mismatch: "".to_string(),
+      location: None
     });
     assert_eq!(mismatches[0].description(), "$.a -> Unexpected form post parameter 'a' received");
   }
@@ -222,7 +234,8 @@ mod tests {
       path: "$.a".to_string(),
       expected: Some("[\"b\"]".into()),
       actual: Some("".into()),
-      mismatch: "Expected form post parameter 'a' but was missing".to_string()
+      mismatch: "Expected form post parameter 'a' but was missing".to_string(),
+      location: None
     });
     assert_eq!(mismatches[0].description(), "$.a -> Expected form post parameter 'a' but was missing");
   }
@@ -245,6 +258,7 @@ mod tests {
       expected: Some("".into()),
       actual: Some("[\"d\"]".into()),
       mismatch: "".to_string(),
+      location: None
     });
     assert_eq!(mismatches[0].description(), "$.c -> Unexpected form post parameter 'c' received");
   }
@@ -267,6 +281,7 @@ mod tests {
       expected: Some("[\"d\"]".into()),
       actual: Some("".into()),
       mismatch: "".to_string(),
+      location: None
     });
     assert_eq!(mismatches[0].description(), "$.c -> Expected form post parameter 'c' but was missing");
   }
@@ -289,6 +304,7 @@ mod tests {
       expected: Some("".into()),
       actual: Some("d".into()),
       mismatch: "".to_string(),
+      location: None
     });
     assert_eq!(mismatches[0].description(), "$.c -> Expected form post parameter 'c' with value '' but was 'd'");
   }
@@ -311,13 +327,15 @@ mod tests {
       expected: Some("[\"d\", \"e\"]".into()),
       actual: Some("[\"d\"]".into()),
       mismatch: "".to_string(),
+      location: None
     });
-    assert_eq!(mismatches[0].description(), "$.c -> Expected form post parameter 'c' value 'e' but was missing");
+    assert_eq!(mismatches[0].description(), "$.c -> Expected form post parameter 'c' value 'e' at index 1 but was missing");
     assert_eq!(mismatches[1], Mismatch::BodyMismatch {
       path: "$.c".to_string(),
       expected: Some("[\"d\", \"e\"]".into()),
       actual: Some("[\"d\"]".into()),
       mismatch: "".to_string(),
+      location: None
     });
     assert_eq!(mismatches[1].description(), "$.c -> Expected form post parameter 'c' with 2 value(s) but received 1 value(s)");
   }
@@ -340,6 +358,7 @@ mod tests {
       expected: Some("b".into()),
       actual: Some("c".into()),
       mismatch: "".to_string(),
+      location: None
     });
     assert_eq!(mismatches[0].description(), "$.a -> Expected form post parameter 'a' with value 'b' but was 'c'");
   }
@@ -411,7 +430,51 @@ mod tests {
       expected: Some("1".into()),
       actual: Some("hgjhghgh".into()),
       mismatch: "".to_string(),
+      location: None
     });
-    assert_eq!(mismatches[0].description(), "$.a -> Expected 'hgjhghgh' to match '\\d+'");
+    assert_eq!(mismatches[0].description(), "$.a -> Mismatch with form post parameter 'a' at index 0: Expected 'hgjhghgh' to match '\\d+'");
+  }
+
+  #[test_log::test]
+  fn match_form_returns_mismatches_ordered_by_field_name() {
+    let expected = Request {
+      body: OptionalBody::Present("c=1&a=1&b=1".bytes().collect(), Some(FORM_URLENCODED.clone()), Some(ContentTypeHint::TEXT)),
+      .. Request::default()
+    };
+    let actual = Request {
+      body: OptionalBody::Present("b=2&c=2&a=2".bytes().collect(), Some(FORM_URLENCODED.clone()), Some(ContentTypeHint::TEXT)),
+      .. Request::default()
+    };
+    let mismatches = match_form_urlencoded(&expected, &actual, &CoreMatchingContext::default())
+      .unwrap_err();
+    expect!(mismatches.iter().map(|m| m.description()).collect::<Vec<_>>()).to(be_equal_to(vec![
+      "$.a -> Expected form post parameter 'a' with value '1' but was '2'".to_string(),
+      "$.b -> Expected form post parameter 'b' with value '1' but was '2'".to_string(),
+      "$.c -> Expected form post parameter 'c' with value '1' but was '2'".to_string()
+    ]));
+  }
+
+  #[test_log::test]
+  fn match_form_with_field_names_that_need_escaping() {
+    let expected = Request {
+      body: OptionalBody::Present("user%5Bname%5D=Fred&user%5Bage%5D=10".bytes().collect(), Some(FORM_URLENCODED.clone()), Some(ContentTypeHint::TEXT)),
+      .. Request::default()
+    };
+    let actual = Request {
+      body: OptionalBody::Present("user%5Bname%5D=Mary&user%5Bage%5D=ten".bytes().collect(), Some(FORM_URLENCODED.clone()), Some(ContentTypeHint::TEXT)),
+      .. Request::default()
+    };
+    let rules = matchingrules! {
+      "body" => { "$['user[name]']" => [ MatchingRule::Type ] }
+    };
+    let context = CoreMatchingContext::new(
+      DiffConfig::AllowUnexpectedKeys,
+      &rules.rules_for_category("body").unwrap_or_default(), &hashmap!{}
+    );
+    let mismatches = match_form_urlencoded(&expected, &actual, &context)
+      .unwrap_err();
+    expect!(mismatches.iter().map(|m| m.description()).collect::<Vec<_>>()).to(be_equal_to(vec![
+      "$['user[age]'] -> Expected form post parameter 'user[age]' with value '10' but was 'ten'".to_string()
+    ]));
   }
 }
@@ -0,0 +1,369 @@
+//! Support for comparing GraphQL requests. GraphQL queries are normalised before being compared,
+//! so that differences in formatting (whitespace, commas and comments) or in the order of the
+//! fields in a selection set do not cause a mismatch.
+//!
+//! Queries can either be sent as the raw query document (with an `application/graphql` content
+//! type), or wrapped in a JSON envelope of the form
+//! `{"query": "...", "variables": {...}, "operationName": "..."}`. For the envelope, the
+//! variables are matched structurally with the JSON matching functions. JSON bodies are only
+//! treated as GraphQL envelopes if they have a GraphQL content type, or are being matched with the
+//! GraphQL body matcher.
+
+use anyhow::anyhow;
+use pact_models::content_types::ContentType;
+use pact_models::http_parts::HttpPart;
+use pact_models::path_exp::DocPath;
+use serde_json::Value;
+use tracing::debug;
+
+use crate::{MatchingContext, Mismatch};
+use crate::json::compare_json;
+
+/// Keys that are allowed in a GraphQL request envelope
+const ENVELOPE_KEYS: [&str; 4] = ["query", "variables", "operationName", "extensions"];
+
+/// Normalises the GraphQL query document. Insignificant characters are removed, and the fields
+/// of every selection set are sorted. Returns an error if the document could not be parsed.
+pub fn normalise_query(query: &str) -> anyhow::Result<String> {
+  let tokens = tokenise(query)?;
+  let mut parser = Parser { tokens, position: 0 };
+  let mut definitions = vec![];
+  while parser.peek().is_some() {
+    let mut header = vec![];
+    while parser.peek().ok_or_else(|| anyhow!("Expected a selection set"))? != "{" {
+      if parser.peek() == Some("(") {
+        header.extend(parser.group("(", ")")?);
+      } else {
+        header.push(parser.next()?);
+      }
+    }
+    let selection_set = parser.selection_set()?;
+    if header.is_empty() {
+      definitions.push(selection_set);
+    } else {
+      header.push(selection_set);
+      definitions.push(join_tokens(&header));
+    }
+  }
+  if definitions.is_empty() {
+    Err(anyhow!("GraphQL document is empty"))
+  } else {
+    Ok(definitions.join(" "))
+  }
+}
+
+/// If the content type is a GraphQL one (`application/graphql` or a type with a `graphql` suffix)
+pub fn is_graphql_content_type(content_type: &ContentType) -> bool {
+  content_type.main_type == "application" && (content_type.sub_type == "graphql" ||
+    content_type.suffix.as_deref() == Some("graphql"))
+}
+
+/// If the JSON value is a GraphQL request envelope (an object with a `query` attribute that
+/// contains a GraphQL document, and only the other attributes allowed in a GraphQL request).
+pub fn is_graphql_envelope(json: &Value) -> bool {
+  match json {
+    Value::Object(map) => map.keys().all(|key| ENVELOPE_KEYS.contains(&key.as_str())) &&
+      match map.get("query") {
+        Some(Value::String(query)) => query.contains('{') && normalise_query(query).is_ok(),
+        _ => false
+      },
+    _ => false
+  }
+}
+
+/// Replaces the query in the GraphQL request envelope with the normalised form
+pub fn normalise_envelope(json: &mut Value) {
+  if let Some(Value::String(query)) = json.get_mut("query") {
+    if let Ok(normalised) = normalise_query(query) {
+      *query = normalised;
+    }
+  }
+}
+
+/// Matches the expected GraphQL body to the actual, and returns any mismatches. The body can
+/// either be the raw GraphQL document, or a JSON request envelope.
+pub fn match_graphql(
+  expected: &(dyn HttpPart + Send + Sync),
+  actual: &(dyn HttpPart + Send + Sync),
+  context: &(dyn MatchingContext + Send + Sync)
+) -> Result<(), Vec<Mismatch>> {
+  let expected_body = expected.body().value().unwrap_or_default();
+  let actual_body = actual.body().value().unwrap_or_default();
+  let expected_json = serde_json::from_slice::<Value>(&expected_body).ok()
+    .filter(is_graphql_envelope);
+  if expected_json.is_some() {
+    debug!("match_graphql: expected body is a JSON request envelope");
+    return crate::json::match_graphql_envelope(expected, actual, context);
+  }
+
+  let expected_query = String::from_utf8_lossy(&expected_body).to_string();
+  let actual_query = String::from_utf8_lossy(&actual_body).to_string();
+  let expected_query = normalise_query(&expected_query).unwrap_or(expected_query);
+  let actual_query = normalise_query(&actual_query).unwrap_or(actual_query);
+  debug!("match_graphql: expected = '{}', actual = '{}'", expected_query, actual_query);
+  compare_json(&DocPath::root(), &Value::String(expected_query), &Value::String(actual_query), context)
+    .map_err(|mismatches| mismatches.iter().map(|mismatch| mismatch.to_body_mismatch()).collect())
+}
+
+fn tokenise(query: &str) -> anyhow::Result<Vec<String>> {
+  let chars = query.chars().collect::<Vec<_>>();
+  let mut tokens = vec![];
+  let mut index = 0;
+  while index < chars.len() {
+    let ch = chars[index];
+    if ch.is_whitespace() || ch == ',' || ch == '\u{feff}' {
+      index += 1;
+    } else if ch == '#' {
+      while index < chars.len() && chars[index] != '\n' && chars[index] != '\r' {
+        index += 1;
+      }
+    } else if ch == '"' {
+      let start = index;
+      if chars[index..].starts_with(&['"', '"', '"']) {
+        index += 3;
+        while index < chars.len() && !chars[index..].starts_with(&['"', '"', '"']) {
+          index += if chars[index] == '\\' { 2 } else { 1 };
+        }
+        index += 3;
+      } else {
+        index += 1;
+        while index < chars.len() && chars[index] != '"' {
+          index += if chars[index] == '\\' { 2 } else { 1 };
+        }
+        index += 1;
+      }
+      if index > chars.len() {
+        return Err(anyhow!("Unterminated string in GraphQL document"));
+      }
+      tokens.push(chars[start..index].iter().collect());
+    } else if chars[index..].starts_with(&['.', '.', '.']) {
+      tokens.push("...".to_string());
+      index += 3;
+    } else if "!$&()|:=@[]{}".contains(ch) {
+      tokens.push(ch.to_string());
+      index += 1;
+    } else if ch.is_alphanumeric() || ch == '_' || ch == '-' {
+      let start = index;
+      let number = ch.is_ascii_digit() || ch == '-';
+      index += 1;
+      while index < chars.len() && (chars[index].is_alphanumeric() || chars[index] == '_' ||
+        (number && ".+-".contains(chars[index]))) {
+        index += 1;
+      }
+      tokens.push(chars[start..index].iter().collect());
+    } else {
+      return Err(anyhow!("Unexpected character '{}' in GraphQL document", ch));
+    }
+  }
+  Ok(tokens)
+}
+
+/// Joins the tokens back into a string, only adding spaces where they are needed
+fn join_tokens(tokens: &[String]) -> String {
+  let mut result = String::new();
+  let mut previous: Option<&str> = None;
+  for token in tokens {
+    let space = match previous {
+      Some(p) => !["(", "[", "$", "@"].contains(&p) &&
+        ![")", "]", ":", "!", "("].contains(&token.as_str()),
+      None => false
+    };
+    if space {
+      result.push(' ');
+    }
+    result.push_str(token);
+    previous = Some(token.as_str());
+  }
+  result
+}
+
+struct Parser {
+  tokens: Vec<String>,
+  position: usize
+}
+
+impl Parser {
+  fn peek(&self) -> Option<&str> {
+    self.tokens.get(self.position).map(|token| token.as_str())
+  }
+
+  fn next(&mut self) -> anyhow::Result<String> {
+    let token = self.tokens.get(self.position).cloned()
+      .ok_or_else(|| anyhow!("Unexpected end of GraphQL document"))?;
+    self.position += 1;
+    Ok(token)
+  }
+
+  fn expect(&mut self, expected: &str) -> anyhow::Result<String> {
+    let token = self.next()?;
+    if token == expected {
+      Ok(token)
+    } else {
+      Err(anyhow!("Expected '{}' but found '{}' in GraphQL document", expected, token))
+    }
+  }
+
+  /// Consumes a balanced group of tokens (i.e. arguments in brackets)
+  fn group(&mut self, open: &str, close: &str) -> anyhow::Result<Vec<String>> {
+    let mut tokens = vec![self.expect(open)?];
+    let mut depth = 1;
+    while depth > 0 {
+      let token = self.next()?;
+      if token == open {
+        depth += 1;
+      } else if token == close {
+        depth -= 1;
+      }
+      tokens.push(token);
+    }
+    Ok(tokens)
+  }
+
+  /// Parses a selection set, and returns it with the selections sorted
+  fn selection_set(&mut self) -> anyhow::Result<String> {
+    self.expect("{")?;
+    let mut selections = vec![];
+    while self.peek() != Some("}") {
+      selections.push(self.selection()?);
+    }
+    self.expect("}")?;
+    selections.sort();
+    Ok(format!("{{ {} }}", selections.join(" ")))
+  }
+
+  fn selection(&mut self) -> anyhow::Result<String> {
+    let mut parts = vec![];
+    if self.peek() == Some("...") {
+      parts.push(self.next()?);
+      if self.peek() == Some("on") {
+        parts.push(self.next()?);
+        parts.push(self.next()?);
+      } else if !matches!(self.peek(), Some("{") | Some("@")) {
+        parts.push(self.next()?);
+      }
+    } else {
+      parts.push(self.next()?);
+      if self.peek() == Some(":") {
+        parts.push(self.next()?);
+        parts.push(self.next()?);
+      }
+    }
+    if self.peek() == Some("(") {
+      parts.extend(self.group("(", ")")?);
+    }
+    while self.peek() == Some("@") {
+      parts.push(self.next()?);
+      parts.push(self.next()?);
+      if self.peek() == Some("(") {
+        parts.extend(self.group("(", ")")?);
+      }
+    }
+    if self.peek() == Some("{") {
+      parts.push(self.selection_set()?);
+    }
+    Ok(join_tokens(&parts))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use bytes::Bytes;
+  use expectest::prelude::*;
+  use maplit::hashmap;
+  use pact_models::bodies::OptionalBody;
+  use pact_models::matchingrules::MatchingRule;
+  use pact_models::matchingrules_list;
+  use pact_models::request::Request;
+  use serde_json::json;
+
+  use crate::{CoreMatchingContext, DiffConfig};
+
+  use super::*;
+
+  fn request(body: &str, content_type: &str) -> Request {
+    Request {
+      body: OptionalBody::Present(Bytes::from(body.to_string()), Some(content_type.into()), None),
+      .. Request::default()
+    }
+  }
+
+  #[test]
+  fn normalise_query_test() {
+    expect!(normalise_query("{ user { name id } }").unwrap()).to(be_equal_to("{ user { id name } }"));
+    expect!(normalise_query(r#"
+      # Fetch the user
+      query GetUser($id: ID!, $size: Int = 10) {
+        user(id: $id) {
+          name,
+          avatar(size: $size)
+          ... on Admin { permissions }
+          id
+        }
+      }
+    "#).unwrap()).to(be_equal_to(
+      "query GetUser($id: ID! $size: Int = 10) { user(id: $id) { ... on Admin { permissions } avatar(size: $size) id name } }"));
+    expect!(normalise_query("query { a(s: \"x,  y\") @include(if: true) }").unwrap())
+      .to(be_equal_to("query { a(s: \"x,  y\") @include(if: true) }"));
+    expect!(normalise_query("{ user { name }").is_err()).to(be_true());
+    expect!(normalise_query("").is_err()).to(be_true());
+  }
+
+  #[test]
+  fn is_graphql_envelope_test() {
+    expect!(is_graphql_envelope(&json!({"query": "{ user { id } }"}))).to(be_true());
+    expect!(is_graphql_envelope(&json!({"query": "{ user { id } }", "variables": {"id": 1}, "operationName": null}))).to(be_true());
+    expect!(is_graphql_envelope(&json!({"query": "books"}))).to(be_false());
+    expect!(is_graphql_envelope(&json!({"query": "{ user { id } }", "page": 1}))).to(be_false());
+    expect!(is_graphql_envelope(&json!(["{ user { id } }"]))).to(be_false());
+  }
+
+  #[test]
+  fn match_graphql_with_raw_query() {
+    let expected = request("query {\n  user(id: 1) {\n    id\n    name\n  }\n}", "application/graphql");
+    let actual = request("query { user(id: 1) { name, id } }", "application/graphql");
+    expect!(match_graphql(&expected, &actual, &CoreMatchingContext::default())).to(be_ok());
+
+    let actual = request("query { user(id: 2) { name, id } }", "application/graphql");
+    expect!(match_graphql(&expected, &actual, &CoreMatchingContext::default())).to(be_err());
+  }
+
+  #[test]
+  fn is_graphql_content_type_test() {
+    expect!(is_graphql_content_type(&ContentType::parse("application/graphql").unwrap())).to(be_true());
+    expect!(is_graphql_content_type(&ContentType::parse("application/graphql+json").unwrap())).to(be_true());
+    expect!(is_graphql_content_type(&ContentType::parse("application/vnd.api+graphql").unwrap())).to(be_true());
+    expect!(is_graphql_content_type(&ContentType::parse("application/json").unwrap())).to(be_false());
+    expect!(is_graphql_content_type(&ContentType::parse("text/plain").unwrap())).to(be_false());
+  }
+
+  #[test]
+  fn match_graphql_with_envelope() {
+    let expected = request(r#"{"query": "query GetUser($id: ID!) {\n  user(id: $id) {\n    id\n    name\n  }\n}", "variables": {"id": "100"}}"#, "application/json");
+    let actual = request(r#"{"variables": {"id": "200"}, "query": "query GetUser($id: ID!) { user(id: $id) { name id } }"}"#, "application/json");
+    let rules = matchingrules_list! {
+      "body";
+      "$.variables.id" => [ MatchingRule::Regex("\\d+".to_string()) ]
+    };
+    let context = CoreMatchingContext::new(DiffConfig::AllowUnexpectedKeys, &rules, &hashmap!{});
+    expect!(match_graphql(&expected, &actual, &context)).to(be_ok());
+
+    let actual = request(r#"{"variables": {"id": "abc"}, "query": "query GetUser($id: ID!) { user(id: $id) { name } }"}"#, "application/json");
+    let mismatches = match_graphql(&expected, &actual, &context).unwrap_err();
+    expect!(mismatches.iter().map(|m| m.description()).collect::<Vec<_>>()).to(be_equal_to(vec![
+      "$.query -> Expected 'query GetUser($id: ID!) { user(id: $id) { name } }' (String) to be equal to 'query GetUser($id: ID!) { user(id: $id) { id name } }' (String)".to_string(),
+      "$.variables.id -> Expected 'abc' to match '\\d+'".to_string()
+    ]));
+  }
+
+  #[test]
+  fn match_json_only_normalises_envelopes_with_a_graphql_content_type() {
+    let expected = request(r#"{"query": "{ user { id name } }"}"#, "application/json");
+    let actual = request(r#"{"query": "{ user { name id } }"}"#, "application/json");
+    let context = CoreMatchingContext::default();
+    expect!(crate::json::match_json(&expected, &actual, &context)).to(be_err());
+
+    let expected = request(r#"{"query": "{ user { id name } }"}"#, "application/graphql+json");
+    let actual = request(r#"{"query": "{ user { name id } }"}"#, "application/graphql+json");
+    expect!(crate::json::match_json(&expected, &actual, &context)).to(be_ok());
+  }
+}
@@ -5,7 +5,7 @@ use std::iter::FromIterator;
 
 use itertools::Itertools;
 use maplit::hashmap;
-use pact_models::headers::PARAMETERISED_HEADERS;
+use pact_models::headers::{find_header, PARAMETERISED_HEADERS};
 use pact_models::matchingrules::MatchingRule;
 use pact_models::path_exp::DocPath;
 use tracing::{instrument, debug};
@@ -18,24 +18,36 @@ fn strip_whitespace<'a, T: FromIterator<&'a str>>(val: &'a str, split_by: &'a st
   val.split(split_by).map(|v| v.trim()).filter(|v| !v.is_empty()).collect()
 }
 
+// Parameter names are case-insensitive, so are stored in lowercase. Any quotes around the values
+// are removed.
 fn parse_charset_parameters(parameters: &[&str]) -> HashMap<String, String> {
   parameters.iter().map(|v| v.split_once('=')
     .map(|(k, v)| (k.trim(), v.trim())))
     .fold(HashMap::new(), |mut map, name_value| {
       if let Some((name, value)) = name_value {
-        map.insert(name.to_string(), value.to_string());
+        let value = value.strip_prefix('"')
+          .and_then(|v| v.strip_suffix('"'))
+          .unwrap_or(value);
+        map.insert(name.to_ascii_lowercase(), value.to_string());
       }
       map
     })
 }
 
+/// Matches a header (or metadata) value that has parameters, like `Content-Type`. Media types
+/// (`type/subtype`) are compared case-insensitively, and only the parameters in the expected value
+/// need to be present in the actual value. Parameter values are compared case-insensitively,
+/// unless there is a matching rule defined for the parameter (i.e. `$['content-type'].charset`).
+#[allow(clippy::too_many_arguments)]
 pub(crate) fn match_parameter_header(
   expected: &str,
   actual: &str,
   header: &str,
   value_type: &str,
   index: usize,
-  single_value: bool
+  single_value: bool,
+  path: &DocPath,
+  context: &dyn MatchingContext
 ) -> Result<(), Vec<String>> {
   let expected_values: Vec<&str> = strip_whitespace(expected, ";");
   let actual_values: Vec<&str> = strip_whitespace(actual, ";");
@@ -49,16 +61,29 @@ pub(crate) fn match_parameter_header(
   };
 
   let mut mismatches = vec![];
-  if expected_parameters.0 == actual_parameters.0 {
+  let values_match = if expected_parameters.0.contains('/') {
+    expected_parameters.0.eq_ignore_ascii_case(actual_parameters.0)
+  } else {
+    expected_parameters.0 == actual_parameters.0
+  };
+  if values_match {
     let expected_parameter_map = parse_charset_parameters(expected_parameters.1);
     let actual_parameter_map = parse_charset_parameters(actual_parameters.1);
-    for (k, v) in expected_parameter_map {
-      if actual_parameter_map.contains_key(&k) {
-        if v.to_ascii_lowercase() != actual_parameter_map.get(&k).unwrap().to_ascii_lowercase() {
+    for (k, v) in expected_parameter_map.iter().sorted() {
+      let parameter_path = path.join(k.as_str());
+      match actual_parameter_map.get(k) {
+        Some(actual_value) => if context.matcher_is_defined(&parameter_path) {
+          let result = matchers::match_values(&parameter_path,
+            &context.select_best_matcher(&parameter_path), v.as_str(), actual_value.as_str());
+          if let Err(messages) = result {
+            mismatches.extend(messages.iter().map(|message| {
+              format!("Expected {} '{}' parameter '{}' to match: {}", value_type, header, k, message)
+            }));
+          }
+        } else if !v.eq_ignore_ascii_case(actual_value) {
           mismatches.push(header_mismatch.clone());
         }
-      } else {
-        mismatches.push(header_mismatch.clone());
+        None => mismatches.push(header_mismatch.clone())
       }
     }
   } else {
@@ -80,6 +105,19 @@ pub(crate) fn match_header_value(
   actual: &str,
   context: &dyn MatchingContext,
   single_value: bool
+) -> Result<(), Vec<CommonMismatch>> {
+  match_value("header", key, index, expected, actual, context, single_value)
+}
+
+// Matches a header or trailer value. The value type is used in the mismatch descriptions.
+fn match_value(
+  value_type: &str,
+  key: &str,
+  index: usize,
+  expected: &str,
+  actual: &str,
+  context: &dyn MatchingContext,
+  single_value: bool
 ) -> Result<(), Vec<CommonMismatch>> {
   let path = DocPath::root().join(key.to_lowercase());
   let indexed_path = path.join(index.to_string());
@@ -87,21 +125,21 @@ pub(crate) fn match_header_value(
   let actual = actual.trim();
 
   let matcher_result = if context.matcher_is_defined(&path) {
-    let result = matchers::match_values(&path, &context.select_best_matcher(&path), expected, actual);
+    let result = matchers::match_values_with_context(&path, &context.select_best_matcher(&path), expected, actual, context);
     if single_value {
       result
     } else {
       result.map_err(|err| err.iter().map(|e| format!("{} for value at index {}", e, index)).collect())
     }
   } else if context.matcher_is_defined(&indexed_path) {
-    let result = matchers::match_values(&indexed_path, &context.select_best_matcher(&indexed_path), expected, actual);
+    let result = matchers::match_values_with_context(&indexed_path, &context.select_best_matcher(&indexed_path), expected, actual, context);
     if single_value {
       result
     } else {
       result.map_err(|err| err.iter().map(|e| format!("{} for value at index {}", e, index)).collect())
     }
   } else if PARAMETERISED_HEADERS.contains(&key.to_lowercase().as_str()) {
-    match_parameter_header(expected, actual, key, "header", index, single_value)
+    match_parameter_header(expected, actual, key, value_type, index, single_value, &path, context)
   } else {
     Matches::matches_with(&expected.to_string(), &actual.to_string(), &MatchingRule::Equality, false)
       .map_err(|err| {
@@ -119,38 +157,47 @@ pub(crate) fn match_header_value(
         path: key.to_string(),
         expected: expected.to_string(),
         actual: actual.to_string(),
-        description: format!("Mismatch with header '{}': {}", key, message)
+        description: format!("Mismatch with {} '{}': {}", value_type, key, message),
+        location: None
       }
     }).collect()
   })
 }
 
 fn find_entry<T>(map: &HashMap<String, T>, key: &str) -> Option<(String, T)> where T: Clone {
-  match map.keys().find(|k| k.to_lowercase() == key.to_lowercase() ) {
-    Some(k) => map.get(k).map(|v| (key.to_string(), v.clone()) ),
-    None => None
+  find_header(map, key).map(|(_, v)| (key.to_string(), v.clone()))
+}
+
+fn missing_value(value_type: &str, key: &str, value: &[String]) -> CommonMismatch {
+  CommonMismatch {
+    path: key.to_string(),
+    expected: format!("{:?}", value.join(", ")),
+    actual: "".to_string(),
+    description: format!("Expected a {} '{}' but was missing", value_type, key),
+    location: None
   }
 }
 
 fn match_header_maps(
+  value_type: &str,
   expected: HashMap<String, Vec<String>>,
   actual: HashMap<String, Vec<String>>,
   context: &dyn MatchingContext
-) -> HashMap<String, Vec<Mismatch>> {
+) -> HashMap<String, Vec<CommonMismatch>> {
   let mut result = hashmap!{};
   for (key, value) in &expected {
     match find_entry(&actual, key) {
       Some((_, actual_values)) => if value.is_empty() && !actual_values.is_empty() {
-        result.insert(key.clone(), vec![Mismatch::HeaderMismatch { key: key.clone(),
+        result.insert(key.clone(), vec![CommonMismatch { path: key.clone(),
           expected: "".to_string(),
-          actual: format!("{}", actual_values.join(", ")),
-          mismatch: format!("Expected an empty header '{}' but actual value was '{}'", key, actual_values.join(", ")) }]);
+          actual: actual_values.join(", "),
+          description: format!("Expected an empty {} '{}' but actual value was '{}'", value_type, key, actual_values.join(", ")), location: None }]);
       } else {
         let mut mismatches = vec![];
 
         // Special case when the headers only have 1 value to improve messaging
         if value.len() == 1 && actual_values.len() == 1 {
-          let comparison_result = match_header_value(key, 0, value.first().unwrap(),
+          let comparison_result = match_value(value_type, key, 0, value.first().unwrap(),
             actual_values.first().unwrap(), context, true)
             .err()
             .unwrap_or_default();
@@ -160,7 +207,7 @@ fn match_header_maps(
           if context.matcher_is_defined(&path) {
             debug!("match_header_maps: Matcher is defined for path {}", path);
             let values_result = compare_lists_with_matchingrules(&path, &context.select_best_matcher(&path), value, &actual_values, context.clone_with(context.matchers()).as_ref(), &mut |_, expected, actual, context| {
-              match_header_value(key, 0, expected, actual, context, false)
+              match_value(value_type, key, 0, expected, actual, context, false)
             });
             mismatches.extend(values_result.err().unwrap_or_default());
           } else {
@@ -169,7 +216,7 @@ fn match_header_maps(
               .pad_using(actual_values.len(), |_| &empty)
               .enumerate() {
               if let Some(actual_value) = actual_values.get(index) {
-                let comparison_result = match_header_value(key, index, val,
+                let comparison_result = match_value(value_type, key, index, val,
                   actual_value, context, false)
                   .err()
                   .unwrap_or_default();
@@ -179,46 +226,68 @@ fn match_header_maps(
                   path: key.clone(),
                   expected: val.clone(),
                   actual: "".to_string(),
-                  description: format!("Mismatch with header '{}': Expected value '{}' at index {} but was missing (actual has {} value(s))",
-                                    key, val, index, actual_values.len())
+                  description: format!("Mismatch with {} '{}': Expected value '{}' at index {} but was missing (actual has {} value(s))",
+                                    value_type, key, val, index, actual_values.len()),
+                  location: None
                 });
               }
             }
           }
         }
 
-        result.insert(key.clone(), mismatches.iter().map(|mismatch| mismatch.to_header_mismatch()).collect());
+        result.insert(key.clone(), mismatches);
       },
       None => {
-        result.insert(key.clone(), vec![Mismatch::HeaderMismatch { key: key.clone(),
-          expected: format!("{:?}", value.join(", ")),
-          actual: "".to_string(),
-          mismatch: format!("Expected a header '{}' but was missing", key) }]);
+        result.insert(key.clone(), vec![missing_value(value_type, key, value)]);
       }
     }
   }
   result
 }
 
-/// Matches the actual headers to the expected ones.
-pub fn match_headers(
+fn match_header_like_values(
+  value_type: &str,
   expected: Option<HashMap<String, Vec<String>>>,
   actual: Option<HashMap<String, Vec<String>>>,
-  context: &(dyn MatchingContext + Send + Sync)
-) -> HashMap<String, Vec<Mismatch>> {
+  context: &dyn MatchingContext
+) -> HashMap<String, Vec<CommonMismatch>> {
   match (actual, expected) {
-    (Some(aqm), Some(eqm)) => match_header_maps(eqm, aqm, context),
+    (Some(aqm), Some(eqm)) => match_header_maps(value_type, eqm, aqm, context),
     (Some(_), None) => hashmap!{},
     (None, Some(eqm)) => eqm.iter().map(|(key, value)| {
-      (key.clone(), vec![Mismatch::HeaderMismatch { key: key.clone(),
-        expected: format!("{:?}", value.join(", ")),
-        actual: "".to_string(),
-        mismatch: format!("Expected a header '{}' but was missing", key) }])
+      (key.clone(), vec![missing_value(value_type, key, value)])
     }).collect(),
     (None, None) => hashmap!{}
   }
 }
 
+/// Matches the actual headers to the expected ones.
+pub fn match_headers(
+  expected: Option<HashMap<String, Vec<String>>>,
+  actual: Option<HashMap<String, Vec<String>>>,
+  context: &(dyn MatchingContext + Send + Sync)
+) -> HashMap<String, Vec<Mismatch>> {
+  match_header_like_values("header", expected, actual, context).iter()
+    .map(|(key, mismatches)| {
+      (key.clone(), mismatches.iter().map(|mismatch| mismatch.to_header_mismatch()).collect())
+    })
+    .collect()
+}
+
+/// Matches the actual HTTP trailers to the expected ones. Trailers are matched in the same way as
+/// headers, using the matching rules from the `trailer` category.
+pub fn match_trailers(
+  expected: Option<HashMap<String, Vec<String>>>,
+  actual: Option<HashMap<String, Vec<String>>>,
+  context: &(dyn MatchingContext + Send + Sync)
+) -> HashMap<String, Vec<Mismatch>> {
+  match_header_like_values("trailer", expected, actual, context).iter()
+    .map(|(key, mismatches)| {
+      (key.clone(), mismatches.iter().map(|mismatch| mismatch.to_trailer_mismatch()).collect())
+    })
+    .collect()
+}
+
 #[cfg(test)]
 mod tests {
   use expectest::prelude::*;
@@ -229,7 +298,7 @@ mod tests {
   use pretty_assertions::assert_eq;
 
   use crate::{CoreMatchingContext, DiffConfig, HeaderMatchingContext, Mismatch, CommonMismatch};
-  use crate::headers::{match_header_value, match_headers, parse_charset_parameters};
+  use crate::headers::{match_header_value, match_headers, match_trailers, parse_charset_parameters};
 
   #[test]
   fn matching_headers_be_true_when_headers_are_equal() {
@@ -249,7 +318,8 @@ mod tests {
       path: "HEADER".to_string(),
       expected: "HEADER".to_string(),
       actual: "HEADER2".to_string(),
-      description: "".to_string()
+      description: "".to_string(),
+      location: None
     });
   }
 
@@ -425,6 +495,7 @@ mod tests {
       expected: "HEADER".to_string(),
       actual: "HEADER".to_string(),
       description: String::default(),
+      location: None
     } ]));
   }
 
@@ -436,6 +507,67 @@ mod tests {
     expect!(mismatches).to(be_ok());
   }
 
+  #[test]
+  fn content_type_header_does_match_when_media_types_are_different_case() {
+    let mismatches = match_header_value("CONTENT-TYPE", 0, "application/json;charset=UTF-8",
+      "Application/JSON; Charset=\"utf-8\"", &CoreMatchingContext::default(), true
+    );
+    expect!(mismatches).to(be_ok());
+  }
+
+  #[test]
+  fn content_type_header_parameters_can_have_matching_rules() {
+    let context = HeaderMatchingContext::new(&CoreMatchingContext::new(
+      DiffConfig::AllowUnexpectedKeys,
+      &matchingrules! {
+        "header" => {
+          "Content-Type.boundary" => [ MatchingRule::Regex("^[\\w-]+$".to_string()) ]
+        }
+      }.rules_for_category("header").unwrap(),
+      &hashmap!{}
+    ));
+    let mismatches = match_header_value("Content-Type", 0, "multipart/form-data; boundary=abc-123",
+      "multipart/form-data; boundary=xyz-789", &context, true
+    );
+    expect!(mismatches).to(be_ok());
+
+    let mismatches = match_header_value("Content-Type", 0, "multipart/form-data; boundary=abc-123",
+      "multipart/form-data; boundary=\"xyz 789\"", &context, true
+    );
+    expect!(mismatches.unwrap_err().iter().map(|m| m.description.clone()).collect::<Vec<_>>()).to(be_equal_to(vec![
+      "Mismatch with header 'Content-Type': Expected header 'Content-Type' parameter 'boundary' to match: Expected 'xyz 789' to match '^[\\w-]+$'".to_string()
+    ]));
+
+    let mismatches = match_header_value("Content-Type", 0, "multipart/form-data; boundary=abc-123",
+      "multipart/mixed; boundary=xyz-789", &context, true
+    );
+    expect!(mismatches).to(be_err());
+  }
+
+  #[test]
+  fn header_values_can_be_compared_ignoring_case() {
+    let context = HeaderMatchingContext::new(&CoreMatchingContext::new(
+      DiffConfig::AllowUnexpectedKeys,
+      &matchingrules! {
+        "header" => { "Authorization" => [ MatchingRule::EqualsIgnoreCase ] }
+      }.rules_for_category("header").unwrap(),
+      &hashmap!{}
+    ));
+    let mismatches = match_headers(
+      Some(hashmap! { "Authorization".to_string() => vec!["Bearer abc123".to_string()] }),
+      Some(hashmap! { "authorization".to_string() => vec!["bearer ABC123".to_string()] }),
+      &context
+    );
+    expect!(mismatches.values().flatten()).to(be_empty());
+
+    let mismatches = match_headers(
+      Some(hashmap! { "Authorization".to_string() => vec!["Bearer abc123".to_string()] }),
+      Some(hashmap! { "authorization".to_string() => vec!["bearer ABC123".to_string()] }),
+      &CoreMatchingContext::default()
+    );
+    expect!(mismatches.values().flatten()).to_not(be_empty());
+  }
+
   #[test]
   fn parse_charset_parameters_test() {
     expect!(parse_charset_parameters(&[])).to(be_equal_to(hashmap!{}));
@@ -447,6 +579,7 @@ mod tests {
       "a".to_string() => "b".to_string(),
       "c".to_string() => "d".to_string()
     }));
+    expect!(parse_charset_parameters(&["Charset=\"UTF-8\""])).to(be_equal_to(hashmap!{ "charset".to_string() => "UTF-8".to_string() }));
   }
 
   // Issue #238
@@ -846,4 +979,56 @@ mod tests {
       }
     ]));
   }
+
+  #[test]
+  fn match_trailers_applies_the_trailer_matching_rules() {
+    let context = HeaderMatchingContext::new(&CoreMatchingContext::new(
+      DiffConfig::AllowUnexpectedKeys,
+      &matchingrules! {
+        "trailer" => { "grpc-status" => [ MatchingRule::Regex("\\d+".to_string()) ] }
+      }.rules_for_category("trailer").unwrap_or_default(), &hashmap!{}
+    ));
+    let expected = hashmap! {
+      "grpc-status".to_string() => vec!["0".to_string()],
+      "grpc-message".to_string() => vec!["OK".to_string()]
+    };
+    let actual = hashmap! {
+      "Grpc-Status".to_string() => vec!["14".to_string()],
+      "Grpc-Message".to_string() => vec!["OK".to_string()]
+    };
+    let result = match_trailers(Some(expected), Some(actual), &context);
+    expect!(result.values().flatten()).to(be_empty());
+
+    let expected = hashmap! { "grpc-status".to_string() => vec!["0".to_string()] };
+    let actual = hashmap! { "grpc-status".to_string() => vec!["UNAVAILABLE".to_string()] };
+    let result = match_trailers(Some(expected), Some(actual), &context);
+    expect!(result.get("grpc-status").unwrap().to_vec()).to(be_equal_to(vec![
+      Mismatch::TrailerMismatch {
+        key: "grpc-status".to_string(),
+        expected: "0".to_string(),
+        actual: "UNAVAILABLE".to_string(),
+        mismatch: "Mismatch with trailer 'grpc-status': Expected 'UNAVAILABLE' to match '\\d+'".to_string()
+      }
+    ]));
+  }
+
+  #[test]
+  fn match_trailers_returns_a_mismatch_if_an_expected_trailer_is_not_received() {
+    let expected = Some(hashmap! { "grpc-status".to_string() => vec!["0".to_string()] });
+    let result = match_trailers(expected.clone(), None, &CoreMatchingContext::default());
+    expect!(result.values().flatten().map(|m| m.description()).collect::<Vec<_>>()).to(be_equal_to(vec![
+      "Expected a trailer 'grpc-status' but was missing".to_string()
+    ]));
+
+    let actual = Some(hashmap! { "grpc-message".to_string() => vec!["OK".to_string()] });
+    let result = match_trailers(expected, actual, &CoreMatchingContext::default());
+    expect!(result.get("grpc-status").unwrap().to_vec()).to(be_equal_to(vec![
+      Mismatch::TrailerMismatch {
+        key: "grpc-status".to_string(),
+        expected: "\"0\"".to_string(),
+        actual: "".to_string(),
+        mismatch: "Expected a trailer 'grpc-status' but was missing".to_string()
+      }
+    ]));
+  }
 }
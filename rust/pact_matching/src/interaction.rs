@@ -0,0 +1,460 @@
+//! Stable entry point for matching a complete interaction.
+//!
+//! The [`match_interaction`] function in this module matches any type of interaction (HTTP
+//! request/response, asynchronous message or synchronous message) and returns the result as an
+//! [`InteractionMatchResult`]. It is intended for tools that embed the matching engine (i.e. mock
+//! servers or test frameworks in other languages), and does not require knowing which of the
+//! functions in the crate root apply to which interaction type.
+//!
+//! ```
+//! # tokio::runtime::Runtime::new().unwrap().block_on(async {
+//! use pact_models::prelude::*;
+//! use pact_models::sync_interaction::RequestResponseInteraction;
+//! use pact_matching::interaction::{match_interaction, InteractionMatchingContext};
+//!
+//! let expected = RequestResponseInteraction {
+//!   request: Request { path: "/items".to_string(), .. Request::default() },
+//!   .. RequestResponseInteraction::default()
+//! };
+//! let actual = RequestResponseInteraction {
+//!   request: Request { path: "/users".to_string(), .. Request::default() },
+//!   .. RequestResponseInteraction::default()
+//! };
+//! let result = match_interaction(&expected, &actual, &InteractionMatchingContext::default()).await;
+//! assert!(!result.all_matched());
+//! # });
+//! ```
+//!
+//! To find the interaction that best matches a request from a set of interactions (for instance,
+//! in a stub server), use [`find_best_matching_interaction`]. The interactions are matched in
+//! parallel, and the results are ranked by the score of the match.
+
+use std::cmp::Reverse;
+use std::panic::RefUnwindSafe;
+use std::sync::Arc;
+
+use futures::future::join_all;
+use pact_models::interaction::Interaction;
+use pact_models::pact::Pact;
+use pact_models::v4::http_parts::HttpRequest;
+use pact_models::v4::pact::V4Pact;
+use tracing::{debug, warn};
+
+use crate::{
+  match_message_with_options,
+  match_request_with_options,
+  match_response_with_options,
+  match_sync_message_with_options,
+  MatchingOptions,
+  Mismatch,
+  RequestMatchResult
+};
+
+/// Context used when matching interactions
+#[derive(Debug)]
+pub struct InteractionMatchingContext {
+  /// Pact the expected interaction belongs to. This is used to look up any plugin configuration,
+  /// and is shared with the tasks that match interactions in parallel.
+  pact: Arc<Box<dyn Pact + Send + Sync + RefUnwindSafe>>,
+  /// Options used to configure the matching (i.e. custom matchers or type coercion)
+  options: MatchingOptions
+}
+
+impl InteractionMatchingContext {
+  /// Creates a new context for interactions from the given Pact
+  pub fn new(pact: &(dyn Pact + Send + Sync + RefUnwindSafe)) -> Self {
+    InteractionMatchingContext {
+      pact: Arc::new(pact.boxed()),
+      options: MatchingOptions::default()
+    }
+  }
+
+  /// Sets the options used to configure the matching
+  pub fn with_options(mut self, options: MatchingOptions) -> Self {
+    self.options = options;
+    self
+  }
+
+  /// Pact the expected interaction belongs to
+  pub fn pact(&self) -> &(dyn Pact + Send + Sync + RefUnwindSafe) {
+    self.pact.as_ref().as_ref()
+  }
+
+  /// Options used to configure the matching
+  pub fn options(&self) -> &MatchingOptions {
+    &self.options
+  }
+}
+
+impl Default for InteractionMatchingContext {
+  fn default() -> Self {
+    InteractionMatchingContext {
+      pact: Arc::new(V4Pact::default().boxed()),
+      options: MatchingOptions::default()
+    }
+  }
+}
+
+/// Result of matching an interaction
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub enum InteractionMatchResult {
+  /// Result of matching an HTTP request/response interaction
+  Http {
+    /// Result of matching the request
+    request: Box<RequestMatchResult>,
+    /// Mismatches from matching the response
+    response: Vec<Mismatch>
+  },
+  /// Mismatches from matching an asynchronous message
+  Message(Vec<Mismatch>),
+  /// Mismatches from matching a synchronous request/response message
+  SynchronousMessage(Vec<Mismatch>),
+  /// The actual interaction is not the same type as the expected one, so could not be matched
+  TypeMismatch {
+    /// Type of the expected interaction
+    expected: String,
+    /// Type of the actual interaction
+    actual: String
+  }
+}
+
+impl InteractionMatchResult {
+  /// Returns all the mismatches
+  pub fn mismatches(&self) -> Vec<Mismatch> {
+    match self {
+      InteractionMatchResult::Http { request, response } => {
+        let mut mismatches = request.mismatches();
+        mismatches.extend_from_slice(response);
+        mismatches
+      }
+      InteractionMatchResult::Message(mismatches) => mismatches.clone(),
+      InteractionMatchResult::SynchronousMessage(mismatches) => mismatches.clone(),
+      InteractionMatchResult::TypeMismatch { expected, actual } => vec![
+        Mismatch::BodyTypeMismatch {
+          expected: expected.clone(),
+          actual: actual.clone(),
+          mismatch: format!("Cannot compare a {} with a {}", expected, actual),
+          expected_body: None,
+          actual_body: None
+        }
+      ]
+    }
+  }
+
+  /// If all the things matched OK
+  pub fn all_matched(&self) -> bool {
+    match self {
+      InteractionMatchResult::Http { request, response } => request.all_matched() && response.is_empty(),
+      InteractionMatchResult::Message(mismatches) => mismatches.is_empty(),
+      InteractionMatchResult::SynchronousMessage(mismatches) => mismatches.is_empty(),
+      InteractionMatchResult::TypeMismatch { .. } => false
+    }
+  }
+}
+
+/// Matches the actual interaction against the expected one. HTTP interactions will have both the
+/// request and response matched, and messages will have their contents and metadata matched.
+/// If the actual interaction is a different type to the expected one, a
+/// [`InteractionMatchResult::TypeMismatch`] is returned.
+pub async fn match_interaction(
+  expected: &(dyn Interaction + Send + Sync + RefUnwindSafe),
+  actual: &(dyn Interaction + Send + Sync + RefUnwindSafe),
+  context: &InteractionMatchingContext
+) -> InteractionMatchResult {
+  let type_mismatch = || InteractionMatchResult::TypeMismatch {
+    expected: expected.type_of(),
+    actual: actual.type_of()
+  };
+
+  if expected.is_v4_sync_message() {
+    match (expected.as_v4_sync_message(), actual.as_v4_sync_message()) {
+      (Some(expected), Some(actual)) => InteractionMatchResult::SynchronousMessage(
+        match_sync_message_with_options(expected, actual, &context.pact, &context.options).await),
+      _ => type_mismatch()
+    }
+  } else if expected.is_message() {
+    if actual.is_message() {
+      InteractionMatchResult::Message(match_message_with_options(&expected.boxed(), &actual.boxed(),
+        &context.pact, &context.options).await)
+    } else {
+      type_mismatch()
+    }
+  } else {
+    match (expected.as_v4_http(), actual.as_v4_http()) {
+      (Some(expected_http), Some(actual_http)) => {
+        let interaction = expected.boxed();
+        InteractionMatchResult::Http {
+          request: Box::new(match_request_with_options(expected_http.request, actual_http.request,
+            &context.pact, &interaction, &context.options).await),
+          response: match_response_with_options(expected_http.response, actual_http.response,
+            &context.pact, &interaction, &context.options).await
+        }
+      }
+      _ => type_mismatch()
+    }
+  }
+}
+
+/// Result of matching a request against one interaction from a set of interactions
+#[derive(Debug)]
+pub struct InteractionRequestMatch {
+  /// Index of the interaction in the set of interactions that were matched against
+  pub index: usize,
+  /// The interaction that was matched against
+  pub interaction: Box<dyn Interaction + Send + Sync + RefUnwindSafe>,
+  /// Result of matching the request
+  pub result: RequestMatchResult
+}
+
+impl InteractionRequestMatch {
+  /// If the request fully matched the interaction
+  pub fn all_matched(&self) -> bool {
+    self.result.all_matched()
+  }
+
+  /// Score of the match. Higher scores are better matches.
+  pub fn score(&self) -> i8 {
+    self.result.score()
+  }
+}
+
+/// Matches the request against the requests of all the HTTP interactions, and returns the results
+/// ranked from the best match to the worst. Interactions that fully match are ranked first, then the
+/// results are ordered by their score, and then by the order of the interactions. Interactions that
+/// are not HTTP interactions are ignored.
+///
+/// When called from a Tokio runtime, the interactions are matched in parallel on the runtime's
+/// worker threads, otherwise they are matched concurrently on the current task.
+pub async fn match_request_against_interactions(
+  request: &HttpRequest,
+  interactions: &[Box<dyn Interaction + Send + Sync + RefUnwindSafe>],
+  context: &InteractionMatchingContext
+) -> Vec<InteractionRequestMatch> {
+  let candidates = interactions.iter()
+    .enumerate()
+    .filter_map(|(index, interaction)| interaction.as_v4_http()
+      .map(|http| (index, interaction.boxed(), http.request)))
+    .collect::<Vec<_>>();
+  debug!("Matching request against {} interaction(s)", candidates.len());
+
+  let mut results = if tokio::runtime::Handle::try_current().is_ok() {
+    let tasks = candidates.into_iter()
+      .map(|(index, interaction, expected)| {
+        let request = request.clone();
+        let pact = context.pact.clone();
+        let options = context.options.clone();
+        tokio::spawn(async move {
+          let result = match_request_with_options(expected, request, pact.as_ref(), &interaction, &options).await;
+          InteractionRequestMatch { index, interaction, result }
+        })
+      })
+      .collect::<Vec<_>>();
+    join_all(tasks).await.into_iter()
+      .filter_map(|result| result.map_err(|err| warn!("Failed to match interaction - {}", err)).ok())
+      .collect::<Vec<_>>()
+  } else {
+    join_all(candidates.into_iter()
+      .map(|(index, interaction, expected)| async move {
+        let result = match_request_with_options(expected, request.clone(), &context.pact, &interaction,
+          &context.options).await;
+        InteractionRequestMatch { index, interaction, result }
+      })).await
+  };
+
+  results.sort_by_key(|m| (Reverse(m.all_matched()), Reverse(m.score()), m.index));
+  results
+}
+
+/// Finds the HTTP interaction whose request best matches the given request. Only interactions
+/// that fully match the request are considered, and if more than one does, the one with the
+/// highest score is returned (or the first one, if they have the same score).
+pub async fn find_best_matching_interaction(
+  request: &HttpRequest,
+  interactions: &[Box<dyn Interaction + Send + Sync + RefUnwindSafe>],
+  context: &InteractionMatchingContext
+) -> Option<InteractionRequestMatch> {
+  match_request_against_interactions(request, interactions, context).await
+    .into_iter()
+    .next()
+    .filter(|m| m.all_matched())
+}
+
+#[cfg(test)]
+mod tests {
+  use bytes::Bytes;
+  use expectest::prelude::*;
+  use maplit::hashmap;
+  use pact_models::bodies::OptionalBody;
+  use pact_models::content_types::JSON;
+  use pact_models::matchingrules;
+  use pact_models::matchingrules::MatchingRule;
+  use pact_models::message::Message;
+  use pact_models::prelude::Consumer;
+  use pact_models::request::Request;
+  use pact_models::response::Response;
+  use pact_models::sync_interaction::RequestResponseInteraction;
+  use serde_json::json;
+
+  use super::*;
+
+  fn http_interaction(path: &str, status: u16) -> RequestResponseInteraction {
+    RequestResponseInteraction {
+      request: Request { path: path.to_string(), .. Request::default() },
+      response: Response { status, .. Response::default() },
+      .. RequestResponseInteraction::default()
+    }
+  }
+
+  fn message(contents: &str) -> Message {
+    Message {
+      contents: OptionalBody::from(contents),
+      .. Message::default()
+    }
+  }
+
+  #[tokio::test]
+  async fn match_interaction_with_http_interactions() {
+    let context = InteractionMatchingContext::default();
+    let expected = http_interaction("/items", 200);
+
+    let result = match_interaction(&expected, &http_interaction("/items", 200), &context).await;
+    expect!(result.all_matched()).to(be_true());
+    expect!(result.mismatches()).to(be_equal_to(vec![]));
+
+    let result = match_interaction(&expected, &http_interaction("/users", 404), &context).await;
+    expect!(result.all_matched()).to(be_false());
+    expect!(result.mismatches()).to(be_equal_to(vec![
+      Mismatch::PathMismatch {
+        expected: "/items".to_string(),
+        actual: "/users".to_string(),
+        mismatch: "Expected '/users' to be equal to '/items'".to_string()
+      },
+      Mismatch::StatusMismatch {
+        expected: 200,
+        actual: 404,
+        mismatch: "expected 200 but was 404".to_string()
+      }
+    ]));
+  }
+
+  #[tokio::test]
+  async fn match_interaction_with_messages() {
+    let context = InteractionMatchingContext::default();
+    let expected = message("{\"id\": 1}");
+
+    let result = match_interaction(&expected, &message("{\"id\": 1}"), &context).await;
+    expect!(result.all_matched()).to(be_true());
+
+    let result = match_interaction(&expected, &message("{\"id\": 2}"), &context).await;
+    expect!(result.all_matched()).to(be_false());
+    expect!(result.mismatches().len()).to(be_equal_to(1));
+  }
+
+  fn http_interactions() -> Vec<Box<dyn Interaction + Send + Sync + RefUnwindSafe>> {
+    let mut with_query = http_interaction("/items", 200);
+    with_query.request.query = Some(hashmap!{ "page".to_string() => vec![Some("2".to_string())] });
+    vec![
+      http_interaction("/users", 200).boxed(),
+      message("{}").boxed(),
+      with_query.boxed(),
+      http_interaction("/items", 200).boxed()
+    ]
+  }
+
+  #[tokio::test(flavor = "multi_thread")]
+  async fn match_request_against_interactions_ranks_the_results() {
+    let request = HttpRequest { path: "/items".to_string(), .. HttpRequest::default() };
+    let results = match_request_against_interactions(&request, &http_interactions(),
+      &InteractionMatchingContext::default()).await;
+    expect!(results.iter().map(|m| (m.index, m.all_matched())).collect::<Vec<_>>()).to(be_equal_to(vec![
+      (3, true),
+      (2, false),
+      (0, false)
+    ]));
+  }
+
+  #[test]
+  fn find_best_matching_interaction_test() {
+    let interactions = http_interactions();
+    let context = InteractionMatchingContext::default();
+
+    let request = HttpRequest { path: "/items".to_string(), .. HttpRequest::default() };
+    let result = futures::executor::block_on(find_best_matching_interaction(&request, &interactions, &context));
+    expect!(result.map(|m| m.index)).to(be_some().value(3));
+
+    let request = HttpRequest {
+      path: "/items".to_string(),
+      query: Some(hashmap!{ "page".to_string() => vec![Some("2".to_string())] }),
+      .. HttpRequest::default()
+    };
+    let result = futures::executor::block_on(find_best_matching_interaction(&request, &interactions, &context));
+    expect!(result.map(|m| m.index)).to(be_some().value(2));
+
+    let request = HttpRequest { path: "/other".to_string(), .. HttpRequest::default() };
+    let result = futures::executor::block_on(find_best_matching_interaction(&request, &interactions, &context));
+    expect!(result.is_none()).to(be_true());
+  }
+
+  #[tokio::test]
+  async fn match_interaction_with_different_interaction_types() {
+    let context = InteractionMatchingContext::default();
+    let result = match_interaction(&message("{}"), &http_interaction("/items", 200), &context).await;
+    expect!(result.clone()).to(be_equal_to(InteractionMatchResult::TypeMismatch {
+      expected: "V3 Asynchronous/Messages".to_string(),
+      actual: "V3 Synchronous/HTTP".to_string()
+    }));
+    expect!(result.all_matched()).to(be_false());
+  }
+
+  #[tokio::test]
+  async fn match_interaction_uses_the_matching_options() {
+    let response = |id: &str| Response {
+      body: OptionalBody::Present(Bytes::from(format!("{{\"id\":\"{}\"}}", id)), Some(JSON.clone()), None),
+      .. Response::default()
+    };
+    let expected = RequestResponseInteraction {
+      response: Response {
+        matching_rules: matchingrules! {
+          "body" => { "$.id" => [ MatchingRule::Custom("prefix".to_string(), hashmap!{ "prefix".to_string() => json!("01") }) ] }
+        },
+        .. response("01ARZ")
+      },
+      .. RequestResponseInteraction::default()
+    };
+    let actual = RequestResponseInteraction {
+      response: response("01BX5"),
+      .. RequestResponseInteraction::default()
+    };
+
+    let result = match_interaction(&expected, &actual, &InteractionMatchingContext::default()).await;
+    expect!(result.all_matched()).to(be_false());
+
+    let options = MatchingOptions::default()
+      .with_custom_matcher("prefix", |_, actual, config| {
+        let prefix = config.get("prefix").and_then(|p| p.as_str()).unwrap_or_default();
+        match actual.as_str() {
+          Some(s) if s.starts_with(prefix) => vec![],
+          _ => vec![format!("Expected {} to start with '{}'", actual, prefix)]
+        }
+      });
+    let context = InteractionMatchingContext::default().with_options(options);
+    let result = match_interaction(&expected, &actual, &context).await;
+    expect!(result.all_matched()).to(be_true());
+    let result = match_interaction(&expected, &RequestResponseInteraction {
+      response: response("7ZZZZ"),
+      .. RequestResponseInteraction::default()
+    }, &context).await;
+    expect!(result.all_matched()).to(be_false());
+  }
+
+  #[test]
+  fn interaction_matching_context_new_test() {
+    let pact = V4Pact {
+      consumer: Consumer { name: "test-consumer".to_string() },
+      .. V4Pact::default()
+    };
+    let context = InteractionMatchingContext::new(&pact);
+    expect!(context.pact().consumer().name).to(be_equal_to("test-consumer"));
+  }
+}
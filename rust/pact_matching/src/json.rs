@@ -1,5 +1,7 @@
 //! The `json` module provides functions to compare and display the differences between JSON bodies
 
+use std::collections::BTreeMap;
+use std::io;
 use std::str::FromStr;
 
 use ansi_term::Colour::*;
@@ -8,7 +10,9 @@ use difference::*;
 use lazy_static::lazy_static;
 use onig::Regex;
 use semver::Version;
-use serde_json::{json, Value};
+use serde::de::IgnoredAny;
+use serde::Serialize;
+use serde_json::Value;
 
 use pact_models::http_parts::HttpPart;
 use pact_models::json_utils::json_to_string;
@@ -17,13 +21,93 @@ use pact_models::path_exp::DocPath;
 #[cfg(feature = "datetime")] use pact_models::time_utils::validate_datetime;
 use tracing::debug;
 
-use crate::{DiffConfig, MatchingContext, Mismatch, CommonMismatch, merge_result};
+use crate::{CoreMatchingContext, DiffConfig, MatchingContext, Mismatch, CommonMismatch, MismatchLocation, merge_result};
 use crate::binary_utils::{convert_data, match_content_type};
+use crate::graphql::{is_graphql_content_type, is_graphql_envelope, normalise_envelope};
 use crate::matchers::*;
 use crate::matchingrules::{compare_lists_with_matchingrules, compare_maps_with_matchingrule};
+use crate::regex_engine::MatchingRegex;
+
+/// Environment variable that sets the maximum size (in bytes) of the JSON values recorded in
+/// mismatches. Larger values are truncated, so that mismatches with very large bodies do not
+/// keep complete copies of them. Defaults to 1 MiB.
+pub const MISMATCH_VALUE_LIMIT_ENV_VAR: &str = "PACT_MISMATCH_VALUE_LIMIT";
+const DEFAULT_MISMATCH_VALUE_LIMIT: usize = 1024 * 1024;
+
+/// Environment variable that sets the size (in bytes) above which byte-identical JSON bodies with
+/// no matching rules are not compared. They are only checked to be valid JSON, without building
+/// the parsed values to compare them. Defaults to 10 MiB.
+pub const JSON_SKIP_IDENTICAL_THRESHOLD_ENV_VAR: &str = "PACT_JSON_SKIP_IDENTICAL_THRESHOLD";
+const DEFAULT_JSON_SKIP_IDENTICAL_THRESHOLD: usize = 10 * 1024 * 1024;
 
 lazy_static! {
   static ref DEC_REGEX: Regex = Regex::new(r"\d+\.\d+").unwrap();
+  static ref MISMATCH_VALUE_LIMIT: usize = env_limit(MISMATCH_VALUE_LIMIT_ENV_VAR, DEFAULT_MISMATCH_VALUE_LIMIT);
+  static ref JSON_SKIP_IDENTICAL_THRESHOLD: usize = env_limit(JSON_SKIP_IDENTICAL_THRESHOLD_ENV_VAR, DEFAULT_JSON_SKIP_IDENTICAL_THRESHOLD);
+}
+
+fn env_limit(name: &str, default: usize) -> usize {
+  std::env::var(name).ok()
+    .and_then(|limit| limit.parse().ok())
+    .unwrap_or(default)
+}
+
+/// Writer that fails once more than the limit has been written to it
+struct LimitedWriter {
+  buffer: Vec<u8>,
+  limit: usize
+}
+
+impl io::Write for LimitedWriter {
+  fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+    let remaining = self.limit - self.buffer.len();
+    if buf.len() > remaining {
+      self.buffer.extend_from_slice(&buf[..remaining]);
+      Err(io::Error::new(io::ErrorKind::WriteZero, "mismatch value limit reached"))
+    } else {
+      self.buffer.extend_from_slice(buf);
+      Ok(buf.len())
+    }
+  }
+
+  fn flush(&mut self) -> io::Result<()> {
+    Ok(())
+  }
+}
+
+fn to_limited_string<T: Serialize + ?Sized>(value: &T, limit: usize) -> String {
+  let mut writer = LimitedWriter { buffer: Vec::new(), limit };
+  let truncated = serde_json::to_writer(&mut writer, value).is_err();
+  if truncated {
+    // The limit could have split a multi-byte character, so drop any partial character at the end
+    if let Err(err) = std::str::from_utf8(&writer.buffer) {
+      writer.buffer.truncate(err.valid_up_to());
+    }
+  }
+  let mut result = String::from_utf8_lossy(&writer.buffer).to_string();
+  if truncated {
+    result.push_str("...");
+  }
+  result
+}
+
+/// Serialises a JSON list or map for a mismatch, truncating it if it exceeds the mismatch value limit
+fn collection_for_mismatch<T: Serialize + ?Sized>(value: &T) -> String {
+  to_limited_string(value, *MISMATCH_VALUE_LIMIT)
+}
+
+fn json_for_mismatch(json: &Value) -> String {
+  match json {
+    Value::Object(_) | Value::Array(_) => collection_for_mismatch(json),
+    _ => json_to_string(json)
+  }
+}
+
+fn value_for_mismatch(json: &Value) -> String {
+  match json {
+    Value::Object(_) | Value::Array(_) => collection_for_mismatch(json),
+    _ => value_of(json)
+  }
 }
 
 fn type_of(json: &Value) -> String {
@@ -68,13 +152,13 @@ impl Matches<&Value> for Value {
   fn matches_with(&self, actual: &Value, matcher: &MatchingRule, cascaded: bool) -> anyhow::Result<()> {
     let result = match matcher {
       MatchingRule::Regex(regex) => {
-        match Regex::new(regex) {
+        match MatchingRegex::new(regex) {
           Ok(re) => {
             let actual_str = match actual {
               Value::String(ref s) => s.clone(),
               _ => actual.to_string()
             };
-            if re.is_match(&actual_str) {
+            if re.is_match(&actual_str)? {
               Ok(())
             } else {
               Err(anyhow!("Expected '{}' to match '{}'", json_to_string(actual), regex))
@@ -156,7 +240,9 @@ impl Matches<&Value> for Value {
             value_of(actual), type_of(actual), value_of(self), type_of(self))),
         }
       },
-      MatchingRule::Equality | MatchingRule::Values => {
+      MatchingRule::Equality | MatchingRule::Values |
+      MatchingRule::EqualsIgnoreOrder | MatchingRule::MinEqualsIgnoreOrder(_) |
+      MatchingRule::MaxEqualsIgnoreOrder(_) | MatchingRule::MinMaxEqualsIgnoreOrder(_, _) => {
         if self == actual {
           Ok(())
         } else {
@@ -164,6 +250,14 @@ impl Matches<&Value> for Value {
             value_of(actual), type_of(actual), value_of(self), type_of(self)))
         }
       },
+      MatchingRule::EqualsIgnoreCase => match (self, actual) {
+        (Value::String(expected), Value::String(actual_str)) => if expected.to_lowercase() == actual_str.to_lowercase() {
+          Ok(())
+        } else {
+          Err(anyhow!("Expected '{}' to be equal to '{}' ignoring case", actual_str, expected))
+        }
+        _ => self.matches_with(actual, &MatchingRule::Equality, cascaded)
+      },
       MatchingRule::Null => match actual {
         Value::Null => Ok(()),
         _ => Err(anyhow!("Expected {} ({}) to be a null value", value_of(actual), type_of(actual)))
@@ -266,6 +360,7 @@ impl Matches<&Value> for Value {
         }
         _ => Err(anyhow!("Expected {} ({}) to match a boolean", value_of(actual), type_of(actual)))
       }
+      MatchingRule::NotEmpty if cascaded => self.matches_with(actual, &MatchingRule::Type, cascaded),
       MatchingRule::NotEmpty => match actual {
         Value::Null => Err(anyhow!("Expected non-empty but got a NULL")),
         Value::String(s) => if s.is_empty() {
@@ -292,6 +387,21 @@ impl Matches<&Value> for Value {
         }
         _ => Err(anyhow!("Expected something that matches a semantic version, but got '{}'", actual))
       }
+      MatchingRule::DecimalWithTolerance(tolerance) => {
+        let as_number = |value: &Value| value.as_f64().or_else(|| value.as_str().and_then(|s| s.parse::<f64>().ok()));
+        match (as_number(self), as_number(actual)) {
+          (Some(expected), Some(actual)) => match_with_tolerance(expected, actual, *tolerance),
+          (None, _) => Err(anyhow!("Unable to match {} ({}) using {:?} as it is not a number",
+            value_of(self), type_of(self), matcher)),
+          (_, None) => Err(anyhow!("Expected {} ({}) to be a number", value_of(actual), type_of(actual)))
+        }
+      }
+      MatchingRule::TimestampWithin(format, seconds, reference) => match actual {
+        Value::String(s) => match_timestamp_within(s, format, *seconds, reference),
+        _ => Err(anyhow!("Expected {} ({}) to be a timestamp", value_of(actual), type_of(actual)))
+      }
+      MatchingRule::JsonSchema(schema) if !cascaded => match_json_schema(schema, actual),
+      MatchingRule::Custom(name, _) => Err(anyhow!("Custom matcher '{}' can only be applied using a matching context it has been registered with", name)),
       _ => Ok(())
     };
     debug!("JSON -> JSON: Comparing '{}' to '{}' using {:?} -> {:?}", self, actual, matcher, result);
@@ -305,8 +415,49 @@ pub fn match_json(
   actual: &(dyn HttpPart + Send + Sync),
   context: &(dyn MatchingContext + Send + Sync)
 ) -> Result<(), Vec<super::Mismatch>> {
-  let expected_json = serde_json::from_slice(&*expected.body().value().unwrap_or_default());
-  let actual_json = serde_json::from_slice(&*actual.body().value().unwrap_or_default());
+  match_json_with_skip_identical_threshold(expected, actual, context, *JSON_SKIP_IDENTICAL_THRESHOLD)
+}
+
+/// Matches the expected GraphQL request envelope to the actual. This is the same as `match_json`,
+/// except the GraphQL queries in the envelopes are always normalised before being compared.
+pub(crate) fn match_graphql_envelope(
+  expected: &(dyn HttpPart + Send + Sync),
+  actual: &(dyn HttpPart + Send + Sync),
+  context: &(dyn MatchingContext + Send + Sync)
+) -> Result<(), Vec<super::Mismatch>> {
+  match_json_body(expected, actual, context, *JSON_SKIP_IDENTICAL_THRESHOLD, true)
+}
+
+fn match_json_with_skip_identical_threshold(
+  expected: &(dyn HttpPart + Send + Sync),
+  actual: &(dyn HttpPart + Send + Sync),
+  context: &(dyn MatchingContext + Send + Sync),
+  threshold: usize
+) -> Result<(), Vec<super::Mismatch>> {
+  let graphql = expected.content_type()
+    .map(|content_type| is_graphql_content_type(&content_type))
+    .unwrap_or_default();
+  match_json_body(expected, actual, context, threshold, graphql)
+}
+
+fn match_json_body(
+  expected: &(dyn HttpPart + Send + Sync),
+  actual: &(dyn HttpPart + Send + Sync),
+  context: &(dyn MatchingContext + Send + Sync),
+  threshold: usize,
+  graphql: bool
+) -> Result<(), Vec<super::Mismatch>> {
+  let expected_body = expected.body().value().unwrap_or_default();
+  let actual_body = actual.body().value().unwrap_or_default();
+  if (expected_body.len() > threshold || actual_body.len() > threshold) && context.matchers().is_empty()
+    && expected_body == actual_body && serde_json::from_slice::<IgnoredAny>(&actual_body).is_ok() {
+    debug!("match_json: large bodies ({} bytes) with no matching rules are identical and valid JSON, not comparing them",
+      actual_body.len());
+    return Ok(());
+  }
+
+  let expected_json = serde_json::from_slice(&expected_body);
+  let actual_json = serde_json::from_slice(&actual_body);
 
   if expected_json.is_err() || actual_json.is_err() {
     let mut mismatches = vec![];
@@ -316,6 +467,7 @@ pub fn match_json(
         expected: expected.body().value(),
         actual: actual.body().value(),
         mismatch: format!("Failed to parse the expected body: '{}'", e),
+        location: None
       });
     }
     if let Err(e) = actual_json {
@@ -324,11 +476,29 @@ pub fn match_json(
         expected: expected.body().value(),
         actual: actual.body().value(),
         mismatch: format!("Failed to parse the actual body: '{}'", e),
+        location: None
       });
     }
     Err(mismatches.clone())
   } else {
-    compare_json(&DocPath::root(), &expected_json.unwrap(), &actual_json.unwrap(), context)
+    let mut expected_json = expected_json.unwrap();
+    let mut actual_json = actual_json.unwrap();
+    if graphql && is_graphql_envelope(&expected_json) && is_graphql_envelope(&actual_json) {
+      debug!("match_json: bodies are GraphQL requests, normalising the queries");
+      normalise_envelope(&mut expected_json);
+      normalise_envelope(&mut actual_json);
+    }
+    let coercion_context;
+    let context = match expected.content_type() {
+      Some(content_type) if context.type_coercion_enabled_for_content_type(&content_type) => {
+        debug!("match_json: type coercion is enabled for content type {}", content_type);
+        coercion_context = CoreMatchingContext::clone_from(context)
+          .with_type_coercion(&[DocPath::root()]);
+        &coercion_context
+      }
+      _ => context
+    };
+    compare_json(&DocPath::root(), &expected_json, &actual_json, context)
       .map_err(|mismatches| mismatches.iter().map(|mismatch| mismatch.to_body_mismatch()).collect())
   }
 }
@@ -397,51 +567,104 @@ pub fn compare_json(
   context: &(dyn MatchingContext + Send + Sync)
 ) -> Result<(), Vec<CommonMismatch>> {
   debug!("compare: Comparing path {}", path);
+  let limits = context.limits();
+  if limits.depth_exceeded(path) {
+    debug!("compare: Maximum depth of {} exceeded at path {}", limits.max_depth.unwrap_or_default(), path);
+    return Err(vec![ limits.depth_mismatch(path, json_for_mismatch(expected), json_for_mismatch(actual)) ]);
+  }
+  if let Some(result) = compare_with_json_schema(path, expected, actual, context) {
+    return result;
+  }
   match (expected, actual) {
     (&Value::Object(ref emap), &Value::Object(ref amap)) => compare_maps(path, emap, amap, context),
     (&Value::Object(_), _) => {
       Err(vec![ CommonMismatch {
         path: path.to_string(),
-        expected: json_to_string(expected),
-        actual: json_to_string(actual),
+        expected: json_for_mismatch(expected),
+        actual: json_for_mismatch(actual),
         description: format!("Type mismatch: Expected {} ({}) to be the same type as {} ({})",
-          value_of(actual), type_of(actual), value_of(expected), type_of(expected))
+          value_for_mismatch(actual), type_of(actual), value_for_mismatch(expected), type_of(expected)),
+        location: Some(MismatchLocation::new(path, &[]))
       } ])
     }
     (&Value::Array(ref elist), &Value::Array(ref alist)) => compare_lists(path, elist, alist, context),
     (&Value::Array(_), _) => {
       Err(vec![ CommonMismatch {
         path: path.to_string(),
-        expected: json_to_string(expected),
-        actual: json_to_string(actual),
+        expected: json_for_mismatch(expected),
+        actual: json_for_mismatch(actual),
         description: format!("Type mismatch: Expected {} ({}) to be the same type as {} ({})",
-          value_of(actual), type_of(actual), value_of(expected), type_of(expected)),
+          value_for_mismatch(actual), type_of(actual), value_for_mismatch(expected), type_of(expected)),
+        location: Some(MismatchLocation::new(path, &[]))
       } ])
     }
     (_, _) => compare_values(path, expected, actual, context)
   }
 }
 
+/// If a JSON Schema matcher is defined against the path, the actual value (including all the values
+/// below it) is matched using the rules for the path instead of being compared with the expected value
+fn compare_with_json_schema(
+  path: &DocPath,
+  expected: &Value,
+  actual: &Value,
+  context: &(dyn MatchingContext + Send + Sync)
+) -> Option<Result<(), Vec<CommonMismatch>>> {
+  if !(actual.is_object() || actual.is_array()) || !context.matcher_is_defined(path) {
+    return None;
+  }
+
+  let rule_list = context.select_best_matcher(path);
+  if rule_list.cascaded || !rule_list.rules.iter().any(|rule| matches!(rule, MatchingRule::JsonSchema(_))) {
+    return None;
+  }
+
+  debug!("compare_with_json_schema: JSON Schema matcher is defined for path {}", path);
+  Some(match_values(path, &rule_list, expected, actual).map_err(|messages| {
+    messages.iter().map(|message| {
+      CommonMismatch {
+        path: path.to_string(),
+        expected: json_for_mismatch(expected),
+        actual: json_for_mismatch(actual),
+        description: message.clone(),
+        location: Some(MismatchLocation::new(path, &rule_list.rules))
+      }
+    }).collect()
+  }))
+}
+
 fn compare_maps(
   path: &DocPath,
   expected: &serde_json::Map<String, Value>,
   actual: &serde_json::Map<String, Value>,
   context: &(dyn MatchingContext + Send + Sync)
 ) -> Result<(), Vec<CommonMismatch>> {
+  let limits = context.limits();
+  if limits.items_exceeded(expected.len(), actual.len()) {
+    debug!("compare_maps: Maximum number of items exceeded at path {}", path);
+    let expected_items = expected.iter().take(limits.item_limit()).map(|(k, v)| (k.clone(), v.clone())).collect();
+    let actual_items = actual.iter().take(limits.item_limit()).map(|(k, v)| (k.clone(), v.clone())).collect();
+    let result = compare_maps(path, &expected_items, &actual_items, context);
+    return merge_result(result, Err(vec![ limits.items_mismatch(path, expected.len(), actual.len()) ]));
+  }
+
   let spath = path.to_string();
   debug!("compare_maps: Comparing maps at {}: {:?} -> {:?}", spath, expected, actual);
   if expected.is_empty() && context.config() == DiffConfig::NoUnexpectedKeys && !actual.is_empty() {
     debug!("compare_maps: Expected map is empty, but actual is not");
+    let actual = collection_for_mismatch(actual);
     Err(vec![ CommonMismatch {
       path: spath,
-      expected: json_to_string(&json!(expected)),
-      actual: json_to_string(&json!(actual)),
-      description: format!("Expected an empty Map but received {}", json_to_string(&json!(actual))),
+      expected: collection_for_mismatch(expected),
+      actual: actual.clone(),
+      description: format!("Expected an empty Map but received {}", actual),
+      location: Some(MismatchLocation::new(path, &[]))
     } ])
   } else {
     let mut result = Ok(());
-    let expected = expected.iter().map(|(k, v)| (k.clone(), v.clone())).collect();
-    let actual = actual.iter().map(|(k, v)| (k.clone(), v.clone())).collect();
+    // Only the keys are copied here, the values are borrowed from the parsed bodies
+    let expected: BTreeMap<String, &Value> = expected.iter().map(|(k, v)| (k.clone(), v)).collect();
+    let actual: BTreeMap<String, &Value> = actual.iter().map(|(k, v)| (k.clone(), v)).collect();
 
     if context.matcher_is_defined(path) {
       debug!("compare_maps: Matcher is defined for path {}", path);
@@ -459,7 +682,7 @@ fn compare_maps(
       for (key, value) in expected.iter() {
         let p = path.join(key);
         if actual.contains_key(key) {
-          result = merge_result(result, compare_json(&p, value, &actual[key], context));
+          result = merge_result(result, compare_json(&p, value, actual[key], context));
         }
       }
     };
@@ -473,6 +696,14 @@ fn compare_lists(
   actual: &[Value],
   context: &(dyn MatchingContext + Send + Sync)
 ) -> Result<(), Vec<CommonMismatch>> {
+  let limits = context.limits();
+  if limits.items_exceeded(expected.len(), actual.len()) {
+    debug!("compare_lists: Maximum number of items exceeded at path {}", path);
+    let result = compare_lists(path, &expected[..expected.len().min(limits.item_limit())],
+      &actual[..actual.len().min(limits.item_limit())], context);
+    return merge_result(result, Err(vec![ limits.items_mismatch(path, expected.len(), actual.len()) ]));
+  }
+
   let spath = path.to_string();
   if context.matcher_is_defined(path) {
     debug!("compare_lists: matcher defined for path '{}'", path);
@@ -480,21 +711,24 @@ fn compare_lists(
         compare_json(p, expected, actual, context)
     })
   } else if expected.is_empty() && !actual.is_empty() {
+    let actual = collection_for_mismatch(actual);
     Err(vec![ CommonMismatch {
       path: spath,
-      expected: json_to_string(&json!(expected)),
-      actual: json_to_string(&json!(actual)),
-      description: format!("Expected an empty List but received {}", json_to_string(&json!(actual))),
+      expected: collection_for_mismatch(expected),
+      actual: actual.clone(),
+      description: format!("Expected an empty List but received {}", actual),
+      location: Some(MismatchLocation::new(path, &[]))
     } ])
   } else {
     let result = compare_list_content(path, expected, actual, context);
     if expected.len() != actual.len() {
       merge_result(result, Err(vec![ CommonMismatch {
         path: spath,
-        expected: json_to_string(&json!(expected)),
-        actual: json_to_string(&json!(actual)),
+        expected: collection_for_mismatch(expected),
+        actual: collection_for_mismatch(actual),
         description: format!("Expected a List with {} elements but received {} elements",
                           expected.len(), actual.len()),
+        location: Some(MismatchLocation::new(path, &[]))
       } ]))
     } else {
       result
@@ -518,9 +752,10 @@ fn compare_list_content(
     } else if !context.matcher_is_defined(&p) {
       result = merge_result(result,Err(vec![ CommonMismatch {
         path: path.to_string(),
-        expected: json_to_string(&json!(expected)),
-        actual: json_to_string(&json!(actual)),
-        description: format!("Expected {} but was missing", json_to_string(value)) } ]))
+        expected: collection_for_mismatch(expected),
+        actual: collection_for_mismatch(actual),
+        description: format!("Expected {} but was missing", json_for_mismatch(value)),
+        location: Some(MismatchLocation::new(path, &[])) } ]))
     }
   }
   result
@@ -532,25 +767,57 @@ fn compare_values(
   actual: &Value,
   context: &(dyn MatchingContext + Send + Sync)
 ) -> Result<(), Vec<CommonMismatch>> {
-  let matcher_result = if context.matcher_is_defined(path) {
+  let coerced = if context.type_coercion_enabled(path) {
+    coerce_value(expected, actual)
+  } else {
+    None
+  };
+  let actual_value = coerced.as_ref().unwrap_or(actual);
+  let rule_list = if context.matcher_is_defined(path) {
+    Some(context.select_best_matcher(path))
+  } else {
+    None
+  };
+  let matcher_result = if let Some(rule_list) = &rule_list {
     debug!("compare_values: Calling match_values for path {}", path);
-    match_values(path, &context.select_best_matcher(&path), expected, actual)
+    match_values_with_context(path, rule_list, expected, actual_value, context)
   } else {
-    expected.matches_with(actual, &MatchingRule::Equality, false).map_err(|err| vec![err.to_string()])
+    expected.matches_with(actual_value, &MatchingRule::Equality, false).map_err(|err| vec![err.to_string()])
   };
   debug!("compare_values: Comparing '{:?}' to '{:?}' at path '{}' -> {:?}", expected, actual, path.to_string(), matcher_result);
+  let rules = rule_list.map(|rule_list| rule_list.rules).unwrap_or_default();
   matcher_result.map_err(|messages| {
     messages.iter().map(|message| {
       CommonMismatch {
         path: path.to_string(),
         expected: format!("{}", expected),
         actual: format!("{}", actual),
-        description: message.clone()
+        description: message.clone(),
+        location: Some(MismatchLocation::new(path, &rules))
       }
     }).collect()
   })
 }
 
+/// Converts the actual value to the type of the expected value, if it is a number, boolean or
+/// string and the conversion is possible. Returns `None` if no conversion is required or possible.
+fn coerce_value(expected: &Value, actual: &Value) -> Option<Value> {
+  match (expected, actual) {
+    (Value::Number(_), Value::String(s)) => match serde_json::from_str::<Value>(s.trim()) {
+      Ok(Value::Number(n)) => Some(Value::Number(n)),
+      _ => None
+    },
+    (Value::Bool(_), Value::String(s)) => match s.trim() {
+      "true" => Some(Value::Bool(true)),
+      "false" => Some(Value::Bool(false)),
+      _ => None
+    },
+    (Value::String(_), Value::Number(n)) => Some(Value::String(n.to_string())),
+    (Value::String(_), Value::Bool(b)) => Some(Value::String(b.to_string())),
+    _ => None
+  }
+}
+
 #[cfg(test)]
 mod tests {
   use std::collections::HashMap;
@@ -563,6 +830,7 @@ mod tests {
   use pact_models::matchingrules::{MatchingRule, MatchingRuleCategory};
   use pact_models::matchingrules::expressions::{MatchingRuleDefinition, ValueType};
   use pact_models::request::Request;
+  use serde_json::json;
 
   use crate::{CoreMatchingContext, DiffConfig};
   use crate::Mismatch;
@@ -583,7 +851,37 @@ mod tests {
       path: s!("$"),
       expected: expected.body.value(),
       actual: actual.body.value(),
-      mismatch: s!("") }]));
+      mismatch: s!(""),
+      location: None }]));
+  }
+
+  #[test]
+  fn match_json_skips_comparing_identical_bodies_over_the_threshold() {
+    let expected = request!(r#"{"json": "is good"}"#);
+    let actual = request!(r#"{"json": "is good"}"#);
+    let context = CoreMatchingContext::with_config(DiffConfig::AllowUnexpectedKeys);
+    expect!(match_json_with_skip_identical_threshold(&expected, &actual, &context, 10)).to(be_ok());
+    expect!(match_json_with_skip_identical_threshold(&expected, &actual, &context, 100)).to(be_ok());
+
+    let actual = request!(r#"{"json": "is bad!"}"#);
+    expect!(match_json_with_skip_identical_threshold(&expected, &actual, &context, 10)).to(be_err());
+  }
+
+  #[test]
+  fn match_json_does_not_skip_comparing_identical_malformed_bodies() {
+    let expected = request!(r#"{"json": "is bad"#);
+    let actual = request!(r#"{"json": "is bad"#);
+    let context = CoreMatchingContext::with_config(DiffConfig::AllowUnexpectedKeys);
+    let result = match_json_with_skip_identical_threshold(&expected, &actual, &context, 10);
+    let descriptions = result.unwrap_err().iter().map(|m| m.description()).collect::<Vec<_>>();
+    expect!(descriptions.len()).to(be_equal_to(2));
+    expect!(descriptions[0].starts_with("$ -> Failed to parse the expected body")).to(be_true());
+    expect!(descriptions[1].starts_with("$ -> Failed to parse the actual body")).to(be_true());
+    expect!(match_json_with_skip_identical_threshold(&expected, &actual, &context, 100)).to(be_err());
+
+    let matchers = matchingrules_list! { "body"; "$.json" => [ MatchingRule::Type ] };
+    let context = CoreMatchingContext::new(DiffConfig::AllowUnexpectedKeys, &matchers, &hashmap!{});
+    expect!(match_json_with_skip_identical_threshold(&expected, &actual, &context, 10)).to(be_err());
   }
 
   #[test]
@@ -597,7 +895,8 @@ mod tests {
           path: s!("$"),
           expected: expected.body.value(),
           actual: actual.body.value(),
-          mismatch: s!("Type mismatch: Expected List [{}] but received Map {}")
+          mismatch: s!("Type mismatch: Expected List [{}] but received Map {}"),
+          location: None
         }
       ]
     ));
@@ -623,7 +922,8 @@ mod tests {
       path: "$".to_string(),
       expected: expected.body.value(),
       actual: actual.body.value(),
-      mismatch: "".to_string()
+      mismatch: "".to_string(),
+      location: None
     }]));
   }
 
@@ -637,7 +937,8 @@ mod tests {
       path: "$".to_string(),
       expected: expected.body.value(),
       actual: actual.body.value(),
-      mismatch: "".to_string()
+      mismatch: "".to_string(),
+      location: None
     }]));
   }
 
@@ -654,7 +955,8 @@ mod tests {
       path: "$".to_string(),
       expected: val1.body.value(),
       actual: val2.body.value(),
-      mismatch: "".to_string()
+      mismatch: "".to_string(),
+      location: None
     } ]));
   }
 
@@ -671,7 +973,8 @@ mod tests {
       path: "$".to_string(),
       expected: val1.body.value(),
       actual: val2.body.value(),
-      mismatch: "".to_string()
+      mismatch: "".to_string(),
+      location: None
     } ]));
   }
 
@@ -688,7 +991,8 @@ mod tests {
       path: "$".to_string(),
       expected: val1.body.value(),
       actual: val2.body.value(),
-      mismatch: "".to_string()
+      mismatch: "".to_string(),
+      location: None
     } ]));
   }
 
@@ -705,7 +1009,8 @@ mod tests {
       path: "$".to_string(),
       expected: val1.body.value(),
       actual: val2.body.value(),
-      mismatch: "".to_string()
+      mismatch: "".to_string(),
+      location: None
     } ]));
   }
 
@@ -722,7 +1027,8 @@ mod tests {
       path: "$".to_string(),
       expected: val1.clone().body.value(),
       actual: val2.clone().body.value(),
-      mismatch: "".to_string()
+      mismatch: "".to_string(),
+      location: None
     } ]));
   }
 
@@ -749,13 +1055,13 @@ mod tests {
     let result = match_json(&val2.clone(), &val3.clone(), &CoreMatchingContext::with_config(DiffConfig::AllowUnexpectedKeys));
     expect!(mismatch_message(&result)).to(be_equal_to("Expected 44 (Integer) to be equal to 22 (Integer)".to_string()));
     expect!(result).to(be_err().value(vec![ Mismatch::BodyMismatch { path: "$[1]".to_string(),
-        expected: Some("22".into()), actual: Some("44".into()), mismatch: "".to_string() } ]));
+        expected: Some("22".into()), actual: Some("44".into()), mismatch: "".to_string(), location: None } ]));
 
     let result = match_json(&val3.clone(), &val4.clone(), &CoreMatchingContext::with_config(DiffConfig::AllowUnexpectedKeys));
     expect!(mismatch_message(&result)).to(be_equal_to("Expected a List with 3 elements but received 4 elements".to_string()));
     expect!(result).to(be_err().value(vec![ Mismatch::BodyMismatch { path: "$".to_string(),
         expected: Some("[11,44,33]".into()),
-        actual: Some("[11,44,33,66]".into()), mismatch: "".to_string() } ]));
+        actual: Some("[11,44,33,66]".into()), mismatch: "".to_string(), location: None } ]));
 
     let result = match_json(&val2.clone(), &val4.clone(), &CoreMatchingContext::with_config(DiffConfig::AllowUnexpectedKeys));
     let mismatches = result.unwrap_err();
@@ -763,12 +1069,12 @@ mod tests {
     let mismatch = mismatches[0].clone();
     expect!(&mismatch).to(be_equal_to(&Mismatch::BodyMismatch { path: "$[1]".to_string(),
         expected: Some("22".into()),
-        actual: Some("44".into()), mismatch: "".to_string()}));
+        actual: Some("44".into()), mismatch: "".to_string(), location: None}));
     expect!(mismatch.description()).to(be_equal_to("$[1] -> Expected 44 (Integer) to be equal to 22 (Integer)".to_string()));
     let mismatch = mismatches[1].clone();
     expect!(&mismatch).to(be_equal_to(&Mismatch::BodyMismatch { path: "$".to_string(),
         expected: Some("[11,22,33]".into()),
-        actual: Some("[11,44,33,66]".into()), mismatch: "".to_string()}));
+        actual: Some("[11,44,33,66]".into()), mismatch: "".to_string(), location: None}));
     expect!(mismatch.description()).to(be_equal_to("$ -> Expected a List with 3 elements but received 4 elements".to_string()));
 
     let context = CoreMatchingContext::new(DiffConfig::AllowUnexpectedKeys, &matchingrules! {
@@ -804,7 +1110,7 @@ mod tests {
     let result = match_json(&val2.clone(), &val3.clone(), &CoreMatchingContext::with_config(DiffConfig::AllowUnexpectedKeys));
     expect!(mismatch_message(&result).as_str()).to(be_equal_to("Expected 3 (Integer) to be equal to 2 (Integer)"));
     expect!(result).to(be_err().value(vec![ Mismatch::BodyMismatch { path: "$.b".to_string(),
-        expected: Some("2".into()), actual: Some("3".into()), mismatch: "".to_string() } ]));
+        expected: Some("2".into()), actual: Some("3".into()), mismatch: "".to_string(), location: None } ]));
 
     let result = match_json(&val2.clone(), &val4.clone(), &CoreMatchingContext::with_config(DiffConfig::AllowUnexpectedKeys));
     expect!(result).to(be_ok());
@@ -813,14 +1119,14 @@ mod tests {
     expect!(mismatch_message(&result)).to(be_equal_to("Expected a Map with keys [a, b] but received one with keys [a, b, c]".to_string()));
     expect!(result).to(be_err().value(vec![ Mismatch::BodyMismatch { path: "$".to_string(),
         expected: Some("[\"a\",\"b\"]".into()),
-        actual: Some("[\"a\",\"b\",\"c\"]".into()), mismatch: "Expected a Map with keys [a, b] but received one with keys [a, b, c]".to_string()
+        actual: Some("[\"a\",\"b\",\"c\"]".into()), mismatch: "Expected a Map with keys [a, b] but received one with keys [a, b, c]".to_string(), location: None
     } ]));
 
     let result = match_json(&val3.clone(), &val4.clone(), &CoreMatchingContext::with_config(DiffConfig::AllowUnexpectedKeys));
     expect!(mismatch_message(&result).as_str()).to(be_equal_to("Expected 2 (Integer) to be equal to 3 (Integer)"));
     expect!(result).to(be_err().value(vec![ Mismatch::BodyMismatch { path: "$.b".to_string(),
         expected: Some("3".into()),
-        actual: Some("2".into()), mismatch: "".to_string() } ]));
+        actual: Some("2".into()), mismatch: "".to_string(), location: None } ]));
 
     let result = match_json(&val3.clone(), &val4.clone(), &CoreMatchingContext::with_config(DiffConfig::NoUnexpectedKeys));
     let mismatches = result.unwrap_err();
@@ -828,12 +1134,12 @@ mod tests {
     let mismatch = mismatches[0].clone();
     expect!(&mismatch).to(be_equal_to(&Mismatch::BodyMismatch { path: "$".to_string(),
         expected: Some("[\"a\",\"b\"]".into()),
-        actual: Some("[\"a\",\"b\",\"c\"]".into()), mismatch: "".to_string()}));
+        actual: Some("[\"a\",\"b\",\"c\"]".into()), mismatch: "".to_string(), location: None}));
     expect!(mismatch.description()).to(be_equal_to("$ -> Expected a Map with keys [a, b] but received one with keys [a, b, c]".to_string()));
     let mismatch = mismatches[1].clone();
     expect!(&mismatch).to(be_equal_to(&Mismatch::BodyMismatch { path: "$.b".to_string(),
         expected: Some("3".into()),
-        actual: Some("2".into()), mismatch: "".to_string()}));
+        actual: Some("2".into()), mismatch: "".to_string(), location: None}));
     expect!(mismatch.description()).to(be_equal_to("$.b -> Expected 2 (Integer) to be equal to 3 (Integer)".to_string()));
 
     let result = match_json(&val4.clone(), &val2.clone(), &CoreMatchingContext::with_config(DiffConfig::AllowUnexpectedKeys));
@@ -842,7 +1148,7 @@ mod tests {
     let mismatch = mismatches[0].clone();
     expect!(&mismatch).to(be_equal_to(&Mismatch::BodyMismatch { path: "$".to_string(),
         expected: Some("[\"a\",\"b\",\"c\"]".into()),
-        actual: Some("[\"a\",\"b\"]".into()), mismatch: "".to_string()}));
+        actual: Some("[\"a\",\"b\"]".into()), mismatch: "".to_string(), location: None}));
     expect!(mismatch.description()).to(be_equal_to("$ -> Actual map is missing the following keys: c".to_string()));
 
     let result = match_json(&val3, &val2, &CoreMatchingContext::new(DiffConfig::AllowUnexpectedKeys, &matchingrules!{
@@ -970,6 +1276,62 @@ mod tests {
     expect!(Value::String("100".into()).matches_with(&Value::Null, &matcher, false)).to(be_ok());
   }
 
+  #[test]
+  #[cfg(feature = "json-schema")]
+  fn json_schema_matcher_test() {
+    let matcher = MatchingRule::JsonSchema(json!({
+      "type": "object",
+      "properties": {
+        "id": { "type": "integer" },
+        "tags": { "type": "array", "items": { "type": "string" } }
+      },
+      "required": ["id"]
+    }));
+    expect!(json!({}).matches_with(&json!({"id": 100, "tags": ["a", "b"]}), &matcher, false)).to(be_ok());
+    expect!(json!({}).matches_with(&json!({"id": 100, "tags": ["a", 2]}), &matcher, false).unwrap_err().to_string()).to(
+      be_equal_to("Expected {\"id\":100,\"tags\":[\"a\",2]} to match the JSON Schema - /tags/1: 2 is not of type \"string\""));
+    expect!(json!({}).matches_with(&json!({"tags": []}), &matcher, false).unwrap_err().to_string()).to(
+      be_equal_to("Expected {\"tags\":[]} to match the JSON Schema - \"id\" is a required property"));
+    expect!(json!({}).matches_with(&json!({"tags": []}), &matcher, true)).to(be_ok());
+    expect!(json!({}).matches_with(&json!(100), &MatchingRule::JsonSchema(json!({"type": "unknown"})), false)).to(be_err());
+  }
+
+  #[test]
+  #[cfg(not(feature = "json-schema"))]
+  fn json_schema_matcher_requires_the_json_schema_feature() {
+    let matcher = MatchingRule::JsonSchema(json!({ "type": "object" }));
+    expect!(json!({}).matches_with(&json!({"id": 100}), &matcher, false).unwrap_err().to_string()).to(
+      be_equal_to("JSON Schema matchers require the json-schema feature to be enabled"));
+  }
+
+  #[test]
+  #[cfg(feature = "json-schema")]
+  fn compare_json_with_json_schema_matcher() {
+    let matchingrules = matchingrules_list! {
+      "body";
+      "$.items" => [ MatchingRule::JsonSchema(json!({
+        "type": "array",
+        "minItems": 1,
+        "items": {
+          "type": "object",
+          "properties": { "id": { "type": "integer", "minimum": 1 } },
+          "required": ["id"]
+        }
+      })) ]
+    };
+    let context = CoreMatchingContext::new(DiffConfig::AllowUnexpectedKeys, &matchingrules, &hashmap!{});
+    let expected = json!({"name": "Fred", "items": [{"id": 1}]});
+    expect!(compare_json(&DocPath::root(), &expected,
+      &json!({"name": "Fred", "items": [{"id": 10, "extra": true}, {"id": 20}]}), &context)).to(be_ok());
+
+    let result = compare_json(&DocPath::root(), &expected,
+      &json!({"name": "Mary", "items": [{"id": 10}, {"id": 0}]}), &context);
+    expect!(result.unwrap_err().iter().map(|m| (m.path.clone(), m.description.clone())).collect::<Vec<_>>()).to(be_equal_to(vec![
+      ("$.items".to_string(), "Expected [{\"id\":10},{\"id\":0}] to match the JSON Schema - /1/id: 0 is less than the minimum of 1".to_string()),
+      ("$.name".to_string(), "Expected 'Mary' (String) to be equal to 'Fred' (String)".to_string())
+    ]));
+  }
+
   #[test]
   fn content_type_matcher_test() {
     let matcher = MatchingRule::ContentType("text/plain".to_string());
@@ -989,6 +1351,24 @@ mod tests {
     }
   }
 
+  #[test]
+  fn match_json_records_the_path_and_applied_rules_with_body_mismatches() {
+    let expected = request!(r#"{"name": "Fred", "items": [{"id": 100}, {"id": 200}]}"#);
+    let actual = request!(r#"{"name": "Mary", "items": [{"id": 100}, {"id": "200"}]}"#);
+    let matchers = matchingrules_list! { "body"; "$.items[*].id" => [ MatchingRule::Integer ] };
+    let context = CoreMatchingContext::new(DiffConfig::AllowUnexpectedKeys, &matchers, &hashmap!{});
+
+    let mismatches = match_json(&expected, &actual, &context).unwrap_err();
+    let locations = mismatches.iter().map(|mismatch| match mismatch {
+      BodyMismatch { location, .. } => location.clone(),
+      _ => None
+    }).collect::<Vec<_>>();
+    expect!(locations).to(be_equal_to(vec![
+      Some(MismatchLocation::new(&DocPath::new_unwrap("$.items[1].id"), &[ MatchingRule::Integer ])),
+      Some(MismatchLocation::new(&DocPath::new_unwrap("$.name"), &[]))
+    ]));
+  }
+
   #[test_log::test]
   fn compare_maps_handles_wildcard_matchers() {
     let val1 = request!(r#"
@@ -1047,6 +1427,55 @@ mod tests {
     expect!(result).to(be_ok());
   }
 
+  fn mismatch_descriptions(result: Result<(), Vec<CommonMismatch>>) -> Vec<(String, String)> {
+    result.unwrap_err().iter().map(|m| (m.path.clone(), m.description.clone())).collect()
+  }
+
+  #[test]
+  fn compare_lists_with_ignore_order_matcher() {
+    let matchingrules = matchingrules_list! { "body"; "$.tags" => [ MatchingRule::EqualsIgnoreOrder ] };
+    let context = CoreMatchingContext::new(DiffConfig::AllowUnexpectedKeys, &matchingrules, &hashmap!{});
+    let expected = json!({"tags": ["red", "green", "blue", "red"]});
+    expect!(compare_json(&DocPath::root(), &expected, &json!({"tags": ["blue", "red", "red", "green"]}), &context)).to(be_ok());
+
+    expect!(mismatch_descriptions(compare_json(&DocPath::root(), &expected,
+      &json!({"tags": ["blue", "red", "green", "green"]}), &context))).to(be_equal_to(vec![
+      ("$.tags".to_string(), "Expected item at index 3 (\"red\") was not found in the actual list".to_string())
+    ]));
+    expect!(mismatch_descriptions(compare_json(&DocPath::root(), &expected,
+      &json!({"tags": ["blue", "red", "green", "red", "pink"]}), &context))).to(be_equal_to(vec![
+      ("$.tags".to_string(), "Expected [\"blue\", \"red\", \"green\", \"red\", \"pink\"] (size 5) to have a size of 4".to_string()),
+      ("$.tags[4]".to_string(), "Actual item at index 4 (\"pink\") did not match any of the expected items".to_string())
+    ]));
+  }
+
+  #[test]
+  fn compare_lists_with_ignore_order_matcher_applies_the_item_rules() {
+    let matchingrules = matchingrules_list! {
+      "body";
+      "$.items" => [ MatchingRule::MinEqualsIgnoreOrder(2) ],
+      "$.items[*].id" => [ MatchingRule::Integer ]
+    };
+    let context = CoreMatchingContext::new(DiffConfig::AllowUnexpectedKeys, &matchingrules, &hashmap!{});
+    let expected = json!({"items": [{"id": 1, "type": "book"}, {"id": 2, "type": "film"}]});
+    expect!(compare_json(&DocPath::root(), &expected, &json!({"items": [
+      {"id": 10, "type": "film"}, {"id": 20, "type": "book"}, {"id": 30, "type": "book"}
+    ]}), &context)).to(be_ok());
+
+    expect!(mismatch_descriptions(compare_json(&DocPath::root(), &expected, &json!({"items": [
+      {"id": 10, "type": "book"}, {"id": 20, "type": "book"}, {"id": 30, "type": "music"}
+    ]}), &context))).to(be_equal_to(vec![
+      ("$.items".to_string(), "Expected item at index 1 ({\"id\":2,\"type\":\"film\"}) was not found in the actual list".to_string()),
+      ("$.items[2]".to_string(), "Actual item at index 2 ({\"id\":30,\"type\":\"music\"}) did not match any of the expected items".to_string())
+    ]));
+    expect!(mismatch_descriptions(compare_json(&DocPath::root(), &expected, &json!({"items": [
+      {"id": 10, "type": "film"}
+    ]}), &context))).to(be_equal_to(vec![
+      ("$.items".to_string(), "Expected [{\"id\":10,\"type\":\"film\"}] (size 1) to have minimum size of 2".to_string()),
+      ("$.items".to_string(), "Expected item at index 0 ({\"id\":1,\"type\":\"book\"}) was not found in the actual list".to_string())
+    ]));
+  }
+
   #[test]
   fn compare_lists_with_array_contains_matcher() {
     let val1 = request!(r#"
@@ -1096,7 +1525,8 @@ mod tests {
         path: "$".to_string(),
         expected: Some("3".into()),
         actual: Some("[\"10\",\"22\",\"6\",\"1\",\"5\",\"2\"]".into()),
-        mismatch: "Variant at index 2 (3) was not found in the actual list".to_string()
+        mismatch: "Variant at index 2 (3) was not found in the actual list".to_string(),
+        location: None
       }
     ]));
   }
@@ -1150,12 +1580,14 @@ mod tests {
         expected: Some("2".into()),
         actual: Some("\"abc123\"".into()),
         mismatch: "Expected 'abc123' (String) to be an integer number".to_string(),
+        location: None
       },
       BodyMismatch {
         path: "$[2]".to_string(),
         expected: Some("1".into()),
         actual: Some("\"test\"".into()),
         mismatch: "Expected 'test' (String) to be an integer number".to_string(),
+        location: None
       }
     ]));
   }
@@ -1329,6 +1761,15 @@ mod tests {
     let result = compare_maps(&DocPath::root(), expected, invalid, &context);
     expect!(result).to(be_err());
   }
+
+  #[test]
+  fn to_limited_string_truncates_large_values() {
+    let list = vec![json!("a"), json!({"b": [1, 2, 3]})];
+    expect!(to_limited_string(&list, 100)).to(be_equal_to("[\"a\",{\"b\":[1,2,3]}]".to_string()));
+    expect!(to_limited_string(&list, 10)).to(be_equal_to("[\"a\",{\"b\":...".to_string()));
+    expect!(to_limited_string(&list, 0)).to(be_equal_to("...".to_string()));
+    expect!(to_limited_string(&json!("café"), 5)).to(be_equal_to("\"caf...".to_string()));
+  }
 }
 
 #[cfg(test)]
@@ -1338,6 +1779,7 @@ mod tests2 {
   use rstest::rstest;
   use serde_json::{json, Value};
 
+  use pact_models::content_types::ContentType;
   use pact_models::matchingrules_list;
   use pact_models::matchingrules::MatchingRule;
   use crate::{CoreMatchingContext, DiffConfig};
@@ -1417,4 +1859,101 @@ mod tests2 {
       expect!(result).to(be_err());
     }
   }
+
+  #[rstest]
+  #[case::non_empty(json!({"items": ["b", ""]}), true)]
+  #[case::different_item_type(json!({"items": ["b", {}]}), false)]
+  #[case::empty(json!({"items": []}), false)]
+  #[case::null_item(json!({"items": ["b", null]}), false)]
+  fn compare_with_not_empty_matcher(#[case] actual: Value, #[case] is_ok: bool) {
+    let matchingrules = matchingrules_list! { "body"; "$.items" => [ MatchingRule::NotEmpty ] };
+    let context = CoreMatchingContext::new(DiffConfig::AllowUnexpectedKeys, &matchingrules, &hashmap!{});
+    let result = compare_json(&DocPath::root(), &json!({"items": ["a"]}), &actual, &context);
+    if is_ok {
+      expect!(result).to(be_ok());
+    } else {
+      expect!(result).to(be_err());
+    }
+  }
+
+  #[rstest]
+  #[case::valid(json!({"id": "01ARZ3NDEKTSV4RRFFQ69G5FAV"}), true)]
+  #[case::invalid(json!({"id": "not-a-ulid"}), false)]
+  #[case::not_a_string(json!({"id": 100}), false)]
+  fn compare_with_custom_matcher(#[case] actual: Value, #[case] is_ok: bool) {
+    let matchingrules = matchingrules_list! { "body"; "$.id" => [
+      MatchingRule::Custom("ulid".to_string(), hashmap!{ "length".to_string() => json!(26) })
+    ] };
+    let context = CoreMatchingContext::new(DiffConfig::AllowUnexpectedKeys, &matchingrules, &hashmap!{})
+      .with_custom_matcher("ulid", |_, actual, config| {
+        let length = config.get("length").and_then(|l| l.as_u64()).unwrap_or_default() as usize;
+        match actual.as_str() {
+          Some(s) if s.len() == length && s.chars().all(|c| c.is_ascii_alphanumeric()) => vec![],
+          _ => vec![format!("Expected {} to be a ULID", actual)]
+        }
+      });
+    let result = compare_json(&DocPath::root(), &json!({"id": "01BX5ZZKBKACTAV9WEVGEMMVRZ"}), &actual, &context);
+    if is_ok {
+      expect!(result).to(be_ok());
+    } else {
+      expect!(result).to(be_err());
+    }
+  }
+
+  #[rstest]
+  #[case::number_as_string(json!({"id": 123, "active": true}), json!({"id": "123", "active": true}), true)]
+  #[case::boolean_as_string(json!({"id": 123, "active": true}), json!({"id": 123, "active": "true"}), true)]
+  #[case::string_as_number(json!({"id": "123", "active": true}), json!({"id": 123, "active": true}), true)]
+  #[case::different_number(json!({"id": 123, "active": true}), json!({"id": "124", "active": true}), false)]
+  #[case::not_a_number(json!({"id": 123, "active": true}), json!({"id": "abc", "active": true}), false)]
+  #[case::not_a_boolean(json!({"id": 123, "active": true}), json!({"id": 123, "active": "yes"}), false)]
+  fn compare_with_type_coercion(#[case] expected: Value, #[case] actual: Value, #[case] is_ok: bool) {
+    let context = CoreMatchingContext::default();
+    expect!(compare_json(&DocPath::root(), &expected, &json!({"id": "123", "active": "true"}), &context)).to(be_err());
+
+    let context = CoreMatchingContext::default().with_type_coercion(&[DocPath::root()]);
+    let result = compare_json(&DocPath::root(), &expected, &actual, &context);
+    if is_ok {
+      expect!(result).to(be_ok());
+    } else {
+      expect!(result).to(be_err());
+    }
+  }
+
+  #[test]
+  fn type_coercion_only_applies_to_the_configured_paths() {
+    let context = CoreMatchingContext::default()
+      .with_type_coercion(&[DocPath::new_unwrap("$.items[*].id")]);
+    let expected = json!({"items": [{"id": 1, "count": 1}]});
+    expect!(compare_json(&DocPath::root(), &expected, &json!({"items": [{"id": "1", "count": 1}]}), &context)).to(be_ok());
+    expect!(compare_json(&DocPath::root(), &expected, &json!({"items": [{"id": "1", "count": "1"}]}), &context)).to(be_err());
+  }
+
+  #[test]
+  fn type_coercion_applies_before_matching_rules() {
+    let matchingrules = matchingrules_list! { "body"; "$.id" => [ MatchingRule::Integer ] };
+    let context = CoreMatchingContext::new(DiffConfig::AllowUnexpectedKeys, &matchingrules, &hashmap!{})
+      .with_type_coercion(&[DocPath::new_unwrap("$.id")]);
+    expect!(compare_json(&DocPath::root(), &json!({"id": 1}), &json!({"id": "100"}), &context)).to(be_ok());
+    expect!(compare_json(&DocPath::root(), &json!({"id": 1}), &json!({"id": "1.5"}), &context)).to(be_err());
+  }
+
+  #[test]
+  fn type_coercion_only_applies_to_the_configured_content_types() {
+    let request = |body: &str, content_type: &str| pact_models::request::Request {
+      headers: Some(hashmap!{ "Content-Type".to_string() => vec![content_type.to_string()] }),
+      body: pact_models::bodies::OptionalBody::Present(body.as_bytes().to_vec().into(), None, None),
+      .. pact_models::request::Request::default()
+    };
+    let context = CoreMatchingContext::default()
+      .with_type_coercion_for_content_types(&[ContentType::parse("application/vnd.api+json").unwrap()]);
+
+    let expected = request(r#"{"id": 1}"#, "application/vnd.api+json");
+    let actual = request(r#"{"id": "1"}"#, "application/vnd.api+json");
+    expect!(match_json(&expected, &actual, &context)).to(be_ok());
+
+    let expected = request(r#"{"id": 1}"#, "application/json");
+    let actual = request(r#"{"id": "1"}"#, "application/json");
+    expect!(match_json(&expected, &actual, &context)).to(be_err());
+  }
 }
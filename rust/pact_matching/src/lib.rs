@@ -14,13 +14,24 @@
 //! To compare any incoming request, it first needs to be converted to a [`models::Request`](models/struct.Request.html) and then can be compared. Same for
 //! any response.
 //!
+//! To match a complete interaction of any type, use the [`match_interaction`](interaction/fn.match_interaction.html)
+//! function from the [`interaction`](interaction/index.html) module.
+//!
+//! Custom matchers, type coercion, explain mode and body limits are configured with
+//! [`MatchingOptions`](struct.MatchingOptions.html), which can be passed to the `_with_options`
+//! variants of the matching functions (i.e. [`match_request_with_options`](fn.match_request_with_options.html))
+//! or set on the context used by `match_interaction`.
+//!
 //! ## Crate features
-//! All features are enabled by default.
+//! All features are enabled by default, except for `yaml`, `csv` and `json-schema`.
 //!
 //! * `datetime`: Enables support of date and time expressions and generators. This will add the `chronos` crate as a dependency.
 //! * `xml`: Enables support for parsing XML documents. This feature will add the `sxd-document` crate as a dependency.
 //! * `plugins`: Enables support for using plugins. This feature will add the `pact-plugin-driver` crate as a dependency.
 //! * `multipart`: Enables support for MIME multipart bodies. This feature will add the `multer` crate as a dependency.
+//! * `yaml`: Enables support for matching YAML documents. This feature will add the `serde_yaml_ng` crate as a dependency.
+//! * `csv`: Enables support for matching CSV documents. This feature will add the `csv` crate as a dependency.
+//! * `json-schema`: Enables support for the JSON Schema matcher. This feature will add the `jsonschema` crate as a dependency.
 //!
 //! ## Reading and writing Pact files
 //!
@@ -48,6 +59,11 @@
 //! For the most part, matching involves matching request and response bodies in JSON or XML format.
 //! Other formats will either have their own matching rules, or will follow the JSON one.
 //!
+//! Text bodies are compared as UTF-8. If the content type of a body has a `charset` parameter
+//! (i.e. `application/json; charset=UTF-16LE`), the body is first decoded using that charset
+//! (UTF-16 and ISO-8859-1 are supported). Line endings can also be normalised before comparing
+//! text bodies by setting the `PACT_MATCHING_NORMALISE_LINE_ENDINGS` environment variable to `true`.
+//!
 //! #### JSON body matching rules
 //!
 //! Bodies consist of Objects (Maps of Key-Value pairs), Arrays (Lists) and values (Strings, Numbers, true, false, null).
@@ -344,11 +360,24 @@
 //! | Values  | V3 | `{ "match": "values" }` | Match the values in a map, ignoring the keys |
 //! | ArrayContains | V4 | `{ "match": "arrayContains", "variants": [...] }` | Checks if all the variants are present in an array. |
 //! | StatusCode | V4 | `{ "match": "statusCode", "status": "success" }` | Matches the response status code. |
-//! | NotEmpty | V4 | `{ "match": "notEmpty" }` | Value must be present and not empty (not null or the empty string) |
+//! | NotEmpty | V4 | `{ "match": "notEmpty" }` | Value must be present and not empty (not null, the empty string or an empty array or map). Any values it contains are only matched by type |
 //! | Semver | V4 | `{ "match": "semver" }` | Value must be valid based on the semver specification |
 //! | Semver | V4 | `{ "match": "semver" }` | Value must be valid based on the semver specification |
 //! | EachKey | V4 | `{ "match": "eachKey", "rules": [{"match": "regex", "regex": "\\$(\\.\\w+)+"}], "value": "$.test.one" }` | Allows defining matching rules to apply to the keys in a map |
 //! | EachValue | V4 | `{ "match": "eachValue", "rules": [{"match": "regex", "regex": "\\$(\\.\\w+)+"}], "value": "$.test.one" }` | Allows defining matching rules to apply to the values in a collection. For maps, delgates to the Values matcher. |
+//! | EqualsIgnoreCase | V4 | `{ "match": "equalsIgnoreCase" }` | Matches using equality, ignoring the case of any string values (i.e. `Bearer` and `bearer` header values) |
+//! | Custom | Not part of the specification | `{ "match": "pact-rust:custom", "name": "jwt", "config": { "alg": "HS256" } }` | Delegates to the custom matcher function registered with the name on the matching context (see the `custom_matchers` module). Only supported by this implementation, and only written to V4 pacts. |
+//! | DecimalWithTolerance | V4 | `{ "match": "decimalWithTolerance", "tolerance": 0.01 }` | Matches numbers that differ from the expected value by no more than the tolerance |
+//! | TimestampWithin | V4 | `{ "match": "timestampWithin", "seconds": 60, "format": "yyyy-MM-dd HH:mm:ss" }` | Matches a timestamp that is within the number of seconds of the `reference` timestamp, or the current time if there is no reference. If there is no format, ISO 8601 is used |
+//! | JsonSchema | V4 | `{ "match": "jsonSchema", "schema": { "type": "object" } }` | Validates the value (and all the values below it) against the JSON Schema document. Requires the `json-schema` feature |
+//! | EqualsIgnoreOrder | V4 | `{ "match": "ignore-order" }` | Matches the items of a collection using equality, ignoring their order. The collections must have the same number of items. Any matching rules for the items are also applied |
+//! | MinEqualsIgnoreOrder | V4 | `{ "match": "ignore-order", "min": 2 }` | Matches the items of a collection using equality, ignoring their order. The length of the actual collection is compared against the minimum |
+//! | MaxEqualsIgnoreOrder | V4 | `{ "match": "ignore-order", "max": 10 }` | Matches the items of a collection using equality, ignoring their order. The length of the actual collection is compared against the maximum |
+//! | MinMaxEqualsIgnoreOrder | V4 | `{ "match": "ignore-order", "min": 2, "max": 10 }` | Matches the items of a collection using equality, ignoring their order. The length of the actual collection is compared against the minimum and maximum |
+//!
+//! Regular expressions are evaluated with a backtracking engine, with a limit on the amount of
+//! backtracking for each match. For pacts from untrusted sources, set the `PACT_MATCHING_REGEX_ENGINE`
+//! environment variable to `linear` to use a linear-time engine instead (see the `regex_engine` module).
 
 #![warn(missing_docs)]
 
@@ -359,6 +388,7 @@ use std::hash::Hash;
 use std::panic::RefUnwindSafe;
 use std::str;
 use std::str::from_utf8;
+use std::sync::OnceLock;
 
 use ansi_term::*;
 use ansi_term::Colour::*;
@@ -386,13 +416,17 @@ use serde::__private::from_utf8_lossy;
 use serde_json::{json, Value};
 #[allow(unused_imports)] use tracing::{debug, error, info, instrument, trace, warn};
 
+use crate::custom_matchers::CustomMatchers;
 use crate::generators::DefaultVariantMatcher;
 use crate::generators::bodies::generators_process_body;
-use crate::headers::{match_header_value, match_headers};
+use crate::headers::{match_header_value, match_headers, match_trailers};
 #[cfg(feature = "plugins")] use crate::json::match_json;
 use crate::matchers::*;
 use crate::matchingrules::DisplayForMismatch;
 use crate::query::match_query_maps;
+use crate::explain::{MatchingExplanation, RuleSelection};
+use crate::limits::MatchingLimits;
+use crate::rule_index::MatchingRuleIndex;
 
 /// Simple macro to convert a string slice to a `String` struct.
 #[macro_export]
@@ -408,13 +442,25 @@ pub mod json;
 pub mod logging;
 pub mod matchingrules;
 pub mod metrics;
+pub mod mismatch_renderer;
+pub mod custom_matchers;
 pub mod generators;
+pub mod rule_index;
+pub mod explain;
+pub mod limits;
+pub mod charsets;
+pub mod regex_engine;
+pub mod interaction;
 
 #[cfg(feature = "xml")] mod xml;
+#[cfg(feature = "yaml")] pub mod yaml;
+#[cfg(feature = "csv")] pub mod csv;
 pub mod binary_utils;
 pub mod headers;
 pub mod query;
 pub mod form_urlencoded;
+pub mod graphql;
+pub mod event_stream;
 
 #[cfg(not(feature = "plugins"))]
 #[derive(Clone, Debug, PartialEq)]
@@ -452,19 +498,86 @@ pub trait MatchingContext: Debug {
 
   /// Clones the current context with the provided matching rules
   fn clone_with(&self, matchers: &MatchingRuleCategory) -> Box<dyn MatchingContext + Send + Sync>;
+
+  /// If values at the path should be coerced to the type of the expected value before being
+  /// compared (i.e. the string `"123"` to the number `123`, or `"true"` to the boolean `true`).
+  /// Type coercion is opt-in, and is disabled by default.
+  fn type_coercion_enabled(&self, _path: &DocPath) -> bool {
+    false
+  }
+
+  /// If all the values in bodies with the content type should be coerced to the type of the
+  /// expected value before being compared. This is disabled by default.
+  fn type_coercion_enabled_for_content_type(&self, _content_type: &ContentType) -> bool {
+    false
+  }
+
+  /// Returns the paths where type coercion has been enabled
+  fn type_coercion_paths(&self) -> &[DocPath] {
+    &[]
+  }
+
+  /// Returns the content types where type coercion has been enabled for all the values
+  fn type_coercion_content_types(&self) -> &[ContentType] {
+    &[]
+  }
+
+  /// Returns the explanation the rules selected by this context are recorded to, if explain mode
+  /// has been enabled. Explain mode is disabled by default.
+  fn explanation(&self) -> Option<&MatchingExplanation> {
+    None
+  }
+
+  /// Returns the depth and size limits to apply when matching bodies
+  fn limits(&self) -> MatchingLimits {
+    MatchingLimits::default()
+  }
+
+  /// If the line endings of text bodies should be normalised before they are compared, so that
+  /// `\r\n` and `\r` will match `\n`. This is disabled by default.
+  fn normalise_line_endings(&self) -> bool {
+    false
+  }
+
+  /// Returns the custom matchers that have been registered with this context, which will be used
+  /// for any custom matching rules. There are no custom matchers registered by default.
+  fn custom_matchers(&self) -> Option<&CustomMatchers> {
+    None
+  }
 }
 
 #[derive(Debug, Clone)]
 /// Core implementation of a matching context
 pub struct CoreMatchingContext {
-  /// Matching rules that apply when matching with the context
+  /// Matching rules that apply when matching with the context. The rule index is built from
+  /// these the first time a rule is looked up, so they must not be changed after the context has
+  /// been used for matching.
   pub matchers: MatchingRuleCategory,
   /// Configuration to apply when matching with the context
   pub config: DiffConfig,
   /// Specification version to apply when matching with the context
   pub matching_spec: PactSpecification,
   /// Any plugin configuration available for the interaction
-  pub plugin_configuration: HashMap<String, PluginInteractionConfig>
+  pub plugin_configuration: HashMap<String, PluginInteractionConfig>,
+  /// Paths where values will be coerced to the type of the expected value before being compared.
+  /// Paths can contain wildcards, and also apply to any values below them (so `$` will enable
+  /// type coercion for everything).
+  type_coercion: Vec<DocPath>,
+  /// Content types of bodies where all the values will be coerced to the type of the expected value
+  type_coercion_content_types: Vec<ContentType>,
+  /// If set, the rules selected for each path are recorded to this explanation
+  pub explanation: Option<MatchingExplanation>,
+  /// Depth and size limits to apply when matching bodies
+  pub limits: MatchingLimits,
+  /// If the line endings of text bodies are normalised before they are compared. Defaults to the
+  /// value of the `PACT_MATCHING_NORMALISE_LINE_ENDINGS` environment variable (which is only read
+  /// once).
+  pub normalise_line_endings: bool,
+  /// Index of the matching rule paths. This is built from the matchers the first time a rule
+  /// is looked up.
+  rule_index: OnceLock<MatchingRuleIndex>,
+  /// Custom matchers to use for any custom matching rules
+  custom_matchers: CustomMatchers
 }
 
 impl CoreMatchingContext {
@@ -490,9 +603,72 @@ impl CoreMatchingContext {
     }
   }
 
+  /// Enables type coercion for values at the given paths. Values at these paths (or below them)
+  /// will be converted to the type of the expected value before being compared, so that a
+  /// provider that returns `"123"` or `"true"` will match an expected `123` or `true`.
+  pub fn with_type_coercion(mut self, paths: &[DocPath]) -> Self {
+    self.type_coercion.extend_from_slice(paths);
+    self
+  }
+
+  /// Enables type coercion for all the values in bodies with the given content types. This only
+  /// applies to JSON bodies.
+  pub fn with_type_coercion_for_content_types(mut self, content_types: &[ContentType]) -> Self {
+    self.type_coercion_content_types.extend_from_slice(content_types);
+    self
+  }
+
+  /// Enables explain mode. The matching rules selected for each path (or that values were compared
+  /// with equality, if there were no rules) will be recorded to the explanation.
+  pub fn with_explanation(mut self, explanation: &MatchingExplanation) -> Self {
+    self.explanation = Some(explanation.clone());
+    self
+  }
+
+  /// Sets the depth and size limits to apply when matching bodies. Values nested deeper than the
+  /// maximum depth, or items past the maximum number of items in a collection, are not compared
+  /// and a mismatch is reported instead.
+  pub fn with_limits(mut self, limits: MatchingLimits) -> Self {
+    self.limits = limits;
+    self
+  }
+
+  /// Sets if the line endings of text bodies are normalised before they are compared, so that
+  /// providers that return `\r\n` line endings will match `\n` line endings.
+  pub fn with_normalised_line_endings(mut self, normalise_line_endings: bool) -> Self {
+    self.normalise_line_endings = normalise_line_endings;
+    self
+  }
+
+  /// Registers a custom matcher function with the given name. Custom matching rules that reference
+  /// the name will be matched using the function, which is called with the expected and actual
+  /// values and the configuration from the matching rule, and must return the list of mismatches.
+  pub fn with_custom_matcher<F>(mut self, name: &str, matcher: F) -> Self
+    where F: Fn(&Value, &Value, &HashMap<String, Value>) -> Vec<String> + Send + Sync + 'static {
+    self.custom_matchers.register(name, matcher);
+    self
+  }
+
+  /// Applies the matching options to the context, replacing any settings already on it
+  pub fn with_options(self, options: &MatchingOptions) -> Self {
+    CoreMatchingContext {
+      type_coercion: options.type_coercion.clone(),
+      type_coercion_content_types: options.type_coercion_content_types.clone(),
+      explanation: options.explanation.clone(),
+      limits: options.limits,
+      normalise_line_endings: options.normalise_line_endings,
+      custom_matchers: options.custom_matchers.clone(),
+      .. self
+    }
+  }
+
+  fn rule_index(&self) -> &MatchingRuleIndex {
+    self.rule_index.get_or_init(|| MatchingRuleIndex::new(&self.matchers))
+  }
+
   fn matchers_for_exact_path(&self, path: &DocPath) -> MatchingRuleCategory {
     match self.matchers.name {
-      Category::HEADER | Category::QUERY => self.matchers.filter(|&(val, _)| {
+      Category::HEADER | Category::QUERY | Category::TRAILER => self.matchers.filter(|&(val, _)| {
         path.len() == 1 && path.first_field() == val.first_field()
       }),
       Category::BODY => self.matchers.filter(|&(val, _)| {
@@ -510,6 +686,12 @@ impl CoreMatchingContext {
       matchers: context.matchers().clone(),
       config: context.config().clone(),
       plugin_configuration: context.plugin_configuration().clone(),
+      explanation: context.explanation().cloned(),
+      limits: context.limits(),
+      normalise_line_endings: context.normalise_line_endings(),
+      custom_matchers: context.custom_matchers().cloned().unwrap_or_default(),
+      type_coercion: context.type_coercion_paths().to_vec(),
+      type_coercion_content_types: context.type_coercion_content_types().to_vec(),
       .. CoreMatchingContext::default()
     }
   }
@@ -521,29 +703,143 @@ impl Default for CoreMatchingContext {
       matchers: Default::default(),
       config: DiffConfig::AllowUnexpectedKeys,
       matching_spec: PactSpecification::V3,
-      plugin_configuration: Default::default()
+      plugin_configuration: Default::default(),
+      type_coercion: Default::default(),
+      type_coercion_content_types: Default::default(),
+      explanation: None,
+      limits: Default::default(),
+      normalise_line_endings: charsets::normalise_line_endings_from_env(),
+      rule_index: Default::default(),
+      custom_matchers: Default::default()
+    }
+  }
+}
+
+/// Options that configure how matching is done. These are applied to all the matching contexts
+/// created by the matching functions that take options (i.e. [`match_request_with_options`]), so
+/// the settings can be used without creating the contexts directly.
+#[derive(Debug, Clone)]
+pub struct MatchingOptions {
+  /// Custom matchers to use for any custom matching rules
+  pub custom_matchers: CustomMatchers,
+  /// Paths in bodies where values will be coerced to the type of the expected value before being
+  /// compared (see [`CoreMatchingContext::with_type_coercion`])
+  pub type_coercion: Vec<DocPath>,
+  /// Content types of bodies where all the values will be coerced to the type of the expected value
+  pub type_coercion_content_types: Vec<ContentType>,
+  /// If set, the rules selected for each path are recorded to this explanation
+  pub explanation: Option<MatchingExplanation>,
+  /// Depth and size limits to apply when matching bodies
+  pub limits: MatchingLimits,
+  /// If the line endings of text bodies are normalised before they are compared
+  pub normalise_line_endings: bool
+}
+
+impl Default for MatchingOptions {
+  fn default() -> Self {
+    MatchingOptions {
+      custom_matchers: Default::default(),
+      type_coercion: Default::default(),
+      type_coercion_content_types: Default::default(),
+      explanation: None,
+      limits: Default::default(),
+      normalise_line_endings: charsets::normalise_line_endings_from_env()
     }
   }
 }
 
+impl MatchingOptions {
+  /// Registers a custom matcher function with the given name (see
+  /// [`CoreMatchingContext::with_custom_matcher`])
+  pub fn with_custom_matcher<F>(mut self, name: &str, matcher: F) -> Self
+    where F: Fn(&Value, &Value, &HashMap<String, Value>) -> Vec<String> + Send + Sync + 'static {
+    self.custom_matchers.register(name, matcher);
+    self
+  }
+
+  /// Enables type coercion for values at the given paths in bodies
+  pub fn with_type_coercion(mut self, paths: &[DocPath]) -> Self {
+    self.type_coercion.extend_from_slice(paths);
+    self
+  }
+
+  /// Enables type coercion for all the values in bodies with the given content types
+  pub fn with_type_coercion_for_content_types(mut self, content_types: &[ContentType]) -> Self {
+    self.type_coercion_content_types.extend_from_slice(content_types);
+    self
+  }
+
+  /// Enables explain mode, recording the selected rules to the explanation
+  pub fn with_explanation(mut self, explanation: &MatchingExplanation) -> Self {
+    self.explanation = Some(explanation.clone());
+    self
+  }
+
+  /// Sets the depth and size limits to apply when matching bodies
+  pub fn with_limits(mut self, limits: MatchingLimits) -> Self {
+    self.limits = limits;
+    self
+  }
+
+  /// Sets if the line endings of text bodies are normalised before they are compared
+  pub fn with_normalised_line_endings(mut self, normalise_line_endings: bool) -> Self {
+    self.normalise_line_endings = normalise_line_endings;
+    self
+  }
+}
+
 impl MatchingContext for CoreMatchingContext {
   #[instrument(level = "trace", ret, skip_all, fields(path, matchers = ?self.matchers))]
   fn matcher_is_defined(&self, path: &DocPath) -> bool {
-    let path = path.to_vec();
-    let path_slice = path.iter().map(|p| p.as_str()).collect_vec();
-    self.matchers.matcher_is_defined(path_slice.as_slice())
+    let defined = if MatchingRuleIndex::supports_category(&self.matchers.name) {
+      self.rule_index().matcher_is_defined(path)
+    } else {
+      let path = path.to_vec();
+      let path_slice = path.iter().map(|p| p.as_str()).collect_vec();
+      self.matchers.matcher_is_defined(path_slice.as_slice())
+    };
+    if let Some(explanation) = &self.explanation {
+      if !defined {
+        explanation.record(&self.matchers.name, path, RuleSelection::Equality);
+      }
+    }
+    defined
   }
 
   fn select_best_matcher(&self, path: &DocPath) -> RuleList {
-    let path = path.to_vec();
-    let path_slice = path.iter().map(|p| p.as_str()).collect_vec();
-    self.matchers.select_best_matcher(path_slice.as_slice())
+    let rules = if MatchingRuleIndex::supports_category(&self.matchers.name) {
+      self.rule_index().select_best_matcher(&self.matchers, path)
+    } else {
+      let path = path.to_vec();
+      let path_slice = path.iter().map(|p| p.as_str()).collect_vec();
+      self.matchers.select_best_matcher(path_slice.as_slice())
+    };
+    if let Some(explanation) = &self.explanation {
+      let selection = if rules.is_empty() {
+        RuleSelection::Equality
+      } else {
+        let rule_path = if MatchingRuleIndex::supports_category(&self.matchers.name) {
+          self.rule_index().best_match(path).map(|m| m.path.clone())
+        } else {
+          None
+        };
+        RuleSelection::Rules { rule_path, rules: rules.clone() }
+      };
+      explanation.record(&self.matchers.name, path, selection);
+    }
+    rules
   }
 
   fn type_matcher_defined(&self, path: &DocPath) -> bool {
-    let path = path.to_vec();
-    let path_slice = path.iter().map(|p| p.as_str()).collect_vec();
-    self.matchers.resolve_matchers_for_path(path_slice.as_slice()).type_matcher_defined()
+    if MatchingRuleIndex::supports_category(&self.matchers.name) {
+      self.rule_index().matching_paths(path).iter()
+        .filter_map(|m| self.matchers.rules.get(m.path))
+        .any(|rules| rules.type_matcher_defined())
+    } else {
+      let path = path.to_vec();
+      let path_slice = path.iter().map(|p| p.as_str()).collect_vec();
+      self.matchers.resolve_matchers_for_path(path_slice.as_slice()).type_matcher_defined()
+    }
   }
 
   fn values_matcher_defined(&self, path: &DocPath) -> bool {
@@ -575,6 +871,7 @@ impl MatchingContext for CoreMatchingContext {
             expected: expected.for_mismatch(),
             actual: actual.for_mismatch(),
             description: format!("Actual map is missing the following keys: {}", missing_keys.join(", ")),
+            location: None
           });
         }
         DiffConfig::NoUnexpectedKeys if expected_keys != actual_keys => {
@@ -584,6 +881,7 @@ impl MatchingContext for CoreMatchingContext {
             actual: actual.for_mismatch(),
             description: format!("Expected a Map with keys [{}] but received one with keys [{}]",
                               expected_keys.join(", "), actual_keys.join(", ")),
+            location: None
           });
         }
         _ => {}
@@ -606,6 +904,7 @@ impl MatchingContext for CoreMatchingContext {
                         expected: "".to_string(),
                         actual: key.clone(),
                         description: err.to_string(),
+                        location: Some(MismatchLocation::new(&key_path, &[rule.clone()]))
                       });
                     }
                   }
@@ -617,6 +916,7 @@ impl MatchingContext for CoreMatchingContext {
                     actual: actual.for_mismatch(),
                     description: format!("Expected a matching rule, found an unresolved reference '{}'",
                       name.name),
+                    location: None
                   });
                 }
               }
@@ -651,9 +951,50 @@ impl MatchingContext for CoreMatchingContext {
       matchers: matchers.clone(),
       config: self.config.clone(),
       matching_spec: self.matching_spec,
-      plugin_configuration: self.plugin_configuration.clone()
+      plugin_configuration: self.plugin_configuration.clone(),
+      type_coercion: self.type_coercion.clone(),
+      type_coercion_content_types: self.type_coercion_content_types.clone(),
+      explanation: self.explanation.clone(),
+      limits: self.limits,
+      normalise_line_endings: self.normalise_line_endings,
+      custom_matchers: self.custom_matchers.clone(),
+      .. CoreMatchingContext::default()
     })
   }
+
+  fn type_coercion_enabled(&self, path: &DocPath) -> bool {
+    let path = path.to_vec();
+    let path_slice = path.iter().map(|p| p.as_str()).collect_vec();
+    self.type_coercion.iter().any(|p| p.matches_path(path_slice.as_slice()))
+  }
+
+  fn type_coercion_enabled_for_content_type(&self, content_type: &ContentType) -> bool {
+    self.type_coercion_content_types.iter().any(|ct| ct.is_equivalent_to(content_type))
+  }
+
+  fn type_coercion_paths(&self) -> &[DocPath] {
+    &self.type_coercion
+  }
+
+  fn type_coercion_content_types(&self) -> &[ContentType] {
+    &self.type_coercion_content_types
+  }
+
+  fn explanation(&self) -> Option<&MatchingExplanation> {
+    self.explanation.as_ref()
+  }
+
+  fn limits(&self) -> MatchingLimits {
+    self.limits
+  }
+
+  fn normalise_line_endings(&self) -> bool {
+    self.normalise_line_endings
+  }
+
+  fn custom_matchers(&self) -> Option<&CustomMatchers> {
+    Some(&self.custom_matchers)
+  }
 }
 
 #[derive(Debug, Clone, Default)]
@@ -667,18 +1008,26 @@ impl HeaderMatchingContext {
   pub fn new(context: &(dyn MatchingContext + Send + Sync)) -> Self {
     let matchers = context.matchers();
     HeaderMatchingContext {
-      inner_context: CoreMatchingContext::new(
-        context.config(),
-        &MatchingRuleCategory {
-          name: matchers.name.clone(),
-          rules: matchers.rules.iter()
-            .map(|(path, rules)| {
-              (path.to_lower_case(), rules.clone())
-            })
-            .collect()
-        },
-        &context.plugin_configuration()
-      )
+      inner_context: CoreMatchingContext {
+        type_coercion: context.type_coercion_paths().to_vec(),
+        type_coercion_content_types: context.type_coercion_content_types().to_vec(),
+        explanation: context.explanation().cloned(),
+        limits: context.limits(),
+        normalise_line_endings: context.normalise_line_endings(),
+        custom_matchers: context.custom_matchers().cloned().unwrap_or_default(),
+        .. CoreMatchingContext::new(
+          context.config(),
+          &MatchingRuleCategory {
+            name: matchers.name.clone(),
+            rules: matchers.rules.iter()
+              .map(|(path, rules)| {
+                (path.to_lower_case(), rules.clone())
+              })
+              .collect()
+          },
+          &context.plugin_configuration()
+        )
+      }
     }
   }
 }
@@ -726,19 +1075,62 @@ impl MatchingContext for HeaderMatchingContext {
         matchers: matchers.clone(),
         config: self.inner_context.config.clone(),
         matching_spec: self.inner_context.matching_spec,
-        plugin_configuration: self.inner_context.plugin_configuration.clone()
+        plugin_configuration: self.inner_context.plugin_configuration.clone(),
+        type_coercion: self.inner_context.type_coercion.clone(),
+        type_coercion_content_types: self.inner_context.type_coercion_content_types.clone(),
+        explanation: self.inner_context.explanation.clone(),
+        limits: self.inner_context.limits,
+        normalise_line_endings: self.inner_context.normalise_line_endings,
+        custom_matchers: self.inner_context.custom_matchers.clone(),
+        .. CoreMatchingContext::default()
       }
     ))
   }
+
+  fn type_coercion_enabled(&self, path: &DocPath) -> bool {
+    self.inner_context.type_coercion_enabled(path)
+  }
+
+  fn type_coercion_enabled_for_content_type(&self, content_type: &ContentType) -> bool {
+    self.inner_context.type_coercion_enabled_for_content_type(content_type)
+  }
+
+  fn type_coercion_paths(&self) -> &[DocPath] {
+    self.inner_context.type_coercion_paths()
+  }
+
+  fn type_coercion_content_types(&self) -> &[ContentType] {
+    self.inner_context.type_coercion_content_types()
+  }
+
+  fn explanation(&self) -> Option<&MatchingExplanation> {
+    self.inner_context.explanation()
+  }
+
+  fn limits(&self) -> MatchingLimits {
+    self.inner_context.limits()
+  }
+
+  fn normalise_line_endings(&self) -> bool {
+    self.inner_context.normalise_line_endings()
+  }
+
+  fn custom_matchers(&self) -> Option<&CustomMatchers> {
+    self.inner_context.custom_matchers()
+  }
 }
 
 lazy_static! {
   static ref BODY_MATCHERS: [
     (fn(content_type: &ContentType) -> bool,
-    fn(expected: &(dyn HttpPart + Send + Sync), actual: &(dyn HttpPart + Send + Sync), context: &(dyn MatchingContext + Send + Sync)) -> Result<(), Vec<Mismatch>>); 5]
+    fn(expected: &(dyn HttpPart + Send + Sync), actual: &(dyn HttpPart + Send + Sync), context: &(dyn MatchingContext + Send + Sync)) -> Result<(), Vec<Mismatch>>); 9]
      = [
+      (|content_type| { content_type.base_type() == "application/graphql" }, graphql::match_graphql),
+      (|content_type| { event_stream::is_event_stream(content_type) || event_stream::is_ndjson(content_type) }, event_stream::match_event_stream),
       (|content_type| { content_type.is_json() }, json::match_json),
       (|content_type| { content_type.is_xml() }, match_xml),
+      (|content_type| { content_type.is_yaml() }, match_yaml),
+      (|content_type| { content_type.base_type() == "text/csv" }, match_csv),
       (|content_type| { content_type.main_type == "multipart" }, binary_utils::match_mime_multipart),
       (|content_type| { content_type.base_type() == "application/x-www-form-urlencoded" }, form_urlencoded::match_form_urlencoded),
       (|content_type| { content_type.is_binary() || content_type.base_type() == "application/octet-stream" }, binary_utils::match_octet_stream)
@@ -761,6 +1153,85 @@ fn match_xml(
   }
 }
 
+fn match_yaml(
+  expected: &(dyn HttpPart + Send + Sync),
+  actual: &(dyn HttpPart + Send + Sync),
+  context: &(dyn MatchingContext + Send + Sync)
+) -> Result<(), Vec<Mismatch>> {
+  #[cfg(feature = "yaml")]
+  {
+    yaml::match_yaml(expected, actual, context)
+  }
+  #[cfg(not(feature = "yaml"))]
+  {
+    debug!("Matching YAML documents requires the yaml feature to be enabled, comparing them as text");
+    match_text(&expected.body().value(), &actual.body().value(), context)
+  }
+}
+
+fn match_csv(
+  expected: &(dyn HttpPart + Send + Sync),
+  actual: &(dyn HttpPart + Send + Sync),
+  context: &(dyn MatchingContext + Send + Sync)
+) -> Result<(), Vec<Mismatch>> {
+  #[cfg(feature = "csv")]
+  {
+    csv::match_csv(expected, actual, context)
+  }
+  #[cfg(not(feature = "csv"))]
+  {
+    debug!("Matching CSV documents requires the csv feature to be enabled, comparing them as text");
+    match_text(&expected.body().value(), &actual.body().value(), context)
+  }
+}
+
+/// Where a body mismatch occurred, and the matching rules that were applied there. This is
+/// recorded by the matching functions when the mismatch is created.
+#[derive(Debug, Clone)]
+pub struct MismatchLocation {
+  /// Path to where the mismatch occurred
+  pub path: DocPath,
+  /// Matching rules that were applied at the path. Will be empty if the values were compared
+  /// using equality.
+  pub rules: Vec<MatchingRule>
+}
+
+impl MismatchLocation {
+  /// Creates the location for a mismatch at the given path with the matching rules that were
+  /// applied
+  pub fn new(path: &DocPath, rules: &[MatchingRule]) -> Self {
+    MismatchLocation {
+      path: path.clone(),
+      rules: rules.to_vec()
+    }
+  }
+}
+
+impl PartialEq for MismatchLocation {
+  fn eq(&self, other: &Self) -> bool {
+    self.path == other.path && self.rules == other.rules
+  }
+}
+
+impl Eq for MismatchLocation {}
+
+impl PartialOrd for MismatchLocation {
+  fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+    Some(self.cmp(other))
+  }
+}
+
+impl Ord for MismatchLocation {
+  fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+    self.path.cmp(&other.path)
+      .then_with(|| {
+        let rules = self.rules.iter().map(|rule| rule.to_json().to_string()).collect_vec();
+        let other_rules = other.rules.iter().map(|rule| rule.to_json().to_string()).collect_vec();
+        rules.cmp(&other_rules)
+      })
+  }
+}
+
 /// Store common mismatch information so it can be converted to different type of mismatches
 #[derive(Debug, Clone, PartialOrd, Ord, Eq)]
 pub struct CommonMismatch {
@@ -771,7 +1242,9 @@ pub struct CommonMismatch {
   /// actual value (as a string)
   actual: String,
   /// Description of the mismatch
-  description: String
+  description: String,
+  /// Structured path and matching rules for mismatches with bodies
+  location: Option<MismatchLocation>
 }
 
 impl CommonMismatch {
@@ -781,10 +1254,17 @@ impl CommonMismatch {
       path: self.path.clone(),
       expected: Some(self.expected.clone().into()),
       actual: Some(self.actual.clone().into()),
-      mismatch: self.description.clone()
+      mismatch: self.description.clone(),
+      location: self.location.clone()
     }
   }
 
+  /// Records where the mismatch occurred and the matching rules that were applied there
+  pub(crate) fn with_location(mut self, path: &DocPath, rules: &[MatchingRule]) -> Self {
+    self.location = Some(MismatchLocation::new(path, rules));
+    self
+  }
+
   /// Convert common mismatch to query mismatch
   pub fn to_query_mismatch(&self) -> Mismatch {
     Mismatch::QueryMismatch {
@@ -804,6 +1284,16 @@ impl CommonMismatch {
       mismatch: self.description.clone()
     }
   }
+
+  /// Convert common mismatch to trailer mismatch
+  pub fn to_trailer_mismatch(&self) -> Mismatch {
+    Mismatch::TrailerMismatch {
+      key: self.path.clone(),
+      expected: self.expected.clone(),
+      actual: self.actual.clone(),
+      mismatch: self.description.clone()
+    }
+  }
 }
 
 impl Display for CommonMismatch {
@@ -825,49 +1315,64 @@ impl From<Mismatch> for CommonMismatch {
         path: "".to_string(),
         expected: expected.clone(),
         actual: actual.clone(),
-        description: "Method mismatch".to_string()
+        description: "Method mismatch".to_string(),
+        location: None
       },
       Mismatch::PathMismatch { expected, actual, mismatch } => CommonMismatch {
         path: "".to_string(),
         expected: expected.clone(),
         actual: actual.clone(),
-        description: mismatch.clone()
+        description: mismatch.clone(),
+        location: None
       },
       Mismatch::StatusMismatch { expected, actual, mismatch } => CommonMismatch {
         path: "".to_string(),
         expected: expected.to_string(),
         actual: actual.to_string(),
-        description: mismatch.clone()
+        description: mismatch.clone(),
+        location: None
       },
       Mismatch::QueryMismatch { parameter, expected, actual, mismatch } => CommonMismatch {
         path: parameter.clone(),
         expected: expected.clone(),
         actual: actual.clone(),
-        description: mismatch.clone()
+        description: mismatch.clone(),
+        location: None
       },
       Mismatch::HeaderMismatch { key, expected, actual, mismatch } => CommonMismatch {
         path: key.clone(),
         expected: expected.clone(),
         actual: actual.clone(),
-        description: mismatch.clone()
+        description: mismatch.clone(),
+        location: None
       },
       Mismatch::BodyTypeMismatch { expected, actual, mismatch, .. } => CommonMismatch {
         path: "".to_string(),
         expected: expected.clone(),
         actual: actual.clone(),
-        description: mismatch.clone()
+        description: mismatch.clone(),
+        location: None
       },
-      Mismatch::BodyMismatch { path, expected, actual, mismatch } => CommonMismatch {
+      Mismatch::BodyMismatch { path, expected, actual, mismatch, location } => CommonMismatch {
         path: path.clone(),
         expected: from_utf8_lossy(expected.unwrap_or_default().as_ref()).to_string(),
         actual: from_utf8_lossy(actual.unwrap_or_default().as_ref()).to_string(),
-        description: mismatch.clone()
+        description: mismatch.clone(),
+        location
       },
       Mismatch::MetadataMismatch { key, expected, actual, mismatch } => CommonMismatch {
         path: key.clone(),
         expected: expected.clone(),
         actual: actual.clone(),
-        description: mismatch.clone()
+        description: mismatch.clone(),
+        location: None
+      },
+      Mismatch::TrailerMismatch { key, expected, actual, mismatch } => CommonMismatch {
+        path: key.clone(),
+        expected: expected.clone(),
+        actual: actual.clone(),
+        description: mismatch.clone(),
+        location: None
       }
     }
   }
@@ -945,7 +1450,10 @@ pub enum Mismatch {
       /// actual value
       actual: Option<Bytes>,
       /// description of the mismatch
-      mismatch: String
+      mismatch: String,
+      /// Structured path to where the mismatch occurred and the matching rules that were applied.
+      /// Will be `None` if the mismatch was not created by comparing the contents of a body.
+      location: Option<MismatchLocation>
     },
     /// Message metadata mismatch
     MetadataMismatch {
@@ -957,6 +1465,17 @@ pub enum Mismatch {
       actual: String,
       /// description of the mismatch
       mismatch: String
+    },
+    /// HTTP trailer mismatch
+    TrailerMismatch {
+      /// trailer key
+      key: String,
+      /// expected value
+      expected: String,
+      /// actual value
+      actual: String,
+      /// description of the mismatch
+      mismatch: String
     }
 }
 
@@ -1029,7 +1548,7 @@ impl Mismatch {
           }
         })
       },
-      Mismatch::BodyMismatch { path, expected, actual, mismatch } => {
+      Mismatch::BodyMismatch { path, expected, actual, mismatch, .. } => {
         json!({
           "type" : "BodyMismatch",
           "path" : path,
@@ -1053,6 +1572,15 @@ impl Mismatch {
           "mismatch" : mismatch
         })
       }
+      Mismatch::TrailerMismatch { key, expected, actual, mismatch } => {
+        json!({
+          "type" : "TrailerMismatch",
+          "key" : key,
+          "expected" : expected,
+          "actual" : actual,
+          "mismatch" : mismatch
+        })
+      }
     }
   }
 
@@ -1066,7 +1594,8 @@ impl Mismatch {
         Mismatch::HeaderMismatch { .. } => "HeaderMismatch",
         Mismatch::BodyTypeMismatch { .. } => "BodyTypeMismatch",
         Mismatch::BodyMismatch { .. } => "BodyMismatch",
-        Mismatch::MetadataMismatch { .. } => "MetadataMismatch"
+        Mismatch::MetadataMismatch { .. } => "MetadataMismatch",
+        Mismatch::TrailerMismatch { .. } => "TrailerMismatch"
       }
     }
 
@@ -1080,7 +1609,8 @@ impl Mismatch {
         Mismatch::HeaderMismatch { ref key, expected: ref e, .. } => format!("includes header '{}' with value '{}'", key, e),
         Mismatch::BodyTypeMismatch { .. } => "has a matching body".to_string(),
         Mismatch::BodyMismatch { .. } => "has a matching body".to_string(),
-        Mismatch::MetadataMismatch { .. } => "has matching metadata".to_string()
+        Mismatch::MetadataMismatch { .. } => "has matching metadata".to_string(),
+        Mismatch::TrailerMismatch { ref key, expected: ref e, .. } => format!("includes trailer '{}' with value '{}'", key, e)
       }
     }
 
@@ -1095,7 +1625,8 @@ impl Mismatch {
         Mismatch::BodyTypeMismatch {  expected: e, actual: a, .. } =>
           format!("Expected a body of '{}' but the actual content type was '{}'", e, a),
         Mismatch::BodyMismatch { path, mismatch, .. } => format!("{} -> {}", path, mismatch),
-        Mismatch::MetadataMismatch { mismatch, .. } => mismatch.clone()
+        Mismatch::MetadataMismatch { mismatch, .. } => mismatch.clone(),
+        Mismatch::TrailerMismatch { mismatch, .. } => mismatch.clone()
       }
     }
 
@@ -1113,6 +1644,8 @@ impl Mismatch {
           format!("expected a body of '{}' but the actual content type was '{}'", Red.paint(e.clone()), Green.paint(a.clone())),
         Mismatch::BodyMismatch { path, mismatch, .. } => format!("{} -> {}", Style::new().bold().paint(path.clone()), mismatch),
         Mismatch::MetadataMismatch { expected: e, actual: a, key: k, .. } => format!("Expected message metadata '{}' to have value '{}' but was '{}'",
+          Style::new().bold().paint(k.clone()), Red.paint(e.to_string()), Green.paint(a.to_string())),
+        Mismatch::TrailerMismatch { expected: e, actual: a, key: k, .. } => format!("Expected trailer '{}' to have value '{}' but was '{}'",
           Style::new().bold().paint(k.clone()), Red.paint(e.to_string()), Green.paint(a.to_string()))
       }
     }
@@ -1153,6 +1686,10 @@ impl PartialEq for Mismatch {
         Mismatch::MetadataMismatch { key: p2, expected: e2, actual: a2, .. }) => {
         p1 == p2 && e1 == e2 && a1 == a2
       },
+      (Mismatch::TrailerMismatch { key: p1, expected: e1, actual: a1, .. },
+        Mismatch::TrailerMismatch { key: p2, expected: e2, actual: a2, .. }) => {
+        p1 == p2 && e1 == e2 && a1 == a2
+      },
       (_, _) => false
     }
   }
@@ -1241,7 +1778,9 @@ pub struct RequestMatchResult {
   /// Query parameter result
   pub query: HashMap<String, Vec<Mismatch>>,
   /// Headers result
-  pub headers: HashMap<String, Vec<Mismatch>>
+  pub headers: HashMap<String, Vec<Mismatch>>,
+  /// Trailers result
+  pub trailers: HashMap<String, Vec<Mismatch>>
 }
 
 impl RequestMatchResult {
@@ -1262,6 +1801,9 @@ impl RequestMatchResult {
       m.extend_from_slice(mismatches.as_slice());
     }
     m.extend_from_slice(self.body.mismatches().as_slice());
+    for mismatches in self.trailers.values() {
+      m.extend_from_slice(mismatches.as_slice());
+    }
 
     m
   }
@@ -1286,7 +1828,7 @@ impl RequestMatchResult {
         score -= 1;
       }
     }
-    for mismatches in self.headers.values() {
+    for mismatches in self.headers.values().chain(self.trailers.values()) {
       if mismatches.is_empty() {
         score += 1;
       } else {
@@ -1316,7 +1858,8 @@ impl RequestMatchResult {
     self.method.is_none() && self.path.is_none() &&
       self.query.values().all(|m| m.is_empty()) &&
       self.headers.values().all(|m| m.is_empty()) &&
-      self.body.all_matched()
+      self.body.all_matched() &&
+      self.trailers.values().all(|m| m.is_empty())
   }
 
   /// If there was a mismatch with the method or path
@@ -1347,7 +1890,8 @@ pub fn match_text(expected: &Option<Bytes>, actual: &Option<Bytes>, context: &dy
           path: "$".to_string(),
           expected: expected.clone(),
           actual: actual.clone(),
-          mismatch: format!("Could not parse expected value as UTF-8 text: {}", err)
+          mismatch: format!("Could not parse expected value as UTF-8 text: {}", err),
+          location: Some(MismatchLocation::new(&path, &[]))
         });
         ""
       }
@@ -1359,18 +1903,21 @@ pub fn match_text(expected: &Option<Bytes>, actual: &Option<Bytes>, context: &dy
           path: "$".to_string(),
           expected: expected.clone(),
           actual: actual.clone(),
-          mismatch: format!("Could not parse actual value as UTF-8 text: {}", err)
+          mismatch: format!("Could not parse actual value as UTF-8 text: {}", err),
+          location: Some(MismatchLocation::new(&path, &[]))
         });
         ""
       }
     };
-    if let Err(messages) = match_values(&path, &context.select_best_matcher(&path), expected_str, actual_str) {
+    let rule_list = context.select_best_matcher(&path);
+    if let Err(messages) = match_values_with_context(&path, &rule_list, expected_str, actual_str, context) {
       for message in messages {
         mismatches.push(Mismatch::BodyMismatch {
           path: "$".to_string(),
           expected: expected.clone(),
           actual: actual.clone(),
-          mismatch: message.clone()
+          mismatch: message.clone(),
+          location: Some(MismatchLocation::new(&path, &rule_list.rules))
         })
       }
     };
@@ -1390,7 +1937,8 @@ pub fn match_text(expected: &Option<Bytes>, actual: &Option<Bytes>, context: &dy
         path: "$".to_string(),
         expected: Some(expected.clone()),
         actual: Some(actual.clone()),
-        mismatch
+        mismatch,
+        location: Some(MismatchLocation::new(&path, &[]))
       }
     ])
   } else {
@@ -1411,7 +1959,7 @@ pub fn match_method(expected: &str, actual: &str) -> Result<(), Mismatch> {
 pub fn match_path(expected: &str, actual: &str, context: &(dyn MatchingContext + Send + Sync)) -> Result<(), Vec<Mismatch>> {
   let path = DocPath::empty();
   let matcher_result = if context.matcher_is_defined(&path) {
-    match_values(&path, &context.select_best_matcher(&path), expected.to_string(), actual.to_string())
+    match_values_with_context(&path, &context.select_best_matcher(&path), expected.to_string(), actual.to_string(), context)
   } else {
     expected.matches_with(actual, &MatchingRule::Equality, false).map_err(|err| vec![err])
       .map_err(|errors| errors.iter().map(|err| err.to_string()).collect())
@@ -1516,7 +2064,8 @@ pub(crate) async fn compare_bodies(
                   path: mismatch.path.clone(),
                   expected: Some(Bytes::from(mismatch.expected)),
                   actual: Some(Bytes::from(mismatch.actual)),
-                  mismatch: mismatch.mismatch.clone()
+                  mismatch: mismatch.mismatch.clone(),
+                  location: None
                 });
               }
             }
@@ -1589,15 +2138,15 @@ async fn match_body_content(
   let actual_body = actual.body();
   match (expected_body, actual_body) {
     (&OptionalBody::Missing, _) => BodyMatchResult::Ok,
-    (&OptionalBody::Null, &OptionalBody::Present(ref b, _, _)) => {
-      BodyMatchResult::BodyMismatches(hashmap!{ "$".into() => vec![Mismatch::BodyMismatch { expected: None, actual: Some(b.clone()),
+    (&OptionalBody::Null, &OptionalBody::Present(..) | &OptionalBody::File(..)) => {
+      BodyMatchResult::BodyMismatches(hashmap!{ "$".into() => vec![Mismatch::BodyMismatch { expected: None, actual: actual_body.value(),
         mismatch: format!("Expected empty body but received {}", actual_body),
-        path: s!("/")}]})
+        path: s!("/"), location: None}]})
     },
-    (&OptionalBody::Empty, &OptionalBody::Present(ref b, _, _)) => {
-      BodyMatchResult::BodyMismatches(hashmap!{ "$".into() => vec![Mismatch::BodyMismatch { expected: None, actual: Some(b.clone()),
+    (&OptionalBody::Empty, &OptionalBody::Present(..) | &OptionalBody::File(..)) => {
+      BodyMatchResult::BodyMismatches(hashmap!{ "$".into() => vec![Mismatch::BodyMismatch { expected: None, actual: actual_body.value(),
         mismatch: format!("Expected empty body but received {}", actual_body),
-        path: s!("/")}]})
+        path: s!("/"), location: None}]})
     },
     (&OptionalBody::Null, _) => BodyMatchResult::Ok,
     (&OptionalBody::Empty, _) => BodyMatchResult::Ok,
@@ -1606,14 +2155,16 @@ async fn match_body_content(
         expected: e.value(),
         actual: None,
         mismatch: format!("Expected body {} but was missing", e),
-        path: s!("/")}]})
+        path: s!("/"),
+        location: None}]})
     },
     (e, &OptionalBody::Empty) => {
       BodyMatchResult::BodyMismatches(hashmap!{ "$".into() => vec![Mismatch::BodyMismatch {
         expected: e.value(),
         actual: None,
         mismatch: format!("Expected body {} but was empty", e),
-        path: s!("/")}]})
+        path: s!("/"),
+        location: None}]})
     },
     (_, _) => compare_bodies(content_type, expected, actual, context).await
   }
@@ -1639,6 +2190,11 @@ pub async fn match_body(
       match_header_value("Content-Type", 0, expected_content_type.to_string().as_str(),
                          actual_content_type.to_string().as_str(), header_context, true
       ).is_ok()) {
+    let normalise_line_endings = context.normalise_line_endings();
+    let expected_normalised = charsets::with_normalised_body(expected, &expected_content_type, normalise_line_endings);
+    let actual_normalised = charsets::with_normalised_body(actual, &actual_content_type, normalise_line_endings);
+    let expected = expected_normalised.as_ref().map(|part| part as &(dyn HttpPart + Send + Sync)).unwrap_or(expected);
+    let actual = actual_normalised.as_ref().map(|part| part as &(dyn HttpPart + Send + Sync)).unwrap_or(actual);
     match_body_content(&expected_content_type, expected, actual, context).await
   } else if expected.body().is_present() {
     BodyMatchResult::BodyTypeMismatch {
@@ -1655,12 +2211,23 @@ pub async fn match_body(
 }
 
 /// Matches the expected and actual requests
-#[allow(unused_variables)]
 pub async fn match_request<'a>(
   expected: HttpRequest,
   actual: HttpRequest,
   pact: &Box<dyn Pact + Send + Sync + RefUnwindSafe + 'a>,
   interaction: &Box<dyn Interaction + Send + Sync + RefUnwindSafe>
+) -> RequestMatchResult {
+  match_request_with_options(expected, actual, pact, interaction, &MatchingOptions::default()).await
+}
+
+/// Matches the expected and actual requests, using the options to configure the matching
+#[allow(unused_variables)]
+pub async fn match_request_with_options<'a>(
+  expected: HttpRequest,
+  actual: HttpRequest,
+  pact: &Box<dyn Pact + Send + Sync + RefUnwindSafe + 'a>,
+  interaction: &Box<dyn Interaction + Send + Sync + RefUnwindSafe>,
+  options: &MatchingOptions
 ) -> RequestMatchResult {
   debug!("comparing to expected {}", expected);
   debug!("     body: '{}'", expected.body.display_string());
@@ -1676,25 +2243,32 @@ pub async fn match_request<'a>(
 
   let path_context = CoreMatchingContext::new(DiffConfig::NoUnexpectedKeys,
     &expected.matching_rules.rules_for_category("path").unwrap_or_default(),
-    &plugin_data);
+    &plugin_data).with_options(options);
   let body_context = CoreMatchingContext::new(DiffConfig::NoUnexpectedKeys,
     &expected.matching_rules.rules_for_category("body").unwrap_or_default(),
-    &plugin_data);
+    &plugin_data).with_options(options);
   let query_context = CoreMatchingContext::new(DiffConfig::NoUnexpectedKeys,
     &expected.matching_rules.rules_for_category("query").unwrap_or_default(),
-    &plugin_data);
+    &plugin_data).with_options(options);
   let header_context = HeaderMatchingContext::new(
     &CoreMatchingContext::new(DiffConfig::NoUnexpectedKeys,
      &expected.matching_rules.rules_for_category("header").unwrap_or_default(),
      &plugin_data
-    )
+    ).with_options(options)
+  );
+  let trailer_context = HeaderMatchingContext::new(
+    &CoreMatchingContext::new(DiffConfig::NoUnexpectedKeys,
+     &expected.matching_rules.rules_for_category("trailer").unwrap_or_default(),
+     &plugin_data
+    ).with_options(options)
   );
   let result = RequestMatchResult {
     method: match_method(&expected.method, &actual.method).err(),
     path: match_path(&expected.path, &actual.path, &path_context).err(),
     body: match_body(&expected, &actual, &body_context, &header_context).await,
     query: match_query(expected.query, actual.query, &query_context),
-    headers: match_headers(expected.headers, actual.headers, &header_context)
+    headers: match_headers(expected.headers, actual.headers, &header_context),
+    trailers: match_trailers(expected.trailers, actual.trailers, &trailer_context)
   };
 
   debug!("--> Mismatches: {:?}", result.mismatches());
@@ -1728,12 +2302,23 @@ pub fn match_status(expected: u16, actual: u16, context: &dyn MatchingContext) -
 }
 
 /// Matches the actual and expected responses.
-#[allow(unused_variables)]
 pub async fn match_response<'a>(
   expected: HttpResponse,
   actual: HttpResponse,
   pact: &Box<dyn Pact + Send + Sync + RefUnwindSafe + 'a>,
   interaction: &Box<dyn Interaction + Send + Sync + RefUnwindSafe>
+) -> Vec<Mismatch> {
+  match_response_with_options(expected, actual, pact, interaction, &MatchingOptions::default()).await
+}
+
+/// Matches the actual and expected responses, using the options to configure the matching
+#[allow(unused_variables)]
+pub async fn match_response_with_options<'a>(
+  expected: HttpResponse,
+  actual: HttpResponse,
+  pact: &Box<dyn Pact + Send + Sync + RefUnwindSafe + 'a>,
+  interaction: &Box<dyn Interaction + Send + Sync + RefUnwindSafe>,
+  options: &MatchingOptions
 ) -> Vec<Mismatch> {
   let mut mismatches = vec![];
 
@@ -1747,15 +2332,21 @@ pub async fn match_response<'a>(
 
   let status_context = CoreMatchingContext::new(DiffConfig::AllowUnexpectedKeys,
     &expected.matching_rules.rules_for_category("status").unwrap_or_default(),
-    &plugin_data);
+    &plugin_data).with_options(options);
   let body_context = CoreMatchingContext::new(DiffConfig::AllowUnexpectedKeys,
     &expected.matching_rules.rules_for_category("body").unwrap_or_default(),
-    &plugin_data);
+    &plugin_data).with_options(options);
   let header_context = HeaderMatchingContext::new(
     &CoreMatchingContext::new(DiffConfig::NoUnexpectedKeys,
       &expected.matching_rules.rules_for_category("header").unwrap_or_default(),
       &plugin_data
-    )
+    ).with_options(options)
+  );
+  let trailer_context = HeaderMatchingContext::new(
+    &CoreMatchingContext::new(DiffConfig::NoUnexpectedKeys,
+      &expected.matching_rules.rules_for_category("trailer").unwrap_or_default(),
+      &plugin_data
+    ).with_options(options)
   );
 
   mismatches.extend_from_slice(match_body(&expected, &actual, &body_context, &header_context).await
@@ -1765,6 +2356,10 @@ pub async fn match_response<'a>(
   }
   let result = match_headers(expected.headers, actual.headers,
                              &header_context);
+  for values in result.values() {
+    mismatches.extend_from_slice(values.as_slice());
+  }
+  let result = match_trailers(expected.trailers, actual.trailers, &trailer_context);
   for values in result.values() {
     mismatches.extend_from_slice(values.as_slice());
   }
@@ -1875,11 +2470,11 @@ fn match_metadata_value(
   debug!("Comparing metadata values for key '{}'", key);
   let path = DocPath::root().join(key);
   let matcher_result = if context.matcher_is_defined(&path) {
-    match_values(&path, &context.select_best_matcher(&path), expected, actual)
+    match_values_with_context(&path, &context.select_best_matcher(&path), expected, actual, context)
   } else if key.to_ascii_lowercase() == "contenttype" || key.to_ascii_lowercase() == "content-type" {
     debug!("Comparing message context type '{}' => '{}'", expected, actual);
     headers::match_parameter_header(expected.as_str().unwrap_or_default(), actual.as_str().unwrap_or_default(),
-      key, "metadata", 0, true)
+      key, "metadata", 0, true, &path, context)
   } else {
     expected.matches_with(actual, &MatchingRule::Equality, false).map_err(|err| vec![err.to_string()])
   };
@@ -1896,11 +2491,21 @@ fn match_metadata_value(
 }
 
 /// Matches the actual and expected messages.
-#[allow(unused_variables)]
 pub async fn match_message<'a>(
   expected: &Box<dyn Interaction + Send + Sync + RefUnwindSafe>,
   actual: &Box<dyn Interaction + Send + Sync + RefUnwindSafe>,
   pact: &Box<dyn Pact + Send + Sync + RefUnwindSafe + 'a>) -> Vec<Mismatch> {
+  match_message_with_options(expected, actual, pact, &MatchingOptions::default()).await
+}
+
+/// Matches the actual and expected messages, using the options to configure the matching
+#[allow(unused_variables)]
+pub async fn match_message_with_options<'a>(
+  expected: &Box<dyn Interaction + Send + Sync + RefUnwindSafe>,
+  actual: &Box<dyn Interaction + Send + Sync + RefUnwindSafe>,
+  pact: &Box<dyn Pact + Send + Sync + RefUnwindSafe + 'a>,
+  options: &MatchingOptions
+) -> Vec<Mismatch> {
   let mut mismatches = vec![];
 
   if expected.is_message() && actual.is_message() {
@@ -1920,17 +2525,18 @@ pub async fn match_message<'a>(
         matchers: matching_rules.rules_for_category("content").unwrap_or_default(),
         config: DiffConfig::AllowUnexpectedKeys,
         matching_spec: PactSpecification::V4,
-        plugin_configuration: plugin_data.clone()
-      }
+        plugin_configuration: plugin_data.clone(),
+        .. CoreMatchingContext::default()
+      }.with_options(options)
     } else {
       CoreMatchingContext::new(DiffConfig::AllowUnexpectedKeys,
                            &matching_rules.rules_for_category("body").unwrap_or_default(),
-                           &plugin_data)
+                           &plugin_data).with_options(options)
     };
 
     let metadata_context = CoreMatchingContext::new(DiffConfig::AllowUnexpectedKeys,
                                                 &matching_rules.rules_for_category("metadata").unwrap_or_default(),
-                                                &plugin_data);
+                                                &plugin_data).with_options(options);
     let contents = match_message_contents(&expected_message.as_message_content(), &actual_message.as_message_content(), &body_context).await;
 
     mismatches.extend_from_slice(contents.err().unwrap_or_default().as_slice());
@@ -1952,18 +2558,40 @@ pub async fn match_message<'a>(
 
 /// Matches synchronous request/response messages
 pub async fn match_sync_message<'a>(expected: SynchronousMessage, actual: SynchronousMessage, pact: &Box<dyn Pact + Send + Sync + RefUnwindSafe + 'a>) -> Vec<Mismatch> {
-  let mut mismatches = match_sync_message_request(&expected, &actual, pact).await;
-  let response_result = match_sync_message_response(&expected, &expected.response, &actual.response, pact).await;
+  match_sync_message_with_options(expected, actual, pact, &MatchingOptions::default()).await
+}
+
+/// Matches synchronous request/response messages, using the options to configure the matching
+pub async fn match_sync_message_with_options<'a>(
+  expected: SynchronousMessage,
+  actual: SynchronousMessage,
+  pact: &Box<dyn Pact + Send + Sync + RefUnwindSafe + 'a>,
+  options: &MatchingOptions
+) -> Vec<Mismatch> {
+  let mut mismatches = match_sync_message_request_with_options(&expected, &actual, pact, options).await;
+  let response_result = match_sync_message_response_with_options(&expected, &expected.response,
+    &actual.response, pact, options).await;
   mismatches.extend_from_slice(&*response_result);
   mismatches
 }
 
 /// Match the request part of a synchronous request/response message
-#[allow(unused_variables)]
 pub async fn match_sync_message_request<'a>(
   expected: &SynchronousMessage,
   actual: &SynchronousMessage,
   pact: &Box<dyn Pact + Send + Sync + RefUnwindSafe + 'a>
+) -> Vec<Mismatch> {
+  match_sync_message_request_with_options(expected, actual, pact, &MatchingOptions::default()).await
+}
+
+/// Match the request part of a synchronous request/response message, using the options to
+/// configure the matching
+#[allow(unused_variables)]
+pub async fn match_sync_message_request_with_options<'a>(
+  expected: &SynchronousMessage,
+  actual: &SynchronousMessage,
+  pact: &Box<dyn Pact + Send + Sync + RefUnwindSafe + 'a>,
+  options: &MatchingOptions
 ) -> Vec<Mismatch> {
   debug!("comparing to expected message request: {:?}", expected);
 
@@ -1978,12 +2606,13 @@ pub async fn match_sync_message_request<'a>(
     matchers: matching_rules.rules_for_category("content").unwrap_or_default(),
     config: DiffConfig::AllowUnexpectedKeys,
     matching_spec: PactSpecification::V4,
-    plugin_configuration: plugin_data.clone()
-  };
+    plugin_configuration: plugin_data.clone(),
+    .. CoreMatchingContext::default()
+  }.with_options(options);
 
   let metadata_context = CoreMatchingContext::new(DiffConfig::AllowUnexpectedKeys,
                                               &matching_rules.rules_for_category("metadata").unwrap_or_default(),
-                                              &plugin_data);
+                                              &plugin_data).with_options(options);
   let contents = match_message_contents(&expected.request, &actual.request, &body_context).await;
 
   let mut mismatches = vec![];
@@ -1995,12 +2624,25 @@ pub async fn match_sync_message_request<'a>(
 }
 
 /// Match the response part of a synchronous request/response message
-#[allow(unused_variables)]
 pub async fn match_sync_message_response<'a>(
   expected: &SynchronousMessage,
   expected_responses: &[MessageContents],
   actual_responses: &[MessageContents],
   pact: &Box<dyn Pact + Send + Sync + RefUnwindSafe + 'a>
+) -> Vec<Mismatch> {
+  match_sync_message_response_with_options(expected, expected_responses, actual_responses, pact,
+    &MatchingOptions::default()).await
+}
+
+/// Match the response part of a synchronous request/response message, using the options to
+/// configure the matching
+#[allow(unused_variables)]
+pub async fn match_sync_message_response_with_options<'a>(
+  expected: &SynchronousMessage,
+  expected_responses: &[MessageContents],
+  actual_responses: &[MessageContents],
+  pact: &Box<dyn Pact + Send + Sync + RefUnwindSafe + 'a>,
+  options: &MatchingOptions
 ) -> Vec<Mismatch> {
   debug!("comparing to expected message responses: {:?}", expected_responses);
 
@@ -2037,12 +2679,13 @@ pub async fn match_sync_message_response<'a>(
         matchers: matching_rules.rules_for_category("content").unwrap_or_default(),
         config: DiffConfig::AllowUnexpectedKeys,
         matching_spec: PactSpecification::V4,
-        plugin_configuration: plugin_data.clone()
-      };
+        plugin_configuration: plugin_data.clone(),
+        .. CoreMatchingContext::default()
+      }.with_options(options);
 
       let metadata_context = CoreMatchingContext::new(DiffConfig::AllowUnexpectedKeys,
                                                   &matching_rules.rules_for_category("metadata").unwrap_or_default(),
-                                                  &plugin_data);
+                                                  &plugin_data).with_options(options);
       let contents = match_message_contents(expected_response, actual_response, &body_context).await;
 
       mismatches.extend_from_slice(contents.err().unwrap_or_default().as_slice());
@@ -0,0 +1,188 @@
+//! Limits that are applied when matching bodies, to protect against pathological bodies that are
+//! very deeply nested or contain very large collections.
+//!
+//! When a limit is reached, the values below that point are not compared and a truncation mismatch
+//! is reported instead, so the match fails with a mismatch rather than exhausting the stack or
+//! taking an unbounded amount of time. There are no limits by default. They can be enabled with the
+//! `PACT_MATCHING_MAX_DEPTH` and `PACT_MATCHING_MAX_ITEMS` environment variables, or set on the
+//! matching context with `CoreMatchingContext::with_limits`.
+
+use std::sync::OnceLock;
+
+use pact_models::path_exp::DocPath;
+
+use crate::CommonMismatch;
+
+/// Environment variable that sets the maximum depth of nested values that will be compared
+pub const MAX_DEPTH_ENV_VAR: &str = "PACT_MATCHING_MAX_DEPTH";
+/// Environment variable that sets the maximum number of items in a collection that will be compared
+pub const MAX_ITEMS_ENV_VAR: &str = "PACT_MATCHING_MAX_ITEMS";
+/// Limits applied when matching bodies
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MatchingLimits {
+  /// Maximum depth of nested values that will be compared. The root of the body has a depth of 0.
+  /// There is no maximum if this is not set.
+  pub max_depth: Option<usize>,
+  /// Maximum number of items in a collection (i.e. a list, map or child elements) that will be
+  /// compared. There is no maximum if this is not set.
+  pub max_items: Option<usize>
+}
+
+impl MatchingLimits {
+  /// Creates a new set of limits
+  pub fn new(max_depth: usize, max_items: usize) -> Self {
+    MatchingLimits { max_depth: Some(max_depth), max_items: Some(max_items) }
+  }
+
+  /// Limits that will never be reached
+  pub fn unlimited() -> Self {
+    MatchingLimits { max_depth: None, max_items: None }
+  }
+
+  /// If the path is nested deeper than the maximum depth
+  pub fn depth_exceeded(&self, path: &DocPath) -> bool {
+    self.max_depth.is_some_and(|max_depth| path.len().saturating_sub(1) > max_depth)
+  }
+
+  /// If the collections have more items than the maximum number of items
+  pub fn items_exceeded(&self, expected_len: usize, actual_len: usize) -> bool {
+    self.max_items.is_some_and(|max_items| expected_len > max_items || actual_len > max_items)
+  }
+
+  /// Number of items of a collection that will be compared
+  pub fn item_limit(&self) -> usize {
+    self.max_items.unwrap_or(usize::MAX)
+  }
+
+  /// Mismatch to report when matching stopped at a path because the maximum depth was exceeded
+  pub fn depth_mismatch(&self, path: &DocPath, expected: String, actual: String) -> CommonMismatch {
+    CommonMismatch {
+      path: path.to_string(),
+      expected,
+      actual,
+      description: format!("Matching stopped at {} as the body is nested more than {} levels deep",
+        path, self.max_depth.unwrap_or_default()),
+      location: None
+    }
+  }
+
+  /// Mismatch to report when only some of the items of the collections were compared because the
+  /// maximum number of items was exceeded
+  pub fn items_mismatch(&self, path: &DocPath, expected_len: usize, actual_len: usize) -> CommonMismatch {
+    CommonMismatch {
+      path: path.to_string(),
+      expected: format!("{} items", expected_len),
+      actual: format!("{} items", actual_len),
+      description: format!("Only the first {} items were compared, as the collection has more than {} items (expected {}, actual {})",
+        self.item_limit(), self.item_limit(), expected_len, actual_len),
+      location: None
+    }
+  }
+}
+
+impl Default for MatchingLimits {
+  /// Returns the limits set with the environment variables (which are only read once). If the
+  /// environment variables are not set, there are no limits.
+  fn default() -> Self {
+    static LIMITS: OnceLock<MatchingLimits> = OnceLock::new();
+    *LIMITS.get_or_init(|| {
+      let env_limit = |name: &str| std::env::var(name).ok().and_then(|limit| limit.parse().ok());
+      MatchingLimits {
+        max_depth: env_limit(MAX_DEPTH_ENV_VAR),
+        max_items: env_limit(MAX_ITEMS_ENV_VAR)
+      }
+    })
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use expectest::prelude::*;
+  use serde_json::{json, Value};
+
+  use crate::{CommonMismatch, CoreMatchingContext};
+  use crate::json::compare_json;
+
+  use super::*;
+
+  fn nested(depth: usize) -> Value {
+    (0..depth).fold(json!(1), |value, _| json!({ "a": value }))
+  }
+
+  fn descriptions(result: Result<(), Vec<CommonMismatch>>) -> Vec<String> {
+    result.unwrap_err().iter().map(|mismatch| format!("{} -> {}", mismatch.path, mismatch.description)).collect()
+  }
+
+  #[test]
+  fn depth_exceeded_test() {
+    let limits = MatchingLimits::new(2, 10);
+    expect!(limits.depth_exceeded(&DocPath::root())).to(be_false());
+    expect!(limits.depth_exceeded(&DocPath::new_unwrap("$.a.b"))).to(be_false());
+    expect!(limits.depth_exceeded(&DocPath::new_unwrap("$.a.b.c"))).to(be_true());
+    expect!(MatchingLimits::unlimited().depth_exceeded(&DocPath::new_unwrap("$.a.b.c"))).to(be_false());
+  }
+
+  #[test]
+  fn items_exceeded_test() {
+    let limits = MatchingLimits::new(10, 2);
+    expect!(limits.items_exceeded(2, 2)).to(be_false());
+    expect!(limits.items_exceeded(1, 3)).to(be_true());
+    expect!(MatchingLimits::unlimited().items_exceeded(100_000, 1_000_000)).to(be_false());
+  }
+
+  #[test]
+  fn compare_json_does_not_limit_bodies_without_limits() {
+    let context = CoreMatchingContext::default().with_limits(MatchingLimits::unlimited());
+    let items = (0..200_000).collect::<Vec<_>>();
+    expect!(compare_json(&DocPath::root(), &json!(items), &json!(items), &context)).to(be_ok());
+    expect!(compare_json(&DocPath::root(), &nested(150), &nested(150), &context)).to(be_ok());
+  }
+
+  #[test]
+  fn compare_json_stops_at_the_maximum_depth() {
+    let context = CoreMatchingContext::default().with_limits(MatchingLimits::new(3, 100));
+    expect!(compare_json(&DocPath::root(), &nested(3), &nested(3), &context)).to(be_ok());
+    expect!(descriptions(compare_json(&DocPath::root(), &nested(5), &nested(5), &context))).to(be_equal_to(vec![
+      "$.a.a.a.a -> Matching stopped at $.a.a.a.a as the body is nested more than 3 levels deep".to_string()
+    ]));
+
+    let context = CoreMatchingContext::default().with_limits(MatchingLimits::new(100, 100));
+    expect!(compare_json(&DocPath::root(), &nested(500), &nested(500), &context)).to(be_err());
+  }
+
+  #[test]
+  fn compare_json_only_compares_the_maximum_number_of_items() {
+    let context = CoreMatchingContext::default().with_limits(MatchingLimits::new(10, 3));
+    expect!(compare_json(&DocPath::root(), &json!([1, 2, 3]), &json!([1, 2, 3]), &context)).to(be_ok());
+    expect!(descriptions(compare_json(&DocPath::root(), &json!([1, 2, 3, 4]), &json!([1, 5, 3, 4, 5]), &context))).to(be_equal_to(vec![
+      "$[1] -> Expected 5 (Integer) to be equal to 2 (Integer)".to_string(),
+      "$ -> Only the first 3 items were compared, as the collection has more than 3 items (expected 4, actual 5)".to_string()
+    ]));
+    expect!(descriptions(compare_json(&DocPath::root(), &json!({"a": 1, "b": 2}),
+      &json!({"a": 1, "b": 2, "c": 3, "d": 4}), &context))).to(be_equal_to(vec![
+      "$ -> Only the first 3 items were compared, as the collection has more than 3 items (expected 2, actual 4)".to_string()
+    ]));
+  }
+
+  #[test]
+  #[cfg(feature = "xml")]
+  fn match_xml_stops_at_the_maximum_depth() {
+    use bytes::Bytes;
+    use pact_models::bodies::OptionalBody;
+    use pact_models::request::Request;
+
+    use crate::xml::match_xml;
+
+    let request = |body: &str| Request {
+      body: OptionalBody::Present(Bytes::from(body.to_string()), Some("application/xml".into()), None),
+      .. Request::default()
+    };
+    let expected = request("<a><b><c><d/></c></b></a>");
+    let actual = request("<a><b><c><e/></c></b></a>");
+    let context = CoreMatchingContext::default().with_limits(MatchingLimits::new(2, 100));
+    let result = match_xml(&expected, &actual, &context);
+    expect!(result.unwrap_err().iter().map(|mismatch| mismatch.description()).collect::<Vec<_>>()).to(be_equal_to(vec![
+      "$.a.b.c -> Matching stopped at $.a.b.c as the body is nested more than 2 levels deep".to_string()
+    ]));
+  }
+}
@@ -6,11 +6,10 @@ use anyhow::anyhow;
 use bytes::Bytes;
 #[cfg(feature = "plugins")] use lazy_static::lazy_static;
 #[cfg(feature = "plugins")] use maplit::hashmap;
-use onig::Regex;
 use pact_models::HttpStatus;
 use pact_models::matchingrules::{MatchingRule, RuleList, RuleLogic};
 use pact_models::path_exp::DocPath;
-#[cfg(feature = "datetime")] use pact_models::time_utils::validate_datetime;
+#[cfg(feature = "datetime")] use pact_models::time_utils::{parse_datetime, validate_datetime};
 #[cfg(feature = "plugins")]  use pact_plugin_driver::catalogue_manager::{
   CatalogueEntry,
   CatalogueEntryProviderType,
@@ -18,9 +17,12 @@ use pact_models::path_exp::DocPath;
   register_core_entries
 };
 use semver::Version;
+use serde_json::Value;
 use tracing::{debug, instrument, trace};
 
 use crate::binary_utils::match_content_type;
+use crate::custom_matchers::{CustomMatcherValue, match_custom};
+use crate::regex_engine::MatchingRegex;
 use crate::{MatchingContext, CommonMismatch};
 
 #[cfg(feature = "plugins")]
@@ -165,9 +167,9 @@ impl Matches<&str> for &str {
   fn matches_with(&self, actual: &str, matcher: &MatchingRule, cascaded: bool) -> anyhow::Result<()> {
     let result = match matcher {
       MatchingRule::Regex(regex) => {
-        match Regex::new(regex) {
+        match MatchingRegex::new(regex) {
           Ok(re) => {
-            if re.is_match(actual) {
+            if re.is_match(actual)? {
               Ok(())
             } else {
               Err(anyhow!("Expected '{}' to match '{}'", actual, regex))
@@ -176,13 +178,22 @@ impl Matches<&str> for &str {
           Err(err) => Err(anyhow!("'{}' is not a valid regular expression - {}", regex, err))
         }
       },
-      MatchingRule::Equality | MatchingRule::Values => {
+      MatchingRule::Equality | MatchingRule::Values |
+      MatchingRule::EqualsIgnoreOrder | MatchingRule::MinEqualsIgnoreOrder(_) |
+      MatchingRule::MaxEqualsIgnoreOrder(_) | MatchingRule::MinMaxEqualsIgnoreOrder(_, _) => {
         if self == &actual {
           Ok(())
         } else {
           Err(anyhow!("Expected '{}' to be equal to '{}'", actual, self))
         }
       },
+      MatchingRule::EqualsIgnoreCase => {
+        if self.to_lowercase() == actual.to_lowercase() {
+          Ok(())
+        } else {
+          Err(anyhow!("Expected '{}' to be equal to '{}' ignoring case", actual, self))
+        }
+      },
       MatchingRule::Type |
       MatchingRule::MinType(_) |
       MatchingRule::MaxType(_) |
@@ -277,7 +288,7 @@ impl Matches<&str> for &str {
         }
       }
       MatchingRule::NotEmpty => {
-        if actual.is_empty() {
+        if !cascaded && actual.is_empty() {
           Err(anyhow!("Expected '' (String) to not be empty"))
         } else {
           Ok(())
@@ -289,7 +300,21 @@ impl Matches<&str> for &str {
           Err(err) => Err(anyhow!("'{}' is not a valid semantic version - {}", actual, err))
         }
       }
+      MatchingRule::DecimalWithTolerance(tolerance) => {
+        match (self.parse::<f64>(), actual.parse::<f64>()) {
+          (Ok(expected), Ok(actual)) => match_with_tolerance(expected, actual, *tolerance),
+          (Err(_), _) => Err(anyhow!("Unable to match '{}' using {:?} as it is not a number", self, matcher)),
+          (_, Err(_)) => Err(anyhow!("Expected '{}' to match a number", actual))
+        }
+      }
+      MatchingRule::TimestampWithin(format, seconds, reference) =>
+        match_timestamp_within(actual, format, *seconds, reference),
+      MatchingRule::JsonSchema(schema) if !cascaded => {
+        let actual = serde_json::from_str(actual).unwrap_or_else(|_| Value::String(actual.to_string()));
+        match_json_schema(schema, &actual)
+      }
       MatchingRule::ContentType(content_type) => match_content_type(actual.as_bytes(), content_type),
+      MatchingRule::Custom(name, _) => Err(anyhow!("Custom matcher '{}' can only be applied using a matching context it has been registered with", name)),
       _ => if !cascaded || matcher.can_cascade() {
         Err(anyhow!("Unable to match '{}' using {:?}", self, matcher))
       } else {
@@ -314,9 +339,9 @@ impl Matches<u64> for &str {
     debug!("String -> u64: comparing '{}' to {} using {:?}", self, actual, matcher);
     match matcher {
       MatchingRule::Regex(regex) => {
-        match Regex::new(regex) {
+        match MatchingRegex::new(regex) {
           Ok(re) => {
-            if re.is_match(&actual.to_string()) {
+            if re.is_match(&actual.to_string())? {
               Ok(())
             } else {
               Err(anyhow!("Expected {} to match '{}'", actual, regex))
@@ -355,10 +380,11 @@ impl Matches<u64> for u64 {
   fn matches_with(&self, actual: u64, matcher: &MatchingRule, cascaded: bool) -> anyhow::Result<()> {
     debug!("u64 -> u64: comparing {} to {} using {:?}", self, actual, matcher);
     match matcher {
+      MatchingRule::DecimalWithTolerance(tolerance) => match_with_tolerance(*self as f64, actual as f64, *tolerance),
       MatchingRule::Regex(regex) => {
-        match Regex::new(regex) {
+        match MatchingRegex::new(regex) {
           Ok(re) => {
-            if re.is_match(&actual.to_string()) {
+            if re.is_match(&actual.to_string())? {
               Ok(())
             } else {
               Err(anyhow!("Expected {} to match '{}'", actual, regex))
@@ -402,10 +428,11 @@ impl Matches<f64> for u64 {
   fn matches_with(&self, actual: f64, matcher: &MatchingRule, cascaded: bool) -> anyhow::Result<()> {
     debug!("u64 -> f64: comparing {} to {} using {:?}", self, actual, matcher);
     match matcher {
+      MatchingRule::DecimalWithTolerance(tolerance) => match_with_tolerance(*self as f64, actual, *tolerance),
       MatchingRule::Regex(regex) => {
-        match Regex::new(regex) {
+        match MatchingRegex::new(regex) {
           Ok(re) => {
-            if re.is_match(&actual.to_string()) {
+            if re.is_match(&actual.to_string())? {
               Ok(())
             } else {
               Err(anyhow!("Expected {} to match '{}'", actual, regex))
@@ -443,10 +470,11 @@ impl Matches<f64> for f64 {
   #[instrument(level = "trace")]
   fn matches_with(&self, actual: f64, matcher: &MatchingRule, cascaded: bool) -> anyhow::Result<()> {
     let result = match matcher {
+      MatchingRule::DecimalWithTolerance(tolerance) => match_with_tolerance(*self, actual, *tolerance),
       MatchingRule::Regex(regex) => {
-        match Regex::new(regex) {
+        match MatchingRegex::new(regex) {
           Ok(re) => {
-            if re.is_match(&actual.to_string()) {
+            if re.is_match(&actual.to_string())? {
               Ok(())
             } else {
               Err(anyhow!("Expected {} to match '{}'", actual, regex))
@@ -491,10 +519,11 @@ impl Matches<u64> for f64 {
   fn matches_with(&self, actual: u64, matcher: &MatchingRule, cascaded: bool) -> anyhow::Result<()> {
     debug!("f64 -> u64: comparing {} to {} using {:?}", self, actual, matcher);
     match matcher {
+      MatchingRule::DecimalWithTolerance(tolerance) => match_with_tolerance(*self, actual as f64, *tolerance),
       MatchingRule::Regex(ref regex) => {
-        match Regex::new(regex) {
+        match MatchingRegex::new(regex) {
           Ok(re) => {
-            if re.is_match(&actual.to_string()) {
+            if re.is_match(&actual.to_string())? {
               Ok(())
             } else {
               Err(anyhow!("Expected '{}' to match '{}'", actual, regex))
@@ -565,9 +594,9 @@ impl Matches<i64> for &str {
     debug!("String -> i64: comparing '{}' to {} using {:?}", self, actual, matcher);
     match matcher {
       MatchingRule::Regex(regex) => {
-        match Regex::new(regex) {
+        match MatchingRegex::new(regex) {
           Ok(re) => {
-            if re.is_match(&actual.to_string()) {
+            if re.is_match(&actual.to_string())? {
               Ok(())
             } else {
               Err(anyhow!("Expected {} to match '{}'", actual, regex))
@@ -605,10 +634,11 @@ impl Matches<i64> for i64 {
   fn matches_with(&self, actual: i64, matcher: &MatchingRule, cascaded: bool) -> anyhow::Result<()> {
     debug!("i64 -> i64: comparing {} to {} using {:?}", self, actual, matcher);
     match matcher {
+      MatchingRule::DecimalWithTolerance(tolerance) => match_with_tolerance(*self as f64, actual as f64, *tolerance),
       MatchingRule::Regex(regex) => {
-        match Regex::new(regex) {
+        match MatchingRegex::new(regex) {
           Ok(re) => {
-            if re.is_match(&actual.to_string()) {
+            if re.is_match(&actual.to_string())? {
               Ok(())
             } else {
               Err(anyhow!("Expected {} to match '{}'", actual, regex))
@@ -673,9 +703,9 @@ impl Matches<bool> for bool {
     debug!("bool -> bool: comparing '{}' to {} using {:?}", self, actual, matcher);
     match matcher {
       MatchingRule::Regex(regex) => {
-        match Regex::new(regex) {
+        match MatchingRegex::new(regex) {
           Ok(re) => {
-            if re.is_match(&actual.to_string()) {
+            if re.is_match(&actual.to_string())? {
               Ok(())
             } else {
               Err(anyhow!("Expected {} to match '{}'", actual, regex))
@@ -716,10 +746,10 @@ impl Matches<&Bytes> for Bytes {
     debug!("Bytes -> Bytes: comparing {} bytes to {} bytes using {:?}", self.len(), actual.len(), matcher);
     match matcher {
       MatchingRule::Regex(regex) => {
-        match Regex::new(regex) {
+        match MatchingRegex::new(regex) {
           Ok(re) => {
             match from_utf8(actual) {
-              Ok(s) => if re.is_match(s) {
+              Ok(s) => if re.is_match(s)? {
                 Ok(())
               } else {
                 Err(anyhow!("Expected '{}' to match '{}'", s, regex))
@@ -754,7 +784,7 @@ impl Matches<&Bytes> for Bytes {
       },
       MatchingRule::ContentType(content_type) => match_content_type(actual, content_type),
       MatchingRule::NotEmpty => {
-        if actual.is_empty() {
+        if !cascaded && actual.is_empty() {
           Err(anyhow!("Expected [] (0 bytes) to not be empty"))
         } else {
           Ok(())
@@ -773,12 +803,34 @@ impl Matches<&Bytes> for Bytes {
 pub fn match_values<E, A>(path: &DocPath, matching_rules: &RuleList, expected: E, actual: A) -> Result<(), Vec<String>>
   where E: Matches<A>, A: Clone {
   trace!("match_values: {} -> {}", std::any::type_name::<E>(), std::any::type_name::<A>());
+  apply_rules(path, matching_rules, |rule| expected.matches_with(actual.clone(), rule, matching_rules.cascaded))
+}
+
+/// Match the provided values using the path and matching rules. Any custom matching rules will be
+/// matched using the custom matchers registered with the context.
+pub fn match_values_with_context<E, A>(
+  path: &DocPath,
+  matching_rules: &RuleList,
+  expected: E,
+  actual: A,
+  context: &dyn MatchingContext
+) -> Result<(), Vec<String>>
+  where E: Matches<A> + CustomMatcherValue, A: Clone + CustomMatcherValue {
+  trace!("match_values_with_context: {} -> {}", std::any::type_name::<E>(), std::any::type_name::<A>());
+  apply_rules(path, matching_rules, |rule| match rule {
+    MatchingRule::Custom(name, config) => match_custom(name, config, &expected, &actual, context),
+    _ => expected.matches_with(actual.clone(), rule, matching_rules.cascaded)
+  })
+}
+
+fn apply_rules<F>(path: &DocPath, matching_rules: &RuleList, mut apply: F) -> Result<(), Vec<String>>
+  where F: FnMut(&MatchingRule) -> anyhow::Result<()> {
   if matching_rules.is_empty() {
     Err(vec![format!("No matcher found for path '{}'", path)])
   } else {
-    let results = matching_rules.rules.iter().map(|rule| {
-      expected.matches_with(actual.clone(), rule, matching_rules.cascaded)
-    }).collect::<Vec<anyhow::Result<()>>>();
+    let results = matching_rules.rules.iter()
+      .map(&mut apply)
+      .collect::<Vec<anyhow::Result<()>>>();
     let result = match matching_rules.rule_logic {
       RuleLogic::And => {
         if results.iter().all(|result| result.is_ok()) {
@@ -797,11 +849,77 @@ pub fn match_values<E, A>(path: &DocPath, matching_rules: &RuleList, expected: E
         }
       }
     };
-    trace!(?result, "match_values: {} -> {}", std::any::type_name::<E>(), std::any::type_name::<A>());
+    trace!(?result, "apply_rules: path {}", path);
     result
   }
 }
 
+/// Matches the actual number if it differs from the expected one by no more than the tolerance
+pub(crate) fn match_with_tolerance(expected: f64, actual: f64, tolerance: f64) -> anyhow::Result<()> {
+  if (actual - expected).abs() <= tolerance {
+    Ok(())
+  } else {
+    Err(anyhow!("Expected {} to be within {} of {}", actual, tolerance, expected))
+  }
+}
+
+/// Matches the actual timestamp if it is within the number of seconds of the reference timestamp,
+/// or of the current time if there is no reference timestamp
+#[allow(unused_variables)]
+pub(crate) fn match_timestamp_within(
+  actual: &str,
+  format: &str,
+  seconds: u64,
+  reference: &Option<String>
+) -> anyhow::Result<()> {
+  #[cfg(feature = "datetime")]
+  {
+    let actual_datetime = parse_datetime(actual, format)
+      .map_err(|err| anyhow!("Expected '{}' to be a valid timestamp - {}", actual, err))?;
+    let (reference_datetime, description) = match reference {
+      Some(reference) => (parse_datetime(reference, format)
+        .map_err(|err| anyhow!("Reference timestamp '{}' is not valid - {}", reference, err))?,
+        format!("'{}'", reference)),
+      None => (chrono::Utc::now().fixed_offset(), "the current time".to_string())
+    };
+    let difference = (actual_datetime - reference_datetime).num_milliseconds().unsigned_abs();
+    if difference <= seconds.saturating_mul(1000) {
+      Ok(())
+    } else {
+      Err(anyhow!("Expected '{}' to be within {} seconds of {}", actual, seconds, description))
+    }
+  }
+  #[cfg(not(feature = "datetime"))]
+  {
+    Err(anyhow!("DateTime matchers require the datetime feature to be enabled"))
+  }
+}
+
+/// Validates the actual value against the JSON Schema document
+#[allow(unused_variables)]
+pub(crate) fn match_json_schema(schema: &Value, actual: &Value) -> anyhow::Result<()> {
+  #[cfg(feature = "json-schema")]
+  {
+    let compiled = jsonschema::JSONSchema::compile(schema)
+      .map_err(|err| anyhow!("JSON Schema is not valid - {}", err))?;
+    compiled.validate(actual).map_err(|errors| {
+      let errors = errors.map(|err| {
+        let path = err.instance_path.to_string();
+        if path.is_empty() {
+          err.to_string()
+        } else {
+          format!("{}: {}", path, err)
+        }
+      }).collect::<Vec<_>>();
+      anyhow!("Expected {} to match the JSON Schema - {}", actual, errors.join(", "))
+    })
+  }
+  #[cfg(not(feature = "json-schema"))]
+  {
+    Err(anyhow!("JSON Schema matchers require the json-schema feature to be enabled"))
+  }
+}
+
 #[instrument(level = "trace")]
 fn match_status_code(status_code: u16, status: &HttpStatus) -> anyhow::Result<()> {
   let matches = match status {
@@ -832,7 +950,7 @@ pub fn match_strings(
 ) -> Result<(), Vec<CommonMismatch>> {
   let matcher_result = if context.matcher_is_defined(&path) {
     debug!("Calling match_values for path {}", path);
-    match_values(&path, &context.select_best_matcher(&path), expected, actual)
+    match_values_with_context(&path, &context.select_best_matcher(&path), expected, actual, context)
   } else {
     expected.matches_with(actual, &MatchingRule::Equality, false).map_err(|err|
       vec![format!("String '{}': {}", path, err)]
@@ -845,7 +963,8 @@ pub fn match_strings(
         path: path.to_string(),
         expected: expected.to_string(),
         actual: actual.to_string(),
-        description: message.clone()
+        description: message.clone(),
+        location: None
       }
     }).collect()
   })
@@ -855,9 +974,12 @@ pub fn match_strings(
 mod tests {
   use expectest::expect;
   use expectest::prelude::*;
+  use maplit::hashmap;
   use pact_models::{matchingrules, matchingrules::RuleList, matchingrules_list};
   use serde_json::json;
 
+  use crate::{CoreMatchingContext, DiffConfig};
+
   use super::*;
 
   #[test]
@@ -1232,6 +1354,75 @@ mod tests {
     expect!(json!("1.0.0").matches_with(&json!("1"), &matcher, false)).to(be_err());
   }
 
+  #[test]
+  fn decimal_with_tolerance_matcher_test() {
+    let matcher = MatchingRule::DecimalWithTolerance(0.01);
+    expect!(100.0.matches_with(100.005, &matcher, false)).to(be_ok());
+    expect!(100.0.matches_with(100.02, &matcher, false)).to(be_err());
+    expect!(100_u64.matches_with(99.995, &matcher, false)).to(be_ok());
+    expect!(100.0.matches_with(100_u64, &matcher, false)).to(be_ok());
+    expect!("1.2345".to_string().matches_with("1.2399", &matcher, false)).to(be_ok());
+    expect!("1.2345".to_string().matches_with("1.2499", &matcher, false)).to(be_err());
+    expect!("1.2345".to_string().matches_with("one", &matcher, false)).to(be_err());
+    expect!(json!(19.99).matches_with(&json!(19.990000000000002), &matcher, false)).to(be_ok());
+    expect!(json!(19.99).matches_with(&json!("19.995"), &matcher, false)).to(be_ok());
+    expect!(json!(19.99).matches_with(&json!(20.5), &matcher, false)).to(be_err());
+    expect!(json!(19.99).matches_with(&json!(true), &matcher, false)).to(be_err());
+  }
+
+  #[test]
+  #[cfg(feature = "datetime")]
+  fn timestamp_within_matcher_test() {
+    let matcher = MatchingRule::TimestampWithin("yyyy-MM-dd HH:mm:ssZ".to_string(), 60,
+      Some("2023-05-01 10:00:00+0000".to_string()));
+    expect!("".to_string().matches_with("2023-05-01 10:00:59+0000", &matcher, false)).to(be_ok());
+    expect!("".to_string().matches_with("2023-05-01 09:59:00+0000", &matcher, false)).to(be_ok());
+    expect!("".to_string().matches_with("2023-05-01 10:01:01+0000", &matcher, false)).to(be_err());
+    expect!("".to_string().matches_with("2023-05-01 11:00:30+0100", &matcher, false)).to(be_ok());
+    expect!("".to_string().matches_with("not a timestamp", &matcher, false)).to(be_err());
+
+    let matcher = MatchingRule::TimestampWithin(String::default(), 60, None);
+    let now = chrono::Utc::now();
+    expect!(json!("").matches_with(&json!(now.to_rfc3339()), &matcher, false)).to(be_ok());
+    expect!(json!("").matches_with(&json!((now - chrono::Duration::hours(1)).to_rfc3339()), &matcher, false))
+      .to(be_err());
+    expect!(json!("").matches_with(&json!(100), &matcher, false)).to(be_err());
+  }
+
+  #[test]
+  fn equals_ignore_case_matcher_test() {
+    let matcher = MatchingRule::EqualsIgnoreCase;
+    expect!("Bearer abc".to_string().matches_with("bearer ABC", &matcher, false)).to(be_ok());
+    expect!("Bearer abc".to_string().matches_with("Basic abc", &matcher, false)).to(be_err());
+    expect!(json!("Bearer abc").matches_with(&json!("BEARER abc"), &matcher, false)).to(be_ok());
+    expect!(json!("Bearer abc").matches_with(&json!("Basic abc"), &matcher, false)).to(be_err());
+    expect!(json!(100).matches_with(&json!(100), &matcher, false)).to(be_ok());
+    expect!(json!(100).matches_with(&json!("100"), &matcher, false)).to(be_err());
+  }
+
+  #[test]
+  fn custom_matcher_test() {
+    let context = CoreMatchingContext::with_config(DiffConfig::AllowUnexpectedKeys)
+      .with_custom_matcher("jwt", |_, actual, _| {
+        match actual.as_str() {
+          Some(s) if s.split('.').count() == 3 => vec![],
+          _ => vec![format!("Expected {} to be a JWT", actual)]
+        }
+      });
+    let path = DocPath::root();
+    let rules = RuleList::new(MatchingRule::Custom("jwt".to_string(), hashmap!{}));
+    expect!(match_values_with_context(&path, &rules, "a.b.c", "x.y.z", &context)).to(be_ok());
+    expect!(match_values_with_context(&path, &rules, "a.b.c", "xyz", &context)).to(be_err());
+    expect!(match_values_with_context(&path, &rules, json!("a.b.c"), json!("x.y.z"), &context)).to(be_ok());
+    expect!(match_values_with_context(&path, &rules, json!("a.b.c"), json!(100), &context)).to(be_err());
+
+    // Custom matchers are not available without a context
+    expect!(match_values(&path, &rules, "a.b.c", "x.y.z")).to(be_err());
+
+    let rules = RuleList::new(MatchingRule::Custom("not-registered".to_string(), hashmap!{}));
+    expect!(match_values_with_context(&path, &rules, "a.b.c", "x.y.z", &context)).to(be_err());
+  }
+
   #[test]
   fn content_type_matcher_test() {
     let matcher = MatchingRule::ContentType("text/plain".to_string());
@@ -7,15 +7,27 @@ use std::str::from_utf8;
 use anyhow::anyhow;
 use itertools::Itertools;
 use maplit::hashmap;
-use onig::Regex;
 use pact_models::matchingrules::{Category, MatchingRule, MatchingRuleCategory, RuleList, RuleLogic};
 use pact_models::path_exp::DocPath;
 use serde_json::{self, json, Value};
 use tracing::{debug, trace};
 
-use crate::{Either, MatchingContext, merge_result, CommonMismatch};
+use crate::{Either, MatchingContext, merge_result, CommonMismatch, MismatchLocation};
 use crate::binary_utils::match_content_type;
 use crate::matchers::Matches;
+use crate::regex_engine::MatchingRegex;
+
+/// Parser for the matching rule definition expressions (i.e. `matching(type, 'Name')` or
+/// `eachValue(matching(integer, 100))`). This can be used to validate a definition before it is
+/// used in a pact, as any errors are returned with a report that points to the problem in the
+/// expression. See [`pact_models::matchingrules::expressions`] for the supported grammar.
+pub use pact_models::matchingrules::expressions::{
+  is_matcher_def,
+  parse_matcher_def,
+  MatchingReference,
+  MatchingRuleDefinition,
+  ValueType
+};
 
 impl <T: Debug + Display + PartialEq + Clone> Matches<&Vec<T>> for &Vec<T> {
   fn matches_with(&self, actual: &Vec<T>, matcher: &MatchingRule, cascaded: bool) -> anyhow::Result<()> {
@@ -68,8 +80,38 @@ impl <T: Debug + Display + PartialEq + Clone> Matches<&[T]> for &[T] {
           Err(anyhow!("Expected {} to be equal to {}", actual.for_mismatch(), self.for_mismatch()))
         }
       }
+      MatchingRule::EqualsIgnoreOrder => {
+        if !cascaded && actual.len() != self.len() {
+          Err(anyhow!("Expected {} (size {}) to have a size of {}", display(actual), actual.len(), self.len()))
+        } else {
+          Ok(())
+        }
+      }
+      MatchingRule::MinEqualsIgnoreOrder(min) => {
+        if !cascaded && actual.len() < *min {
+          Err(anyhow!("Expected {} (size {}) to have minimum size of {}", display(actual), actual.len(), min))
+        } else {
+          Ok(())
+        }
+      }
+      MatchingRule::MaxEqualsIgnoreOrder(max) => {
+        if !cascaded && actual.len() > *max {
+          Err(anyhow!("Expected {} (size {}) to have maximum size of {}", display(actual), actual.len(), max))
+        } else {
+          Ok(())
+        }
+      }
+      MatchingRule::MinMaxEqualsIgnoreOrder(min, max) => {
+        if !cascaded && actual.len() < *min {
+          Err(anyhow!("Expected {} (size {}) to have minimum size of {}", display(actual), actual.len(), min))
+        } else if !cascaded && actual.len() > *max {
+          Err(anyhow!("Expected {} (size {}) to have maximum size of {}", display(actual), actual.len(), max))
+        } else {
+          Ok(())
+        }
+      }
       MatchingRule::NotEmpty => {
-        if actual.is_empty() {
+        if !cascaded && actual.is_empty() {
           Err(anyhow!("Expected {} (Array) to not be empty", actual.for_mismatch()))
         } else {
           Ok(())
@@ -103,10 +145,10 @@ impl Matches<&[u8]> for Vec<u8> {
     debug!("byte slice -> byte slice: comparing {:?} to {:?} using {:?}", self, actual, matcher);
     let result = match matcher {
       MatchingRule::Regex(regex) => {
-        match Regex::new(regex) {
+        match MatchingRegex::new(regex) {
           Ok(re) => {
             let text = from_utf8(actual).unwrap_or_default();
-            if re.is_match(text) {
+            if re.is_match(text)? {
               Ok(())
             } else {
               Err(anyhow!("Expected '{}' to match '{}'", text, regex))
@@ -151,7 +193,7 @@ impl Matches<&[u8]> for Vec<u8> {
           .map_err(|err| anyhow!("Failed to match data to have a content type of '{}': {}", expected_content_type, err))
       }
       MatchingRule::NotEmpty => {
-        if actual.is_empty() {
+        if !cascaded && actual.is_empty() {
           Err(anyhow!("Expected [] (0 bytes) to not be empty"))
         } else {
           Ok(())
@@ -208,7 +250,7 @@ impl <T: Debug + Display + Clone + PartialEq> Matches<&BTreeMap<String, T>> for
         }
       }
       MatchingRule::NotEmpty => {
-        if actual.is_empty() {
+        if !cascaded && actual.is_empty() {
           Err(anyhow!("Expected {} (Array) to not be empty", actual.for_mismatch()))
         } else {
           Ok(())
@@ -218,6 +260,8 @@ impl <T: Debug + Display + Clone + PartialEq> Matches<&BTreeMap<String, T>> for
       MatchingRule::EachKey(_) => Ok(()),
       MatchingRule::EachValue(_) => Ok(()),
       MatchingRule::Values => Ok(()),
+      MatchingRule::EqualsIgnoreOrder | MatchingRule::MinEqualsIgnoreOrder(_) |
+      MatchingRule::MaxEqualsIgnoreOrder(_) | MatchingRule::MinMaxEqualsIgnoreOrder(_, _) => Ok(()),
       _ => Err(anyhow!("Unable to match {} using {:?}", self.for_mismatch(), matcher))
     };
     debug!("Comparing '{:?}' to '{:?}' using {:?} -> {:?}", self, actual, matcher, result);
@@ -295,7 +339,8 @@ pub fn compare_maps_with_matchingrule<T: Display + Debug + Clone + PartialEq>(
               path: path.to_string(),
               expected: format!("{:?}", expected),
               actual: format!("{:?}", actual),
-              description: format!("Found an un-resolved reference {}", reference.name)
+              description: format!("Found an un-resolved reference {}", reference.name),
+              location: None
             }]));
             None
           }
@@ -330,7 +375,8 @@ pub fn compare_maps_with_matchingrule<T: Display + Debug + Clone + PartialEq>(
         path: path.to_string(),
         expected: expected.for_mismatch(),
         actual: actual.for_mismatch(),
-        description: mismatch.to_string()
+        description: mismatch.to_string(),
+        location: Some(MismatchLocation::new(path, std::slice::from_ref(rule)))
       }]));
     }
     let expected_keys = expected.keys().cloned().collect();
@@ -361,11 +407,27 @@ pub fn compare_lists_with_matchingrule<T: Display + Debug + PartialEq + Clone +
 
   if !expected.is_empty() {
     match rule {
-      // TODO: need to implement the ignore order matchers (See Pact-JVM core/matchers/src/main/kotlin/au/com/dius/pact/core/matchers/Matchers.kt:133)
-      // is EqualsIgnoreOrderMatcher,
-      //         is MinEqualsIgnoreOrderMatcher,
-      //         is MaxEqualsIgnoreOrderMatcher,
-      //         is MinMaxEqualsIgnoreOrderMatcher -> {
+      MatchingRule::EqualsIgnoreOrder | MatchingRule::MinEqualsIgnoreOrder(_) |
+        MatchingRule::MaxEqualsIgnoreOrder(_) | MatchingRule::MinMaxEqualsIgnoreOrder(_, _) => {
+        debug!("Matching {} ignoring the order of the items", path);
+        if let Err(mismatch) = expected.matches_with(actual, rule, cascaded) {
+          result.push(CommonMismatch {
+            path: path.to_string(),
+            expected: expected.for_mismatch(),
+            actual: actual.for_mismatch(),
+            description: mismatch.to_string(),
+            location: Some(MismatchLocation::new(path, std::slice::from_ref(rule)))
+          });
+        }
+
+        let matches = expected.iter().map(|expected_value| {
+          actual.iter().enumerate().map(|(index, actual_value)| {
+            debug!("Comparing list item {} with value '{:?}' to '{:?}'", index, actual_value, expected_value);
+            callback(&path.join(index.to_string()), expected_value, actual_value, context).is_ok()
+          }).collect::<Vec<_>>()
+        }).collect::<Vec<_>>();
+        result.extend(match_list_contents_ignoring_order(path, expected, actual, &matches));
+      }
       MatchingRule::ArrayContains(variants) => {
         debug!("Matching {} with ArrayContains", path);
         let variants = if variants.is_empty() {
@@ -387,7 +449,8 @@ pub fn compare_lists_with_matchingrule<T: Display + Debug + PartialEq + Clone +
                   path: path.to_string(),
                   expected: expected_value.to_string(),
                   actual: actual.for_mismatch(),
-                  description: format!("Variant at index {} ({}) was not found in the actual list", index, expected_value)
+                  description: format!("Variant at index {} ({}) was not found in the actual list", index, expected_value),
+                  location: Some(MismatchLocation::new(path, std::slice::from_ref(rule)))
                 });
               };
             },
@@ -397,7 +460,8 @@ pub fn compare_lists_with_matchingrule<T: Display + Debug + PartialEq + Clone +
                 expected: expected.for_mismatch(),
                 actual: actual.for_mismatch(),
                 description: format!("ArrayContains: variant {} is missing from the expected list, which has {} items",
-                                  index, expected.len())
+                                  index, expected.len()),
+                location: Some(MismatchLocation::new(path, std::slice::from_ref(rule)))
               });
             }
           }
@@ -413,7 +477,8 @@ pub fn compare_lists_with_matchingrule<T: Display + Debug + PartialEq + Clone +
                 path: path.to_string(),
                 expected: expected.for_mismatch(),
                 actual: actual.for_mismatch(),
-                description: format!("Found an un-resolved reference {}", reference.name)
+                description: format!("Found an un-resolved reference {}", reference.name),
+                location: None
               });
               None
             }
@@ -438,7 +503,8 @@ pub fn compare_lists_with_matchingrule<T: Display + Debug + PartialEq + Clone +
             path: path.to_string(),
             expected: expected.for_mismatch(),
             actual: actual.for_mismatch(),
-            description: mismatch.to_string()
+            description: mismatch.to_string(),
+            location: Some(MismatchLocation::new(path, std::slice::from_ref(rule)))
           });
         }
 
@@ -471,7 +537,8 @@ pub fn compare_lists_with_matchingrules<T>(
       path: path.to_string(),
       expected: format!("{:?}", expected),
       actual: format!("{:?}", actual),
-      description: format!("No matcher found for path '{}'", path)
+      description: format!("No matcher found for path '{}'", path),
+      location: None
     })
   } else {
     let results = matching_rules.rules.iter().map(|rule| {
@@ -532,7 +599,8 @@ fn match_list_contents<T: Display + Debug + PartialEq + Clone + Sized>(
         path: path.to_string(),
         expected: expected.for_mismatch(),
         actual: actual.for_mismatch(),
-        description: format!("Expected {} ({}) but was missing", value, index)
+        description: format!("Expected {} ({}) but was missing", value, index),
+        location: None
       });
     }
   }
@@ -540,6 +608,71 @@ fn match_list_contents<T: Display + Debug + PartialEq + Clone + Sized>(
   result
 }
 
+/// Compares the list items ignoring their order, given which expected items match which actual items.
+/// Each expected item is paired with a different actual item that it matches (finding the largest
+/// number of pairs), and then any remaining actual items must match at least one of the expected items.
+fn match_list_contents_ignoring_order<T: Display>(
+  path: &DocPath,
+  expected: &[T],
+  actual: &[T],
+  matches: &[Vec<bool>]
+) -> Vec<CommonMismatch> {
+  let mut pairs = vec![None; actual.len()];
+  for expected_index in 0..expected.len() {
+    let mut visited = vec![false; actual.len()];
+    pair_list_item(expected_index, matches, &mut visited, &mut pairs);
+  }
+
+  let mut result = vec![];
+  for (index, value) in expected.iter().enumerate() {
+    if !pairs.contains(&Some(index)) {
+      result.push(CommonMismatch {
+        path: path.to_string(),
+        expected: expected.for_mismatch(),
+        actual: actual.for_mismatch(),
+        description: format!("Expected item at index {} ({}) was not found in the actual list", index, value),
+        location: None
+      });
+    }
+  }
+  for (index, value) in actual.iter().enumerate() {
+    if pairs[index].is_none() && !matches.iter().any(|row| row[index]) {
+      result.push(CommonMismatch {
+        path: path.join(index.to_string()).to_string(),
+        expected: expected.for_mismatch(),
+        actual: value.to_string(),
+        description: format!("Actual item at index {} ({}) did not match any of the expected items", index, value),
+        location: None
+      });
+    }
+  }
+  result
+}
+
+/// Tries to pair the expected item with an actual item it matches, moving any previously paired
+/// expected items to other actual items if required (augmenting path search)
+fn pair_list_item(
+  expected_index: usize,
+  matches: &[Vec<bool>],
+  visited: &mut [bool],
+  pairs: &mut [Option<usize>]
+) -> bool {
+  for (actual_index, is_match) in matches[expected_index].iter().enumerate() {
+    if *is_match && !visited[actual_index] {
+      visited[actual_index] = true;
+      let paired = match pairs[actual_index] {
+        Some(other_index) => pair_list_item(other_index, matches, visited, pairs),
+        None => true
+      };
+      if paired {
+        pairs[actual_index] = Some(expected_index);
+        return true;
+      }
+    }
+  }
+  false
+}
+
 #[cfg(test)]
 mod tests {
   use std::collections::{BTreeSet, HashMap, HashSet};
@@ -761,7 +894,8 @@ mod tests {
         path: "$".to_string(),
         expected: "{\"a\":\"100\",\"b\":\"101\",\"c\":\"102\"}".to_string(),
         actual: "{\"b\":\"103\"}".to_string(),
-        description: "Expected {\"b\": \"103\"} (size 1) to have minimum size of 2".to_string()
+        description: "Expected {\"b\": \"103\"} (size 1) to have minimum size of 2".to_string(),
+        location: None
       }
     ]));
 
@@ -799,7 +933,8 @@ mod tests {
         path: "$".to_string(),
         expected: "{\"a\":\"100\"}".to_string(),
         actual: "{\"a\":\"101\",\"b\":\"102\",\"c\":\"103\"}".to_string(),
-        description: "Expected {\"a\": \"101\", \"b\": \"102\", \"c\": \"103\"} (size 3) to have maximum size of 2".to_string()
+        description: "Expected {\"a\": \"101\", \"b\": \"102\", \"c\": \"103\"} (size 3) to have maximum size of 2".to_string(),
+        location: None
       }
     ]));
 
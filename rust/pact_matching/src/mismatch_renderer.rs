@@ -0,0 +1,384 @@
+//! Renders mismatches for display to users, either as a human-readable diff or as a
+//! machine-readable JSON tree grouped by the part of the request or response that diverged.
+//! Body mismatches can also be converted to a structured form (`BodyMismatchDetails`) with the
+//! path as a `DocPath` and JSON pointer, the expected and actual fragments and the matching rules
+//! that were applied at that path when the mismatch was created.
+
+use ansi_term::Colour::{Green, Red};
+use ansi_term::Style;
+use bytes::Bytes;
+use difference::{Changeset, Difference};
+use itertools::Itertools;
+use pact_models::matchingrules::MatchingRule;
+use pact_models::path_exp::DocPath;
+use serde_json::{json, Map, Value};
+
+use crate::Mismatch;
+
+/// Structured details of a body mismatch
+#[derive(Debug, Clone, PartialEq)]
+pub struct BodyMismatchDetails {
+  /// Path to where the mismatch occurred
+  pub path: DocPath,
+  /// Path to where the mismatch occurred as a JSON pointer (RFC 6901). This will be empty for
+  /// mismatches with the whole body.
+  pub pointer: String,
+  /// Expected fragment of the body. JSON fragments are parsed, anything else is kept as a string.
+  pub expected: Option<Value>,
+  /// Actual fragment of the body. JSON fragments are parsed, anything else is kept as a string.
+  pub actual: Option<Value>,
+  /// Matching rules that were applied at the path. Will be empty if the values were compared
+  /// using equality.
+  pub rules: Vec<MatchingRule>,
+  /// Description of the mismatch
+  pub mismatch: String
+}
+
+impl BodyMismatchDetails {
+  /// Creates the structured details for a body mismatch from the location recorded when the
+  /// mismatch was created. Mismatches without a recorded location have their path parsed, and no
+  /// rules. Returns `None` for any other type of mismatch, or if the path can not be parsed.
+  pub fn from_mismatch(mismatch: &Mismatch) -> Option<Self> {
+    match mismatch {
+      Mismatch::BodyMismatch { path, expected, actual, mismatch, location } => {
+        let (path, rules) = match location {
+          Some(location) => (location.path.clone(), location.rules.clone()),
+          None => (DocPath::new(path.as_str()).ok()?, vec![])
+        };
+        let pointer = path.as_json_pointer().unwrap_or_default();
+        Some(BodyMismatchDetails {
+          path,
+          pointer,
+          expected: expected.as_ref().map(bytes_to_fragment),
+          actual: actual.as_ref().map(bytes_to_fragment),
+          rules,
+          mismatch: mismatch.clone()
+        })
+      }
+      _ => None
+    }
+  }
+
+  /// Converts these details into a JSON form
+  pub fn to_json(&self) -> Value {
+    let mut json = json!({
+      "pointer": self.pointer,
+      "expected": self.expected,
+      "actual": self.actual,
+      "mismatch": self.mismatch
+    });
+    if !self.rules.is_empty() {
+      json["rules"] = Value::Array(self.rules.iter().map(|rule| rule.to_json()).collect());
+    }
+    json
+  }
+}
+
+/// Renders a list of mismatches in a form suitable for test failure output
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct MismatchRenderer {
+  colour: bool
+}
+
+impl MismatchRenderer {
+  /// Creates a new renderer that renders without ANSI colours
+  pub fn new() -> Self {
+    MismatchRenderer::default()
+  }
+
+  /// Enables or disables ANSI colours in the rendered diff
+  pub fn with_colour(mut self, colour: bool) -> Self {
+    self.colour = colour;
+    self
+  }
+
+  /// Renders the mismatches as a unified diff. Each mismatch is shown under a heading naming
+  /// where it occurred, with expected values prefixed with `-` and actual values with `+`.
+  /// JSON body fragments are pretty-printed so that nested values are compared line by line, and
+  /// any matching rules that were applied to a body mismatch are listed under its heading.
+  pub fn render_diff(&self, mismatches: &[Mismatch]) -> String {
+    let mut output = String::new();
+    for mismatch in mismatches {
+      let details = BodyMismatchDetails::from_mismatch(mismatch);
+      let (heading, expected, actual) = match mismatch {
+        Mismatch::MethodMismatch { expected, actual } =>
+          ("method".to_string(), expected.clone(), actual.clone()),
+        Mismatch::PathMismatch { expected, actual, .. } =>
+          ("path".to_string(), expected.clone(), actual.clone()),
+        Mismatch::StatusMismatch { expected, actual, .. } =>
+          ("status".to_string(), expected.to_string(), actual.to_string()),
+        Mismatch::QueryMismatch { parameter, expected, actual, .. } =>
+          (format!("query parameter '{}'", parameter), expected.clone(), actual.clone()),
+        Mismatch::HeaderMismatch { key, expected, actual, .. } =>
+          (format!("header '{}'", key), expected.clone(), actual.clone()),
+        Mismatch::BodyTypeMismatch { expected, actual, .. } =>
+          ("body content type".to_string(), expected.clone(), actual.clone()),
+        Mismatch::BodyMismatch { path, expected, actual, .. } =>
+          (path.clone(), bytes_to_diff_string(expected), bytes_to_diff_string(actual)),
+        Mismatch::MetadataMismatch { key, expected, actual, .. } =>
+          (format!("metadata '{}'", key), expected.clone(), actual.clone()),
+        Mismatch::TrailerMismatch { key, expected, actual, .. } =>
+          (format!("trailer '{}'", key), expected.clone(), actual.clone())
+      };
+
+      output.push_str(&self.paint(Style::new().bold(), format!("{}:", heading)));
+      output.push('\n');
+      if let Some(details) = &details {
+        if !details.rules.is_empty() {
+          output.push_str(&format!("  (matching with {})\n",
+            details.rules.iter().map(|rule| rule.name()).join(", ")));
+        }
+      }
+      for change in Changeset::new(&expected, &actual, "\n").diffs {
+        match change {
+          Difference::Same(ref x) => for line in x.lines() {
+            output.push_str(&format!("   {}\n", line));
+          },
+          Difference::Rem(ref x) => for line in x.lines() {
+            output.push_str(&self.paint(Red.normal(), format!("  -{}", line)));
+            output.push('\n');
+          },
+          Difference::Add(ref x) => for line in x.lines() {
+            output.push_str(&self.paint(Green.normal(), format!("  +{}", line)));
+            output.push('\n');
+          }
+        }
+      }
+      output.push_str(&format!("  {}\n", mismatch.description()));
+    }
+    output
+  }
+
+  /// Renders the mismatches as a JSON tree. The top level keys are the parts of the request or
+  /// response (`method`, `path`, `status`, `query`, `headers`, `body` and `metadata`). Query
+  /// parameters, headers, metadata and body mismatches are keyed by name or path, with a list of
+  /// the mismatches found there.
+  pub fn render_json(&self, mismatches: &[Mismatch]) -> Value {
+    let mut tree = Map::new();
+    for mismatch in mismatches {
+      let entry = json!({
+        "expected": mismatch_expected(mismatch),
+        "actual": mismatch_actual(mismatch),
+        "mismatch": mismatch.description()
+      });
+      let (category, key) = match mismatch {
+        Mismatch::MethodMismatch { .. } => ("method", None),
+        Mismatch::PathMismatch { .. } => ("path", None),
+        Mismatch::StatusMismatch { .. } => ("status", None),
+        Mismatch::QueryMismatch { parameter, .. } => ("query", Some(parameter.clone())),
+        Mismatch::HeaderMismatch { key, .. } => ("headers", Some(key.clone())),
+        Mismatch::BodyTypeMismatch { .. } => ("body", Some("contentType".to_string())),
+        Mismatch::BodyMismatch { path, .. } => ("body", Some(path.clone())),
+        Mismatch::MetadataMismatch { key, .. } => ("metadata", Some(key.clone())),
+        Mismatch::TrailerMismatch { key, .. } => ("trailers", Some(key.clone()))
+      };
+      match key {
+        Some(key) => {
+          let map = tree.entry(category)
+            .or_insert_with(|| Value::Object(Map::new()));
+          if let Value::Object(map) = map {
+            if let Value::Array(list) = map.entry(key).or_insert_with(|| Value::Array(vec![])) {
+              list.push(entry);
+            }
+          }
+        }
+        None => {
+          tree.insert(category.to_string(), entry);
+        }
+      }
+    }
+    Value::Object(tree)
+  }
+
+  fn paint(&self, style: Style, text: String) -> String {
+    if self.colour {
+      style.paint(text).to_string()
+    } else {
+      text
+    }
+  }
+}
+
+fn bytes_to_string(bytes: &Option<Bytes>) -> String {
+  bytes.as_ref()
+    .map(|b| String::from_utf8_lossy(b).to_string())
+    .unwrap_or_default()
+}
+
+fn bytes_to_fragment(bytes: &Bytes) -> Value {
+  serde_json::from_slice(bytes)
+    .unwrap_or_else(|_| Value::String(String::from_utf8_lossy(bytes).to_string()))
+}
+
+fn bytes_to_diff_string(bytes: &Option<Bytes>) -> String {
+  match bytes {
+    Some(bytes) => match serde_json::from_slice::<Value>(bytes) {
+      Ok(json) => serde_json::to_string_pretty(&json).unwrap_or_else(|_| json.to_string()),
+      Err(_) => String::from_utf8_lossy(bytes).to_string()
+    },
+    None => String::default()
+  }
+}
+
+fn mismatch_expected(mismatch: &Mismatch) -> Value {
+  match mismatch {
+    Mismatch::MethodMismatch { expected, .. } => json!(expected),
+    Mismatch::PathMismatch { expected, .. } => json!(expected),
+    Mismatch::StatusMismatch { expected, .. } => json!(expected),
+    Mismatch::QueryMismatch { expected, .. } => json!(expected),
+    Mismatch::HeaderMismatch { expected, .. } => json!(expected),
+    Mismatch::BodyTypeMismatch { expected, .. } => json!(expected),
+    Mismatch::BodyMismatch { expected, .. } => json!(bytes_to_string(expected)),
+    Mismatch::MetadataMismatch { expected, .. } => json!(expected),
+    Mismatch::TrailerMismatch { expected, .. } => json!(expected)
+  }
+}
+
+fn mismatch_actual(mismatch: &Mismatch) -> Value {
+  match mismatch {
+    Mismatch::MethodMismatch { actual, .. } => json!(actual),
+    Mismatch::PathMismatch { actual, .. } => json!(actual),
+    Mismatch::StatusMismatch { actual, .. } => json!(actual),
+    Mismatch::QueryMismatch { actual, .. } => json!(actual),
+    Mismatch::HeaderMismatch { actual, .. } => json!(actual),
+    Mismatch::BodyTypeMismatch { actual, .. } => json!(actual),
+    Mismatch::BodyMismatch { actual, .. } => json!(bytes_to_string(actual)),
+    Mismatch::MetadataMismatch { actual, .. } => json!(actual),
+    Mismatch::TrailerMismatch { actual, .. } => json!(actual)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use bytes::Bytes;
+  use expectest::prelude::*;
+  use pact_models::matchingrules::MatchingRule;
+  use pact_models::path_exp::DocPath;
+  use serde_json::json;
+
+  use crate::{Mismatch, MismatchLocation};
+
+  use super::{BodyMismatchDetails, MismatchRenderer};
+
+  fn mismatches() -> Vec<Mismatch> {
+    vec![
+      Mismatch::MethodMismatch { expected: "GET".to_string(), actual: "POST".to_string() },
+      Mismatch::HeaderMismatch {
+        key: "X-Version".to_string(),
+        expected: "1".to_string(),
+        actual: "2".to_string(),
+        mismatch: "Mismatch with header 'X-Version': Expected '1' but received '2'".to_string()
+      },
+      Mismatch::BodyMismatch {
+        path: "$.name".to_string(),
+        expected: Some(Bytes::from("\"Fred\"")),
+        actual: Some(Bytes::from("\"Mary\"")),
+        mismatch: "Expected 'Fred' to be equal to 'Mary'".to_string(),
+        location: None
+      }
+    ]
+  }
+
+  #[test]
+  fn render_diff_test() {
+    let diff = MismatchRenderer::new().render_diff(&mismatches());
+    expect!(diff).to(be_equal_to(
+      "method:\n  -GET\n  +POST\n  expected GET but was POST\n\
+      header 'X-Version':\n  -1\n  +2\n  Mismatch with header 'X-Version': Expected '1' but received '2'\n\
+      $.name:\n  -\"Fred\"\n  +\"Mary\"\n  $.name -> Expected 'Fred' to be equal to 'Mary'\n"));
+  }
+
+  #[test]
+  fn render_diff_with_colour_test() {
+    let diff = MismatchRenderer::new().with_colour(true).render_diff(&mismatches()[0..1]);
+    expect!(diff).to(be_equal_to(
+      "\u{1b}[1mmethod:\u{1b}[0m\n\u{1b}[31m  -GET\u{1b}[0m\n\u{1b}[32m  +POST\u{1b}[0m\n  expected GET but was POST\n"));
+  }
+
+  #[test]
+  fn render_json_test() {
+    let tree = MismatchRenderer::new().render_json(&mismatches());
+    expect!(tree).to(be_equal_to(json!({
+      "method": {
+        "expected": "GET",
+        "actual": "POST",
+        "mismatch": "expected GET but was POST"
+      },
+      "headers": {
+        "X-Version": [
+          {
+            "expected": "1",
+            "actual": "2",
+            "mismatch": "Mismatch with header 'X-Version': Expected '1' but received '2'"
+          }
+        ]
+      },
+      "body": {
+        "$.name": [
+          {
+            "expected": "\"Fred\"",
+            "actual": "\"Mary\"",
+            "mismatch": "$.name -> Expected 'Fred' to be equal to 'Mary'"
+          }
+        ]
+      }
+    })));
+  }
+
+  #[test]
+  fn body_mismatch_details_uses_the_recorded_location() {
+    let mismatch = Mismatch::BodyMismatch {
+      path: "$.items[1].id".to_string(),
+      expected: Some(Bytes::from("100")),
+      actual: Some(Bytes::from("\"100\"")),
+      mismatch: "Expected '100' to be an integer".to_string(),
+      location: Some(MismatchLocation::new(&DocPath::new_unwrap("$.items[1].id"), &[ MatchingRule::Integer ]))
+    };
+
+    let details = BodyMismatchDetails::from_mismatch(&mismatch);
+    expect!(details.clone()).to(be_some().value(BodyMismatchDetails {
+      path: DocPath::new_unwrap("$.items[1].id"),
+      pointer: "/items/1/id".to_string(),
+      expected: Some(json!(100)),
+      actual: Some(json!("100")),
+      rules: vec![ MatchingRule::Integer ],
+      mismatch: "Expected '100' to be an integer".to_string()
+    }));
+    expect!(details.unwrap().to_json()).to(be_equal_to(json!({
+      "pointer": "/items/1/id",
+      "expected": 100,
+      "actual": "100",
+      "rules": [ { "match": "integer" } ],
+      "mismatch": "Expected '100' to be an integer"
+    })));
+  }
+
+  #[test]
+  fn body_mismatch_details_without_a_recorded_location() {
+    let details = BodyMismatchDetails::from_mismatch(&mismatches()[2]);
+    expect!(details).to(be_some().value(BodyMismatchDetails {
+      path: DocPath::new_unwrap("$.name"),
+      pointer: "/name".to_string(),
+      expected: Some(json!("Fred")),
+      actual: Some(json!("Mary")),
+      rules: vec![],
+      mismatch: "Expected 'Fred' to be equal to 'Mary'".to_string()
+    }));
+    expect!(BodyMismatchDetails::from_mismatch(&mismatches()[0])).to(be_none());
+  }
+
+  #[test]
+  fn render_diff_with_nested_body_test() {
+    let mismatch = Mismatch::BodyMismatch {
+      path: "$.address".to_string(),
+      expected: Some(Bytes::from("{\"street\":\"Main St\",\"number\":10}")),
+      actual: Some(Bytes::from("{\"street\":\"Main St\",\"number\":\"10\"}")),
+      mismatch: "Expected a Number but received a String".to_string(),
+      location: Some(MismatchLocation::new(&DocPath::new_unwrap("$.address"), &[ MatchingRule::Type ]))
+    };
+    let diff = MismatchRenderer::new().render_diff(&[mismatch]);
+    expect!(diff).to(be_equal_to(
+      "$.address:\n  (matching with type)\n   {\n  -  \"number\": 10,\n  +  \"number\": \"10\",\n     \"street\": \"Main St\"\n   }\n  \
+      $.address -> Expected a Number but received a String\n"));
+  }
+}
@@ -5,7 +5,8 @@ use std::collections::HashMap;
 use itertools::Itertools;
 use maplit::hashmap;
 use pact_models::matchingrules::MatchingRule;
-use pact_models::path_exp::DocPath;
+use pact_models::path_exp::{DocPath, PathToken};
+use pact_models::query_strings::nested_query_path;
 use tracing::debug;
 
 use crate::{matchers, Matches, MatchingContext, merge_result, Mismatch, CommonMismatch};
@@ -50,17 +51,28 @@ pub(crate) fn match_query_maps(
   result
 }
 
+/// Matches the values for a query parameter. Repeated parameters are compared in order, unless
+/// there is a matching rule defined for the parameter. Rules defined for the parameter (i.e.
+/// `$.id`) are applied to the list of values (for instance, `arrayContains` can be used to ignore
+/// the order of the values), and will cascade to each value. Rules can also be defined for a value
+/// at a particular index (i.e. `$.id[1]`).
 fn match_query_values(
   key: &str,
   expected: &[String],
   actual: &[String],
   context: &dyn MatchingContext
 ) -> Result<(), Vec<CommonMismatch>> {
-  let path = DocPath::root().join(key);
+  let path = query_parameter_path(key, context);
   if context.matcher_is_defined(&path) {
     debug!("match_query_values: Matcher defined for query parameter '{}", key);
     compare_lists_with_matchingrules(&path, &context.select_best_matcher(&path), expected, actual, context.clone_with(context.matchers()).as_ref(), &mut |p, expected, actual, context| {
-      compare_query_parameter_value(p, expected, actual, 0, context)
+      // The list matching functions will pass in the path to the value being compared, which will
+      // be the parameter path with the index of the value appended
+      let index = match p.tokens().last() {
+        Some(PathToken::Index(index)) if p.len() == path.len() + 1 => *index,
+        _ => 0
+      };
+      compare_query_parameter_value(key, &path, expected, actual, index, context)
     })
   } else {
     if expected.is_empty() && !actual.is_empty() {
@@ -68,7 +80,8 @@ fn match_query_values(
         path: key.to_string(),
         expected: format!("{:?}", expected),
         actual: format!("{:?}", actual),
-        description: format!("Expected an empty parameter list for '{}' but received {:?}", key, actual)
+        description: format!("Expected an empty parameter list for '{}' but received {:?}", key, actual),
+        location: None
       } ])
     } else {
       let mismatch = if expected.len() != actual.len() {
@@ -78,51 +91,72 @@ fn match_query_values(
           actual: format!("{:?}", actual),
           description: format!(
             "Expected query parameter '{}' with {} value(s) but received {} value(s)",
-            key, expected.len(), actual.len())
+            key, expected.len(), actual.len()),
+          location: None
         } ])
       } else {
         Ok(())
       };
-      merge_result(compare_query_parameter_values(&path, expected, actual, context), mismatch)
+      merge_result(compare_query_parameter_values(key, &path, expected, actual, context), mismatch)
     }
   }
 }
 
+/// Path used to look up the matching rules for a query parameter. Parameters using the nested
+/// bracket syntax (i.e. `filter[name]`) can also be targeted with the nested path (`$.filter.name`)
+/// if there are no rules for the parameter name.
+fn query_parameter_path(key: &str, context: &dyn MatchingContext) -> DocPath {
+  let path = DocPath::root().join(key);
+  if context.matcher_is_defined(&path) {
+    path
+  } else {
+    nested_query_path(key)
+      .filter(|nested_path| context.matcher_is_defined(nested_path))
+      .unwrap_or(path)
+  }
+}
+
 fn compare_query_parameter_value(
+  key: &str,
   path: &DocPath,
   expected: &str,
   actual: &str,
   index: usize,
   context: &dyn MatchingContext
 ) -> Result<(), Vec<CommonMismatch>> {
-  let index = index.to_string();
-  let index_path = path.join(index.as_str());
+  let index_path = path.join(index.to_string());
   let matcher_result = if context.matcher_is_defined(&index_path) {
-    matchers::match_values(&index_path, &context.select_best_matcher(&index_path),
-      expected.to_string(), actual.to_string())
+    matchers::match_values_with_context(&index_path, &context.select_best_matcher(&index_path),
+      expected.to_string(), actual.to_string(), context)
+      .map_err(|messages| messages.iter()
+        .map(|message| format!("Mismatch with query parameter '{}' at index {}: {}", key, index, message))
+        .collect())
   } else {
     expected.matches_with(actual, &MatchingRule::Equality, false)
       .map_err(|_error| vec![
-        format!("Expected query parameter '{}' with value '{}' but was '{}'",
-          path.to_vec().last().cloned().unwrap_or_else(|| "??".to_string()),
-          expected,
-          actual
-        )
+        if index == 0 {
+          format!("Expected query parameter '{}' with value '{}' but was '{}'", key, expected, actual)
+        } else {
+          format!("Expected query parameter '{}' at index {} with value '{}' but was '{}'",
+            key, index, expected, actual)
+        }
       ])
   };
   matcher_result.map_err(|messages| {
     messages.iter().map(|message| {
       CommonMismatch {
-        path: path.first_field().unwrap_or_default().to_string(),
+        path: key.to_string(),
         expected: expected.to_string(),
         actual: actual.to_string(),
-        description: message.clone()
+        description: message.clone(),
+        location: None
       }
     }).collect()
   })
 }
 
 fn compare_query_parameter_values(
+  key: &str,
   path: &DocPath,
   expected: &[String],
   actual: &[String],
@@ -134,19 +168,19 @@ fn compare_query_parameter_values(
     .enumerate()
     .flat_map(|(index, val)| {
       if index < actual.len() {
-        match compare_query_parameter_value(path, val, &actual[index], index, context) {
+        match compare_query_parameter_value(key, path, val, &actual[index], index, context) {
           Ok(_) => vec![],
           Err(errors) => errors
         }
       } else if context.matcher_is_defined(path) {
         vec![]
       } else {
-        let key = path.first_field().unwrap_or_default().to_string();
         vec![ CommonMismatch {
-          path: key.clone(),
+          path: key.to_string(),
           expected: format!("{:?}", expected),
           actual: format!("{:?}", actual),
-          description: format!("Expected query parameter '{}' value '{}' but was missing", key, val)
+          description: format!("Expected query parameter '{}' value '{}' at index {} but was missing", key, val, index),
+          location: None
         } ]
       }
     })
@@ -183,4 +217,94 @@ mod tests {
     expect!(super::match_query_values("id", &expected, &actual, &context))
       .to(be_ok());
   }
+
+  #[test]
+  fn compare_values_with_a_matcher_for_a_value_at_an_index() {
+    let expected = ["1".to_string(), "2".to_string()];
+    let rules = matchingrules! {
+      "query" => { "id[1]" => [ MatchingRule::Regex("\\d+".to_string()) ] }
+    };
+    let context = CoreMatchingContext::new(
+      DiffConfig::AllowUnexpectedKeys,
+      &rules.rules_for_category("query").unwrap_or_default(),
+      &hashmap!{}
+    );
+
+    expect!(super::match_query_values("id", &expected, &["1".to_string(), "200".to_string()], &context))
+      .to(be_ok());
+    let result = super::match_query_values("id", &expected, &["2".to_string(), "200".to_string()], &context);
+    expect!(result.unwrap_err().iter().map(|m| m.description.clone()).collect::<Vec<_>>()).to(be_equal_to(vec![
+      "Expected query parameter 'id' with value '1' but was '2'".to_string()
+    ]));
+    let result = super::match_query_values("id", &expected, &["1".to_string(), "two".to_string()], &context);
+    expect!(result.unwrap_err().iter().map(|m| m.description.clone()).collect::<Vec<_>>()).to(be_equal_to(vec![
+      "Mismatch with query parameter 'id' at index 1: Expected 'two' to match '\\d+'".to_string()
+    ]));
+  }
+
+  #[test]
+  fn compare_repeated_values_with_a_matcher_reports_the_index() {
+    let expected = ["1".to_string()];
+    let actual = ["100".to_string(), "200".to_string(), "three".to_string()];
+    let rules = matchingrules! {
+      "query" => { "id" => [ MatchingRule::Regex("^\\d+$".to_string()) ] }
+    };
+    let context = CoreMatchingContext::new(
+      DiffConfig::AllowUnexpectedKeys,
+      &rules.rules_for_category("query").unwrap_or_default(),
+      &hashmap!{}
+    );
+
+    let result = super::match_query_values("id", &expected, &actual, &context);
+    expect!(result.unwrap_err().iter().map(|m| m.description.clone()).collect::<Vec<_>>()).to(be_equal_to(vec![
+      "Mismatch with query parameter 'id' at index 2: Expected 'three' to match '^\\d+$'".to_string()
+    ]));
+  }
+
+  #[test]
+  fn compare_values_in_a_different_order() {
+    let expected = ["alligator".to_string(), "hippo".to_string()];
+    let actual = ["hippo".to_string(), "alligator".to_string()];
+
+    expect!(super::match_query_values("animal", &expected, &actual, &CoreMatchingContext::default()))
+      .to(be_err());
+
+    let rules = matchingrules! {
+      "query" => { "animal" => [ MatchingRule::ArrayContains(vec![]) ] }
+    };
+    let context = CoreMatchingContext::new(
+      DiffConfig::AllowUnexpectedKeys,
+      &rules.rules_for_category("query").unwrap_or_default(),
+      &hashmap!{}
+    );
+    expect!(super::match_query_values("animal", &expected, &actual, &context)).to(be_ok());
+    expect!(super::match_query_values("animal", &expected, &["hippo".to_string(), "elephant".to_string()], &context))
+      .to(be_err());
+  }
+
+  #[test]
+  fn nested_query_parameters_can_be_targeted_with_the_nested_path() {
+    let rules = matchingrules! {
+      "query" => {
+        "$.filter.name" => [ MatchingRule::Regex("^\\w+$".to_string()) ],
+        "$.ids" => [ MatchingRule::MinType(1) ]
+      }
+    };
+    let context = CoreMatchingContext::new(
+      DiffConfig::AllowUnexpectedKeys,
+      &rules.rules_for_category("query").unwrap_or_default(),
+      &hashmap!{}
+    );
+
+    expect!(super::match_query_values("filter[name]", &["x".to_string()], &["abc".to_string()], &context))
+      .to(be_ok());
+    let result = super::match_query_values("filter[name]", &["x".to_string()], &["a b".to_string()], &context);
+    expect!(result.unwrap_err().iter().map(|m| (m.path.clone(), m.description.clone())).collect::<Vec<_>>()).to(be_equal_to(vec![
+      ("filter[name]".to_string(), "Mismatch with query parameter 'filter[name]' at index 0: Expected 'a b' to match '^\\w+$'".to_string())
+    ]));
+    expect!(super::match_query_values("ids[]", &["1".to_string()], &["2".to_string(), "3".to_string()], &context))
+      .to(be_ok());
+    expect!(super::match_query_values("filter[type]", &["x".to_string()], &["y".to_string()], &context))
+      .to(be_err());
+  }
 }
@@ -0,0 +1,202 @@
+//! Evaluation of the regular expressions used by regex matchers.
+//!
+//! By default, regular expressions are evaluated with the Oniguruma engine. This supports
+//! look-around and back-references, but it is a backtracking engine, so some patterns can take an
+//! exponential amount of time to evaluate. The amount of backtracking allowed for a single match is
+//! limited (see `PACT_MATCHING_REGEX_RETRY_LIMIT`), and when the limit is exceeded the match fails
+//! with an error instead of hanging.
+//!
+//! For pacts from untrusted sources, the regular expressions can instead be evaluated with the
+//! linear-time engine from the `regex` crate, by setting the `PACT_MATCHING_REGEX_ENGINE`
+//! environment variable to `linear`. This engine guarantees that matching takes time linear in the
+//! size of the input, but does not support look-around or back-references, so patterns that use
+//! them are reported as invalid.
+//!
+//! The length of the patterns is not limited by default. To reject patterns longer than a number of
+//! characters with either engine, set the `PACT_MATCHING_REGEX_MAX_LENGTH` environment variable
+//! (or the `max_length` of the `RegexSettings`).
+
+use anyhow::anyhow;
+use onig::{MatchParam, SearchOptions};
+use regex::RegexBuilder;
+
+/// Environment variable that selects the regex engine (`backtracking` or `linear`)
+pub const REGEX_ENGINE_ENV_VAR: &str = "PACT_MATCHING_REGEX_ENGINE";
+/// Environment variable that sets the maximum amount of backtracking allowed for a single match
+/// with the backtracking engine
+pub const REGEX_RETRY_LIMIT_ENV_VAR: &str = "PACT_MATCHING_REGEX_RETRY_LIMIT";
+/// Environment variable that sets the maximum length of a regex pattern. If it is not set, the
+/// length of the patterns is not limited.
+pub const REGEX_MAX_LENGTH_ENV_VAR: &str = "PACT_MATCHING_REGEX_MAX_LENGTH";
+const DEFAULT_RETRY_LIMIT: u32 = 10_000_000;
+// Limit on the size of the compiled program for the linear engine, so large repetitions
+// (i.e. `a{1000}{1000}`) are rejected
+const LINEAR_SIZE_LIMIT: usize = 1 << 20;
+
+/// Engine used to evaluate regular expressions
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RegexEngine {
+  /// Oniguruma backtracking engine, with a limit on the amount of backtracking (default)
+  Backtracking,
+  /// Linear-time engine from the `regex` crate
+  Linear
+}
+
+impl RegexEngine {
+  /// Returns the engine configured with the `PACT_MATCHING_REGEX_ENGINE` environment variable,
+  /// defaulting to the backtracking engine
+  pub fn from_env() -> Self {
+    match std::env::var(REGEX_ENGINE_ENV_VAR) {
+      Ok(engine) if engine.eq_ignore_ascii_case("linear") => RegexEngine::Linear,
+      _ => RegexEngine::Backtracking
+    }
+  }
+}
+
+/// Settings used when compiling and evaluating regular expressions
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RegexSettings {
+  /// Engine to evaluate the regular expressions with
+  pub engine: RegexEngine,
+  /// Maximum length of a pattern. `None` means the length is not limited (the default).
+  pub max_length: Option<usize>,
+  /// Maximum amount of backtracking allowed for a single match (backtracking engine only)
+  pub retry_limit: u32
+}
+
+impl Default for RegexSettings {
+  fn default() -> Self {
+    let env_setting = |name: &str| std::env::var(name).ok();
+    RegexSettings {
+      engine: RegexEngine::from_env(),
+      max_length: env_setting(REGEX_MAX_LENGTH_ENV_VAR).and_then(|v| v.parse().ok()),
+      retry_limit: env_setting(REGEX_RETRY_LIMIT_ENV_VAR).and_then(|v| v.parse().ok()).unwrap_or(DEFAULT_RETRY_LIMIT)
+    }
+  }
+}
+
+#[derive(Debug)]
+enum CompiledRegex {
+  Backtracking(onig::Regex),
+  Linear(regex::Regex)
+}
+
+/// Compiled regular expression from a regex matcher. Matches are against the whole of the value.
+#[derive(Debug)]
+pub struct MatchingRegex {
+  pattern: String,
+  regex: CompiledRegex,
+  retry_limit: u32
+}
+
+impl MatchingRegex {
+  /// Compiles the pattern with the settings from the environment
+  pub fn new(pattern: &str) -> anyhow::Result<Self> {
+    MatchingRegex::with_settings(pattern, &RegexSettings::default())
+  }
+
+  /// Validates and compiles the pattern with the given settings
+  pub fn with_settings(pattern: &str, settings: &RegexSettings) -> anyhow::Result<Self> {
+    if let Some(max_length) = settings.max_length {
+      let length = pattern.chars().count();
+      if length > max_length {
+        return Err(anyhow!("pattern is {} characters long, which is more than the maximum of {}",
+          length, max_length));
+      }
+    }
+
+    let regex = match settings.engine {
+      RegexEngine::Backtracking => onig::Regex::new(pattern)
+        .map(CompiledRegex::Backtracking)
+        .map_err(|err| anyhow!(err.description().to_string()))?,
+      RegexEngine::Linear => RegexBuilder::new(format!(r"\A(?:{})\z", pattern).as_str())
+        .size_limit(LINEAR_SIZE_LIMIT)
+        .build()
+        .map(CompiledRegex::Linear)?
+    };
+    Ok(MatchingRegex {
+      pattern: pattern.to_string(),
+      regex,
+      retry_limit: settings.retry_limit
+    })
+  }
+
+  /// If the regular expression matches the whole of the text. Returns an error if the match
+  /// could not be completed (i.e. the backtracking limit was exceeded).
+  pub fn is_match(&self, text: &str) -> anyhow::Result<bool> {
+    match &self.regex {
+      CompiledRegex::Backtracking(regex) => {
+        let mut param = MatchParam::default();
+        param.set_retry_limit_in_match(self.retry_limit);
+        regex.match_with_param(text, 0, SearchOptions::SEARCH_OPTION_WHOLE_STRING, None, param)
+          .map(|result| result == Some(text.len()))
+          .map_err(|err| anyhow!("Failed to match '{}' with '{}' - {}", text, self.pattern, err.description()))
+      }
+      CompiledRegex::Linear(regex) => Ok(regex.is_match(text))
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use expectest::prelude::*;
+  use rstest::rstest;
+
+  use super::*;
+
+  fn settings(engine: RegexEngine) -> RegexSettings {
+    RegexSettings { engine, max_length: Some(100), retry_limit: 10_000 }
+  }
+
+  #[rstest]
+  #[case(RegexEngine::Backtracking)]
+  #[case(RegexEngine::Linear)]
+  fn matches_the_whole_value(#[case] engine: RegexEngine) {
+    let regex = MatchingRegex::with_settings(r"\d+", &settings(engine)).unwrap();
+    expect!(regex.is_match("1234").unwrap()).to(be_true());
+    expect!(regex.is_match("a1234").unwrap()).to(be_false());
+    expect!(regex.is_match("1234a").unwrap()).to(be_false());
+
+    let regex = MatchingRegex::with_settings("a|ab", &settings(engine)).unwrap();
+    expect!(regex.is_match("ab").unwrap()).to(be_true());
+  }
+
+  #[rstest]
+  #[case(RegexEngine::Backtracking)]
+  #[case(RegexEngine::Linear)]
+  fn rejects_patterns_that_are_too_long(#[case] engine: RegexEngine) {
+    let result = MatchingRegex::with_settings(&"a".repeat(101), &settings(engine));
+    expect!(result.unwrap_err().to_string()).to(
+      be_equal_to("pattern is 101 characters long, which is more than the maximum of 100"));
+  }
+
+  #[rstest]
+  #[case(RegexEngine::Backtracking)]
+  #[case(RegexEngine::Linear)]
+  fn does_not_limit_the_length_of_patterns_without_a_maximum(#[case] engine: RegexEngine) {
+    let settings = RegexSettings { max_length: None, .. settings(engine) };
+    let regex = MatchingRegex::with_settings(&"a".repeat(5000), &settings).unwrap();
+    expect!(regex.is_match(&"a".repeat(5000)).unwrap()).to(be_true());
+  }
+
+  #[test]
+  fn backtracking_engine_fails_the_match_when_the_retry_limit_is_exceeded() {
+    let regex = MatchingRegex::with_settings("(a|b|ab)*bc", &settings(RegexEngine::Backtracking)).unwrap();
+    let text = "ab".repeat(30) + "acbc";
+    expect!(regex.is_match(&text)).to(be_err());
+
+    let regex = MatchingRegex::with_settings("(a|b|ab)*bc", &settings(RegexEngine::Linear)).unwrap();
+    expect!(regex.is_match(&text).unwrap()).to(be_false());
+  }
+
+  #[test]
+  fn linear_engine_does_not_support_look_around() {
+    expect!(MatchingRegex::with_settings(r"\d+(?=px)", &settings(RegexEngine::Backtracking))).to(be_ok());
+    expect!(MatchingRegex::with_settings(r"\d+(?=px)", &settings(RegexEngine::Linear))).to(be_err());
+  }
+
+  #[test]
+  fn linear_engine_rejects_patterns_that_compile_to_large_programs() {
+    expect!(MatchingRegex::with_settings(r"\w{1000}{1000}", &settings(RegexEngine::Linear))).to(be_err());
+  }
+}
@@ -0,0 +1,240 @@
+//! Pre-compiled index of the paths of a category of matching rules.
+//!
+//! Resolving the matching rules for a path in a body requires every rule path to be compared
+//! against the path being matched. As this is done for every node in the body, it gets slow with
+//! large bodies and many rules. This index stores the rule paths as a tree of path tokens, so the
+//! rules that apply to a path can be found by walking the tree with the tokens from the path.
+//! The results are the same as the weighting calculated by `DocPath::path_weight`. Rule paths with
+//! array slices or deep wildcards (`**`) are not stored in the tree, and are matched directly.
+
+use std::borrow::Cow;
+use std::collections::HashMap;
+
+use pact_models::matchingrules::{Category, MatchingRuleCategory, RuleList};
+use pact_models::path_exp::{DocPath, PathToken};
+
+/// Node in the tree of path tokens
+#[derive(Debug, Clone, Default)]
+struct IndexNode {
+  root: Option<Box<IndexNode>>,
+  fields: HashMap<String, IndexNode>,
+  indices: HashMap<usize, IndexNode>,
+  star: Option<Box<IndexNode>>,
+  star_index: Option<Box<IndexNode>>,
+  /// Rule path that ends at this node
+  path: Option<DocPath>
+}
+
+impl IndexNode {
+  fn insert(&mut self, tokens: &[PathToken], path: &DocPath) {
+    match tokens.split_first() {
+      None => self.path = Some(path.clone()),
+      Some((token, rest)) => {
+        let node = match token {
+          PathToken::Root => self.root.get_or_insert_with(Default::default).as_mut(),
+          PathToken::Field(name) => self.fields.entry(name.clone()).or_default(),
+          PathToken::Index(index) => self.indices.entry(*index).or_default(),
+          PathToken::Star => self.star.get_or_insert_with(Default::default).as_mut(),
+          PathToken::StarIndex => self.star_index.get_or_insert_with(Default::default).as_mut(),
+          // Paths with these tokens are matched directly (see MatchingRuleIndex::new)
+          PathToken::Slice(_, _) | PathToken::DeepWildcard => return
+        };
+        node.insert(rest, path);
+      }
+    }
+  }
+
+  /// Returns the child nodes that match the path fragment, along with the weight of the match
+  fn children(&self, fragment: &str) -> Vec<(&IndexNode, usize)> {
+    let mut children = vec![];
+    if fragment == "$" {
+      if let Some(node) = &self.root {
+        children.push((node.as_ref(), 2));
+      }
+    }
+    if let Some(node) = self.fields.get(fragment) {
+      children.push((node, 2));
+    }
+    if let Ok(index) = fragment.parse::<usize>() {
+      if let Some(node) = self.indices.get(&index) {
+        children.push((node, 2));
+      }
+      if let Some(node) = &self.star_index {
+        children.push((node.as_ref(), 1));
+      }
+    }
+    if let Some(node) = &self.star {
+      children.push((node.as_ref(), 1));
+    }
+    children
+  }
+}
+
+/// Rule path that matched a path, with the weight of the match
+#[derive(Debug, Clone, PartialEq)]
+pub struct IndexMatch<'a> {
+  /// Path the rules are defined against
+  pub path: &'a DocPath,
+  /// Weight of the match (this will be greater than zero)
+  pub weight: usize,
+  /// Number of tokens in the rule path
+  pub length: usize
+}
+
+/// Index of the rule paths for a category of matching rules
+#[derive(Debug, Clone, Default)]
+pub struct MatchingRuleIndex {
+  root: IndexNode,
+  unindexed: Vec<DocPath>
+}
+
+impl MatchingRuleIndex {
+  /// Builds the index for the paths of the matching rules in the category
+  pub fn new(category: &MatchingRuleCategory) -> Self {
+    let mut root = IndexNode::default();
+    let mut unindexed = vec![];
+    for path in category.rules.keys() {
+      if path.tokens().iter().any(|token| matches!(token, PathToken::Slice(_, _) | PathToken::DeepWildcard)) {
+        unindexed.push(path.clone());
+      } else {
+        root.insert(path.tokens(), path);
+      }
+    }
+    MatchingRuleIndex { root, unindexed }
+  }
+
+  /// Returns all the rule paths that match the given path. A rule path matches if all its tokens
+  /// match the start of the path.
+  pub fn matching_paths(&self, path: &DocPath) -> Vec<IndexMatch<'_>> {
+    let mut matches = vec![];
+    let mut active = vec![(&self.root, 1)];
+    let mut length = 0;
+    loop {
+      for (node, weight) in &active {
+        if let Some(path) = &node.path {
+          matches.push(IndexMatch { path, weight: *weight, length });
+        }
+      }
+      let token = match path.tokens().get(length) {
+        Some(token) => token,
+        None => break
+      };
+      let fragment = match token {
+        PathToken::Field(name) => Cow::Borrowed(name.as_str()),
+        _ => Cow::Owned(token.to_string())
+      };
+      active = active.iter()
+        .flat_map(|(node, weight)| node.children(fragment.as_ref()).into_iter()
+          .map(move |(child, w)| (child, weight * w)))
+        .collect();
+      if active.is_empty() {
+        break;
+      }
+      length += 1;
+    }
+
+    if !self.unindexed.is_empty() {
+      let fragments = path.to_vec();
+      let fragments = fragments.iter().map(|f| f.as_str()).collect::<Vec<_>>();
+      for rule_path in &self.unindexed {
+        let (weight, length) = rule_path.path_weight(&fragments);
+        if weight > 0 {
+          matches.push(IndexMatch { path: rule_path, weight, length });
+        }
+      }
+    }
+    matches
+  }
+
+  /// If there is a matcher defined for the path
+  pub fn matcher_is_defined(&self, path: &DocPath) -> bool {
+    !self.matching_paths(path).is_empty()
+  }
+
+  /// Returns the rule path with the highest weighting that matches the given path
+  pub fn best_match(&self, path: &DocPath) -> Option<IndexMatch<'_>> {
+    self.matching_paths(path).into_iter()
+      .max_by_key(|m| m.weight * m.length)
+  }
+
+  /// Selects the best matcher for the given path from the category the index was built from. This
+  /// is the rule list with the path with the highest weighting.
+  pub fn select_best_matcher(&self, category: &MatchingRuleCategory, path: &DocPath) -> RuleList {
+    self.best_match(path)
+      .and_then(|m| category.rules.get(m.path).map(|rules| rules.as_cascaded(m.length != path.len())))
+      .unwrap_or_default()
+  }
+
+  /// If the rules for the category can be looked up using the index. Only body and metadata rules
+  /// are resolved with the weighted and cascaded paths the index calculates; other categories
+  /// (i.e. headers and query parameters) select the rules for the parameter name directly.
+  pub fn supports_category(category: &Category) -> bool {
+    matches!(category, Category::BODY | Category::METADATA)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use expectest::prelude::*;
+  use itertools::Itertools;
+  use pact_models::matchingrules::MatchingRule;
+  use pact_models::matchingrules_list;
+
+  use super::*;
+
+  #[test]
+  fn matching_paths_returns_the_same_weights_as_the_rule_paths() {
+    let rules = matchingrules_list! {
+      "body";
+      "$" => [ MatchingRule::Type ],
+      "$.items" => [ MatchingRule::MinType(1) ],
+      "$.items[*].id" => [ MatchingRule::Integer ],
+      "$.items[1].id" => [ MatchingRule::Decimal ],
+      "$.*.name" => [ MatchingRule::Regex("\\w+".to_string()) ],
+      "$.items[*].*" => [ MatchingRule::Type ],
+      "$.items[0:2].name" => [ MatchingRule::Type ],
+      "$.**.value" => [ MatchingRule::Integer ],
+      "$.items[*].**.name" => [ MatchingRule::Type ]
+    };
+    let index = MatchingRuleIndex::new(&rules);
+
+    for path in ["$", "$.items", "$.items[0]", "$.items[0].id", "$.items[1].id", "$.other.name",
+      "$.items[2].name", "$.items.1.id", "$.other", "$.items[0].id.value", "$.items[1].name",
+      "$.value", "$.items[3].a.b.name"] {
+      let path = DocPath::new_unwrap(path);
+      let fragments = path.to_vec();
+      let fragments = fragments.iter().map(|f| f.as_str()).collect_vec();
+      let expected = rules.rules.keys()
+        .map(|rule_path| (rule_path.to_string(), rule_path.path_weight(&fragments)))
+        .filter(|(_, (weight, _))| *weight > 0)
+        .sorted()
+        .collect_vec();
+      let actual = index.matching_paths(&path).iter()
+        .map(|m| (m.path.to_string(), (m.weight, m.length)))
+        .sorted()
+        .collect_vec();
+      expect!(actual).to(be_equal_to(expected));
+    }
+  }
+
+  #[test]
+  fn select_best_matcher_test() {
+    let rules = matchingrules_list! {
+      "body";
+      "$.items" => [ MatchingRule::MinType(1) ],
+      "$.items[*].id" => [ MatchingRule::Integer ],
+      "$.items[1].id" => [ MatchingRule::Decimal ]
+    };
+    let index = MatchingRuleIndex::new(&rules);
+
+    expect!(index.select_best_matcher(&rules, &DocPath::new_unwrap("$.items[0].id")).rules)
+      .to(be_equal_to(vec![ MatchingRule::Integer ]));
+    expect!(index.select_best_matcher(&rules, &DocPath::new_unwrap("$.items[1].id")).rules)
+      .to(be_equal_to(vec![ MatchingRule::Decimal ]));
+    let cascaded = index.select_best_matcher(&rules, &DocPath::new_unwrap("$.items[1].name"));
+    expect!(cascaded.rules).to(be_equal_to(vec![ MatchingRule::MinType(1) ]));
+    expect!(cascaded.cascaded).to(be_true());
+    expect!(index.select_best_matcher(&rules, &DocPath::new_unwrap("$.other")).is_empty()).to(be_true());
+    expect!(index.matcher_is_defined(&DocPath::new_unwrap("$.other"))).to(be_false());
+  }
+}
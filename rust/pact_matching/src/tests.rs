@@ -6,7 +6,7 @@ use pretty_assertions::assert_eq;
 use pact_models::{matchingrules, matchingrules_list};
 use pact_models::matchingrules::expressions::{MatchingRuleDefinition, ValueType};
 use pact_models::bodies::OptionalBody;
-use pact_models::content_types::{JSON, TEXT};
+use pact_models::content_types::{ContentTypeClass, JSON, TEXT, register_content_type, unregister_content_type};
 use pact_models::HttpStatus;
 use pact_models::request::Request;
 
@@ -83,6 +83,43 @@ fn match_query_applies_matching_rules_when_param_has_an_underscore() {
   expect!(result.values().flatten()).to(be_empty());
 }
 
+#[test]
+fn core_matching_context_selects_query_rules_by_parameter_name() {
+  let rules = matchingrules_list! {
+    "query";
+    "id" => [ MatchingRule::Regex("^[0-9]+$".to_string()) ],
+    "name" => [ MatchingRule::Type ]
+  };
+  let context = CoreMatchingContext::new(DiffConfig::AllowUnexpectedKeys, &rules, &hashmap!{});
+
+  let path = DocPath::root().join("id");
+  expect!(context.matcher_is_defined(&path)).to(be_true());
+  expect!(context.select_best_matcher(&path).rules).to(be_equal_to(vec![
+    MatchingRule::Regex("^[0-9]+$".to_string())
+  ]));
+  let path = DocPath::root().join("other");
+  expect!(context.matcher_is_defined(&path)).to(be_false());
+  expect!(context.select_best_matcher(&path).is_empty()).to(be_true());
+
+  // Values of a query parameter get the same rules as selected directly from the category
+  let path = DocPath::root().join("id").join("1");
+  expect!(context.select_best_matcher(&path)).to(be_equal_to(rules.select_best_matcher(&["$", "id", "1"])));
+}
+
+#[test]
+fn core_matching_context_only_finds_type_matchers_for_the_matching_contents_path() {
+  let rules = matchingrules_list! {
+    "contents";
+    "$.a" => [ MatchingRule::Type ]
+  };
+  let context = CoreMatchingContext::new(DiffConfig::AllowUnexpectedKeys, &rules, &hashmap!{});
+
+  expect!(context.type_matcher_defined(&DocPath::new_unwrap("$.a"))).to(be_true());
+  expect!(context.type_matcher_defined(&DocPath::new_unwrap("$.a.b"))).to(be_true());
+  expect!(context.type_matcher_defined(&DocPath::new_unwrap("$.b"))).to(be_false());
+  expect!(context.type_matcher_defined(&DocPath::root())).to(be_false());
+}
+
 #[test]
 fn match_query_returns_a_mismatch_if_there_is_no_expected_query_string() {
   let expected = None;
@@ -201,7 +238,7 @@ fn match_query_returns_a_mismatch_if_the_query_values_have_different_lengths() {
     parameter: "c".to_string(),
     expected: "[\"d\", \"e\"]".to_string(),
     actual: "[\"d\"]".to_string(),
-    mismatch: "Expected query parameter 'c' value 'e' but was missing".to_string(),
+    mismatch: "Expected query parameter 'c' value 'e' at index 1 but was missing".to_string(),
   });
 }
 
@@ -570,6 +607,70 @@ async fn body_matches_with_extended_mime_types() {
   expect!(result3.mismatches().iter()).to(be_empty());
 }
 
+#[tokio::test]
+async fn body_matches_vendor_mime_types_registered_by_suffix() {
+  register_content_type("application/*+hal-test", ContentTypeClass::Json).unwrap();
+  let expected = Request {
+    headers: Some(hashmap! { "Content-Type".to_string() => vec!["application/vnd.acme.v2+hal-test".to_string()] }),
+    body: OptionalBody::Present(r#"{"test":true}"#.into(), None, None),
+    ..Request::default()
+  };
+  let actual = Request {
+    headers: Some(hashmap! { "Content-Type".to_string() => vec!["application/vnd.acme.v2+hal-test".to_string()] }),
+    body: OptionalBody::Present(r#"{ "test": true }"#.into(), None, None),
+    ..Request::default()
+  };
+  let result = match_body(&expected, &actual, &CoreMatchingContext::default(), &CoreMatchingContext::default()).await;
+  unregister_content_type("application/*+hal-test");
+  expect!(result.mismatches().iter()).to(be_empty());
+}
+
+#[tokio::test]
+async fn body_matching_decodes_the_body_using_the_charset_from_the_content_type() {
+  let expected = Request {
+    headers: Some(hashmap! { "Content-Type".to_string() => vec!["application/json".to_string()] }),
+    body: OptionalBody::Present(r#"{"name":"Jos\u00e9"}"#.into(), None, None),
+    ..Request::default()
+  };
+  let utf16_body: Vec<u8> = r#"{"name": "José"}"#.encode_utf16().flat_map(|unit| unit.to_le_bytes()).collect();
+  let actual = Request {
+    headers: Some(hashmap! { "Content-Type".to_string() => vec!["application/json; charset=UTF-16LE".to_string()] }),
+    body: OptionalBody::Present(utf16_body.into(), None, None),
+    ..Request::default()
+  };
+  let result = match_body(&expected, &actual, &CoreMatchingContext::default(), &CoreMatchingContext::default()).await;
+  expect!(result.mismatches().iter()).to(be_empty());
+
+  let actual = Request {
+    headers: Some(hashmap! { "Content-Type".to_string() => vec!["application/json; charset=ISO-8859-1".to_string()] }),
+    body: OptionalBody::Present(b"{\"name\": \"Jos\xE9\"}".to_vec().into(), None, None),
+    ..Request::default()
+  };
+  let result = match_body(&expected, &actual, &CoreMatchingContext::default(), &CoreMatchingContext::default()).await;
+  expect!(result.mismatches().iter()).to(be_empty());
+}
+
+#[tokio::test]
+async fn body_matching_can_normalise_line_endings() {
+  let expected = Request {
+    headers: Some(hashmap! { "Content-Type".to_string() => vec!["text/plain".to_string()] }),
+    body: OptionalBody::Present("line 1\nline 2\n".into(), None, None),
+    ..Request::default()
+  };
+  let actual = Request {
+    headers: Some(hashmap! { "Content-Type".to_string() => vec!["text/plain".to_string()] }),
+    body: OptionalBody::Present("line 1\r\nline 2\r\n".into(), None, None),
+    ..Request::default()
+  };
+  let context = CoreMatchingContext::default().with_normalised_line_endings(false);
+  let result = match_body(&expected, &actual, &context, &CoreMatchingContext::default()).await;
+  expect!(result.mismatches().iter()).to_not(be_empty());
+
+  let context = CoreMatchingContext::default().with_normalised_line_endings(true);
+  let result = match_body(&expected, &actual, &context, &CoreMatchingContext::default()).await;
+  expect!(result.mismatches().iter()).to(be_empty());
+}
+
 #[tokio::test]
 async fn body_matches_with_nested_matchers() {
   let expected = Request {
@@ -623,7 +724,7 @@ fn partial_equal_for_method_mismatch() {
   expect!(&mismatch).to_not(be_equal_to(&Mismatch::QueryMismatch { parameter: s!(""), expected: s!(""), actual: s!(""), mismatch: "".into() }));
   expect!(&mismatch).to_not(be_equal_to(&Mismatch::HeaderMismatch { key: s!(""), expected: s!(""), actual: s!(""), mismatch: "".into() }));
   expect!(&mismatch).to_not(be_equal_to(&Mismatch::BodyTypeMismatch { expected: s!(""), actual: s!(""), mismatch: "".into(), expected_body: None, actual_body: None }));
-  expect!(&mismatch).to_not(be_equal_to(&Mismatch::BodyMismatch { expected: Some("get".into()), actual: Some("post".into()), mismatch: "".into(), path: s!("/") }));
+  expect!(&mismatch).to_not(be_equal_to(&Mismatch::BodyMismatch { expected: Some("get".into()), actual: Some("post".into()), mismatch: "".into(), path: s!("/"), location: None }));
 }
 
 #[test]
@@ -641,7 +742,7 @@ fn partial_equal_for_path_mismatch() {
   expect!(&mismatch).to_not(be_equal_to(&Mismatch::QueryMismatch { parameter: s!(""), expected: s!(""), actual: s!(""), mismatch: "".into() }));
   expect!(&mismatch).to_not(be_equal_to(&Mismatch::HeaderMismatch { key: s!(""), expected: s!(""), actual: s!(""), mismatch: "".into() }));
   expect!(&mismatch).to_not(be_equal_to(&Mismatch::BodyTypeMismatch { expected: s!(""), actual: s!(""), mismatch: "".into(), expected_body: None, actual_body: None }));
-  expect!(&mismatch).to_not(be_equal_to(&Mismatch::BodyMismatch { expected: Some("get".into()), actual: Some("post".into()), mismatch: "".into(), path: s!("/") }));
+  expect!(&mismatch).to_not(be_equal_to(&Mismatch::BodyMismatch { expected: Some("get".into()), actual: Some("post".into()), mismatch: "".into(), path: s!("/"), location: None }));
 }
 
 #[test]
@@ -659,7 +760,7 @@ fn partial_equal_for_status_mismatch() {
   expect!(&mismatch).to_not(be_equal_to(&Mismatch::QueryMismatch { parameter: s!(""), expected: s!(""), actual: s!(""), mismatch: s!("") }));
   expect!(&mismatch).to_not(be_equal_to(&Mismatch::HeaderMismatch { key: s!(""), expected: s!(""), actual: s!(""), mismatch: s!("") }));
   expect!(&mismatch).to_not(be_equal_to(&Mismatch::BodyTypeMismatch { expected: s!(""), actual: s!(""), mismatch: s!(""), expected_body: None, actual_body: None }));
-  expect!(&mismatch).to_not(be_equal_to(&Mismatch::BodyMismatch { expected: Some("get".into()), actual: Some("post".into()), mismatch: s!(""), path: s!("/") }));
+  expect!(&mismatch).to_not(be_equal_to(&Mismatch::BodyMismatch { expected: Some("get".into()), actual: Some("post".into()), mismatch: s!(""), path: s!("/"), location: None }));
 }
 
 #[test]
@@ -677,7 +778,7 @@ fn partial_equal_for_body_type_mismatch() {
   expect!(&mismatch).to_not(be_equal_to(&Mismatch::QueryMismatch { parameter: s!(""), expected: s!(""), actual: s!(""), mismatch: s!("") }));
   expect!(&mismatch).to_not(be_equal_to(&Mismatch::HeaderMismatch { key: s!(""), expected: s!(""), actual: s!(""), mismatch: s!("") }));
   expect!(&mismatch).to_not(be_equal_to(&Mismatch::PathMismatch { expected: s!(""), actual: s!(""), mismatch: s!("") }));
-  expect!(&mismatch).to_not(be_equal_to(&Mismatch::BodyMismatch { expected: Some("get".into()), actual: Some("post".into()), mismatch: s!(""), path: s!("/") }));
+  expect!(&mismatch).to_not(be_equal_to(&Mismatch::BodyMismatch { expected: Some("get".into()), actual: Some("post".into()), mismatch: s!(""), path: s!("/"), location: None }));
 }
 
 #[test]
@@ -699,7 +800,7 @@ fn partial_equal_for_query_mismatch() {
   expect!(&mismatch).to_not(be_equal_to(&Mismatch::PathMismatch { expected: s!(""), actual: s!(""), mismatch: s!("") }));
   expect!(&mismatch).to_not(be_equal_to(&Mismatch::HeaderMismatch { key: s!(""), expected: s!(""), actual: s!(""), mismatch: s!("") }));
   expect!(&mismatch).to_not(be_equal_to(&Mismatch::BodyTypeMismatch { expected: s!(""), actual: s!(""), mismatch: s!(""), expected_body: None, actual_body: None }));
-  expect!(&mismatch).to_not(be_equal_to(&Mismatch::BodyMismatch { expected: Some("get".into()), actual: Some("post".into()), mismatch: s!(""), path: s!("/") }));
+  expect!(&mismatch).to_not(be_equal_to(&Mismatch::BodyMismatch { expected: Some("get".into()), actual: Some("post".into()), mismatch: s!(""), path: s!("/"), location: None }));
 }
 
 #[test]
@@ -721,17 +822,17 @@ fn partial_equal_for_header_mismatch() {
   expect!(&mismatch).to_not(be_equal_to(&Mismatch::PathMismatch { expected: s!(""), actual: s!(""), mismatch: s!("") }));
   expect!(&mismatch).to_not(be_equal_to(&Mismatch::QueryMismatch { parameter: s!(""), expected: s!(""), actual: s!(""), mismatch: s!("") }));
   expect!(&mismatch).to_not(be_equal_to(&Mismatch::BodyTypeMismatch { expected: s!(""), actual: s!(""), mismatch: s!(""), expected_body: None, actual_body: None }));
-  expect!(&mismatch).to_not(be_equal_to(&Mismatch::BodyMismatch { expected: Some("get".into()), actual: Some("post".into()), mismatch: s!(""), path: s!("/") }));
+  expect!(&mismatch).to_not(be_equal_to(&Mismatch::BodyMismatch { expected: Some("get".into()), actual: Some("post".into()), mismatch: s!(""), path: s!("/"), location: None }));
 }
 
 #[test]
 fn partial_equal_for_body_mismatch() {
-  let mismatch = Mismatch::BodyMismatch { path: s!("key"), expected: Some("v1".into()), actual: Some("v2".into()), mismatch: s!("") };
-  let mismatch2 = Mismatch::BodyMismatch { path: s!("key"), expected: Some("v1".into()), actual: Some("v2".into()), mismatch: s!("") };
-  let mismatch3 = Mismatch::BodyMismatch { path: s!("key2"), expected: Some("v1".into()), actual: Some("v2".into()), mismatch: s!("") };
-  let mismatch4 = Mismatch::BodyMismatch { path: s!("key"), expected: None, actual: Some("v2".into()), mismatch: s!("") };
-  let mismatch5 = Mismatch::BodyMismatch { path: s!("key"), expected: Some("v1".into()), actual: None, mismatch: s!("") };
-  let mismatch6 = Mismatch::BodyMismatch { path: s!("key"), expected: Some("v1".into()), actual: Some("v2".into()), mismatch: s!("did not match") };
+  let mismatch = Mismatch::BodyMismatch { path: s!("key"), expected: Some("v1".into()), actual: Some("v2".into()), mismatch: s!(""), location: None };
+  let mismatch2 = Mismatch::BodyMismatch { path: s!("key"), expected: Some("v1".into()), actual: Some("v2".into()), mismatch: s!(""), location: None };
+  let mismatch3 = Mismatch::BodyMismatch { path: s!("key2"), expected: Some("v1".into()), actual: Some("v2".into()), mismatch: s!(""), location: None };
+  let mismatch4 = Mismatch::BodyMismatch { path: s!("key"), expected: None, actual: Some("v2".into()), mismatch: s!(""), location: None };
+  let mismatch5 = Mismatch::BodyMismatch { path: s!("key"), expected: Some("v1".into()), actual: None, mismatch: s!(""), location: None };
+  let mismatch6 = Mismatch::BodyMismatch { path: s!("key"), expected: Some("v1".into()), actual: Some("v2".into()), mismatch: s!("did not match"), location: None };
   expect!(&mismatch).to(be_equal_to(&mismatch));
   expect!(&mismatch).to(be_equal_to(&mismatch2));
   expect!(&mismatch).to(be_equal_to(&mismatch6));
@@ -817,6 +918,7 @@ async fn matching_text_body_be_false_when_bodies_are_not_equal() {
     expected: expected.body.value(),
     actual: actual.body.value(),
     mismatch: s!(""),
+    location: None
   });
 }
 
@@ -848,6 +950,27 @@ async fn matching_text_body_must_use_defined_matcher() {
   expect!(mismatches.mismatches().iter()).to_not(be_empty());
 }
 
+#[test]
+fn match_text_records_the_path_and_applied_rules_with_body_mismatches() {
+  let expected = Some(Bytes::from("expected body value"));
+  let actual = Some(Bytes::from("actual body value"));
+  let rule_location = |result: Result<(), Vec<Mismatch>>| match result.unwrap_err().first() {
+    Some(Mismatch::BodyMismatch { location, .. }) => location.clone(),
+    _ => None
+  };
+
+  expect!(rule_location(match_text(&expected, &actual, &CoreMatchingContext::default()))).to(
+    be_some().value(MismatchLocation::new(&DocPath::root(), &[])));
+
+  let context = CoreMatchingContext::new(
+    DiffConfig::AllowUnexpectedKeys,
+    &matchingrules_list! { "body"; "$" => [ MatchingRule::Regex(s!("\\d+")) ] },
+    &hashmap!{}
+  );
+  expect!(rule_location(match_text(&expected, &actual, &context))).to(
+    be_some().value(MismatchLocation::new(&DocPath::root(), &[ MatchingRule::Regex(s!("\\d+")) ])));
+}
+
 #[test]
 fn values_matcher_defined() {
   let context = CoreMatchingContext::new(
@@ -952,3 +1075,137 @@ fn match_metadata_value_with_content_type_test() {
   let result = match_metadata_value("key", &expected, &actual, &context);
   expect!(result).to(be_err());
 }
+
+#[test_log::test]
+fn match_message_metadata_with_matching_rules_for_keys_with_special_characters() {
+  use pact_models::v4::message_parts::MessageContents;
+
+  let expected = MessageContents::from_json(&json!({
+    "contents": "test",
+    "metadata": {
+      "correlation.id": "3f1d3c4e-4b1c-4a5e-8a47-5ad3c3a8f1e2",
+      "destination": "X001"
+    },
+    "matchingRules": {
+      "metadata": {
+        "correlation.id": { "matchers": [{ "match": "regex", "regex": "[0-9a-f-]{36}" }] },
+        "destination": { "matchers": [{ "match": "regex", "regex": "X\\d+" }] }
+      }
+    }
+  })).unwrap();
+  let actual = MessageContents {
+    metadata: hashmap!{
+      "correlation.id".to_string() => json!("b5d6a4a0-1f1c-4f61-9c1e-0e2d8c1e7a11"),
+      "destination".to_string() => json!("X987")
+    },
+    .. expected.clone()
+  };
+  let context = CoreMatchingContext::new(DiffConfig::AllowUnexpectedKeys,
+    &expected.matching_rules.rules_for_category(Category::METADATA).unwrap_or_default(), &hashmap!{});
+
+  let result = match_message_metadata(&expected, &actual, &context);
+  expect!(result.values().flatten().count()).to(be_equal_to(0));
+
+  let actual = MessageContents {
+    metadata: hashmap!{
+      "correlation.id".to_string() => json!("not-a-uuid"),
+      "destination".to_string() => json!("X987")
+    },
+    .. expected.clone()
+  };
+  let result = match_message_metadata(&expected, &actual, &context);
+  expect!(result.get("correlation.id").cloned().unwrap_or_default().len()).to(be_equal_to(1));
+}
+
+#[tokio::test]
+async fn generate_message_applies_metadata_generators_for_keys_with_special_characters() {
+  use pact_models::message::Message;
+  use pact_models::PactSpecification;
+  use pact_models::generators::GeneratorTestMode;
+  use crate::generators::generate_message;
+
+  let message = Message::from_json(0, &json!({
+    "description": "test message",
+    "contents": "test",
+    "metadata": {
+      "correlation.id": "3f1d3c4e-4b1c-4a5e-8a47-5ad3c3a8f1e2"
+    },
+    "generators": {
+      "metadata": {
+        "correlation.id": { "type": "Uuid" }
+      }
+    }
+  }), &PactSpecification::V3).unwrap();
+
+  let generated = generate_message(&message, &GeneratorTestMode::Consumer, &hashmap!{},
+    &vec![], &hashmap!{}).await;
+
+  expect!(generated.metadata.len()).to(be_equal_to(1));
+  expect!(generated.metadata.get("correlation.id").cloned()).to_not(be_equal_to(message.metadata.get("correlation.id").cloned()));
+}
+
+#[tokio::test]
+async fn match_response_compares_the_trailers() {
+  use pact_models::v4::pact::V4Pact;
+  use pact_models::v4::synch_http::SynchronousHttp;
+
+  let expected = HttpResponse {
+    trailers: Some(hashmap!{ "grpc-status".to_string() => vec!["0".to_string()] }),
+    matching_rules: matchingrules!{
+      "trailer" => { "grpc-status" => [ MatchingRule::Regex("^[0-9]+$".to_string()) ] }
+    },
+    .. HttpResponse::default()
+  };
+  let pact = V4Pact::default().boxed();
+  let interaction = SynchronousHttp::default().boxed();
+
+  let actual = HttpResponse {
+    trailers: Some(hashmap!{ "grpc-status".to_string() => vec!["2".to_string()] }),
+    .. HttpResponse::default()
+  };
+  let result = match_response(expected.clone(), actual, &pact, &interaction).await;
+  expect!(result.iter()).to(be_empty());
+
+  let result = match_response(expected, HttpResponse::default(), &pact, &interaction).await;
+  expect!(result.iter().map(|m| m.description()).collect::<Vec<_>>()).to(be_equal_to(vec![
+    "Expected a trailer 'grpc-status' but was missing".to_string()
+  ]));
+}
+
+fn context_with_settings() -> CoreMatchingContext {
+  CoreMatchingContext::with_config(DiffConfig::AllowUnexpectedKeys)
+    .with_type_coercion(&[DocPath::new_unwrap("$.id")])
+    .with_type_coercion_for_content_types(&[JSON.clone()])
+    .with_limits(limits::MatchingLimits::new(5, 10))
+    .with_normalised_line_endings(true)
+    .with_explanation(&explain::MatchingExplanation::new())
+}
+
+fn expect_settings(context: &(dyn MatchingContext + Send + Sync)) {
+  expect!(context.type_coercion_paths().to_vec()).to(be_equal_to(vec![DocPath::new_unwrap("$.id")]));
+  expect!(context.type_coercion_enabled(&DocPath::new_unwrap("$.id"))).to(be_true());
+  expect!(context.type_coercion_content_types().to_vec()).to(be_equal_to(vec![JSON.clone()]));
+  expect!(context.type_coercion_enabled_for_content_type(&JSON)).to(be_true());
+  expect!(context.limits()).to(be_equal_to(limits::MatchingLimits::new(5, 10)));
+  expect!(context.normalise_line_endings()).to(be_true());
+  expect!(context.explanation().is_some()).to(be_true());
+}
+
+#[test]
+fn core_matching_context_clone_with_keeps_the_settings() {
+  let context = context_with_settings();
+  expect_settings(context.clone_with(&matchingrules_list! { "body"; "$" => [ MatchingRule::Type ] }).as_ref());
+}
+
+#[test]
+fn core_matching_context_clone_from_keeps_the_settings() {
+  let context = context_with_settings();
+  expect_settings(&CoreMatchingContext::clone_from(&context));
+}
+
+#[test]
+fn header_matching_context_keeps_the_settings() {
+  let context = HeaderMatchingContext::new(&context_with_settings());
+  expect_settings(&context);
+  expect_settings(context.clone_with(&matchingrules_list! { "header"; "X" => [ MatchingRule::Type ] }).as_ref());
+}
@@ -2,9 +2,8 @@ use std::collections::btree_map::{BTreeMap, Entry};
 
 use anyhow::anyhow;
 use bytes::Bytes;
-use itertools::{EitherOrBoth, Itertools};
+use itertools::{Either, EitherOrBoth, Itertools};
 use maplit::*;
-use onig::Regex;
 use sxd_document::dom::*;
 use sxd_document::QName;
 
@@ -16,7 +15,8 @@ use pact_models::xml_utils::parse_bytes;
 use tracing::debug;
 
 use crate::matchers::*;
-use crate::MatchingContext;
+use crate::regex_engine::MatchingRegex;
+use crate::{MatchingContext, MismatchLocation};
 
 use super::DiffConfig;
 use super::Mismatch;
@@ -27,7 +27,7 @@ pub fn match_xml(
   context: &(dyn MatchingContext + Send + Sync)
 ) -> Result<(), Vec<super::Mismatch>> {
   let mut mismatches = vec![];
-  match (expected.body(), actual.body()) {
+  match (expected.body().loaded().as_ref(), actual.body().loaded().as_ref()) {
     (OptionalBody::Missing, _) => (),
     (OptionalBody::Empty, _) => (),
     (OptionalBody::Null, _) => (),
@@ -41,14 +41,16 @@ pub fn match_xml(
             path: "$".to_string(),
             expected: expected.body().value(),
             actual: actual.body().value(),
-            mismatch: format!("Failed to parse the expected body: '{:?}'", e)});
+            mismatch: format!("Failed to parse the expected body: '{:?}'", e),
+            location: Some(MismatchLocation::new(&DocPath::root(), &[]))});
         }
         if let Err(e) = actual_result {
           mismatches.push(Mismatch::BodyMismatch {
             path: "$".to_string(),
             expected: expected.body().value(),
             actual: actual.body().value(),
-            mismatch: format!("Failed to parse the actual body: '{:?}'", e)});
+            mismatch: format!("Failed to parse the actual body: '{:?}'", e),
+            location: Some(MismatchLocation::new(&DocPath::root(), &[]))});
         }
       } else {
         let expected_package = expected_result.unwrap();
@@ -68,7 +70,8 @@ pub fn match_xml(
         path: "$".into(),
         expected: expected.body().value(),
         actual: None,
-        mismatch: format!("Expected an XML body {} but was missing", expected.body())
+        mismatch: format!("Expected an XML body {} but was missing", expected.body()),
+        location: Some(MismatchLocation::new(&DocPath::root(), &[]))
       });
     }
   }
@@ -92,9 +95,9 @@ impl<'a> Matches<&'a Element<'a>> for &'a Element<'a> {
     fn matches_with(&self, actual: &Element, matcher: &MatchingRule, cascaded: bool) -> anyhow::Result<()> {
         let result = match *matcher {
           MatchingRule::Regex(ref regex) => {
-            match Regex::new(regex) {
+            match MatchingRegex::new(regex) {
               Ok(re) => {
-                if re.is_match(actual.name().local_part()) {
+                if re.is_match(actual.name().local_part())? {
                   Ok(())
                 } else {
                   Err(anyhow!("Expected '{}' to match '{}'", name(actual.name()), regex))
@@ -133,11 +136,13 @@ impl<'a> Matches<&'a Element<'a>> for &'a Element<'a> {
                   Err(anyhow!("Expected '{}' to be equal to '{}'", name(self.name()), name(actual.name())))
              }
           },
-          MatchingRule::NotEmpty => if actual.children().is_empty() {
+          MatchingRule::NotEmpty => if !cascaded && actual.children().is_empty() {
             Err(anyhow!("Expected '{}' to have at least one child", name(actual.name())))
           } else {
             Ok(())
           },
+          // eachKey and eachValue apply to the attributes of the element
+          MatchingRule::EachKey(_) | MatchingRule::EachValue(_) => Ok(()),
           _ => Err(anyhow!("Unable to match {:?} using {:?}", self, matcher))
         };
         debug!("Comparing '{:?}' to '{:?}' using {:?} -> {:?}", self, actual, matcher, result);
@@ -152,21 +157,35 @@ fn compare_element(
   mismatches: &mut Vec<super::Mismatch>,
   context: &dyn MatchingContext
 ) {
-  let matcher_result = if context.matcher_is_defined(path) {
+  let limits = context.limits();
+  if limits.depth_exceeded(path) {
+    debug!("Maximum depth of {} exceeded at path {}", limits.max_depth.unwrap_or_default(), path);
+    mismatches.push(limits.depth_mismatch(path, name(expected.name()), name(actual.name())).to_body_mismatch());
+    return;
+  }
+
+  let rule_list = if context.matcher_is_defined(path) {
+    Some(context.select_best_matcher(path))
+  } else {
+    None
+  };
+  let matcher_result = if let Some(rule_list) = &rule_list {
     debug!("calling match_values {:?} on {:?}", path, actual);
-    match_values(path, &context.select_best_matcher(&path), expected, actual)
+    match_values(path, rule_list, expected, actual)
   } else {
     expected.matches_with(actual, &MatchingRule::Equality, false).map_err(|err| vec![err.to_string()])
   };
   debug!("Comparing '{:?}' to '{:?}' at path '{}' -> {:?}", expected, actual, path, matcher_result);
   match matcher_result {
     Err(messages) => {
+      let rules = rule_list.map(|rule_list| rule_list.rules).unwrap_or_default();
       for message in messages {
         mismatches.push(Mismatch::BodyMismatch {
           path: path.to_string(),
           expected: Some(name(expected.name()).into()),
           actual: Some(name(actual.name()).into()),
-          mismatch: message.clone()
+          mismatch: message.clone(),
+          location: Some(MismatchLocation::new(path, &rules))
         })
       }
     },
@@ -189,12 +208,15 @@ fn compare_attributes(
         .iter().map(|attr| (name(attr.name()), s!(attr.value()))).collect();
     let actual_attributes: BTreeMap<String, String> = actual.attributes()
         .iter().map(|attr| (name(attr.name()), s!(attr.value()))).collect();
-    if expected_attributes.is_empty() && !actual_attributes.is_empty() && context.config() == DiffConfig::NoUnexpectedKeys {
+    if context.direct_matcher_defined(path, &hashset!{ "each-key", "each-value" }) {
+      compare_attributes_with_each_rules(path, &expected_attributes, &actual_attributes, mismatches, context);
+    } else if expected_attributes.is_empty() && !actual_attributes.is_empty() && context.config() == DiffConfig::NoUnexpectedKeys {
       mismatches.push(Mismatch::BodyMismatch {
         path: path.to_string(),
         expected: Some(format!("{:?}", expected_attributes).into()),
         actual: Some(format!("{:?}", actual_attributes).into()),
-        mismatch: format!("Did not expect any attributes but received {:?}", actual_attributes)
+        mismatch: format!("Did not expect any attributes but received {:?}", actual_attributes),
+        location: Some(MismatchLocation::new(path, &[]))
       });
     } else {
         match context.config() {
@@ -203,14 +225,16 @@ fn compare_attributes(
                     expected: Some(format!("{:?}", expected_attributes).into()),
                     actual: Some(format!("{:?}", actual_attributes).into()),
                     mismatch: format!("Expected at least {} attribute(s) but received {} attribute(s)",
-                    expected_attributes.len(), actual_attributes.len())});
+                    expected_attributes.len(), actual_attributes.len()),
+                    location: Some(MismatchLocation::new(path, &[]))});
             },
             DiffConfig::NoUnexpectedKeys if expected_attributes.len() != actual_attributes.len() => {
                 mismatches.push(Mismatch::BodyMismatch { path: path.to_string(),
                     expected: Some(format!("{:?}", expected_attributes).into()),
                     actual: Some(format!("{:?}", actual_attributes).into()),
                     mismatch: format!("Expected {} attribute(s) but received {} attribute(s)",
-                    expected_attributes.len(), actual_attributes.len())});
+                    expected_attributes.len(), actual_attributes.len()),
+                    location: Some(MismatchLocation::new(path, &[]))});
             },
             _ => ()
         }
@@ -226,13 +250,72 @@ fn compare_attributes(
               path: p.to_string(),
               expected: Some(Bytes::from(key.clone())),
               actual: None,
-              mismatch: format!("Expected attribute '{}'='{}' but was missing", key, value)
+              mismatch: format!("Expected attribute '{}'='{}' but was missing", key, value),
+              location: Some(MismatchLocation::new(&p, &[]))
             });
           }
         }
     }
 }
 
+fn compare_attributes_with_each_rules(
+  path: &DocPath,
+  expected_attributes: &BTreeMap<String, String>,
+  actual_attributes: &BTreeMap<String, String>,
+  mismatches: &mut Vec<super::Mismatch>,
+  context: &dyn MatchingContext
+) {
+  let mut values_checked = false;
+  for matcher in context.select_best_matcher(path).rules {
+    let (definition, keys) = match matcher {
+      MatchingRule::EachKey(definition) => (definition, true),
+      MatchingRule::EachValue(definition) => (definition, false),
+      _ => continue
+    };
+    values_checked |= !keys;
+    for sub_matcher in &definition.rules {
+      match sub_matcher {
+        Either::Left(rule) => for (key, value) in actual_attributes {
+          let (expected, actual) = if keys {
+            (String::default(), key)
+          } else {
+            (expected_attributes.get(key).cloned().unwrap_or_else(|| definition.value.clone()), value)
+          };
+          if let Err(err) = expected.matches_with(actual.as_str(), rule, false) {
+            let attribute_path = path.join("@".to_owned() + key);
+            mismatches.push(Mismatch::BodyMismatch {
+              path: attribute_path.to_string(),
+              expected: Some(Bytes::from(expected)),
+              actual: Some(Bytes::from(actual.clone())),
+              mismatch: err.to_string(),
+              location: Some(MismatchLocation::new(&attribute_path, &[rule.clone()]))
+            });
+          }
+        },
+        Either::Right(reference) => mismatches.push(Mismatch::BodyMismatch {
+          path: path.to_string(),
+          expected: Some(format!("{:?}", expected_attributes).into()),
+          actual: Some(format!("{:?}", actual_attributes).into()),
+          mismatch: format!("Expected a matching rule, found an unresolved reference '{}'", reference.name),
+          location: Some(MismatchLocation::new(path, &[]))
+        })
+      }
+    }
+  }
+
+  // With only an eachKey rule, the values of the attributes in both elements still need to match
+  if !values_checked {
+    for (key, value) in expected_attributes {
+      if let Some(actual_value) = actual_attributes.get(key) {
+        let p = path.join("@".to_owned() + key);
+        if let Err(m) = compare_value(&p, value, actual_value, context) {
+          mismatches.extend_from_slice(&m);
+        }
+      }
+    }
+  }
+}
+
 fn children<'a>(element: &Element<'a>) -> Vec<Element<'a>> {
   element.children().iter().cloned()
     .map(|child| child.element())
@@ -251,15 +334,22 @@ fn compare_children(
   mismatches: &mut Vec<super::Mismatch>,
   context: &dyn MatchingContext
 ) {
-  let expected_children = children(expected);
-  let actual_children = children(actual);
+  let mut expected_children = children(expected);
+  let mut actual_children = children(actual);
+  let limits = context.limits();
+  if limits.items_exceeded(expected_children.len(), actual_children.len()) {
+    mismatches.push(limits.items_mismatch(path, expected_children.len(), actual_children.len()).to_body_mismatch());
+    expected_children.truncate(limits.item_limit());
+    actual_children.truncate(limits.item_limit());
+  }
 
   if expected_children.is_empty() && !actual_children.is_empty() && context.config() == DiffConfig::NoUnexpectedKeys {
     mismatches.push(Mismatch::BodyMismatch {
       path: path.to_string(),
       expected: Some(desc_children(&expected_children).into()),
       actual: Some(desc_children(&actual_children).into()),
-      mismatch: format!("Expected no children but received [{}]", desc_children(&actual_children))
+      mismatch: format!("Expected no children but received [{}]", desc_children(&actual_children)),
+      location: Some(MismatchLocation::new(path, &[]))
     });
   } else {
     let mut expected_children_by_name: BTreeMap<String, Vec<Element>> = btreemap!{};
@@ -296,7 +386,8 @@ fn compare_children(
                   path: p.to_string(),
                   expected: Some(desc_children(&expected_children).into()),
                   actual: Some(desc_children(&actual_children).into()),
-                  mismatch: format!("Unexpected child <{}/>", name(actual.name()))
+                  mismatch: format!("Unexpected child <{}/>", name(actual.name())),
+                  location: Some(MismatchLocation::new(&p, &[]))
                 });
               },
               EitherOrBoth::Left(expected) => {
@@ -304,7 +395,8 @@ fn compare_children(
                   path: p.to_string(),
                   expected: Some(desc_children(&expected_children.clone()).into()),
                   actual: Some(desc_children(&actual_children.clone()).into()),
-                  mismatch: format!("Expected child <{}/> but was missing", name(expected.name()))
+                  mismatch: format!("Expected child <{}/> but was missing", name(expected.name())),
+                  location: Some(MismatchLocation::new(&p, &[]))
                 });
               },
               EitherOrBoth::Both(expected, actual) => {
@@ -318,7 +410,8 @@ fn compare_children(
           path: path.to_string(),
           expected: Some(desc_children(&expected_children.clone()).into()),
           actual: Some(desc_children(&actual_children.clone()).into()),
-          mismatch: format!("Unexpected child <{}/>", key)
+          mismatch: format!("Unexpected child <{}/>", key),
+          location: Some(MismatchLocation::new(path, &[]))
         });
       }
     }
@@ -329,7 +422,8 @@ fn compare_children(
           path: path.to_string(),
           expected: Some(desc_children(&expected_children.clone()).into()),
           actual: Some(desc_children(&actual_children.clone()).into()),
-          mismatch: format!("Expected child <{}/> but was missing", key)
+          mismatch: format!("Expected child <{}/> but was missing", key),
+          location: Some(MismatchLocation::new(path, &[]))
         });
       }
     }
@@ -352,8 +446,13 @@ fn compare_text(
         .map(|child| child.text().unwrap().text().trim())
         .collect::<String>();
     let p = path.join("#text");
-    let matcher_result = if context.matcher_is_defined(&p) {
-      match_values(&p, &context.select_best_matcher(&p), expected_text.trim(), actual_text.trim())
+    let rule_list = if context.matcher_is_defined(&p) {
+      Some(context.select_best_matcher(&p))
+    } else {
+      None
+    };
+    let matcher_result = if let Some(rule_list) = &rule_list {
+      match_values(&p, rule_list, expected_text.trim(), actual_text.trim())
     } else {
       expected_text.matches_with(actual_text.trim(), &MatchingRule::Equality, false)
         .map_err(|err| vec![err.to_string()])
@@ -361,12 +460,14 @@ fn compare_text(
     debug!("Comparing text '{}' to '{}' at path '{}' -> {:?}", expected_text, actual_text,
         path.to_string(), matcher_result);
     if let Err(messages) = matcher_result {
+      let rules = rule_list.map(|rule_list| rule_list.rules).unwrap_or_default();
       for message in messages {
         mismatches.push(Mismatch::BodyMismatch {
           path: p.to_string(),
           expected: Some(expected_text.clone().into()),
           actual: Some(actual_text.clone().into()),
-          mismatch: message.clone()
+          mismatch: message.clone(),
+          location: Some(MismatchLocation::new(&p, &rules))
         })
       }
     }
@@ -378,19 +479,26 @@ fn compare_value(
   actual: &str,
   context: &dyn MatchingContext
 ) -> Result<(), Vec<Mismatch>> {
-  let matcher_result = if context.matcher_is_defined(path) {
-    match_values(path, &context.select_best_matcher(&path), expected, actual)
+  let rule_list = if context.matcher_is_defined(path) {
+    Some(context.select_best_matcher(path))
+  } else {
+    None
+  };
+  let matcher_result = if let Some(rule_list) = &rule_list {
+    match_values(path, rule_list, expected, actual)
   } else {
     expected.matches_with(actual, &MatchingRule::Equality, false).map_err(|err| vec![err.to_string()])
   };
   debug!("Comparing '{}' to '{}' at path '{}' -> {:?}", expected, actual, path, matcher_result);
+  let rules = rule_list.map(|rule_list| rule_list.rules).unwrap_or_default();
   matcher_result.map_err(|messages| {
     messages.iter().map(|message| {
       Mismatch::BodyMismatch {
         path: path.to_string(),
         expected: Some(expected.to_string().into()),
         actual: Some(actual.to_string().into()),
-        mismatch: message.clone()
+        mismatch: message.clone(),
+        location: Some(MismatchLocation::new(path, &rules))
       }
     }).collect()
   })
@@ -407,6 +515,7 @@ mod tests {
   use pact_models::bodies::OptionalBody;
   use pact_models::matchingrules;
   use pact_models::matchingrules::MatchingRule;
+  use pact_models::matchingrules::expressions::{MatchingRuleDefinition, ValueType};
   use pact_models::request::Request;
 
   use crate::{CoreMatchingContext, DiffConfig};
@@ -466,7 +575,8 @@ mod tests {
       path: s!("$"),
       expected: Some(Bytes::new()),
       actual: Some(Bytes::new()),
-      mismatch: s!("")
+      mismatch: s!(""),
+      location: None
     }));
   }
 
@@ -479,7 +589,8 @@ mod tests {
       path: s!("$"),
       expected: expected.body.value(),
       actual: actual.body.value(),
-      mismatch: s!("")
+      mismatch: s!(""),
+      location: None
     } ]));
   }
 
@@ -492,7 +603,8 @@ mod tests {
       path: s!("$"),
       expected: expected.body.value(),
       actual: actual.body.value(),
-      mismatch: s!("")
+      mismatch: s!(""),
+      location: None
     } ]));
   }
 
@@ -555,7 +667,7 @@ mod tests {
     let result = match_xml(&expected, &actual, &CoreMatchingContext::with_config(DiffConfig::AllowUnexpectedKeys));
     expect!(mismatch_message(&result)).to(be_equal_to(s!("Expected 'foo' to be equal to 'bar'")));
     expect!(result).to(be_err().value(vec![ Mismatch::BodyMismatch { path: s!("$.foo"), expected: Some("foo".into()),
-        actual: Some("bar".into()), mismatch: s!("") } ]));
+        actual: Some("bar".into()), mismatch: s!(""), location: None } ]));
   }
 
   #[test]
@@ -584,15 +696,15 @@ mod tests {
     let mismatch = mismatches[0].clone();
     expect!(&mismatch).to(be_equal_to(&Mismatch::BodyMismatch { path: "$.blah".to_string(),
         expected: Some("{\"a\": \"c\", \"c\": \"b\"}".into()),
-        actual: Some("{\"a\": \"b\"}".into()), mismatch: "".to_string()}));
+        actual: Some("{\"a\": \"b\"}".into()), mismatch: "".to_string(), location: None}));
     expect!(mismatch.description()).to(be_equal_to("$.blah -> Expected at least 2 attribute(s) but received 1 attribute(s)".to_string()));
     let mismatch = mismatches[1].clone();
     expect!(&mismatch).to(be_equal_to(&Mismatch::BodyMismatch { path: "$.blah['@a']".to_string(), expected: Some("c".into()),
-        actual: Some("b".into()), mismatch: "".to_string()}));
+        actual: Some("b".into()), mismatch: "".to_string(), location: None}));
     expect!(mismatch.description()).to(be_equal_to("$.blah['@a'] -> Expected 'b' to be equal to 'c'".to_string()));
     let mismatch = mismatches[2].clone();
     expect!(&mismatch).to(be_equal_to(&Mismatch::BodyMismatch { path: "$.blah['@c']".to_string(), expected: Some("c".into()),
-        actual: None, mismatch: "".to_string() }));
+        actual: None, mismatch: "".to_string(), location: None }));
     expect!(mismatch.description()).to(be_equal_to("$.blah['@c'] -> Expected attribute \'c\'=\'b\' but was missing".to_string()));
   }
 
@@ -607,7 +719,7 @@ mod tests {
     let result = match_xml(&expected, &actual, &CoreMatchingContext::with_config(DiffConfig::NoUnexpectedKeys));
     expect!(mismatch_message(&result)).to(be_equal_to(s!("Did not expect any attributes but received {\"a\": \"b\", \"c\": \"d\"}")));
     expect!(result).to(be_err().value(vec![ Mismatch::BodyMismatch { path: s!("$.blah"), expected: Some("{}".into()),
-      actual: Some("{\"a\": \"b\", \"c\": \"d\"}".into()), mismatch: s!("") } ]));
+      actual: Some("{\"a\": \"b\", \"c\": \"d\"}".into()), mismatch: s!(""), location: None } ]));
   }
 
   #[test]
@@ -633,7 +745,7 @@ mod tests {
     let result = match_xml(&expected, &actual, &CoreMatchingContext::with_config(DiffConfig::NoUnexpectedKeys));
     expect!(mismatch_message(&result)).to(be_equal_to(s!("Expected 1 attribute(s) but received 2 attribute(s)")));
     expect!(result).to(be_err().value(vec![ Mismatch::BodyMismatch { path: s!("$.foo"), expected: Some("{\"something\": \"100\"}".into()),
-        actual: Some("{\"something\": \"100\", \"somethingElse\": \"101\"}".into()), mismatch: s!("") } ]));
+        actual: Some("{\"something\": \"100\", \"somethingElse\": \"101\"}".into()), mismatch: s!(""), location: None } ]));
   }
 
   #[test]
@@ -647,7 +759,7 @@ mod tests {
     let result = match_xml(&expected, &actual, &CoreMatchingContext::with_config(DiffConfig::NoUnexpectedKeys));
     expect!(mismatch_message(&result)).to(be_equal_to("Expected attribute \'somethingElse\'=\'100\' but was missing".to_string()));
     expect!(result).to(be_err().value(vec![ Mismatch::BodyMismatch { path: "$.foo['@somethingElse']".to_string(), expected: Some("somethingElse".into()),
-        actual: None, mismatch: "".to_string() } ]));
+        actual: None, mismatch: "".to_string(), location: None } ]));
   }
 
   #[test]
@@ -661,7 +773,7 @@ mod tests {
     let result = match_xml(&expected.clone(), &actual.clone(), &CoreMatchingContext::with_config(DiffConfig::NoUnexpectedKeys));
     expect!(mismatch_message(&result)).to(be_equal_to("Expected '101' to be equal to '100'".to_string()));
     expect!(result).to(be_err().value(vec![ Mismatch::BodyMismatch { path: "$.foo['@somethingElse']".to_string(), expected: Some("100".into()),
-        actual: Some("101".into()), mismatch: "".to_string() } ]));
+        actual: Some("101".into()), mismatch: "".to_string(), location: None } ]));
 
     let result = match_xml(&expected, &actual, &CoreMatchingContext::new(DiffConfig::NoUnexpectedKeys, &matchingrules!{
       "body" => {
@@ -685,7 +797,8 @@ mod tests {
       path: s!("$.foo"),
       expected: Some(Bytes::new()),
       actual: Some("bar".into()),
-      mismatch: s!("")
+      mismatch: s!(""),
+      location: None
     } ]));
   }
 
@@ -727,7 +840,8 @@ mod tests {
       path: s!("$.foo"),
       expected: Some("bar".into()),
       actual: Some(Bytes::new()),
-      mismatch: s!("")
+      mismatch: s!(""),
+      location: None
     } ]));
   }
 
@@ -743,7 +857,7 @@ mod tests {
     expect!(mismatch_message(&result)).to(be_equal_to(s!("Expected child <four/> but was missing")));
     expect!(result).to(be_err().value(vec![ Mismatch::BodyMismatch { path: s!("$.foo"),
         expected: Some("one, two, three, four".into()),
-        actual: Some("one, two, three".into()), mismatch: s!("") } ]));
+        actual: Some("one, two, three".into()), mismatch: s!(""), location: None } ]));
   }
 
   #[test]
@@ -760,12 +874,12 @@ mod tests {
     let mismatch = mismatches[0].clone();
     expect!(&mismatch).to(be_equal_to(&Mismatch::BodyMismatch { path: s!("$.foo"),
       expected: Some("one, two, three, three".into()),
-      actual: Some("one, two, three, four".into()), mismatch: s!("")}));
+      actual: Some("one, two, three, four".into()), mismatch: s!(""), location: None}));
     expect!(mismatch.description()).to(be_equal_to(s!("$.foo -> Unexpected child <four/>")));
     let mismatch = mismatches[1].clone();
     expect!(&mismatch).to(be_equal_to(&Mismatch::BodyMismatch { path: s!("$.foo.three"),
       expected: Some("three, three".into()),
-      actual: Some("one, two, three, four".into()), mismatch: s!("")}));
+      actual: Some("one, two, three, four".into()), mismatch: s!(""), location: None}));
     expect!(mismatch.description()).to(be_equal_to(s!("$.foo.three -> Expected child <three/> but was missing")));
   }
 
@@ -817,7 +931,7 @@ mod tests {
     expect!(mismatch_message(&result)).to(be_equal_to(s!("Expected 'hello mars' to be equal to 'hello world'")));
     expect!(result).to(be_err().value(vec![ Mismatch::BodyMismatch { path: "$.foo['#text']".to_string(),
         expected: Some("hello world".into()),
-        actual: Some("hello mars".into()), mismatch: "".to_string() } ]));
+        actual: Some("hello mars".into()), mismatch: "".to_string(), location: None } ]));
 
     let result = match_xml(&expected, &actual, &CoreMatchingContext::new(DiffConfig::AllowUnexpectedKeys, &matchingrules!{
         "body" => {
@@ -839,7 +953,7 @@ mod tests {
     expect!(mismatch_message(&result)).to(be_equal_to("Expected 'hellomars' to be equal to 'helloworld'".to_string()));
     expect!(result).to(be_err().value(vec![ Mismatch::BodyMismatch { path: "$.foo['#text']".to_string(),
         expected: Some("helloworld".into()),
-        actual: Some("hellomars".into()), mismatch: "".to_string() } ]));
+        actual: Some("hellomars".into()), mismatch: "".to_string(), location: None } ]));
 
     let result = match_xml(&expected, &actual, &CoreMatchingContext::new(DiffConfig::AllowUnexpectedKeys, &matchingrules!{
       "body" => {
@@ -972,7 +1086,8 @@ mod tests {
       path: "$['urn:other:blah']".to_string(),
       expected: Some("urn:other:blah".into()),
       actual: Some("urn:ns:blah".into()),
-      mismatch: "Expected 'urn:other:blah' to be equal to 'urn:ns:blah'".to_string()
+      mismatch: "Expected 'urn:other:blah' to be equal to 'urn:ns:blah'".to_string(),
+      location: None
     } ]));
   }
 
@@ -985,7 +1100,8 @@ mod tests {
       path: "$['urn:other:blah']".to_string(),
       expected: Some("urn:other:blah".into()),
       actual: Some("blah".into()),
-      mismatch: "Expected 'urn:other:blah' to be equal to 'blah'".to_string()
+      mismatch: "Expected 'urn:other:blah' to be equal to 'blah'".to_string(),
+      location: None
     } ]));
   }
 
@@ -998,7 +1114,8 @@ mod tests {
       path: "$.blah".to_string(),
       expected: Some("blah".into()),
       actual: Some("urn:ns:blah".into()),
-      mismatch: "Expected 'blah' to be equal to 'urn:ns:blah'".to_string()
+      mismatch: "Expected 'blah' to be equal to 'urn:ns:blah'".to_string(),
+      location: None
     } ]));
   }
 
@@ -1019,7 +1136,8 @@ mod tests {
       path: "$.foo['@urn:b:something']".to_string(),
       expected: Some("urn:b:something".into()),
       actual: None,
-      mismatch: "Expected attribute 'urn:b:something'='100' but was missing".to_string()
+      mismatch: "Expected attribute 'urn:b:something'='100' but was missing".to_string(),
+      location: None
     } ]));
   }
 
@@ -1095,17 +1213,17 @@ mod tests {
     let mismatch = mismatches[0].clone();
     expect!(&mismatch).to(be_equal_to(&Mismatch::BodyMismatch { path: s!("$.animals.cat"),
       expected: Some("cat".into()),
-      actual: Some("dog, dog, cat, cat, cat, wolf".into()), mismatch: s!("")}));
+      actual: Some("dog, dog, cat, cat, cat, wolf".into()), mismatch: s!(""), location: None}));
     expect!(mismatch.description()).to(be_equal_to(s!("$.animals.cat -> Unexpected child <cat/>")));
     let mismatch = mismatches[1].clone();
     expect!(&mismatch).to(be_equal_to(&Mismatch::BodyMismatch { path: s!("$.animals.cat"),
       expected: Some("cat".into()),
-      actual: Some("dog, dog, cat, cat, cat, wolf".into()), mismatch: s!("")}));
+      actual: Some("dog, dog, cat, cat, cat, wolf".into()), mismatch: s!(""), location: None}));
     expect!(mismatch.description()).to(be_equal_to(s!("$.animals.cat -> Unexpected child <cat/>")));
     let mismatch = mismatches[2].clone();
     expect!(&mismatch).to(be_equal_to(&Mismatch::BodyMismatch { path: s!("$.animals.dog"),
       expected: Some("dog".into()),
-      actual: Some("dog, dog, cat, cat, cat, wolf".into()), mismatch: "Unexpected child <dog/>".into()}));
+      actual: Some("dog, dog, cat, cat, cat, wolf".into()), mismatch: "Unexpected child <dog/>".into(), location: None}));
     expect!(mismatch.description()).to(be_equal_to(s!("$.animals.dog -> Unexpected child <dog/>")));
   }
 
@@ -1161,8 +1279,91 @@ mod tests {
         path: "$.foo['@somethingElse']".into(),
         expected: Some("true".into()),
         actual: Some("101".into()),
-        mismatch: Default::default()
+        mismatch: Default::default(),
+        location: None
+      }
+    ]));
+  }
+
+  #[test]
+  fn match_attributes_with_each_key_matcher() {
+    let expected = request!(r#"<?xml version="1.0" encoding="UTF-8"?>
+    <foo a-1="x"/>
+    "#);
+    let actual = request!(r#"<?xml version="1.0" encoding="UTF-8"?>
+    <foo a-1="x" b-2="y" BAD="z"/>
+    "#);
+    let matching_rules = matchingrules! {
+      "body" => {
+        "$.foo" => [ MatchingRule::EachKey(MatchingRuleDefinition::new("a-1".to_string(), ValueType::String,
+          MatchingRule::Regex("^[a-z]-\\d$".to_string()), None)) ]
+      }
+    }.rules_for_category("body").unwrap();
+    let result = match_xml(&expected.clone(), &actual.clone(),
+                           &CoreMatchingContext::new(DiffConfig::NoUnexpectedKeys, &matching_rules, &hashmap!{}));
+    expect!(mismatch_message(&result)).to(be_equal_to("Expected 'BAD' to match '^[a-z]-\\d$'".to_string()));
+    expect!(result).to(be_err().value(vec![
+      Mismatch::BodyMismatch {
+        path: "$.foo['@BAD']".into(),
+        expected: Some("".into()),
+        actual: Some("BAD".into()),
+        mismatch: Default::default(),
+        location: None
+      }
+    ]));
+  }
+
+  #[test]
+  fn match_attributes_with_each_value_matcher() {
+    let expected = request!(r#"<?xml version="1.0" encoding="UTF-8"?>
+    <foo a="1"/>
+    "#);
+    let actual = request!(r#"<?xml version="1.0" encoding="UTF-8"?>
+    <foo a="100" b="x" c="20"/>
+    "#);
+    let matching_rules = matchingrules! {
+      "body" => {
+        "$.foo" => [ MatchingRule::EachValue(MatchingRuleDefinition::new("1".to_string(), ValueType::String,
+          MatchingRule::Regex("\\d+".to_string()), None)) ]
+      }
+    }.rules_for_category("body").unwrap();
+    let result = match_xml(&expected.clone(), &actual.clone(),
+                           &CoreMatchingContext::new(DiffConfig::NoUnexpectedKeys, &matching_rules, &hashmap!{}));
+    expect!(mismatch_message(&result)).to(be_equal_to("Expected 'x' to match '\\d+'".to_string()));
+    expect!(result).to(be_err().value(vec![
+      Mismatch::BodyMismatch {
+        path: "$.foo['@b']".into(),
+        expected: Some("1".into()),
+        actual: Some("x".into()),
+        mismatch: Default::default(),
+        location: None
       }
     ]));
   }
+
+  #[test]
+  fn match_xml_records_the_path_and_applied_rules_with_body_mismatches() {
+    let expected = request!(r#"<?xml version="1.0" encoding="UTF-8"?>
+    <foo><item id="100"/><name>Fred</name></foo>
+    "#);
+    let actual = request!(r#"<?xml version="1.0" encoding="UTF-8"?>
+    <foo><item id="abc"/><name>Mary</name></foo>
+    "#);
+    let matching_rules = matchingrules! {
+      "body" => {
+        "$.foo.item['@id']" => [ MatchingRule::Integer ]
+      }
+    }.rules_for_category("body").unwrap();
+    let context = CoreMatchingContext::new(DiffConfig::AllowUnexpectedKeys, &matching_rules, &hashmap!{});
+
+    let mismatches = match_xml(&expected, &actual, &context).unwrap_err();
+    let locations = mismatches.iter().map(|mismatch| match mismatch {
+      Mismatch::BodyMismatch { location, .. } => location.clone(),
+      _ => None
+    }).collect::<Vec<_>>();
+    expect!(locations).to(be_equal_to(vec![
+      Some(MismatchLocation::new(&DocPath::new_unwrap("$.foo.item['@id']"), &[ MatchingRule::Integer ])),
+      Some(MismatchLocation::new(&DocPath::new_unwrap("$.foo.name['#text']"), &[]))
+    ]));
+  }
 }
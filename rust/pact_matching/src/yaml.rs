@@ -0,0 +1,123 @@
+//! Matching functions for YAML documents. YAML bodies are parsed into the same tree as JSON
+//! bodies, and are then compared with the JSON matching functions, so matching rules can be
+//! applied with the same paths as for JSON (i.e. `$.items[*].id`).
+
+use pact_models::http_parts::HttpPart;
+use pact_models::path_exp::DocPath;
+use serde_json::Value;
+use tracing::debug;
+
+use crate::{MatchingContext, Mismatch};
+use crate::json::compare_json;
+
+/// Parses a YAML document into a JSON value. Only documents that can be represented as JSON are
+/// supported (i.e. mapping keys must be strings, numbers or booleans).
+pub fn parse_yaml(data: &[u8]) -> anyhow::Result<Value> {
+  serde_yaml_ng::from_slice(data).map_err(|err| anyhow::anyhow!(err))
+}
+
+/// Matches the expected YAML body to the actual, and returns any mismatches
+pub fn match_yaml(
+  expected: &(dyn HttpPart + Send + Sync),
+  actual: &(dyn HttpPart + Send + Sync),
+  context: &(dyn MatchingContext + Send + Sync)
+) -> Result<(), Vec<Mismatch>> {
+  let expected_yaml = parse_yaml(&expected.body().value().unwrap_or_default());
+  let actual_yaml = parse_yaml(&actual.body().value().unwrap_or_default());
+  debug!("match_yaml: expected = {:?}, actual = {:?}", expected_yaml, actual_yaml);
+
+  match (expected_yaml, actual_yaml) {
+    (Ok(expected_yaml), Ok(actual_yaml)) => {
+      compare_json(&DocPath::root(), &expected_yaml, &actual_yaml, context)
+        .map_err(|mismatches| mismatches.iter().map(|mismatch| mismatch.to_body_mismatch()).collect())
+    }
+    (expected_yaml, actual_yaml) => {
+      let mut mismatches = vec![];
+      if let Err(e) = expected_yaml {
+        mismatches.push(Mismatch::BodyMismatch {
+          path: "$".to_string(),
+          expected: expected.body().value(),
+          actual: actual.body().value(),
+          mismatch: format!("Failed to parse the expected body: '{}'", e),
+          location: None
+        });
+      }
+      if let Err(e) = actual_yaml {
+        mismatches.push(Mismatch::BodyMismatch {
+          path: "$".to_string(),
+          expected: expected.body().value(),
+          actual: actual.body().value(),
+          mismatch: format!("Failed to parse the actual body: '{}'", e),
+          location: None
+        });
+      }
+      Err(mismatches)
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use bytes::Bytes;
+  use expectest::prelude::*;
+  use maplit::hashmap;
+  use pact_models::bodies::OptionalBody;
+  use pact_models::matchingrules::MatchingRule;
+  use pact_models::matchingrules_list;
+  use pact_models::request::Request;
+
+  use crate::{CoreMatchingContext, DiffConfig, Mismatch};
+
+  use super::*;
+
+  fn request(body: &str) -> Request {
+    Request {
+      body: OptionalBody::Present(Bytes::from(body.to_string()), Some("application/yaml".into()), None),
+      .. Request::default()
+    }
+  }
+
+  #[test]
+  fn match_yaml_with_equal_documents() {
+    let expected = request("name: Fred\nitems:\n  - id: 1\n  - id: 2\n");
+    let actual = request("items: [{id: 1}, {id: 2}]\nname: 'Fred'\n");
+    expect!(match_yaml(&expected, &actual, &CoreMatchingContext::default())).to(be_ok());
+  }
+
+  #[test]
+  fn match_yaml_with_different_values() {
+    let expected = request("name: Fred\nitems:\n  - id: 1\n");
+    let actual = request("name: Mary\nitems:\n  - id: 1\n");
+    let mismatches = match_yaml(&expected, &actual, &CoreMatchingContext::default()).unwrap_err();
+    expect!(mismatches.len()).to(be_equal_to(1));
+    expect!(mismatches[0].description()).to(
+      be_equal_to("$.name -> Expected 'Mary' (String) to be equal to 'Fred' (String)"));
+  }
+
+  #[test]
+  fn match_yaml_with_matching_rules() {
+    let expected = request("name: Fred\nitems:\n  - id: 1\n");
+    let actual = request("name: Mary\nitems:\n  - id: 100\n  - id: 200\n");
+    let rules = matchingrules_list! {
+      "body";
+      "$.name" => [ MatchingRule::Type ],
+      "$.items" => [ MatchingRule::MinType(1) ],
+      "$.items[*].id" => [ MatchingRule::Integer ]
+    };
+    let context = CoreMatchingContext::new(DiffConfig::AllowUnexpectedKeys, &rules, &hashmap!{});
+    expect!(match_yaml(&expected, &actual, &context)).to(be_ok());
+
+    let actual = request("name: Mary\nitems:\n  - id: one\n");
+    expect!(match_yaml(&expected, &actual, &context)).to(be_err());
+  }
+
+  #[test]
+  fn match_yaml_with_invalid_document() {
+    let expected = request("name: Fred\n");
+    let actual = request("name: [Fred\n");
+    let mismatches = match_yaml(&expected, &actual, &CoreMatchingContext::default()).unwrap_err();
+    expect!(mismatches.len()).to(be_equal_to(1));
+    expect!(matches!(&mismatches[0], Mismatch::BodyMismatch { mismatch, .. }
+      if mismatch.starts_with("Failed to parse the actual body"))).to(be_true());
+  }
+}
@@ -1,9 +1,14 @@
 //! Module for handling interaction content (bodies)
 
+use std::borrow::Cow;
 use std::fmt::{Display, Formatter};
+use std::fs::File;
 use std::hash::{Hash, Hasher};
+use std::io::{self, Cursor, Read, Write};
 use std::mem;
+use std::path::{Path, PathBuf};
 use std::str::from_utf8;
+use std::sync::{Arc, OnceLock};
 
 use base64::Engine;
 use base64::engine::general_purpose::STANDARD as BASE64;
@@ -14,8 +19,64 @@ use tracing::warn;
 
 use crate::content_types::{ContentType, ContentTypeHint};
 
+/// Body contents that are stored in a file, and are only loaded into memory when they are needed.
+/// Clones of the body share the loaded contents, so they are only loaded once.
+#[derive(Debug, Clone)]
+pub struct FileBody {
+  path: PathBuf,
+  len: u64,
+  contents: Arc<OnceLock<Bytes>>
+}
+
+impl FileBody {
+  /// Creates a body backed by the given file. Returns an error if the file metadata can not be read.
+  pub fn new(path: impl AsRef<Path>) -> io::Result<Self> {
+    let path = path.as_ref().to_path_buf();
+    let len = path.metadata()?.len();
+    Ok(FileBody { path, len, contents: Default::default() })
+  }
+
+  /// Path to the file with the contents
+  pub fn path(&self) -> &Path {
+    &self.path
+  }
+
+  /// Length of the contents in bytes (when the body was created)
+  pub fn len(&self) -> u64 {
+    self.len
+  }
+
+  /// If the file was empty when the body was created
+  pub fn is_empty(&self) -> bool {
+    self.len == 0
+  }
+
+  /// If the contents of the file have been loaded into memory
+  pub fn is_loaded(&self) -> bool {
+    self.contents.get().is_some()
+  }
+
+  /// Returns the contents of the file, loading them into memory if they have not already been
+  /// loaded
+  pub fn contents(&self) -> io::Result<&Bytes> {
+    if let Some(contents) = self.contents.get() {
+      return Ok(contents);
+    }
+    let contents = Bytes::from(std::fs::read(&self.path)?);
+    Ok(self.contents.get_or_init(|| contents))
+  }
+
+  /// Returns a reader for the contents. This does not load the contents into memory.
+  pub fn reader(&self) -> io::Result<Box<dyn Read + Send>> {
+    match self.contents.get() {
+      Some(contents) => Ok(Box::new(Cursor::new(contents.clone()))),
+      None => Ok(Box::new(File::open(&self.path)?))
+    }
+  }
+}
+
 /// Enum that defines the four main states that a body of a request and response can be in a pact
-/// file.
+/// file, as well as bodies that are backed by a file.
 #[derive(Debug, Clone, Deserialize, Serialize)]
 #[serde(untagged)]
 pub enum OptionalBody {
@@ -27,32 +88,65 @@ pub enum OptionalBody {
   /// from null values. It is treated as `Empty`.
   Null,
   /// A non-empty body that is present in the pact file.
-  Present(Bytes, Option<ContentType>, Option<ContentTypeHint>)
+  Present(Bytes, Option<ContentType>, Option<ContentTypeHint>),
+  /// A non-empty body with the contents stored in a file. The contents are loaded when they are
+  /// first needed. This is treated the same as `Present`. File backed bodies are never read from
+  /// or written with serde, they need to be converted with `loaded` first.
+  #[serde(skip)]
+  File(FileBody, Option<ContentType>, Option<ContentTypeHint>)
 }
 
 impl OptionalBody {
 
   /// If the body is present in the pact file and not empty or null.
   pub fn is_present(&self) -> bool {
-    matches!(*self, OptionalBody::Present(_, _, _))
+    matches!(*self, OptionalBody::Present(_, _, _) | OptionalBody::File(_, _, _))
   }
 
-  /// Returns the body if present, otherwise returns None.
-  pub fn value(&self) -> Option<Bytes> {
+  /// If the body contents are stored in a file
+  pub fn is_file(&self) -> bool {
+    matches!(*self, OptionalBody::File(_, _, _))
+  }
+
+  /// Returns a reference to the body contents if present, otherwise returns None. File backed
+  /// bodies will be loaded into memory. If the contents of a file backed body can not be loaded,
+  /// the error is logged and None is returned (use `try_value_ref` to get the error).
+  pub fn value_ref(&self) -> Option<&Bytes> {
+    match self.try_value_ref() {
+      Ok(value) => value,
+      Err(err) => {
+        warn!("{}", err);
+        None
+      }
+    }
+  }
+
+  /// Returns a reference to the body contents if present, otherwise returns None. File backed
+  /// bodies will be loaded into memory, and an error is returned if the file can not be read.
+  pub fn try_value_ref(&self) -> io::Result<Option<&Bytes>> {
     match self {
-      OptionalBody::Present(s, _, _) => Some(s.clone()),
-      _ => None
+      OptionalBody::Present(s, _, _) => Ok(Some(s)),
+      OptionalBody::File(file, _, _) => file.contents()
+        .map(Some)
+        .map_err(|err| io::Error::new(err.kind(),
+          format!("Failed to load the body from '{}' - {}", file.path().display(), err))),
+      _ => Ok(None)
     }
   }
 
+  /// Returns the body if present, otherwise returns None.
+  pub fn value(&self) -> Option<Bytes> {
+    self.value_ref().cloned()
+  }
+
   /// Returns the body as a UTF-8 string if present and is a textual form, otherwise returns None.
   pub fn value_as_string(&self) -> Option<String> {
     match self {
-      OptionalBody::Present(s, ct, hint) => {
+      OptionalBody::Present(_, ct, hint) | OptionalBody::File(_, ct, hint) => {
         if Self::is_text(ct, hint) {
-          from_utf8(s)
+          self.value_ref()
+            .and_then(|s| from_utf8(s).ok())
             .map(|s| s.to_string())
-            .ok()
         } else {
           None
         }
@@ -74,10 +168,9 @@ impl OptionalBody {
   /// Returns the body if present as a UTF-8 string, otherwise returns the empty string.
   #[deprecated(since = "0.4.2", note = "This does not deal with binary bodies, use value_as_str or display_string instead")]
   pub fn str_value(&self) -> &str {
-    match self {
-      OptionalBody::Present(s, _, _) => from_utf8(s).unwrap_or(""),
-      _ => ""
-    }
+    self.value_ref()
+      .and_then(|s| from_utf8(s).ok())
+      .unwrap_or("")
   }
 
   /// For text bodies (are present and have either a content type hint of TEXT or a content type
@@ -85,11 +178,12 @@ impl OptionalBody {
   /// present, will display the first 32 bytes in hexidecimal form. Otherwise returns the empty string.
   pub fn display_string(&self) -> String {
     match self {
-      OptionalBody::Present(s, ct, hint) => {
+      OptionalBody::Present(_, ct, hint) | OptionalBody::File(_, ct, hint) => {
         if Self::is_text(ct, hint) {
-          from_utf8(s)
+          self.value_ref()
+            .and_then(|s| from_utf8(s).ok())
             .map(|s| s.to_string())
-            .unwrap_or_else(|_| self.display_bytes(32))
+            .unwrap_or_else(|| self.display_bytes(32))
         } else {
           self.display_bytes(32)
         }
@@ -98,10 +192,49 @@ impl OptionalBody {
     }
   }
 
+  /// Returns this body with the contents of a file backed body loaded into memory (as a `Present`
+  /// body). Other bodies are returned as is.
+  pub fn loaded(&self) -> Cow<'_, OptionalBody> {
+    match self {
+      OptionalBody::File(_, ct, hint) => Cow::Owned(match self.value() {
+        Some(bytes) => OptionalBody::Present(bytes, ct.clone(), *hint),
+        None => OptionalBody::Missing
+      }),
+      _ => Cow::Borrowed(self)
+    }
+  }
+
+  /// Writes the body contents to the file at the given path, and returns a file backed body for
+  /// it. Bodies that are not present are returned as is.
+  pub fn write_to_file(&self, path: impl AsRef<Path>) -> io::Result<OptionalBody> {
+    match self {
+      OptionalBody::Present(_, ct, hint) | OptionalBody::File(_, ct, hint) => {
+        let mut file = File::create(path.as_ref())?;
+        self.write_to(&mut file)?;
+        file.flush()?;
+        Ok(OptionalBody::File(FileBody::new(path)?, ct.clone(), *hint))
+      }
+      _ => Ok(self.clone())
+    }
+  }
+
+  /// Writes the body contents to the writer, returning the number of bytes written. The contents
+  /// of file backed bodies are copied from the file, without loading them into memory.
+  pub fn write_to(&self, writer: &mut dyn Write) -> io::Result<u64> {
+    match self {
+      OptionalBody::Present(bytes, _, _) => {
+        writer.write_all(bytes)?;
+        Ok(bytes.len() as u64)
+      }
+      OptionalBody::File(file, _, _) => io::copy(&mut file.reader()?, writer),
+      _ => Ok(0)
+    }
+  }
+
   /// If the body has a content type associated to it
   pub fn has_content_type(&self) -> bool {
     match self {
-      OptionalBody::Present(_, content_type, _) => content_type.is_some(),
+      OptionalBody::Present(_, content_type, _) | OptionalBody::File(_, content_type, _) => content_type.is_some(),
       _ => false
     }
   }
@@ -109,7 +242,7 @@ impl OptionalBody {
   /// Parsed content type of the body
   pub fn content_type(&self) -> Option<ContentType> {
     match self {
-      OptionalBody::Present(_, content_type, _) =>
+      OptionalBody::Present(_, content_type, _) | OptionalBody::File(_, content_type, _) =>
         content_type.clone(),
       _ => None
     }
@@ -119,6 +252,7 @@ impl OptionalBody {
   pub fn with_content_type(&self, content_type: Option<ContentType>) -> Self {
     match self {
       OptionalBody::Present(b, _, h) => OptionalBody::Present(b.clone(), content_type, h.clone()),
+      OptionalBody::File(f, _, h) => OptionalBody::File(f.clone(), content_type, *h),
       OptionalBody::Missing => OptionalBody::Missing,
       OptionalBody::Empty => OptionalBody::Empty,
       OptionalBody::Null => OptionalBody::Null,
@@ -130,14 +264,21 @@ impl OptionalBody {
     match self {
       OptionalBody::Present(b, ct, h) =>
         OptionalBody::Present(b.clone(), ct.clone().or(content_type), h.clone()),
+      OptionalBody::File(f, ct, h) =>
+        OptionalBody::File(f.clone(), ct.clone().or(content_type), *h),
       OptionalBody::Missing => OptionalBody::Missing,
       OptionalBody::Empty => OptionalBody::Empty,
       OptionalBody::Null => OptionalBody::Null,
     }
   }
 
-  /// Converts this body into a V4 Pact file JSON format
+  /// Converts this body into a V4 Pact file JSON format. File backed bodies need to be loaded into
+  /// memory, as the contents are embedded in the JSON. If the file can not be read, the error is
+  /// logged and the body is treated as missing.
   pub fn to_v4_json(&self) -> Value {
+    if self.is_file() {
+      return self.loaded().to_v4_json();
+    }
     match self {
       OptionalBody::Present(bytes, content_type, ct_override) => {
         let content_type = content_type.as_ref().cloned().unwrap_or_default();
@@ -181,13 +322,13 @@ impl OptionalBody {
 
   /// Set the content type of the body. If the body is missing or empty, this is a no-op.
   pub fn set_content_type(&mut self, content_type: &ContentType) {
-    if let OptionalBody::Present(_, ct, _) = self {
+    if let OptionalBody::Present(_, ct, _) | OptionalBody::File(_, ct, _) = self {
        *ct = Some(content_type.clone());
     }
   }
 
   pub(crate) fn display_bytes(&self, max_bytes: usize) -> String {
-    if let OptionalBody::Present(bytes, _, _) = self {
+    if let Some(bytes) = self.value_ref() {
       if bytes.len() <= max_bytes {
         let b_str: String = bytes.iter().map(|b| format!("{:0X}", b)).collect();
         format!("{} ({} bytes)", b_str, bytes.len())
@@ -254,6 +395,13 @@ impl Display for OptionalBody {
           write!(f, "Present({} bytes)", s.len())
         }
       }
+      OptionalBody::File(ref file, ref content_type, _) => {
+        if let Some(content_type) = content_type {
+          write!(f, "File({}, {} bytes, {})", file.path().display(), file.len(), content_type)
+        } else {
+          write!(f, "File({}, {} bytes)", file.path().display(), file.len())
+        }
+      }
     }
   }
 }
@@ -268,6 +416,8 @@ impl PartialEq for OptionalBody {
   fn eq(&self, other: &Self) -> bool {
     match (self, other) {
       (OptionalBody::Present(b1, _, _), OptionalBody::Present(b2, _, _)) => b1.eq(b2),
+      (OptionalBody::Present(_, _, _) | OptionalBody::File(_, _, _), OptionalBody::Present(_, _, _) | OptionalBody::File(_, _, _)) =>
+        self.value_ref() == other.value_ref(),
       (_, _) => mem::discriminant(self) == mem::discriminant(other)
     }
   }
@@ -277,6 +427,12 @@ impl Eq for OptionalBody { }
 
 impl Hash for OptionalBody {
   fn hash<H: Hasher>(&self, state: &mut H) {
+    if self.is_file() {
+      // File backed bodies are equal to present bodies with the same contents, so need to have
+      // the same hash. This requires loading the contents into memory, as hashing the file in
+      // chunks would give a different result with some hashers.
+      return self.loaded().hash(state);
+    }
     mem::discriminant(self).hash(state);
     if let OptionalBody::Present(b, _, _) = self {
       b.hash(state);
@@ -291,10 +447,11 @@ mod tests {
   use bytes::Bytes;
   use expectest::prelude::*;
   use hashers::fx_hash::FxHasher;
+  use serde_json::json;
 
   use crate::content_types::{ContentType, ContentTypeHint, JSON, TEXT};
 
-  use super::OptionalBody;
+  use super::{FileBody, OptionalBody};
 
   #[test]
   fn display_tests() {
@@ -396,4 +553,98 @@ mod tests {
     assert_ne!(b1, b4);
     assert_ne!(b4, b7);
   }
+
+  fn temp_file(name: &str, contents: &str) -> std::path::PathBuf {
+    let path = std::env::temp_dir().join(format!("pact-models-{}-{}", std::process::id(), name));
+    std::fs::write(&path, contents).unwrap();
+    path
+  }
+
+  #[test]
+  fn file_body_is_loaded_when_needed() {
+    let path = temp_file("file_body_is_loaded_when_needed", "{\"a\": 100}");
+    let body = OptionalBody::File(FileBody::new(&path).unwrap(), Some(JSON.clone()), None);
+    let clone = body.clone();
+
+    expect!(body.is_present()).to(be_true());
+    expect!(format!("{}", body)).to(be_equal_to(format!("File({}, 10 bytes, application/json)", path.display())));
+    if let OptionalBody::File(file, _, _) = &clone {
+      expect!(file.is_loaded()).to(be_false());
+    }
+    expect!(body.value_as_string()).to(be_some().value("{\"a\": 100}"));
+    if let OptionalBody::File(file, _, _) = &clone {
+      expect!(file.is_loaded()).to(be_true());
+    }
+    expect!(body.to_v4_json()).to(be_equal_to(json!({
+      "content": { "a": 100 },
+      "contentType": "application/json",
+      "encoded": false
+    })));
+
+    std::fs::remove_file(path).unwrap();
+  }
+
+  #[test]
+  fn file_body_equals_present_body_with_the_same_contents() {
+    let path = temp_file("file_body_equals_present_body_with_the_same_contents", "hello");
+    let body = OptionalBody::File(FileBody::new(&path).unwrap(), None, None);
+
+    expect!(&body).to(be_equal_to(&OptionalBody::Present("hello".into(), None, None)));
+    expect!(&body).to_not(be_equal_to(&OptionalBody::Present("hello!".into(), None, None)));
+    expect!(h(&body)).to(be_equal_to(h(&OptionalBody::Present("hello".into(), None, None))));
+    expect!(body.loaded().into_owned()).to(be_equal_to(OptionalBody::Present("hello".into(), None, None)));
+
+    std::fs::remove_file(path).unwrap();
+  }
+
+  #[test]
+  fn file_bodies_with_the_same_path_are_compared_by_their_contents() {
+    let path = temp_file("file_bodies_with_the_same_path_are_compared_by_their_contents", "hello");
+    let body = OptionalBody::File(FileBody::new(&path).unwrap(), None, None);
+    let other = OptionalBody::File(FileBody::new(&path).unwrap(), None, None);
+    expect!(body.value_as_string()).to(be_some().value("hello"));
+    std::fs::write(&path, "hello!").unwrap();
+
+    expect!(&body).to_not(be_equal_to(&other));
+    expect!(h(&body)).to_not(be_equal_to(h(&other)));
+
+    std::fs::remove_file(path).unwrap();
+  }
+
+  #[test]
+  fn file_bodies_can_not_be_deserialized() {
+    let result = serde_json::from_value::<OptionalBody>(json!([{ "path": "/etc/passwd", "len": 10 }, null, null]));
+    expect!(result.map(|body| body.is_file()).unwrap_or(false)).to(be_false());
+  }
+
+  #[test]
+  fn file_body_returns_an_error_if_the_file_can_not_be_read() {
+    let path = temp_file("file_body_returns_an_error_if_the_file_can_not_be_read", "hello");
+    let body = OptionalBody::File(FileBody::new(&path).unwrap(), None, None);
+    std::fs::remove_file(&path).unwrap();
+
+    let result = body.try_value_ref();
+    expect!(result.is_err()).to(be_true());
+    expect!(result.unwrap_err().to_string().starts_with(
+      format!("Failed to load the body from '{}'", path.display()).as_str())).to(be_true());
+    expect!(body.value_ref()).to(be_none());
+    expect!(OptionalBody::Present("hello".into(), None, None).try_value_ref().unwrap()).to(be_some());
+    expect!(OptionalBody::Missing.try_value_ref().unwrap()).to(be_none());
+  }
+
+  #[test]
+  fn write_to_file_test() {
+    let path = std::env::temp_dir().join(format!("pact-models-{}-write_to_file_test", std::process::id()));
+    let body = OptionalBody::Present("some text".into(), Some(TEXT.clone()), None);
+
+    let file_body = body.write_to_file(&path).unwrap();
+    expect!(file_body.is_file()).to(be_true());
+    expect!(file_body.content_type()).to(be_some().value(TEXT.clone()));
+    let mut buffer = vec![];
+    expect!(file_body.write_to(&mut buffer).unwrap()).to(be_equal_to(9));
+    expect!(buffer).to(be_equal_to(b"some text".to_vec()));
+    expect!(OptionalBody::Missing.write_to_file(&path).unwrap()).to(be_equal_to(OptionalBody::Missing));
+
+    std::fs::remove_file(path).unwrap();
+  }
 }
@@ -1,9 +1,10 @@
 //! Module for handling content types
 
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, HashMap};
 use std::convert::TryFrom;
 use std::fmt::{Debug, Display, Formatter};
 use std::str::{from_utf8, FromStr};
+use std::sync::RwLock;
 
 use anyhow::anyhow;
 use itertools::Itertools;
@@ -66,6 +67,49 @@ lazy_static! {
   static ref HTMLREGEXP: Regex = Regex::new(r"^\s*(<!DOCTYPE)|(<HTML>).*").unwrap();
   static ref JSONREGEXP: Regex = Regex::new(r#"^\s*(true|false|null|[0-9]+|"\w*|\{\s*(}|"\w+)|\[\s*)"#).unwrap();
   static ref XMLREGEXP2: Regex = Regex::new(r#"^\s*<\w+\s*(:\w+=["”][^"”]+["”])?.*"#).unwrap();
+
+  /// Content types that have been registered at runtime, keyed by the base content type
+  static ref CONTENT_TYPE_REGISTRY: RwLock<HashMap<String, ContentTypeClass>> = RwLock::new(HashMap::new());
+}
+
+/// Classification of a content type, which determines how bodies with that content type are handled
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ContentTypeClass {
+  /// Handled as JSON
+  Json,
+  /// Handled as XML
+  Xml,
+  /// Handled as YAML
+  Yaml,
+  /// Handled as plain text
+  Text,
+  /// Handled as binary
+  Binary
+}
+
+/// Registers the classification to use for a content type, overriding the built-in rules. Any
+/// attributes on the content type are ignored (i.e. `application/vnd.acme+msgpack` will be used
+/// for `application/vnd.acme+msgpack;version=2`). A wildcard can be used to register all the
+/// content types with a particular suffix (i.e. `application/*+msgpack` or `*/*+msgpack`), which is
+/// useful for vendor media types used for API versioning. Returns an error if the content type is
+/// not valid.
+pub fn register_content_type(content_type: &str, class: ContentTypeClass) -> anyhow::Result<()> {
+  let content_type = ContentType::parse(content_type)?;
+  let key = content_type.registry_key();
+  debug!("Registering content type '{}' as {:?}", key, class);
+  if let Some(previous) = CONTENT_TYPE_REGISTRY.write().unwrap().insert(key.clone(), class) {
+    warn!("Content type '{}' was already registered as {:?}, it has been replaced", key, previous);
+  }
+  Ok(())
+}
+
+/// Removes the classification registered for the content type. Returns true if one was registered.
+pub fn unregister_content_type(content_type: &str) -> bool {
+  match ContentType::parse(content_type) {
+    Ok(content_type) => CONTENT_TYPE_REGISTRY.write().unwrap()
+      .remove(&content_type.registry_key()).is_some(),
+    Err(_) => false
+  }
 }
 
 const KNOWN_TEXT_TYPES: [(&'static str, &'static str); 1] = [
@@ -89,8 +133,34 @@ impl ContentType {
     }
   }
 
+  /// Returns the classification registered for this content type with `register_content_type`
+  pub fn registered_class(&self) -> Option<ContentTypeClass> {
+    let registry = CONTENT_TYPE_REGISTRY.read().unwrap();
+    if registry.is_empty() {
+      None
+    } else {
+      registry.get(&self.registry_key())
+        .or_else(|| self.suffix.as_ref().and_then(|suffix| {
+          let suffix = suffix.to_lowercase();
+          registry.get(&format!("{}/*+{}", self.main_type.to_lowercase(), suffix))
+            .or_else(|| registry.get(&format!("*/*+{}", suffix)))
+        }))
+        .copied()
+    }
+  }
+
+  fn registry_key(&self) -> String {
+    match &self.suffix {
+      Some(suffix) => format!("{}/{}+{}", self.main_type, self.sub_type, suffix),
+      None => format!("{}/{}", self.main_type, self.sub_type)
+    }.to_lowercase()
+  }
+
   /// If it is a JSON type
   pub fn is_json(&self) -> bool {
+    if let Some(class) = self.registered_class() {
+      return class == ContentTypeClass::Json;
+    }
     self.main_type == "application" && (self.sub_type.starts_with("json") ||
       self.suffix.as_ref().unwrap_or(&String::default()) == "json" ||
       self.sub_type == "graphql")
@@ -98,6 +168,9 @@ impl ContentType {
 
   /// If it is a XML type
   pub fn is_xml(&self) -> bool {
+    if let Some(class) = self.registered_class() {
+      return class == ContentTypeClass::Xml;
+    }
     (self.main_type == "application" || self.main_type == "text") && (self.sub_type == "xml" ||
       self.suffix.as_ref().unwrap_or(&String::default()) == "xml")
   }
@@ -107,13 +180,29 @@ impl ContentType {
     (self.main_type == "application" || self.main_type == "text") && self.sub_type == "xml"
   }
 
+  /// If it is a YAML type
+  pub fn is_yaml(&self) -> bool {
+    if let Some(class) = self.registered_class() {
+      return class == ContentTypeClass::Yaml;
+    }
+    (self.main_type == "application" || self.main_type == "text") && (self.sub_type == "yaml" ||
+      self.sub_type == "x-yaml" || self.suffix.as_ref().unwrap_or(&String::default()) == "yaml")
+  }
+
   /// If it is a text type
   pub fn is_text(&self) -> bool {
-    self.main_type == "text" || self.is_xml() || self.is_json() || self.is_known_text_type()
+    if let Some(class) = self.registered_class() {
+      return class != ContentTypeClass::Binary;
+    }
+    self.main_type == "text" || self.is_xml() || self.is_json() || self.is_yaml() ||
+      self.is_known_text_type()
   }
 
   /// If it is a known binary type
   pub fn is_binary(&self) -> bool {
+    if let Some(class) = self.registered_class() {
+      return class == ContentTypeClass::Binary;
+    }
     match self.main_type.as_str() {
       "audio" | "font" | "image" | "video" => true,
       "text" => false,
@@ -349,7 +438,7 @@ mod tests {
   use expectest::prelude::*;
   use maplit::btreemap;
 
-  use super::ContentType;
+  use super::{ContentType, ContentTypeClass, JSON, XML, register_content_type, unregister_content_type};
 
   #[test]
   fn parse_test() {
@@ -444,6 +533,16 @@ mod tests {
     expect!(content_type.is_json()).to(be_true());
   }
 
+  #[test]
+  fn is_yaml_test() {
+    expect!(ContentType::parse("application/yaml").unwrap().is_yaml()).to(be_true());
+    expect!(ContentType::parse("text/yaml").unwrap().is_yaml()).to(be_true());
+    expect!(ContentType::parse("application/x-yaml").unwrap().is_yaml()).to(be_true());
+    expect!(ContentType::parse("application/openapi+yaml").unwrap().is_yaml()).to(be_true());
+    expect!(ContentType::parse("application/yaml").unwrap().is_binary()).to(be_false());
+    expect!(ContentType::parse("application/json").unwrap().is_yaml()).to(be_false());
+  }
+
   #[test]
   fn is_xml_test() {
     let content_type = ContentType::parse("application/atom+xml").unwrap();
@@ -573,4 +672,65 @@ mod tests {
     expect!(content_type2.is_equivalent_to(&content_type3)).to(be_true());
     expect!(content_type2.is_equivalent_to(&content_type4)).to(be_false());
   }
+
+  #[test]
+  fn registered_content_types_override_the_built_in_rules() {
+    let content_type = ContentType::parse("application/vnd.registry-test+msgpack;version=2").unwrap();
+    expect!(content_type.registered_class()).to(be_none());
+    expect!(content_type.is_binary()).to(be_true());
+
+    register_content_type("application/vnd.registry-test+msgpack", ContentTypeClass::Json).unwrap();
+    expect!(content_type.registered_class()).to(be_some().value(ContentTypeClass::Json));
+    expect!(content_type.is_json()).to(be_true());
+    expect!(content_type.is_text()).to(be_true());
+    expect!(content_type.is_binary()).to(be_false());
+    expect!(content_type.is_xml()).to(be_false());
+
+    register_content_type("application/vnd.registry-test+msgpack", ContentTypeClass::Binary).unwrap();
+    expect!(content_type.is_json()).to(be_false());
+    expect!(content_type.is_text()).to(be_false());
+    expect!(content_type.is_binary()).to(be_true());
+
+    expect!(unregister_content_type("application/vnd.registry-test+msgpack")).to(be_true());
+    expect!(unregister_content_type("application/vnd.registry-test+msgpack")).to(be_false());
+    expect!(content_type.registered_class()).to(be_none());
+
+    expect!(register_content_type("not a content type", ContentTypeClass::Text)).to(be_err());
+  }
+
+  #[test]
+  fn vendor_content_types_with_a_suffix_are_handled_as_the_suffix_type() {
+    let content_type = ContentType::parse("application/vnd.acme.v2+json;charset=UTF-8").unwrap();
+    expect!(content_type.is_json()).to(be_true());
+    expect!(content_type.is_text()).to(be_true());
+    expect!(content_type.base_type()).to(be_equal_to(JSON.clone()));
+
+    let content_type = ContentType::parse("application/vnd.acme.v2+xml").unwrap();
+    expect!(content_type.is_xml()).to(be_true());
+    expect!(content_type.is_json()).to(be_false());
+    expect!(content_type.base_type()).to(be_equal_to(XML.clone()));
+  }
+
+  #[test]
+  fn content_types_can_be_registered_by_suffix() {
+    let content_type = ContentType::parse("application/vnd.acme.v2+suffix-test").unwrap();
+    let text_content_type = ContentType::parse("text/vnd.acme.v2+suffix-test").unwrap();
+    expect!(content_type.registered_class()).to(be_none());
+
+    register_content_type("*/*+suffix-test", ContentTypeClass::Json).unwrap();
+    expect!(content_type.is_json()).to(be_true());
+    expect!(text_content_type.is_json()).to(be_true());
+
+    register_content_type("application/*+suffix-test", ContentTypeClass::Xml).unwrap();
+    expect!(content_type.is_xml()).to(be_true());
+    expect!(text_content_type.is_json()).to(be_true());
+
+    register_content_type("application/vnd.acme.v2+suffix-test", ContentTypeClass::Binary).unwrap();
+    expect!(content_type.is_binary()).to(be_true());
+
+    expect!(unregister_content_type("application/vnd.acme.v2+suffix-test")).to(be_true());
+    expect!(unregister_content_type("application/*+suffix-test")).to(be_true());
+    expect!(unregister_content_type("*/*+suffix-test")).to(be_true());
+    expect!(content_type.registered_class()).to(be_none());
+  }
 }
@@ -0,0 +1,360 @@
+//! Conversion of pacts between the versions of the Pact specification.
+//!
+//! Upgrading a pact to a later version of the specification is lossless. Downgrading is lossy, as
+//! constructs that the older specification does not support (i.e. V4 only matching rules, pending
+//! interactions, generators in V2 pacts) can not be written. These are removed from the converted
+//! pact and reported, so pacts can be generated for older verifiers while knowing what has been
+//! lost.
+
+use std::fmt::{Display, Formatter};
+use std::panic::RefUnwindSafe;
+
+use serde_json::{Map, Value};
+use tracing::warn;
+
+use crate::interaction::Interaction;
+use crate::matchingrules::MatchingRule;
+use crate::pact::{load_pact_from_json, Pact};
+use crate::PactSpecification;
+use crate::v4::interaction::V4Interaction;
+
+/// A construct that was removed when converting a pact
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DroppedConstruct {
+  /// Description of the interaction the construct was removed from, if it was not at the pact level
+  pub interaction: Option<String>,
+  /// Description of the construct that was removed
+  pub description: String
+}
+
+impl DroppedConstruct {
+  fn new(interaction: Option<&dyn Interaction>, description: impl Into<String>) -> Self {
+    DroppedConstruct {
+      interaction: interaction.map(|i| i.description()),
+      description: description.into()
+    }
+  }
+}
+
+impl Display for DroppedConstruct {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    match &self.interaction {
+      Some(interaction) => write!(f, "Interaction '{}': {}", interaction, self.description),
+      None => write!(f, "{}", self.description)
+    }
+  }
+}
+
+/// Result of converting a pact to a version of the specification
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConvertedPact {
+  /// Version of the specification the pact was converted to
+  pub specification: PactSpecification,
+  /// JSON form of the converted pact
+  pub json: Value,
+  /// Constructs that were removed, as they are not supported by the specification
+  pub dropped: Vec<DroppedConstruct>
+}
+
+impl ConvertedPact {
+  /// If the conversion did not lose any information
+  pub fn is_lossless(&self) -> bool {
+    self.dropped.is_empty()
+  }
+}
+
+/// Converts the pact to the given version of the specification. Any constructs that are not
+/// supported by the specification are removed, and returned in the result (as well as being
+/// logged as warnings). Returns an error if the pact can't be represented in that version (i.e.
+/// message pacts before V3, or pacts with mixed interaction types before V4).
+pub fn convert_pact(pact: &dyn Pact, specification: PactSpecification) -> anyhow::Result<ConvertedPact> {
+  let v4_pact = pact.as_v4_pact()?;
+  let mut json = v4_pact.to_json(specification)?;
+  let mut dropped = vec![];
+
+  if specification < PactSpecification::V4 {
+    if !v4_pact.plugin_data.is_empty() {
+      dropped.push(DroppedConstruct::new(None, "plugin data"));
+    }
+    for interaction in &v4_pact.interactions {
+      dropped.extend(v4_only_constructs(interaction.as_ref()));
+    }
+  }
+
+  if specification < PactSpecification::V3 {
+    for interaction in &v4_pact.interactions {
+      let provider_states = interaction.provider_states();
+      if provider_states.len() > 1 {
+        dropped.push(DroppedConstruct::new(Some(interaction.to_super()),
+          format!("all provider states except '{}'", provider_states[0].name)));
+      }
+      if provider_states.iter().take(1).any(|state| !state.params.is_empty()) {
+        dropped.push(DroppedConstruct::new(Some(interaction.to_super()), "provider state parameters"));
+      }
+      if let Some(http) = interaction.as_v4_http() {
+        if http.request.generators.is_not_empty() {
+          dropped.push(DroppedConstruct::new(Some(interaction.to_super()), "request generators"));
+        }
+        if http.response.generators.is_not_empty() {
+          dropped.push(DroppedConstruct::new(Some(interaction.to_super()), "response generators"));
+        }
+      }
+    }
+  }
+
+  if specification < PactSpecification::V4 {
+    let interactions_key = if json.get("messages").is_some() { "messages" } else { "interactions" };
+    if let Some(Value::Array(interactions)) = json.get_mut(interactions_key) {
+      for interaction in interactions.iter_mut() {
+        let description = interaction.get("description")
+          .and_then(|d| d.as_str())
+          .map(|d| d.to_string());
+        if let Some(map) = interaction.as_object_mut() {
+          remove_unsupported_rules(map, &specification, &description, &mut dropped);
+        }
+        for part in ["request", "response"] {
+          if let Some(Value::Object(map)) = interaction.get_mut(part) {
+            remove_unsupported_rules(map, &specification, &description, &mut dropped);
+          }
+        }
+      }
+    }
+  }
+
+  for construct in &dropped {
+    warn!("Converting the pact to {}, {} was removed", specification, construct);
+  }
+  Ok(ConvertedPact { specification, json, dropped })
+}
+
+/// Loads the pact from the JSON and converts it to the given version of the specification
+/// (see [`convert_pact`])
+pub fn convert_pact_json(source: &str, json: &Value, specification: PactSpecification) -> anyhow::Result<ConvertedPact> {
+  let pact: Box<dyn Pact + Send + Sync + RefUnwindSafe> = load_pact_from_json(source, json)?;
+  convert_pact(pact.as_ref(), specification)
+}
+
+fn v4_only_constructs(interaction: &(dyn V4Interaction + Send + Sync + RefUnwindSafe)) -> Vec<DroppedConstruct> {
+  let mut dropped = vec![];
+  let mut add = |description: &str| dropped.push(DroppedConstruct::new(Some(interaction.to_super()), description));
+  if interaction.pending() {
+    add("pending flag");
+  }
+  if !interaction.comments().is_empty() {
+    add("comments");
+  }
+  if !interaction.plugin_config().is_empty() {
+    add("plugin configuration");
+  }
+  if !interaction.interaction_markup().is_empty() {
+    add("interaction markup");
+  }
+  if interaction.transport().is_some() {
+    add("transport");
+  }
+  if let Some(http) = interaction.as_v4_http() {
+    if http.request.trailers.is_some() {
+      add("request trailers");
+    }
+    if http.response.trailers.is_some() {
+      add("response trailers");
+    }
+  }
+  dropped
+}
+
+// Removes the matching rules that the specification does not support from the matching rules of
+// the JSON form of an interaction or HTTP part
+fn remove_unsupported_rules(
+  json: &mut Map<String, Value>,
+  specification: &PactSpecification,
+  interaction: &Option<String>,
+  dropped: &mut Vec<DroppedConstruct>
+) {
+  let mut drop = |rule: &str, path: &str| dropped.push(DroppedConstruct {
+    interaction: interaction.clone(),
+    description: format!("{} matching rule at '{}'", rule, path)
+  });
+  let supported = |rule: &Value| MatchingRule::from_json(rule)
+    .map(|rule| rule.min_spec_version() <= *specification)
+    .unwrap_or(true);
+
+  if *specification < PactSpecification::V2 {
+    if let Some(Value::Object(rules)) = json.remove("matchingRules") {
+      for path in rules.keys() {
+        drop("all", path);
+      }
+    }
+  } else if *specification == PactSpecification::V2 {
+    if let Some(Value::Object(rules)) = json.get_mut("matchingRules") {
+      rules.retain(|path, rule| {
+        let keep = supported(rule);
+        if !keep {
+          drop(rule_name(rule), path);
+        }
+        keep
+      });
+    }
+  } else if let Some(Value::Object(categories)) = json.get_mut("matchingRules") {
+    for (category, rules) in categories.iter_mut() {
+      let Value::Object(rules) = rules else { continue };
+      if rules.contains_key("matchers") {
+        // Rules for the category are not keyed by path (i.e. the path category)
+        retain_matchers(rules, &supported, &mut |rule| drop(rule, category));
+      } else {
+        rules.retain(|path, rule_list| match rule_list {
+          Value::Object(rule_list) => {
+            retain_matchers(rule_list, &supported, &mut |rule| drop(rule, &format!("{}:{}", category, path)));
+            rule_list.get("matchers").and_then(|m| m.as_array()).map(|m| !m.is_empty()).unwrap_or(true)
+          }
+          _ => true
+        });
+      }
+    }
+    categories.retain(|_, rules| rules.as_object().map(|rules| !rules.is_empty()).unwrap_or(true));
+  }
+}
+
+fn retain_matchers(
+  rule_list: &mut Map<String, Value>,
+  supported: &dyn Fn(&Value) -> bool,
+  drop: &mut dyn FnMut(&str)
+) {
+  if let Some(Value::Array(matchers)) = rule_list.get_mut("matchers") {
+    matchers.retain(|rule| {
+      let keep = supported(rule);
+      if !keep {
+        drop(rule_name(rule));
+      }
+      keep
+    });
+  }
+}
+
+fn rule_name(rule: &Value) -> &str {
+  rule.get("match").and_then(|name| name.as_str()).unwrap_or("unknown")
+}
+
+#[cfg(test)]
+mod tests {
+  use expectest::prelude::*;
+  use maplit::hashmap;
+  use serde_json::json;
+
+  use crate::matchingrules;
+  use crate::matchingrules::MatchingRule;
+  use crate::provider_states::ProviderState;
+  use crate::request::Request;
+  use crate::response::Response;
+  use crate::sync_interaction::RequestResponseInteraction;
+  use crate::sync_pact::RequestResponsePact;
+  use crate::v4::http_parts::HttpResponse;
+  use crate::v4::pact::V4Pact;
+  use crate::v4::synch_http::SynchronousHttp;
+
+  use super::*;
+
+  fn dropped(converted: &ConvertedPact) -> Vec<String> {
+    converted.dropped.iter().map(|d| d.to_string()).collect()
+  }
+
+  #[test]
+  fn upgrading_a_pact_is_lossless() {
+    let pact = RequestResponsePact {
+      interactions: vec![RequestResponseInteraction {
+        description: "a request".to_string(),
+        response: Response {
+          matching_rules: matchingrules! { "body" => { "$.id" => [ MatchingRule::Type ] } },
+          .. Response::default()
+        },
+        .. RequestResponseInteraction::default()
+      }],
+      .. RequestResponsePact::default()
+    };
+    let converted = convert_pact(&pact, PactSpecification::V4).unwrap();
+    expect!(converted.is_lossless()).to(be_true());
+    expect!(converted.json["metadata"]["pactSpecification"]["version"].clone()).to(be_equal_to(json!("4.0")));
+    expect!(converted.json["interactions"][0]["type"].clone()).to(be_equal_to(json!("Synchronous/HTTP")));
+  }
+
+  #[test]
+  fn downgrading_to_v3_removes_the_v4_constructs() {
+    let pact = V4Pact {
+      interactions: vec![Box::new(SynchronousHttp {
+        description: "a request".to_string(),
+        pending: true,
+        comments: hashmap! { "text".to_string() => json!(["a comment"]) },
+        response: HttpResponse {
+          matching_rules: matchingrules! {
+            "body" => {
+              "$.id" => [ MatchingRule::Integer, MatchingRule::NotEmpty ],
+              "$.name" => [ MatchingRule::Semver ]
+            }
+          },
+          .. HttpResponse::default()
+        },
+        .. SynchronousHttp::default()
+      })],
+      .. V4Pact::default()
+    };
+
+    let converted = convert_pact(&pact, PactSpecification::V3).unwrap();
+    expect!(dropped(&converted)).to(be_equal_to(vec![
+      "Interaction 'a request': pending flag".to_string(),
+      "Interaction 'a request': comments".to_string(),
+      "Interaction 'a request': notEmpty matching rule at 'body:$.id'".to_string(),
+      "Interaction 'a request': semver matching rule at 'body:$.name'".to_string()
+    ]));
+    expect!(converted.json["interactions"][0]["response"]["matchingRules"].clone()).to(be_equal_to(json!({
+      "body": {
+        "$.id": { "combine": "AND", "matchers": [ { "match": "integer" } ] }
+      }
+    })));
+  }
+
+  #[test]
+  fn downgrading_to_v2_removes_the_v3_constructs() {
+    let pact = RequestResponsePact {
+      interactions: vec![RequestResponseInteraction {
+        description: "a request".to_string(),
+        provider_states: vec![
+          ProviderState { name: "state one".to_string(), params: hashmap! { "id".to_string() => json!(1) } },
+          ProviderState::default("state two")
+        ],
+        request: Request {
+          matching_rules: matchingrules! { "path" => { "" => [ MatchingRule::Regex("/\\d+".to_string()) ] } },
+          .. Request::default()
+        },
+        response: Response {
+          matching_rules: matchingrules! { "body" => { "$.id" => [ MatchingRule::Integer ] } },
+          .. Response::default()
+        },
+        .. RequestResponseInteraction::default()
+      }],
+      .. RequestResponsePact::default()
+    };
+
+    let converted = convert_pact(&pact, PactSpecification::V2).unwrap();
+    expect!(dropped(&converted)).to(be_equal_to(vec![
+      "Interaction 'a request': all provider states except 'state one'".to_string(),
+      "Interaction 'a request': provider state parameters".to_string(),
+      "Interaction 'a request': integer matching rule at '$.body.id'".to_string()
+    ]));
+    expect!(converted.json["interactions"][0]["request"]["matchingRules"].clone()).to(be_equal_to(json!({
+      "$.path": { "match": "regex", "regex": "/\\d+" }
+    })));
+    expect!(converted.json["interactions"][0]["response"]["matchingRules"].clone()).to(be_equal_to(json!({})));
+  }
+
+  #[test]
+  fn message_pacts_can_not_be_downgraded_to_v2() {
+    let json = json!({
+      "consumer": { "name": "consumer" },
+      "provider": { "name": "provider" },
+      "messages": [ { "description": "a message", "contents": "text" } ],
+      "metadata": { "pactSpecification": { "version": "3.0.0" } }
+    });
+    expect!(convert_pact_json("test", &json, PactSpecification::V2)).to(be_err());
+    expect!(convert_pact_json("test", &json, PactSpecification::V3).unwrap().is_lossless()).to(be_true());
+  }
+}
@@ -0,0 +1,338 @@
+//! Semantic comparison of two versions of a pact.
+//!
+//! Interactions are matched up between the pacts using their description, provider states and
+//! type. Interactions that are only in one of the pacts are reported as added or removed, while
+//! interactions in both pacts are compared field by field (using their V4 JSON form), so changes
+//! to the requests, responses, contents and matching rules are reported with the path to the
+//! field that changed.
+
+use std::collections::BTreeSet;
+use std::fmt::{Display, Formatter};
+
+use itertools::Itertools;
+use serde_json::{json, Value};
+
+use crate::pact::{interaction_key, Pact};
+use crate::path_exp::{DocPath, PathToken};
+use crate::v4::interaction::V4Interaction;
+
+/// Type of change between the two pacts
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ChangeType {
+  /// Only in the new pact
+  Added,
+  /// Only in the old pact
+  Removed,
+  /// In both pacts, but different
+  Changed
+}
+
+impl Display for ChangeType {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    match self {
+      ChangeType::Added => write!(f, "added"),
+      ChangeType::Removed => write!(f, "removed"),
+      ChangeType::Changed => write!(f, "changed")
+    }
+  }
+}
+
+/// Difference in a single field of an interaction
+#[derive(Debug, Clone, PartialEq)]
+pub struct FieldDiff {
+  /// Path to the field in the JSON form of the interaction (i.e. `$.response.status`)
+  pub path: DocPath,
+  /// Type of change
+  pub change: ChangeType,
+  /// Value of the field in the old pact
+  pub old_value: Option<Value>,
+  /// Value of the field in the new pact
+  pub new_value: Option<Value>
+}
+
+impl FieldDiff {
+  /// If the field is part of the matching rules of the interaction
+  pub fn is_matching_rule_change(&self) -> bool {
+    self.path.tokens().iter().any(|token| *token == PathToken::Field("matchingRules".to_string()))
+  }
+
+  /// Converts this difference to a JSON value
+  pub fn to_json(&self) -> Value {
+    json!({
+      "path": self.path.to_string(),
+      "change": self.change.to_string(),
+      "oldValue": self.old_value,
+      "newValue": self.new_value
+    })
+  }
+}
+
+impl Display for FieldDiff {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    match (&self.old_value, &self.new_value) {
+      (Some(old), Some(new)) => write!(f, "{} changed from {} to {}", self.path, old, new),
+      (None, Some(new)) => write!(f, "{} added with {}", self.path, new),
+      (Some(old), None) => write!(f, "{} removed (was {})", self.path, old),
+      (None, None) => write!(f, "{} {}", self.path, self.change)
+    }
+  }
+}
+
+/// Difference in an interaction between the two pacts
+#[derive(Debug, Clone, PartialEq)]
+pub struct InteractionDiff {
+  /// Description of the interaction
+  pub description: String,
+  /// Names of the provider states of the interaction
+  pub provider_states: Vec<String>,
+  /// Type of interaction (i.e. `Synchronous/HTTP`)
+  pub interaction_type: String,
+  /// Type of change
+  pub change: ChangeType,
+  /// Fields that are different. Only set for changed interactions.
+  pub fields: Vec<FieldDiff>
+}
+
+impl InteractionDiff {
+  /// Converts this difference to a JSON value
+  pub fn to_json(&self) -> Value {
+    json!({
+      "description": self.description,
+      "providerStates": self.provider_states,
+      "type": self.interaction_type,
+      "change": self.change.to_string(),
+      "fields": self.fields.iter().map(|field| field.to_json()).collect::<Vec<_>>()
+    })
+  }
+}
+
+impl Display for InteractionDiff {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    write!(f, "{} interaction '{}'", self.interaction_type, self.description)?;
+    if !self.provider_states.is_empty() {
+      write!(f, " given '{}'", self.provider_states.join("', '"))?;
+    }
+    write!(f, " was {}", self.change)?;
+    for field in &self.fields {
+      write!(f, "\n  {}", field)?;
+    }
+    Ok(())
+  }
+}
+
+/// Differences between two pacts
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PactDiff {
+  /// Interactions that were added, removed or changed
+  pub interactions: Vec<InteractionDiff>
+}
+
+impl PactDiff {
+  /// If the pacts have no differences
+  pub fn is_empty(&self) -> bool {
+    self.interactions.is_empty()
+  }
+
+  /// Interactions with the given type of change
+  pub fn with_change(&self, change: ChangeType) -> Vec<&InteractionDiff> {
+    self.interactions.iter().filter(|i| i.change == change).collect()
+  }
+
+  /// Converts the differences to a JSON value
+  pub fn to_json(&self) -> Value {
+    json!({
+      "interactions": self.interactions.iter().map(|i| i.to_json()).collect::<Vec<_>>()
+    })
+  }
+}
+
+impl Display for PactDiff {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    if self.is_empty() {
+      write!(f, "No differences")
+    } else {
+      write!(f, "{}", self.interactions.iter().join("\n"))
+    }
+  }
+}
+
+/// Compares the interactions of the old and new versions of a pact
+pub fn diff_pacts(old: &dyn Pact, new: &dyn Pact) -> anyhow::Result<PactDiff> {
+  let old_pact = old.as_v4_pact()?;
+  let new_pact = new.as_v4_pact()?;
+  let old_interactions = old_pact.interactions.iter()
+    .map(|i| (interaction_key(i.as_ref()), i))
+    .collect::<Vec<_>>();
+  let new_interactions = new_pact.interactions.iter()
+    .map(|i| (interaction_key(i.as_ref()), i))
+    .collect::<Vec<_>>();
+  let keys = old_interactions.iter().chain(new_interactions.iter())
+    .map(|(key, _)| key.clone())
+    .collect::<BTreeSet<_>>();
+
+  let interactions = keys.into_iter()
+    .filter_map(|key| {
+      let old_interaction = old_interactions.iter().find(|(k, _)| *k == key).map(|(_, i)| i);
+      let new_interaction = new_interactions.iter().find(|(k, _)| *k == key).map(|(_, i)| i);
+      let interaction_type = old_interaction.or(new_interaction)
+        .map(|i| i.v4_type().to_string())
+        .unwrap_or_default();
+      let (change, fields) = match (old_interaction, new_interaction) {
+        (Some(old_interaction), Some(new_interaction)) => {
+          let mut fields = vec![];
+          diff_json(&DocPath::root(), &interaction_json(old_interaction.as_ref()),
+            &interaction_json(new_interaction.as_ref()), &mut fields);
+          if fields.is_empty() {
+            return None;
+          }
+          (ChangeType::Changed, fields)
+        }
+        (None, _) => (ChangeType::Added, vec![]),
+        (_, None) => (ChangeType::Removed, vec![])
+      };
+      let (description, provider_states, _) = key;
+      Some(InteractionDiff { description, provider_states, interaction_type, change, fields })
+    })
+    .collect();
+  Ok(PactDiff { interactions })
+}
+
+fn interaction_json(interaction: &dyn V4Interaction) -> Value {
+  let mut json = interaction.to_json();
+  if let Some(map) = json.as_object_mut() {
+    // The key is derived from the contents of the interaction
+    map.remove("key");
+  }
+  json
+}
+
+/// Compares the JSON values, adding a difference for each field that is different
+pub fn diff_json(path: &DocPath, old: &Value, new: &Value, diffs: &mut Vec<FieldDiff>) {
+  match (old, new) {
+    (Value::Object(old_map), Value::Object(new_map)) => {
+      for key in old_map.keys().chain(new_map.keys()).collect::<BTreeSet<_>>() {
+        let mut field_path = path.clone();
+        field_path.push_field(key);
+        match (old_map.get(key), new_map.get(key)) {
+          (Some(old_value), Some(new_value)) => diff_json(&field_path, old_value, new_value, diffs),
+          (old_value, new_value) => diffs.push(field_diff(field_path, old_value, new_value))
+        }
+      }
+    }
+    (Value::Array(old_items), Value::Array(new_items)) => {
+      for index in 0..old_items.len().max(new_items.len()) {
+        let mut item_path = path.clone();
+        item_path.push_index(index);
+        match (old_items.get(index), new_items.get(index)) {
+          (Some(old_value), Some(new_value)) => diff_json(&item_path, old_value, new_value, diffs),
+          (old_value, new_value) => diffs.push(field_diff(item_path, old_value, new_value))
+        }
+      }
+    }
+    _ => if old != new {
+      diffs.push(field_diff(path.clone(), Some(old), Some(new)));
+    }
+  }
+}
+
+fn field_diff(path: DocPath, old_value: Option<&Value>, new_value: Option<&Value>) -> FieldDiff {
+  let change = match (old_value, new_value) {
+    (None, _) => ChangeType::Added,
+    (_, None) => ChangeType::Removed,
+    _ => ChangeType::Changed
+  };
+  FieldDiff {
+    path,
+    change,
+    old_value: old_value.cloned(),
+    new_value: new_value.cloned()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use expectest::prelude::*;
+  use serde_json::json;
+
+  use crate::matchingrules;
+  use crate::matchingrules::MatchingRule;
+  use crate::provider_states::ProviderState;
+  use crate::request::Request;
+  use crate::response::Response;
+  use crate::sync_interaction::RequestResponseInteraction;
+  use crate::sync_pact::RequestResponsePact;
+
+  use super::*;
+
+  fn interaction(description: &str, response: Response) -> RequestResponseInteraction {
+    RequestResponseInteraction {
+      description: description.to_string(),
+      provider_states: vec![ProviderState::default("state one")],
+      request: Request { path: "/one".to_string(), .. Request::default() },
+      response,
+      .. RequestResponseInteraction::default()
+    }
+  }
+
+  fn pact(interactions: Vec<RequestResponseInteraction>) -> RequestResponsePact {
+    RequestResponsePact { interactions, .. RequestResponsePact::default() }
+  }
+
+  #[test]
+  fn diff_of_the_same_pact_is_empty() {
+    let pact = pact(vec![interaction("a", Response::default())]);
+    let diff = diff_pacts(&pact, &pact).unwrap();
+    expect!(diff.is_empty()).to(be_true());
+    expect!(diff.to_string()).to(be_equal_to("No differences"));
+  }
+
+  #[test]
+  fn diff_pacts_reports_added_and_removed_interactions() {
+    let old = pact(vec![interaction("a", Response::default()), interaction("b", Response::default())]);
+    let new = pact(vec![interaction("b", Response::default()), interaction("c", Response::default())]);
+    let diff = diff_pacts(&old, &new).unwrap();
+    expect!(diff.with_change(ChangeType::Removed).iter().map(|i| i.description.clone()).collect::<Vec<_>>())
+      .to(be_equal_to(vec!["a".to_string()]));
+    expect!(diff.with_change(ChangeType::Added).iter().map(|i| i.description.clone()).collect::<Vec<_>>())
+      .to(be_equal_to(vec!["c".to_string()]));
+    expect!(diff.with_change(ChangeType::Changed)).to(be_equal_to(Vec::<&InteractionDiff>::new()));
+    expect!(diff.to_string()).to(be_equal_to(
+      "Synchronous/HTTP interaction 'a' given 'state one' was removed\nSynchronous/HTTP interaction 'c' given 'state one' was added"));
+  }
+
+  #[test]
+  fn diff_pacts_reports_the_fields_that_changed() {
+    let old = pact(vec![interaction("a", Response {
+      status: 200,
+      matching_rules: matchingrules! { "body" => { "$.id" => [ MatchingRule::Type ] } },
+      .. Response::default()
+    })]);
+    let new = pact(vec![interaction("a", Response {
+      status: 201,
+      matching_rules: matchingrules! { "body" => { "$.id" => [ MatchingRule::Integer ] } },
+      .. Response::default()
+    })]);
+    let diff = diff_pacts(&old, &new).unwrap();
+    let changed = diff.with_change(ChangeType::Changed);
+    expect!(changed.len()).to(be_equal_to(1));
+    let fields = &changed[0].fields;
+    expect!(fields.iter().map(|field| field.to_string()).collect::<Vec<_>>()).to(be_equal_to(vec![
+      "$.response.matchingRules.body['$.id'].matchers[0].match changed from \"type\" to \"integer\"".to_string(),
+      "$.response.status changed from 200 to 201".to_string()
+    ]));
+    expect!(fields.iter().map(|field| field.is_matching_rule_change()).collect::<Vec<_>>())
+      .to(be_equal_to(vec![true, false]));
+  }
+
+  #[test]
+  fn diff_json_test() {
+    let mut diffs = vec![];
+    diff_json(&DocPath::root(), &json!({"a": 1, "b": [1, 2], "c": true}),
+      &json!({"a": 1, "b": [1], "d": null}), &mut diffs);
+    expect!(diffs.iter().map(|diff| (diff.path.to_string(), diff.change)).collect::<Vec<_>>()).to(be_equal_to(vec![
+      ("$.b[1]".to_string(), ChangeType::Removed),
+      ("$.c".to_string(), ChangeType::Removed),
+      ("$.d".to_string(), ChangeType::Added)
+    ]));
+  }
+}
@@ -365,7 +365,14 @@ pub fn contains_expressions(value: &str) -> bool {
   value.contains("${")
 }
 
-/// Parse the expressions and return the generated value
+/// Parse the expressions and return the generated value.
+///
+/// Expressions are of the form `${key}`, where the key is looked up with the value resolver. If
+/// there is no value for the whole key, a dotted key (i.e. `${user.id}` or `${items.0}`) is looked
+/// up by resolving the first part and then selecting the remaining parts from the resolved value.
+/// A default can be given after `:-` (i.e. `${token:-abc}`), which is used if there is no value
+/// for the key or the value is null. Both the key and the default can contain nested expressions
+/// (i.e. `${user.${field}}` or `${token:-${defaultToken}}`).
 pub fn parse_expression(value: &str, value_resolver: &dyn ValueResolver<Value>) -> anyhow::Result<Value> {
   if contains_expressions(value) {
     replace_expressions(value, value_resolver)
@@ -382,15 +389,10 @@ fn replace_expressions(value: &str, value_resolver: &dyn ValueResolver<Value>) -
     if index > 0 {
       result.push(json!(&buffer[0..index]));
     }
-    let end_position = buffer.find('}')
+    let end_position = find_closing_brace(buffer, index + 2)
       .ok_or_else(|| anyhow!("Missing closing brace in expression string '{}'", value))?;
     if end_position - index > 2 {
-      let lookup_key = &buffer[(index + 2)..end_position];
-      if let Some(lookup) = value_resolver.resolve_value(lookup_key) {
-        result.push(lookup);
-      } else {
-        return Err(anyhow!("No value for '{}' found", lookup_key));
-      }
+      result.push(resolve_expression(&buffer[(index + 2)..end_position], value_resolver)?);
     }
     buffer = &buffer[(end_position + 1)..];
     position = buffer.find("${");
@@ -406,6 +408,71 @@ fn replace_expressions(value: &str, value_resolver: &dyn ValueResolver<Value>) -
   }
 }
 
+/// Finds the brace that closes the expression starting at `start`, skipping any nested expressions
+fn find_closing_brace(value: &str, start: usize) -> Option<usize> {
+  let mut depth = 0;
+  let mut chars = value.char_indices().skip_while(|(i, _)| *i < start).peekable();
+  while let Some((i, ch)) = chars.next() {
+    match ch {
+      '$' if chars.peek().map(|(_, next)| *next) == Some('{') => {
+        chars.next();
+        depth += 1;
+      }
+      '}' if depth == 0 => return Some(i),
+      '}' => depth -= 1,
+      _ => ()
+    }
+  }
+  None
+}
+
+/// Splits the expression into the key and the default value (after `:-`), ignoring any `:-` in
+/// nested expressions
+fn split_default(expression: &str) -> (&str, Option<&str>) {
+  let mut depth = 0;
+  let mut chars = expression.char_indices().peekable();
+  while let Some((i, ch)) = chars.next() {
+    let next = chars.peek().map(|(_, next)| *next);
+    match ch {
+      '$' if next == Some('{') => {
+        chars.next();
+        depth += 1;
+      }
+      '}' if depth > 0 => depth -= 1,
+      ':' if depth == 0 && next == Some('-') => return (&expression[..i], Some(&expression[(i + 2)..])),
+      _ => ()
+    }
+  }
+  (expression, None)
+}
+
+fn resolve_expression(expression: &str, value_resolver: &dyn ValueResolver<Value>) -> anyhow::Result<Value> {
+  let (key, default) = split_default(expression);
+  let key = if contains_expressions(key) {
+    json_to_string(&replace_expressions(key, value_resolver)?)
+  } else {
+    key.to_string()
+  };
+  match (lookup_value(key.as_str(), value_resolver), default) {
+    (Some(value), _) if !value.is_null() => Ok(value),
+    (_, Some(default)) => parse_expression(default, value_resolver),
+    (Some(value), None) => Ok(value),
+    (None, None) => Err(anyhow!("No value for '{}' found", key))
+  }
+}
+
+fn lookup_value(key: &str, value_resolver: &dyn ValueResolver<Value>) -> Option<Value> {
+  value_resolver.resolve_value(key).or_else(|| {
+    let mut parts = key.split('.');
+    let first = parts.next().filter(|_| key.contains('.'))?;
+    parts.try_fold(value_resolver.resolve_value(first)?, |value, part| match value {
+      Value::Object(map) => map.get(part).cloned(),
+      Value::Array(items) => part.parse::<usize>().ok().and_then(|index| items.get(index).cloned()),
+      _ => None
+    })
+  })
+}
+
 #[cfg(test)]
 mod tests {
   use expectest::prelude::*;
@@ -481,6 +548,49 @@ mod tests {
     expect!(parse_expression("${value1}/${value2}", &resolver)).to(be_ok().value(json!("[value]/100")));
   }
 
+  #[test]
+  fn looks_up_dotted_keys_in_structured_values() {
+    let resolver = MapValueResolver { context: hashmap!{
+      "user" => json!({ "id": 100, "name": { "first": "Mary" }, "roles": ["admin", "user"] }),
+      "user.id" => json!("flat")
+    } };
+    expect!(parse_expression("${user.name.first}", &resolver)).to(be_ok().value(json!("Mary")));
+    expect!(parse_expression("${user.roles.1}", &resolver)).to(be_ok().value(json!("user")));
+    expect!(parse_expression("/users/${user.name.first}", &resolver)).to(be_ok().value(json!("/users/Mary")));
+    expect!(parse_expression("${user.id}", &resolver)).to(be_ok().value(json!("flat")));
+    expect!(parse_expression("${user.email}", &resolver)).to(be_err());
+    expect!(parse_expression("${user.roles.2}", &resolver)).to(be_err());
+  }
+
+  #[test]
+  fn uses_the_default_value_when_there_is_no_value_for_the_key() {
+    let resolver = MapValueResolver { context: hashmap!{
+      "token" => json!("xyz"),
+      "empty" => Value::Null,
+      "defaultToken" => json!("def")
+    } };
+    expect!(parse_expression("${token:-abc}", &resolver)).to(be_ok().value(json!("xyz")));
+    expect!(parse_expression("${other:-abc}", &resolver)).to(be_ok().value(json!("abc")));
+    expect!(parse_expression("${empty:-abc}", &resolver)).to(be_ok().value(json!("abc")));
+    expect!(parse_expression("${other:-}", &resolver)).to(be_ok().value(json!("")));
+    expect!(parse_expression("Bearer ${other:-abc}", &resolver)).to(be_ok().value(json!("Bearer abc")));
+    expect!(parse_expression("${empty}", &resolver)).to(be_ok().value(Value::Null));
+  }
+
+  #[test]
+  fn supports_nested_expressions() {
+    let resolver = MapValueResolver { context: hashmap!{
+      "field" => json!("id"),
+      "user" => json!({ "id": 100 }),
+      "defaultToken" => json!("def")
+    } };
+    expect!(parse_expression("${user.${field}}", &resolver)).to(be_ok().value(json!(100)));
+    expect!(parse_expression("${other:-${defaultToken}}", &resolver)).to(be_ok().value(json!("def")));
+    expect!(parse_expression("${other:-${missing:-abc}}/x", &resolver)).to(be_ok().value(json!("abc/x")));
+    expect!(parse_expression("${other:-${missing}}", &resolver)).to(be_err());
+    expect!(parse_expression("${other:-${defaultToken}", &resolver)).to(be_err());
+  }
+
   #[test]
   fn with_a_defined_type_converts_the_expression_into_the_correct_type() {
     expect!(u16::try_from(DataValue { wrapped: json!("100"), data_type: DataType::RAW })).to(be_ok().value(100));
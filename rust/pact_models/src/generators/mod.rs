@@ -9,14 +9,15 @@ use std::ops::Index;
 use std::str::FromStr;
 
 use anyhow::anyhow;
-#[cfg(feature = "datetime")] use chrono::{DateTime, Local};
+#[cfg(feature = "datetime")] use chrono::{DateTime, FixedOffset, Local};
 use indextree::{Arena, NodeId};
+use lazy_static::lazy_static;
 use itertools::Itertools;
 use maplit::hashmap;
-#[cfg(not(target_family = "wasm"))] use onig::{Captures, Regex};
+#[cfg(not(target_family = "wasm"))] use onig::Regex;
 use rand::distributions::Alphanumeric;
 use rand::prelude::*;
-#[cfg(target_family = "wasm")] use regex::{Captures, Regex};
+#[cfg(target_family = "wasm")] use regex::Regex;
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
 use tracing::{debug, instrument, trace, warn};
@@ -26,10 +27,10 @@ use crate::bodies::OptionalBody;
 use crate::expression_parser::{contains_expressions, DataType, DataValue, MapValueResolver, parse_expression};
 #[cfg(feature = "datetime")] use crate::generators::datetime_expressions::{execute_date_expression, execute_datetime_expression, execute_time_expression};
 use crate::json_utils::{get_field_as_string, json_to_string, JsonToNum};
-use crate::matchingrules::{Category, MatchingRuleCategory};
+use crate::matchingrules::{Category, MatchingRuleCategory, metadata_key_path, metadata_path_key};
 use crate::PactSpecification;
-use crate::path_exp::{DocPath, PathToken};
-#[cfg(feature = "datetime")] use crate::time_utils::{parse_pattern, to_chrono_pattern};
+use crate::path_exp::{DocPath, matches_token, PathToken};
+#[cfg(feature = "datetime")] use crate::time_utils::format_datetime;
 
 #[cfg(feature = "datetime")] pub mod datetime_expressions;
 #[cfg(feature = "datetime")] mod date_expression_parser;
@@ -143,7 +144,11 @@ pub enum Generator {
   /// Generates a URL with the mock server as the base URL
   MockServerURL(String, String),
   /// List of variants which can have embedded generators
-  ArrayContains(Vec<(usize, MatchingRuleCategory, HashMap<DocPath, Generator>)>)
+  ArrayContains(Vec<(usize, MatchingRuleCategory, HashMap<DocPath, Generator>)>),
+  /// Generates a random string that matches the provided regex, using the seed for the random
+  /// number generator. The same value will always be generated for the same regex and seed. This
+  /// is written to pact files as a `Regex` generator with a `seed` attribute.
+  SeededRegex(String, u64)
 }
 
 impl Generator {
@@ -160,6 +165,7 @@ impl Generator {
       Generator::RandomHexadecimal(digits) => Some(json!({ "type": "RandomHexadecimal", "digits": digits })),
       Generator::RandomString(size) => Some(json!({ "type": "RandomString", "size": size })),
       Generator::Regex(ref regex) => Some(json!({ "type": "Regex", "regex": regex })),
+      Generator::SeededRegex(ref regex, seed) => Some(json!({ "type": "Regex", "regex": regex, "seed": seed })),
       Generator::Date(format, exp) => {
         match (format, exp) {
           (Some(format), Some(exp)) => Some(json!({ "type": "Date", "format": format, "expression": exp })),
@@ -213,7 +219,10 @@ impl Generator {
       "RandomDecimal" => Some(Generator::RandomDecimal(<u16>::json_to_number(map, "digits", 10))),
       "RandomHexadecimal" => Some(Generator::RandomHexadecimal(<u16>::json_to_number(map, "digits", 10))),
       "RandomString" => Some(Generator::RandomString(<u16>::json_to_number(map, "size", 10))),
-      "Regex" => map.get("regex").map(|val| Generator::Regex(json_to_string(val))),
+      "Regex" => map.get("regex").map(|val| match map.get("seed").and_then(|seed| seed.as_u64()) {
+        Some(seed) => Generator::SeededRegex(json_to_string(val), seed),
+        None => Generator::Regex(json_to_string(val))
+      }),
       "Date" => Some(Generator::Date(get_field_as_string("format", map), get_field_as_string("expression", map))),
       "Time" => Some(Generator::Time(get_field_as_string("format", map), get_field_as_string("expression", map))),
       "DateTime" => Some(Generator::DateTime(get_field_as_string("format", map), get_field_as_string("expression", map))),
@@ -247,7 +256,7 @@ impl Generator {
       Generator::RandomDecimal(_) => "RandomDecimal",
       Generator::RandomHexadecimal(_) => "RandomHexadecimal",
       Generator::RandomString(_) => "RandomString",
-      Generator::Regex(_) => "Regex",
+      Generator::Regex(_) | Generator::SeededRegex(_, _) => "Regex",
       Generator::Date(_, _) => "Date",
       Generator::Time(_, _) => "Time",
       Generator::DateTime(_, _) => "DateTime",
@@ -272,6 +281,7 @@ impl Generator {
       Generator::RandomHexadecimal(digits) => hashmap!{ "digits" => json!(digits) },
       Generator::RandomString(digits) => hashmap!{ "digits" => json!(digits) },
       Generator::Regex(r) => hashmap!{ "regex" => json!(r) },
+      Generator::SeededRegex(r, seed) => hashmap!{ "regex" => json!(r), "seed" => json!(seed) },
       Generator::Date(format, exp) => {
         match (format, exp) {
           (Some(format), Some(exp)) => hashmap!{ "format" => Value::String(format.clone()), "expression" => Value::String(exp.clone()) },
@@ -334,6 +344,10 @@ impl Hash for Generator {
       Generator::RandomHexadecimal(digits) => digits.hash(state),
       Generator::RandomString(size) => size.hash(state),
       Generator::Regex(re) => re.hash(state),
+      Generator::SeededRegex(re, seed) => {
+        re.hash(state);
+        seed.hash(state);
+      },
       Generator::DateTime(format, exp) => {
         format.hash(state);
         exp.hash(state);
@@ -378,6 +392,7 @@ impl PartialEq for Generator {
       (Generator::RandomHexadecimal(digits1), Generator::RandomHexadecimal(digits2)) => digits1 == digits2,
       (Generator::RandomString(size1), Generator::RandomString(size2)) => size1 == size2,
       (Generator::Regex(re1), Generator::Regex(re2)) => re1 == re2,
+      (Generator::SeededRegex(re1, seed1), Generator::SeededRegex(re2, seed2)) => re1 == re2 && seed1 == seed2,
       (Generator::DateTime(format1, exp1), Generator::DateTime(format2, exp2)) => format1 == format2 && exp1 == exp2,
       (Generator::Time(format1, exp1), Generator::Time(format2, exp2)) => format1 == format2 && exp1 == exp2,
       (Generator::Date(format1, exp1), Generator::Date(format2, exp2)) => format1 == format2 && exp1 == exp2,
@@ -537,6 +552,8 @@ impl Generators {
                 &Value::Object(ref map) => {
                   if *category == GeneratorCategory::QUERY || *category == GeneratorCategory::HEADER {
                     self.parse_generator_from_map(category, map, Some(DocPath::root().push_field(sub_k).clone()));
+                  } else if *category == GeneratorCategory::METADATA {
+                    self.parse_generator_from_map(category, map, Some(metadata_key_path(sub_k)?));
                   } else {
                     self.parse_generator_from_map(category, map, Some(DocPath::new(sub_k)?));
                   }
@@ -602,6 +619,15 @@ impl Generators {
           }
           map.insert(cat.clone(), Value::Object(generators));
         },
+        GeneratorCategory::METADATA => {
+          let mut generators = serde_json::Map::new();
+          for (key, val) in category {
+            if let Some(json) = val.to_json() {
+              generators.insert(metadata_path_key(key), json);
+            }
+          }
+          map.insert(cat.clone(), Value::Object(generators));
+        },
         _ => {
           let mut generators = serde_json::Map::new();
           for (key, val) in category {
@@ -813,7 +839,8 @@ pub fn generate_decimal(digits: usize) -> String {
 const HEX_CHARSET: &str = "0123456789ABCDEF";
 pub fn generate_hexadecimal(digits: usize) -> String {
   let mut rnd = rand::thread_rng();
-  HEX_CHARSET.chars().choose_multiple(&mut rnd, digits).iter().join("")
+  let chars: Vec<char> = HEX_CHARSET.chars().collect();
+  (0..digits).map(|_| chars.choose(&mut rnd).unwrap()).collect()
 }
 
 impl GenerateValue<u16> for Generator {
@@ -840,24 +867,111 @@ pub fn generate_ascii_string(size: usize) -> String {
 }
 
 fn strip_anchors(regex: &str) -> &str {
-  regex
-    .strip_prefix('^').unwrap_or(regex)
-    .strip_suffix('$').unwrap_or(regex)
+  let regex = regex.strip_prefix('^').unwrap_or(regex);
+  regex.strip_suffix('$').unwrap_or(regex)
+}
+
+fn generate_from_regex(regex: &str, seed: Option<u64>) -> anyhow::Result<String> {
+  let mut parser = regex_syntax::ParserBuilder::new().unicode(false).build();
+  match parser.parse(strip_anchors(regex)) {
+    Ok(hir) => {
+      match rand_regex::Regex::with_hir(hir, 20) {
+        Ok(gen) => match seed {
+          Some(seed) => Ok(StdRng::seed_from_u64(seed).sample(gen)),
+          None => Ok(rand::thread_rng().sample(gen))
+        },
+        Err(err) => {
+          warn!("Failed to generate a value from regular expression - {}", err);
+          Err(anyhow!("Failed to generate a value from regular expression - {}", err))
+        }
+      }
+    },
+    Err(err) => {
+      warn!("'{}' is not a valid regular expression - {}", regex, err);
+      Err(anyhow!("'{}' is not a valid regular expression - {}", regex, err))
+    }
+  }
+}
+
+lazy_static! {
+  static ref ABSOLUTE_URL_ORIGIN: regex::Regex = regex::Regex::new(r#"[a-zA-Z][a-zA-Z0-9+.\-]*://[^/?#\s"'<>]*"#).unwrap();
+}
+
+/// Returns the replacement for a part of the example matched by the regex. If there is a capture
+/// group, the replacement is the mock server URL followed by the captured value. Otherwise the
+/// scheme, host and port of any absolute URLs in the matched part are replaced with the mock server
+/// URL. Returns None if there is no capture group and no absolute URLs.
+fn mock_server_url_replacement(matched: &str, captured: Option<&str>, url: &str) -> Option<String> {
+  match captured {
+    Some(path) => Some(format!("{}{}", url, path)),
+    None => if ABSOLUTE_URL_ORIGIN.is_match(matched) {
+      Some(ABSOLUTE_URL_ORIGIN.replace_all(matched, regex::NoExpand(url)).to_string())
+    } else {
+      None
+    }
+  }
 }
 
+/// Replaces all the parts of the example matched by the regex with the mock server URL (see
+/// [`mock_server_url_replacement`]). Returns None if the regex does not match.
 #[cfg(not(target_family = "wasm"))]
-fn replace_with_regex(example: &String, url: String, re: Regex) -> String {
-  re.replace(example, |caps: &Captures| {
-    format!("{}{}", url, caps.at(1).unwrap())
-  }).to_string()
+fn replace_with_regex(example: &str, url: &str, re: &Regex) -> Option<String> {
+  let mut result = String::new();
+  let mut last = 0;
+  let mut matched = false;
+  for caps in re.captures_iter(example) {
+    let (start, end) = caps.pos(0)?;
+    result.push_str(&example[last..start]);
+    result.push_str(&mock_server_url_replacement(&example[start..end], caps.at(1), url)?);
+    last = end;
+    matched = true;
+  }
+  result.push_str(&example[last..]);
+  if matched { Some(result) } else { None }
 }
 
+/// Replaces all the parts of the example matched by the regex with the mock server URL (see
+/// [`mock_server_url_replacement`]). Returns None if the regex does not match.
 #[cfg(target_family = "wasm")]
-fn replace_with_regex(example: &String, url: String, re: Regex) -> String {
-  re.replace(example, |caps: &Captures| {
-    let m = caps.get(1).unwrap();
-    format!("{}{}", url, m.as_str())
-  }).to_string()
+fn replace_with_regex(example: &str, url: &str, re: &Regex) -> Option<String> {
+  let mut result = String::new();
+  let mut last = 0;
+  let mut matched = false;
+  for caps in re.captures_iter(example) {
+    let m = caps.get(0)?;
+    result.push_str(&example[last..m.start()]);
+    result.push_str(&mock_server_url_replacement(m.as_str(), caps.get(1).map(|c| c.as_str()), url)?);
+    last = m.end();
+    matched = true;
+  }
+  result.push_str(&example[last..]);
+  if matched { Some(result) } else { None }
+}
+
+fn generate_mock_server_url(example: &str, regex: &str, context: &HashMap<&str, Value>) -> anyhow::Result<String> {
+  debug!("Generating URL from Mock Server details");
+  let mock_server_details = context.get("mockServer")
+    .ok_or_else(|| anyhow!("MockServerURL: can not generate a value as there is no mock server details in the test context"))?
+    .as_object()
+    .ok_or_else(|| anyhow!("MockServerURL: can not generate a value as the mock server details in the test context is not an Object"))?;
+  let url = get_field_as_string("url", mock_server_details)
+    .or_else(|| get_field_as_string("href", mock_server_details))
+    .ok_or_else(|| anyhow!("MockServerURL: can not generate a value as there is no mock server URL in the test context"))?;
+  let re = Regex::new(regex)
+    .map_err(|err| anyhow!("MockServerURL: Failed to generate value: {}", err))?;
+  replace_with_regex(example, url.trim_end_matches('/'), &re)
+    .ok_or_else(|| anyhow!("MockServerURL: can not generate a value as the regex '{}' does not match a URL or a capture group in the example '{}'", regex, example))
+}
+
+/// Returns the base date-time for the date and time generators from the test context, or the
+/// current local time. The time zone offset of the base value is kept.
+#[cfg(feature = "datetime")]
+fn base_date_time(context: &HashMap<&str, Value>, key: &str) -> anyhow::Result<DateTime<FixedOffset>> {
+  match context.get(key) {
+    None => Ok(Local::now().into()),
+    Some(d) => json_to_string(d).parse::<DateTime<FixedOffset>>()
+      .map_err(|err| anyhow!("'{}' is not a valid value for {} - {}", d, key, err))
+  }
 }
 
 impl GenerateValue<String> for Generator {
@@ -879,43 +993,18 @@ impl GenerateValue<String> for Generator {
       Generator::RandomDecimal(digits) => Ok(generate_decimal(*digits as usize)),
       Generator::RandomHexadecimal(digits) => Ok(generate_hexadecimal(*digits as usize)),
       Generator::RandomString(size) => Ok(generate_ascii_string(*size as usize)),
-      Generator::Regex(ref regex) => {
-        let mut parser = regex_syntax::ParserBuilder::new().unicode(false).build();
-        match parser.parse(strip_anchors(regex)) {
-          Ok(hir) => {
-            match rand_regex::Regex::with_hir(hir, 20) {
-              Ok(gen) => Ok(rnd.sample(gen)),
-              Err(err) => {
-                warn!("Failed to generate a value from regular expression - {}", err);
-                Err(anyhow!("Failed to generate a value from regular expression - {}", err))
-              }
-            }
-          },
-          Err(err) => {
-            warn!("'{}' is not a valid regular expression - {}", regex, err);
-            Err(anyhow!("'{}' is not a valid regular expression - {}", regex, err))
-          }
-        }
-      },
+      Generator::Regex(ref regex) => generate_from_regex(regex, None),
+      Generator::SeededRegex(ref regex, seed) => generate_from_regex(regex, Some(*seed)),
       Generator::Date(_format, _exp) => {
         #[cfg(feature = "datetime")]
         {
-          let base = match context.get("baseDate") {
-            None => Local::now(),
-            Some(d) => json_to_string(d).parse::<DateTime<Local>>()?
-          };
+          let base = base_date_time(context, "baseDate")?;
           let date = execute_date_expression(&base, _exp.clone().unwrap_or_default().as_str())?;
           match _format {
-            Some(pattern) => match parse_pattern(pattern) {
-              Ok(tokens) => {
-                #[allow(deprecated)]
-                Ok(date.date().format(&to_chrono_pattern(&tokens)).to_string())
-              },
-              Err(err) => {
-                warn!("Date format {} is not valid - {}", pattern, err);
-                Err(anyhow!("Date format {} is not valid - {}", pattern, err))
-              }
-            },
+            Some(pattern) => format_datetime(&date, pattern).map_err(|err| {
+              warn!("Date format {} is not valid - {}", pattern, err);
+              anyhow!("Date format {} is not valid - {}", pattern, err)
+            }),
             None => Ok(date.naive_local().date().to_string())
           }
         }
@@ -927,19 +1016,13 @@ impl GenerateValue<String> for Generator {
       Generator::Time(_format, _exp) => {
         #[cfg(feature = "datetime")]
         {
-          let base = match context.get("baseTime") {
-            None => Local::now(),
-            Some(d) => json_to_string(d).parse::<DateTime<Local>>()?
-          };
+          let base = base_date_time(context, "baseTime")?;
           let time = execute_time_expression(&base, _exp.clone().unwrap_or_default().as_str())?;
           match _format {
-            Some(pattern) => match parse_pattern(pattern) {
-              Ok(tokens) => Ok(time.format(&to_chrono_pattern(&tokens)).to_string()),
-              Err(err) => {
-                warn!("Time format {} is not valid - {}", pattern, err);
-                Err(anyhow!("Time format {} is not valid - {}", pattern, err))
-              }
-            },
+            Some(pattern) => format_datetime(&time, pattern).map_err(|err| {
+              warn!("Time format {} is not valid - {}", pattern, err);
+              anyhow!("Time format {} is not valid - {}", pattern, err)
+            }),
             None => Ok(time.time().format("%H:%M:%S").to_string())
           }
         }
@@ -951,19 +1034,13 @@ impl GenerateValue<String> for Generator {
       Generator::DateTime(_format, _exp) => {
         #[cfg(feature = "datetime")]
         {
-          let base = match context.get("baseDateTime") {
-            None => Local::now(),
-            Some(d) => json_to_string(d).parse::<DateTime<Local>>()?
-          };
+          let base = base_date_time(context, "baseDateTime")?;
           let date_time = execute_datetime_expression(&base, _exp.clone().unwrap_or_default().as_str())?;
           match _format {
-            Some(pattern) => match parse_pattern(pattern) {
-              Ok(tokens) => Ok(date_time.format(&to_chrono_pattern(&tokens)).to_string()),
-              Err(err) => {
-                warn!("DateTime format {} is not valid - {}", pattern, err);
-                Err(anyhow!("DateTime format {} is not valid - {}", pattern, err))
-              }
-            },
+            Some(pattern) => format_datetime(&date_time, pattern).map_err(|err| {
+              warn!("DateTime format {} is not valid - {}", pattern, err);
+              anyhow!("DateTime format {} is not valid - {}", pattern, err)
+            }),
             None => Ok(date_time.format("%Y-%m-%dT%H:%M:%S.%3f%z").to_string())
           }
         }
@@ -975,23 +1052,7 @@ impl GenerateValue<String> for Generator {
       Generator::RandomBoolean => Ok(format!("{}", rnd.gen::<bool>())),
       Generator::ProviderStateGenerator(ref exp, ref dt) =>
         generate_value_from_context(exp, context, dt).map(|val| val.to_string()),
-      Generator::MockServerURL(example, regex) => if let Some(mock_server_details) = context.get("mockServer") {
-        debug!("Generating URL from Mock Server details");
-        match mock_server_details.as_object() {
-          Some(mock_server_details) => {
-            match get_field_as_string("url", mock_server_details) {
-              Some(url) => match Regex::new(regex) {
-                Ok(re) => Ok(replace_with_regex(example, url, re)),
-                Err(err) => Err(anyhow!("MockServerURL: Failed to generate value: {}", err))
-              },
-              None => Err(anyhow!("MockServerURL: can not generate a value as there is no mock server 'url' in the test context {:?}", context))
-            }
-          },
-          None => Err(anyhow!("MockServerURL: can not generate a value as there is no mock server details in the test context"))
-        }
-      } else {
-        Err(anyhow!("MockServerURL: can not generate a value as there is no mock server details in the test context"))
-      },
+      Generator::MockServerURL(example, regex) => generate_mock_server_url(example, regex, context),
       Generator::ArrayContains(_) => Err(anyhow!("can only use ArrayContains with lists"))
     };
     debug!("Generator = {:?}, Generated value = {:?}", self, result);
@@ -1046,43 +1107,18 @@ impl GenerateValue<Value> for Generator {
       },
       Generator::RandomHexadecimal(digits) => Ok(json!(generate_hexadecimal(*digits as usize))),
       Generator::RandomString(size) => Ok(json!(generate_ascii_string(*size as usize))),
-      Generator::Regex(ref regex) => {
-        let mut parser = regex_syntax::ParserBuilder::new().unicode(false).build();
-        match parser.parse(regex) {
-          Ok(hir) => {
-            match rand_regex::Regex::with_hir(hir, 20) {
-              Ok(gen) => Ok(json!(rand::thread_rng().sample::<String, _>(gen))),
-              Err(err) => {
-                warn!("Failed to generate a value from regular expression - {}", err);
-                Err(anyhow!("Failed to generate a value from regular expression - {}", err))
-              }
-            }
-          },
-          Err(err) => {
-            warn!("'{}' is not a valid regular expression - {}", regex, err);
-            Err(anyhow!("'{}' is not a valid regular expression - {}", regex, err))
-          }
-        }
-      },
+      Generator::Regex(ref regex) => generate_from_regex(regex, None).map(Value::String),
+      Generator::SeededRegex(ref regex, seed) => generate_from_regex(regex, Some(*seed)).map(Value::String),
       Generator::Date(_format, _exp) => {
         #[cfg(feature = "datetime")]
         {
-          let base = match context.get("baseDate") {
-            None => Local::now(),
-            Some(d) => json_to_string(d).parse::<DateTime<Local>>()?
-          };
+          let base = base_date_time(context, "baseDate")?;
           let date = execute_date_expression(&base, _exp.clone().unwrap_or_default().as_str())?;
           match _format {
-            Some(pattern) => match parse_pattern(pattern) {
-              Ok(tokens) => {
-                #[allow(deprecated)]
-                Ok(json!(date.date().format(&to_chrono_pattern(&tokens)).to_string()))
-              },
-              Err(err) => {
-                warn!("Date format {} is not valid - {}", pattern, err);
-                Err(anyhow!("Could not generate a random date from {} - {}", pattern, err))
-              }
-            },
+            Some(pattern) => format_datetime(&date, pattern).map(|value| json!(value)).map_err(|err| {
+              warn!("Date format {} is not valid - {}", pattern, err);
+              anyhow!("Could not generate a random date from {} - {}", pattern, err)
+            }),
             None => Ok(json!(date.naive_local().date().to_string()))
           }
         }
@@ -1094,19 +1130,13 @@ impl GenerateValue<Value> for Generator {
       Generator::Time(_format, _exp) => {
         #[cfg(feature = "datetime")]
         {
-          let base = match context.get("baseTime") {
-            None => Local::now(),
-            Some(d) => json_to_string(d).parse::<DateTime<Local>>()?
-          };
+          let base = base_date_time(context, "baseTime")?;
           let time = execute_time_expression(&base, _exp.clone().unwrap_or_default().as_str())?;
           match _format {
-            Some(pattern) => match parse_pattern(pattern) {
-              Ok(tokens) => Ok(json!(time.format(&to_chrono_pattern(&tokens)).to_string())),
-              Err(err) => {
-                warn!("Time format {} is not valid - {}", pattern, err);
-                Err(anyhow!("Could not generate a random time from {} - {}", pattern, err))
-              }
-            },
+            Some(pattern) => format_datetime(&time, pattern).map(|value| json!(value)).map_err(|err| {
+              warn!("Time format {} is not valid - {}", pattern, err);
+              anyhow!("Could not generate a random time from {} - {}", pattern, err)
+            }),
             None => Ok(json!(time.time().format("%H:%M:%S").to_string()))
           }
         }
@@ -1118,19 +1148,13 @@ impl GenerateValue<Value> for Generator {
       Generator::DateTime(_format, _exp) => {
         #[cfg(feature = "datetime")]
         {
-          let base = match context.get("baseDateTime") {
-            None => Local::now(),
-            Some(d) => json_to_string(d).parse::<DateTime<Local>>()?
-          };
+          let base = base_date_time(context, "baseDateTime")?;
           let date_time = execute_datetime_expression(&base, _exp.clone().unwrap_or_default().as_str())?;
           match _format {
-            Some(pattern) => match parse_pattern(pattern) {
-              Ok(tokens) => Ok(json!(date_time.format(&to_chrono_pattern(&tokens)).to_string())),
-              Err(err) => {
-                warn!("DateTime format {} is not valid - {}", pattern, err);
-                Err(anyhow!("Could not generate a random date-time from {} - {}", pattern, err))
-              }
-            },
+            Some(pattern) => format_datetime(&date_time, pattern).map(|value| json!(value)).map_err(|err| {
+              warn!("DateTime format {} is not valid - {}", pattern, err);
+              anyhow!("Could not generate a random date-time from {} - {}", pattern, err)
+            }),
             None => Ok(json!(date_time.format("%Y-%m-%dT%H:%M:%S.%3f%z").to_string()))
           }
         }
@@ -1145,26 +1169,8 @@ impl GenerateValue<Value> for Generator {
           Ok(val) => val.as_json(),
           Err(err) => Err(err)
         },
-      Generator::MockServerURL(example, regex) => {
-        debug!("context = {:?}", context);
-        if let Some(mock_server_details) = context.get("mockServer") {
-          match mock_server_details.as_object() {
-            Some(mock_server_details) => {
-              match get_field_as_string("url", mock_server_details)
-                .or_else(|| get_field_as_string("href", mock_server_details)) {
-                Some(url) => match Regex::new(regex) {
-                  Ok(re) => Ok(Value::String(replace_with_regex(example, url, re))),
-                  Err(err) => Err(anyhow!("MockServerURL: Failed to generate value: {}", err))
-                },
-                None => Err(anyhow!("MockServerURL: can not generate a value as there is no mock server URL in the test context"))
-              }
-            },
-            None => Err(anyhow!("MockServerURL: can not generate a value as the mock server details in the test context is not an Object"))
-          }
-        } else {
-          Err(anyhow!("MockServerURL: can not generate a value as there is no mock server details in the test context"))
-        }
-      }
+      Generator::MockServerURL(example, regex) => generate_mock_server_url(example, regex, context)
+        .map(Value::String),
       Generator::ArrayContains(variants) => match value {
         Value::Array(vec) => {
           let mut result = vec.clone();
@@ -1196,65 +1202,58 @@ pub struct JsonHandler {
 }
 
 impl JsonHandler {
-  fn query_object_graph(&self, path_exp: &Vec<PathToken>, tree: &mut Arena<String>, root: NodeId, body: Value) {
-    let mut body_cursor = body;
-    let mut it = path_exp.iter();
-    let mut node_cursor = root;
-    loop {
-      match it.next() {
-        Some(token) => {
-          match token {
-            &PathToken::Field(ref name) => {
-              match body_cursor.clone().as_object() {
-                Some(map) => match map.get(name) {
-                  Some(val) => {
-                    node_cursor = node_cursor.append_value(name.clone(), tree);
-                    body_cursor = val.clone();
-                  },
-                  None => return
-                },
-                None => return
-              }
-            },
-            &PathToken::Index(index) => {
-              match body_cursor.clone().as_array() {
-                Some(list) => if list.len() > index {
-                  node_cursor = node_cursor.append_value(format!("{}", index), tree);
-                  body_cursor = list[index].clone();
-                },
-                None => return
-              }
-            }
-            &PathToken::Star => {
-              match body_cursor.clone().as_object() {
-                Some(map) => {
-                  let remaining = it.by_ref().cloned().collect();
-                  for (key, val) in map {
-                    let node = node_cursor.append_value(key.clone(), tree);
-                    body_cursor = val.clone();
-                    self.query_object_graph(&remaining, tree, node, val.clone());
-                  }
-                },
-                None => return
-              }
-            },
-            &PathToken::StarIndex => {
-              match body_cursor.clone().as_array() {
-                Some(list) => {
-                  let remaining = it.by_ref().cloned().collect();
-                  for (index, val) in list.iter().enumerate() {
-                    let node = node_cursor.append_value(format!("{}", index), tree);
-                    body_cursor = val.clone();
-                    self.query_object_graph(&remaining, tree, node,val.clone());
-                  }
-                },
-                None => return
-              }
-            },
-            _ => ()
+  /// Walks the JSON value with the path tokens, adding a node to the tree for each value visited.
+  /// The nodes for the values matched by the full path are added to `matched`.
+  fn query_object_graph(
+    &self,
+    path_exp: &[PathToken],
+    tree: &mut Arena<String>,
+    root: NodeId,
+    body: &Value,
+    matched: &mut Vec<NodeId>
+  ) {
+    let (token, remaining) = match path_exp.split_first() {
+      Some(next) => next,
+      None => {
+        matched.push(root);
+        return;
+      }
+    };
+    match token {
+      PathToken::Root => self.query_object_graph(remaining, tree, root, body, matched),
+      PathToken::Field(name) => if let Some(val) = body.as_object().and_then(|map| map.get(name)) {
+        let node = root.append_value(name.clone(), tree);
+        self.query_object_graph(remaining, tree, node, val, matched);
+      },
+      PathToken::Index(index) => if let Some(val) = body.as_array().and_then(|list| list.get(*index)) {
+        let node = root.append_value(format!("{}", index), tree);
+        self.query_object_graph(remaining, tree, node, val, matched);
+      },
+      PathToken::Star => if let Some(map) = body.as_object() {
+        for (key, val) in map {
+          let node = root.append_value(key.clone(), tree);
+          self.query_object_graph(remaining, tree, node, val, matched);
+        }
+      },
+      PathToken::StarIndex | PathToken::Slice(_, _) => if let Some(list) = body.as_array() {
+        for (index, val) in list.iter().enumerate() {
+          if matches_token(index.to_string().as_str(), token) > 0 {
+            let node = root.append_value(format!("{}", index), tree);
+            self.query_object_graph(remaining, tree, node, val, matched);
           }
-        },
-        None => break
+        }
+      },
+      PathToken::DeepWildcard => {
+        self.query_object_graph(remaining, tree, root, body, matched);
+        let children: Vec<(String, &Value)> = match body {
+          Value::Object(map) => map.iter().map(|(key, val)| (key.clone(), val)).collect(),
+          Value::Array(list) => list.iter().enumerate().map(|(index, val)| (index.to_string(), val)).collect(),
+          _ => vec![]
+        };
+        for (key, val) in children {
+          let node = root.append_value(key, tree);
+          self.query_object_graph(path_exp, tree, node, val, matched);
+        }
       }
     }
   }
@@ -1287,17 +1286,18 @@ impl ContentTypeHandler<Value> for JsonHandler {
     let path_exp = key;
     let mut tree = Arena::new();
     let root = tree.new_node("".into());
-    self.query_object_graph(path_exp.tokens(), &mut tree, root, self.value.clone());
-    let expanded_paths = root.descendants(&tree).fold(Vec::<String>::new(), |mut acc, node_id| {
-      let node = tree.index(node_id);
-      if !node.get().is_empty() && node.first_child().is_none() {
-        let path: Vec<String> = node_id.ancestors(&tree).map(|n| format!("{}", tree.index(n).get())).collect();
-        if path.len() == path_exp.len() {
-          acc.push(path.iter().rev().join("/"));
-        }
-      }
-      acc
-    });
+    let mut matched = vec![];
+    self.query_object_graph(path_exp.tokens(), &mut tree, root, &self.value, &mut matched);
+    let expanded_paths = matched.iter()
+      .filter(|node_id| **node_id != root)
+      .map(|node_id| {
+        let path: Vec<String> = node_id.ancestors(&tree)
+          .map(|n| tree.index(n).get().replace('~', "~0").replace('/', "~1"))
+          .collect();
+        path.iter().rev().join("/")
+      })
+      .unique()
+      .collect_vec();
 
     if !expanded_paths.is_empty() {
       for pointer_str in expanded_paths {
@@ -1749,6 +1749,12 @@ mod tests {
     expect!(Generator::from_map("Regex", &json!({ "min": 5 }).as_object().unwrap())).to(be_none());
     expect!(Generator::from_map("Regex", &json!({ "regex": "\\d+" }).as_object().unwrap())).to(be_some().value(Generator::Regex("\\d+".to_string())));
     expect!(Generator::from_map("Regex", &json!({ "regex": 5 }).as_object().unwrap())).to(be_some().value(Generator::Regex("5".to_string())));
+    expect!(Generator::from_map("Regex", json!({ "regex": "\\d+", "seed": 42 }).as_object().unwrap())).to(be_some().value(Generator::SeededRegex("\\d+".to_string(), 42)));
+    expect!(Generator::SeededRegex("\\d+".to_string(), 42).to_json().unwrap()).to(be_equal_to(json!({
+      "type": "Regex",
+      "regex": "\\d+",
+      "seed": 42
+    })));
   }
 
   #[test]
@@ -2055,6 +2061,25 @@ mod tests {
     expect!(generated3.unwrap()).to(be_equal_to(now.add(Duration::try_days(1).unwrap()).format("%Y-%m-%d %H:%M:%S").to_string()));
   }
 
+  #[test]
+  #[cfg(feature = "datetime")]
+  fn datetime_generator_keeps_the_time_zone_of_the_base_date_time() {
+    let context = hashmap!{
+      "baseDateTime" => json!("2024-02-28T22:30:00+10:00")
+    };
+    let generated = Generator::DateTime(None, Some("+1 day @ +2 hours".into()))
+      .generate_value(&"".to_string(), &context, &NoopVariantMatcher.boxed());
+    expect!(generated.unwrap()).to(be_equal_to("2024-03-01T00:30:00.000+1000"));
+
+    let generated = Generator::DateTime(Some("yyyy-MM-dd HH:mm:ssXXX".into()), None)
+      .generate_value(&json!(""), &context, &NoopVariantMatcher.boxed());
+    expect!(generated.unwrap()).to(be_equal_to(json!("2024-02-28 22:30:00+10:00")));
+
+    let generated = Generator::DateTime(None, None)
+      .generate_value(&"".to_string(), &hashmap!{ "baseDateTime" => json!("not a date") }, &NoopVariantMatcher.boxed());
+    expect!(generated).to(be_err());
+  }
+
   #[test]
   fn regex_generator_test() {
     let generated = Generator::Regex(r"\d{4}\w{1,4}".into()).generate_value(&"".to_string(), &hashmap!{}, &NoopVariantMatcher.boxed());
@@ -2067,6 +2092,23 @@ mod tests {
     assert_that!(generated.unwrap(), matches_regex(r"^\d{1,2}/\d{1,2}$"));
   }
 
+  #[test]
+  fn regex_generator_with_a_seed_generates_the_same_value() {
+    let generator = Generator::SeededRegex(r"[a-z]{5}-\d{5}".into(), 1234);
+    let generated = generator.generate_value(&"".to_string(), &hashmap!{}, &NoopVariantMatcher.boxed()).unwrap();
+    assert_that!(generated.as_str(), matches_regex(r"^[a-z]{5}-\d{5}$"));
+    for _ in 0..5 {
+      expect!(generator.generate_value(&"".to_string(), &hashmap!{}, &NoopVariantMatcher.boxed()).unwrap())
+        .to(be_equal_to(generated.clone()));
+      expect!(generator.generate_value(&json!(""), &hashmap!{}, &NoopVariantMatcher.boxed()).unwrap())
+        .to(be_equal_to(json!(generated.clone())));
+    }
+
+    let generator = Generator::SeededRegex(r"[a-z]{5}-\d{5}".into(), 4321);
+    expect!(generator.generate_value(&"".to_string(), &hashmap!{}, &NoopVariantMatcher.boxed()).unwrap())
+      .to_not(be_equal_to(generated));
+  }
+
   #[test]
   fn regex_generator_test_with_anchors() {
     let generator = Generator::Regex(r"^\/api\/families\/[0-9a-f]{8}(-[0-9a-f]{4}){3}-[0-9a-f]{12}$".into());
@@ -2082,6 +2124,18 @@ mod tests {
     expect!(&err).to(contain("error: unrecognized escape sequence"));
   }
 
+  #[test]
+  fn regex_generator_strips_anchors_for_all_value_types() {
+    let generated = Generator::Regex(r"^\d{3}".into()).generate_value(&"".to_string(), &hashmap!{}, &NoopVariantMatcher.boxed());
+    assert_that!(generated.unwrap(), matches_regex(r"^\d{3}$"));
+
+    let generated = Generator::Regex(r"[a-z]{2}$".into()).generate_value(&"".to_string(), &hashmap!{}, &NoopVariantMatcher.boxed());
+    assert_that!(generated.unwrap(), matches_regex(r"^[a-z]{2}$"));
+
+    let generated = Generator::Regex(r"^[a-z]{2}-\d{2}$".into()).generate_value(&json!(""), &hashmap!{}, &NoopVariantMatcher.boxed());
+    assert_that!(generated.unwrap().as_str().unwrap(), matches_regex(r"^[a-z]{2}-\d{2}$"));
+  }
+
   #[test]
   fn uuid_generator_test() {
     let generated = Generator::Uuid(None).generate_value(&"".to_string(), &hashmap!{}, &NoopVariantMatcher.boxed());
@@ -2126,6 +2180,15 @@ mod tests {
     assert_that!(generated, matches_regex(r"^\d\.\d$"));
   }
 
+  #[test]
+  fn random_hexadecimal_generator_test() {
+    let generated = Generator::RandomHexadecimal(40).generate_value(&"".to_string(), &hashmap!{}, &NoopVariantMatcher.boxed());
+    assert_that!(generated.unwrap(), matches_regex(r"^[0-9A-F]{40}$"));
+
+    let generated = Generator::RandomHexadecimal(8).generate_value(&json!(""), &hashmap!{}, &NoopVariantMatcher.boxed());
+    assert_that!(generated.unwrap().as_str().unwrap(), matches_regex(r"^[0-9A-F]{8}$"));
+  }
+
   #[test]
   fn mock_server_url_generator_test() {
     let generator = Generator::MockServerURL("http://localhost:1234/path".into(), ".*(/path)$".into());
@@ -2159,6 +2222,52 @@ mod tests {
     expect!(generated.unwrap()).to(be_equal_to(Value::String("http://127.0.0.1:38055/pacts/provider/p/for-verification".to_string())));
   }
 
+  #[test]
+  fn mock_server_url_generator_only_replaces_the_matched_part_of_the_example() {
+    let context = hashmap!{
+      "mockServer" => json!({
+        "href": "http://127.0.0.1:38055/",
+        "port": 38055
+      })
+    };
+    let generator = Generator::MockServerURL(
+      "{\"next\": \"http://localhost:1234/orders?page=2\"}".into(),
+      "http://[^/]+(/orders\\?page=\\d+)".into()
+    );
+    expect!(generator.generate_value(&"".to_string(), &context, &NoopVariantMatcher.boxed()))
+      .to(be_ok().value("{\"next\": \"http://127.0.0.1:38055/orders?page=2\"}".to_string()));
+
+    let generator = Generator::MockServerURL("/path".into(), ".*/path".into());
+    expect!(generator.generate_value(&"".to_string(), &context, &NoopVariantMatcher.boxed())).to(be_err());
+
+    let generator = Generator::MockServerURL("http://localhost:1234/path".into(), ".*(/other)".into());
+    expect!(generator.generate_value(&json!(""), &context, &NoopVariantMatcher.boxed())).to(be_err());
+  }
+
+  #[test]
+  fn mock_server_url_generator_rewrites_absolute_urls() {
+    let context = hashmap!{
+      "mockServer" => json!({
+        "href": "http://127.0.0.1:38055",
+        "port": 38055
+      })
+    };
+    let generator = Generator::MockServerURL("https://api.example.com:8443/orders/1?expand=true#items".into(), ".*/orders/\\d+.*".into());
+    expect!(generator.generate_value(&"".to_string(), &context, &NoopVariantMatcher.boxed()))
+      .to(be_ok().value("http://127.0.0.1:38055/orders/1?expand=true#items".to_string()));
+
+    let generator = Generator::MockServerURL(
+      "{\"self\": \"http://localhost:1234/orders/1\", \"next\": \"http://localhost:1234/orders/2\"}".into(),
+      "http://localhost:1234/orders/\\d+".into()
+    );
+    expect!(generator.generate_value(&json!(""), &context, &NoopVariantMatcher.boxed()))
+      .to(be_ok().value(json!("{\"self\": \"http://127.0.0.1:38055/orders/1\", \"next\": \"http://127.0.0.1:38055/orders/2\"}")));
+
+    let generator = Generator::MockServerURL("<http://localhost:1234/orders?page=2>; rel=\"next\"".into(), "<[^>]+>".into());
+    expect!(generator.generate_value(&"".to_string(), &context, &NoopVariantMatcher.boxed()))
+      .to(be_ok().value("<http://127.0.0.1:38055/orders?page=2>; rel=\"next\"".to_string()));
+  }
+
   #[test]
   fn applies_the_generator_to_a_json_map_entry() {
     let map = json!({"a": 100, "b": "B", "c": "C"});
@@ -2289,6 +2398,39 @@ mod tests {
     expect!(&json_handler.value[2]).to_not(be_equal_to(&json!("C")));
   }
 
+  #[test]
+  fn applies_the_generator_to_an_array_slice() {
+    let value = json!({"items": [{"id": 1}, {"id": 2}, {"id": 3}, {"id": 4}]});
+    let mut json_handler = JsonHandler { value };
+
+    json_handler.apply_key(&DocPath::new_unwrap("$.items[1:3].id"), &Generator::RandomString(4), &hashmap!{}, &NoopVariantMatcher.boxed());
+
+    expect!(&json_handler.value["items"][0]["id"]).to(be_equal_to(&json!(1)));
+    expect!(json_handler.value["items"][1]["id"].is_string()).to(be_true());
+    expect!(json_handler.value["items"][2]["id"].is_string()).to(be_true());
+    expect!(&json_handler.value["items"][3]["id"]).to(be_equal_to(&json!(4)));
+  }
+
+  #[test]
+  fn applies_the_generator_with_a_deep_wildcard() {
+    let value = json!({
+      "id": 1,
+      "user": { "id": 2, "name": "a" },
+      "items": [{ "id": 3, "tags": [{ "id": 4 }] }],
+      "a/b": { "id": 5 }
+    });
+    let mut json_handler = JsonHandler { value };
+
+    json_handler.apply_key(&DocPath::new_unwrap("$.**.id"), &Generator::RandomString(4), &hashmap!{}, &NoopVariantMatcher.boxed());
+
+    expect!(json_handler.value["id"].is_string()).to(be_true());
+    expect!(json_handler.value["user"]["id"].is_string()).to(be_true());
+    expect!(&json_handler.value["user"]["name"]).to(be_equal_to(&json!("a")));
+    expect!(json_handler.value["items"][0]["id"].is_string()).to(be_true());
+    expect!(json_handler.value["items"][0]["tags"][0]["id"].is_string()).to(be_true());
+    expect!(json_handler.value["a/b"]["id"].is_string()).to(be_true());
+  }
+
   #[test]
   fn applies_the_generator_to_the_object_graph_with_wildcard() {
     let value = json!({
@@ -2325,6 +2467,30 @@ mod tests {
     expect!(json.to_string()).to_not(be_equal_to("{}"));
   }
 
+  #[test]
+  fn loads_and_serialises_metadata_generators_with_special_characters_in_the_keys() {
+    let json = json!({
+      "generators": {
+        "metadata": {
+          "correlation.id": { "type": "Uuid" },
+          "$.key": { "type": "RandomInt", "min": 1, "max": 10 }
+        }
+      }
+    });
+
+    let generators = generators_from_json(&json).unwrap();
+    expect!(generators.categories.get(&GeneratorCategory::METADATA).cloned()).to(be_some().value(hashmap!{
+      DocPath::root().join("correlation.id") => Generator::Uuid(None),
+      DocPath::root().join("key") => Generator::RandomInt(1, 10)
+    }));
+    expect!(generators_to_json(&generators, &PactSpecification::V3)).to(be_equal_to(json!({
+      "metadata": {
+        "correlation.id": { "type": "Uuid" },
+        "key": { "type": "RandomInt", "min": 1, "max": 10 }
+      }
+    })));
+  }
+
   #[test]
   fn hash_test_for_generators() {
     let g1 = Generators::default();
@@ -2471,6 +2637,7 @@ mod tests2 {
   #[case(Generator::ProviderStateGenerator("".to_string(), None), "ProviderState")]
   #[case(Generator::MockServerURL("".to_string(), "".to_string()), "MockServerURL")]
   #[case(Generator::ArrayContains(vec![]), "ArrayContains")]
+  #[case(Generator::SeededRegex("".to_string(), 0), "Regex")]
   fn generator_name_test(#[case] generator: Generator, #[case] name: &str) {
     expect!(generator.name()).to(be_equal_to(name));
   }
@@ -1,3 +1,8 @@
+//! Utilities for dealing with HTTP header names and values, so all the components handle headers
+//! in the same way
+
+use std::collections::HashMap;
+
 pub static PARAMETERISED_HEADERS: [&str; 2] = ["accept", "content-type"];
 pub static SINGLE_VALUE_HEADERS: [&str; 9] = [
   "date",
@@ -11,21 +16,119 @@ pub static SINGLE_VALUE_HEADERS: [&str; 9] = [
   "user-agent",
 ];
 
+/// Header names that do not follow the normal capitalisation rules
+static SPECIAL_CASE_HEADERS: [&str; 7] = [
+  "DNT",
+  "ETag",
+  "TE",
+  "WWW-Authenticate",
+  "Content-MD5",
+  "X-XSS-Protection",
+  "X-UA-Compatible"
+];
+
+static DAY_NAMES: [&str; 14] = [
+  "mon", "tue", "wed", "thu", "fri", "sat", "sun",
+  "monday", "tuesday", "wednesday", "thursday", "friday", "saturday", "sunday"
+];
+
 /// Tries to parse the header value into multiple values, taking into account headers that should
 /// not be split.
 pub fn parse_header(name: &str, value: &str) -> Vec<String> {
   if SINGLE_VALUE_HEADERS.contains(&name.to_lowercase().as_str()) {
     vec![ value.trim().to_string() ]
   } else {
-    value.split(',').map(|v| v.trim().to_string()).collect()
+    split_header_value(value)
+  }
+}
+
+/// Splits a header value into multiple values on commas (RFC 9110 section 5.6.1). Commas inside
+/// quoted strings, URI references (`<...>`) and HTTP dates (i.e. `Mon, 01 Dec 2008 01:15:39 GMT`)
+/// are not treated as separators. Values are trimmed.
+pub fn split_header_value(value: &str) -> Vec<String> {
+  let mut values = vec![];
+  let mut current = String::new();
+  let mut in_quotes = false;
+  let mut in_uri = false;
+  let mut chars = value.chars();
+  while let Some(ch) = chars.next() {
+    match ch {
+      '\\' if in_quotes => {
+        current.push(ch);
+        if let Some(next) = chars.next() {
+          current.push(next);
+        }
+      }
+      '"' if !in_uri => {
+        in_quotes = !in_quotes;
+        current.push(ch);
+      }
+      '<' if !in_quotes => {
+        in_uri = true;
+        current.push(ch);
+      }
+      '>' if !in_quotes => {
+        in_uri = false;
+        current.push(ch);
+      }
+      ',' if !in_quotes && !in_uri && !is_date_separator(&current, chars.clone()) => {
+        values.push(current.trim().to_string());
+        current.clear();
+      }
+      _ => current.push(ch)
+    }
   }
+  values.push(current.trim().to_string());
+  values
+}
+
+/// If the comma is part of an HTTP date (a day name followed by a comma and then the day number)
+fn is_date_separator(value: &str, mut rest: impl Iterator<Item=char>) -> bool {
+  let last_word = value.rsplit(|ch: char| !ch.is_ascii_alphabetic()).next().unwrap_or_default();
+  DAY_NAMES.contains(&last_word.to_lowercase().as_str()) &&
+    rest.find(|ch| *ch != ' ').map(|ch| ch.is_ascii_digit()).unwrap_or(false)
+}
+
+/// Returns the canonical form of a header name, with each dash separated word capitalised (i.e.
+/// `content-type` becomes `Content-Type`). Headers with a well known special form (i.e. `ETag`)
+/// will use that.
+pub fn canonical_header_name(name: &str) -> String {
+  let name = name.trim();
+  if let Some(special) = SPECIAL_CASE_HEADERS.iter().find(|header| header.eq_ignore_ascii_case(name)) {
+    return special.to_string();
+  }
+
+  name.split('-')
+    .map(|word| {
+      let mut chars = word.chars();
+      match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars.flat_map(|ch| ch.to_lowercase())).collect(),
+        None => String::new()
+      }
+    })
+    .collect::<Vec<String>>()
+    .join("-")
+}
+
+/// Compares two header names, ignoring case
+pub fn header_names_equal(name: &str, other: &str) -> bool {
+  name.trim().eq_ignore_ascii_case(other.trim())
+}
+
+/// Case-insensitive lookup of a header in a map of headers. Returns the key and value as stored
+/// in the map.
+pub fn find_header<'a, V>(headers: &'a HashMap<String, V>, name: &str) -> Option<(&'a String, &'a V)> {
+  headers.iter().find(|(key, _)| header_names_equal(key, name))
 }
 
 #[cfg(test)]
 mod tests {
   use expectest::prelude::*;
 
-  use crate::headers::parse_header;
+  use maplit::hashmap;
+  use rstest::rstest;
+
+  use crate::headers::{canonical_header_name, find_header, parse_header, split_header_value};
 
   #[test]
   fn parse_simple_header_value() {
@@ -56,4 +159,38 @@ mod tests {
     let parsed = parse_header("User-Agent", "Mozilla/5.0 (X11; Linux x86_64) AppleWebKit/537.36 (KHTML, like Gecko) QtWebEngine/6.6.3 Chrome/112.0.5615.213 Safari/537.36");
     expect!(parsed).to(be_equal_to(vec!["Mozilla/5.0 (X11; Linux x86_64) AppleWebKit/537.36 (KHTML, like Gecko) QtWebEngine/6.6.3 Chrome/112.0.5615.213 Safari/537.36"]));
   }
+
+  #[rstest]
+  #[case("", vec![""])]
+  #[case("a, b,c", vec!["a", "b", "c"])]
+  #[case("a, \"b, c\", d", vec!["a", "\"b, c\"", "d"])]
+  #[case("a, \"b \\\", c\", d", vec!["a", "\"b \\\", c\"", "d"])]
+  #[case("<http://x/?a=1,2>; rel=next, <http://x/?a=3>; rel=last", vec!["<http://x/?a=1,2>; rel=next", "<http://x/?a=3>; rel=last"])]
+  #[case("110 - \"warning\" \"Sat, 01 Jan 2000 00:00:00 GMT\", 199 - \"other\"", vec!["110 - \"warning\" \"Sat, 01 Jan 2000 00:00:00 GMT\"", "199 - \"other\""])]
+  #[case("W/\"1\", Mon, 01 Dec 2008 01:15:39 GMT", vec!["W/\"1\"", "Mon, 01 Dec 2008 01:15:39 GMT"])]
+  #[case("expires=Sunday, 06-Nov-94 08:49:37 GMT, b", vec!["expires=Sunday, 06-Nov-94 08:49:37 GMT", "b"])]
+  #[case("Mon, Tue", vec!["Mon", "Tue"])]
+  fn split_header_value_test(#[case] value: &str, #[case] expected: Vec<&str>) {
+    expect!(split_header_value(value)).to(be_equal_to(expected));
+  }
+
+  #[rstest]
+  #[case("content-type", "Content-Type")]
+  #[case("CONTENT-TYPE", "Content-Type")]
+  #[case("x-request-id", "X-Request-Id")]
+  #[case("accept", "Accept")]
+  #[case("etag", "ETag")]
+  #[case("www-authenticate", "WWW-Authenticate")]
+  #[case("-a--b-", "-A--B-")]
+  fn canonical_header_name_test(#[case] name: &str, #[case] expected: &str) {
+    expect!(canonical_header_name(name)).to(be_equal_to(expected));
+  }
+
+  #[test]
+  fn find_header_ignores_case() {
+    let headers = hashmap!{ "Content-Type".to_string() => 1, "accept".to_string() => 2 };
+    expect!(find_header(&headers, "content-type")).to(be_some().value((&"Content-Type".to_string(), &1)));
+    expect!(find_header(&headers, "ACCEPT")).to(be_some().value((&"accept".to_string(), &2)));
+    expect!(find_header(&headers, "other")).to(be_none());
+  }
 }
@@ -8,6 +8,7 @@ use maplit::hashmap;
 use crate::bodies::OptionalBody;
 use crate::content_types::{ContentType, detect_content_type_from_string};
 use crate::generators::{Generator, GeneratorCategory, Generators};
+use crate::headers::find_header;
 use crate::matchingrules::{Category, MatchingRules};
 use crate::path_exp::DocPath;
 
@@ -44,15 +45,15 @@ pub trait HttpPart {
   /// Tries to detect the content type of the body by matching some regular expressions against
   /// the first 32 characters.
   fn detect_content_type(&self) -> Option<ContentType> {
-    match *self.body() {
-      OptionalBody::Present(ref body, _, _) => {
+    match self.body().value_ref() {
+      Some(body) => {
         let s: String = match from_utf8(body) {
           Ok(s) => s.to_string(),
           Err(_) => String::new()
         };
         detect_content_type_from_string(&s)
       },
-      _ => None
+      None => None
     }
   }
 
@@ -81,9 +82,8 @@ pub trait HttpPart {
   /// Checks if the HTTP Part has the given header
   fn lookup_header_value(&self, header_name: &str) -> Option<String> {
     match *self.headers() {
-      Some(ref h) => h.iter()
-        .find(|kv| kv.0.to_lowercase() == header_name.to_lowercase())
-        .map(|kv| kv.1.clone().join(", ")),
+      Some(ref h) => find_header(h, header_name)
+        .map(|(_, values)| values.join(", ")),
       None => None
     }
   }
@@ -1,11 +1,22 @@
 //! Module for fetching documents via HTTP
 
-use std::fmt::{Display, Formatter};
+use std::fmt::{Debug, Display, Formatter};
+use std::fs;
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
 
 use anyhow::anyhow;
-use reqwest::blocking::Client;
-use reqwest::Error;
-use serde_json::Value;
+use rand::Rng;
+use reqwest::{Certificate, Client, ClientBuilder, Error, Proxy, RequestBuilder, Response, StatusCode};
+use reqwest::header::{ACCEPT, ETAG, HeaderName, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED};
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use tokio::runtime::{Builder, Handle};
+use tokio::time::sleep;
+use tracing::{debug, warn};
 
 /// Type of authentication to use
 #[derive(Debug, Clone)]
@@ -14,6 +25,10 @@ pub enum HttpAuth {
   User(String, Option<String>),
   /// Bearer token
   Token(String),
+  /// Bearer token obtained using the OAuth2 client credentials grant
+  OAuth2(OAuth2ClientCredentials),
+  /// Custom header (name and value)
+  Header(String, String),
   /// No authentication
   None
 }
@@ -26,33 +41,444 @@ impl HttpAuth {
       _ => false
     }
   }
+
+  /// Applies the authentication to the request. For OAuth2, this will request an access token
+  /// from the token endpoint using the client if there is no valid cached token.
+  pub async fn apply(&self, client: &Client, request: RequestBuilder) -> anyhow::Result<RequestBuilder> {
+    match self {
+      HttpAuth::User(username, password) => Ok(request.basic_auth(username, password.clone())),
+      HttpAuth::Token(token) => Ok(request.bearer_auth(token)),
+      HttpAuth::OAuth2(credentials) => Ok(request.bearer_auth(credentials.access_token(client).await?)),
+      HttpAuth::Header(name, value) => Ok(request.header(name.as_str(), value.as_str())),
+      HttpAuth::None => Ok(request)
+    }
+  }
+
+  /// Called when a request using this authentication was rejected with a `401 Unauthorized`
+  /// status. If a cached OAuth2 access token was used, it is invalidated (the server may have
+  /// revoked it before it expired) and true is returned, so the request can be retried once with
+  /// a new token. Returns false for the other types of authentication.
+  pub fn invalidate_on_unauthorized(&self) -> bool {
+    match self {
+      HttpAuth::OAuth2(credentials) => {
+        debug!("Request was unauthorized, invalidating the cached OAuth2 access token");
+        credentials.invalidate();
+        true
+      }
+      _ => false
+    }
+  }
+}
+
+/// Credentials for the OAuth2 client credentials grant. The access token is cached (and shared
+/// between clones) until it expires or a request using it is rejected as unauthorized, after
+/// which a new token will be requested.
+#[derive(Clone)]
+pub struct OAuth2ClientCredentials {
+  /// URL of the token endpoint
+  pub token_url: String,
+  /// Client ID
+  pub client_id: String,
+  /// Client secret
+  pub client_secret: String,
+  /// Scopes to request
+  pub scopes: Vec<String>,
+  token: Arc<Mutex<Option<OAuth2Token>>>
+}
+
+#[derive(Clone)]
+struct OAuth2Token {
+  access_token: String,
+  expires_at: Option<Instant>
+}
+
+impl Debug for OAuth2ClientCredentials {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    f.debug_struct("OAuth2ClientCredentials")
+      .field("token_url", &self.token_url)
+      .field("client_id", &self.client_id)
+      .field("client_secret", &"<redacted>")
+      .field("scopes", &self.scopes)
+      .field("token", &self.token.lock().unwrap().as_ref().map(|_| "<redacted>"))
+      .finish()
+  }
+}
+
+impl OAuth2Token {
+  fn is_valid(&self) -> bool {
+    self.expires_at.map(|expires_at| Instant::now() < expires_at).unwrap_or(true)
+  }
+}
+
+impl OAuth2ClientCredentials {
+  /// Amount of time before the token expires that it will be refreshed
+  const EXPIRY_MARGIN: Duration = Duration::from_secs(30);
+
+  /// Creates the credentials for the given token endpoint
+  pub fn new<S: Into<String>>(token_url: S, client_id: S, client_secret: S, scopes: &[&str]) -> Self {
+    OAuth2ClientCredentials {
+      token_url: token_url.into(),
+      client_id: client_id.into(),
+      client_secret: client_secret.into(),
+      scopes: scopes.iter().map(|scope| scope.to_string()).collect(),
+      token: Default::default()
+    }
+  }
+
+  /// Returns the cached access token, or requests a new one from the token endpoint if there is
+  /// no cached token or it has expired.
+  pub async fn access_token(&self, client: &Client) -> anyhow::Result<String> {
+    if let Some(token) = self.token.lock().unwrap().as_ref().filter(|token| token.is_valid()) {
+      return Ok(token.access_token.clone());
+    }
+
+    debug!("Requesting an OAuth2 access token from '{}'", self.token_url);
+    let mut form = vec![
+      ("grant_type", "client_credentials".to_string()),
+      ("client_id", self.client_id.clone()),
+      ("client_secret", self.client_secret.clone())
+    ];
+    if !self.scopes.is_empty() {
+      form.push(("scope", self.scopes.join(" ")));
+    }
+    let response = client.post(self.token_url.as_str())
+      .header(ACCEPT, "application/json")
+      .form(&form)
+      .send().await
+      .map_err(|err| anyhow!("Failed to request an OAuth2 access token from '{}' - {}", self.token_url, err))?;
+    if !response.status().is_success() {
+      return Err(anyhow!("Failed to request an OAuth2 access token from '{}' - {}", self.token_url, response.status()));
+    }
+
+    let json: Value = response.json().await
+      .map_err(|err| anyhow!("Failed to parse the OAuth2 token response from '{}' - {}", self.token_url, err))?;
+    let access_token = json.get("access_token")
+      .and_then(|token| token.as_str())
+      .ok_or_else(|| anyhow!("OAuth2 token response from '{}' did not contain an access token", self.token_url))?
+      .to_string();
+    let expires_at = json.get("expires_in")
+      .and_then(|expires_in| expires_in.as_u64())
+      .map(|expires_in| Instant::now() + Duration::from_secs(expires_in).saturating_sub(Self::EXPIRY_MARGIN));
+    *self.token.lock().unwrap() = Some(OAuth2Token { access_token: access_token.clone(), expires_at });
+    Ok(access_token)
+  }
+
+  /// Clears the cached access token, so a new one will be requested
+  pub fn invalidate(&self) {
+    *self.token.lock().unwrap() = None;
+  }
+}
+
+/// Policy for retrying failed requests. Requests that fail with a connection error or a server
+/// error (5xx) status are retried, with an exponential backoff between the attempts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+  /// Maximum number of times to retry a request
+  pub max_retries: u8,
+  /// Delay before the first retry. This is doubled for each retry after that.
+  pub initial_delay: Duration,
+  /// Maximum delay between the retries
+  pub max_delay: Duration,
+  /// If a random amount of jitter (up to half the delay) should be removed from each delay, so
+  /// that many clients do not retry at the same time
+  pub jitter: bool
+}
+
+impl RetryPolicy {
+  /// Policy that does not retry any requests
+  pub fn none() -> Self {
+    RetryPolicy {
+      max_retries: 0,
+      .. RetryPolicy::default()
+    }
+  }
+
+  /// Delay to wait before the given retry attempt (starting at 1)
+  pub fn delay(&self, attempt: u8) -> Duration {
+    let delay = self.initial_delay
+      .saturating_mul(2_u32.saturating_pow(attempt.saturating_sub(1) as u32))
+      .min(self.max_delay);
+    if self.jitter {
+      delay.mul_f64(rand::thread_rng().gen_range(0.5..=1.0))
+    } else {
+      delay
+    }
+  }
+}
+
+impl Default for RetryPolicy {
+  fn default() -> Self {
+    RetryPolicy {
+      max_retries: 3,
+      initial_delay: Duration::from_millis(100),
+      max_delay: Duration::from_secs(5),
+      jitter: true
+    }
+  }
+}
+
+/// Options used to configure the HTTP client used to fetch documents
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct HttpClientOptions {
+  /// URL of a proxy to send all the requests through (i.e. `http://proxy.example.com:3128`). If
+  /// not set, any proxy configured with the `HTTP_PROXY` or `HTTPS_PROXY` environment variables
+  /// will be used.
+  pub proxy: Option<String>,
+  /// PEM files with additional root CA certificates to trust. Each file can contain more than one
+  /// certificate.
+  pub ca_certificates: Vec<PathBuf>,
+  /// Do not verify TLS certificates. This is insecure, and should only be used for testing.
+  pub disable_tls_verification: bool
+}
+
+impl HttpClientOptions {
+  /// Applies the options to the client builder
+  pub fn configure(&self, builder: ClientBuilder) -> anyhow::Result<ClientBuilder> {
+    let mut builder = builder.danger_accept_invalid_certs(self.disable_tls_verification);
+    if let Some(proxy) = &self.proxy {
+      let proxy = Proxy::all(proxy.as_str())
+        .map_err(|err| anyhow!("'{}' is not a valid proxy URL - {}", proxy, err))?;
+      builder = builder.proxy(proxy);
+    }
+    for path in &self.ca_certificates {
+      let pem = fs::read(path)
+        .map_err(|err| anyhow!("Could not read CA certificates from '{}' - {}", path.display(), err))?;
+      let certificates = Certificate::from_pem_bundle(&pem)
+        .map_err(|err| anyhow!("Could not load CA certificates from '{}' - {}", path.display(), err))?;
+      for certificate in certificates {
+        builder = builder.add_root_certificate(certificate);
+      }
+    }
+    Ok(builder)
+  }
+
+  /// Builds an HTTP client configured with the options
+  pub fn build_client(&self) -> anyhow::Result<Client> {
+    self.configure(Client::builder())?
+      .build()
+      .map_err(|err| anyhow!("Could not create the HTTP client - {}", err))
+  }
+}
+
+/// Response that has been cached, along with the validators used to make conditional requests
+#[derive(Debug, Clone, PartialEq)]
+pub struct CachedResponse {
+  /// Value of the `ETag` header from the response
+  pub etag: Option<String>,
+  /// Value of the `Last-Modified` header from the response
+  pub last_modified: Option<String>,
+  /// JSON body of the response
+  pub body: Value
+}
+
+impl CachedResponse {
+  /// If the response has any validators that can be used to make a conditional request
+  pub fn has_validators(&self) -> bool {
+    self.etag.is_some() || self.last_modified.is_some()
+  }
+}
+
+/// Cache for the documents fetched via HTTP. Cached documents are only used if the server
+/// responds to a conditional request with a `304 Not Modified` status.
+pub trait HttpCache: Debug + Send + Sync {
+  /// Returns the cached response for the URL
+  fn get(&self, url: &str) -> Option<CachedResponse>;
+
+  /// Stores the response for the URL in the cache
+  fn put(&self, url: &str, response: &CachedResponse) -> anyhow::Result<()>;
+}
+
+/// HTTP cache that stores the responses as files in a directory
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileHttpCache {
+  dir: PathBuf
+}
+
+impl FileHttpCache {
+  /// Creates a cache that stores the responses in the given directory. The directory will be
+  /// created when the first response is stored.
+  pub fn new(dir: impl AsRef<Path>) -> Self {
+    FileHttpCache { dir: dir.as_ref().to_path_buf() }
+  }
+
+  fn path_for(&self, url: &str) -> PathBuf {
+    self.dir.join(format!("{}.json", hex::encode(Sha256::digest(url.as_bytes()))))
+  }
+}
+
+impl HttpCache for FileHttpCache {
+  fn get(&self, url: &str) -> Option<CachedResponse> {
+    let contents = fs::read(self.path_for(url)).ok()?;
+    let json: Value = match serde_json::from_slice(&contents) {
+      Ok(json) => json,
+      Err(err) => {
+        warn!("Ignoring invalid cache entry for '{}' - {}", url, err);
+        return None;
+      }
+    };
+    if json.get("url").and_then(|v| v.as_str()) != Some(url) {
+      return None;
+    }
+    Some(CachedResponse {
+      etag: json.get("etag").and_then(|v| v.as_str()).map(|v| v.to_string()),
+      last_modified: json.get("lastModified").and_then(|v| v.as_str()).map(|v| v.to_string()),
+      body: json.get("body").cloned().unwrap_or_default()
+    })
+  }
+
+  fn put(&self, url: &str, response: &CachedResponse) -> anyhow::Result<()> {
+    fs::create_dir_all(&self.dir)?;
+    let json = json!({
+      "url": url,
+      "etag": response.etag,
+      "lastModified": response.last_modified,
+      "body": response.body
+    });
+    fs::write(self.path_for(url), serde_json::to_vec(&json)?)?;
+    Ok(())
+  }
 }
 
-/// Fetches the JSON from a URL
+/// Options used when fetching documents via HTTP. The defaults are to not retry failed requests,
+/// use an HTTP client with the default settings and not use a cache.
+#[derive(Debug, Clone)]
+pub struct FetchOptions {
+  /// Policy for retrying failed requests
+  pub retry_policy: RetryPolicy,
+  /// Options used to configure the HTTP client
+  pub client_options: HttpClientOptions,
+  /// Cache used to avoid downloading documents that have not changed
+  pub cache: Option<Arc<dyn HttpCache>>
+}
+
+impl Default for FetchOptions {
+  fn default() -> Self {
+    FetchOptions {
+      retry_policy: RetryPolicy::none(),
+      client_options: HttpClientOptions::default(),
+      cache: None
+    }
+  }
+}
+
+/// Fetches the JSON from a URL. This is a blocking wrapper around [`fetch_json_from_url_async`],
+/// and should not be used from async code.
+#[allow(clippy::ptr_arg)]
 pub fn fetch_json_from_url(url: &String, auth: &Option<HttpAuth>) -> anyhow::Result<(String, Value)> {
-  let client = Client::new();
-  let request = match auth {
-    &Some(ref auth) => {
-      match auth {
-        &HttpAuth::User(ref username, ref password) => client.get(url).basic_auth(username.clone(), password.clone()),
-        &HttpAuth::Token(ref token) => client.get(url).bearer_auth(token.clone()),
-        _ => client.get(url)
+  block_on(fetch_json_from_url_async(url, auth))
+}
+
+/// Fetches the JSON from a URL using the async HTTP client
+pub async fn fetch_json_from_url_async(url: &str, auth: &Option<HttpAuth>) -> anyhow::Result<(String, Value)> {
+  fetch_json_from_url_with_options(url, auth, &FetchOptions::default()).await
+}
+
+/// Fetches the JSON from a URL using the async HTTP client configured with the options. Failed
+/// requests are retried using the retry policy from the options. If there is a cache and it has
+/// a response for the URL, a conditional request is made (using the `ETag` and `Last-Modified`
+/// values from the cached response), and the cached JSON is returned if the server responds with
+/// `304 Not Modified`.
+pub async fn fetch_json_from_url_with_options(
+  url: &str,
+  auth: &Option<HttpAuth>,
+  options: &FetchOptions
+) -> anyhow::Result<(String, Value)> {
+  let retry_policy = &options.retry_policy;
+  let cache = options.cache.as_deref();
+  let client = options.client_options.build_client()?;
+  let cached = cache.and_then(|cache| cache.get(url))
+    .filter(|cached| cached.has_validators());
+  let mut attempt = 0;
+  let mut token_refreshed = false;
+  loop {
+    let mut request = match auth {
+      Some(auth) => auth.apply(&client, client.get(url)).await?,
+      None => client.get(url)
+    };
+    if let Some(cached) = &cached {
+      if let Some(etag) = &cached.etag {
+        request = request.header(IF_NONE_MATCH, etag);
       }
-    },
-    &None => client.get(url)
-  };
+      if let Some(last_modified) = &cached.last_modified {
+        request = request.header(IF_MODIFIED_SINCE, last_modified);
+      }
+    }
 
-  match request.send() {
-    Ok(res) => if res.status().is_success() {
-      let pact_json: Result<Value, Error> = res.json();
-      match pact_json {
-        Ok(ref json) => Ok((url.clone(), json.clone())),
-        Err(err) => Err(anyhow!("Failed to parse JSON - {}", err))
+    let result = request.send().await;
+    if let (Ok(res), Some(auth)) = (&result, auth) {
+      if res.status() == StatusCode::UNAUTHORIZED && !token_refreshed && auth.invalidate_on_unauthorized() {
+        debug!("Request to '{}' was unauthorized, retrying with a new access token", url);
+        token_refreshed = true;
+        continue;
       }
-    } else {
-      Err(anyhow!("Request failed with status - {}", res.status()))
-    },
-    Err(err) => Err(anyhow!("Request failed - {}", err))
+    }
+    let retryable = match &result {
+      Ok(res) => res.status().is_server_error(),
+      Err(err) => err.is_connect() || err.is_timeout()
+    };
+    if retryable && attempt < retry_policy.max_retries {
+      attempt += 1;
+      let delay = retry_policy.delay(attempt);
+      match &result {
+        Ok(res) => warn!("Request to '{}' failed with status {}, retrying in {:?} (attempt {}/{})",
+          url, res.status(), delay, attempt, retry_policy.max_retries),
+        Err(err) => warn!("Request to '{}' failed - {}, retrying in {:?} (attempt {}/{})",
+          url, err, delay, attempt, retry_policy.max_retries)
+      }
+      sleep(delay).await;
+      continue;
+    }
+
+    return match result {
+      Ok(res) => if res.status() == StatusCode::NOT_MODIFIED && cached.is_some() {
+        debug!("'{}' has not been modified, using the cached response", url);
+        Ok((url.to_string(), cached.map(|cached| cached.body).unwrap_or_default()))
+      } else if res.status().is_success() {
+        let etag = header_value(&res, ETAG);
+        let last_modified = header_value(&res, LAST_MODIFIED);
+        let pact_json: Result<Value, Error> = res.json().await;
+        match pact_json {
+          Ok(json) => {
+            if let Some(cache) = cache {
+              let response = CachedResponse { etag, last_modified, body: json.clone() };
+              if response.has_validators() {
+                if let Err(err) = cache.put(url, &response) {
+                  warn!("Failed to cache the response for '{}' - {}", url, err);
+                }
+              }
+            }
+            Ok((url.to_string(), json))
+          },
+          Err(err) => Err(anyhow!("Failed to parse JSON - {}", err))
+        }
+      } else {
+        Err(anyhow!("Request failed with status - {}", res.status()))
+      },
+      Err(err) => Err(anyhow!("Request failed - {}", err))
+    }
+  }
+}
+
+fn header_value(response: &Response, name: HeaderName) -> Option<String> {
+  response.headers().get(name)
+    .and_then(|value| value.to_str().ok())
+    .map(|value| value.to_string())
+}
+
+/// Runs the future to completion on a new single-threaded runtime. If there is already a runtime
+/// running on this thread, the future is run on a new thread, as runtimes can not be nested.
+pub(crate) fn block_on<T: Send>(future: impl Future<Output = anyhow::Result<T>> + Send) -> anyhow::Result<T> {
+  let run = move || -> anyhow::Result<T> {
+    Builder::new_current_thread()
+      .enable_all()
+      .build()?
+      .block_on(future)
+  };
+  if Handle::try_current().is_ok() {
+    thread::scope(|scope| scope.spawn(run).join())
+      .map_err(|_| anyhow!("Thread running the HTTP request panicked"))?
+  } else {
+    run()
   }
 }
 
@@ -69,6 +495,9 @@ impl Display for HttpAuth {
           write!(f, "User({}, [no password])", username)
         }
       }
+      HttpAuth::OAuth2(ref credentials) => write!(f, "OAuth2({}, {})", credentials.client_id, credentials.token_url),
+      HttpAuth::Header(ref name, ref value) =>
+        write!(f, "Header({}, {:*<width$})", name, value.get(0..4).unwrap_or(""), width = value.len()),
       _ => write!(f, "None")
     }
   }
@@ -79,3 +508,271 @@ impl Default for HttpAuth {
     HttpAuth::User("".to_string(), None)
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use std::io::{Read, Write};
+  use std::net::TcpListener;
+  use std::sync::{Arc, Mutex};
+
+  use expectest::prelude::*;
+  use serde_json::json;
+
+  use super::*;
+
+  #[test]
+  fn fetch_json_from_url_returns_an_error_if_the_request_fails() {
+    let result = fetch_json_from_url(&"http://127.0.0.1:1/pact.json".to_string(), &None);
+    expect!(result.unwrap_err().to_string()).to(be_equal_to("Request failed - error sending request for url (http://127.0.0.1:1/pact.json)"));
+  }
+
+  // Serves the responses, one per connection, and returns the URL of the server
+  fn serve(responses: Vec<&'static str>) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}/pact.json", listener.local_addr().unwrap());
+    thread::spawn(move || {
+      for response in responses {
+        let (mut stream, _) = listener.accept().unwrap();
+        let mut buffer = [0; 4096];
+        let _ = stream.read(&mut buffer);
+        let _ = stream.write_all(response.as_bytes());
+      }
+    });
+    url
+  }
+
+  // Serves the responses, one per connection, and returns the URL of the server along with the
+  // requests that were received
+  fn serve_and_record(responses: Vec<&'static str>) -> (String, Arc<Mutex<Vec<String>>>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}/pact.json", listener.local_addr().unwrap());
+    let requests = Arc::new(Mutex::new(vec![]));
+    let received = requests.clone();
+    thread::spawn(move || {
+      for response in responses {
+        let (mut stream, _) = listener.accept().unwrap();
+        let mut buffer = [0; 4096];
+        let len = stream.read(&mut buffer).unwrap_or_default();
+        received.lock().unwrap().push(String::from_utf8_lossy(&buffer[..len]).to_lowercase());
+        let _ = stream.write_all(response.as_bytes());
+      }
+    });
+    (url, requests)
+  }
+
+  const UNAVAILABLE: &str = "HTTP/1.1 503 Service Unavailable\r\ncontent-length: 0\r\nconnection: close\r\n\r\n";
+  const OK: &str = "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: 7\r\nconnection: close\r\n\r\n{\"a\":1}";
+  const OK_WITH_ETAG: &str = "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\netag: \"1234\"\r\ncontent-length: 7\r\nconnection: close\r\n\r\n{\"a\":1}";
+  const NOT_MODIFIED: &str = "HTTP/1.1 304 Not Modified\r\netag: \"1234\"\r\nconnection: close\r\n\r\n";
+
+  fn no_delay(max_retries: u8) -> RetryPolicy {
+    RetryPolicy {
+      max_retries,
+      initial_delay: Duration::ZERO,
+      .. RetryPolicy::default()
+    }
+  }
+
+  #[test]
+  fn retry_policy_delay_test() {
+    let policy = RetryPolicy {
+      max_retries: 5,
+      initial_delay: Duration::from_millis(100),
+      max_delay: Duration::from_millis(500),
+      jitter: false
+    };
+    expect!(policy.delay(1)).to(be_equal_to(Duration::from_millis(100)));
+    expect!(policy.delay(2)).to(be_equal_to(Duration::from_millis(200)));
+    expect!(policy.delay(3)).to(be_equal_to(Duration::from_millis(400)));
+    expect!(policy.delay(4)).to(be_equal_to(Duration::from_millis(500)));
+    expect!(policy.delay(u8::MAX)).to(be_equal_to(Duration::from_millis(500)));
+
+    let policy = RetryPolicy { jitter: true, .. policy };
+    let delay = policy.delay(2);
+    expect!(delay >= Duration::from_millis(100) && delay <= Duration::from_millis(200)).to(be_true());
+  }
+
+  fn with_retries(max_retries: u8) -> FetchOptions {
+    FetchOptions {
+      retry_policy: no_delay(max_retries),
+      .. FetchOptions::default()
+    }
+  }
+
+  #[test]
+  fn fetch_json_from_url_does_not_retry_server_errors() {
+    let url = serve(vec![UNAVAILABLE, OK]);
+    let result = fetch_json_from_url(&url, &None);
+    expect!(result.unwrap_err().to_string()).to(be_equal_to("Request failed with status - 503 Service Unavailable"));
+  }
+
+  #[test]
+  fn fetch_json_from_url_with_options_retries_server_errors() {
+    let url = serve(vec![UNAVAILABLE, UNAVAILABLE, OK]);
+    let result = block_on(fetch_json_from_url_with_options(&url, &None, &with_retries(3)));
+    expect!(result.unwrap().1).to(be_equal_to(json!({"a": 1})));
+  }
+
+  #[test]
+  fn fetch_json_from_url_with_options_fails_once_the_retries_are_exhausted() {
+    let url = serve(vec![UNAVAILABLE, UNAVAILABLE, OK]);
+    let result = block_on(fetch_json_from_url_with_options(&url, &None, &with_retries(1)));
+    expect!(result.unwrap_err().to_string()).to(be_equal_to("Request failed with status - 503 Service Unavailable"));
+  }
+
+  #[test]
+  fn fetch_json_from_url_with_options_sends_the_request_via_the_proxy() {
+    let proxy = serve(vec![OK]);
+    let options = FetchOptions {
+      client_options: HttpClientOptions {
+        proxy: Some(proxy.trim_end_matches("/pact.json").to_string()),
+        .. HttpClientOptions::default()
+      },
+      .. FetchOptions::default()
+    };
+    let result = block_on(fetch_json_from_url_with_options("http://pacts.invalid/pact.json", &None, &options));
+    expect!(result.unwrap()).to(be_equal_to(("http://pacts.invalid/pact.json".to_string(), json!({"a": 1}))));
+  }
+
+  #[test]
+  fn http_client_options_returns_an_error_if_the_options_are_invalid() {
+    let options = HttpClientOptions {
+      proxy: Some("not a url".to_string()),
+      .. HttpClientOptions::default()
+    };
+    expect!(options.build_client().unwrap_err().to_string()).to(
+      be_equal_to("'not a url' is not a valid proxy URL - builder error"));
+
+    let options = HttpClientOptions {
+      ca_certificates: vec![PathBuf::from("/does/not/exist.pem")],
+      .. HttpClientOptions::default()
+    };
+    expect!(options.build_client()).to(be_err());
+
+    let options = HttpClientOptions {
+      disable_tls_verification: true,
+      .. HttpClientOptions::default()
+    };
+    expect!(options.build_client()).to(be_ok());
+  }
+
+  #[tokio::test]
+  async fn fetch_json_from_url_can_be_called_from_a_runtime() {
+    let result = fetch_json_from_url(&"http://127.0.0.1:1/pact.json".to_string(), &None);
+    expect!(result).to(be_err());
+
+    let result = fetch_json_from_url_async("http://127.0.0.1:1/pact.json", &None).await;
+    expect!(result).to(be_err());
+  }
+
+  #[test]
+  fn fetch_json_from_url_with_options_uses_the_cached_response_if_not_modified() {
+    let dir = std::env::temp_dir().join(format!("pact-models-http-cache-{}", std::process::id()));
+    let cache = FileHttpCache::new(&dir);
+    let options = FetchOptions {
+      cache: Some(Arc::new(cache.clone())),
+      .. FetchOptions::default()
+    };
+    let (url, requests) = serve_and_record(vec![OK_WITH_ETAG, NOT_MODIFIED]);
+
+    let result = block_on(fetch_json_from_url_with_options(&url, &None, &options));
+    expect!(result.unwrap().1).to(be_equal_to(json!({"a": 1})));
+    expect!(cache.get(&url)).to(be_some().value(CachedResponse {
+      etag: Some("\"1234\"".to_string()),
+      last_modified: None,
+      body: json!({"a": 1})
+    }));
+
+    let result = block_on(fetch_json_from_url_with_options(&url, &None, &options));
+    expect!(result.unwrap().1).to(be_equal_to(json!({"a": 1})));
+
+    let requests = requests.lock().unwrap();
+    expect!(requests[0].contains("if-none-match")).to(be_false());
+    expect!(requests[1].contains("if-none-match: \"1234\"")).to(be_true());
+
+    let _ = fs::remove_dir_all(dir);
+  }
+
+  const TOKEN: &str = "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: 46\r\nconnection: close\r\n\r\n{\"access_token\":\"abc123\",\"expires_in\":3600000}";
+
+  #[test]
+  fn fetch_json_from_url_with_oauth2_auth_requests_and_caches_an_access_token() {
+    let (url, requests) = serve_and_record(vec![TOKEN, OK, OK]);
+    let token_url = url.replace("/pact.json", "/token");
+    let auth = Some(HttpAuth::OAuth2(OAuth2ClientCredentials::new(token_url.as_str(), "client", "secret", &["read", "write"])));
+
+    let result = fetch_json_from_url(&url, &auth);
+    expect!(result.unwrap().1).to(be_equal_to(json!({"a": 1})));
+    let result = fetch_json_from_url(&url, &auth);
+    expect!(result.unwrap().1).to(be_equal_to(json!({"a": 1})));
+
+    let requests = requests.lock().unwrap();
+    expect!(requests.len()).to(be_equal_to(3));
+    expect!(requests[0].starts_with("post /token")).to(be_true());
+    expect!(requests[0].contains("grant_type=client_credentials&client_id=client&client_secret=secret&scope=read+write")).to(be_true());
+    expect!(requests[1].contains("authorization: bearer abc123")).to(be_true());
+    expect!(requests[2].contains("authorization: bearer abc123")).to(be_true());
+  }
+
+  const UNAUTHORIZED: &str = "HTTP/1.1 401 Unauthorized\r\ncontent-length: 0\r\nconnection: close\r\n\r\n";
+  const NEW_TOKEN: &str = "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: 46\r\nconnection: close\r\n\r\n{\"access_token\":\"def456\",\"expires_in\":3600000}";
+
+  #[test]
+  fn fetch_json_from_url_with_oauth2_auth_refreshes_the_token_once_if_the_request_is_unauthorized() {
+    let (url, requests) = serve_and_record(vec![TOKEN, UNAUTHORIZED, NEW_TOKEN, OK]);
+    let token_url = url.replace("/pact.json", "/token");
+    let auth = Some(HttpAuth::OAuth2(OAuth2ClientCredentials::new(token_url.as_str(), "client", "secret", &[])));
+
+    let result = fetch_json_from_url(&url, &auth);
+    expect!(result.unwrap().1).to(be_equal_to(json!({"a": 1})));
+
+    let requests = requests.lock().unwrap();
+    expect!(requests.len()).to(be_equal_to(4));
+    expect!(requests[0].starts_with("post /token")).to(be_true());
+    expect!(requests[1].contains("authorization: bearer abc123")).to(be_true());
+    expect!(requests[2].starts_with("post /token")).to(be_true());
+    expect!(requests[3].contains("authorization: bearer def456")).to(be_true());
+  }
+
+  #[test]
+  fn fetch_json_from_url_with_oauth2_auth_only_refreshes_the_token_once() {
+    let (url, requests) = serve_and_record(vec![TOKEN, UNAUTHORIZED, NEW_TOKEN, UNAUTHORIZED]);
+    let token_url = url.replace("/pact.json", "/token");
+    let auth = Some(HttpAuth::OAuth2(OAuth2ClientCredentials::new(token_url.as_str(), "client", "secret", &[])));
+
+    let result = fetch_json_from_url(&url, &auth);
+    expect!(result.unwrap_err().to_string()).to(be_equal_to("Request failed with status - 401 Unauthorized"));
+    expect!(requests.lock().unwrap().len()).to(be_equal_to(4));
+  }
+
+  #[test]
+  fn fetch_json_from_url_with_token_auth_does_not_retry_unauthorized_requests() {
+    let (url, requests) = serve_and_record(vec![UNAUTHORIZED, OK]);
+    let auth = Some(HttpAuth::Token("1234".to_string()));
+
+    let result = fetch_json_from_url(&url, &auth);
+    expect!(result.unwrap_err().to_string()).to(be_equal_to("Request failed with status - 401 Unauthorized"));
+    expect!(requests.lock().unwrap().len()).to(be_equal_to(1));
+  }
+
+  #[test]
+  fn fetch_json_from_url_with_header_auth_sends_the_header() {
+    let (url, requests) = serve_and_record(vec![OK]);
+    let auth = Some(HttpAuth::Header("X-Api-Key".to_string(), "1234".to_string()));
+
+    let result = fetch_json_from_url(&url, &auth);
+    expect!(result.unwrap().1).to(be_equal_to(json!({"a": 1})));
+    expect!(requests.lock().unwrap()[0].contains("x-api-key: 1234")).to(be_true());
+  }
+
+  #[test]
+  fn oauth2_client_credentials_debug_does_not_include_the_secret() {
+    let credentials = OAuth2ClientCredentials::new("http://localhost/token", "my-client", "my-secret", &[]);
+    *credentials.token.lock().unwrap() = Some(OAuth2Token { access_token: "1234".to_string(), expires_at: None });
+
+    let debug = format!("{:?}", HttpAuth::OAuth2(credentials));
+    expect!(debug.contains("my-client")).to(be_true());
+    expect!(debug.contains("my-secret")).to(be_false());
+    expect!(debug.contains("1234")).to(be_false());
+  }
+}
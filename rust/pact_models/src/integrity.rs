@@ -0,0 +1,243 @@
+//! Integrity metadata for pact files. This records how a pact file was generated, along with a
+//! hash of the pact contents, so it can be checked later that the file has not been edited by hand
+//! since it was generated.
+
+#[cfg(not(target_family = "wasm"))] use std::fs;
+#[cfg(not(target_family = "wasm"))] use std::path::Path;
+
+use anyhow::anyhow;
+#[cfg(not(target_family = "wasm"))] use anyhow::Context;
+use serde_json::{json, Map, Value};
+use sha2::{Digest, Sha256};
+
+#[cfg(not(target_family = "wasm"))] use crate::pact::read_pact_json;
+use crate::PACT_RUST_VERSION;
+
+/// Key in the pact metadata where the integrity metadata is stored
+pub const INTEGRITY_METADATA_KEY: &str = "integrity";
+
+/// Metadata about how a pact file was generated
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IntegrityMetadata {
+  /// Hash of the pact contents (not including the integrity metadata), in the form `sha256:<hex>`
+  pub hash: String,
+  /// Library and version that generated the pact file
+  pub generator: String,
+  /// When the pact file was generated (RFC 3339 format)
+  pub timestamp: Option<String>
+}
+
+impl IntegrityMetadata {
+  /// Creates the integrity metadata for the pact JSON
+  pub fn for_pact(pact_json: &Value) -> Self {
+    IntegrityMetadata {
+      hash: pact_content_hash(pact_json),
+      generator: format!("pact_models/{}", PACT_RUST_VERSION.unwrap_or("unknown")),
+      timestamp: timestamp()
+    }
+  }
+
+  /// Loads the integrity metadata from the metadata of the pact JSON. Returns `None` if there is
+  /// no integrity metadata, or if it is not in the correct form.
+  pub fn from_pact_json(pact_json: &Value) -> Option<Self> {
+    let integrity = pact_json.get("metadata")?.get(INTEGRITY_METADATA_KEY)?;
+    Some(IntegrityMetadata {
+      hash: integrity.get("hash")?.as_str()?.to_string(),
+      generator: integrity.get("generator").and_then(|v| v.as_str()).unwrap_or_default().to_string(),
+      timestamp: integrity.get("timestamp").and_then(|v| v.as_str()).map(|v| v.to_string())
+    })
+  }
+
+  /// Converts this metadata to JSON
+  pub fn to_json(&self) -> Value {
+    let mut json = json!({
+      "hash": self.hash,
+      "generator": self.generator
+    });
+    if let Some(timestamp) = &self.timestamp {
+      json["timestamp"] = Value::String(timestamp.clone());
+    }
+    json
+  }
+}
+
+#[cfg(feature = "datetime")]
+fn timestamp() -> Option<String> {
+  Some(chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true))
+}
+
+#[cfg(not(feature = "datetime"))]
+fn timestamp() -> Option<String> {
+  None
+}
+
+/// Result of verifying the integrity of a pact
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IntegrityResult {
+  /// The pact contents match the hash in the integrity metadata
+  Valid(IntegrityMetadata),
+  /// The pact contents do not match the hash in the integrity metadata, so the pact has been
+  /// changed since it was generated
+  Modified {
+    /// Integrity metadata from the pact
+    metadata: IntegrityMetadata,
+    /// Hash of the current pact contents
+    actual_hash: String
+  },
+  /// The pact does not have any integrity metadata
+  Missing
+}
+
+impl IntegrityResult {
+  /// If the pact contents match the hash in the integrity metadata
+  pub fn is_valid(&self) -> bool {
+    matches!(self, IntegrityResult::Valid(_))
+  }
+}
+
+/// Calculates the hash of the pact JSON. The integrity metadata is excluded, and the hash is
+/// calculated over the same canonical form that [`canonical_pact_json`](crate::pact::canonical_pact_json)
+/// writes (serde_json stores objects in a `BTreeMap`, so the keys are always sorted), so the hash
+/// does not depend on the formatting of the pact file.
+pub fn pact_content_hash(pact_json: &Value) -> String {
+  let mut json = pact_json.clone();
+  remove_integrity_metadata(&mut json);
+  format!("sha256:{}", hex::encode(Sha256::digest(json.to_string().as_bytes())))
+}
+
+/// Adds the integrity metadata (content hash, generating library version and timestamp) to the
+/// metadata of the pact JSON, replacing any existing integrity metadata. Returns an error if the
+/// JSON is not a pact.
+pub fn add_integrity_metadata(pact_json: &mut Value) -> anyhow::Result<()> {
+  let integrity = IntegrityMetadata::for_pact(pact_json);
+  let pact = pact_json.as_object_mut()
+    .ok_or_else(|| anyhow!("Pact JSON must be an object"))?;
+  let metadata = pact.entry("metadata").or_insert_with(|| Value::Object(Map::new()));
+  match metadata.as_object_mut() {
+    Some(metadata) => {
+      metadata.insert(INTEGRITY_METADATA_KEY.to_string(), integrity.to_json());
+      Ok(())
+    }
+    None => Err(anyhow!("Pact metadata must be an object"))
+  }
+}
+
+/// Removes any integrity metadata from the pact JSON
+pub fn remove_integrity_metadata(pact_json: &mut Value) {
+  if let Some(metadata) = pact_json.get_mut("metadata").and_then(|md| md.as_object_mut()) {
+    metadata.remove(INTEGRITY_METADATA_KEY);
+  }
+}
+
+/// Checks the pact JSON against its integrity metadata
+pub fn verify_integrity(pact_json: &Value) -> IntegrityResult {
+  match IntegrityMetadata::from_pact_json(pact_json) {
+    Some(metadata) => {
+      let actual_hash = pact_content_hash(pact_json);
+      if actual_hash == metadata.hash {
+        IntegrityResult::Valid(metadata)
+      } else {
+        IntegrityResult::Modified { metadata, actual_hash }
+      }
+    }
+    None => IntegrityResult::Missing
+  }
+}
+
+/// Checks that the pact file has not been changed since it was generated. Gzip compressed pact
+/// files are supported. Returns an error if the file can not be read or is not valid JSON.
+#[cfg(not(target_family = "wasm"))]
+pub fn verify_pact_file_integrity(path: &Path) -> anyhow::Result<IntegrityResult> {
+  let mut file = fs::File::open(path)?;
+  let json = read_pact_json(&mut file)
+    .with_context(|| format!("Failed to load pact file '{}'", path.display()))?;
+  Ok(verify_integrity(&json))
+}
+
+/// If integrity metadata should be added to pact files when they are written. This is enabled by
+/// setting the `PACT_INTEGRITY_METADATA` environment variable to `true`.
+pub fn integrity_metadata_enabled() -> bool {
+  std::env::var("PACT_INTEGRITY_METADATA")
+    .map(|v| v == "true")
+    .unwrap_or(false)
+}
+
+#[cfg(test)]
+mod tests {
+  use std::io::Write;
+
+  use expectest::prelude::*;
+  use flate2::Compression;
+  use flate2::write::GzEncoder;
+  use serde_json::json;
+
+  use super::*;
+
+  fn pact_json() -> Value {
+    json!({
+      "consumer": { "name": "consumer" },
+      "provider": { "name": "provider" },
+      "interactions": [
+        { "description": "a request", "request": { "method": "GET", "path": "/" }, "response": { "status": 200 } }
+      ],
+      "metadata": {
+        "pactSpecification": { "version": "3.0.0" }
+      }
+    })
+  }
+
+  #[test]
+  fn pact_content_hash_does_not_depend_on_key_order_or_integrity_metadata() {
+    let json = pact_json();
+    let reordered: Value = serde_json::from_str(r#"{
+      "metadata": { "pactSpecification": { "version": "3.0.0" }, "integrity": { "hash": "sha256:1234" } },
+      "interactions": [
+        { "response": { "status": 200 }, "request": { "path": "/", "method": "GET" }, "description": "a request" }
+      ],
+      "provider": { "name": "provider" },
+      "consumer": { "name": "consumer" }
+    }"#).unwrap();
+
+    expect!(pact_content_hash(&json)).to(be_equal_to(pact_content_hash(&reordered)));
+    expect!(pact_content_hash(&json).starts_with("sha256:")).to(be_true());
+  }
+
+  #[test]
+  fn verify_integrity_test() {
+    let mut json = pact_json();
+    expect!(verify_integrity(&json)).to(be_equal_to(IntegrityResult::Missing));
+
+    add_integrity_metadata(&mut json).unwrap();
+    let result = verify_integrity(&json);
+    expect!(result.is_valid()).to(be_true());
+    let metadata = IntegrityMetadata::from_pact_json(&json).unwrap();
+    expect!(metadata.generator.starts_with("pact_models/")).to(be_true());
+
+    json["interactions"][0]["response"]["status"] = json!(201);
+    expect!(verify_integrity(&json)).to(be_equal_to(IntegrityResult::Modified {
+      metadata,
+      actual_hash: pact_content_hash(&json)
+    }));
+
+    remove_integrity_metadata(&mut json);
+    expect!(verify_integrity(&json)).to(be_equal_to(IntegrityResult::Missing));
+  }
+
+  #[test]
+  fn verify_pact_file_integrity_supports_gzipped_pact_files() {
+    let mut json = pact_json();
+    add_integrity_metadata(&mut json).unwrap();
+
+    let mut dir = std::env::temp_dir();
+    dir.push(format!("pact_test_{}", rand::random::<u16>()));
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("pact.json.gz");
+    let mut encoder = GzEncoder::new(fs::File::create(&path).unwrap(), Compression::default());
+    encoder.write_all(json.to_string().as_bytes()).unwrap();
+    encoder.finish().unwrap();
+
+    let result = verify_pact_file_integrity(&path);
+    fs::remove_dir_all(&dir).unwrap();
+    expect!(result.unwrap().is_valid()).to(be_true());
+  }
+}
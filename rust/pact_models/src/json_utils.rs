@@ -107,7 +107,16 @@ pub fn get_field_as_string(field: &str, map: &Map<String, Value>) -> Option<Stri
 
 /// Returns the headers from a JSON struct as Map String -> Vec<String>
 pub fn headers_from_json(request: &Value) -> Option<HashMap<String, Vec<String>>> {
-  match request.get("headers") {
+  header_map_from_json(request, "headers")
+}
+
+/// Parses the HTTP trailers from the JSON struct. Trailers are parsed in the same way as headers.
+pub fn trailers_from_json(json: &Value) -> Option<HashMap<String, Vec<String>>> {
+  header_map_from_json(json, "trailers")
+}
+
+fn header_map_from_json(json: &Value, attr_name: &str) -> Option<HashMap<String, Vec<String>>> {
+  match json.get(attr_name) {
     Some(Value::Object(m)) => {
       Some(m.iter().map(|(key, val)| {
         match val {
@@ -24,16 +24,19 @@ pub mod bodies;
 pub mod v4;
 pub mod provider_states;
 pub mod verify_json;
+pub mod validate;
 pub mod json_utils;
 pub mod expression_parser;
 #[cfg(feature = "datetime")] pub mod time_utils;
 #[cfg(feature = "datetime")] mod timezone_db;
 #[cfg(not(target_family = "wasm"))] pub mod file_utils;
 #[cfg(feature = "xml")] pub mod xml_utils;
+#[cfg(feature = "xml")] pub mod xml_builder;
 pub mod matchingrules;
 pub mod generators;
 pub mod path_exp;
 pub mod query_strings;
+pub mod multipart;
 #[cfg(not(target_family = "wasm"))] pub mod http_utils;
 pub mod http_parts;
 pub mod request;
@@ -43,9 +46,11 @@ pub mod interaction;
 pub mod sync_interaction;
 pub mod message;
 pub mod pact;
+pub mod diff;
+pub mod conversion;
+pub mod integrity;
 pub mod sync_pact;
 pub mod message_pact;
-mod iterator_utils;
 pub mod plugins;
 
 /// A "prelude" or a default list of import types to include.
@@ -103,6 +103,24 @@
 //! array to have to have at least 2 items, at most 10, and each item in the array must match the
 //! given regex.
 //!
+//! ## Parsing expressions
+//!
+//! Use [`parse_matcher_def`] to parse an expression into a [`MatchingRuleDefinition`], and
+//! [`is_matcher_def`] to check if a string value looks like an expression. If an expression is not
+//! valid, the error will contain a report that points to the position in the expression where the
+//! problem was found, so it can be displayed to the user. For example, `matching(type,'Name') 100`
+//! will return the error:
+//!
+//! ```text
+//! Error: Expected a comma, got '100'
+//!    ╭─[expression:1:23]
+//!    │
+//!  1 │ matching(type,'Name') 100
+//!    │                       ─┬─
+//!    │                        ╰─── Expected a comma before this
+//! ───╯
+//! ```
+//!
 //! ## Grammar
 //!
 //! There is a grammar for the definitions in [ANTLR4 format](https://github.com/pact-foundation/pact-plugins/blob/main/docs/matching-rule-definition.g4).
@@ -339,20 +357,14 @@ pub fn is_matcher_def(v: &str) -> bool {
 //     ;
 fn matching_definition(lex: &mut Lexer<MatcherDefinitionToken>, v: &str) -> anyhow::Result<MatchingRuleDefinition> {
   let mut value = matching_definition_exp(lex, v)?;
-  while let Some(Ok(next)) = lex.next() {
-    if next == MatcherDefinitionToken::Comma {
+  while let Some(next) = lex.next() {
+    if let Ok(MatcherDefinitionToken::Comma) = next {
       value = value.merge(&matching_definition_exp(lex, v)?);
     } else {
-      return Err(anyhow!("expected comma, got '{}'", lex.slice()));
+      return Err(anyhow!(error_message(lex, v, "Expected a comma", "Expected a comma before this")?));
     }
   }
-
-  let remainder = lex.remainder();
-  if !remainder.is_empty() {
-    Err(anyhow!("expected not more tokens, got '{}' with '{}' remaining", lex.slice(), remainder))
-  } else {
-    Ok(value)
-  }
+  Ok(value)
 }
 
 // matchingDefinitionExp returns [ MatchingRuleDefinition value ] :
@@ -543,10 +555,10 @@ fn parse_not_empty(
     if let Ok(MatcherDefinitionToken::RightBracket) = next {
       Ok(result)
     } else {
-      Err(anyhow!("expected closing bracket, got '{}'", lex.slice()))
+      Err(anyhow!(error_message(lex, v, "Expected a closing bracket", "Expected a closing bracket before this")?))
     }
   } else {
-    Err(anyhow!("expected '(', got '{}'", lex.remainder()))
+    Err(anyhow!(error_message(lex, v, "Expected an opening bracket", "Expected an opening bracket before this")?))
   }
 }
 
@@ -1522,6 +1534,47 @@ mod test {
             ".trim_margin().unwrap()));
   }
 
+  #[test]
+  fn parse_matcher_def_with_invalid_trailing_tokens() {
+    expect!(as_string!(super::parse_matcher_def("matching(type,'Name') notEmpty('test')"))).to(
+      be_err().value(
+        "|Error: Expected a comma, got 'notEmpty'
+            |   ╭─[expression:1:23]
+            |   │
+            | 1 │ matching(type,'Name') notEmpty('test')
+            |   │                       ────┬─── \u{0020}
+            |   │                           ╰───── Expected a comma before this
+            |───╯
+            |
+            ".trim_margin().unwrap()));
+  }
+
+  #[test]
+  fn parse_not_empty_with_invalid_brackets() {
+    expect!(as_string!(super::parse_matcher_def("notEmpty('test' 'other')"))).to(
+      be_err().value(
+        "|Error: Expected a closing bracket, got ''other''
+            |   ╭─[expression:1:17]
+            |   │
+            | 1 │ notEmpty('test' 'other')
+            |   │                 ───┬─── \u{0020}
+            |   │                    ╰───── Expected a closing bracket before this
+            |───╯
+            |
+            ".trim_margin().unwrap()));
+    expect!(as_string!(super::parse_matcher_def("notEmpty 'test'"))).to(
+      be_err().value(
+        "|Error: Expected an opening bracket, got ''test''
+            |   ╭─[expression:1:10]
+            |   │
+            | 1 │ notEmpty 'test'
+            |   │          ───┬── \u{0020}
+            |   │             ╰──── Expected an opening bracket before this
+            |───╯
+            |
+            ".trim_margin().unwrap()));
+  }
+
   #[test]
   fn parse_each_key_test() {
     let mut lex = MatcherDefinitionToken::lexer("(matching($'bob'))");
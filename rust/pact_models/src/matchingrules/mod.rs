@@ -57,8 +57,12 @@ fn rules_from_json(attributes: &Map<String, Value>) -> anyhow::Result<Vec<Either
   }
 }
 
+/// Type used to serialise custom matching rules. This is namespaced, as custom matchers are not part
+/// of the Pact specification.
+pub const CUSTOM_MATCHER_TYPE: &str = "pact-rust:custom";
+
 /// Set of all matching rules
-#[derive(Debug, Clone, Eq)]
+#[derive(Debug, Clone)]
 pub enum MatchingRule {
   /// Matcher using equals
   Equality,
@@ -105,7 +109,32 @@ pub enum MatchingRule {
   /// Matcher for keys in a map
   EachKey(MatchingRuleDefinition),
   /// Matcher for values in a collection. This delegates to the Values matcher for maps.
-  EachValue(MatchingRuleDefinition)
+  EachValue(MatchingRuleDefinition),
+  /// Matcher that delegates to a custom matcher function registered by name, with the
+  /// configuration to pass to it. This is not part of the Pact specification, and is serialised
+  /// with the `pact-rust:custom` type so that it is not mistaken for a specification matcher.
+  Custom(String, HashMap<String, Value>),
+  /// Match using equals, ignoring the case of any strings
+  EqualsIgnoreCase,
+  /// Match numbers that differ from the expected value by no more than the given tolerance
+  DecimalWithTolerance(f64),
+  /// Match a timestamp (using the format, or ISO 8601 if it is empty) that is within the number of
+  /// seconds of the reference timestamp. If there is no reference timestamp, the current time is used.
+  TimestampWithin(String, u64, Option<String>),
+  /// Validate the value (and all the values below it) against a JSON Schema document
+  JsonSchema(Value),
+  /// Match the items of a collection using equality, ignoring the order of the items. The
+  /// collections must have the same number of items.
+  EqualsIgnoreOrder,
+  /// Match the items of a collection using equality, ignoring the order of the items, and a minimum
+  /// length for the collection
+  MinEqualsIgnoreOrder(usize),
+  /// Match the items of a collection using equality, ignoring the order of the items, and a maximum
+  /// length for the collection
+  MaxEqualsIgnoreOrder(usize),
+  /// Match the items of a collection using equality, ignoring the order of the items, and a minimum
+  /// and maximum length for the collection
+  MinMaxEqualsIgnoreOrder(usize, usize)
 }
 
 impl MatchingRule {
@@ -193,6 +222,27 @@ impl MatchingRule {
       MatchingRule::StatusCode(status) => json!({ "match": "statusCode", "status": status.to_json() }),
       MatchingRule::NotEmpty => json!({ "match": "notEmpty" }),
       MatchingRule::Semver => json!({ "match": "semver" }),
+      MatchingRule::Custom(name, config) => json!({ "match": CUSTOM_MATCHER_TYPE, "name": name,
+        "config": config }),
+      MatchingRule::EqualsIgnoreCase => json!({ "match": "equalsIgnoreCase" }),
+      MatchingRule::DecimalWithTolerance(tolerance) => json!({ "match": "decimalWithTolerance",
+        "tolerance": tolerance }),
+      MatchingRule::TimestampWithin(format, seconds, reference) => {
+        let mut json = json!({ "match": "timestampWithin", "format": Value::String(format.clone()),
+          "seconds": seconds });
+        if let Some(reference) = reference {
+          json["reference"] = Value::String(reference.clone());
+        }
+        json
+      }
+      MatchingRule::JsonSchema(schema) => json!({ "match": "jsonSchema", "schema": schema }),
+      MatchingRule::EqualsIgnoreOrder => json!({ "match": "ignore-order" }),
+      MatchingRule::MinEqualsIgnoreOrder(min) => json!({ "match": "ignore-order",
+        "min": json!(*min as u64) }),
+      MatchingRule::MaxEqualsIgnoreOrder(max) => json!({ "match": "ignore-order",
+        "max": json!(*max as u64) }),
+      MatchingRule::MinMaxEqualsIgnoreOrder(min, max) => json!({ "match": "ignore-order",
+        "min": json!(*min as u64), "max": json!(*max as u64) }),
       MatchingRule::EachKey(definition) => {
         let mut json = json!({
           "match": "eachKey",
@@ -276,7 +326,16 @@ impl MatchingRule {
       MatchingRule::NotEmpty => "not-empty",
       MatchingRule::Semver => "semver",
       MatchingRule::EachKey(_) => "each-key",
-      MatchingRule::EachValue(_) => "each-value"
+      MatchingRule::EachValue(_) => "each-value",
+      MatchingRule::Custom(_, _) => "custom",
+      MatchingRule::EqualsIgnoreCase => "equals-ignore-case",
+      MatchingRule::DecimalWithTolerance(_) => "decimal-with-tolerance",
+      MatchingRule::TimestampWithin(_, _, _) => "timestamp-within",
+      MatchingRule::JsonSchema(_) => "json-schema",
+      MatchingRule::EqualsIgnoreOrder => "equals-ignore-order",
+      MatchingRule::MinEqualsIgnoreOrder(_) => "min-equals-ignore-order",
+      MatchingRule::MaxEqualsIgnoreOrder(_) => "max-equals-ignore-order",
+      MatchingRule::MinMaxEqualsIgnoreOrder(_, _) => "min-max-equals-ignore-order"
     }.to_string()
   }
 
@@ -311,6 +370,27 @@ impl MatchingRule {
       MatchingRule::StatusCode(sc) => hashmap!{ "status" => sc.to_json() },
       MatchingRule::NotEmpty => empty,
       MatchingRule::Semver => empty,
+      MatchingRule::EqualsIgnoreCase => empty,
+      MatchingRule::DecimalWithTolerance(tolerance) => hashmap!{ "tolerance" => json!(tolerance) },
+      MatchingRule::TimestampWithin(format, seconds, reference) => {
+        let mut map = hashmap!{
+          "format" => Value::String(format.clone()),
+          "seconds" => json!(seconds)
+        };
+        if let Some(reference) = reference {
+          map.insert("reference", Value::String(reference.clone()));
+        }
+        map
+      }
+      MatchingRule::JsonSchema(schema) => hashmap!{ "schema" => schema.clone() },
+      MatchingRule::EqualsIgnoreOrder => empty,
+      MatchingRule::MinEqualsIgnoreOrder(min) => hashmap!{ "min" => json!(min) },
+      MatchingRule::MaxEqualsIgnoreOrder(max) => hashmap!{ "max" => json!(max) },
+      MatchingRule::MinMaxEqualsIgnoreOrder(min, max) => hashmap!{ "min" => json!(min), "max" => json!(max) },
+      MatchingRule::Custom(name, config) => hashmap!{
+        "name" => Value::String(name.clone()),
+        "config" => json!(config)
+      },
       MatchingRule::EachKey(definition) | MatchingRule::EachValue(definition) => {
         let mut map = hashmap! {
           "rules" => Value::Array(definition.rules.iter()
@@ -445,6 +525,49 @@ impl MatchingRule {
       },
       "notEmpty" | "not-empty" => Ok(MatchingRule::NotEmpty),
       "semver" => Ok(MatchingRule::Semver),
+      "equalsIgnoreCase" | "equals-ignore-case" => Ok(MatchingRule::EqualsIgnoreCase),
+      "decimalWithTolerance" | "decimal-with-tolerance" => match attributes.get("tolerance") {
+        Some(tolerance) => match tolerance.as_f64().or_else(|| tolerance.as_str().and_then(|s| s.parse().ok())) {
+          Some(tolerance) if tolerance >= 0.0 => Ok(MatchingRule::DecimalWithTolerance(tolerance)),
+          _ => Err(anyhow!("DecimalWithTolerance matcher 'tolerance' field must be a number that is not negative"))
+        },
+        None => Err(anyhow!("DecimalWithTolerance matcher missing 'tolerance' field")),
+      },
+      "timestampWithin" | "timestamp-within" => match attributes.get("seconds").and_then(|v| v.as_u64()) {
+        Some(seconds) => Ok(MatchingRule::TimestampWithin(
+          attributes.get("format").map(json_to_string).unwrap_or_default(),
+          seconds,
+          attributes.get("reference").map(json_to_string)
+        )),
+        None => Err(anyhow!("TimestampWithin matcher missing 'seconds' field")),
+      },
+      "jsonSchema" | "json-schema" => match attributes.get("schema") {
+        Some(Value::String(schema)) => serde_json::from_str(schema)
+          .map(MatchingRule::JsonSchema)
+          .map_err(|err| anyhow!("JsonSchema matcher 'schema' field is not a valid JSON document - {}", err)),
+        Some(schema @ (Value::Object(_) | Value::Bool(_))) => Ok(MatchingRule::JsonSchema(schema.clone())),
+        Some(_) => Err(anyhow!("JsonSchema matcher 'schema' field must be a JSON Schema document")),
+        None => Err(anyhow!("JsonSchema matcher missing 'schema' field")),
+      },
+      "ignore-order" | "ignoreOrder" | "equals-ignore-order" => match (json_to_num(attributes.get("min").cloned()), json_to_num(attributes.get("max").cloned())) {
+        (Some(min), Some(max)) => Ok(MatchingRule::MinMaxEqualsIgnoreOrder(min, max)),
+        (Some(min), None) => Ok(MatchingRule::MinEqualsIgnoreOrder(min)),
+        (None, Some(max)) => Ok(MatchingRule::MaxEqualsIgnoreOrder(max)),
+        _ => Ok(MatchingRule::EqualsIgnoreOrder)
+      },
+      CUSTOM_MATCHER_TYPE => match attributes.get("name") {
+        Some(name) => {
+          let config = match attributes.get("config") {
+            Some(Value::Object(config)) => config.iter()
+              .map(|(k, v)| (k.clone(), v.clone()))
+              .collect(),
+            Some(Value::Null) | None => HashMap::default(),
+            Some(_) => return Err(anyhow!("Custom matcher 'config' field is not an Object"))
+          };
+          Ok(MatchingRule::Custom(json_to_string(name), config))
+        }
+        None => Err(anyhow!("Custom matcher missing 'name' field")),
+      },
       "eachKey" | "each-key" => {
         let generator = generator_from_json(&attributes);
         let value = attributes.get("value").cloned().unwrap_or_default();
@@ -488,9 +611,49 @@ impl MatchingRule {
       MatchingRule::Values => false,
       MatchingRule::EachValue(_) => false,
       MatchingRule::EachKey(_) => false,
+      MatchingRule::JsonSchema(_) => false,
       _ => true
     }
   }
+
+  /// Returns the earliest version of the Pact specification that supports this matching rule
+  pub fn min_spec_version(&self) -> PactSpecification {
+    match self {
+      MatchingRule::Regex(_) => PactSpecification::V2,
+      MatchingRule::Type => PactSpecification::V2,
+      MatchingRule::MinType(_) => PactSpecification::V2,
+      MatchingRule::MaxType(_) => PactSpecification::V2,
+      MatchingRule::MinMaxType(_, _) => PactSpecification::V2,
+      MatchingRule::Equality => PactSpecification::V3,
+      MatchingRule::Timestamp(_) => PactSpecification::V3,
+      MatchingRule::Time(_) => PactSpecification::V3,
+      MatchingRule::Date(_) => PactSpecification::V3,
+      MatchingRule::Include(_) => PactSpecification::V3,
+      MatchingRule::Number => PactSpecification::V3,
+      MatchingRule::Integer => PactSpecification::V3,
+      MatchingRule::Decimal => PactSpecification::V3,
+      MatchingRule::Null => PactSpecification::V3,
+      MatchingRule::ContentType(_) => PactSpecification::V3,
+      MatchingRule::Values => PactSpecification::V3,
+      MatchingRule::ArrayContains(_) => PactSpecification::V4,
+      MatchingRule::Boolean => PactSpecification::V4,
+      MatchingRule::StatusCode(_) => PactSpecification::V4,
+      MatchingRule::NotEmpty => PactSpecification::V4,
+      MatchingRule::Semver => PactSpecification::V4,
+      MatchingRule::EachKey(_) => PactSpecification::V4,
+      MatchingRule::EachValue(_) => PactSpecification::V4,
+      // Not part of any specification, but can only be written to V4 pacts
+      MatchingRule::Custom(_, _) => PactSpecification::V4,
+      MatchingRule::EqualsIgnoreCase => PactSpecification::V4,
+      MatchingRule::DecimalWithTolerance(_) => PactSpecification::V4,
+      MatchingRule::TimestampWithin(_, _, _) => PactSpecification::V4,
+      MatchingRule::JsonSchema(_) => PactSpecification::V4,
+      MatchingRule::EqualsIgnoreOrder => PactSpecification::V4,
+      MatchingRule::MinEqualsIgnoreOrder(_) => PactSpecification::V4,
+      MatchingRule::MaxEqualsIgnoreOrder(_) => PactSpecification::V4,
+      MatchingRule::MinMaxEqualsIgnoreOrder(_, _) => PactSpecification::V4
+    }
+  }
 }
 
 impl Hash for MatchingRule {
@@ -504,11 +667,31 @@ impl Hash for MatchingRule {
         min.hash(state);
         max.hash(state);
       }
+      MatchingRule::MinEqualsIgnoreOrder(min) => min.hash(state),
+      MatchingRule::MaxEqualsIgnoreOrder(max) => max.hash(state),
+      MatchingRule::MinMaxEqualsIgnoreOrder(min, max) => {
+        min.hash(state);
+        max.hash(state);
+      }
       MatchingRule::Timestamp(format) => format.hash(state),
       MatchingRule::Time(format) => format.hash(state),
       MatchingRule::Date(format) => format.hash(state),
       MatchingRule::Include(str) => str.hash(state),
       MatchingRule::ContentType(str) => str.hash(state),
+      MatchingRule::DecimalWithTolerance(tolerance) => tolerance.to_bits().hash(state),
+      MatchingRule::TimestampWithin(format, seconds, reference) => {
+        format.hash(state);
+        seconds.hash(state);
+        reference.hash(state);
+      }
+      MatchingRule::JsonSchema(schema) => schema.to_string().hash(state),
+      MatchingRule::Custom(name, config) => {
+        name.hash(state);
+        for (k, v) in config.iter().sorted_by(|(a, _), (b, _)| Ord::cmp(a, b)) {
+          k.hash(state);
+          v.to_string().hash(state);
+        }
+      }
       MatchingRule::ArrayContains(variants) => {
         for (index, rules, generators) in variants {
           index.hash(state);
@@ -531,17 +714,28 @@ impl PartialEq for MatchingRule {
       (MatchingRule::MinType(min1), MatchingRule::MinType(min2)) => min1 == min2,
       (MatchingRule::MaxType(max1), MatchingRule::MaxType(max2)) => max1 == max2,
       (MatchingRule::MinMaxType(min1, max1), MatchingRule::MinMaxType(min2, max2)) => min1 == min2 && max1 == max2,
+      (MatchingRule::MinEqualsIgnoreOrder(min1), MatchingRule::MinEqualsIgnoreOrder(min2)) => min1 == min2,
+      (MatchingRule::MaxEqualsIgnoreOrder(max1), MatchingRule::MaxEqualsIgnoreOrder(max2)) => max1 == max2,
+      (MatchingRule::MinMaxEqualsIgnoreOrder(min1, max1), MatchingRule::MinMaxEqualsIgnoreOrder(min2, max2)) => min1 == min2 && max1 == max2,
       (MatchingRule::Timestamp(format1), MatchingRule::Timestamp(format2)) => format1 == format2,
       (MatchingRule::Time(format1), MatchingRule::Time(format2)) => format1 == format2,
       (MatchingRule::Date(format1), MatchingRule::Date(format2)) => format1 == format2,
       (MatchingRule::Include(str1), MatchingRule::Include(str2)) => str1 == str2,
       (MatchingRule::ContentType(str1), MatchingRule::ContentType(str2)) => str1 == str2,
       (MatchingRule::ArrayContains(variants1), MatchingRule::ArrayContains(variants2)) => variants1 == variants2,
+      (MatchingRule::Custom(name1, config1), MatchingRule::Custom(name2, config2)) => name1 == name2 && config1 == config2,
+      (MatchingRule::DecimalWithTolerance(tolerance1), MatchingRule::DecimalWithTolerance(tolerance2)) =>
+        tolerance1.to_bits() == tolerance2.to_bits(),
+      (MatchingRule::TimestampWithin(format1, seconds1, reference1), MatchingRule::TimestampWithin(format2, seconds2, reference2)) =>
+        format1 == format2 && seconds1 == seconds2 && reference1 == reference2,
+      (MatchingRule::JsonSchema(schema1), MatchingRule::JsonSchema(schema2)) => schema1 == schema2,
       _ => mem::discriminant(self) == mem::discriminant(other)
     }
   }
 }
 
+impl Eq for MatchingRule {}
+
 /// Enumeration to define how to combine rules
 #[derive(PartialEq, Debug, Clone, Copy, Eq, Hash, PartialOrd, Ord)]
 pub enum RuleLogic {
@@ -696,7 +890,9 @@ pub enum Category {
   /// Message contents (body)
   CONTENTS,
   /// Message metadata
-  METADATA
+  METADATA,
+  /// Request/Response Trailer
+  TRAILER
 }
 
 impl Category {
@@ -721,6 +917,7 @@ impl FromStr for Category {
       "status" => Ok(Category::STATUS),
       "contents" => Ok(Category::CONTENTS),
       "metadata" => Ok(Category::METADATA),
+      "trailer" | "trailers" => Ok(Category::TRAILER),
       _ => Err(format!("'{}' is not a valid Category", s))
     }
   }
@@ -736,7 +933,8 @@ impl <'a> Into<&'a str> for Category {
       Category::BODY => "body",
       Category::STATUS => "status",
       Category::CONTENTS => "contents",
-      Category::METADATA => "metadata"
+      Category::METADATA => "metadata",
+      Category::TRAILER => "trailer"
     }
   }
 }
@@ -863,11 +1061,14 @@ impl MatchingRuleCategory {
   pub fn to_v3_json(&self) -> Value {
     Value::Object(self.rules.iter().fold(serde_json::Map::new(), |mut map, (category, rulelist)| {
       match self.name {
-        Category::HEADER | Category::QUERY => {
+        Category::HEADER | Category::QUERY | Category::TRAILER => {
           let name = category.first_field().map(|v| v.to_string())
             .unwrap_or_else(|| category.to_string());
           map.insert(name, rulelist.to_v3_json());
         }
+        Category::METADATA => {
+          map.insert(metadata_path_key(category), rulelist.to_v3_json());
+        }
         _ => {
           map.insert(String::from(category), rulelist.to_v3_json());
         }
@@ -887,7 +1088,7 @@ impl MatchingRuleCategory {
       Category::BODY => for (k, v) in self.rules.clone() {
         map.insert(String::from(k).replace("$", "$.body"), v.to_v2_json());
       }
-      Category::HEADER | Category::QUERY => for (k, v) in &self.rules {
+      Category::HEADER | Category::QUERY | Category::TRAILER => for (k, v) in &self.rules {
         let mut path = DocPath::root();
         path.push_field(self.name.v2_form());
         path.push_path(k);
@@ -923,7 +1124,7 @@ impl MatchingRuleCategory {
   pub fn resolve_matchers_for_path(&self, path: &[&str]) -> MatchingRuleCategory {
     match self.name {
       Category::HEADER| Category::QUERY | Category::BODY |
-      Category::CONTENTS | Category::METADATA => self.filter(|(val, _)| {
+      Category::CONTENTS | Category::METADATA | Category::TRAILER => self.filter(|(val, _)| {
         val.matches_path(path)
       }),
       _ => self.clone()
@@ -994,12 +1195,16 @@ impl MatchingRuleCategory {
     } else if let Value::Object(m) = rules {
       if m.contains_key("matchers") {
         self.add_rule_list(DocPath::empty(), rules)?;
-      } else if self.name == Category::QUERY || self.name == Category::HEADER {
+      } else if self.name == Category::QUERY || self.name == Category::HEADER || self.name == Category::TRAILER {
         for (k, v) in m {
           let mut path = DocPath::root();
           path.push_field(k);
           self.add_rule_list(path, v)?;
         }
+      } else if self.name == Category::METADATA {
+        for (k, v) in m {
+          self.add_rule_list(metadata_key_path(k)?, v)?;
+        }
       } else {
         for (k, v) in m {
           self.add_rule_list(DocPath::new(k)?, v)?;
@@ -1191,7 +1396,7 @@ impl MatchingRules {
       Category::BODY => self.rules_for_category(Category::BODY).map(|category| category.filter(|&(val, _)| {
         val.matches_path_exactly(path)
       })),
-      Category::HEADER | Category::QUERY => self.rules_for_category(category.clone()).map(|category| category.filter(|&(val, _)| {
+      Category::HEADER | Category::QUERY | Category::TRAILER => self.rules_for_category(category.clone()).map(|category| category.filter(|&(val, _)| {
         path.len() == 1 && Some(path[0]) == val.first_field()
       })),
       _ => self.rules_for_category(category)
@@ -1374,6 +1579,24 @@ pub fn matchers_to_json(matchers: &MatchingRules, spec_version: &PactSpecificati
   }
 }
 
+/// Returns the path for a metadata key. Metadata keys are normally the names of the metadata
+/// values (i.e. `correlation.id`), but keys starting with `$` are parsed as path expressions.
+pub(crate) fn metadata_key_path(key: &str) -> anyhow::Result<DocPath> {
+  if key == "$" || key.starts_with("$.") || key.starts_with("$[") {
+    DocPath::new(key)
+  } else {
+    Ok(DocPath::root().push_field(key).clone())
+  }
+}
+
+/// Returns the key to use in JSON for the path of a metadata value
+pub(crate) fn metadata_path_key(path: &DocPath) -> String {
+  match path.tokens().as_slice() {
+    [PathToken::Root, PathToken::Field(name)] => name.clone(),
+    _ => String::from(path)
+  }
+}
+
 /// Macro to ease constructing matching rules
 /// Example usage:
 /// ```
@@ -1775,6 +1998,29 @@ mod tests {
     }));
   }
 
+  #[test]
+  fn loads_trailer_matching_rules() {
+    let matching_rules_json = json!({"matchingRules": {
+      "trailer": {
+        "grpc-status": {"matchers": [{"match": "regex", "regex": "\\d+"}]}
+      }
+    }});
+
+    let matching_rules = matchers_from_json(&matching_rules_json, &None).unwrap();
+
+    expect!(matching_rules.categories()).to(be_equal_to(hashset!{ Category::TRAILER }));
+    expect!(matching_rules.rules_for_category("trailer")).to(be_some().value(MatchingRuleCategory {
+      name: Category::TRAILER,
+      rules: hashmap!{ DocPath::root().join("grpc-status") => RuleList { rules: vec![
+        MatchingRule::Regex("\\d+".to_string()) ], rule_logic: RuleLogic::And, cascaded: false } }
+    }));
+    expect!(matchers_to_json(&matching_rules, &PactSpecification::V4)).to(be_equal_to(json!({
+      "trailer": {
+        "grpc-status": {"combine": "AND", "matchers": [{"match": "regex", "regex": "\\d+"}]}
+      }
+    })));
+  }
+
   #[test]
   fn load_from_v2_map_supports_headers_and_query_parameters_in_encoded_format() {
     let matching_rules_json = json!({
@@ -1993,6 +2239,32 @@ mod tests {
     }));
   }
 
+  #[test]
+  fn loads_v3_matching_rules_for_metadata_keys_with_special_characters() {
+    let matching_rules_json = json!({"matchingRules": {
+      "metadata": {
+        "correlation.id": { "matchers": [{ "match": "regex", "regex": "[0-9a-f-]+" }] },
+        "$.destination": { "matchers": [{ "match": "type" }] }
+      }
+    }});
+
+    let matching_rules = matchers_from_json(&matching_rules_json, &None).unwrap();
+
+    expect!(matching_rules.rules_for_category("metadata")).to(be_some().value(MatchingRuleCategory {
+      name: "metadata".into(),
+      rules: hashmap!{
+        DocPath::root().join("correlation.id") => RuleList { rules: vec![ MatchingRule::Regex("[0-9a-f-]+".to_string()) ], rule_logic: RuleLogic::And, cascaded: false },
+        DocPath::root().join("destination") => RuleList { rules: vec![ MatchingRule::Type ], rule_logic: RuleLogic::And, cascaded: false }
+      }
+    }));
+    expect!(matchers_to_json(&matching_rules, &PactSpecification::V4)).to(be_equal_to(json!({
+      "metadata": {
+        "correlation.id": { "combine": "AND", "matchers": [{ "match": "regex", "regex": "[0-9a-f-]+" }] },
+        "destination": { "combine": "AND", "matchers": [{ "match": "type" }] }
+      }
+    })));
+  }
+
   #[test]
   fn matching_rule_from_json_test() {
     expect!(MatchingRule::from_json(&Value::from_str("\"test string\"").unwrap())).to(be_err());
@@ -2136,6 +2408,64 @@ mod tests {
     expect!(MatchingRule::from_json(&json)).to(be_ok().value(
       MatchingRule::StatusCode(HttpStatus::StatusCodes(vec![200, 201, 204]))
     ));
+
+    let json = json!({
+      "match": "pact-rust:custom",
+      "name": "jwt",
+      "config": { "alg": "HS256" }
+    });
+    expect!(MatchingRule::from_json(&json)).to(be_ok().value(
+      MatchingRule::Custom("jwt".to_string(), hashmap!{ "alg".to_string() => json!("HS256") })
+    ));
+    expect!(MatchingRule::from_json(&json!({ "match": "pact-rust:custom", "name": "ulid" }))).to(be_ok().value(
+      MatchingRule::Custom("ulid".to_string(), hashmap!{})
+    ));
+    expect!(MatchingRule::from_json(&json!({ "match": "pact-rust:custom" }))).to(be_err());
+    expect!(MatchingRule::from_json(&json!({ "match": "custom", "name": "ulid" }))).to(be_err());
+    expect!(MatchingRule::from_json(&json!({ "match": "equalsIgnoreCase" }))).to(be_ok().value(
+      MatchingRule::EqualsIgnoreCase
+    ));
+    expect!(MatchingRule::from_json(&json!({ "match": "decimalWithTolerance", "tolerance": 0.01 }))).to(be_ok().value(
+      MatchingRule::DecimalWithTolerance(0.01)
+    ));
+    expect!(MatchingRule::from_json(&json!({ "match": "decimal-with-tolerance", "tolerance": "0.5" }))).to(be_ok().value(
+      MatchingRule::DecimalWithTolerance(0.5)
+    ));
+    expect!(MatchingRule::from_json(&json!({ "match": "decimalWithTolerance" }))).to(be_err());
+    expect!(MatchingRule::from_json(&json!({ "match": "decimalWithTolerance", "tolerance": -1 }))).to(be_err());
+    expect!(MatchingRule::from_json(&json!({ "match": "timestampWithin", "seconds": 60 }))).to(be_ok().value(
+      MatchingRule::TimestampWithin(String::default(), 60, None)
+    ));
+    expect!(MatchingRule::from_json(&json!({
+      "match": "timestampWithin",
+      "format": "yyyy-MM-dd HH:mm:ss",
+      "seconds": 3600,
+      "reference": "2023-05-01 10:00:00"
+    }))).to(be_ok().value(
+      MatchingRule::TimestampWithin("yyyy-MM-dd HH:mm:ss".to_string(), 3600, Some("2023-05-01 10:00:00".to_string()))
+    ));
+    expect!(MatchingRule::from_json(&json!({ "match": "timestampWithin" }))).to(be_err());
+    expect!(MatchingRule::from_json(&json!({ "match": "jsonSchema", "schema": { "type": "integer" } }))).to(be_ok().value(
+      MatchingRule::JsonSchema(json!({ "type": "integer" }))
+    ));
+    expect!(MatchingRule::from_json(&json!({ "match": "json-schema", "schema": "{\"type\": \"string\"}" }))).to(be_ok().value(
+      MatchingRule::JsonSchema(json!({ "type": "string" }))
+    ));
+    expect!(MatchingRule::from_json(&json!({ "match": "jsonSchema", "schema": "{" }))).to(be_err());
+    expect!(MatchingRule::from_json(&json!({ "match": "jsonSchema", "schema": 100 }))).to(be_err());
+    expect!(MatchingRule::from_json(&json!({ "match": "jsonSchema" }))).to(be_err());
+    expect!(MatchingRule::from_json(&json!({ "match": "ignore-order" }))).to(be_ok().value(
+      MatchingRule::EqualsIgnoreOrder
+    ));
+    expect!(MatchingRule::from_json(&json!({ "match": "ignore-order", "min": 1 }))).to(be_ok().value(
+      MatchingRule::MinEqualsIgnoreOrder(1)
+    ));
+    expect!(MatchingRule::from_json(&json!({ "match": "ignore-order", "max": "5" }))).to(be_ok().value(
+      MatchingRule::MaxEqualsIgnoreOrder(5)
+    ));
+    expect!(MatchingRule::from_json(&json!({ "match": "ignore-order", "min": 1, "max": 5 }))).to(be_ok().value(
+      MatchingRule::MinMaxEqualsIgnoreOrder(1, 5)
+    ));
   }
 
   #[test]
@@ -2182,6 +2512,37 @@ mod tests {
         "status": [400, 401, 404]
       })));
 
+    expect!(MatchingRule::DecimalWithTolerance(0.001).to_json()).to(
+      be_equal_to(json!({
+        "match": "decimalWithTolerance",
+        "tolerance": 0.001
+      })));
+    expect!(MatchingRule::TimestampWithin(String::default(), 60, None).to_json()).to(
+      be_equal_to(json!({
+        "match": "timestampWithin",
+        "format": "",
+        "seconds": 60
+      })));
+    expect!(MatchingRule::TimestampWithin("yyyy-MM-dd".to_string(), 86400, Some("2023-05-01".to_string())).to_json()).to(
+      be_equal_to(json!({
+        "match": "timestampWithin",
+        "format": "yyyy-MM-dd",
+        "seconds": 86400,
+        "reference": "2023-05-01"
+      })));
+    expect!(MatchingRule::EqualsIgnoreOrder.to_json()).to(be_equal_to(json!({ "match": "ignore-order" })));
+    expect!(MatchingRule::MinMaxEqualsIgnoreOrder(1, 5).to_json()).to(
+      be_equal_to(json!({
+        "match": "ignore-order",
+        "min": 1,
+        "max": 5
+      })));
+    expect!(MatchingRule::JsonSchema(json!({ "type": "object", "required": ["id"] })).to_json()).to(
+      be_equal_to(json!({
+        "match": "jsonSchema",
+        "schema": { "type": "object", "required": ["id"] }
+      })));
+
     expect!(MatchingRule::Timestamp("YYYY".to_string()).to_json()).to(
       be_equal_to(json!({
         "match": "datetime",
@@ -2197,6 +2558,12 @@ mod tests {
         "match": "time",
         "format": "HH"
       })));
+    expect!(MatchingRule::Custom("jwt".to_string(), hashmap!{ "alg".to_string() => json!("HS256") }).to_json()).to(
+      be_equal_to(json!({
+        "match": "pact-rust:custom",
+        "name": "jwt",
+        "config": { "alg": "HS256" }
+      })));
   }
 
   #[test]
@@ -2288,6 +2655,21 @@ mod tests {
     expect!(matchers.wildcard_matcher_is_defined("body", &vec!["$", "a", "b", "c"])).to(be_false());
   }
 
+  #[test]
+  fn timestamp_within_round_trips_through_json_and_values() {
+    for rule in [
+      MatchingRule::TimestampWithin(String::default(), 60, None),
+      MatchingRule::TimestampWithin("yyyy-MM-dd".to_string(), 86400, Some("2023-05-01".to_string()))
+    ] {
+      expect!(MatchingRule::from_json(&rule.to_json())).to(be_ok().value(rule.clone()));
+
+      let values = rule.values();
+      expect!(values.get("format")).to(be_some());
+      let attributes = Value::Object(values.iter().map(|(k, v)| (k.to_string(), v.clone())).collect());
+      expect!(MatchingRule::create(rule.name().as_str(), &attributes)).to(be_ok().value(rule.clone()));
+    }
+  }
+
   #[test]
   fn min_and_max_values_get_serialised_to_json_as_numbers() {
     expect!(MatchingRule::MinType(1).to_json().to_string()).to(be_equal_to("{\"match\":\"type\",\"min\":1}"));
@@ -2627,4 +3009,15 @@ mod tests {
       }
     )
   }
+
+  #[test]
+  fn min_spec_version_test() {
+    expect!(MatchingRule::Regex("\\d+".to_string()).min_spec_version()).to(be_equal_to(PactSpecification::V2));
+    expect!(MatchingRule::MinType(1).min_spec_version()).to(be_equal_to(PactSpecification::V2));
+    expect!(MatchingRule::Integer.min_spec_version()).to(be_equal_to(PactSpecification::V3));
+    expect!(MatchingRule::Values.min_spec_version()).to(be_equal_to(PactSpecification::V3));
+    expect!(MatchingRule::Semver.min_spec_version()).to(be_equal_to(PactSpecification::V4));
+    expect!(MatchingRule::NotEmpty.min_spec_version()).to(be_equal_to(PactSpecification::V4));
+    expect!(MatchingRule::DecimalWithTolerance(0.1).min_spec_version()).to(be_equal_to(PactSpecification::V4));
+  }
 }
@@ -15,7 +15,7 @@ use tracing::warn;
 
 use crate::bodies::OptionalBody;
 use crate::content_types::ContentType;
-use crate::generators::{Generators, generators_to_json};
+use crate::generators::{Generators, generators_from_json, generators_to_json};
 use crate::http_parts::HttpPart;
 use crate::interaction::Interaction;
 use crate::json_utils::{body_from_json, json_to_string};
@@ -226,7 +226,7 @@ impl Message {
                   contents: body,
                   matching_rules: matchers_from_json(json, &None)?,
                   metadata,
-                  generators: Generators::default(),
+                  generators: generators_from_json(json)?,
                 })
             },
             _ => Err(anyhow!("Messages require Pact Specification version 3"))
@@ -253,7 +253,7 @@ impl Message {
             &self.generators.clone(), spec_version));
         }
 
-        match self.contents {
+        match *self.contents.loaded() {
           OptionalBody::Present(ref body, _, _) => {
             let content_type = self.message_content_type().unwrap_or_default();
             if content_type.is_json() {
@@ -274,7 +274,8 @@ impl Message {
             }
           },
           OptionalBody::Empty => { map.insert("contents".to_string(), Value::String("".to_string())); },
-          OptionalBody::Missing => (),
+          // File backed bodies are loaded above, so only become Missing if the file can not be read
+          OptionalBody::Missing | OptionalBody::File(..) => (),
           OptionalBody::Null => { map.insert("contents".to_string(), Value::Null); }
         }
         if !self.provider_states.is_empty() {
@@ -1,7 +1,6 @@
 //! The `message_pact` module defines a Pact
 //! that contains Messages instead of Interactions.
 
-use std::cmp::Ordering;
 use std::collections::{BTreeMap, HashMap};
 use std::fs;
 use std::fs::File;
@@ -11,18 +10,18 @@ use std::path::Path;
 use std::sync::{Arc, Mutex};
 
 use anyhow::{anyhow, bail};
-use itertools::EitherOrBoth::{Both, Left, Right};
-use itertools::Itertools;
 use maplit::*;
 use serde_json::{json, Map, Value};
 use tracing::debug;
 
 use crate::{Consumer, PactSpecification, Provider};
 #[cfg(not(target_family = "wasm"))] use crate::file_utils::with_read_lock;
+#[cfg(not(target_family = "wasm"))] use crate::pact::read_pact_json;
 #[cfg(not(target_family = "wasm"))] use crate::http_utils::{self, HttpAuth};
 use crate::interaction::Interaction;
 use crate::message::Message;
-use crate::pact::{determine_spec_version, Pact, parse_meta_data, ReadWritePact};
+use crate::pact;
+use crate::pact::{determine_spec_version, MergeStrategy, Pact, parse_meta_data, ReadWritePact};
 use crate::PACT_RUST_VERSION;
 use crate::plugins::PluginData;
 use crate::sync_pact::RequestResponsePact;
@@ -170,6 +169,19 @@ impl MessagePact {
       determine_spec_version("<MessagePact>", &self.metadata)
     }
 
+    /// Removes any interactions that are semantically equal to an earlier interaction in the Pact
+    /// (ignoring the interaction keys and IDs). Returns the number of interactions removed.
+    pub fn deduplicate_interactions(&mut self) -> usize {
+      pact::deduplicate_interactions(&mut self.messages, pact::interaction_identity)
+    }
+
+    /// Normalizes the Pact, so that equivalent pacts have the same form. Duplicate interactions
+    /// are removed, and the interactions are sorted by description, provider states and type.
+    pub fn normalize(&mut self) {
+      self.deduplicate_interactions();
+      self.messages.sort_by_cached_key(pact::interaction_key);
+    }
+
     /// Creates a `MessagePact` from a `Value` struct.
     pub fn from_json(file: &str, pact_json: &Value) -> anyhow::Result<MessagePact> {
         let metadata = parse_meta_data(pact_json);
@@ -297,52 +309,14 @@ impl ReadWritePact for MessagePact {
   #[cfg(not(target_family = "wasm"))]
   fn read_pact(path: &Path) -> anyhow::Result<MessagePact> {
     with_read_lock(path, 3, &mut |f| {
-      let pact_json: Value = serde_json::from_reader(f)?;
+      let pact_json = read_pact_json(f)?;
       MessagePact::from_json(&format!("{:?}", path), &pact_json)
         .map_err(|e| anyhow!(e))
     })
   }
 
   fn merge(&self, pact: &dyn Pact) -> anyhow::Result<Box<dyn Pact + Send + Sync + RefUnwindSafe>> {
-    if self.consumer.name == pact.consumer().name && self.provider.name == pact.provider().name {
-      let messages: Vec<Result<Message, String>> = self.messages.iter()
-        .merge_join_by(pact.interactions().iter(), |a, b| {
-          let cmp = Ord::cmp(&a.description, &b.description());
-          if cmp == Ordering::Equal && ! &a.provider_states().is_empty(){
-            Ord::cmp(&a.provider_states.iter().map(|p| p.name.clone()).collect::<Vec<String>>(),
-                     &b.provider_states().iter().map(|p| p.name.clone()).collect::<Vec<String>>())
-          } else {
-            cmp
-          }
-        })
-        .map(|either| match either {
-          Left(i) => Ok(i.clone()),
-          Right(i) => i.as_message()
-            .ok_or(format!("Can't convert interaction of type {} to V3 Asynchronous/Messages", i.type_of())),
-          Both(_, i) => i.as_message()
-            .ok_or(format!("Can't convert interaction of type {} to V3 Asynchronous/Messages", i.type_of()))
-        })
-        .collect();
-      let errors: Vec<String> = messages.iter()
-        .filter(|i| i.is_err())
-        .map(|i| i.as_ref().unwrap_err().to_string())
-        .collect();
-      if errors.is_empty() {
-        Ok(Box::new(MessagePact {
-          provider: self.provider.clone(),
-          consumer: self.consumer.clone(),
-          messages: messages.iter()
-            .filter(|i| i.is_ok())
-            .map(|i| i.as_ref().unwrap().clone()).collect(),
-          metadata: self.metadata.clone(),
-          specification_version: self.specification_version
-        }))
-      } else {
-        Err(anyhow!("Unable to merge pacts: {}", errors.join(", ")))
-      }
-    } else {
-      Err(anyhow!("Unable to merge pacts, as they have different consumers or providers"))
-    }
+    pact::merge_pacts(self, pact, MergeStrategy::KeepNewest)
   }
 
   fn default_file_name(&self) -> String {
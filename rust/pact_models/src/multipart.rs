@@ -0,0 +1,311 @@
+//! Structured model of MIME multipart bodies (i.e. `multipart/form-data`)
+
+use std::fmt::{Display, Formatter};
+use std::fmt;
+
+use anyhow::anyhow;
+use bytes::{Bytes, BytesMut};
+use maplit::btreemap;
+
+use crate::bodies::OptionalBody;
+use crate::content_types::ContentType;
+
+/// Part of a multipart body
+#[derive(Debug, Clone, PartialEq)]
+pub struct MultipartPart {
+  /// Name of the part (from the Content-Disposition header)
+  pub name: String,
+  /// File name of the part, if it is a file upload
+  pub filename: Option<String>,
+  /// Content type of the part
+  pub content_type: Option<ContentType>,
+  /// Any other headers for the part
+  pub headers: Vec<(String, String)>,
+  /// Contents of the part
+  pub body: Bytes
+}
+
+impl MultipartPart {
+  /// Creates a part with the given name and contents
+  pub fn new<S: Into<String>, B: Into<Bytes>>(name: S, body: B) -> Self {
+    MultipartPart {
+      name: name.into(),
+      filename: None,
+      content_type: None,
+      headers: vec![],
+      body: body.into()
+    }
+  }
+
+  /// Sets the file name of the part
+  pub fn filename<S: Into<String>>(mut self, filename: S) -> Self {
+    self.filename = Some(filename.into());
+    self
+  }
+
+  /// Sets the content type of the part
+  pub fn content_type(mut self, content_type: ContentType) -> Self {
+    self.content_type = Some(content_type);
+    self
+  }
+
+  /// Adds a header to the part
+  pub fn header<S: Into<String>, V: Into<String>>(mut self, name: S, value: V) -> Self {
+    self.headers.push((name.into(), value.into()));
+    self
+  }
+
+  /// Returns the contents of the part as a body with the content type of the part
+  pub fn part_body(&self) -> OptionalBody {
+    if self.body.is_empty() {
+      OptionalBody::Empty
+    } else {
+      OptionalBody::Present(self.body.clone(), self.content_type.clone(), None)
+    }
+  }
+
+  fn write_to(&self, buffer: &mut BytesMut) {
+    buffer.extend_from_slice(b"Content-Disposition: form-data; name=\"");
+    buffer.extend_from_slice(self.name.as_bytes());
+    buffer.extend_from_slice(b"\"");
+    if let Some(filename) = &self.filename {
+      buffer.extend_from_slice(b"; filename=\"");
+      buffer.extend_from_slice(filename.as_bytes());
+      buffer.extend_from_slice(b"\"");
+    }
+    buffer.extend_from_slice(b"\r\n");
+    if let Some(content_type) = &self.content_type {
+      buffer.extend_from_slice(format!("Content-Type: {}\r\n", content_type).as_bytes());
+    }
+    for (name, value) in &self.headers {
+      buffer.extend_from_slice(format!("{}: {}\r\n", name, value).as_bytes());
+    }
+    buffer.extend_from_slice(b"\r\n");
+    buffer.extend_from_slice(&self.body);
+    buffer.extend_from_slice(b"\r\n");
+  }
+
+  fn parse(contents: &[u8]) -> anyhow::Result<MultipartPart> {
+    let (header_block, body) = if contents.starts_with(b"\r\n") {
+      (&contents[..0], &contents[2..])
+    } else {
+      match find_bytes(contents, b"\r\n\r\n", 0) {
+        Some(index) => (&contents[..index], &contents[index + 4..]),
+        None => return Err(anyhow!("Multipart part is missing the blank line after the headers"))
+      }
+    };
+
+    let mut name = None;
+    let mut filename = None;
+    let mut content_type = None;
+    let mut headers = vec![];
+    for line in String::from_utf8_lossy(header_block).split("\r\n").filter(|line| !line.is_empty()) {
+      let (header, value) = line.split_once(':')
+        .ok_or_else(|| anyhow!("Multipart part header '{}' is not valid", line))?;
+      let value = value.trim();
+      if header.trim().eq_ignore_ascii_case("content-disposition") {
+        for param in value.split(';').skip(1) {
+          if let Some((key, param_value)) = param.split_once('=') {
+            let param_value = param_value.trim().trim_matches('"').to_string();
+            match key.trim().to_lowercase().as_str() {
+              "name" => name = Some(param_value),
+              "filename" => filename = Some(param_value),
+              _ => {}
+            }
+          }
+        }
+      } else if header.trim().eq_ignore_ascii_case("content-type") {
+        content_type = Some(ContentType::parse(value)?);
+      } else {
+        headers.push((header.trim().to_string(), value.to_string()));
+      }
+    }
+
+    Ok(MultipartPart {
+      name: name.ok_or_else(|| anyhow!("Multipart part does not have a name in the Content-Disposition header"))?,
+      filename,
+      content_type,
+      headers,
+      body: Bytes::copy_from_slice(body)
+    })
+  }
+}
+
+/// MIME multipart body, made up of a number of parts separated by a boundary marker
+#[derive(Debug, Clone, PartialEq)]
+pub struct Multipart {
+  /// Boundary marker used to separate the parts
+  pub boundary: String,
+  /// Parts of the body
+  pub parts: Vec<MultipartPart>
+}
+
+impl Multipart {
+  /// Creates an empty multipart body with the given boundary marker
+  pub fn new<S: Into<String>>(boundary: S) -> Self {
+    Multipart {
+      boundary: boundary.into(),
+      parts: vec![]
+    }
+  }
+
+  /// Adds a part to the body
+  pub fn part(mut self, part: MultipartPart) -> Self {
+    self.parts.push(part);
+    self
+  }
+
+  /// Returns the part with the given name
+  pub fn find_part(&self, name: &str) -> Option<&MultipartPart> {
+    self.parts.iter().find(|part| part.name == name)
+  }
+
+  /// Content type for the body, including the boundary marker
+  pub fn content_type(&self) -> ContentType {
+    ContentType {
+      main_type: "multipart".to_string(),
+      sub_type: "form-data".to_string(),
+      attributes: btreemap! { "boundary".to_string() => self.boundary.clone() },
+      .. ContentType::default()
+    }
+  }
+
+  /// Encodes the body
+  pub fn to_bytes(&self) -> Bytes {
+    let mut buffer = BytesMut::new();
+    for part in &self.parts {
+      buffer.extend_from_slice(format!("--{}\r\n", self.boundary).as_bytes());
+      part.write_to(&mut buffer);
+    }
+    buffer.extend_from_slice(format!("--{}--\r\n", self.boundary).as_bytes());
+    buffer.freeze()
+  }
+
+  /// Encodes the body as it would be stored in a Pact file
+  pub fn to_body(&self) -> OptionalBody {
+    OptionalBody::Present(self.to_bytes(), Some(self.content_type()), None)
+  }
+
+  /// Parses a multipart body using the given boundary marker. Any preamble before the first
+  /// boundary and epilogue after the closing boundary are ignored.
+  pub fn parse(body: &[u8], boundary: &str) -> anyhow::Result<Multipart> {
+    let delimiter = format!("--{}", boundary);
+    let mut parts = vec![];
+
+    let mut index = if body.starts_with(delimiter.as_bytes()) {
+      delimiter.len()
+    } else {
+      let delimiter = format!("\r\n{}", delimiter);
+      find_bytes(body, delimiter.as_bytes(), 0)
+        .map(|index| index + delimiter.len())
+        .ok_or_else(|| anyhow!("Multipart body does not contain the boundary marker '{}'", boundary))?
+    };
+
+    let next_delimiter = format!("\r\n{}", delimiter);
+    loop {
+      let rest = &body[index..];
+      if rest.starts_with(b"--") {
+        break;
+      }
+      let start = if rest.starts_with(b"\r\n") {
+        index + 2
+      } else {
+        return Err(anyhow!("Multipart boundary marker '{}' is not followed by a new line", boundary));
+      };
+      let end = find_bytes(body, next_delimiter.as_bytes(), start)
+        .ok_or_else(|| anyhow!("Multipart body is missing the closing boundary marker '{}--'", boundary))?;
+      parts.push(MultipartPart::parse(&body[start..end])?);
+      index = end + next_delimiter.len();
+    }
+
+    Ok(Multipart {
+      boundary: boundary.to_string(),
+      parts
+    })
+  }
+
+  /// Parses a multipart body, using the boundary marker from the content type of the body
+  pub fn from_body(body: &OptionalBody) -> anyhow::Result<Multipart> {
+    let content_type = body.content_type()
+      .ok_or_else(|| anyhow!("Body does not have a content type"))?;
+    let boundary = boundary_from_content_type(&content_type)
+      .ok_or_else(|| anyhow!("Content type '{}' does not have a boundary marker", content_type))?;
+    Multipart::parse(&body.value().unwrap_or_default(), boundary.as_str())
+  }
+}
+
+impl Display for Multipart {
+  fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+    let parts = self.parts.iter()
+      .map(|part| part.name.as_str())
+      .collect::<Vec<_>>();
+    write!(f, "multipart/form-data (boundary {}) with parts [{}]", self.boundary, parts.join(", "))
+  }
+}
+
+/// Returns the boundary marker from a multipart content type
+pub fn boundary_from_content_type(content_type: &ContentType) -> Option<String> {
+  if content_type.main_type == "multipart" {
+    content_type.attributes.get("boundary").cloned()
+  } else {
+    None
+  }
+}
+
+fn find_bytes(haystack: &[u8], needle: &[u8], from: usize) -> Option<usize> {
+  if from > haystack.len() {
+    return None;
+  }
+  haystack[from..].windows(needle.len())
+    .position(|window| window == needle)
+    .map(|index| index + from)
+}
+
+#[cfg(test)]
+mod tests {
+  use expectest::prelude::*;
+
+  use crate::bodies::OptionalBody;
+  use crate::content_types::{ContentType, JSON, TEXT};
+
+  use super::*;
+
+  #[test]
+  fn to_bytes_encodes_the_parts() {
+    let multipart = Multipart::new("ABCD")
+      .part(MultipartPart::new("part-1", "{}").filename("1.json").content_type(JSON.clone()))
+      .part(MultipartPart::new("part-2", "TEXT"));
+    expect!(String::from_utf8(multipart.to_bytes().to_vec()).unwrap()).to(be_equal_to(
+      "--ABCD\r\nContent-Disposition: form-data; name=\"part-1\"; filename=\"1.json\"\r\n\
+      Content-Type: application/json\r\n\r\n{}\r\n\
+      --ABCD\r\nContent-Disposition: form-data; name=\"part-2\"\r\n\r\nTEXT\r\n--ABCD--\r\n"));
+    expect!(multipart.content_type().to_string()).to(be_equal_to("multipart/form-data;boundary=ABCD"));
+  }
+
+  #[test]
+  fn parse_round_trips_the_body() {
+    let multipart = Multipart::new("ABCD")
+      .part(MultipartPart::new("part-1", "{}").filename("1.json").content_type(JSON.clone()))
+      .part(MultipartPart::new("part-2", "TEXT\r\nMORE TEXT").content_type(TEXT.clone()).header("X-Test", "1"));
+    let parsed = Multipart::from_body(&multipart.to_body()).unwrap();
+    expect!(parsed).to(be_equal_to(multipart));
+  }
+
+  #[test]
+  fn parse_ignores_the_preamble_and_epilogue() {
+    let body = b"preamble\r\n--XYZ\r\ncontent-disposition: form-data; name=file; filename=\"a.txt\"\r\n\
+      content-type: text/plain\r\n\r\nabc\r\n--XYZ--\r\nepilogue";
+    let multipart = Multipart::parse(body, "XYZ").unwrap();
+    expect!(multipart.parts.len()).to(be_equal_to(1));
+    let part = multipart.find_part("file").unwrap();
+    expect!(part.filename.clone()).to(be_some().value("a.txt"));
+    expect!(part.part_body()).to(be_equal_to(OptionalBody::Present("abc".into(), ContentType::parse("text/plain").ok(), None)));
+  }
+
+  #[test]
+  fn parse_fails_on_invalid_bodies() {
+    expect!(Multipart::parse(b"no markers here", "XYZ")).to(be_err());
+    expect!(Multipart::parse(b"--XYZ\r\nContent-Disposition: form-data; name=a\r\n\r\nabc", "XYZ")).to(be_err());
+    expect!(Multipart::parse(b"--XYZ\r\nContent-Type: text/plain\r\n\r\nabc\r\n--XYZ--", "XYZ")).to(be_err());
+  }
+}
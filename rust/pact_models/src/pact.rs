@@ -7,7 +7,7 @@ use std::fs::File;
 use std::io::{Read, Seek, SeekFrom, Write};
 use std::ops::Deref;
 use std::panic::RefUnwindSafe;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::sync::{Arc, Mutex};
 
 use anyhow::{anyhow, Context};
@@ -20,6 +20,7 @@ use crate::{Consumer, PactSpecification, Provider};
 #[cfg(not(target_family = "wasm"))] use crate::file_utils::{with_read_lock_for_open_file, with_write_lock};
 #[cfg(not(target_family = "wasm"))] use crate::http_utils;
 #[cfg(not(target_family = "wasm"))] use crate::http_utils::HttpAuth;
+#[cfg(not(target_family = "wasm"))] use crate::integrity::{add_integrity_metadata, integrity_metadata_enabled, remove_integrity_metadata};
 use crate::interaction::Interaction;
 use crate::message_pact::MessagePact;
 use crate::plugins::PluginData;
@@ -133,30 +134,142 @@ impl PartialEq for Box<dyn Pact> {
   }
 }
 
-/// Reads the pact file and parses the resulting JSON into a `Pact` struct
+/// Reads the pact file and parses the resulting JSON into a `Pact` struct. Gzip compressed pact
+/// files (i.e. `pact.json.gz`) are decompressed.
 #[cfg(not(target_family = "wasm"))]
 pub fn read_pact(file: &Path) -> anyhow::Result<Box<dyn Pact + Send + Sync + RefUnwindSafe>> {
   let mut f = File::open(file)?;
   read_pact_from_file(&mut f, file)
 }
 
-/// Reads the pact from the file and parses the resulting JSON into a `Pact` struct
+/// Reads the pact from the file and parses the resulting JSON into a `Pact` struct. Gzip
+/// compressed pact files are decompressed.
 #[cfg(not(target_family = "wasm"))]
 pub fn read_pact_from_file(file: &mut File, path: &Path) -> anyhow::Result<Box<dyn Pact + Send + Sync + RefUnwindSafe>> {
-  let buf = with_read_lock_for_open_file(path, file, 3, &mut |f| {
-    let mut buf = String::new();
-    f.read_to_string(&mut buf)?;
-    Ok(buf)
-  })?;
-  let pact_json = serde_json::from_str(&buf)
+  let pact_json = with_read_lock_for_open_file(path, file, 3, &mut |f| read_pact_json(f))?;
+  load_pact_from_json(&*path.to_string_lossy(), &pact_json)
+    .map_err(|e| anyhow!(e))
+}
+
+/// Gzip magic bytes
+#[cfg(not(target_family = "wasm"))]
+const GZIP_HEADER: [u8; 2] = [0x1f, 0x8b];
+
+/// Reads the JSON contents of a pact file, decompressing the contents if they are gzip compressed
+#[cfg(not(target_family = "wasm"))]
+pub(crate) fn read_pact_json<R: Read>(reader: &mut R) -> anyhow::Result<Value> {
+  let mut buffer = vec![];
+  reader.read_to_end(&mut buffer)?;
+  let contents = if buffer.starts_with(&GZIP_HEADER) {
+    let mut decoded = vec![];
+    flate2::read::GzDecoder::new(buffer.as_slice()).read_to_end(&mut decoded)
+      .context("Failed to decompress gzipped Pact file")?;
+    decoded
+  } else {
+    buffer
+  };
+  serde_json::from_slice(&contents)
     .context("Failed to parse Pact JSON")
     .map_err(|err| {
-      error!("read_pact_from_file: {}", err);
-      debug!("read_pact_from_file: file contents = '{}'", buf);
+      error!("read_pact_json: {}", err);
+      debug!("read_pact_json: file contents = '{}'", String::from_utf8_lossy(&contents));
       err
-    })?;
-  load_pact_from_json(&*path.to_string_lossy(), &pact_json)
-    .map_err(|e| anyhow!(e))
+    })
+}
+
+/// If the pact file should be gzip compressed (the file name ends in `.gz`)
+#[cfg(not(target_family = "wasm"))]
+fn is_gzip_file_name(path: &Path) -> bool {
+  path.extension().unwrap_or_default().eq_ignore_ascii_case("gz")
+}
+
+/// Encodes the pact JSON for writing to the given path, compressing it if it is a gzip file
+#[cfg(not(target_family = "wasm"))]
+fn encode_pact_file(path: &Path, pact_json: &Value) -> anyhow::Result<Vec<u8>> {
+  let json = serde_json::to_string_pretty(pact_json)?;
+  if is_gzip_file_name(path) {
+    let mut encoder = flate2::write::GzEncoder::new(vec![], flate2::Compression::default());
+    encoder.write_all(json.as_bytes())?;
+    Ok(encoder.finish()?)
+  } else {
+    Ok(json.into_bytes())
+  }
+}
+
+/// Result of loading a pact file from a directory, along with the path to the file
+pub type PactFileLoadResult = (PathBuf, anyhow::Result<Box<dyn Pact + Send + Sync + RefUnwindSafe>>);
+
+/// Discovers and loads all the pact files in the given directory. If `recursive` is true, any
+/// sub-directories will also be searched. By default, files ending in `.json` or `.json.gz` are
+/// loaded, otherwise only the files whose path (relative to the directory) matches the `glob`
+/// pattern are loaded.
+///
+/// Returns an error if the directory can not be read or the glob pattern is invalid. Failures to
+/// load individual files are returned along with the file path, and do not abort the load.
+#[cfg(not(target_family = "wasm"))]
+pub fn load_pacts_from_dir<P: AsRef<Path>>(
+  path: P,
+  recursive: bool,
+  glob: Option<&str>
+) -> anyhow::Result<Vec<PactFileLoadResult>> {
+  Ok(find_pacts_in_dir(path, recursive, glob)?.into_iter()
+    .map(|file| {
+      trace!("load_pacts_from_dir: loading pact file {:?}", file);
+      let result = read_pact(&file)
+        .with_context(|| format!("Failed to load pact file '{}'", file.display()));
+      (file, result)
+    })
+    .collect())
+}
+
+/// Finds the pact files in the given directory, without loading them. The files are found in the
+/// same way as [`load_pacts_from_dir`], and are returned in sorted order.
+#[cfg(not(target_family = "wasm"))]
+pub fn find_pacts_in_dir<P: AsRef<Path>>(
+  path: P,
+  recursive: bool,
+  glob: Option<&str>
+) -> anyhow::Result<Vec<PathBuf>> {
+  let dir = path.as_ref();
+  let pattern = glob.map(glob::Pattern::new)
+    .transpose()
+    .with_context(|| format!("'{}' is not a valid glob pattern", glob.unwrap_or_default()))?;
+  let mut files = vec![];
+  find_pact_files(dir, dir, recursive, &pattern, &mut files)?;
+  files.sort();
+  Ok(files)
+}
+
+#[cfg(not(target_family = "wasm"))]
+fn find_pact_files(
+  base: &Path,
+  dir: &Path,
+  recursive: bool,
+  pattern: &Option<glob::Pattern>,
+  files: &mut Vec<PathBuf>
+) -> anyhow::Result<()> {
+  debug!("Scanning {:?} for pact files", dir);
+  for entry in fs::read_dir(dir).with_context(|| format!("Could not read directory '{}'", dir.display()))? {
+    let path = entry?.path();
+    if path.is_dir() {
+      if recursive {
+        find_pact_files(base, &path, recursive, pattern, files)?;
+      }
+    } else if let Some(pattern) = pattern {
+      if pattern.matches_path(path.strip_prefix(base).unwrap_or(&path)) {
+        files.push(path);
+      }
+    } else if is_pact_file_name(&path) {
+      files.push(path);
+    }
+  }
+  Ok(())
+}
+
+#[cfg(not(target_family = "wasm"))]
+fn is_pact_file_name(path: &Path) -> bool {
+  let name = path.file_name().unwrap_or_default().to_string_lossy().to_lowercase();
+  name.ends_with(".json") || name.ends_with(".json.gz")
 }
 
 /// Reads the pact file from a URL and parses the resulting JSON into a `Pact` struct
@@ -167,6 +280,14 @@ pub fn load_pact_from_url(url: &str, auth: &Option<HttpAuth>) -> anyhow::Result<
   load_pact_from_json(&url, &pact_json)
 }
 
+/// Reads the pact file from a URL using the async HTTP client and parses the resulting JSON into
+/// a `Pact` struct
+#[cfg(not(target_family = "wasm"))]
+pub async fn load_pact_from_url_async(url: &str, auth: &Option<HttpAuth>) -> anyhow::Result<Box<dyn Pact + Send + Sync + RefUnwindSafe>> {
+  let (url, pact_json) = http_utils::fetch_json_from_url_async(url, auth).await?;
+  load_pact_from_json(&url, &pact_json)
+}
+
 /// Loads a Pact model from a JSON Value
 pub fn load_pact_from_json(source: &str, json: &Value) -> anyhow::Result<Box<dyn Pact + Send + Sync + RefUnwindSafe>> {
   match json {
@@ -212,8 +333,10 @@ lazy_static!{
 }
 
 /// Writes the pact out to the provided path. If there is an existing pact at the path, the two
-/// pacts will be merged together unless overwrite is true. Returns an error if the file can not
-/// be written or the pacts can not be merged.
+/// pacts will be merged together unless overwrite is true (see [`merge_pacts`]). Conflicting
+/// interactions are replaced for V4 pacts, while for older pacts they must be identical. Returns an
+/// error if the file can not be written or the pacts can not be merged. If the path ends in `.gz`
+/// (i.e. `pact.json.gz`), the pact file will be gzip compressed.
 #[cfg(not(target_family = "wasm"))]
 pub fn write_pact(
   pact: Box<dyn Pact>,
@@ -233,26 +356,230 @@ pub fn write_pact(
             existing_pact.specification_version());
     }
 
-    let merged_pact = pact.merge(existing_pact.deref())?;
-    let pact_json = serde_json::to_string_pretty(&merged_pact.to_json(pact_spec)?)?;
+    let strategy = if pact.is_v4() { MergeStrategy::KeepNewest } else { MergeStrategy::DedupeIdentical };
+    let merged_pact = normalize_pact(merge_pacts(existing_pact.deref(), pact.deref(), strategy)?.as_ref())?;
+    let pact_json = encode_pact_file(path, &canonical_pact_json(merged_pact.as_ref(), pact_spec)?)?;
 
     with_write_lock(path, &mut f, 3, &mut |f| {
       f.set_len(0)?;
       f.seek(SeekFrom::Start(0))?;
-      f.write_all(pact_json.as_bytes())?;
+      f.write_all(&pact_json)?;
       Ok(())
     })
   } else {
     debug!("Writing new pact file to {:?}", path);
-    let result = serde_json::to_string_pretty(&pact.to_json(pact_spec)?)?;
+    let pact = normalize_pact(pact.as_ref())?;
+    let result = encode_pact_file(path, &canonical_pact_json(pact.as_ref(), pact_spec)?)?;
     let mut file = File::create(path)?;
     with_write_lock(path, &mut file, 3, &mut |f| {
-      f.write_all(result.as_bytes())?;
+      f.write_all(&result)?;
       Ok(())
     })
   }
 }
 
+/// Returns the JSON form of the pact that is written to the pact file, including any integrity
+/// metadata. The keys of all objects are sorted, as serde_json stores objects in a `BTreeMap`
+/// (this crate does not enable the `preserve_order` feature), so the same pact always produces
+/// the same pact file.
+#[cfg(not(target_family = "wasm"))]
+pub fn canonical_pact_json(pact: &dyn Pact, pact_spec: PactSpecification) -> anyhow::Result<Value> {
+  with_integrity_metadata(pact.to_json(pact_spec)?)
+}
+
+/// Adds the integrity metadata to the pact JSON if enabled (see [`integrity_metadata_enabled`]),
+/// otherwise any existing integrity metadata is removed as it will no longer be valid.
+#[cfg(not(target_family = "wasm"))]
+fn with_integrity_metadata(mut pact_json: Value) -> anyhow::Result<Value> {
+  if integrity_metadata_enabled() {
+    add_integrity_metadata(&mut pact_json)?;
+  } else {
+    remove_integrity_metadata(&mut pact_json);
+  }
+  Ok(pact_json)
+}
+
+/// Strategy used to resolve conflicts when merging pacts. Interactions conflict if they have the
+/// same description, provider states and type.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MergeStrategy {
+  /// Conflicting interactions are replaced with the ones from the newer pact
+  KeepNewest,
+  /// Any conflicting interactions are an error, even if they are identical
+  Error,
+  /// Identical interactions are only included once, while conflicting interactions that are
+  /// different are an error
+  DedupeIdentical
+}
+
+/// Key used to identify the same interaction in different pacts (description, provider state
+/// names and interaction type). Interactions are sorted using this key.
+pub(crate) fn interaction_key<I: Interaction + ?Sized>(interaction: &I) -> (String, Vec<String>, String) {
+  (
+    interaction.description(),
+    interaction.provider_states().iter().map(|state| state.name.clone()).collect(),
+    interaction.type_of()
+  )
+}
+
+/// If the interactions are the same interaction in different pacts. V4 interactions that both
+/// have keys are the same if the keys are equal, otherwise they are the same if they have the same
+/// [`interaction_key`].
+pub(crate) fn same_interaction<I: Interaction + ?Sized>(a: &I, b: &I) -> bool {
+  match (a.as_v4().and_then(|a| a.key()), b.as_v4().and_then(|b| b.key())) {
+    (Some(key_a), Some(key_b)) => key_a == key_b,
+    _ => interaction_key(a) == interaction_key(b)
+  }
+}
+
+/// Value used to compare interactions for semantic equality. This is the V4 JSON form of the
+/// interaction without the interaction key, so interactions from different specification versions
+/// can be compared.
+pub(crate) fn interaction_identity<I: Interaction + ?Sized>(interaction: &I) -> Value {
+  match interaction.as_v4() {
+    Some(interaction) => {
+      let mut json = interaction.to_json();
+      if let Some(map) = json.as_object_mut() {
+        map.remove("key");
+      }
+      json
+    }
+    None => Value::Null
+  }
+}
+
+/// Removes the interactions that are semantically equal to an earlier interaction (see
+/// [`interaction_identity`]), keeping the first one. Returns the number of interactions removed.
+pub(crate) fn deduplicate_interactions<T>(interactions: &mut Vec<T>, identity: impl Fn(&T) -> Value) -> usize {
+  let len = interactions.len();
+  let mut seen = vec![];
+  interactions.retain(|interaction| {
+    let identity = identity(interaction);
+    if seen.contains(&identity) {
+      false
+    } else {
+      seen.push(identity);
+      true
+    }
+  });
+  len - interactions.len()
+}
+
+/// Returns a normalized copy of the pact (see `V4Pact::normalize`), so that equivalent pacts
+/// have the same form.
+pub(crate) fn normalize_pact(pact: &dyn Pact) -> anyhow::Result<Box<dyn Pact + Send + Sync + RefUnwindSafe>> {
+  if pact.is_v4() {
+    let mut pact = pact.as_v4_pact()?;
+    pact.normalize();
+    Ok(pact.boxed())
+  } else if let Ok(mut pact) = pact.as_message_pact() {
+    pact.normalize();
+    Ok(pact.boxed())
+  } else {
+    let mut pact = pact.as_request_response_pact()?;
+    pact.normalize();
+    Ok(pact.boxed())
+  }
+}
+
+/// Merges the interactions of the newer pact into the existing one, resolving any conflicting
+/// interactions with the strategy. The metadata and plugin data of the pacts are also merged.
+/// Returns an error if the pacts have different consumers or providers, or there are conflicts
+/// that the strategy can't resolve.
+///
+/// The pacts are merged in their own specification version. The existing interactions keep their
+/// order (conflicting interactions that are replaced keep their position), and any new interactions
+/// are appended in the order of the newer pact. If either of the pacts is a V4 pact, a V4 pact is
+/// returned. Otherwise both pacts must be of the same type, and the merged pact will be of that type.
+pub fn merge_pacts(
+  existing: &dyn Pact,
+  newer: &dyn Pact,
+  strategy: MergeStrategy
+) -> anyhow::Result<Box<dyn Pact + Send + Sync + RefUnwindSafe>> {
+  if existing.consumer().name != newer.consumer().name || existing.provider().name != newer.provider().name {
+    return Err(anyhow!("Unable to merge pacts, as they have different consumers or providers"));
+  }
+
+  if existing.is_v4() || newer.is_v4() {
+    let mut merged = existing.as_v4_pact()?;
+    let newer_pact = newer.as_v4_pact()?;
+    merge_interactions(&mut merged.interactions, &newer_pact.interactions, strategy,
+      |a, b| same_interaction(a.as_ref(), b.as_ref()), |i| i.description(),
+      |i| interaction_identity(i.as_ref()))?;
+    for (key, value) in newer_pact.metadata {
+      match (merged.metadata.get_mut(&key), value) {
+        (Some(Value::Object(existing_value)), Value::Object(value)) => existing_value.extend(value),
+        (_, value) => { merged.metadata.insert(key, value); }
+      }
+    }
+    for plugin in &newer_pact.plugin_data {
+      merged.add_plugin_data(plugin);
+    }
+    Ok(merged.boxed())
+  } else if let Ok(mut merged) = existing.as_message_pact() {
+    let newer_pact = newer.as_message_pact()?;
+    merge_interactions(&mut merged.messages, &newer_pact.messages, strategy,
+      same_interaction, |i| i.description(), interaction_identity)?;
+    merge_v3_metadata(&mut merged.metadata, newer_pact.metadata);
+    if newer_pact.specification_version > merged.specification_version {
+      merged.specification_version = newer_pact.specification_version;
+    }
+    Ok(merged.boxed())
+  } else {
+    let mut merged = existing.as_request_response_pact()?;
+    let newer_pact = newer.as_request_response_pact()?;
+    merge_interactions(&mut merged.interactions, &newer_pact.interactions, strategy,
+      same_interaction, |i| i.description(), interaction_identity)?;
+    merge_v3_metadata(&mut merged.metadata, newer_pact.metadata);
+    if newer_pact.specification_version > merged.specification_version {
+      merged.specification_version = newer_pact.specification_version;
+    }
+    Ok(merged.boxed())
+  }
+}
+
+/// Merges the newer interactions into the existing ones using the strategy. Replaced interactions
+/// keep their position, and new interactions are appended.
+fn merge_interactions<T: Clone>(
+  existing: &mut Vec<T>,
+  newer: &[T],
+  strategy: MergeStrategy,
+  same: impl Fn(&T, &T) -> bool,
+  description: impl Fn(&T) -> String,
+  identity: impl Fn(&T) -> Value
+) -> anyhow::Result<()> {
+  let mut conflicts = vec![];
+  for interaction in newer {
+    match existing.iter().position(|i| same(i, interaction)) {
+      None => existing.push(interaction.clone()),
+      Some(index) => match strategy {
+        MergeStrategy::KeepNewest => existing[index] = interaction.clone(),
+        MergeStrategy::DedupeIdentical if identity(&existing[index]) == identity(interaction) => {}
+        _ => conflicts.push(description(interaction))
+      }
+    }
+  }
+
+  if conflicts.is_empty() {
+    Ok(())
+  } else {
+    warn!("The following conflicting interactions where found:");
+    for description in &conflicts {
+      warn!(" Interaction '{}'", description);
+    }
+    Err(anyhow!("Unable to merge pacts, as there were {} conflict(s) between the interactions. Please clean out your pact directory before running the tests.",
+      conflicts.len()))
+  }
+}
+
+fn merge_v3_metadata(
+  existing: &mut BTreeMap<String, BTreeMap<String, String>>,
+  newer: BTreeMap<String, BTreeMap<String, String>>
+) {
+  for (key, value) in newer {
+    existing.entry(key).or_default().extend(value);
+  }
+}
 
 /// Construct Metadata from JSON value
 pub fn parse_meta_data(pact_json: &Value) -> BTreeMap<String, BTreeMap<String, String>> {
@@ -431,7 +758,11 @@ pub(crate) fn verify_metadata(metadata: &Value, _spec_version: PactSpecification
 mod tests {
   use std::{env, fs, io};
   use std::fs::File;
-  use std::io::Read;
+  use std::io::{Read, Write};
+  use std::path::Path;
+
+  use flate2::Compression;
+  use flate2::write::GzEncoder;
 
   use expectest::prelude::*;
   use maplit::{btreemap, hashmap};
@@ -447,13 +778,14 @@ mod tests {
   use crate::generators::Generator;
   use crate::matchingrules;
   use crate::matchingrules::MatchingRule;
-  use crate::pact::{Pact, ReadWritePact, write_pact};
+  use crate::pact::{find_pacts_in_dir, load_pacts_from_dir, merge_pacts, PactFileLoadResult, MergeStrategy, Pact, ReadWritePact, write_pact};
   use crate::PACT_RUST_VERSION;
   use crate::provider_states::ProviderState;
   use crate::request::Request;
   use crate::response::Response;
   use crate::sync_interaction::RequestResponseInteraction;
   use crate::sync_pact::RequestResponsePact;
+  use crate::v4::interaction::V4Interaction;
   use crate::v4::pact::V4Pact;
   use crate::v4::synch_http::SynchronousHttp;
 
@@ -1915,4 +2247,302 @@ mod tests {
     let merged_pact = pact.merge(&updated_pact);
     expect(merged_pact.unwrap().as_request_response_pact().unwrap()).to(be_equal_to(updated_pact));
   }
+
+  fn interaction(description: &str, status: u16) -> RequestResponseInteraction {
+    RequestResponseInteraction {
+      description: description.to_string(),
+      provider_states: vec![ProviderState::default("state one")],
+      response: Response { status, .. Response::default() },
+      .. RequestResponseInteraction::default()
+    }
+  }
+
+  fn statuses(pact: &dyn Pact) -> Vec<(String, u16)> {
+    pact.as_request_response_pact().unwrap().interactions.iter()
+      .map(|i| (i.description.clone(), i.response.status))
+      .collect()
+  }
+
+  #[test]
+  fn merge_pacts_test() {
+    let existing = RequestResponsePact {
+      interactions: vec![interaction("b", 200), interaction("c", 200)],
+      .. RequestResponsePact::default()
+    };
+    let newer = RequestResponsePact {
+      interactions: vec![interaction("a", 200), interaction("b", 500)],
+      .. RequestResponsePact::default()
+    };
+
+    let merged = merge_pacts(&existing, &newer, MergeStrategy::KeepNewest).unwrap();
+    expect!(merged.is_v4()).to(be_false());
+    expect!(statuses(merged.as_ref())).to(be_equal_to(vec![
+      ("b".to_string(), 500), ("c".to_string(), 200), ("a".to_string(), 200)
+    ]));
+
+    expect!(merge_pacts(&existing, &newer, MergeStrategy::Error).unwrap_err().to_string()).to(
+      be_equal_to("Unable to merge pacts, as there were 1 conflict(s) between the interactions. Please clean out your pact directory before running the tests."));
+    expect!(merge_pacts(&existing, &newer, MergeStrategy::DedupeIdentical)).to(be_err());
+  }
+
+  #[test]
+  fn merge_pacts_with_identical_interactions() {
+    let existing = RequestResponsePact {
+      interactions: vec![interaction("a", 200)],
+      .. RequestResponsePact::default()
+    };
+    let newer = RequestResponsePact {
+      interactions: vec![interaction("a", 200), interaction("b", 200)],
+      .. RequestResponsePact::default()
+    };
+
+    let merged = merge_pacts(&existing, &newer, MergeStrategy::DedupeIdentical).unwrap();
+    expect!(statuses(merged.as_ref())).to(be_equal_to(vec![("a".to_string(), 200), ("b".to_string(), 200)]));
+    expect!(merge_pacts(&existing, &newer, MergeStrategy::Error)).to(be_err());
+  }
+
+  #[test]
+  fn merge_pacts_keeps_the_specification_version_of_v3_pacts() {
+    let existing = RequestResponsePact {
+      interactions: vec![RequestResponseInteraction { id: Some("1".to_string()), .. interaction("b", 200) }],
+      specification_version: PactSpecification::V2,
+      .. RequestResponsePact::default()
+    };
+    let newer = RequestResponsePact {
+      interactions: vec![RequestResponseInteraction { id: Some("2".to_string()), .. interaction("a", 200) }],
+      specification_version: PactSpecification::V2,
+      .. RequestResponsePact::default()
+    };
+
+    let merged = merge_pacts(&existing, &newer, MergeStrategy::Error).unwrap();
+    expect!(merged.specification_version()).to(be_equal_to(PactSpecification::V2));
+    let merged = merged.as_request_response_pact().unwrap();
+    expect!(merged.interactions.iter().map(|i| i.id.clone()).collect::<Vec<_>>()).to(be_equal_to(vec![
+      Some("1".to_string()), Some("2".to_string())
+    ]));
+  }
+
+  #[test]
+  fn merge_pacts_with_message_pacts() {
+    let message = |description: &str| Message {
+      description: description.to_string(),
+      .. Message::default()
+    };
+    let existing = MessagePact {
+      consumer: Consumer { name: "default_consumer".to_string() },
+      provider: Provider { name: "default_provider".to_string() },
+      messages: vec![message("b")],
+      metadata: btreemap!{},
+      specification_version: PactSpecification::V3
+    };
+    let newer = MessagePact {
+      messages: vec![message("a"), message("b")],
+      .. existing.clone()
+    };
+
+    let merged = merge_pacts(&existing, &newer, MergeStrategy::DedupeIdentical).unwrap();
+    expect!(merged.is_v4()).to(be_false());
+    let merged = merged.as_message_pact().unwrap();
+    expect!(merged.messages.iter().map(|m| m.description.clone()).collect::<Vec<_>>()).to(be_equal_to(vec![
+      "b".to_string(), "a".to_string()
+    ]));
+    expect!(merge_pacts(&existing, &RequestResponsePact::default(), MergeStrategy::KeepNewest)).to(be_err());
+  }
+
+  #[test]
+  fn merge_pacts_keeps_v4_interactions_with_different_keys() {
+    let v4_interaction = |key: &str| SynchronousHttp {
+      description: "a".to_string(),
+      key: Some(key.to_string()),
+      .. SynchronousHttp::default()
+    }.boxed_v4();
+    let existing = V4Pact {
+      interactions: vec![v4_interaction("1234")],
+      .. V4Pact::default()
+    };
+    let newer = V4Pact {
+      interactions: vec![v4_interaction("5678"), v4_interaction("1234")],
+      .. V4Pact::default()
+    };
+
+    let merged = merge_pacts(&existing, &newer, MergeStrategy::KeepNewest).unwrap().as_v4_pact().unwrap();
+    expect!(merged.interactions.iter().map(|i| i.key()).collect::<Vec<_>>()).to(be_equal_to(vec![
+      Some("1234".to_string()), Some("5678".to_string())
+    ]));
+    expect!(existing.merge(&newer).unwrap().interactions().len()).to(be_equal_to(2));
+  }
+
+  #[test]
+  fn merge_pacts_returns_a_v4_pact_if_either_pact_is_v4() {
+    let existing = RequestResponsePact {
+      interactions: vec![interaction("a", 200)],
+      .. RequestResponsePact::default()
+    };
+    let newer = RequestResponsePact {
+      interactions: vec![interaction("b", 200)],
+      .. RequestResponsePact::default()
+    }.as_v4_pact().unwrap();
+
+    let merged = merge_pacts(&existing, &newer, MergeStrategy::Error).unwrap();
+    expect!(merged.is_v4()).to(be_true());
+    expect!(merged.interactions().len()).to(be_equal_to(2));
+  }
+
+  #[test]
+  fn merge_pacts_returns_an_error_if_the_consumers_are_different() {
+    let existing = RequestResponsePact::default();
+    let newer = RequestResponsePact {
+      consumer: Consumer { name: "other consumer".to_string() },
+      .. RequestResponsePact::default()
+    };
+    expect!(merge_pacts(&existing, &newer, MergeStrategy::KeepNewest)).to(be_err());
+  }
+
+  #[test]
+  fn deduplicate_interactions_test() {
+    let mut pact = RequestResponsePact {
+      interactions: vec![
+        interaction("b", 200),
+        interaction("a", 200),
+        RequestResponseInteraction { id: Some("1234".to_string()), .. interaction("b", 200) },
+        interaction("b", 500)
+      ],
+      .. RequestResponsePact::default()
+    };
+    expect!(pact.deduplicate_interactions()).to(be_equal_to(1));
+    expect!(statuses(&pact)).to(be_equal_to(vec![
+      ("b".to_string(), 200), ("a".to_string(), 200), ("b".to_string(), 500)
+    ]));
+    expect!(pact.deduplicate_interactions()).to(be_equal_to(0));
+  }
+
+  #[test]
+  fn normalize_test() {
+    let mut pact = RequestResponsePact {
+      interactions: vec![interaction("c", 200), interaction("a", 200), interaction("b", 200), interaction("a", 200)],
+      .. RequestResponsePact::default()
+    };
+    let mut v4_pact = pact.as_v4_pact().unwrap();
+
+    pact.normalize();
+    expect!(statuses(&pact)).to(be_equal_to(vec![
+      ("a".to_string(), 200), ("b".to_string(), 200), ("c".to_string(), 200)
+    ]));
+
+    v4_pact.normalize();
+    expect!(v4_pact.interactions.iter().map(|i| i.description()).collect::<Vec<_>>()).to(
+      be_equal_to(vec!["a".to_string(), "b".to_string(), "c".to_string()]));
+  }
+
+  #[test]
+  fn write_pact_writes_canonical_pact_files() {
+    let pact = RequestResponsePact {
+      consumer: Consumer { name: "canonical_consumer".to_string() },
+      provider: Provider { name: "canonical_provider".to_string() },
+      interactions: vec![interaction("c", 200), interaction("a", 200), interaction("b", 200)],
+      .. RequestResponsePact::default()
+    };
+    let reordered = RequestResponsePact {
+      interactions: vec![interaction("b", 200), interaction("c", 200), interaction("a", 200)],
+      .. pact.clone()
+    };
+    let mut dir = env::temp_dir();
+    dir.push(format!("pact_test_{}", rand::random::<u16>()));
+    let path = dir.join(pact.default_file_name());
+
+    let result = write_pact(pact.boxed(), path.as_path(), PactSpecification::V3, true);
+    let pact_file = read_pact_file(path.to_str().unwrap()).unwrap_or_default();
+    let result2 = write_pact(reordered.boxed(), path.as_path(), PactSpecification::V3, true);
+    let pact_file2 = read_pact_file(path.to_str().unwrap()).unwrap_or_default();
+    fs::remove_dir_all(&dir).unwrap_or(());
+
+    expect!(result).to(be_ok());
+    expect!(result2).to(be_ok());
+    let json: Value = serde_json::from_str(&pact_file).unwrap();
+    let descriptions = json["interactions"].as_array().unwrap().iter()
+      .map(|i| i["description"].as_str().unwrap_or_default())
+      .collect::<Vec<_>>();
+    expect!(descriptions).to(be_equal_to(vec!["a", "b", "c"]));
+    assert_eq!(pact_file, pact_file2);
+  }
+
+  #[test]
+  fn load_pacts_from_dir_test() {
+    let pact = RequestResponsePact {
+      consumer: Consumer { name: "load_pacts_from_dir_consumer".to_string() },
+      provider: Provider { name: "load_pacts_from_dir_provider".to_string() },
+      interactions: vec![interaction("a", 200)],
+      .. RequestResponsePact::default()
+    };
+    let json = pact.to_json(PactSpecification::V3).unwrap().to_string();
+
+    let mut dir = env::temp_dir();
+    dir.push(format!("pact_test_{}", rand::random::<u16>()));
+    fs::create_dir_all(dir.join("nested")).unwrap();
+    fs::write(dir.join("pact.json"), &json).unwrap();
+    fs::write(dir.join("invalid.json"), "{").unwrap();
+    fs::write(dir.join("README.md"), "not a pact").unwrap();
+    let mut encoder = GzEncoder::new(File::create(dir.join("nested").join("pact.json.gz")).unwrap(), Compression::default());
+    encoder.write_all(json.as_bytes()).unwrap();
+    encoder.finish().unwrap();
+
+    let names = |results: &Vec<PactFileLoadResult>| {
+      results.iter()
+        .map(|(path, result)| (path.strip_prefix(&dir).unwrap().to_string_lossy().to_string(), result.is_ok()))
+        .collect::<Vec<_>>()
+    };
+
+    let top_level = load_pacts_from_dir(&dir, false, None);
+    let recursive = load_pacts_from_dir(&dir, true, None);
+    let with_glob = load_pacts_from_dir(&dir, true, Some("**/*.gz"));
+    let bad_glob = load_pacts_from_dir(&dir, true, Some("[a"));
+    let missing_dir = load_pacts_from_dir(dir.join("missing"), true, None);
+    let found = find_pacts_in_dir(&dir, false, None);
+    let top_level = top_level.map(|r| names(&r));
+    let recursive = recursive.map(|r| names(&r));
+    let with_glob = with_glob.map(|r| names(&r));
+    fs::remove_dir_all(&dir).unwrap_or(());
+
+    expect!(top_level.unwrap()).to(be_equal_to(vec![
+      ("invalid.json".to_string(), false), ("pact.json".to_string(), true)
+    ]));
+    let nested = Path::new("nested").join("pact.json.gz").to_string_lossy().to_string();
+    expect!(recursive.unwrap()).to(be_equal_to(vec![
+      ("invalid.json".to_string(), false), (nested.clone(), true), ("pact.json".to_string(), true)
+    ]));
+    expect!(with_glob.unwrap()).to(be_equal_to(vec![(nested, true)]));
+    expect!(bad_glob).to(be_err());
+    expect!(missing_dir).to(be_err());
+    expect!(found.unwrap()).to(be_equal_to(vec![dir.join("invalid.json"), dir.join("pact.json")]));
+  }
+
+  #[test]
+  fn write_and_read_gzipped_pact_files() {
+    let pact = RequestResponsePact {
+      consumer: Consumer { name: "gzip_consumer".to_string() },
+      provider: Provider { name: "gzip_provider".to_string() },
+      interactions: vec![interaction("a", 200)],
+      .. RequestResponsePact::default()
+    };
+    let pact2 = RequestResponsePact {
+      interactions: vec![interaction("b", 200)],
+      .. pact.clone()
+    };
+    let mut dir = env::temp_dir();
+    dir.push(format!("pact_test_{}", rand::random::<u16>()));
+    let path = dir.join("pact.json.gz");
+
+    let result = write_pact(pact.boxed(), path.as_path(), PactSpecification::V3, true);
+    let result2 = write_pact(pact2.boxed(), path.as_path(), PactSpecification::V3, false);
+    let header = fs::read(&path).unwrap_or_default().into_iter().take(2).collect::<Vec<_>>();
+    let loaded = crate::pact::read_pact(&path).map(|pact| pact.interactions().len());
+    let loaded_v3 = RequestResponsePact::read_pact(&path).map(|pact| pact.interactions.len());
+    fs::remove_dir_all(&dir).unwrap_or(());
+
+    expect!(result).to(be_ok());
+    expect!(result2).to(be_ok());
+    expect!(header).to(be_equal_to(vec![0x1f, 0x8b]));
+    expect!(loaded).to(be_ok().value(2));
+    expect!(loaded_v3).to(be_ok().value(2));
+  }
 }
@@ -33,7 +33,25 @@ pub enum PathToken {
   /// * token
   Star,
   /// * index token
-  StarIndex
+  StarIndex,
+  /// Array slice token (i.e. `[1:3]`), from the start index (inclusive) to the end index
+  /// (exclusive). A missing start or end index means the start or end of the array.
+  Slice(Option<usize>, Option<usize>),
+  /// ** token, which matches any number of path elements (including none)
+  DeepWildcard
+}
+
+impl PathToken {
+  fn write_slice(out: &mut impl Write, start: &Option<usize>, end: &Option<usize>) -> std::fmt::Result {
+    if let Some(start) = start {
+      write!(out, "{}", start)?;
+    }
+    out.write_char(':')?;
+    if let Some(end) = end {
+      write!(out, "{}", end)?;
+    }
+    Ok(())
+  }
 }
 
 impl Display for PathToken {
@@ -43,12 +61,14 @@ impl Display for PathToken {
       PathToken::Field(n) => write!(f, "{}", n),
       PathToken::Index(n) => write!(f, "{}", n),
       PathToken::Star => write!(f, "*"),
-      PathToken::StarIndex => write!(f, "*")
+      PathToken::StarIndex => write!(f, "*"),
+      PathToken::Slice(start, end) => PathToken::write_slice(f, start, end),
+      PathToken::DeepWildcard => write!(f, "**")
     }
   }
 }
 
-fn matches_token(path_fragment: &str, path_token: &PathToken) -> usize {
+pub(crate) fn matches_token(path_fragment: &str, path_token: &PathToken) -> usize {
   match path_token {
     PathToken::Root if path_fragment == "$" => 2,
     PathToken::Field(name) if path_fragment == name => 2,
@@ -60,11 +80,36 @@ fn matches_token(path_fragment: &str, path_token: &PathToken) -> usize {
       Ok(_) => 1,
       _ => 0
     },
-    PathToken::Star => 1,
+    PathToken::Slice(start, end) => match path_fragment.parse::<usize>() {
+      Ok(i) if start.map(|start| i >= start).unwrap_or(true) && end.map(|end| i < end).unwrap_or(true) => 1,
+      _ => 0
+    },
+    PathToken::Star | PathToken::DeepWildcard => 1,
     _ => 0
   }
 }
 
+/// Calculates the weight of the path tokens against the path fragments, where a deep wildcard
+/// token can match any number of fragments. Returns the weight and the number of fragments
+/// matched for the best match.
+fn deep_path_weight(tokens: &[PathToken], path: &[&str]) -> Option<(usize, usize)> {
+  match tokens.split_first() {
+    None => Some((1, 0)),
+    Some((PathToken::DeepWildcard, rest)) => (0..=path.len())
+      .filter_map(|skip| deep_path_weight(rest, &path[skip..])
+        .map(|(weight, matched)| (weight, matched + skip)))
+      .max_by_key(|(weight, matched)| weight * matched),
+    Some((token, rest)) => {
+      let fragment = path.first()?;
+      match matches_token(fragment, token) {
+        0 => None,
+        weight => deep_path_weight(rest, &path[1..])
+          .map(|(w, matched)| (w * weight, matched + 1))
+      }
+    }
+  }
+}
+
 #[derive(Debug, Clone, Eq, Serialize, Deserialize)]
 #[serde(try_from = "String")]
 #[serde(into = "String")]
@@ -154,13 +199,20 @@ impl DocPath {
     self.path_tokens.last() == Some(&PathToken::Star)
   }
 
+  /// If this path contains a deep wildcard (`**`), so it can match paths of different lengths
+  pub fn has_deep_wildcard(&self) -> bool {
+    self.path_tokens.contains(&PathToken::DeepWildcard)
+  }
+
   /// Calculates the path weight for this path expression and a given path.
   /// Returns a tuple of the calculated weight and the number of path tokens matched.
   pub fn path_weight(&self, path: &[&str]) -> (usize, usize) {
     trace!("Calculating weight for path tokens '{:?}' and path '{:?}'",
            self.path_tokens, path);
     let weight = {
-      if path.len() >= self.len() {
+      if self.has_deep_wildcard() {
+        deep_path_weight(&self.path_tokens, path).unwrap_or((0, self.len()))
+      } else if path.len() >= self.len() {
         (
           self.path_tokens.iter().zip(path.iter())
           .fold(1, |acc, (token, fragment)| acc * matches_token(fragment, token)),
@@ -184,7 +236,8 @@ impl DocPath {
   /// If the path matches the given path (the calculated path weight is greater than zero) and
   /// both paths have the same length.
   pub fn matches_path_exactly(&self, path: &[&str]) -> bool {
-     self.len() == path.len() && self.matches_path(path)
+    let (weight, matched) = self.path_weight(path);
+    weight > 0 && matched == path.len()
   }
 
   /// Creates a new path by cloning this one and pushing the string onto the end
@@ -233,16 +286,7 @@ impl DocPath {
 
   /// Mutates this path by pushing a path token onto the end.
   pub fn push(&mut self, path_token: PathToken) -> &mut Self {
-    match &path_token {
-      PathToken::Root => self.expr.push_str("$"),
-      PathToken::Field(v) => {
-        let s = &mut self.expr;
-        write_obj_key_for_path(s, v.as_str())
-      },
-      PathToken::Index(i) => { let _ = write!(self.expr, "[{}]", i); },
-      PathToken::Star => self.expr.push_str(".*"),
-      PathToken::StarIndex => self.expr.push_str("[*]")
-    };
+    write_token_for_path(&mut self.expr, &path_token);
     self.path_tokens.push(path_token);
     self
   }
@@ -283,24 +327,7 @@ impl DocPath {
     let mut buffer = String::new();
 
     for token in &self.path_tokens {
-      match token {
-        PathToken::Root => buffer.push('$'),
-        PathToken::Field(v) => {
-          write_obj_key_for_path(&mut buffer, v.as_str());
-        }
-        PathToken::Index(i) => {
-          let _ = write!(buffer, "[{}]", i);
-        }
-        PathToken::Star => {
-          buffer.push('.');
-          buffer.push('*');
-        }
-        PathToken::StarIndex => {
-          buffer.push('[');
-          buffer.push('*');
-          buffer.push(']');
-        }
-      }
+      write_token_for_path(&mut buffer, token);
     }
 
     buffer
@@ -339,6 +366,12 @@ impl DocPath {
         PathToken::StarIndex => {
           return Err(anyhow!("* can not be converted to a JSON pointer"));
         }
+        PathToken::Slice(_, _) => {
+          return Err(anyhow!("Array slices can not be converted to a JSON pointer"));
+        }
+        PathToken::DeepWildcard => {
+          return Err(anyhow!("** can not be converted to a JSON pointer"));
+        }
       }
     }
 
@@ -362,6 +395,26 @@ fn write_obj_key_for_path(mut out: impl Write, key: &str) {
   };
 }
 
+fn write_token_for_path(out: &mut String, token: &PathToken) {
+  // unwrap is safe, as write! is infallible for String
+  let _ = match token {
+    PathToken::Root => out.write_char('$'),
+    PathToken::Field(v) => {
+      write_obj_key_for_path(&mut *out, v.as_str());
+      Ok(())
+    },
+    PathToken::Index(i) => write!(out, "[{}]", i),
+    PathToken::Star => out.write_str(".*"),
+    PathToken::StarIndex => out.write_str("[*]"),
+    PathToken::Slice(start, end) => {
+      out.push('[');
+      let _ = PathToken::write_slice(out, start, end);
+      out.write_char(']')
+    },
+    PathToken::DeepWildcard => out.write_str(".**")
+  };
+}
+
 #[cfg(test)]
 fn obj_key_for_path(key: &str) -> String {
   let mut out = String::new();
@@ -427,17 +480,32 @@ fn is_identifier_char(ch: char) -> bool {
   ch.is_alphabetic() || ch.is_numeric() || ch == '_' || ch == '-' || ch == ':' || ch == '#' || ch == '@'
 }
 
-// identifier -> a-zA-Z0-9+
-fn identifier<I>(ch: char, chars: &mut Peekable<I>, tokens: &mut Vec<PathToken>, path: &str) -> Result<(), String>
+// escaped_char -> \\ any
+fn escaped_char<I>(chars: &mut Peekable<I>, path: &str, index: usize) -> Result<char, String>
+  where I: Iterator<Item=(usize, char)> {
+  chars.next()
+    .map(|(_, ch)| ch)
+    .ok_or_else(|| format!("Expected a character after \"\\\" in path expression \"{}\" at index {}", path, index))
+}
+
+// identifier -> (a-zA-Z0-9 | escaped_char)+
+fn identifier<I>(ch: (usize, char), chars: &mut Peekable<I>, tokens: &mut Vec<PathToken>, path: &str) -> Result<(), String>
   where I: Iterator<Item=(usize, char)> {
   let mut id = String::new();
-  id.push(ch);
+  if ch.1 == '\\' {
+    id.push(escaped_char(chars, path, ch.0)?);
+  } else {
+    id.push(ch.1);
+  }
   let mut next_char = peek(chars);
   while next_char.is_some() {
     let ch = next_char.unwrap();
     if is_identifier_char(ch.1) {
       chars.next();
       id.push(ch.1);
+    } else if ch.1 == '\\' {
+      chars.next();
+      id.push(escaped_char(chars, path, ch.0)?);
     } else if ch.1 == '.' || ch.1 == '\'' || ch.1 == '[' {
       break;
     } else {
@@ -450,17 +518,22 @@ fn identifier<I>(ch: char, chars: &mut Peekable<I>, tokens: &mut Vec<PathToken>,
   Ok(())
 }
 
-// path_identifier -> identifier | *
+// path_identifier -> identifier | * | **
 fn path_identifier<I>(chars: &mut Peekable<I>, tokens: &mut Vec<PathToken>, path: &str, index: usize) -> Result<(), String>
   where I: Iterator<Item=(usize, char)> {
   match chars.next() {
     Some(ch) => match ch.1 {
       '*' => {
-        tokens.push(PathToken::Star);
+        if peek(chars).map(|ch| ch.1) == Some('*') {
+          chars.next();
+          tokens.push(PathToken::DeepWildcard);
+        } else {
+          tokens.push(PathToken::Star);
+        }
         Ok(())
       },
-      c if is_identifier_char(c) => {
-        identifier(c, chars, tokens, path)?;
+      c if is_identifier_char(c) || c == '\\' => {
+        identifier(ch, chars, tokens, path)?;
         Ok(())
       },
       _ => Err(format!("Expected either a \"*\" or path identifier in path expression \"{}\" at index {}",
@@ -471,53 +544,57 @@ fn path_identifier<I>(chars: &mut Peekable<I>, tokens: &mut Vec<PathToken>, path
   }
 }
 
-// string_path -> [^']+
+// string_path -> ([^'\\] | escaped_char)+
 fn string_path<I>(chars: &mut Peekable<I>, tokens: &mut Vec<PathToken>, path: &str, index: usize) -> Result<(), String>
   where I: Iterator<Item=(usize, char)> {
   let mut id = String::new();
-  let mut next_char = peek(chars);
-  if next_char.is_some() {
-    chars.next();
-    let mut ch = next_char.unwrap();
-    next_char = peek(chars);
-    while ch.1 != '\'' && next_char.is_some() {
-      id.push(ch.1);
-      chars.next();
-      ch = next_char.unwrap();
-      next_char = peek(chars);
-    }
-    if ch.1 == '\'' {
-      if id.is_empty() {
-        Err(format!("Empty strings are not allowed in path expression \"{}\" at index {}", path, ch.0))
+  let mut last_index = index;
+  loop {
+    match chars.next() {
+      Some((i, '\'')) => return if id.is_empty() {
+        Err(format!("Empty strings are not allowed in path expression \"{}\" at index {}", path, i))
       } else {
         tokens.push(PathToken::Field(id));
         Ok(())
-      }
-    } else {
-      Err(format!("Unterminated string in path expression \"{}\" at index {}", path, ch.0))
+      },
+      Some((i, '\\')) => {
+        id.push(escaped_char(chars, path, i)?);
+        last_index = i + 1;
+      },
+      Some((i, ch)) => {
+        id.push(ch);
+        last_index = i;
+      },
+      None => return Err(format!("Unterminated string in path expression \"{}\" at index {}", path, last_index))
     }
-  } else {
-    Err(format!("Unterminated string in path expression \"{}\" at index {}", path, index))
   }
 }
 
-// index_path -> [0-9]+
-fn index_path<I>(chars: &mut Peekable<I>, tokens: &mut Vec<PathToken>, path: &str) -> Result<(), String>
+// number -> [0-9]*
+fn number<I>(chars: &mut Peekable<I>) -> Option<usize>
   where I: Iterator<Item=(usize, char)> {
   let mut id = String::new();
-  let mut next_char = chars.next();
-  id.push(next_char.unwrap().1);
-  next_char = peek(chars);
-  while next_char.is_some() {
-    let ch = next_char.unwrap();
-    if ch.1.is_numeric() {
-      id.push(ch.1);
-      chars.next();
-    } else {
-      break;
-    }
-    next_char = peek(chars);
+  while let Some(ch) = peek(chars).filter(|ch| ch.1.is_ascii_digit()) {
+    id.push(ch.1);
+    chars.next();
   }
+  id.parse().ok()
+}
+
+// index_path -> [0-9]+ | [0-9]* : [0-9]*
+fn index_path<I>(chars: &mut Peekable<I>, tokens: &mut Vec<PathToken>, path: &str) -> Result<(), String>
+  where I: Iterator<Item=(usize, char)> {
+  let start = number(chars);
+  let mut next_char = peek(chars);
+  let is_slice = next_char.map(|ch| ch.1) == Some(':');
+  let end = if is_slice {
+    chars.next();
+    let end = number(chars);
+    next_char = peek(chars);
+    end
+  } else {
+    None
+  };
 
   if let Some(ch) = next_char {
     if ch.1 != ']' {
@@ -526,7 +603,11 @@ fn index_path<I>(chars: &mut Peekable<I>, tokens: &mut Vec<PathToken>, path: &st
     }
   }
 
-  tokens.push(PathToken::Index(id.parse().unwrap()));
+  if is_slice {
+    tokens.push(PathToken::Slice(start, end));
+  } else {
+    tokens.push(PathToken::Index(start.unwrap_or_default()));
+  }
   Ok(())
 }
 
@@ -539,7 +620,7 @@ fn bracket_path<I>(chars: &mut Peekable<I>, tokens: &mut Vec<PathToken>, path: &
       if c.1 == '\'' {
         chars.next();
         string_path(chars, tokens, path, c.0)?
-      } else if c.1.is_numeric() {
+      } else if c.1.is_ascii_digit() || c.1 == ':' {
         index_path(chars, tokens, path)?
       } else if c.1 == '*' {
         chars.next();
@@ -601,7 +682,7 @@ pub fn parse_path_exp(path: &str) -> Result<Vec<PathToken>, String> {
         }
         c if c.is_alphabetic() || c.is_numeric() => {
           tokens.push(PathToken::Root);
-          identifier(c, &mut chars, &mut tokens, path)?;
+          identifier(ch, &mut chars, &mut tokens, path)?;
           path_exp(&mut chars, &mut tokens, path)?;
           Ok(tokens)
         }
@@ -848,6 +929,89 @@ mod tests {
       be_err().value("Indexes can only consist of numbers or a \"*\", found \"-\" instead in path expression \"$[-1]\" at index 2".to_string()));
   }
 
+  #[test]
+  fn parse_path_exp_with_array_slices() {
+    expect!(parse_path_exp("$.a[0:2]")).to(
+      be_ok().value(vec![PathToken::Root, PathToken::Field("a".to_string()), PathToken::Slice(Some(0), Some(2))]));
+    expect!(parse_path_exp("$.a[1:].b")).to(
+      be_ok().value(vec![PathToken::Root, PathToken::Field("a".to_string()), PathToken::Slice(Some(1), None),
+                         PathToken::Field("b".to_string())]));
+    expect!(parse_path_exp("$[:3]")).to(
+      be_ok().value(vec![PathToken::Root, PathToken::Slice(None, Some(3))]));
+    expect!(parse_path_exp("$[:]")).to(
+      be_ok().value(vec![PathToken::Root, PathToken::Slice(None, None)]));
+    expect!(parse_path_exp("$[1:x]")).to(
+      be_err().value("Indexes can only consist of numbers or a \"*\", found \"x\" instead in path expression \"$[1:x]\" at index 4".to_string()));
+  }
+
+  #[test]
+  fn parse_path_exp_with_deep_wildcards() {
+    expect!(parse_path_exp("$.**.id")).to(
+      be_ok().value(vec![PathToken::Root, PathToken::DeepWildcard, PathToken::Field("id".to_string())]));
+    expect!(parse_path_exp("$.items[*].**")).to(
+      be_ok().value(vec![PathToken::Root, PathToken::Field("items".to_string()), PathToken::StarIndex,
+                         PathToken::DeepWildcard]));
+  }
+
+  #[test]
+  fn parse_path_exp_with_escaped_characters() {
+    expect!(parse_path_exp(r"$.a\.b.c")).to(
+      be_ok().value(vec![PathToken::Root, PathToken::Field("a.b".to_string()), PathToken::Field("c".to_string())]));
+    expect!(parse_path_exp(r"$.\[0\]")).to(
+      be_ok().value(vec![PathToken::Root, PathToken::Field("[0]".to_string())]));
+    expect!(parse_path_exp(r"$['it\'s']")).to(
+      be_ok().value(vec![PathToken::Root, PathToken::Field("it's".to_string())]));
+    expect!(parse_path_exp(r"$['a\\b']")).to(
+      be_ok().value(vec![PathToken::Root, PathToken::Field(r"a\b".to_string())]));
+    expect!(parse_path_exp(r"$.a\")).to(
+      be_err().value(r#"Expected a character after "\" in path expression "$.a\" at index 3"#.to_string()));
+  }
+
+  #[test]
+  fn paths_round_trip_through_their_expressions() {
+    let mut path = DocPath::root();
+    path.push_field("a.b")
+      .push_field("it's [quoted]")
+      .push_field(r"back\slash")
+      .push(PathToken::Slice(Some(1), Some(3)))
+      .push(PathToken::DeepWildcard)
+      .push(PathToken::Slice(None, Some(2)))
+      .push_star_index()
+      .push_field("id");
+    expect!(path.to_string()).to(be_equal_to(r"$['a.b']['it\'s [quoted]']['back\\slash'][1:3].**[:2][*].id"));
+    expect!(parse_path_exp(path.to_string().as_str())).to(be_ok().value(path.tokens().clone()));
+  }
+
+  #[test]
+  fn matches_path_with_array_slices() {
+    let path = DocPath::new_unwrap("$.items[1:3].id");
+    expect!(path.path_weight(&["$", "items", "0", "id"]).0).to(be_equal_to(0));
+    expect!(path.path_weight(&["$", "items", "1", "id"])).to(be_equal_to((8, 4)));
+    expect!(path.path_weight(&["$", "items", "2", "id"])).to(be_equal_to((8, 4)));
+    expect!(path.path_weight(&["$", "items", "3", "id"]).0).to(be_equal_to(0));
+    expect!(path.path_weight(&["$", "items", "name", "id"]).0).to(be_equal_to(0));
+    expect!(DocPath::new_unwrap("$[2:]").matches_path(&["$", "100"])).to(be_true());
+  }
+
+  #[test]
+  fn matches_path_with_deep_wildcards() {
+    let path = DocPath::new_unwrap("$.**.id");
+    expect!(path.path_weight(&["$", "id"])).to(be_equal_to((4, 2)));
+    expect!(path.path_weight(&["$", "a", "b", "id"])).to(be_equal_to((4, 4)));
+    expect!(path.path_weight(&["$", "a", "b"]).0).to(be_equal_to(0));
+    expect!(path.matches_path_exactly(&["$", "a", "0", "id"])).to(be_true());
+    expect!(path.matches_path_exactly(&["$", "a", "id", "b"])).to(be_false());
+
+    let path = DocPath::new_unwrap("$.items[*].**.name");
+    expect!(path.matches_path_exactly(&["$", "items", "0", "name"])).to(be_true());
+    expect!(path.matches_path_exactly(&["$", "items", "0", "a", "b", "name"])).to(be_true());
+    expect!(path.matches_path(&["$", "items", "a", "name"])).to(be_false());
+
+    let specific = DocPath::new_unwrap("$.a.b.id");
+    let weight = |p: &DocPath| { let (w, l) = p.path_weight(&["$", "a", "b", "id"]); w * l };
+    expect!(weight(&specific) > weight(&DocPath::new_unwrap("$.**.id"))).to(be_true());
+  }
+
   #[test]
   fn obj_key_for_path_quotes_keys_when_necessary() {
     assert_eq!(obj_key_for_path("foo"), ".foo");
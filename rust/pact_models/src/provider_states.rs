@@ -5,8 +5,10 @@ use std::cmp::Eq;
 use std::collections::HashMap;
 use std::hash::{Hash, Hasher};
 
+use anyhow::anyhow;
 use maplit::*;
 use serde::{Deserialize, Serialize};
+use serde::de::DeserializeOwned;
 use serde_json::*;
 use tracing::warn;
 
@@ -32,6 +34,29 @@ impl ProviderState {
     }
   }
 
+  /// Creates a state with the given name and the parameters from the value, which must serialize
+  /// to a JSON object (i.e. a struct or a map). Returns an error if it does not.
+  pub fn with_params<T: Into<String>, P: Serialize>(name: T, params: &P) -> anyhow::Result<ProviderState> {
+    let name = name.into();
+    match to_value(params) {
+      Ok(Value::Object(map)) => Ok(ProviderState {
+        name,
+        params: map.into_iter().collect()
+      }),
+      Ok(value) => Err(anyhow!("Parameters for provider state '{}' must be an object, got {}",
+        name, json_type_of(&value))),
+      Err(err) => Err(anyhow!("Failed to serialize the parameters for provider state '{}' - {}", name, err))
+    }
+  }
+
+  /// Deserializes the parameters of this state into the given type. Returns an error describing
+  /// the problem if the parameters are missing a required value or have the wrong type.
+  pub fn params_as<T: DeserializeOwned>(&self) -> anyhow::Result<T> {
+    let params = Value::Object(self.params.iter().map(|(k, v)| (k.clone(), v.clone())).collect());
+    from_value(params)
+      .map_err(|err| anyhow!("Failed to deserialize the parameters for provider state '{}' - {}", self.name, err))
+  }
+
   /// Constructs a provider state from the `Json` struct
   pub fn from_json_v3(pact_json: &Value) -> ProviderState {
     let state = match pact_json.get("name") {
@@ -213,6 +238,67 @@ mod tests {
     }));
   }
 
+  #[derive(Debug, PartialEq, Serialize, Deserialize)]
+  struct UserParams {
+    id: u32,
+    name: String,
+    #[serde(default)]
+    admin: bool
+  }
+
+  #[test]
+  fn params_as_deserializes_the_parameters() {
+    let state = ProviderState {
+      name: "user exists".to_string(),
+      params: hashmap!{
+        "id".to_string() => json!(100),
+        "name".to_string() => json!("Fred")
+      }
+    };
+    expect!(state.params_as::<UserParams>()).to(be_ok().value(UserParams {
+      id: 100,
+      name: "Fred".to_string(),
+      admin: false
+    }));
+  }
+
+  #[test]
+  fn params_as_returns_a_useful_error() {
+    let state = ProviderState {
+      name: "user exists".to_string(),
+      params: hashmap!{ "id".to_string() => json!("100") }
+    };
+    expect!(state.params_as::<UserParams>().unwrap_err().to_string()).to(be_equal_to(
+      "Failed to deserialize the parameters for provider state 'user exists' - invalid type: string \"100\", expected u32"));
+
+    let state = ProviderState {
+      name: "user exists".to_string(),
+      params: hashmap!{ "id".to_string() => json!(100) }
+    };
+    expect!(state.params_as::<UserParams>().unwrap_err().to_string()).to(be_equal_to(
+      "Failed to deserialize the parameters for provider state 'user exists' - missing field `name`"));
+  }
+
+  #[test]
+  fn with_params_serializes_the_parameters() {
+    let state = ProviderState::with_params("user exists", &UserParams {
+      id: 100,
+      name: "Fred".to_string(),
+      admin: true
+    }).unwrap();
+    expect!(state).to(be_equal_to(ProviderState {
+      name: "user exists".to_string(),
+      params: hashmap!{
+        "id".to_string() => json!(100),
+        "name".to_string() => json!("Fred"),
+        "admin".to_string() => json!(true)
+      }
+    }));
+
+    expect!(ProviderState::with_params("user exists", &100).unwrap_err().to_string()).to(be_equal_to(
+      "Parameters for provider state 'user exists' must be an object, got Number"));
+  }
+
   #[test]
   fn pact_with_no_provider_states() {
     let json = r#"{
@@ -3,10 +3,11 @@ use std::str::from_utf8;
 
 use hex::FromHex;
 use itertools::Itertools;
-use serde_json::Value;
+use serde_json::{Map, Value};
 use tracing::{error, trace, warn};
 
 use crate::PactSpecification;
+use crate::path_exp::DocPath;
 
 /// Decodes a query string using a percent-encoding scheme
 pub fn decode_query(query: &str) -> Result<String, String> {
@@ -192,6 +193,138 @@ pub fn query_to_json(query: HashMap<String, Vec<Option<String>>>, spec_version:
   }
 }
 
+/// Splits a query parameter name that uses the nested bracket syntax (i.e. `filter[name]` or
+/// `ids[]`) into the name and the keys for each level. An empty key is used for `[]`. Returns
+/// `None` if the name does not use the bracket syntax.
+pub fn split_nested_query_name(name: &str) -> Option<Vec<String>> {
+  let start = name.find('[')?;
+  if start == 0 || !name.ends_with(']') {
+    return None;
+  }
+
+  let mut keys = vec![name[..start].to_string()];
+  let mut rest = &name[start..];
+  while !rest.is_empty() {
+    let end = rest.find(']')?;
+    if !rest.starts_with('[') || rest[1..end].contains('[') {
+      return None;
+    }
+    keys.push(rest[1..end].to_string());
+    rest = &rest[end + 1..];
+  }
+
+  // Only a trailing [] is supported for lists
+  if keys[..keys.len() - 1].iter().skip(1).any(|key| key.is_empty()) {
+    None
+  } else {
+    Some(keys)
+  }
+}
+
+/// Returns the path that matching rules can use to target a query parameter that uses the
+/// nested bracket syntax (i.e. `$.filter.name` for `filter[name]` and `$.ids` for `ids[]`).
+/// Returns `None` if the name does not use the bracket syntax.
+pub fn nested_query_path(name: &str) -> Option<DocPath> {
+  split_nested_query_name(name).map(|keys| {
+    keys.iter()
+      .filter(|key| !key.is_empty())
+      .fold(DocPath::root(), |path, key| path.join(key.as_str()))
+  })
+}
+
+fn query_value_to_json(value: &Option<String>) -> Value {
+  match value {
+    Some(value) => Value::String(value.clone()),
+    None => Value::Null
+  }
+}
+
+/// Converts a query string map into a structured JSON value, using the nested bracket syntax of
+/// the parameter names. So `filter[name]=x&ids[]=1&ids[]=2` will be converted to
+/// `{"filter": {"name": "x"}, "ids": ["1", "2"]}`. Parameters without brackets are converted to a
+/// string value, or a list if the parameter is repeated. Parameters that conflict with another
+/// parameter (i.e. `a=1&a[b]=2`) are ignored.
+pub fn parse_nested_query(query: &HashMap<String, Vec<Option<String>>>) -> Value {
+  let mut result = Map::new();
+  for (name, values) in query.iter().sorted_by(|(a, _), (b, _)| Ord::cmp(a, b)) {
+    let (keys, is_list) = match split_nested_query_name(name) {
+      Some(mut keys) => {
+        let is_list = keys.last().map(|key| key.is_empty()).unwrap_or_default();
+        if is_list {
+          keys.pop();
+        }
+        (keys, is_list)
+      }
+      None => (vec![name.clone()], false)
+    };
+    let value = if is_list || values.len() > 1 {
+      Value::Array(values.iter().map(query_value_to_json).collect())
+    } else {
+      values.first().map(query_value_to_json).unwrap_or(Value::Null)
+    };
+
+    if !insert_nested_value(&mut result, &keys, value) {
+      warn!("Query parameter '{}' conflicts with another parameter, ignoring it", name);
+    }
+  }
+  Value::Object(result)
+}
+
+fn insert_nested_value(map: &mut Map<String, Value>, keys: &[String], value: Value) -> bool {
+  match keys {
+    [] => false,
+    [key] => if map.contains_key(key) {
+      false
+    } else {
+      map.insert(key.clone(), value);
+      true
+    },
+    [key, rest @ ..] => match map.entry(key.clone()).or_insert_with(|| Value::Object(Map::new())) {
+      Value::Object(inner) => insert_nested_value(inner, rest, value),
+      _ => false
+    }
+  }
+}
+
+fn flatten_nested_query(name: String, value: &Value, query: &mut HashMap<String, Vec<Option<String>>>) {
+  match value {
+    Value::Object(map) => for (key, value) in map {
+      flatten_nested_query(format!("{}[{}]", name, key), value, query);
+    },
+    Value::Array(values) => if values.iter().any(|value| value.is_object() || value.is_array()) {
+      for (index, value) in values.iter().enumerate() {
+        flatten_nested_query(format!("{}[{}]", name, index), value, query);
+      }
+    } else {
+      let entry = query.entry(format!("{}[]", name)).or_default();
+      entry.extend(values.iter().map(|value| match value {
+        Value::String(s) => Some(s.clone()),
+        Value::Null => None,
+        _ => Some(value.to_string())
+      }));
+    },
+    Value::String(s) => query.entry(name).or_default().push(Some(s.clone())),
+    Value::Null => query.entry(name).or_default().push(None),
+    _ => query.entry(name).or_default().push(Some(value.to_string()))
+  }
+}
+
+/// Converts a structured JSON value into a query string map, using the nested bracket syntax for
+/// the parameter names. This is the inverse of `parse_nested_query`, except that all lists are
+/// written using the `[]` syntax (i.e. `{"ids": ["1", "2"]}` is converted to `ids[]=1&ids[]=2`)
+/// and lists that contain objects are written with the index of each item. Returns an empty map
+/// if the value is not a JSON object.
+pub fn nested_query_from_json(value: &Value) -> HashMap<String, Vec<Option<String>>> {
+  let mut query = HashMap::new();
+  match value {
+    Value::Object(map) => for (key, value) in map {
+      flatten_nested_query(key.clone(), value, &mut query);
+    },
+    _ => warn!("Only JSON objects can be converted into a query string, ignoring '{}'", value)
+  }
+  query
+}
+
 #[cfg(test)]
 mod tests {
   use std::collections::HashMap;
@@ -201,7 +334,17 @@ mod tests {
   use pretty_assertions::assert_eq;
   use rstest::rstest;
 
-  use crate::query_strings::parse_query_string;
+  use serde_json::json;
+
+  use crate::path_exp::DocPath;
+  use crate::query_strings::{
+    build_query_string,
+    nested_query_from_json,
+    nested_query_path,
+    parse_nested_query,
+    parse_query_string,
+    split_nested_query_name
+  };
 
   #[test]
   fn parse_query_string_test() {
@@ -288,4 +431,72 @@ mod tests {
     let result = super::build_query_string(map);
     assert_eq!(result, expected)
   }
+
+  #[rstest]
+  #[case("a", None)]
+  #[case("[a]", None)]
+  #[case("a[", None)]
+  #[case("a]", None)]
+  #[case("a[b]c", None)]
+  #[case("a[[b]]", None)]
+  #[case("a[][b]", None)]
+  #[case("a[b]", Some(vec!["a", "b"]))]
+  #[case("a[]", Some(vec!["a", ""]))]
+  #[case("a[b][c]", Some(vec!["a", "b", "c"]))]
+  #[case("a[b][]", Some(vec!["a", "b", ""]))]
+  fn split_nested_query_name_test(#[case] name: &str, #[case] expected: Option<Vec<&str>>) {
+    let expected = expected.map(|keys| keys.iter().map(|key| key.to_string()).collect::<Vec<_>>());
+    expect!(split_nested_query_name(name)).to(be_equal_to(expected));
+  }
+
+  #[test]
+  fn nested_query_path_test() {
+    expect!(nested_query_path("a")).to(be_none());
+    expect!(nested_query_path("filter[name]")).to(be_some().value(DocPath::new_unwrap("$.filter.name")));
+    expect!(nested_query_path("ids[]")).to(be_some().value(DocPath::new_unwrap("$.ids")));
+    expect!(nested_query_path("a[0][b]")).to(be_some().value(DocPath::new_unwrap("$.a[0].b")));
+  }
+
+  #[test]
+  fn parse_nested_query_test() {
+    let query = parse_query_string("filter[name]=x&filter[type][]=a&filter[type][]=b&ids[]=1&ids[]=2&page=1&a=1&a=2&b&b[c]=1")
+      .unwrap();
+    expect!(parse_nested_query(&query)).to(be_equal_to(json!({
+      "a": ["1", "2"],
+      "b": null,
+      "filter": {
+        "name": "x",
+        "type": ["a", "b"]
+      },
+      "ids": ["1", "2"],
+      "page": "1"
+    })));
+  }
+
+  #[test]
+  fn nested_query_from_json_test() {
+    let query = nested_query_from_json(&json!({
+      "filter": {
+        "name": "x",
+        "type": ["a", "b"]
+      },
+      "ids": [1, 2],
+      "items": [{ "id": 1 }],
+      "page": "1",
+      "flag": null
+    }));
+    expect!(build_query_string(query.clone())).to(be_equal_to(
+      "filter[name]=x&filter[type][]=a&filter[type][]=b&flag&ids[]=1&ids[]=2&items[0][id]=1&page=1"));
+    expect!(parse_nested_query(&query)).to(be_equal_to(json!({
+      "filter": {
+        "name": "x",
+        "type": ["a", "b"]
+      },
+      "flag": null,
+      "ids": ["1", "2"],
+      "items": { "0": { "id": "1" } },
+      "page": "1"
+    })));
+    expect!(nested_query_from_json(&json!("a=b")).is_empty()).to(be_true());
+  }
 }
@@ -192,7 +192,7 @@ impl Request {
         map.insert("headers".to_string(), headers_to_json(&self.headers.clone().unwrap()));
       }
 
-      match self.body {
+      match *self.body.loaded() {
         OptionalBody::Present(ref body, _, _) => if self.content_type().unwrap_or_default().is_json() {
           match serde_json::from_slice(body) {
             Ok(json_body) => { map.insert("body".to_string(), json_body); },
@@ -208,7 +208,8 @@ impl Request {
           };
         },
         OptionalBody::Empty => { map.insert("body".to_string(), Value::String(String::default())); },
-        OptionalBody::Missing => (),
+        // File backed bodies are loaded above, so only become Missing if the file can not be read
+        OptionalBody::Missing | OptionalBody::File(..) => (),
         OptionalBody::Null => { map.insert("body".to_string(), Value::Null); }
       }
 
@@ -267,6 +268,7 @@ impl Request {
       query: self.query.clone(),
       headers: self.headers.clone(),
       body: self.body.clone(),
+      trailers: None,
       matching_rules: self.matching_rules.clone(),
       generators: self.generators.clone()
     }
@@ -69,7 +69,7 @@ impl Response {
       if self.headers.is_some() {
         map.insert("headers".to_string(), headers_to_json(&self.headers.clone().unwrap()));
       }
-      match self.body {
+      match *self.body.loaded() {
         OptionalBody::Present(ref body, _, _) => {
           if self.content_type().unwrap_or_default().is_json() {
             match serde_json::from_slice(body) {
@@ -87,7 +87,8 @@ impl Response {
           }
         },
         OptionalBody::Empty => { map.insert("body".to_string(), Value::String("".to_string())); },
-        OptionalBody::Missing => (),
+        // File backed bodies are loaded above, so only become Missing if the file can not be read
+        OptionalBody::Missing | OptionalBody::File(..) => (),
         OptionalBody::Null => { map.insert("body".to_string(), Value::Null); }
       }
       if self.matching_rules.is_not_empty() {
@@ -126,6 +127,7 @@ impl Response {
       status: self.status,
       headers: self.headers.clone(),
       body: self.body.clone(),
+      trailers: None,
       matching_rules: self.matching_rules.clone(),
       generators: self.generators.clone()
     }
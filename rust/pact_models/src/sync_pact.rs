@@ -1,13 +1,11 @@
 //! Synchronous HTTP Request/Response Pact
 
-use std::cmp::Ordering;
 use std::collections::{BTreeMap, HashMap};
 use std::panic::RefUnwindSafe;
 use std::path::Path;
 use std::sync::{Arc, Mutex};
 
-use anyhow::{anyhow, Context};
-use itertools::EitherOrBoth::{Both, Left, Right};
+use anyhow::anyhow;
 use itertools::Itertools;
 use maplit::{btreemap, hashset};
 use serde_json::{json, Map, Value};
@@ -15,11 +13,12 @@ use tracing::warn;
 
 use crate::{Consumer, PactSpecification, Provider};
 #[cfg(not(target_family = "wasm"))] use crate::file_utils::with_read_lock;
+#[cfg(not(target_family = "wasm"))] use crate::pact::read_pact_json;
 #[cfg(not(target_family = "wasm"))] use crate::http_utils::{self, HttpAuth};
 use crate::interaction::{Interaction, PactConflict, parse_interactions};
-use crate::iterator_utils::CartesianProductIterator;
 use crate::message_pact::MessagePact;
-use crate::pact::{determine_spec_version, metadata_schema, Pact, parse_meta_data, ReadWritePact, verify_metadata};
+use crate::pact;
+use crate::pact::{determine_spec_version, MergeStrategy, metadata_schema, Pact, parse_meta_data, ReadWritePact, verify_metadata};
 use crate::PACT_RUST_VERSION;
 use crate::plugins::PluginData;
 use crate::sync_interaction::RequestResponseInteraction;
@@ -163,6 +162,19 @@ impl RequestResponsePact {
     determine_spec_version(&"<Pact>".to_string(), &self.metadata)
   }
 
+  /// Removes any interactions that are semantically equal to an earlier interaction in the Pact
+  /// (ignoring the interaction keys and IDs). Returns the number of interactions removed.
+  pub fn deduplicate_interactions(&mut self) -> usize {
+    pact::deduplicate_interactions(&mut self.interactions, pact::interaction_identity)
+  }
+
+  /// Normalizes the Pact, so that equivalent pacts have the same form. Duplicate interactions
+  /// are removed, and the interactions are sorted by description, provider states and type.
+  pub fn normalize(&mut self) {
+    self.deduplicate_interactions();
+    self.interactions.sort_by_cached_key(pact::interaction_key);
+  }
+
   /// Creates a `Pact` from a `Value` struct.
   pub fn from_json(source: &str, pact_json: &Value) -> anyhow::Result<RequestResponsePact> {
     let metadata = parse_meta_data(pact_json);
@@ -275,70 +287,30 @@ impl ReadWritePact for RequestResponsePact {
   #[cfg(not(target_family = "wasm"))]
   fn read_pact(path: &Path) -> anyhow::Result<RequestResponsePact> {
     with_read_lock(path, 3, &mut |f| {
-      let pact_json = serde_json::from_reader(f)
-        .context("Failed to parse Pact JSON")?;
+      let pact_json = read_pact_json(f)?;
       RequestResponsePact::from_json(&format!("{:?}", path), &pact_json)
     })
   }
 
   fn merge(&self, pact: &dyn Pact) -> anyhow::Result<Box<dyn Pact + Send + Sync + RefUnwindSafe>> {
-    if self.consumer.name == pact.consumer().name && self.provider.name == pact.provider().name {
-      let conflicts = CartesianProductIterator::new(&self.interactions, &pact.interactions())
-        .map(|(i1, i2)| i1.conflicts_with(i2.as_ref()))
-        .filter(|conflicts| !conflicts.is_empty())
-        .collect::<Vec<Vec<PactConflict>>>();
-      let num_conflicts = conflicts.len();
-      if num_conflicts > 0 {
-        warn!("The following conflicting interactions where found:");
-        for interaction_conflicts in conflicts {
-          warn!(" Interaction '{}':", interaction_conflicts.first().unwrap().interaction);
-          for conflict in interaction_conflicts {
-            warn!("   {}", conflict.description);
-          }
-        }
-        Err(anyhow!("Unable to merge pacts, as there were {} conflict(s) between the interactions. Please clean out your pact directory before running the tests.",
-                    num_conflicts))
-      } else {
-        let interactions: Vec<Result<RequestResponseInteraction, String>> = self.interactions.iter()
-          .merge_join_by(pact.interactions().iter(), |a, b| {
-            let cmp = Ord::cmp(&a.description, &b.description());
-            if cmp == Ordering::Equal && ! &a.provider_states().is_empty(){
-              Ord::cmp(&a.provider_states.iter().map(|p| p.name.clone()).collect::<Vec<String>>(),
-                       &b.provider_states().iter().map(|p| p.name.clone()).collect::<Vec<String>>())
-            } else {
-              cmp
-            }
-          })
-          .map(|either| match either {
-            Left(i) => Ok(i.clone()),
-            Right(i) => i.as_request_response()
-              .ok_or(format!("Can't convert interaction of type {} to V3 Synchronous/HTTP", i.type_of())),
-            Both(_, i) => i.as_request_response()
-              .ok_or(format!("Can't convert interaction of type {} to V3 Synchronous/HTTP", i.type_of()))
-          })
-          .collect();
-
-        let errors: Vec<String> = interactions.iter()
-          .filter(|i| i.is_err())
-          .map(|i| i.as_ref().unwrap_err().to_string())
-          .collect();
-        if errors.is_empty() {
-          Ok(Box::new(RequestResponsePact {
-            provider: self.provider.clone(),
-            consumer: self.consumer.clone(),
-            interactions: interactions.iter()
-              .filter(|i| i.is_ok())
-              .map(|i| i.as_ref().unwrap().clone()).collect(),
-            metadata: self.metadata.clone(),
-            specification_version: self.specification_version.clone()
-          }))
-        } else {
-          Err(anyhow!("Unable to merge pacts: {}", errors.join(", ")))
+    let other_interactions = pact.interactions();
+    let conflicts = self.interactions.iter()
+      .cartesian_product(other_interactions.iter())
+      .map(|(i1, i2)| i1.conflicts_with(i2.as_ref()))
+      .filter(|conflicts| !conflicts.is_empty())
+      .collect::<Vec<Vec<PactConflict>>>();
+    if !conflicts.is_empty() {
+      warn!("The following conflicting interactions where found:");
+      for interaction_conflicts in &conflicts {
+        warn!(" Interaction '{}':", interaction_conflicts.first().unwrap().interaction);
+        for conflict in interaction_conflicts {
+          warn!("   {}", conflict.description);
         }
       }
-    } else {
-      Err(anyhow!("Unable to merge pacts, as they have different consumers or providers"))
+      return Err(anyhow!("Unable to merge pacts, as there were {} conflict(s) between the interactions. Please clean out your pact directory before running the tests.",
+        conflicts.len()));
     }
+    pact::merge_pacts(self, pact, MergeStrategy::KeepNewest)
   }
 
   fn default_file_name(&self) -> String {
@@ -291,6 +291,7 @@ fn write_pact_file_with_provider_state_generator_test() {
           query: None,
           headers: None,
           body: OptionalBody::Missing,
+          trailers: None,
           matching_rules: MatchingRules {
             rules: hashmap!{
               Category::PATH => MatchingRuleCategory {
@@ -315,6 +316,7 @@ fn write_pact_file_with_provider_state_generator_test() {
           status: 200,
           headers: Some(hashmap!{"Content-Type".to_string() => vec!["text/plain; charset=utf-8".to_string()]}),
           body: OptionalBody::from("data: testData, id: 42"),
+          trailers: None,
           matching_rules: MatchingRules {
             rules: hashmap!{
               Category::HEADER => MatchingRuleCategory {
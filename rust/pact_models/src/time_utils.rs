@@ -34,22 +34,25 @@
 //!   |Z     | zone-offset               | offset-Z     |  +0000; -0800; -08:00; |
 //!   |'     | escape for text           | delimiter    | |
 //!   |''    | single quote              | literal      |  ' |
+//!   |[     | optional section start    |              | |
+//!   |]     | optional section end      |              | |
 
 use std::fmt::{Display, Formatter};
 
 use anyhow::anyhow;
-use chrono::Local;
+use chrono::{DateTime, FixedOffset, Local, NaiveDate, NaiveDateTime, NaiveTime, Offset, TimeZone, Utc};
+use chrono_tz::{OffsetName, Tz};
 use gregorian::Month;
 use itertools::Itertools;
 use nom::branch::alt;
-use nom::bytes::complete::{is_a, is_not, tag, tag_no_case, take_while_m_n};
-use nom::character::complete::{alphanumeric1, char, digit1};
-use nom::combinator::{opt, value};
+use nom::bytes::complete::{is_a, is_not, tag, tag_no_case, take_while1, take_while_m_n};
+use nom::character::complete::{alpha0, char, digit1};
+use nom::combinator::{opt, recognize, value};
 use nom::Err::{Error, Failure};
 use nom::error::{ErrorKind, ParseError};
 use nom::IResult;
-use nom::multi::many1;
-use nom::sequence::{delimited, preceded, separated_pair, terminated, tuple};
+use nom::multi::{many0, many1};
+use nom::sequence::{delimited, preceded, terminated, tuple};
 use tracing::{debug, error, trace, warn};
 
 use crate::timezone_db::*;
@@ -88,7 +91,8 @@ pub enum DateTimePatternToken {
   QuarterOfYear(usize),
   QuarterOfYearNum(usize),
   MillisecondOfDay,
-  NanosecondOfDay
+  NanosecondOfDay,
+  Optional(Vec<DateTimePatternToken>)
 }
 
 #[derive(Debug, PartialEq, Clone)]
@@ -403,15 +407,23 @@ fn timezone_pattern(s: &str) -> IResult<&str, DateTimePatternToken, DateTimePatt
   })
 }
 
+fn optional_pattern(s: &str) -> IResult<&str, DateTimePatternToken, DateTimePatternError<&str>> {
+  delimited(char('['), many1(pattern_token), char(']'))(s)
+    .map(|(remaining, result)| (remaining, DateTimePatternToken::Optional(result)))
+}
+
 fn text_pattern(s: &str) -> IResult<&str, DateTimePatternToken, DateTimePatternError<&str>> {
   is_not("abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789'{}[]")(s).map(|(remaining, result)| {
     (remaining, DateTimePatternToken::Text(result.to_string()))
   })
 }
 
-fn era(s: &str, _count: usize) -> IResult<&str, String, DateTimeError<&str>> {
-  alt((tag_no_case("ad"), tag_no_case("bc")))(s)
-    .map(|(remaining, result)| (remaining, result.into()))
+fn era(s: &str, count: usize) -> IResult<&str, String, DateTimeError<&str>> {
+  match count {
+    4 => alt((tag_no_case("anno domini"), tag_no_case("before christ")))(s),
+    5 => alt((tag_no_case("a"), tag_no_case("b")))(s),
+    _ => alt((tag_no_case("ad"), tag_no_case("bc")))(s)
+  }.map(|(remaining, result)| (remaining, result.into()))
 }
 
 fn ampm(s: &str) -> IResult<&str, String, DateTimeError<&str>> {
@@ -635,17 +647,26 @@ fn timezone(s: &str, d: usize) -> IResult<&str, String, DateTimeError<&str>> {
       }
     })
   } else {
-    Err(Error(DateTimeError::FullTimezonesNotSupported(s.to_string())))
+    // Full timezone names (i.e. Pacific Standard Time) are localised, so accept any sequence of
+    // capitalised words ending in 'Time'
+    recognize(tuple((many0(terminated(timezone_name_word, char(' '))), tag("Time"))))(s)
+      .map(|(remaining, result)| (remaining, result.into()))
   }
 }
 
+fn timezone_name_word(s: &str) -> IResult<&str, &str, DateTimeError<&str>> {
+  recognize(tuple((take_while_m_n(1, 1, is_uppercase), alpha0)))(s)
+}
+
 fn timezone_id(s: &str) -> IResult<&str, String, DateTimeError<&str>> {
-  separated_pair(alphanumeric1, char('/'), alphanumeric1)(s).and_then(|(remaining, result)| {
-    let tz = format!("{}/{}", result.0, result.1);
-    if ZONES.contains(tz.as_str()) {
-      Ok((remaining, tz))
+  if let Ok((remaining, _)) = alt((tag::<&str, &str, DateTimeError<&str>>("Z"), timezone_hour_min))(s) {
+    return Ok((remaining, s[..s.len() - remaining.len()].to_string()));
+  }
+  take_while1(|ch: char| ch.is_alphanumeric() || "/_+-".contains(ch))(s).and_then(|(remaining, result)| {
+    if ZONES.contains(result) {
+      Ok((remaining, result.into()))
     } else {
-      Err(Error(DateTimeError::InvalidTimezone(tz)))
+      Err(Error(DateTimeError::InvalidTimezone(result.to_string())))
     }
   })
 }
@@ -736,31 +757,40 @@ fn quarter(s: &str, count: usize) -> IResult<&str, String, DateTimeError<&str>>
   }
 }
 
+fn pattern_token(s: &str) -> IResult<&str, DateTimePatternToken, DateTimePatternError<&str>> {
+  alt((
+    alt((
+      era_pattern,
+      year_pattern,
+      month_pattern,
+      day_in_year_pattern,
+      day_in_month_pattern,
+      quarter_pattern,
+      week_in_year_month_pattern,
+      day_name_pattern,
+      day_of_week_pattern,
+      ampm_pattern,
+      hour_pattern
+    )),
+    alt((
+      minute_pattern,
+      second_pattern,
+      millisecond_pattern,
+      nanosecond_pattern,
+      millisecond_of_day_pattern,
+      nanosecond_of_day_pattern,
+      quoted_text_pattern,
+      quote_pattern,
+      timezone_pattern,
+      optional_pattern,
+      text_pattern
+    ))
+  ))(s)
+}
+
 /// Parses a string into a vector of date/time tokens
 pub fn parse_pattern(s: &str) -> Result<Vec<DateTimePatternToken>, String> {
-  match many1(alt((
-    era_pattern,
-    year_pattern,
-    month_pattern,
-    day_in_year_pattern,
-    day_in_month_pattern,
-    quarter_pattern,
-    week_in_year_month_pattern,
-    day_name_pattern,
-    day_of_week_pattern,
-    ampm_pattern,
-    hour_pattern,
-    minute_pattern,
-    second_pattern,
-    millisecond_pattern,
-    nanosecond_pattern,
-    millisecond_of_day_pattern,
-    nanosecond_of_day_pattern,
-    quoted_text_pattern,
-    quote_pattern,
-    timezone_pattern,
-    text_pattern
-  )))(s) {
+  match many1(pattern_token)(s) {
     Ok((remaining, result)) => if !remaining.is_empty() {
       let error = format!("Parsing datetime pattern '{}' failed at text '{}'", s, remaining);
       debug!("{}", error);
@@ -777,8 +807,21 @@ pub fn parse_pattern(s: &str) -> Result<Vec<DateTimePatternToken>, String> {
 }
 
 fn validate_datetime_string(value: &str, pattern_tokens: &[DateTimePatternToken]) -> Result<(), String> {
-  let mut buffer = value;
   let mut matched_values = vec![];
+  let buffer = match_pattern_tokens(value, pattern_tokens, &mut matched_values)?;
+  if !buffer.is_empty() {
+    Err(format!("Remaining data after applying pattern {:?}", buffer))
+  } else {
+    validate_matched_values(&matched_values)
+  }
+}
+
+fn match_pattern_tokens<'a>(
+  value: &'a str,
+  pattern_tokens: &[DateTimePatternToken],
+  matched_values: &mut Vec<(DateTimePatternToken, String)>
+) -> Result<&'a str, String> {
+  let mut buffer = value;
   for token in pattern_tokens {
     let result = match token {
       DateTimePatternToken::Era(count) => era(buffer, *count),
@@ -811,6 +854,15 @@ fn validate_datetime_string(value: &str, pattern_tokens: &[DateTimePatternToken]
       DateTimePatternToken::QuarterOfYearNum(count) => quarter_num(buffer, *count),
       DateTimePatternToken::MillisecondOfDay => digit1(buffer).map(|(remaining, result)| (remaining, result.into())),
       DateTimePatternToken::NanosecondOfDay => digit1(buffer).map(|(remaining, result)| (remaining, result.into())),
+      DateTimePatternToken::Optional(tokens) => {
+        // Optional sections are only applied if all the values in the section match
+        let mut optional_values = vec![];
+        if let Ok(remaining) = match_pattern_tokens(buffer, tokens, &mut optional_values) {
+          matched_values.extend(optional_values);
+          buffer = remaining;
+        }
+        continue;
+      }
     }.map_err(|err| format!("{:?}", err))?;
 
     matched_values.push((token.clone(), result.1));
@@ -818,11 +870,7 @@ fn validate_datetime_string(value: &str, pattern_tokens: &[DateTimePatternToken]
     buffer = result.0;
   }
 
-  if !buffer.is_empty() {
-    Err(format!("Remaining data after applying pattern {:?}", buffer))
-  } else {
-    validate_matched_values(&matched_values)
-  }
+  Ok(buffer)
 }
 
 fn validate_matched_values(matched_values: &Vec<(DateTimePatternToken, String)>) -> Result<(), String> {
@@ -885,13 +933,213 @@ pub fn validate_datetime(value: &str, format: &str) -> Result<(), String> {
   }
 }
 
+/// Parses the given datetime using the pattern. If the pattern is empty, the value is parsed as
+/// an ISO 8601 (RFC 3339) timestamp. Values without a timezone are taken to be in the local timezone.
+/// Timezones can be given as offsets (`+10:00`, `Z`, `GMT+10`), timezone IDs (`Australia/Melbourne`)
+/// or abbreviations (`AEST`), and are resolved to the offset in effect at the given date and time.
+pub fn parse_datetime(value: &str, format: &str) -> Result<DateTime<FixedOffset>, String> {
+  if format.is_empty() {
+    return DateTime::parse_from_rfc3339(value)
+      .map_err(|err| format!("'{}' is not a valid ISO 8601 timestamp - {}", value, err));
+  }
+
+  validate_datetime(value, format)?;
+  let pattern_tokens = parse_pattern(format)?;
+  if let Some(index) = pattern_tokens.iter().position(is_timezone_token) {
+    // Split the timezone out of the value, and parse the rest as a local date and time
+    let before = match_pattern_tokens(value, &pattern_tokens[..index], &mut vec![])?;
+    let after = match_pattern_tokens(before, &pattern_tokens[index..=index], &mut vec![])?;
+    let zone = &before[..before.len() - after.len()];
+    let local_value = format!("{}{}", &value[..value.len() - before.len()], after);
+    let local_tokens = pattern_tokens.iter().enumerate()
+      .filter(|(i, _)| *i != index)
+      .map(|(_, token)| token.clone())
+      .collect_vec();
+    let datetime = parse_naive_datetime(&local_value, &local_tokens)
+      .map_err(|err| format!("Unable to parse '{}' using '{}' - {}", value, format, err))?;
+    return resolve_timezone(zone, &datetime)
+      .ok_or_else(|| format!("Unable to parse '{}' using '{}' - '{}' is not a known timezone", value, format, zone));
+  }
+
+  let chrono_pattern = to_chrono_pattern(&pattern_tokens);
+  // Chrono does not accept 'Z' for a zero offset, which the X pattern allows
+  let with_offset = value.strip_suffix('Z').map(|v| format!("{}+00:00", v));
+  match DateTime::parse_from_str(value, chrono_pattern.as_str())
+    .or_else(|err| with_offset.map(|v| DateTime::parse_from_str(&v, chrono_pattern.as_str())).unwrap_or(Err(err))) {
+    Ok(datetime) => Ok(datetime),
+    Err(_) => parse_naive_datetime(value, &pattern_tokens)
+      .map_err(|err| format!("Unable to parse '{}' using '{}' - {}", value, format, err))
+      .and_then(|datetime| Local.from_local_datetime(&datetime).earliest()
+        .map(|datetime| datetime.fixed_offset())
+        .ok_or_else(|| format!("'{}' is not a valid local time", value)))
+  }
+}
+
+fn is_timezone_token(token: &DateTimePatternToken) -> bool {
+  matches!(token, DateTimePatternToken::TimezoneName(_) | DateTimePatternToken::TimezoneId(_) |
+    DateTimePatternToken::TimezoneOffset(_) | DateTimePatternToken::TimezoneOffsetGmt(_) |
+    DateTimePatternToken::TimezoneOffsetX(_) | DateTimePatternToken::TimezoneOffsetXZZero(_))
+}
+
+/// Parses a value without a timezone. Values with only a date are taken to be at midnight.
+fn parse_naive_datetime(value: &str, pattern_tokens: &[DateTimePatternToken]) -> Result<NaiveDateTime, chrono::ParseError> {
+  let chrono_pattern = to_chrono_pattern(pattern_tokens);
+  NaiveDateTime::parse_from_str(value, chrono_pattern.as_str())
+    .or_else(|err| NaiveDate::parse_from_str(value, chrono_pattern.as_str())
+      .map(|date| date.and_time(NaiveTime::MIN))
+      .map_err(|_| err))
+}
+
+/// Resolves the timezone (as an offset, timezone ID or abbreviation) to the offset in effect at the
+/// given local date and time
+fn resolve_timezone(zone: &str, datetime: &NaiveDateTime) -> Option<DateTime<FixedOffset>> {
+  let offset = match zone {
+    "Z" | "GMT" | "UTC" => Some(Utc.fix()),
+    _ => parse_offset(zone.strip_prefix("GMT").or_else(|| zone.strip_prefix("UTC")).unwrap_or(zone))
+  };
+  if let Some(offset) = offset {
+    return offset.from_local_datetime(datetime).earliest();
+  }
+
+  if let Ok(tz) = zone.parse::<Tz>() {
+    return tz.from_local_datetime(datetime).earliest().map(|datetime| datetime.fixed_offset());
+  }
+
+  // Abbreviations are ambiguous, so use the first zone where the abbreviation is in effect
+  ZONES_ABBR.get(zone)
+    .and_then(|zones| zones.iter()
+      .filter_map(|name| name.parse::<Tz>().ok())
+      .filter_map(|tz| tz.from_local_datetime(datetime).earliest())
+      .find(|datetime| datetime.offset().abbreviation() == zone)
+      .map(|datetime| datetime.fixed_offset()))
+}
+
+/// Parses an offset in one of the forms `+HH`, `+H:MM`, `+HHMM`, `+HH:MM`, `+HHMMSS` or `+HH:MM:SS`
+fn parse_offset(offset: &str) -> Option<FixedOffset> {
+  let (sign, digits) = if let Some(digits) = offset.strip_prefix('+') {
+    (1, digits)
+  } else if let Some(digits) = offset.strip_prefix('-') {
+    (-1, digits)
+  } else {
+    return None;
+  };
+  let parts = if digits.contains(':') {
+    digits.split(':').map(|part| part.to_string()).collect_vec()
+  } else if digits.len() <= 2 {
+    vec![digits.to_string()]
+  } else {
+    digits.chars().chunks(2).into_iter().map(|chunk| chunk.collect::<String>()).collect_vec()
+  };
+  if parts.is_empty() || parts.len() > 3 || parts.iter().any(|part| part.is_empty() || part.len() > 2) {
+    return None;
+  }
+  let mut seconds = 0;
+  for (part, multiplier) in parts.iter().zip([3600, 60, 1]) {
+    seconds += part.parse::<i32>().ok()? * multiplier;
+  }
+  FixedOffset::east_opt(sign * seconds)
+}
+
+/// Formats the date and time using the pattern. Timezone and offset tokens are formatted from
+/// the offset of the value. Values with a named timezone (i.e. `chrono_tz::Tz`) will use the
+/// timezone abbreviation for timezone names, otherwise the offset is used (`Z` for a zero offset).
+pub fn format_datetime<Tz: TimeZone>(value: &DateTime<Tz>, format: &str) -> Result<String, String>
+  where Tz::Offset: Display {
+  let pattern_tokens = parse_pattern(format)?;
+  Ok(format_pattern_tokens(value, &pattern_tokens))
+}
+
+fn format_pattern_tokens<Tz: TimeZone>(value: &DateTime<Tz>, pattern_tokens: &[DateTimePatternToken]) -> String
+  where Tz::Offset: Display {
+  let offset = value.offset().fix().local_minus_utc();
+  let mut buffer = String::new();
+  for token in pattern_tokens {
+    match token {
+      DateTimePatternToken::TimezoneName(_) | DateTimePatternToken::TimezoneId(_) => {
+        let name = value.offset().to_string();
+        if name.starts_with(['+', '-']) {
+          buffer.push_str(&format_offset(offset, true, false, true));
+        } else {
+          buffer.push_str(&name);
+        }
+      }
+      DateTimePatternToken::TimezoneOffset(count) => match count {
+        1..=3 => buffer.push_str(&format_offset(offset, false, false, false)),
+        4 => buffer.push_str(&format_gmt_offset(offset, true)),
+        _ => buffer.push_str(&format_offset(offset, true, false, true))
+      },
+      DateTimePatternToken::TimezoneOffsetGmt(count) => buffer.push_str(&format_gmt_offset(offset, *count == 4)),
+      DateTimePatternToken::TimezoneOffsetX(count) => buffer.push_str(&format_x_offset(offset, *count)),
+      DateTimePatternToken::TimezoneOffsetXZZero(count) => if offset == 0 {
+        buffer.push('Z');
+      } else {
+        buffer.push_str(&format_x_offset(offset, *count));
+      },
+      DateTimePatternToken::Optional(tokens) => buffer.push_str(&format_pattern_tokens(value, tokens)),
+      _ => buffer.push_str(&value.format(&to_chrono_pattern(std::slice::from_ref(token))).to_string())
+    }
+  }
+  buffer
+}
+
+/// Formats the offset in seconds as `+HHMM` or `+HH:MM`, adding the seconds if `with_seconds` is
+/// set and they are not zero. If `z_for_zero` is set, `Z` is returned for a zero offset.
+fn format_offset(offset: i32, colon: bool, with_seconds: bool, z_for_zero: bool) -> String {
+  if offset == 0 && z_for_zero {
+    return "Z".to_string();
+  }
+  let sign = if offset < 0 { '-' } else { '+' };
+  let offset = offset.abs();
+  let separator = if colon { ":" } else { "" };
+  let mut result = format!("{}{:02}{}{:02}", sign, offset / 3600, separator, (offset / 60) % 60);
+  if with_seconds && offset % 60 != 0 {
+    result.push_str(&format!("{}{:02}", separator, offset % 60));
+  }
+  result
+}
+
+fn format_x_offset(offset: i32, count: usize) -> String {
+  match count {
+    1 => {
+      let result = format_offset(offset, false, false, false);
+      if offset % 3600 == 0 { result[..3].to_string() } else { result }
+    }
+    2 => format_offset(offset, false, false, false),
+    3 => format_offset(offset, true, false, false),
+    4 => format_offset(offset, false, true, false),
+    _ => format_offset(offset, true, true, false)
+  }
+}
+
+/// Formats the offset as `GMT+H:MM` (short form) or `GMT+HH:MM` (long form), or `GMT` if it is zero
+fn format_gmt_offset(offset: i32, long_form: bool) -> String {
+  if offset == 0 {
+    "GMT".to_string()
+  } else if long_form {
+    format!("GMT{}", format_offset(offset, true, true, false))
+  } else {
+    let sign = if offset < 0 { '-' } else { '+' };
+    let offset = offset.abs();
+    let minutes = (offset / 60) % 60;
+    if minutes == 0 {
+      format!("GMT{}{}", sign, offset / 3600)
+    } else {
+      format!("GMT{}{}:{:02}", sign, offset / 3600, minutes)
+    }
+  }
+}
+
 /// Converts the date time pattern tokens to a chrono formatted string
 pub fn to_chrono_pattern(tokens: &[DateTimePatternToken]) -> String {
   let mut buffer = String::new();
 
   for token in tokens {
     match token {
-      DateTimePatternToken::Era(_count) => buffer.push_str("AD"),
+      DateTimePatternToken::Era(count) => buffer.push_str(match count {
+        4 => "Anno Domini",
+        5 => "A",
+        _ => "AD"
+      }),
       DateTimePatternToken::Year(d) => buffer.push_str(if *d == 2 { "%y" } else { "%Y" }),
       DateTimePatternToken::WeekInYear => buffer.push_str("%U"),
       DateTimePatternToken::WeekInMonth(_) => warn!("Chono does not support week in month"),
@@ -921,6 +1169,7 @@ pub fn to_chrono_pattern(tokens: &[DateTimePatternToken]) -> String {
       DateTimePatternToken::TimezoneOffsetX(_d) => buffer.push_str("%:z"),
       DateTimePatternToken::TimezoneOffsetXZZero(_d) => buffer.push_str("%:z"),
       DateTimePatternToken::AmPm => buffer.push_str("%p"),
+      DateTimePatternToken::Optional(tokens) => buffer.push_str(&to_chrono_pattern(tokens)),
       _ => warn!("Chono does not support {:?}", token)
     };
   }
@@ -934,9 +1183,7 @@ pub fn generate_string(format: &str) -> Result<String, String> {
   match parse_pattern(format) {
     Ok(pattern_tokens) => {
       trace!("parsed date/time patterns: {:?}", pattern_tokens);
-      let chrono_pattern = to_chrono_pattern(&pattern_tokens);
-      trace!("Chrono pattern: {}", chrono_pattern);
-      Ok(Local::now().format(chrono_pattern.as_str()).to_string())
+      Ok(format_pattern_tokens(&Local::now(), &pattern_tokens))
     },
     Err(err) => {
       error!("Error parsing '{}': {:?}", format, err);
@@ -978,6 +1225,18 @@ mod tests {
 
   use super::*;
 
+  #[test]
+  fn parse_datetime_test() {
+    expect!(parse_datetime("2023-05-01T10:11:12+10:00", "").map(|dt| dt.timestamp())).to(be_ok().value(1682899872));
+    expect!(parse_datetime("2023-05-01T10:11:12Z", "yyyy-MM-dd'T'HH:mm:ssXXX").map(|dt| dt.timestamp()))
+      .to(be_ok().value(1682935872));
+    expect!(parse_datetime("2023-05-01 10:11:12 +0000", "yyyy-MM-dd HH:mm:ss Z").map(|dt| dt.timestamp()))
+      .to(be_ok().value(1682935872));
+    expect!(parse_datetime("2023-05-01 10:11:12", "yyyy-MM-dd HH:mm:ss")).to(be_ok());
+    expect!(parse_datetime("2023-05-01", "")).to(be_err());
+    expect!(parse_datetime("2023-13-01 10:11:12", "yyyy-MM-dd HH:mm:ss")).to(be_err());
+  }
+
   #[test]
   fn parse_date_and_time() {
     expect!(validate_datetime("2001-01-02", "yyyy-MM-dd")).to(be_ok());
@@ -989,7 +1248,7 @@ mod tests {
     expect!(validate_datetime("2001.07.04 AD at 12:08:56 PDT", "yyyy.MM.dd G 'at' HH:mm:ss z")).to(be_ok());
     expect!(validate_datetime("Wed, Jul 4, '01", "EEE, MMM d, ''yy")).to(be_ok());
     expect!(validate_datetime("12:08 PM", "h:mm a")).to(be_ok());
-//    "hh 'o''clock' a, zzzz"	12 o'clock PM, Pacific Daylight Time
+    expect!(validate_datetime("12 o'clock PM, Pacific Daylight Time", "hh 'o''clock' a, zzzz")).to(be_ok());
     expect!(validate_datetime("0:08 PM, AEST", "K:mm a, z")).to(be_ok());
     expect!(validate_datetime("02001.July.04 AD 12:08 PM", "yyyyy.MMMMM.dd G hh:mm a")).to(be_ok());
     expect!(validate_datetime("Wed, 4 Jul 2001 12:08:56 -0700", "EEE, d MMM yyyy HH:mm:ss Z")).to(be_ok());
@@ -1040,6 +1299,12 @@ mod tests {
     expect!(validate_datetime("bc", "GGG")).to(be_ok());
     expect!(validate_datetime("BC", "G")).to(be_ok());
     expect!(validate_datetime("BX", "G")).to(be_err());
+    expect!(validate_datetime("Anno Domini", "GGGG")).to(be_ok());
+    expect!(validate_datetime("before christ", "GGGG")).to(be_ok());
+    expect!(validate_datetime("AD", "GGGG")).to(be_err());
+    expect!(validate_datetime("A", "GGGGG")).to(be_ok());
+    expect!(validate_datetime("B", "GGGGG")).to(be_ok());
+    expect!(validate_datetime("X", "GGGGG")).to(be_err());
   }
 
   #[test]
@@ -1286,6 +1551,38 @@ mod tests {
     // expect!(validate_datetime("UTC", "z")).to(be_ok());
     // expect!(validate_datetime("aest", "z")).to(be_err());
     // expect!(validate_datetime("AEST", "zzzz")).to(be_err());
+
+    expect!(validate_datetime("Pacific Standard Time", "zzzz")).to(be_ok());
+    expect!(validate_datetime("Coordinated Universal Time", "zzzz")).to(be_ok());
+    expect!(validate_datetime("AEST", "zzzz")).to(be_err());
+
+    expect!(validate_datetime("America/Los_Angeles", "VV")).to(be_ok());
+    expect!(validate_datetime("America/Argentina/Buenos_Aires", "VV")).to(be_ok());
+    expect!(validate_datetime("Z", "VV")).to(be_ok());
+    expect!(validate_datetime("-08:30", "VV")).to(be_ok());
+    expect!(validate_datetime("Moon/Tranquility_Base", "VV")).to(be_err());
+  }
+
+  #[test]
+  fn parse_optional_sections() {
+    expect!(parse_pattern("HH[:mm]")).to(
+      be_ok().value(vec![
+        DateTimePatternToken::Hour24ZeroBased,
+        DateTimePatternToken::Optional(vec![DateTimePatternToken::Text(":".into()), DateTimePatternToken::Minute])
+      ]));
+    expect!(parse_pattern("HH[:mm")).to(be_err());
+
+    let format = "yyyy-MM-dd['T'HH:mm[:ss[.SSS]]][XXX]";
+    expect!(validate_datetime("2001-07-04", format)).to(be_ok());
+    expect!(validate_datetime("2001-07-04T12:08", format)).to(be_ok());
+    expect!(validate_datetime("2001-07-04T12:08:56", format)).to(be_ok());
+    expect!(validate_datetime("2001-07-04T12:08:56.235", format)).to(be_ok());
+    expect!(validate_datetime("2001-07-04T12:08:56.235+10:00", format)).to(be_ok());
+    expect!(validate_datetime("2001-07-04Z", format)).to(be_ok());
+    expect!(validate_datetime("2001-07-04T12", format)).to(be_err());
+    expect!(validate_datetime("2001-02-30T12:08", format)).to(be_err());
+
+    expect!(to_chrono_pattern(&parse_pattern(format).unwrap())).to(be_equal_to("%Y-%m-%dT%H:%M:%S.%3f%:z"));
   }
 
   #[test]
@@ -1333,4 +1630,45 @@ mod tests {
     expect!(validate_tz_abbreviation("AEDT")).to(be_true());
     expect!(validate_tz_abbreviation("XXX")).to(be_false());
   }
+
+  #[test]
+  fn format_datetime_test() {
+    let datetime = DateTime::parse_from_rfc3339("2023-05-01T10:11:12.345+10:00").unwrap();
+    let utc = DateTime::parse_from_rfc3339("2023-05-01T10:11:12Z").unwrap();
+    let half_hour = DateTime::parse_from_rfc3339("2023-05-01T10:11:12-09:30").unwrap();
+
+    expect!(format_datetime(&datetime, "yyyy-MM-dd'T'HH:mm:ss.SSS")).to(be_ok().value("2023-05-01T10:11:12.345"));
+    expect!(format_datetime(&datetime, "yyyy-MM-dd'T'HH:mm:ssXXX")).to(be_ok().value("2023-05-01T10:11:12+10:00"));
+    expect!(format_datetime(&utc, "yyyy-MM-dd'T'HH:mm:ssXXX")).to(be_ok().value("2023-05-01T10:11:12Z"));
+    expect!(format_datetime(&utc, "yyyy-MM-dd'T'HH:mm:ssxxx")).to(be_ok().value("2023-05-01T10:11:12+00:00"));
+    expect!(format_datetime(&datetime, "HH:mm Z")).to(be_ok().value("10:11 +1000"));
+    expect!(format_datetime(&datetime, "HH:mm ZZZZ")).to(be_ok().value("10:11 GMT+10:00"));
+    expect!(format_datetime(&utc, "HH:mm ZZZZZ")).to(be_ok().value("10:11 Z"));
+    expect!(format_datetime(&datetime, "HH:mm X")).to(be_ok().value("10:11 +10"));
+    expect!(format_datetime(&half_hour, "HH:mm X")).to(be_ok().value("10:11 -0930"));
+    expect!(format_datetime(&datetime, "HH:mm O")).to(be_ok().value("10:11 GMT+10"));
+    expect!(format_datetime(&half_hour, "HH:mm O")).to(be_ok().value("10:11 GMT-9:30"));
+    expect!(format_datetime(&datetime, "HH:mm OOOO")).to(be_ok().value("10:11 GMT+10:00"));
+    expect!(format_datetime(&datetime, "HH:mm z")).to(be_ok().value("10:11 +10:00"));
+    expect!(format_datetime(&datetime, "HH:mm[ VV]")).to(be_ok().value("10:11 +10:00"));
+
+    let melbourne = datetime.with_timezone(&chrono_tz::Australia::Melbourne);
+    expect!(format_datetime(&melbourne, "yyyy-MM-dd HH:mm z")).to(be_ok().value("2023-05-01 10:11 AEST"));
+    expect!(format_datetime(&datetime, "yyyy-MM-dd HH:mm ppp")).to(be_err());
+  }
+
+  #[test]
+  fn parse_datetime_with_timezones() {
+    let expected = DateTime::parse_from_rfc3339("2023-05-01T10:11:12+10:00").unwrap();
+    expect!(parse_datetime("2023-05-01 10:11:12 +1000", "yyyy-MM-dd HH:mm:ss Z")).to(be_ok().value(expected));
+    expect!(parse_datetime("2023-05-01 10:11:12 GMT+10:00", "yyyy-MM-dd HH:mm:ss ZZZZ")).to(be_ok().value(expected));
+    expect!(parse_datetime("2023-05-01 10:11:12 GMT+10", "yyyy-MM-dd HH:mm:ss O")).to(be_ok().value(expected));
+    expect!(parse_datetime("2023-05-01 10:11:12 +10", "yyyy-MM-dd HH:mm:ss X")).to(be_ok().value(expected));
+    expect!(parse_datetime("2023-05-01 10:11:12 Australia/Melbourne", "yyyy-MM-dd HH:mm:ss VV")).to(be_ok().value(expected));
+    expect!(parse_datetime("2023-05-01 10:11:12 AEST", "yyyy-MM-dd HH:mm:ss z")).to(be_ok().value(expected));
+    expect!(parse_datetime("Australia/Melbourne 2023-05-01 10:11:12", "VV yyyy-MM-dd HH:mm:ss")).to(be_ok().value(expected));
+    expect!(parse_datetime("2023-05-01 Z", "yyyy-MM-dd X").map(|dt| dt.to_rfc3339())).to(be_ok().value("2023-05-01T00:00:00+00:00"));
+    expect!(parse_datetime("2023-01-01 10:11:12 Australia/Melbourne", "yyyy-MM-dd HH:mm:ss VV").map(|dt| dt.to_rfc3339()))
+      .to(be_ok().value("2023-01-01T10:11:12+11:00"));
+  }
 }
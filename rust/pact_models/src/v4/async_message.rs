@@ -18,14 +18,14 @@ use crate::content_types::ContentType;
 use crate::generators::{Generators, generators_from_json, generators_to_json};
 use crate::http_parts::HttpPart;
 use crate::interaction::Interaction;
-use crate::json_utils::{hash_json, is_empty, json_to_string};
+use crate::json_utils::{is_empty, json_to_string};
 use crate::matchingrules::{matchers_from_json, matchers_to_json, MatchingRules};
 use crate::message::Message;
 use crate::PactSpecification;
 use crate::provider_states::ProviderState;
 use crate::sync_interaction::RequestResponseInteraction;
 use crate::v4::http_parts::body_from_json;
-use crate::v4::interaction::{InteractionMarkup, parse_plugin_config, V4Interaction};
+use crate::v4::interaction::{InteractionMarkup, parse_plugin_config, V4Interaction, validate_interaction_key};
 use crate::v4::message_parts::{MessageContents, metadata_to_headers};
 use crate::v4::sync_message::SynchronousMessage;
 use crate::v4::synch_http::SynchronousHttp;
@@ -225,6 +225,16 @@ impl V4Interaction for AsynchronousMessage {
     self.key.clone()
   }
 
+  fn set_key(&mut self, key: Option<String>) -> anyhow::Result<()> {
+    validate_interaction_key(&key)?;
+    self.key = key;
+    Ok(())
+  }
+
+  fn set_pending(&mut self, pending: bool) {
+    self.pending = pending;
+  }
+
   fn boxed_v4(&self) -> Box<dyn V4Interaction + Send + Sync + RefUnwindSafe> {
     Box::new(self.clone())
   }
@@ -275,6 +285,10 @@ impl V4Interaction for AsynchronousMessage {
       Some(key) => key.clone()
     }
   }
+
+  fn calculate_key(&self) -> String {
+    self.calc_hash()
+  }
 }
 
 impl Interaction for AsynchronousMessage {
@@ -446,13 +460,7 @@ impl Hash for AsynchronousMessage {
   fn hash<H: Hasher>(&self, state: &mut H) {
     self.description.hash(state);
     self.provider_states.hash(state);
-    self.contents.contents.hash(state);
-    for (k, v) in &self.contents.metadata {
-      k.hash(state);
-      hash_json(v, state);
-    }
-    self.contents.matching_rules.hash(state);
-    self.contents.generators.hash(state);
+    self.contents.hash(state);
     self.pending.hash(state);
   }
 }
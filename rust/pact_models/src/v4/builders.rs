@@ -0,0 +1,491 @@
+//! Fluent builders for V4 HTTP requests and responses, and V4 interactions. These are a
+//! lightweight alternative to the consumer DSL for tools that need to construct interactions.
+
+use bytes::Bytes;
+use serde_json::Value;
+
+use crate::bodies::OptionalBody;
+use crate::content_types::{ContentType, JSON};
+use crate::generators::{Generator, GeneratorCategory};
+use crate::http_parts::HttpPart;
+use crate::matchingrules::{Category, MatchingRule, RuleLogic};
+use crate::path_exp::DocPath;
+use crate::provider_states::ProviderState;
+use crate::v4::async_message::AsynchronousMessage;
+use crate::v4::http_parts::{HttpRequest, HttpResponse};
+use crate::v4::message_parts::MessageContents;
+use crate::v4::sync_message::SynchronousMessage;
+use crate::v4::synch_http::SynchronousHttp;
+
+/// Builder for a V4 HTTP request
+#[derive(Debug, Clone, Default)]
+pub struct HttpRequestBuilder {
+  request: HttpRequest
+}
+
+impl HttpRequestBuilder {
+  /// Creates a builder for a request with the given method and path
+  pub fn new<M: Into<String>, P: Into<String>>(method: M, path: P) -> Self {
+    HttpRequestBuilder {
+      request: HttpRequest {
+        method: method.into().to_uppercase(),
+        path: path.into(),
+        .. HttpRequest::default()
+      }
+    }
+  }
+
+  /// Sets the request method
+  pub fn method<M: Into<String>>(mut self, method: M) -> Self {
+    self.request.method = method.into().to_uppercase();
+    self
+  }
+
+  /// Sets the request path
+  pub fn path<P: Into<String>>(mut self, path: P) -> Self {
+    self.request.path = path.into();
+    self
+  }
+
+  /// Adds a query parameter value. Repeated calls with the same name add additional values.
+  pub fn query_param<N: Into<String>, V: Into<String>>(mut self, name: N, value: V) -> Self {
+    self.request.query.get_or_insert_with(Default::default)
+      .entry(name.into())
+      .or_default()
+      .push(Some(value.into()));
+    self
+  }
+
+  /// Adds a header value. Repeated calls with the same name (ignoring case) add additional values.
+  pub fn header<N: Into<String>, V: Into<String>>(mut self, name: N, value: V) -> Self {
+    append_header(&mut self.request, name.into(), value.into());
+    self
+  }
+
+  /// Sets the request body
+  pub fn body(mut self, body: OptionalBody) -> Self {
+    self.request.body = body;
+    self
+  }
+
+  /// Sets the request body with the given content type. The `Content-Type` header will be set if
+  /// it has not already been set.
+  pub fn body_with_content_type<B: Into<Bytes>>(mut self, body: B, content_type: ContentType) -> Self {
+    set_body(&mut self.request, body.into(), content_type);
+    self
+  }
+
+  /// Sets the request body to the JSON document. The `Content-Type` header will be set if it has
+  /// not already been set.
+  pub fn json_body(mut self, body: &Value) -> Self {
+    set_body(&mut self.request, body.to_string().into(), JSON.clone());
+    self
+  }
+
+  /// Adds a matching rule for the given category and path. Paths are only required for the body,
+  /// header, query and metadata categories, otherwise use `DocPath::empty()`.
+  pub fn matching_rule<C: Into<Category>>(mut self, category: C, path: DocPath, rule: MatchingRule) -> Self {
+    add_matching_rule(&mut self.request, category.into(), path, rule);
+    self
+  }
+
+  /// Adds a generator for the given category and path. Paths are only required for the body,
+  /// header, query and metadata categories, otherwise use `DocPath::empty()`.
+  pub fn generator(mut self, category: GeneratorCategory, path: DocPath, generator: Generator) -> Self {
+    self.request.generators.add_generator_with_subcategory(&category, path, generator);
+    self
+  }
+
+  /// Returns the built request
+  pub fn build(self) -> HttpRequest {
+    self.request
+  }
+}
+
+/// Builder for a V4 HTTP response
+#[derive(Debug, Clone, Default)]
+pub struct HttpResponseBuilder {
+  response: HttpResponse
+}
+
+impl HttpResponseBuilder {
+  /// Creates a builder for a response with the given status
+  pub fn new(status: u16) -> Self {
+    HttpResponseBuilder {
+      response: HttpResponse {
+        status,
+        .. HttpResponse::default()
+      }
+    }
+  }
+
+  /// Sets the response status
+  pub fn status(mut self, status: u16) -> Self {
+    self.response.status = status;
+    self
+  }
+
+  /// Adds a header value. Repeated calls with the same name (ignoring case) add additional values.
+  pub fn header<N: Into<String>, V: Into<String>>(mut self, name: N, value: V) -> Self {
+    append_header(&mut self.response, name.into(), value.into());
+    self
+  }
+
+  /// Sets the response body
+  pub fn body(mut self, body: OptionalBody) -> Self {
+    self.response.body = body;
+    self
+  }
+
+  /// Sets the response body with the given content type. The `Content-Type` header will be set if
+  /// it has not already been set.
+  pub fn body_with_content_type<B: Into<Bytes>>(mut self, body: B, content_type: ContentType) -> Self {
+    set_body(&mut self.response, body.into(), content_type);
+    self
+  }
+
+  /// Sets the response body to the JSON document. The `Content-Type` header will be set if it has
+  /// not already been set.
+  pub fn json_body(mut self, body: &Value) -> Self {
+    set_body(&mut self.response, body.to_string().into(), JSON.clone());
+    self
+  }
+
+  /// Adds a matching rule for the given category and path. Paths are only required for the body
+  /// and header categories, otherwise use `DocPath::empty()`.
+  pub fn matching_rule<C: Into<Category>>(mut self, category: C, path: DocPath, rule: MatchingRule) -> Self {
+    add_matching_rule(&mut self.response, category.into(), path, rule);
+    self
+  }
+
+  /// Adds a generator for the given category and path. Paths are only required for the body
+  /// and header categories, otherwise use `DocPath::empty()`.
+  pub fn generator(mut self, category: GeneratorCategory, path: DocPath, generator: Generator) -> Self {
+    self.response.generators.add_generator_with_subcategory(&category, path, generator);
+    self
+  }
+
+  /// Returns the built response
+  pub fn build(self) -> HttpResponse {
+    self.response
+  }
+}
+
+/// Builder for the contents of a V4 message
+#[derive(Debug, Clone, Default)]
+pub struct MessageContentsBuilder {
+  contents: MessageContents
+}
+
+impl MessageContentsBuilder {
+  /// Creates a builder for empty message contents
+  pub fn new() -> Self {
+    MessageContentsBuilder::default()
+  }
+
+  /// Sets the contents of the message
+  pub fn contents(mut self, contents: OptionalBody) -> Self {
+    self.contents.contents = contents;
+    self
+  }
+
+  /// Sets the contents of the message with the given content type. The content type will also be
+  /// added to the metadata if it has not already been set.
+  pub fn contents_with_content_type<B: Into<Bytes>>(mut self, contents: B, content_type: ContentType) -> Self {
+    self.contents.metadata.entry("contentType".to_string())
+      .or_insert_with(|| Value::String(content_type.to_string()));
+    self.contents.contents = OptionalBody::Present(contents.into(), Some(content_type), None);
+    self
+  }
+
+  /// Sets the contents of the message to the JSON document
+  pub fn json_contents(self, contents: &Value) -> Self {
+    self.contents_with_content_type(contents.to_string(), JSON.clone())
+  }
+
+  /// Adds a metadata value to the message
+  pub fn metadata<K: Into<String>>(mut self, key: K, value: Value) -> Self {
+    self.contents.metadata.insert(key.into(), value);
+    self
+  }
+
+  /// Adds a matching rule for the given category (`body` or `metadata`) and path
+  pub fn matching_rule<C: Into<Category>>(mut self, category: C, path: DocPath, rule: MatchingRule) -> Self {
+    self.contents.matching_rules.add_category(category.into()).add_rule(path, rule, RuleLogic::And);
+    self
+  }
+
+  /// Adds a generator for the given category (`body` or `metadata`) and path
+  pub fn generator(mut self, category: GeneratorCategory, path: DocPath, generator: Generator) -> Self {
+    self.contents.generators.add_generator_with_subcategory(&category, path, generator);
+    self
+  }
+
+  /// Returns the built message contents
+  pub fn build(self) -> MessageContents {
+    self.contents
+  }
+}
+
+/// Builder for a V4 synchronous HTTP interaction
+#[derive(Debug, Clone, Default)]
+pub struct SynchronousHttpBuilder {
+  interaction: SynchronousHttp
+}
+
+impl SynchronousHttpBuilder {
+  /// Creates a builder for an interaction with the given description
+  pub fn new<S: Into<String>>(description: S) -> Self {
+    SynchronousHttpBuilder {
+      interaction: SynchronousHttp {
+        description: description.into(),
+        .. SynchronousHttp::default()
+      }
+    }
+  }
+
+  /// Adds a provider state to the interaction
+  pub fn given(mut self, provider_state: ProviderState) -> Self {
+    self.interaction.provider_states.push(provider_state);
+    self
+  }
+
+  /// Sets the request of the interaction
+  pub fn request(mut self, request: HttpRequest) -> Self {
+    self.interaction.request = request;
+    self
+  }
+
+  /// Sets the response of the interaction
+  pub fn response(mut self, response: HttpResponse) -> Self {
+    self.interaction.response = response;
+    self
+  }
+
+  /// Marks the interaction as pending
+  pub fn pending(mut self, pending: bool) -> Self {
+    self.interaction.pending = pending;
+    self
+  }
+
+  /// Returns the built interaction
+  pub fn build(self) -> SynchronousHttp {
+    self.interaction
+  }
+}
+
+/// Builder for a V4 asynchronous message interaction
+#[derive(Debug, Clone, Default)]
+pub struct AsynchronousMessageBuilder {
+  message: AsynchronousMessage
+}
+
+impl AsynchronousMessageBuilder {
+  /// Creates a builder for a message with the given description
+  pub fn new<S: Into<String>>(description: S) -> Self {
+    AsynchronousMessageBuilder {
+      message: AsynchronousMessage {
+        description: description.into(),
+        .. AsynchronousMessage::default()
+      }
+    }
+  }
+
+  /// Adds a provider state to the message
+  pub fn given(mut self, provider_state: ProviderState) -> Self {
+    self.message.provider_states.push(provider_state);
+    self
+  }
+
+  /// Sets the contents of the message
+  pub fn contents(mut self, contents: MessageContents) -> Self {
+    self.message.contents = contents;
+    self
+  }
+
+  /// Sets the transport the message is sent with
+  pub fn transport<S: Into<String>>(mut self, transport: S) -> Self {
+    self.message.transport = Some(transport.into());
+    self
+  }
+
+  /// Marks the message as pending
+  pub fn pending(mut self, pending: bool) -> Self {
+    self.message.pending = pending;
+    self
+  }
+
+  /// Returns the built message
+  pub fn build(self) -> AsynchronousMessage {
+    self.message
+  }
+}
+
+/// Builder for a V4 synchronous message interaction
+#[derive(Debug, Clone, Default)]
+pub struct SynchronousMessageBuilder {
+  message: SynchronousMessage
+}
+
+impl SynchronousMessageBuilder {
+  /// Creates a builder for a message with the given description
+  pub fn new<S: Into<String>>(description: S) -> Self {
+    SynchronousMessageBuilder {
+      message: SynchronousMessage {
+        description: description.into(),
+        .. SynchronousMessage::default()
+      }
+    }
+  }
+
+  /// Adds a provider state to the message
+  pub fn given(mut self, provider_state: ProviderState) -> Self {
+    self.message.provider_states.push(provider_state);
+    self
+  }
+
+  /// Sets the request message
+  pub fn request(mut self, request: MessageContents) -> Self {
+    self.message.request = request;
+    self
+  }
+
+  /// Adds a response message. Can be called multiple times for multiple response messages.
+  pub fn response(mut self, response: MessageContents) -> Self {
+    self.message.response.push(response);
+    self
+  }
+
+  /// Sets the transport the message is sent with
+  pub fn transport<S: Into<String>>(mut self, transport: S) -> Self {
+    self.message.transport = Some(transport.into());
+    self
+  }
+
+  /// Marks the message as pending
+  pub fn pending(mut self, pending: bool) -> Self {
+    self.message.pending = pending;
+    self
+  }
+
+  /// Returns the built message
+  pub fn build(self) -> SynchronousMessage {
+    self.message
+  }
+}
+
+fn append_header<P: HttpPart>(part: &mut P, name: String, value: String) {
+  let headers = part.headers_mut();
+  let key = headers.keys()
+    .find(|key| key.eq_ignore_ascii_case(&name))
+    .cloned()
+    .unwrap_or(name);
+  headers.entry(key).or_default().push(value);
+}
+
+fn set_body<P: HttpPart>(part: &mut P, body: Bytes, content_type: ContentType) {
+  if !part.has_header("Content-Type") {
+    append_header(part, "Content-Type".to_string(), content_type.to_string());
+  }
+  *part.body_mut() = OptionalBody::Present(body, Some(content_type), None);
+}
+
+fn add_matching_rule<P: HttpPart>(part: &mut P, category: Category, path: DocPath, rule: MatchingRule) {
+  part.matching_rules_mut().add_category(category).add_rule(path, rule, RuleLogic::And);
+}
+
+#[cfg(test)]
+mod tests {
+  use expectest::prelude::*;
+  use maplit::hashmap;
+  use serde_json::json;
+
+  use crate::bodies::OptionalBody;
+  use crate::content_types::JSON;
+  use crate::generators::{Generator, GeneratorCategory};
+  use crate::matchingrules::MatchingRule;
+  use crate::matchingrules_list;
+  use crate::path_exp::DocPath;
+  use crate::provider_states::ProviderState;
+
+  use super::*;
+
+  #[test]
+  fn http_request_builder_test() {
+    let request = HttpRequestBuilder::new("post", "/items")
+      .query_param("page", "1")
+      .query_param("page", "2")
+      .header("X-Test", "a")
+      .header("x-test", "b")
+      .json_body(&json!({ "id": 100 }))
+      .matching_rule("body", DocPath::new_unwrap("$.id"), MatchingRule::Integer)
+      .generator(GeneratorCategory::BODY, DocPath::new_unwrap("$.id"), Generator::RandomInt(1, 10))
+      .build();
+
+    expect!(request.method.as_str()).to(be_equal_to("POST"));
+    expect!(request.path.as_str()).to(be_equal_to("/items"));
+    expect!(request.query).to(be_some().value(hashmap!{
+      "page".to_string() => vec![Some("1".to_string()), Some("2".to_string())]
+    }));
+    expect!(request.headers).to(be_some().value(hashmap!{
+      "X-Test".to_string() => vec!["a".to_string(), "b".to_string()],
+      "Content-Type".to_string() => vec!["application/json".to_string()]
+    }));
+    expect!(request.body).to(be_equal_to(OptionalBody::Present("{\"id\":100}".into(), Some(JSON.clone()), None)));
+    expect!(request.matching_rules.rules_for_category("body")).to(be_some().value(matchingrules_list! {
+      "body"; "$.id" => [ MatchingRule::Integer ]
+    }));
+    expect!(request.generators.categories.get(&GeneratorCategory::BODY)
+      .and_then(|category| category.get(&DocPath::new_unwrap("$.id"))))
+      .to(be_some().value(&Generator::RandomInt(1, 10)));
+  }
+
+  #[test]
+  fn http_response_builder_test() {
+    let response = HttpResponseBuilder::new(201)
+      .header("Content-Type", "text/plain")
+      .body_with_content_type("Created", ContentType::parse("text/plain").unwrap())
+      .build();
+
+    expect!(response.status).to(be_equal_to(201));
+    expect!(response.headers).to(be_some().value(hashmap!{
+      "Content-Type".to_string() => vec!["text/plain".to_string()]
+    }));
+    expect!(response.body.value_as_string()).to(be_some().value("Created"));
+  }
+
+  #[test]
+  fn interaction_builders_test() {
+    let http = SynchronousHttpBuilder::new("create an item")
+      .given(ProviderState::default("no items"))
+      .request(HttpRequestBuilder::new("POST", "/items").build())
+      .response(HttpResponseBuilder::new(201).build())
+      .build();
+    expect!(http.description.as_str()).to(be_equal_to("create an item"));
+    expect!(http.provider_states.len()).to(be_equal_to(1));
+    expect!(http.request.method.as_str()).to(be_equal_to("POST"));
+    expect!(http.response.status).to(be_equal_to(201));
+
+    let message = AsynchronousMessageBuilder::new("item created")
+      .contents(MessageContentsBuilder::new()
+        .json_contents(&json!({ "id": 100 }))
+        .metadata("topic", json!("items"))
+        .build())
+      .pending(true)
+      .build();
+    expect!(message.contents.contents.value_as_string()).to(be_some().value("{\"id\":100}"));
+    expect!(message.contents.metadata).to(be_equal_to(hashmap!{
+      "contentType".to_string() => json!("application/json"),
+      "topic".to_string() => json!("items")
+    }));
+    expect!(message.pending).to(be_true());
+
+    let sync_message = SynchronousMessageBuilder::new("get item")
+      .request(MessageContentsBuilder::new().json_contents(&json!({ "id": 100 })).build())
+      .response(MessageContentsBuilder::new().json_contents(&json!({ "id": 100, "name": "item" })).build())
+      .transport("grpc")
+      .build();
+    expect!(sync_message.response.len()).to(be_equal_to(1));
+    expect!(sync_message.transport).to(be_some().value("grpc"));
+  }
+}
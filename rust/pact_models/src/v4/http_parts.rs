@@ -17,8 +17,9 @@ use tracing::{debug, warn};
 use crate::bodies::OptionalBody;
 use crate::content_types::{ContentType, ContentTypeHint, detect_content_type_from_bytes};
 use crate::generators::{Generators, generators_from_json, generators_to_json};
+use crate::headers::find_header;
 use crate::http_parts::HttpPart;
-use crate::json_utils::{headers_from_json, json_to_string};
+use crate::json_utils::{headers_from_json, json_to_string, trailers_from_json};
 use crate::matchingrules::{matchers_from_json, matchers_to_json, MatchingRules};
 use crate::PactSpecification;
 use crate::query_strings::{query_to_json, v3_query_from_json};
@@ -39,6 +40,8 @@ pub struct HttpRequest {
   pub headers: Option<HashMap<String, Vec<String>>>,
   /// Request body
   pub body: OptionalBody,
+  /// Request trailers (headers sent after the body)
+  pub trailers: Option<HashMap<String, Vec<String>>>,
   /// Request matching rules
   pub matching_rules: MatchingRules,
   /// Request generators
@@ -73,6 +76,7 @@ impl HttpRequest {
       query: query_val,
       headers: headers.clone(),
       body: body_from_json(request_json, "body", &headers),
+      trailers: trailers_from_json(request_json),
       matching_rules: matchers_from_json(request_json, &None)?,
       generators: generators_from_json(request_json)?,
     })
@@ -105,6 +109,15 @@ impl HttpRequest {
         map.insert("body".to_string(), Value::Object(body));
       }
 
+      if let Some(ref trailers) = self.trailers {
+        map.insert("trailers".to_string(), Value::Object(
+          trailers.iter()
+            .sorted_by(|(a, _), (b, _)| Ord::cmp(a, b))
+            .map(|(k, v)| (k.clone(), json!(v)))
+            .collect()
+        ));
+      }
+
       if self.matching_rules.is_not_empty() {
         map.insert("matchingRules".to_string(), matchers_to_json(
           &self.matching_rules.clone(), &PactSpecification::V4));
@@ -176,10 +189,8 @@ impl HttpRequest {
 
   /// Case-insensitive search for a header name
   fn lookup_header_key<H: Into<String>>(&self, header_name: H) -> Option<String> {
-    let name = header_name.into().to_lowercase();
     match self.headers {
-      Some(ref h) => h.iter()
-        .find(|(k, _v)| k.to_lowercase() == name)
+      Some(ref h) => find_header(h, header_name.into().as_str())
         .map(|(k, _v)| k.clone()),
       None => None
     }
@@ -198,6 +209,7 @@ impl PartialEq for HttpRequest {
       self.query == other.query &&
       self.headers == other.headers &&
       self.body == other.body &&
+      self.trailers == other.trailers &&
       self.matching_rules == other.matching_rules &&
       self.generators == other.generators
   }
@@ -223,6 +235,14 @@ impl Hash for HttpRequest {
     }
 
     self.body.hash(state);
+
+    if let Some(ref trailers) = self.trailers {
+      for (k, v) in trailers.iter().sorted_by(|(a, _), (b, _)| Ord::cmp(a, b)) {
+        k.to_lowercase().hash(state);
+        v.hash(state);
+      }
+    }
+
     self.matching_rules.hash(state);
     self.generators.hash(state);
   }
@@ -429,6 +449,7 @@ impl Default for HttpRequest {
       query: None,
       headers: None,
       body: OptionalBody::Missing,
+      trailers: None,
       matching_rules: MatchingRules::default(),
       generators: Generators::default()
     }
@@ -444,6 +465,8 @@ pub struct HttpResponse {
   pub headers: Option<HashMap<String, Vec<String>>>,
   /// Response body
   pub body: OptionalBody,
+  /// Response trailers (headers sent after the body)
+  pub trailers: Option<HashMap<String, Vec<String>>>,
   /// Response matching rules
   pub matching_rules: MatchingRules,
   /// Response generators
@@ -463,6 +486,7 @@ impl Default for HttpResponse {
       status: 200,
       headers: None,
       body: OptionalBody::Missing,
+      trailers: None,
       matching_rules: MatchingRules::default(),
       generators: Generators::default()
     }
@@ -474,6 +498,7 @@ impl PartialEq for HttpResponse {
     self.status == other.status &&
       self.headers == other.headers &&
       self.body == other.body &&
+      self.trailers == other.trailers &&
       self.matching_rules == other.matching_rules &&
       self.generators == other.generators
   }
@@ -491,6 +516,14 @@ impl Hash for HttpResponse {
     }
 
     self.body.hash(state);
+
+    if let Some(ref trailers) = self.trailers {
+      for (k, v) in trailers.iter().sorted_by(|(a, _), (b, _)| Ord::cmp(a, b)) {
+        k.to_lowercase().hash(state);
+        v.hash(state);
+      }
+    }
+
     self.matching_rules.hash(state);
     self.generators.hash(state);
   }
@@ -508,6 +541,7 @@ impl HttpResponse {
       status: status_val,
       headers: headers.clone(),
       body: body_from_json(response, "body", &headers),
+      trailers: trailers_from_json(response),
       matching_rules: matchers_from_json(response, &None)?,
       generators: generators_from_json(response)?,
     })
@@ -534,6 +568,15 @@ impl HttpResponse {
         map.insert("body".to_string(), Value::Object(body));
       }
 
+      if let Some(ref trailers) = self.trailers {
+        map.insert("trailers".to_string(), Value::Object(
+          trailers.iter()
+            .sorted_by(|(a, _), (b, _)| Ord::cmp(a, b))
+            .map(|(k, v)| (k.clone(), json!(v)))
+            .collect()
+        ));
+      }
+
       if self.matching_rules.is_not_empty() {
         map.insert("matchingRules".to_string(), matchers_to_json(
           &self.matching_rules.clone(), &PactSpecification::V4));
@@ -608,10 +651,8 @@ impl HttpResponse {
 
   /// Case-insensitive search for a header name
   fn lookup_header_key<H: Into<String>>(&self, header_name: H) -> Option<String> {
-    let name = header_name.into().to_lowercase();
     match self.headers {
-      Some(ref h) => h.iter()
-        .find(|(k, _v)| k.to_lowercase() == name)
+      Some(ref h) => find_header(h, header_name.into().as_str())
         .map(|(k, _v)| k.clone()),
       None => None
     }
@@ -857,6 +898,34 @@ mod tests {
     );
   }
 
+  #[test]
+  fn http_response_to_json_with_trailers() {
+    let response = HttpResponse { trailers: Some(hashmap!{
+      "grpc-status".to_string() => vec!["0".to_string()],
+      "grpc-message".to_string() => vec!["OK".to_string()]
+    }), .. HttpResponse::default() };
+    expect!(response.to_json().to_string()).to(
+      be_equal_to(r#"{"status":200,"trailers":{"grpc-message":["OK"],"grpc-status":["0"]}}"#)
+    );
+  }
+
+  #[test]
+  fn http_response_from_json_with_trailers() {
+    let response_json = json!({
+      "status": 200,
+      "trailers": {
+        "grpc-status": "0",
+        "server-timing": ["cpu;dur=2.4", "db;dur=53"]
+      }
+    });
+    let response = HttpResponse::from_json(&response_json).unwrap();
+    expect!(response.headers).to(be_none());
+    expect!(response.trailers).to(be_some().value(hashmap!{
+      "grpc-status".to_string() => vec!["0".to_string()],
+      "server-timing".to_string() => vec!["cpu;dur=2.4".to_string(), "db;dur=53".to_string()]
+    }));
+  }
+
   #[test]
   fn http_response_to_json_with_json_body() {
     let response = HttpResponse {
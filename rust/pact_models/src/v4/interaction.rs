@@ -1,8 +1,10 @@
 //! V4 Pact interaction
 
+use std::collections::hash_map::DefaultHasher;
 use std::collections::HashMap;
 use std::fmt::{Display, Formatter};
 use std::fmt;
+use std::hash::{Hash, Hasher};
 use std::panic::RefUnwindSafe;
 use std::str::FromStr;
 
@@ -125,6 +127,86 @@ pub trait V4Interaction: Interaction + Send + Sync {
 
   /// Returns the current key if set, otherwise calculates a new one
   fn unique_key(&self) -> String;
+
+  /// Calculates the key for this interaction from a hash of its contents (description, provider
+  /// states, request/response or message parts and pending flag). Any key, ID or comments set on
+  /// the interaction are ignored, so the same interaction will always have the same key.
+  ///
+  /// The default implementation hashes the JSON form of the interaction. The interactions in
+  /// this crate override it to hash their fields directly.
+  fn calculate_key(&self) -> String {
+    let mut json = self.to_json();
+    if let Some(map) = json.as_object_mut() {
+      map.remove("key");
+      map.remove("_id");
+      map.remove("comments");
+    }
+    let mut s = DefaultHasher::new();
+    json.to_string().hash(&mut s);
+    format!("{:x}", s.finish())
+  }
+
+  /// Sets the key for this interaction. Returns an error if the key is blank or contains whitespace.
+  fn set_key(&mut self, key: Option<String>) -> anyhow::Result<()>;
+
+  /// Sets if this interaction is pending
+  fn set_pending(&mut self, pending: bool);
+
+  /// Text comments added to the interaction (the `text` comment)
+  fn text_comments(&self) -> Vec<String> {
+    match self.comments().get(TEXT_COMMENT) {
+      Some(Value::Array(values)) => values.iter().map(json_to_string).collect(),
+      Some(Value::String(comment)) if !comment.is_empty() => vec![comment.clone()],
+      _ => vec![]
+    }
+  }
+
+  /// Appends a text comment to the interaction. If the `text` comment is not an array, it will
+  /// be replaced with one.
+  fn add_text_comment(&mut self, comment: &str) {
+    match self.comments_mut().entry(TEXT_COMMENT.to_string()).or_insert(Value::Array(vec![])) {
+      Value::Array(values) => values.push(Value::String(comment.to_string())),
+      other => *other = Value::Array(vec![Value::String(comment.to_string())])
+    }
+  }
+
+  /// Name of the test that created this interaction (the `testname` comment)
+  fn test_name(&self) -> Option<String> {
+    self.comments().get(TEST_NAME_COMMENT)
+      .map(json_to_string)
+      .filter(|name| !name.is_empty())
+  }
+
+  /// Sets the name of the test that created this interaction. Returns an error if the name is blank.
+  fn set_test_name(&mut self, test_name: Option<&str>) -> anyhow::Result<()> {
+    match test_name {
+      Some(name) if name.trim().is_empty() => Err(anyhow!("Test name can not be blank")),
+      Some(name) => {
+        self.comments_mut().insert(TEST_NAME_COMMENT.to_string(), json!(name));
+        Ok(())
+      }
+      None => {
+        self.comments_mut().remove(TEST_NAME_COMMENT);
+        Ok(())
+      }
+    }
+  }
+}
+
+/// Key of the comments entry containing the text comments for an interaction
+pub const TEXT_COMMENT: &str = "text";
+
+/// Key of the comments entry containing the name of the test that created an interaction
+pub const TEST_NAME_COMMENT: &str = "testname";
+
+/// Validates a key set on an interaction. Keys can not be blank or contain whitespace.
+pub(crate) fn validate_interaction_key(key: &Option<String>) -> anyhow::Result<()> {
+  match key {
+    Some(key) if key.is_empty() => Err(anyhow!("Interaction key can not be empty")),
+    Some(key) if key.chars().any(char::is_whitespace) =>
+      Err(anyhow!("Interaction key '{}' can not contain whitespace", key)),
+    _ => Ok(())
+  }
 }
 
 impl Display for dyn V4Interaction {
@@ -249,7 +331,8 @@ mod tests {
   use serde_json::json;
 
   use crate::provider_states::ProviderState;
-  use crate::v4::interaction::interaction_from_json;
+  use crate::v4::interaction::{interaction_from_json, V4Interaction};
+  use crate::v4::synch_http::SynchronousHttp;
 
   #[test]
   fn loading_interaction_from_json() {
@@ -388,4 +471,61 @@ mod tests {
   //   };
   //   expect!(interaction1.conflicts_with(&interaction2).iter()).to_not(be_empty());
   // }
+
+  #[test]
+  fn text_comments_and_test_name_accessors() {
+    let mut interaction = SynchronousHttp::default();
+    expect!(interaction.text_comments()).to(be_equal_to(Vec::<String>::new()));
+    expect!(interaction.test_name()).to(be_none());
+
+    interaction.add_text_comment("comment one");
+    interaction.add_text_comment("comment two");
+    expect!(interaction.set_test_name(Some("a_test"))).to(be_ok());
+    expect!(interaction.text_comments()).to(be_equal_to(vec!["comment one".to_string(), "comment two".to_string()]));
+    expect!(interaction.test_name()).to(be_some().value("a_test"));
+    expect!(interaction.comments.get("text")).to(be_some().value(&json!(["comment one", "comment two"])));
+
+    expect!(interaction.set_test_name(Some("  "))).to(be_err());
+    expect!(interaction.set_test_name(None)).to(be_ok());
+    expect!(interaction.test_name()).to(be_none());
+  }
+
+  #[test]
+  fn set_key_validates_the_key() {
+    let mut interaction = SynchronousHttp::default();
+    expect!(interaction.set_key(Some("abc123".to_string()))).to(be_ok());
+    expect!(interaction.key.clone()).to(be_some().value("abc123"));
+    expect!(interaction.set_key(Some("".to_string()))).to(be_err());
+    expect!(interaction.set_key(Some("abc 123".to_string()))).to(be_err());
+    expect!(interaction.key.clone()).to(be_some().value("abc123"));
+    expect!(interaction.set_key(None)).to(be_ok());
+    expect!(interaction.key.clone()).to(be_none());
+
+    interaction.set_pending(true);
+    expect!(interaction.pending).to(be_true());
+  }
+
+  #[test]
+  fn calculate_key_only_depends_on_the_interaction_contents() {
+    let interaction_json = json!({
+      "type": "Synchronous/HTTP",
+      "description": "a request",
+      "providerStates": [{ "name": "provider state" }],
+      "request": { "method": "GET", "path": "/", "headers": { "A": "1", "B": "2", "C": "3" } },
+      "response": { "status": 200 }
+    });
+    let interaction = interaction_from_json("", 0, &interaction_json).unwrap();
+    let key = interaction.calculate_key();
+    expect!(interaction.unique_key()).to(be_equal_to(key.as_str()));
+    expect!(interaction.with_unique_key().key()).to(be_some().value(key.as_str()));
+
+    let mut interaction2 = interaction_from_json("", 1, &interaction_json).unwrap();
+    interaction2.set_key(Some("abc123".to_string())).unwrap();
+    interaction2.add_text_comment("a comment");
+    expect!(interaction2.unique_key()).to(be_equal_to("abc123"));
+    expect!(interaction2.calculate_key()).to(be_equal_to(key.as_str()));
+
+    interaction2.set_pending(true);
+    expect!(interaction2.calculate_key()).to_not(be_equal_to(key.as_str()));
+  }
 }
@@ -16,6 +16,7 @@ pub mod message_parts;
 pub mod sync_message;
 pub mod async_message;
 pub mod pact;
+pub mod builders;
 
 /// V4 Interaction Type
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
@@ -1,13 +1,11 @@
 //! V4 specification Pact
 
-use std::cmp::Ordering;
 use std::collections::{BTreeMap, HashMap, HashSet};
 use std::panic::RefUnwindSafe;
 use std::path::Path;
 use std::sync::{Arc, Mutex};
 
-use anyhow::{anyhow, Context};
-use itertools::EitherOrBoth::{Both, Left, Right};
+use anyhow::anyhow;
 use itertools::Itertools;
 use maplit::btreemap;
 use serde_json::{json, Map, Value};
@@ -15,10 +13,12 @@ use tracing::{trace, warn};
 
 use crate::{Consumer, PactSpecification, Provider};
 #[cfg(not(target_family = "wasm"))] use crate::file_utils::with_read_lock;
+#[cfg(not(target_family = "wasm"))] use crate::pact::read_pact_json;
 use crate::interaction::Interaction;
 use crate::json_utils::json_to_string;
 use crate::message_pact::MessagePact;
-use crate::pact::{Pact, ReadWritePact};
+use crate::pact;
+use crate::pact::{MergeStrategy, Pact, ReadWritePact};
 use crate::PACT_RUST_VERSION;
 use crate::plugins::PluginData;
 use crate::sync_pact::RequestResponsePact;
@@ -42,6 +42,19 @@ pub struct V4Pact {
 }
 
 impl V4Pact {
+  /// Removes any interactions that are semantically equal to an earlier interaction in the Pact
+  /// (ignoring the interaction keys and IDs). Returns the number of interactions removed.
+  pub fn deduplicate_interactions(&mut self) -> usize {
+    pact::deduplicate_interactions(&mut self.interactions, |i| pact::interaction_identity(i.as_ref()))
+  }
+
+  /// Normalizes the Pact, so that equivalent pacts have the same form. Duplicate interactions
+  /// are removed, and the interactions are sorted by description, provider states and type.
+  pub fn normalize(&mut self) {
+    self.deduplicate_interactions();
+    self.interactions.sort_by_cached_key(|i| pact::interaction_key(i.as_ref()));
+  }
+
   fn metadata_to_json(&self) -> Value {
     let mut md_map: serde_json::Map<String, Value> = self.metadata.iter()
       .map(|(k, v)| {
@@ -95,7 +108,7 @@ impl V4Pact {
       .collect()
   }
 
-  fn add_plugin_data(&mut self, other_data: &PluginData) {
+  pub(crate) fn add_plugin_data(&mut self, other_data: &PluginData) {
     if let Some(data) = self.plugin_data.iter_mut()
       .find(|data| data.name == other_data.name && data.version == other_data.version) {
       data.merge(&other_data.configuration);
@@ -348,67 +361,13 @@ impl Default for V4Pact {
 impl ReadWritePact for V4Pact {
   #[cfg(not(target_family = "wasm"))]
   fn read_pact(path: &Path) -> anyhow::Result<V4Pact> {
-    let json = with_read_lock(path, 3, &mut |f| {
-      serde_json::from_reader::<_, Value>(f).context("Failed to parse Pact JSON")
-    })?;
+    let json = with_read_lock(path, 3, &mut |f| read_pact_json(f))?;
 
     Self::pact_from_json(&json, &*path.to_string_lossy())
   }
 
   fn merge(&self, other: &dyn Pact) -> anyhow::Result<Box<dyn Pact + Send + Sync + RefUnwindSafe>> {
-    if self.consumer.name == other.consumer().name && self.provider.name == other.provider().name {
-      let mut new_pact = V4Pact {
-        consumer: self.consumer.clone(),
-        provider: self.provider.clone(),
-        interactions: self.interactions.iter()
-          .merge_join_by(other.interactions().iter().map(|i| i.as_v4().unwrap()), |a, b| {
-            match (a.key(), b.key()) {
-              (Some(key_a), Some(key_b)) => Ord::cmp(&key_a, &key_b),
-              (_, _) => {
-                let type_a = a.type_of();
-                let type_b = b.type_of();
-                let cmp = Ord::cmp(&a.description(), &b.description());
-                if cmp == Ordering::Equal && !a.provider_states().is_empty() {
-                  let cmp = Ord::cmp(&a.provider_states().iter().map(|p| p.name.clone()).collect::<Vec<String>>(),
-                  &b.provider_states().iter().map(|p| p.name.clone()).collect::<Vec<String>>());
-                  if cmp == Ordering::Equal {
-                       Ord::cmp(&type_a, &type_b)
-                  } else
-                  {
-                    cmp
-                  }
-                }
-                else if cmp == Ordering::Equal && a.provider_states().is_empty() {
-                  Ord::cmp(&type_a, &type_b)
-                } 
-                 else {
-                  cmp
-                }
-              }
-            }
-          })
-          .map(|either| {
-            match either {
-              Left(i) => i.clone(),
-              Right(i) => i.boxed_v4(),
-              Both(i, _) => i.clone()
-            }
-          })
-          .collect(),
-        metadata: self.metadata.clone(),
-        plugin_data: self.plugin_data.clone()
-      };
-
-      if other.is_v4() {
-        for plugin in other.as_v4_pact().unwrap_or_default().plugin_data {
-          new_pact.add_plugin_data(&plugin);
-        }
-      }
-
-      Ok(Box::new(new_pact))
-    } else {
-      Err(anyhow!("Unable to merge pacts, as they have different consumers or providers"))
-    }
+    pact::merge_pacts(self, other, MergeStrategy::KeepNewest)
   }
 
   fn default_file_name(&self) -> String {
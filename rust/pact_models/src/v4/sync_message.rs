@@ -21,7 +21,7 @@ use crate::message::Message;
 use crate::provider_states::ProviderState;
 use crate::sync_interaction::RequestResponseInteraction;
 use crate::v4::async_message::AsynchronousMessage;
-use crate::v4::interaction::{InteractionMarkup, parse_plugin_config, V4Interaction};
+use crate::v4::interaction::{InteractionMarkup, parse_plugin_config, V4Interaction, validate_interaction_key};
 use crate::v4::message_parts::MessageContents;
 use crate::v4::synch_http::SynchronousHttp;
 use crate::v4::V4InteractionType;
@@ -207,6 +207,16 @@ impl V4Interaction for SynchronousMessage {
     self.key.clone()
   }
 
+  fn set_key(&mut self, key: Option<String>) -> anyhow::Result<()> {
+    validate_interaction_key(&key)?;
+    self.key = key;
+    Ok(())
+  }
+
+  fn set_pending(&mut self, pending: bool) {
+    self.pending = pending;
+  }
+
   fn boxed_v4(&self) -> Box<dyn V4Interaction + Send + Sync + RefUnwindSafe> {
     Box::new(self.clone())
   }
@@ -257,6 +267,10 @@ impl V4Interaction for SynchronousMessage {
       Some(key) => key.clone()
     }
   }
+
+  fn calculate_key(&self) -> String {
+    self.calc_hash()
+  }
 }
 
 impl Interaction for SynchronousMessage {
@@ -0,0 +1,438 @@
+//! Module for validating the matching rules and generators in a Pact against the rules for its
+//! specification version (i.e. unknown matchers, malformed paths and invalid generator configs)
+
+#[cfg(not(target_family = "wasm"))] use std::path::Path;
+use std::str::FromStr;
+
+use regex::Regex;
+use serde_json::{Map, Value};
+
+use crate::PactSpecification;
+#[cfg(not(target_family = "wasm"))] use crate::file_utils::with_read_lock;
+use crate::generators::{Generator, GeneratorCategory};
+use crate::matchingrules::{Category, MatchingRule};
+use crate::pact::Pact;
+#[cfg(not(target_family = "wasm"))] use crate::pact::{determine_spec_version, parse_meta_data, read_pact_json};
+use crate::path_exp::DocPath;
+#[cfg(feature = "datetime")] use crate::time_utils::parse_pattern;
+use crate::verify_json::{json_type_of, PactFileVerificationResult, ResultLevel};
+
+/// Attributes that contain example data and not Pact structures, so are not searched
+const DATA_ATTRIBUTES: [&str; 6] = ["body", "contents", "metadata", "providerStates", "comments", "pluginConfiguration"];
+
+/// Validates the matching rules and generators of the Pact, returning any errors or warnings found
+pub fn validate_pact(pact: &dyn Pact) -> Vec<PactFileVerificationResult> {
+  let spec_version = pact.specification_version();
+  match pact.to_json(spec_version) {
+    Ok(pact_json) => validate_pact_json(&pact_json, spec_version),
+    Err(err) => vec![
+      PactFileVerificationResult::new("/", ResultLevel::ERROR, format!("Could not convert the Pact to JSON - {}", err))
+    ]
+  }
+}
+
+/// Validates the matching rules and generators in the Pact JSON, returning any errors or warnings
+/// found (sorted by path). Unlike loading the Pact, this will return all the problems with the file
+/// and not just the first one.
+pub fn validate_pact_json(pact_json: &Value, spec_version: PactSpecification) -> Vec<PactFileVerificationResult> {
+  let mut results = vec![];
+  match pact_json {
+    Value::Object(map) => for (key, value) in map {
+      if key != "metadata" {
+        validate_value(&format!("/{}", key), value, spec_version, &mut results);
+      }
+    }
+    _ => results.push(PactFileVerificationResult::new("/", ResultLevel::ERROR,
+      format!("Must be an Object, got {}", json_type_of(pact_json))))
+  }
+  results.sort_by(|a, b| a.path.cmp(&b.path));
+  results
+}
+
+/// Loads the Pact file and validates the matching rules and generators in it. The specification
+/// version is taken from the metadata in the file. Returns an error if the file can not be read
+/// or is not valid JSON.
+#[cfg(not(target_family = "wasm"))]
+pub fn validate_pact_file(path: &Path) -> anyhow::Result<Vec<PactFileVerificationResult>> {
+  let pact_json = with_read_lock(path, 3, &mut |f| read_pact_json(f))?;
+  let spec_version = determine_spec_version(&path.to_string_lossy(), &parse_meta_data(&pact_json));
+  Ok(validate_pact_json(&pact_json, spec_version))
+}
+
+fn validate_value(
+  path: &str,
+  value: &Value,
+  spec_version: PactSpecification,
+  results: &mut Vec<PactFileVerificationResult>
+) {
+  match value {
+    Value::Object(map) => for (key, value) in map {
+      let path = format!("{}/{}", path, key);
+      match key.as_str() {
+        "matchingRules" => validate_matching_rules(&path, value, spec_version, results),
+        "generators" => validate_generators(&path, value, spec_version, results),
+        _ => if !DATA_ATTRIBUTES.contains(&key.as_str()) {
+          validate_value(&path, value, spec_version, results)
+        }
+      }
+    }
+    Value::Array(values) => for (index, value) in values.iter().enumerate() {
+      validate_value(&format!("{}/{}", path, index), value, spec_version, results);
+    }
+    _ => {}
+  }
+}
+
+fn validate_matching_rules(
+  path: &str,
+  rules: &Value,
+  spec_version: PactSpecification,
+  results: &mut Vec<PactFileVerificationResult>
+) {
+  if spec_version == PactSpecification::V1 || spec_version == PactSpecification::V1_1 {
+    results.push(PactFileVerificationResult::new(path, ResultLevel::WARNING,
+      format!("Matching rules are not supported by {} pacts and will be ignored", spec_version)));
+    return;
+  }
+
+  match rules {
+    Value::Object(map) => if map.keys().next().map(|key| key.starts_with('$')).unwrap_or(false) {
+      for (key, rule) in map {
+        let rule_path = format!("{}/{}", path, key);
+        if let Err(err) = DocPath::new(key) {
+          results.push(PactFileVerificationResult::new(&rule_path, ResultLevel::ERROR,
+            format!("'{}' is not a valid path expression - {}", key, err)));
+        }
+        validate_matcher(&rule_path, rule, spec_version, results);
+      }
+    } else {
+      for (category, rules) in map {
+        let category_path = format!("{}/{}", path, category);
+        match Category::from_str(category) {
+          Ok(category) => validate_category(&category_path, &category, rules, spec_version, results),
+          Err(err) => results.push(PactFileVerificationResult::new(&category_path, ResultLevel::ERROR, err))
+        }
+      }
+    }
+    _ => results.push(PactFileVerificationResult::new(path, ResultLevel::ERROR,
+      format!("Must be an Object, got {}", json_type_of(rules))))
+  }
+}
+
+fn validate_category(
+  path: &str,
+  category: &Category,
+  rules: &Value,
+  spec_version: PactSpecification,
+  results: &mut Vec<PactFileVerificationResult>
+) {
+  match rules {
+    Value::Object(map) => if map.contains_key("matchers") {
+      validate_rule_list(path, rules, spec_version, results);
+    } else {
+      for (key, rule_list) in map {
+        let rule_path = format!("{}/{}", path, key);
+        let is_path_key = match category {
+          Category::BODY | Category::CONTENTS => true,
+          Category::METADATA => key.starts_with('$'),
+          _ => false
+        };
+        if is_path_key {
+          if let Err(err) = DocPath::new(key) {
+            results.push(PactFileVerificationResult::new(&rule_path, ResultLevel::ERROR,
+              format!("'{}' is not a valid path expression - {}", key, err)));
+          }
+        }
+        validate_rule_list(&rule_path, rule_list, spec_version, results);
+      }
+    }
+    _ => results.push(PactFileVerificationResult::new(path, ResultLevel::ERROR,
+      format!("Must be an Object, got {}", json_type_of(rules))))
+  }
+}
+
+fn validate_rule_list(
+  path: &str,
+  rule_list: &Value,
+  spec_version: PactSpecification,
+  results: &mut Vec<PactFileVerificationResult>
+) {
+  if let Some(combine) = rule_list.get("combine") {
+    let combine = combine.as_str().unwrap_or_default().to_uppercase();
+    if combine != "AND" && combine != "OR" {
+      results.push(PactFileVerificationResult::new(format!("{}/combine", path), ResultLevel::WARNING,
+        format!("'{}' is not a valid rule logic, it must be either AND or OR", combine)));
+    }
+  }
+
+  match rule_list.get("matchers") {
+    Some(Value::Array(matchers)) => for (index, matcher) in matchers.iter().enumerate() {
+      validate_matcher(&format!("{}/matchers/{}", path, index), matcher, spec_version, results);
+    }
+    Some(matchers) => results.push(PactFileVerificationResult::new(format!("{}/matchers", path),
+      ResultLevel::ERROR, format!("Must be an Array, got {}", json_type_of(matchers)))),
+    None => results.push(PactFileVerificationResult::new(path, ResultLevel::ERROR, "Missing matchers"))
+  }
+}
+
+fn validate_matcher(
+  path: &str,
+  matcher: &Value,
+  spec_version: PactSpecification,
+  results: &mut Vec<PactFileVerificationResult>
+) {
+  match MatchingRule::from_json(matcher) {
+    Ok(rule) => {
+      if let Some(min_version) = matcher_spec_version(&rule) {
+        if spec_version < min_version {
+          results.push(PactFileVerificationResult::new(path, ResultLevel::WARNING,
+            format!("The '{}' matcher is not supported by {} pacts (requires {})", rule.name(), spec_version, min_version)));
+        }
+      }
+      if let Err(err) = validate_matcher_config(&rule) {
+        results.push(PactFileVerificationResult::new(path, ResultLevel::ERROR, err));
+      }
+    }
+    Err(err) => results.push(PactFileVerificationResult::new(path, ResultLevel::ERROR, err.to_string()))
+  }
+}
+
+/// Returns the minimum specification version required for the matching rule
+fn matcher_spec_version(rule: &MatchingRule) -> Option<PactSpecification> {
+  match rule {
+    MatchingRule::Regex(_) | MatchingRule::Type | MatchingRule::MinType(_) | MatchingRule::MaxType(_) |
+    MatchingRule::MinMaxType(_, _) => None,
+    MatchingRule::Equality | MatchingRule::Timestamp(_) | MatchingRule::Time(_) | MatchingRule::Date(_) |
+    MatchingRule::Include(_) | MatchingRule::Number | MatchingRule::Integer | MatchingRule::Decimal |
+    MatchingRule::Null | MatchingRule::ContentType(_) | MatchingRule::Values => Some(PactSpecification::V3),
+    _ => Some(PactSpecification::V4)
+  }
+}
+
+fn validate_matcher_config(rule: &MatchingRule) -> Result<(), String> {
+  match rule {
+    MatchingRule::Regex(regex) => Regex::new(regex)
+      .map(|_| ())
+      .map_err(|err| format!("'{}' is not a valid regular expression - {}", regex, err)),
+    MatchingRule::MinMaxType(min, max) | MatchingRule::MinMaxEqualsIgnoreOrder(min, max) => if min > max {
+      Err(format!("The minimum ({}) of the '{}' matcher is greater than the maximum ({})", min, rule.name(), max))
+    } else {
+      Ok(())
+    }
+    MatchingRule::Timestamp(format) | MatchingRule::Time(format) | MatchingRule::Date(format) |
+    MatchingRule::TimestampWithin(format, _, _) => validate_date_format(format),
+    _ => Ok(())
+  }
+}
+
+#[cfg(feature = "datetime")]
+fn validate_date_format(format: &str) -> Result<(), String> {
+  if format.is_empty() {
+    Ok(())
+  } else {
+    parse_pattern(format)
+      .map(|_| ())
+      .map_err(|err| format!("'{}' is not a valid date/time format - {}", format, err))
+  }
+}
+
+#[cfg(not(feature = "datetime"))]
+fn validate_date_format(_format: &str) -> Result<(), String> {
+  Ok(())
+}
+
+fn validate_generators(
+  path: &str,
+  generators: &Value,
+  spec_version: PactSpecification,
+  results: &mut Vec<PactFileVerificationResult>
+) {
+  if spec_version < PactSpecification::V3 {
+    results.push(PactFileVerificationResult::new(path, ResultLevel::WARNING,
+      format!("Generators are not supported by {} pacts and will be ignored", spec_version)));
+    return;
+  }
+
+  match generators {
+    Value::Object(map) => for (category, generators) in map {
+      let category_path = format!("{}/{}", path, category);
+      match GeneratorCategory::from_str(category) {
+        Ok(category) => match (&category, generators) {
+          (GeneratorCategory::PATH | GeneratorCategory::METHOD | GeneratorCategory::STATUS, Value::Object(generator)) =>
+            validate_generator(&category_path, generator, results),
+          (_, Value::Object(generators)) => for (key, generator) in generators {
+            let generator_path = format!("{}/{}", category_path, key);
+            let is_path_key = match category {
+              GeneratorCategory::BODY => true,
+              GeneratorCategory::METADATA => key.starts_with('$'),
+              _ => false
+            };
+            if is_path_key {
+              if let Err(err) = DocPath::new(key) {
+                results.push(PactFileVerificationResult::new(&generator_path, ResultLevel::ERROR,
+                  format!("'{}' is not a valid path expression - {}", key, err)));
+              }
+            }
+            match generator {
+              Value::Object(generator) => validate_generator(&generator_path, generator, results),
+              _ => results.push(PactFileVerificationResult::new(&generator_path, ResultLevel::ERROR,
+                format!("Must be an Object, got {}", json_type_of(generator))))
+            }
+          }
+          _ => results.push(PactFileVerificationResult::new(&category_path, ResultLevel::ERROR,
+            format!("Must be an Object, got {}", json_type_of(generators))))
+        }
+        Err(err) => results.push(PactFileVerificationResult::new(&category_path, ResultLevel::ERROR, err))
+      }
+    }
+    _ => results.push(PactFileVerificationResult::new(path, ResultLevel::ERROR,
+      format!("Must be an Object, got {}", json_type_of(generators))))
+  }
+}
+
+fn validate_generator(
+  path: &str,
+  generator: &Map<String, Value>,
+  results: &mut Vec<PactFileVerificationResult>
+) {
+  match generator.get("type") {
+    Some(Value::String(generator_type)) => match Generator::from_map(generator_type, generator) {
+      Some(generator) => if let Err(err) = validate_generator_config(&generator) {
+        results.push(PactFileVerificationResult::new(path, ResultLevel::ERROR, err));
+      }
+      None => results.push(PactFileVerificationResult::new(path, ResultLevel::ERROR,
+        format!("'{}' is not a valid generator type, or the generator is missing required attributes", generator_type)))
+    }
+    Some(generator_type) => results.push(PactFileVerificationResult::new(format!("{}/type", path), ResultLevel::ERROR,
+      format!("Must be a String, got {}", json_type_of(generator_type)))),
+    None => results.push(PactFileVerificationResult::new(path, ResultLevel::ERROR, "Missing generator type"))
+  }
+}
+
+fn validate_generator_config(generator: &Generator) -> Result<(), String> {
+  match generator {
+    Generator::RandomInt(min, max) => if min > max {
+      Err(format!("The minimum ({}) of the RandomInt generator is greater than the maximum ({})", min, max))
+    } else {
+      Ok(())
+    }
+    Generator::Regex(regex) | Generator::SeededRegex(regex, _) => Regex::new(regex)
+      .map(|_| ())
+      .map_err(|err| format!("'{}' is not a valid regular expression - {}", regex, err)),
+    Generator::Date(Some(format), _) | Generator::Time(Some(format), _) |
+    Generator::DateTime(Some(format), _) => validate_date_format(format),
+    Generator::ProviderStateGenerator(expression, _) => if expression.trim().is_empty() {
+      Err("The ProviderState generator expression can not be empty".to_string())
+    } else {
+      Ok(())
+    }
+    _ => Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use expectest::prelude::*;
+  use serde_json::json;
+
+  use crate::PactSpecification;
+  use crate::sync_pact::RequestResponsePact;
+  use crate::verify_json::ResultLevel;
+
+  use super::*;
+
+  fn errors(results: &[PactFileVerificationResult]) -> Vec<(String, ResultLevel)> {
+    results.iter().map(|result| (result.path.clone(), result.level.clone())).collect()
+  }
+
+  #[test]
+  fn validate_pact_json_reports_invalid_matchers_and_paths() {
+    let pact_json = json!({
+      "interactions": [{
+        "request": {
+          "body": { "matchingRules": { "not": "checked" } },
+          "matchingRules": {
+            "body": {
+              "$.id": { "matchers": [{ "match": "integer" }, { "match": "unknown" }] },
+              "$.items[": { "matchers": [{ "match": "type", "min": 2, "max": 1 }] }
+            },
+            "cookies": {}
+          }
+        },
+        "response": {
+          "matchingRules": {
+            "header": {
+              "X-Id": { "matchers": [{ "match": "regex", "regex": "[a-z" }], "combine": "XOR" }
+            }
+          }
+        }
+      }]
+    });
+    let results = validate_pact_json(&pact_json, PactSpecification::V3);
+    expect!(errors(&results)).to(be_equal_to(vec![
+      ("/interactions/0/request/matchingRules/body/$.id/matchers/1".to_string(), ResultLevel::ERROR),
+      ("/interactions/0/request/matchingRules/body/$.items[".to_string(), ResultLevel::ERROR),
+      ("/interactions/0/request/matchingRules/body/$.items[/matchers/0".to_string(), ResultLevel::ERROR),
+      ("/interactions/0/request/matchingRules/cookies".to_string(), ResultLevel::ERROR),
+      ("/interactions/0/response/matchingRules/header/X-Id/combine".to_string(), ResultLevel::WARNING),
+      ("/interactions/0/response/matchingRules/header/X-Id/matchers/0".to_string(), ResultLevel::ERROR)
+    ]));
+    expect!(results[0].message.as_str()).to(be_equal_to("unknown is not a valid matching rule type"));
+  }
+
+  #[test]
+  fn validate_pact_json_reports_matchers_and_generators_not_supported_by_the_spec_version() {
+    let pact_json = json!({
+      "interactions": [{
+        "request": {
+          "matchingRules": {
+            "$.body.id": { "match": "integer" },
+            "$.body.name": { "match": "type" }
+          },
+          "generators": {
+            "body": { "$.id": { "type": "RandomInt" } }
+          }
+        }
+      }]
+    });
+    let results = validate_pact_json(&pact_json, PactSpecification::V2);
+    expect!(errors(&results)).to(be_equal_to(vec![
+      ("/interactions/0/request/generators".to_string(), ResultLevel::WARNING),
+      ("/interactions/0/request/matchingRules/$.body.id".to_string(), ResultLevel::WARNING)
+    ]));
+  }
+
+  #[test]
+  fn validate_pact_json_reports_invalid_generators() {
+    let pact_json = json!({
+      "interactions": [{
+        "request": {
+          "generators": {
+            "body": {
+              "$.id": { "type": "RandomInt", "min": 10, "max": 1 },
+              "$.name": { "type": "Unknown" },
+              "$.value": { "type": "Regex" },
+              "$.date": { "type": "Date", "format": "yyyy-MM-dd" }
+            },
+            "path": { "type": "ProviderState", "expression": " " },
+            "cookies": {}
+          }
+        }
+      }]
+    });
+    let results = validate_pact_json(&pact_json, PactSpecification::V3);
+    expect!(errors(&results)).to(be_equal_to(vec![
+      ("/interactions/0/request/generators/body/$.id".to_string(), ResultLevel::ERROR),
+      ("/interactions/0/request/generators/body/$.name".to_string(), ResultLevel::ERROR),
+      ("/interactions/0/request/generators/body/$.value".to_string(), ResultLevel::ERROR),
+      ("/interactions/0/request/generators/cookies".to_string(), ResultLevel::ERROR),
+      ("/interactions/0/request/generators/path".to_string(), ResultLevel::ERROR)
+    ]));
+  }
+
+  #[test]
+  fn validate_pact_returns_no_results_for_a_valid_pact() {
+    let pact = RequestResponsePact::default();
+    expect!(validate_pact(&pact).iter()).to(be_empty());
+  }
+}
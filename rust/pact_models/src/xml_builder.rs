@@ -0,0 +1,313 @@
+//! Builder for constructing XML bodies, along with the matching rules and generators for them
+
+use anyhow::anyhow;
+use sxd_document::dom::{ChildOfElement, Document, Element};
+use sxd_document::Package;
+use sxd_document::writer::format_document;
+use tracing::trace;
+
+use crate::generators::{Generator, GeneratorCategory, Generators};
+use crate::matchingrules::{MatchingRule, MatchingRuleCategory, RuleLogic};
+use crate::path_exp::DocPath;
+
+/// Attribute of an XML element
+#[derive(Debug, Clone)]
+pub struct XmlAttribute {
+  /// Name of the attribute
+  pub name: String,
+  /// Example value of the attribute
+  pub value: String,
+  /// Matching rules to apply to the attribute value
+  pub matching_rules: Vec<MatchingRule>,
+  /// Generator for the attribute value
+  pub generator: Option<Generator>
+}
+
+impl XmlAttribute {
+  /// Creates an attribute with the given name and value
+  pub fn new<S: Into<String>, V: ToString>(name: S, value: V) -> Self {
+    XmlAttribute {
+      name: name.into(),
+      value: value.to_string(),
+      matching_rules: vec![],
+      generator: None
+    }
+  }
+
+  /// Adds a matching rule to apply to the attribute value
+  pub fn matching_rule(mut self, rule: MatchingRule) -> Self {
+    self.matching_rules.push(rule);
+    self
+  }
+
+  /// Sets the generator for the attribute value
+  pub fn generator(mut self, generator: Generator) -> Self {
+    self.generator = Some(generator);
+    self
+  }
+}
+
+/// Text node in an XML element
+#[derive(Debug, Clone)]
+pub struct XmlText {
+  /// Example text content
+  pub content: String,
+  /// Matching rules to apply to the text
+  pub matching_rules: Vec<MatchingRule>,
+  /// Generator for the text
+  pub generator: Option<Generator>
+}
+
+impl XmlText {
+  /// Creates a text node with the given content
+  pub fn new<S: Into<String>>(content: S) -> Self {
+    XmlText {
+      content: content.into(),
+      matching_rules: vec![],
+      generator: None
+    }
+  }
+
+  /// Adds a matching rule to apply to the text
+  pub fn matching_rule(mut self, rule: MatchingRule) -> Self {
+    self.matching_rules.push(rule);
+    self
+  }
+
+  /// Sets the generator for the text
+  pub fn generator(mut self, generator: Generator) -> Self {
+    self.generator = Some(generator);
+    self
+  }
+}
+
+/// Child node of an XML element
+#[derive(Debug, Clone)]
+pub enum XmlNode {
+  /// Child element
+  Element(XmlElement),
+  /// Text node
+  Text(XmlText)
+}
+
+/// Element in an XML document. Names can include a namespace prefix (i.e. `ns1:projects`), with the
+/// namespace declared on the element or one of its parents with `namespace`.
+#[derive(Debug, Clone)]
+pub struct XmlElement {
+  /// Name of the element
+  pub name: String,
+  /// Attributes of the element
+  pub attributes: Vec<XmlAttribute>,
+  /// Child elements and text nodes
+  pub children: Vec<XmlNode>,
+  /// Matching rules to apply to the element
+  pub matching_rules: Vec<MatchingRule>,
+  /// Generator for the element
+  pub generator: Option<Generator>,
+  /// Number of copies of the element to include in the example document
+  pub examples: usize
+}
+
+impl XmlElement {
+  /// Creates an element with the given name
+  pub fn new<S: Into<String>>(name: S) -> Self {
+    XmlElement {
+      name: name.into(),
+      attributes: vec![],
+      children: vec![],
+      matching_rules: vec![],
+      generator: None,
+      examples: 1
+    }
+  }
+
+  /// Declares a namespace on this element. If the prefix is `None`, the namespace will be the
+  /// default namespace.
+  pub fn namespace(self, prefix: Option<&str>, uri: &str) -> Self {
+    match prefix {
+      Some(prefix) => self.attribute(format!("xmlns:{}", prefix), uri),
+      None => self.attribute("xmlns", uri)
+    }
+  }
+
+  /// Adds an attribute to the element
+  pub fn attribute<S: Into<String>, V: ToString>(self, name: S, value: V) -> Self {
+    self.with_attribute(XmlAttribute::new(name, value))
+  }
+
+  /// Adds an attribute to the element, along with any matching rules and generator for it
+  pub fn with_attribute(mut self, attribute: XmlAttribute) -> Self {
+    self.attributes.push(attribute);
+    self
+  }
+
+  /// Adds a text node to the element
+  pub fn text<S: Into<String>>(self, content: S) -> Self {
+    self.with_text(XmlText::new(content))
+  }
+
+  /// Adds a text node to the element, along with any matching rules and generator for it
+  pub fn with_text(mut self, text: XmlText) -> Self {
+    self.children.push(XmlNode::Text(text));
+    self
+  }
+
+  /// Adds a child element
+  pub fn child(mut self, element: XmlElement) -> Self {
+    self.children.push(XmlNode::Element(element));
+    self
+  }
+
+  /// Adds a matching rule to apply to the element (i.e. a type matcher to match repeated elements)
+  pub fn matching_rule(mut self, rule: MatchingRule) -> Self {
+    self.matching_rules.push(rule);
+    self
+  }
+
+  /// Sets the generator for the element
+  pub fn generator(mut self, generator: Generator) -> Self {
+    self.generator = Some(generator);
+    self
+  }
+
+  /// Sets the number of copies of the element to include in the example document. This is ignored
+  /// for the root element.
+  pub fn examples(mut self, examples: usize) -> Self {
+    self.examples = examples;
+    self
+  }
+
+  /// Builds the XML document with this element as the root element. Any matching rules and
+  /// generators are added to the given body category, using the paths of the elements in the
+  /// document (i.e. `$.root.child`, `$.root.child['@id']` and `$.root.child.#text`).
+  pub fn build(
+    &self,
+    matching_rules: &mut MatchingRuleCategory,
+    generators: &mut Generators
+  ) -> anyhow::Result<Vec<u8>> {
+    let package = Package::new();
+    let doc = package.as_document();
+    let root = create_element(doc, self, "$", matching_rules, generators);
+    doc.root().append_child(root);
+
+    let mut output = vec![];
+    format_document(&doc, &mut output)
+      .map_err(|err| anyhow!("Unable to generate a valid XML document: {}", err))?;
+    Ok(output)
+  }
+}
+
+fn create_element<'a>(
+  doc: Document<'a>,
+  element: &XmlElement,
+  parent_path: &str,
+  matching_rules: &mut MatchingRuleCategory,
+  generators: &mut Generators
+) -> Element<'a> {
+  let path = format!("{}.{}", parent_path, element.name);
+  trace!("create_element: {}", path);
+  add_rules(&path, &element.matching_rules, element.generator.as_ref(), matching_rules, generators);
+
+  let new_element = doc.create_element(element.name.as_str());
+  for attribute in &element.attributes {
+    let attribute_path = format!("{}['@{}']", path, attribute.name);
+    add_rules(&attribute_path, &attribute.matching_rules, attribute.generator.as_ref(), matching_rules, generators);
+    new_element.set_attribute_value(attribute.name.as_str(), attribute.value.as_str());
+  }
+
+  for child in &element.children {
+    match child {
+      XmlNode::Element(child) => {
+        let child_element = create_element(doc, child, &path, matching_rules, generators);
+        for _ in 1..child.examples {
+          new_element.append_child(duplicate_element(doc, &child_element));
+        }
+        if child.examples > 0 {
+          new_element.append_child(child_element);
+        }
+      }
+      XmlNode::Text(text) => {
+        let text_path = format!("{}.#text", path);
+        add_rules(&text_path, &text.matching_rules, text.generator.as_ref(), matching_rules, generators);
+        new_element.append_child(doc.create_text(text.content.as_str()));
+      }
+    }
+  }
+
+  new_element
+}
+
+fn add_rules(
+  path: &str,
+  rules: &[MatchingRule],
+  generator: Option<&Generator>,
+  matching_rules: &mut MatchingRuleCategory,
+  generators: &mut Generators
+) {
+  if !rules.is_empty() || generator.is_some() {
+    let doc_path = DocPath::new(path).unwrap_or(DocPath::root());
+    for rule in rules {
+      matching_rules.add_rule(doc_path.clone(), rule.clone(), RuleLogic::And);
+    }
+    if let Some(generator) = generator {
+      generators.add_generator_with_subcategory(&GeneratorCategory::BODY, doc_path, generator.clone());
+    }
+  }
+}
+
+/// Creates a deep copy of the element in the document
+pub(crate) fn duplicate_element<'a>(doc: Document<'a>, el: &Element<'a>) -> Element<'a> {
+  let element = doc.create_element(el.name());
+  for attr in el.attributes() {
+    element.set_attribute_value(attr.name(), attr.value());
+  }
+  for child in el.children() {
+    match child {
+      ChildOfElement::Element(el) => element.append_child(duplicate_element(doc, &el)),
+      ChildOfElement::Text(txt) => element.append_child(doc.create_text(txt.text())),
+      _ => ()
+    }
+  }
+  element
+}
+
+#[cfg(test)]
+mod tests {
+  use expectest::prelude::*;
+
+  use crate::generators::{Generator, GeneratorCategory, Generators};
+  use crate::matchingrules::{MatchingRule, MatchingRuleCategory};
+  use crate::path_exp::DocPath;
+
+  use super::*;
+
+  #[test]
+  fn build_xml_body_test() {
+    let root = XmlElement::new("ns1:projects")
+      .namespace(Some("ns1"), "http://some.namespace/and/more/stuff")
+      .attribute("id", "1234")
+      .child(XmlElement::new("ns1:project")
+        .matching_rule(MatchingRule::Type)
+        .examples(2)
+        .with_attribute(XmlAttribute::new("id", 1).matching_rule(MatchingRule::Integer))
+        .child(XmlElement::new("ns1:name")
+          .with_text(XmlText::new("Project 1").generator(Generator::RandomString(10)))));
+
+    let mut matching_rules = MatchingRuleCategory::empty("body");
+    let mut generators = Generators::default();
+    let body = root.build(&mut matching_rules, &mut generators).unwrap();
+
+    expect!(String::from_utf8(body).unwrap()).to(be_equal_to(
+      "<?xml version='1.0'?><ns1:projects xmlns:ns1='http://some.namespace/and/more/stuff' id='1234'>\
+      <ns1:project id='1'><ns1:name>Project 1</ns1:name></ns1:project>\
+      <ns1:project id='1'><ns1:name>Project 1</ns1:name></ns1:project></ns1:projects>"));
+    expect!(matching_rules.rules.len()).to(be_equal_to(2));
+    expect!(matching_rules.rules.get(&DocPath::new_unwrap("$.ns1:projects.ns1:project")).map(|rules| rules.rules.clone()))
+      .to(be_some().value(vec![MatchingRule::Type]));
+    expect!(matching_rules.rules.get(&DocPath::new_unwrap("$.ns1:projects.ns1:project['@id']")).map(|rules| rules.rules.clone()))
+      .to(be_some().value(vec![MatchingRule::Integer]));
+    expect!(generators.categories.get(&GeneratorCategory::BODY)
+      .and_then(|category| category.get(&DocPath::new_unwrap("$.ns1:projects.ns1:project.ns1:name.#text"))))
+      .to(be_some().value(&Generator::RandomString(10)));
+  }
+}
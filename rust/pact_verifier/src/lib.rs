@@ -6,7 +6,6 @@
 use std::collections::HashMap;
 use std::fmt::{Debug, Display, Formatter};
 use std::fmt;
-use std::fs;
 use std::future::Future;
 use std::panic::RefUnwindSafe;
 use std::path::Path;
@@ -24,10 +23,9 @@ use itertools::Itertools;
 #[cfg(feature = "plugins")] use itertools::Either;
 use maplit::*;
 use pact_models::generators::GeneratorTestMode;
-use pact_models::http_utils::HttpAuth;
+use pact_models::http_utils::{HttpAuth, HttpClientOptions};
 use pact_models::interaction::Interaction;
-use pact_models::json_utils::json_to_string;
-use pact_models::pact::{load_pact_from_json, Pact, read_pact};
+use pact_models::pact::{load_pact_from_json, load_pacts_from_dir, Pact, read_pact};
 use pact_models::prelude::v4::SynchronousHttp;
 use pact_models::provider_states::*;
 use pact_models::v4::interaction::V4Interaction;
@@ -44,7 +42,7 @@ use tracing::{debug, debug_span, error, info, Instrument, instrument, trace, war
 
 pub use callback_executors::NullRequestFilterExecutor;
 use callback_executors::RequestFilterExecutor;
-use pact_matching::{match_response, Mismatch};
+use pact_matching::{match_response_with_options, MatchingOptions, Mismatch};
 use pact_matching::logging::LOG_ID;
 use pact_matching::metrics::{MetricEvent, send_metrics_async};
 
@@ -55,7 +53,7 @@ use crate::pact_broker::{
   Link,
   PactBrokerError,
   PactVerificationContext,
-  publish_verification_results,
+  publish_verification_results_with_options,
   TestResult
 };
 pub use crate::pact_broker::{ConsumerVersionSelector, PactsForVerificationRequest};
@@ -369,7 +367,14 @@ async fn verify_response_from_provider<F: RequestFilterExecutor>(
   });
   match make_provider_request(provider, &request, options, client, transport).await {
     Ok(ref actual_response) => {
-      let mismatches = match_response(expected_response.clone(), actual_response.clone(), pact, &interaction.boxed()).await;
+      let mut expected_response = expected_response.clone();
+      if actual_response.trailers.is_none() && expected_response.trailers.as_ref().is_some_and(|t| !t.is_empty()) {
+        warn!("The provider response can not contain trailers (it was not sent with chunked transfer \
+          encoding or over HTTP/2), so the expected trailers will not be verified");
+        expected_response.trailers = None;
+      }
+      let mismatches = match_response_with_options(expected_response, actual_response.clone(), pact,
+        &interaction.boxed(), &options.matching_options).await;
       if mismatches.is_empty() {
         Ok(interaction.id.clone())
       } else {
@@ -568,7 +573,8 @@ async fn verify_interaction_using_transport<'a, F: RequestFilterExecutor>(
                     path: path.clone(),
                     expected: Some(expected.clone()),
                     actual: Some(actual.clone()),
-                    mismatch: mismatch.clone()
+                    mismatch: mismatch.clone(),
+                    location: None
                   })
                 }
               }).collect(),
@@ -742,29 +748,23 @@ fn generate_display_for_result(
   output.push(format!("      has a matching body ({})", body_result));
 }
 
-fn walkdir(
+fn load_pacts_for_provider(
   dir: &Path,
   provider: &ProviderInfo
 ) -> anyhow::Result<Vec<anyhow::Result<(Box<dyn Pact + Send + Sync + RefUnwindSafe>, Duration)>>> {
-    let mut pacts = vec![];
-    debug!("Scanning {:?}", dir);
-    for entry in fs::read_dir(dir)? {
-        let entry = entry?;
-        let path = entry.path();
-        if path.is_dir() {
-            walkdir(&path, provider)?;
-        } else {
-          match timeit(|| read_pact(&path)) {
-            Ok((pact, tm)) => {
-              if pact.provider().name == provider.name {
-                pacts.push(Ok((pact, tm)));
-              }
-            }
-            Err(err) => pacts.push(Err(err))
-          };
-        }
-    }
-    Ok(pacts)
+  let (results, tm) = timeit(|| load_pacts_from_dir(dir, false, None))?;
+  // The time to load the directory is split between the pact files in it
+  let tm = tm / results.len().max(1) as u32;
+  Ok(results.into_iter()
+    .filter_map(|(_, result)| match result {
+      Ok(pact) => if pact.provider().name == provider.name {
+        Some(Ok((pact, tm)))
+      } else {
+        None
+      }
+      Err(err) => Some(Err(err))
+    })
+    .collect())
 }
 
 fn display_body_mismatch(
@@ -917,7 +917,14 @@ pub struct VerificationOptions<F> where F: RequestFilterExecutor {
   /// If coloured output should be used (using ANSI escape codes)
   pub coloured_output: bool,
   /// If no pacts are found to verify, then this should be an error
-  pub no_pacts_is_error: bool
+  pub no_pacts_is_error: bool,
+  /// Options for the HTTP client used to fetch pacts from URLs and the Pact Broker, and to publish
+  /// the verification results (i.e. to use a proxy or custom root CA certificates). These do not
+  /// apply to the requests made to the provider.
+  pub http_client_options: HttpClientOptions,
+  /// Options used to configure how the responses from the provider are matched (i.e. custom
+  /// matchers, type coercion or body limits)
+  pub matching_options: MatchingOptions
 }
 
 impl <F: RequestFilterExecutor> Default for VerificationOptions<F> {
@@ -928,7 +935,9 @@ impl <F: RequestFilterExecutor> Default for VerificationOptions<F> {
       request_timeout: 5000,
       custom_headers: Default::default(),
       coloured_output: true,
-      no_pacts_is_error: true
+      no_pacts_is_error: true,
+      http_client_options: HttpClientOptions::default(),
+      matching_options: MatchingOptions::default()
     }
   }
 }
@@ -988,7 +997,8 @@ pub async fn verify_provider_async<F: RequestFilterExecutor, S: ProviderStateExe
   pact_matching::matchers::configure_core_catalogue();
 
   LOG_ID.scope(format!("verify:{}", provider_info.name), async {
-    let pact_results = fetch_pacts(source, consumers, &provider_info).await;
+    let pact_results = fetch_pacts(source, consumers, &provider_info,
+      &verification_options.http_client_options).await;
 
     let mut total_results = 0;
     let mut pending_errors: Vec<(String, MismatchResult)> = vec![];
@@ -1083,7 +1093,8 @@ pub async fn verify_provider_async<F: RequestFilterExecutor, S: ProviderStateExe
             verification_result.interaction_results.extend_from_slice(results.as_slice());
 
             if let Some(publish) = publish_options {
-              publish_result(results.as_slice(), &pact_source, &publish, metrics_data.as_ref()).await;
+              publish_result(results.as_slice(), &pact_source, &publish, metrics_data.as_ref(),
+                &verification_options.http_client_options).await;
 
               if !errors.is_empty() || !pending_errors.is_empty() {
                 process_notices(&context, VERIFICATION_NOTICE_AFTER_ERROR_RESULT_AND_PUBLISH, &mut verification_result);
@@ -1195,6 +1206,7 @@ pub fn interaction_mismatch_output(
         (Mismatch::HeaderMismatch { key: p1, .. }, Mismatch::HeaderMismatch { key: p2, .. }) => Ord::cmp(&p1, &p2),
         (Mismatch::BodyMismatch { path: p1, .. }, Mismatch::BodyMismatch { path: p2, .. }) => Ord::cmp(&p1, &p2),
         (Mismatch::MetadataMismatch { key: p1, .. }, Mismatch::MetadataMismatch { key: p2, .. }) => Ord::cmp(&p1, &p2),
+        (Mismatch::TrailerMismatch { key: p1, .. }, Mismatch::TrailerMismatch { key: p2, .. }) => Ord::cmp(&p1, &p2),
         _ => Ord::cmp(m1, m2)
       }
     }) {
@@ -1212,7 +1224,8 @@ pub fn interaction_mismatch_output(
 #[tracing::instrument(level = "trace")]
 async fn fetch_pact(
   source: PactSource,
-  provider: &ProviderInfo
+  provider: &ProviderInfo,
+  client_options: &HttpClientOptions
 ) -> Vec<anyhow::Result<(Box<dyn Pact + Send + Sync + RefUnwindSafe>, Option<PactVerificationContext>, PactSource, Duration)>> {
   trace!("fetch_pact(source={})", source);
 
@@ -1225,7 +1238,7 @@ async fn fetch_pact(
           (pact, None, source.clone(), tm)
         })
     ],
-    PactSource::Dir(dir) => match walkdir(Path::new(dir), provider) {
+    PactSource::Dir(dir) => match load_pacts_for_provider(Path::new(dir), provider) {
       Ok(pact_results) => pact_results.into_iter().map(|pact_result| {
           match pact_result {
               Ok((pact, tm)) => {
@@ -1238,7 +1251,8 @@ async fn fetch_pact(
       Err(err) => vec![Err(anyhow!("Could not load pacts from directory '{}' - {}", dir, err))]
     },
     PactSource::URL(url, auth) => vec![
-      timeit_async(pact_broker::fetch_pact_from_url(url, auth)).await
+      timeit_async(pact_broker::fetch_pact_from_url_with_options(url, auth,
+        &pact_broker::url_fetch_options(client_options))).await
         .map_err(|err| anyhow!("Failed to load pact '{}' - {}", url, err))
         .map(|((pact, links), tm)| {
           trace!(%url, duration = ?tm, "Loaded pact from url");
@@ -1257,10 +1271,11 @@ async fn fetch_pact(
         })
     ],
     PactSource::BrokerUrl(provider_name, broker_url, auth, _) => {
-      let result = timeit_async(pact_broker::fetch_pacts_from_broker(
+      let result = timeit_async(pact_broker::fetch_pacts_from_broker_with_options(
         broker_url.as_str(),
         provider_name.as_str(),
-        auth.clone()
+        auth.clone(),
+        client_options
       )).await;
 
       match result {
@@ -1295,7 +1310,7 @@ async fn fetch_pact(
       provider_name, broker_url, enable_pending, include_wip_pacts_since,
       provider_tags, provider_branch, selectors,
       auth, links: _ } => {
-      let result = timeit_async(pact_broker::fetch_pacts_dynamically_from_broker(
+      let result = timeit_async(pact_broker::fetch_pacts_dynamically_from_broker_with_options(
         broker_url.as_str(),
         provider_name.clone(),
         *enable_pending,
@@ -1303,7 +1318,8 @@ async fn fetch_pact(
         provider_tags.clone(),
         provider_branch.clone(),
         selectors.clone(),
-        auth.clone()
+        auth.clone(),
+        client_options
       )).await;
 
       match result {
@@ -1343,7 +1359,8 @@ async fn fetch_pact(
         })
     ],
     PactSource::WebhookCallbackUrl { pact_url, broker_url, auth, .. } => vec![
-      timeit_async(pact_broker::fetch_pact_from_url(pact_url, auth)).await
+      timeit_async(pact_broker::fetch_pact_from_url_with_options(pact_url, auth,
+        &pact_broker::url_fetch_options(client_options))).await
         .map_err(|err| anyhow!("Failed to load pact '{}' - {}", pact_url, err))
         .map(|((pact, links), tm)| {
           trace!(%pact_url, duration = ?tm, "Loaded pact from url");
@@ -1380,13 +1397,14 @@ fn is_pact_broker_source(links: &Vec<Link>) -> bool {
 async fn fetch_pacts(
   source: Vec<PactSource>,
   consumers: Vec<String>,
-  provider: &ProviderInfo
+  provider: &ProviderInfo,
+  client_options: &HttpClientOptions
 ) -> Vec<anyhow::Result<(Box<dyn Pact + Send + Sync + RefUnwindSafe>, Option<PactVerificationContext>, PactSource, Duration)>> {
   trace!("fetch_pacts(source={}, consumers={:?})", source.iter().map(|s| s.to_string()).join(", "), consumers);
 
   futures::stream::iter(source)
     .then(|pact_source| async {
-      futures::stream::iter(fetch_pact(pact_source, provider).await)
+      futures::stream::iter(fetch_pact(pact_source, provider, client_options).await)
     })
     .flatten()
     .filter(|res| futures::future::ready(filter_consumers(&consumers, res)))
@@ -1543,31 +1561,14 @@ pub async fn verify_pact_internal<'a, F: RequestFilterExecutor, S: ProviderState
 }
 
 fn process_comments(interaction: &dyn V4Interaction, output: &mut Vec<String>) {
-  let comments = interaction.comments();
+  if let Some(test_name) = interaction.test_name() {
+    output.push(format!("\n  Test Name: {}", test_name));
+  }
+  let comments = interaction.text_comments();
   if !comments.is_empty() {
-    if let Some(testname) = comments.get("testname") {
-      let s = json_to_string(testname);
-      if !s.is_empty() {
-        output.push(format!("\n  Test Name: {}", s));
-      }
-    }
-    if let Some(comment_text) = comments.get("text") {
-      match comment_text {
-        Value::Array(comment_text) => if !comment_text.is_empty() {
-          output.push("\n  Comments:".to_string());
-          for value in comment_text {
-            output.push(json_to_string(value));
-          }
-          output.push(String::default());
-        }
-        Value::String(comment) => if !comment.is_empty() {
-          output.push("\n  Comments:".to_string());
-          output.push(comment.clone());
-          output.push(String::default());
-        }
-        _ => {}
-      }
-    }
+    output.push("\n  Comments:".to_string());
+    output.extend(comments);
+    output.push(String::default());
   }
 }
 
@@ -1575,18 +1576,20 @@ async fn publish_result(
   results: &[VerificationInteractionResult],
   source: &PactSource,
   options: &PublishOptions,
-  metrics_data: Option<&VerificationMetrics>
+  metrics_data: Option<&VerificationMetrics>,
+  client_options: &HttpClientOptions
 ) {
   let publish_result = match source {
     PactSource::BrokerUrl(_, broker_url, auth, links) => {
       publish_to_broker(results, source, &options.build_url, &options.provider_tags,
         &options.provider_branch, &options.provider_version, links.clone(), broker_url.clone(),
-        auth.clone(), metrics_data
+        auth.clone(), metrics_data, client_options
       ).await
     }
     PactSource::BrokerWithDynamicConfiguration { broker_url, auth, links, provider_branch, provider_tags, .. } => {
       publish_to_broker(results, source, &options.build_url, &provider_tags, &provider_branch,
-        &options.provider_version, links.clone(), broker_url.clone(), auth.clone(), metrics_data
+        &options.provider_version, links.clone(), broker_url.clone(), auth.clone(), metrics_data,
+        client_options
       ).await
     }
     _ => {
@@ -1610,7 +1613,8 @@ async fn publish_to_broker(
   links: Vec<Link>,
   broker_url: String,
   auth: Option<HttpAuth>,
-  metrics_data: Option<&VerificationMetrics>
+  metrics_data: Option<&VerificationMetrics>,
+  client_options: &HttpClientOptions
 ) -> Result<Value, pact_broker::PactBrokerError> {
   info!("Publishing verification results back to the Pact Broker");
   let result = if results.iter().all(|r| r.result.is_ok()) {
@@ -1624,7 +1628,7 @@ async fn publish_to_broker(
         .collect()
     )
   };
-  publish_verification_results(
+  publish_verification_results_with_options(
     links,
     broker_url.as_str(),
     auth.clone(),
@@ -1633,7 +1637,8 @@ async fn publish_to_broker(
     build_url.clone(),
     provider_tags.clone(),
     provider_branch.clone(),
-    metrics_data
+    metrics_data,
+    client_options
   ).await
 }
 
@@ -3,17 +3,18 @@
 use std::collections::HashMap;
 use std::panic::RefUnwindSafe;
 use std::str::from_utf8;
+use std::sync::Arc;
 
 use anyhow::anyhow;
 use futures::stream::*;
 use itertools::Itertools;
 use maplit::hashmap;
 use pact_models::http_utils;
-use pact_models::http_utils::HttpAuth;
+use pact_models::http_utils::{FetchOptions, FileHttpCache, HttpAuth, HttpCache, HttpClientOptions, RetryPolicy};
 use pact_models::json_utils::json_to_string;
 use pact_models::pact::{load_pact_from_json, Pact};
 use regex::{Captures, Regex};
-use reqwest::{Method, Url};
+use reqwest::{Method, RequestBuilder, Url};
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
 use serde_with::skip_serializing_none;
@@ -204,6 +205,17 @@ impl HALClient {
     HALClient { url: url.to_string(), auth, ..HALClient::default() }
   }
 
+  /// Initialise a client with the URL and optional authentication, using an HTTP client
+  /// configured with the options (i.e. to use a proxy or custom root CA certificates)
+  pub fn with_url_and_options(url: &str, auth: Option<HttpAuth>, options: &HttpClientOptions) -> anyhow::Result<HALClient> {
+    Ok(HALClient {
+      client: options.configure(client_builder())?.build()?,
+      url: url.to_string(),
+      auth,
+      ..HALClient::default()
+    })
+  }
+
   fn update_path_info(&self, path_info: serde_json::Value) -> HALClient {
     HALClient {
       client: self.client.clone(),
@@ -296,23 +308,25 @@ impl HALClient {
     let url = self.resolve_path(path)?;
     debug!("Final broker URL: {}", url);
 
-    let request_builder = match self.auth {
-        Some(ref auth) => match auth {
-            HttpAuth::User(username, password) => self.client.get(url).basic_auth(username, password.clone()),
-            HttpAuth::Token(token) => self.client.get(url).bearer_auth(token),
-            _ => self.client.get(url)
-        },
-        None => self.client.get(url)
-    }.header("accept", "application/hal+json, application/json");
-
-    let response = with_retries(self.retries, request_builder).await
-      .map_err(|err| {
-          PactBrokerError::IoError(format!("Failed to access pact broker path '{}' - {}. URL: '{}'",
-              &path,
-              err,
-              &self.url,
-          ))
-      })?;
+    let mut token_refreshed = false;
+    let response = loop {
+      let request_builder = self.apply_auth(self.client.get(url.clone())).await?
+        .header("accept", "application/hal+json, application/json");
+
+      let response = with_retries(self.retries, request_builder).await
+        .map_err(|err| {
+            PactBrokerError::IoError(format!("Failed to access pact broker path '{}' - {}. URL: '{}'",
+                &path,
+                err,
+                &self.url,
+            ))
+        })?;
+      if !token_refreshed && self.invalidate_token(&response) {
+        token_refreshed = true;
+        continue;
+      }
+      break response;
+    };
 
     self.parse_broker_response(path.to_string(), response)
         .await
@@ -480,24 +494,23 @@ impl HALClient {
       base_url.join(&url.path())?
     };
 
-    let request_builder = match self.auth {
-      Some(ref auth) => match auth {
-        HttpAuth::User(username, password) => self.client
-          .request(method, url.clone())
-          .basic_auth(username, password.clone()),
-        HttpAuth::Token(token) => self.client
-          .request(method, url.clone())
-          .bearer_auth(token),
-        _ => self.client.request(method, url.clone())
-      },
-      None => self.client.request(method, url.clone())
-    }
-      .header("Content-Type", "application/json")
-      .header("Accept", "application/hal+json")
-      .header("Accept", "application/json")
-      .body(body.to_string());
-
-    let response = with_retries(self.retries, request_builder).await;
+    let mut token_refreshed = false;
+    let response = loop {
+      let request_builder = self.apply_auth(self.client.request(method.clone(), url.clone())).await?
+        .header("Content-Type", "application/json")
+        .header("Accept", "application/hal+json")
+        .header("Accept", "application/json")
+        .body(body.to_string());
+
+      let response = with_retries(self.retries, request_builder).await;
+      if let Ok(res) = &response {
+        if !token_refreshed && self.invalidate_token(res) {
+          token_refreshed = true;
+          continue;
+        }
+      }
+      break response;
+    };
     match response {
       Ok(res) => self.parse_broker_response(url.path().to_string(), res).await,
       Err(err) => Err(PactBrokerError::IoError(
@@ -506,6 +519,21 @@ impl HALClient {
     }
   }
 
+  async fn apply_auth(&self, request: RequestBuilder) -> Result<RequestBuilder, PactBrokerError> {
+    match &self.auth {
+      Some(auth) => auth.apply(&self.client, request).await
+        .map_err(|err| PactBrokerError::IoError(format!("Failed to authenticate with the pact broker - {}", err))),
+      None => Ok(request)
+    }
+  }
+
+  // If the request was rejected as unauthorized and the cached access token was invalidated, in
+  // which case the request should be retried once with a new token
+  fn invalidate_token(&self, response: &reqwest::Response) -> bool {
+    response.status() == reqwest::StatusCode::UNAUTHORIZED &&
+      self.auth.as_ref().map(|auth| auth.invalidate_on_unauthorized()).unwrap_or(false)
+  }
+
   fn with_doc_context(&self, doc_attributes: &[Link]) -> Result<HALClient, PactBrokerError> {
     let links: serde_json::Map<String, serde_json::Value> = doc_attributes.iter()
       .map(|link| (link.name.clone(), link.as_json())).collect();
@@ -542,14 +570,16 @@ fn handle_validation_errors(body: Value) -> PactBrokerError {
   }
 }
 
+fn client_builder() -> reqwest::ClientBuilder {
+  reqwest::ClientBuilder::new()
+    .user_agent(format!("{}/{}", env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION")))
+    .tcp_keepalive(None)
+}
+
 impl Default for HALClient {
   fn default() -> Self {
     HALClient {
-      client: reqwest::ClientBuilder::new()
-        .user_agent(format!("{}/{}", env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION")))
-        .tcp_keepalive(None)
-        .build()
-        .unwrap(),
+      client: client_builder().build().unwrap(),
       url: "".to_string(),
       path_info: None,
       auth: None,
@@ -578,11 +608,20 @@ pub async fn fetch_pacts_from_broker(
   broker_url: &str,
   provider_name: &str,
   auth: Option<HttpAuth>
+) -> anyhow::Result<Vec<anyhow::Result<(Box<dyn Pact + Send + Sync + RefUnwindSafe>, Option<PactVerificationContext>, Vec<Link>)>>> {
+  fetch_pacts_from_broker_with_options(broker_url, provider_name, auth, &HttpClientOptions::default()).await
+}
+
+pub(crate) async fn fetch_pacts_from_broker_with_options(
+  broker_url: &str,
+  provider_name: &str,
+  auth: Option<HttpAuth>,
+  client_options: &HttpClientOptions
 ) -> anyhow::Result<Vec<anyhow::Result<(Box<dyn Pact + Send + Sync + RefUnwindSafe>, Option<PactVerificationContext>, Vec<Link>)>>> {
   trace!("fetch_pacts_from_broker(broker_url='{}', provider_name='{}', auth={})", broker_url,
     provider_name, auth.clone().unwrap_or_default());
 
-    let mut hal_client = HALClient::with_url(broker_url, auth);
+    let mut hal_client = HALClient::with_url_and_options(broker_url, auth, client_options)?;
     let template_values = hashmap!{ "provider".to_string() => provider_name.to_string() };
 
     hal_client = hal_client.navigate("pb:latest-provider-pacts", &template_values)
@@ -649,13 +688,30 @@ pub async fn fetch_pacts_dynamically_from_broker(
   provider_branch: Option<String>,
   consumer_version_selectors: Vec<ConsumerVersionSelector>,
   auth: Option<HttpAuth>
+) -> anyhow::Result<Vec<Result<(Box<dyn Pact + Send + Sync + RefUnwindSafe>, Option<PactVerificationContext>, Vec<Link>), PactBrokerError>>> {
+  fetch_pacts_dynamically_from_broker_with_options(broker_url, provider_name, pending,
+    include_wip_pacts_since, provider_tags, provider_branch, consumer_version_selectors, auth,
+    &HttpClientOptions::default()).await
+}
+
+#[allow(clippy::too_many_arguments)]
+pub(crate) async fn fetch_pacts_dynamically_from_broker_with_options(
+  broker_url: &str,
+  provider_name: String,
+  pending: bool,
+  include_wip_pacts_since: Option<String>,
+  provider_tags: Vec<String>,
+  provider_branch: Option<String>,
+  consumer_version_selectors: Vec<ConsumerVersionSelector>,
+  auth: Option<HttpAuth>,
+  client_options: &HttpClientOptions
 ) -> anyhow::Result<Vec<Result<(Box<dyn Pact + Send + Sync + RefUnwindSafe>, Option<PactVerificationContext>, Vec<Link>), PactBrokerError>>> {
   trace!("fetch_pacts_dynamically_from_broker(broker_url='{}', provider_name='{}', pending={}, \
     include_wip_pacts_since={:?}, provider_tags: {:?}, consumer_version_selectors: {:?}, auth={})",
     broker_url, provider_name, pending, include_wip_pacts_since, provider_tags,
     consumer_version_selectors, auth.clone().unwrap_or_default());
 
-    let mut hal_client = HALClient::with_url(broker_url, auth);
+    let mut hal_client = HALClient::with_url_and_options(broker_url, auth, client_options)?;
     let template_values = hashmap!{ "provider".to_string() => provider_name.clone() };
 
     hal_client = hal_client.navigate("pb:provider-pacts-for-verification", &template_values)
@@ -777,12 +833,34 @@ pub async fn fetch_pacts_dynamically_from_broker(
 /// Fetch the Pact from the given URL, using any required authentication. This will use a GET
 /// request to the given URL and parse the result into a Pact model. It will also look for any HAL
 /// links in the response, returning those if found.
+///
+/// If the `PACT_URL_CACHE_DIR` environment variable is set, the pacts are cached in that
+/// directory and are only downloaded again if they have changed.
 pub async fn fetch_pact_from_url(url: &str, auth: &Option<HttpAuth>) -> anyhow::Result<(Box<dyn Pact + Send + Sync + RefUnwindSafe>, Vec<Link>)> {
-  let url = url.to_string();
-  let auth = auth.clone();
-  let (url, pact_json) = tokio::task::spawn_blocking(move || {
-    http_utils::fetch_json_from_url(&url, &auth)
-  }).await??;
+  fetch_pact_from_url_with_options(url, auth, &url_fetch_options(&HttpClientOptions::default())).await
+}
+
+/// Options used by the verifier to fetch pacts from URLs. Failed requests are retried, and the
+/// pacts are cached if the `PACT_URL_CACHE_DIR` environment variable is set.
+pub(crate) fn url_fetch_options(client_options: &HttpClientOptions) -> FetchOptions {
+  let cache = std::env::var("PACT_URL_CACHE_DIR").ok()
+    .filter(|dir| !dir.is_empty())
+    .map(|dir| Arc::new(FileHttpCache::new(dir)) as Arc<dyn HttpCache>);
+  FetchOptions {
+    retry_policy: RetryPolicy::default(),
+    client_options: client_options.clone(),
+    cache
+  }
+}
+
+/// Fetch the Pact from the given URL, using the options to configure the retries, HTTP client and
+/// cache (see [`fetch_pact_from_url`]).
+pub async fn fetch_pact_from_url_with_options(
+  url: &str,
+  auth: &Option<HttpAuth>,
+  options: &FetchOptions
+) -> anyhow::Result<(Box<dyn Pact + Send + Sync + RefUnwindSafe>, Vec<Link>)> {
+  let (url, pact_json) = http_utils::fetch_json_from_url_with_options(url, auth, options).await?;
   let pact = load_pact_from_json(&url, &pact_json)?;
   let links = links_from_json(&pact_json);
   Ok((pact, links))
@@ -819,7 +897,25 @@ pub async fn publish_verification_results(
   branch: Option<String>,
   metrics_data: Option<&VerificationMetrics>
 ) -> Result<serde_json::Value, PactBrokerError> {
-  let hal_client = HALClient::with_url(broker_url, auth.clone());
+  publish_verification_results_with_options(links, broker_url, auth, result, version, build_url,
+    provider_tags, branch, metrics_data, &HttpClientOptions::default()).await
+}
+
+#[allow(clippy::too_many_arguments)]
+pub(crate) async fn publish_verification_results_with_options(
+  links: Vec<Link>,
+  broker_url: &str,
+  auth: Option<HttpAuth>,
+  result: TestResult,
+  version: String,
+  build_url: Option<String>,
+  provider_tags: Vec<String>,
+  branch: Option<String>,
+  metrics_data: Option<&VerificationMetrics>,
+  client_options: &HttpClientOptions
+) -> Result<serde_json::Value, PactBrokerError> {
+  let hal_client = HALClient::with_url_and_options(broker_url, auth.clone(), client_options)
+    .map_err(|err| PactBrokerError::IoError(err.to_string()))?;
 
   if branch.is_some() {
     publish_provider_branch(&hal_client, &links, &branch.unwrap(), &version).await?;
@@ -918,6 +1014,11 @@ fn build_payload(
                         "attribute": "metadata",
                         "identifier": key,
                         "description": mismatch
+                      })),
+                      Mismatch::TrailerMismatch { key, mismatch, .. } => acc.0.push(json!({
+                        "attribute": "trailer",
+                        "identifier": key,
+                        "description": mismatch
                       }))
                     }
                   }
@@ -1189,6 +1290,26 @@ mod tests {
     expect!(client.fetch("/").await).to(be_err());
   }
 
+  #[test]
+  fn with_url_and_options_returns_an_error_if_the_options_are_invalid() {
+    let options = HttpClientOptions {
+      proxy: Some("not a url".to_string()),
+      .. HttpClientOptions::default()
+    };
+    expect!(HALClient::with_url_and_options("http://localhost", None, &options).is_err()).to(be_true());
+    expect!(HALClient::with_url_and_options("http://localhost", None, &HttpClientOptions::default()).is_ok()).to(be_true());
+  }
+
+  #[tokio::test]
+  async fn fetch_pacts_from_broker_uses_the_http_client_options() {
+    let options = HttpClientOptions {
+      proxy: Some("not a url".to_string()),
+      .. HttpClientOptions::default()
+    };
+    let result = fetch_pacts_from_broker_with_options("http://localhost", "provider", None, &options).await;
+    expect!(result.unwrap_err().to_string()).to(be_equal_to("'not a url' is not a valid proxy URL - builder error"));
+  }
+
   #[test_log::test(tokio::test)]
   async fn fetch_returns_an_error_if_it_does_not_get_a_success_response() {
     let pact_broker = PactBuilder::new("RustPactVerifier", "PactBroker")
@@ -9,9 +9,10 @@ use std::fmt::{Display, Formatter};
 
 use anyhow::anyhow;
 use futures::future::*;
-use http::{HeaderMap, HeaderValue, Method};
+use http::{HeaderMap, HeaderValue, Method, Version};
 use http::header::{HeaderName, InvalidHeaderName, InvalidHeaderValue};
-use http::header::CONTENT_TYPE;
+use http::header::{CONTENT_TYPE, TRANSFER_ENCODING};
+use http_body_util::BodyExt;
 use http::method::InvalidMethod;
 use itertools::Itertools;
 use maplit::hashmap;
@@ -124,6 +125,10 @@ fn create_native_request(
 
   match request.body {
     OptionalBody::Present(ref s, _, _) => builder = builder.body(s.clone()),
+    OptionalBody::File(ref file, _, _) => match file.contents() {
+      Ok(contents) => builder = builder.body(contents.clone()),
+      Err(err) => warn!("Failed to read the request body from '{}' - {}", file.path().display(), err)
+    },
     OptionalBody::Null => {
       if request.content_type().unwrap_or_default().is_json() {
         builder = builder.body("null");
@@ -161,30 +166,48 @@ fn extract_headers(headers: &HeaderMap) -> Option<HashMap<String, Vec<String>>>
   }
 }
 
-async fn extract_body(response: reqwest::Response, pact_response: &HttpResponse) -> anyhow::Result<OptionalBody> {
-  let body = response.bytes().await?;
+fn extract_body(body: bytes::Bytes, pact_response: &HttpResponse) -> OptionalBody {
   if !body.is_empty() {
-    Ok(OptionalBody::Present(body, pact_response.content_type(), None))
+    OptionalBody::Present(body, pact_response.content_type(), None)
   } else {
-    Ok(OptionalBody::Empty)
+    OptionalBody::Empty
   }
 }
 
+/// If trailers can be sent with the response. Trailers require chunked transfer encoding with
+/// HTTP/1.1, while HTTP/2 and later always support them.
+fn response_supports_trailers(version: Version, headers: &HeaderMap) -> bool {
+  version >= Version::HTTP_2 || headers.get_all(TRANSFER_ENCODING).iter()
+    .filter_map(|value| value.to_str().ok())
+    .flat_map(|value| value.split(','))
+    .any(|value| value.trim().eq_ignore_ascii_case("chunked"))
+}
+
 async fn native_response_to_pact_response(native_response: reqwest::Response) -> anyhow::Result<HttpResponse> {
   debug!("Received native response: {:?}", native_response);
 
   let status = native_response.status().as_u16();
   let headers = extract_headers(native_response.headers());
+  let supports_trailers = response_supports_trailers(native_response.version(), native_response.headers());
   let response = HttpResponse {
     status,
     headers,
     .. HttpResponse::default()
   };
 
-  let body = extract_body(native_response, &response).await?;
+  let collected = http::Response::from(native_response).into_body().collect().await?;
+  // If the response can not carry trailers, leave them as None so that trailer matching is skipped
+  let trailers = if supports_trailers {
+    Some(collected.trailers().and_then(extract_headers).unwrap_or_default())
+  } else {
+    None
+  };
+  let body = extract_body(collected.to_bytes(), &response);
 
   Ok(HttpResponse {
-    body, .. response.clone()
+    body,
+    trailers,
+    .. response.clone()
   })
 }
 
@@ -286,7 +309,7 @@ pub async fn make_state_change_request(
 mod tests {
   use expectest::expect;
   use expectest::prelude::*;
-  use http::HeaderMap;
+  use http::{HeaderMap, Version};
   use itertools::Itertools;
   use maplit::*;
   use pact_models::bodies::OptionalBody;
@@ -302,7 +325,7 @@ mod tests {
     VerificationOptions
   };
 
-  use super::{create_native_request, extract_headers, join_paths};
+  use super::{create_native_request, extract_headers, join_paths, response_supports_trailers};
 
   #[test]
   fn extract_headers_tests() {
@@ -319,6 +342,15 @@ mod tests {
     expect!(extract_headers(&HeaderMap::new())).to(be_none());
   }
 
+  #[test]
+  fn response_supports_trailers_test() {
+    let mut headers = HeaderMap::new();
+    expect!(response_supports_trailers(Version::HTTP_11, &headers)).to(be_false());
+    expect!(response_supports_trailers(Version::HTTP_2, &headers)).to(be_true());
+    headers.insert("transfer-encoding", "gzip, Chunked".parse().unwrap());
+    expect!(response_supports_trailers(Version::HTTP_11, &headers)).to(be_true());
+  }
+
   #[test]
   fn extract_headers_when_header_value_is_a_comma_separated_string() {
     let mut headers = HeaderMap::new();
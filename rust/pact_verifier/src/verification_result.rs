@@ -183,7 +183,8 @@ mod tests {
           path: "1.2.3.4".to_string(),
           expected: Some("100".into()),
           actual: Some("200".into()),
-          mismatch: "Expected 100 but got 200".to_string()
+          mismatch: "Expected 100 but got 200".to_string(),
+          location: None
         }
       ],
       interaction_id: None
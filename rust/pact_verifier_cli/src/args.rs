@@ -149,6 +149,24 @@ pub(crate) fn setup_app() -> Command {
       .long("ignore-no-pacts-error")
       .action(ArgAction::SetTrue)
       .help("Do not fail if no pacts are found to verify"))
+    .arg(Arg::new("validate-pacts")
+      .long("validate-pacts")
+      .action(ArgAction::SetTrue)
+      .help("Validates the matching rules and generators in the pact files loaded with --file and --dir before verifying them. Any errors will fail the verification."))
+    .arg(Arg::new("pact-proxy")
+      .long("pact-proxy")
+      .action(ArgAction::Set)
+      .value_parser(NonEmptyStringValueParser::new())
+      .help("URL of a proxy to send the requests to fetch pacts from URLs or the Pact Broker (and publish verification results) through"))
+    .arg(Arg::new("pact-ca-certificate")
+      .long("pact-ca-certificate")
+      .action(ArgAction::Append)
+      .value_parser(NonEmptyStringValueParser::new())
+      .help("PEM file with additional root CA certificates to trust when fetching pacts from URLs or the Pact Broker (can be repeated)"))
+    .arg(Arg::new("disable-pact-tls-verification")
+      .long("disable-pact-tls-verification")
+      .action(ArgAction::SetTrue)
+      .help("Disables validation of TLS certificates when fetching pacts from URLs or the Pact Broker. This is insecure, and should only be used for testing."))
 
     .group(ArgGroup::new("auth").multiple(true))
     .next_help_heading("Authentication options")
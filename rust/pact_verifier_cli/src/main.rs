@@ -46,6 +46,14 @@
 //!           URL of a Pact to verify via a webhook callback. Requires the broker-url to be set. [env: PACT_WEBHOOK_CALLBACK_URL=]
 //!       --ignore-no-pacts-error
 //!           Do not fail if no pacts are found to verify
+//!       --validate-pacts
+//!           Validates the matching rules and generators in the pact files loaded with --file and --dir before verifying them. Any errors will fail the verification.
+//!       --pact-proxy <pact-proxy>
+//!           URL of a proxy to send the requests to fetch pacts from URLs or the Pact Broker (and publish verification results) through
+//!       --pact-ca-certificate <pact-ca-certificate>
+//!           PEM file with additional root CA certificates to trust when fetching pacts from URLs or the Pact Broker (can be repeated)
+//!       --disable-pact-tls-verification
+//!           Disables validation of TLS certificates when fetching pacts from URLs or the Pact Broker. This is insecure, and should only be used for testing.
 //!
 //! Authentication options:
 //!       --user <user>          Username to use when fetching pacts from URLS [env: PACT_BROKER_USERNAME=]
@@ -392,6 +400,7 @@
 #![type_length_limit="100000000"]
 
 use std::env;
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
 use std::sync::Arc;
 use std::time::Duration;
@@ -402,7 +411,11 @@ use log::{LevelFilter};
 use maplit::hashmap;
 use serde_json::Value;
 use pact_models::{PACT_RUST_VERSION, PactSpecification};
+use pact_models::pact::find_pacts_in_dir;
+use pact_models::http_utils::HttpClientOptions;
 use pact_models::prelude::HttpAuth;
+use pact_models::validate::validate_pact_file;
+use pact_models::verify_json::ResultLevel;
 use tokio::time::sleep;
 use tracing::{debug, debug_span, error, Instrument, warn};
 use tracing_subscriber::FmtSubscriber;
@@ -458,6 +471,9 @@ async fn handle_matches(matches: &ArgMatches) -> Result<(), i32> {
 
   let provider = configure_provider(matches);
   let source = pact_source(matches);
+  if matches.get_flag("validate-pacts") && !validate_pact_files(&source) {
+    return Err(2);
+  }
   let filter = interaction_filter(matches);
   let provider_state_executor = Arc::new(HttpRequestProviderStateExecutor {
     state_change_url: matches.get_one::<String>("state-change-url").cloned(),
@@ -484,6 +500,12 @@ async fn handle_matches(matches: &ArgMatches) -> Result<(), i32> {
     custom_headers,
     coloured_output,
     no_pacts_is_error: !matches.get_flag("ignore-no-pacts-error"),
+    http_client_options: HttpClientOptions {
+      proxy: matches.get_one::<String>("pact-proxy").cloned(),
+      ca_certificates: matches.get_many::<String>("pact-ca-certificate")
+        .map_or_else(Vec::new, |files| files.map(PathBuf::from).collect()),
+      disable_tls_verification: matches.get_flag("disable-pact-tls-verification")
+    },
     .. VerificationOptions::default()
   };
 
@@ -545,6 +567,45 @@ async fn handle_matches(matches: &ArgMatches) -> Result<(), i32> {
     })
 }
 
+/// Validates the pact files from the file and directory sources, printing any problems found.
+/// Returns false if any of the files could not be loaded or have errors.
+fn validate_pact_files(sources: &[PactSource]) -> bool {
+  let mut files = vec![];
+  for source in sources {
+    match source {
+      PactSource::File(file) => files.push(Path::new(file).to_path_buf()),
+      PactSource::Dir(dir) => match find_pacts_in_dir(dir, false, None) {
+        Ok(pact_files) => files.extend(pact_files),
+        Err(err) => {
+          error!("Failed to find pact files in '{}' - {}", dir, err);
+          return false;
+        }
+      },
+      _ => {}
+    }
+  }
+
+  let mut valid = true;
+  for file in files {
+    match validate_pact_file(&file) {
+      Ok(results) => if !results.is_empty() {
+        println!("{}:", file.display());
+        for result in results {
+          println!("  {} {} - {}", result.level, result.path, result.message);
+          if result.level == ResultLevel::ERROR {
+            valid = false;
+          }
+        }
+      },
+      Err(err) => {
+        error!("Failed to validate pact file '{}' - {}", file.display(), err);
+        valid = false;
+      }
+    }
+  }
+  valid
+}
+
 fn setup_output(matches: &ArgMatches) -> bool {
   let coloured_output = !matches.get_flag("no-colour");
   let level = matches.get_one::<String>("loglevel").cloned().unwrap_or("warn".to_string());